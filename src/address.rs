@@ -1,18 +1,22 @@
 mod error;
 
-use std::fmt::{Debug, Display, Formatter};
-use std::str::FromStr;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{Debug, Display, Formatter};
+use core::str::FromStr;
 
 use base64::engine::general_purpose::{STANDARD_NO_PAD, URL_SAFE_NO_PAD};
 use base64::Engine;
 use crc::Crc;
 pub use error::*;
-use lazy_static::lazy_static;
 use serde::de::{Error, Visitor};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-lazy_static! {
-    pub static ref CRC_16_XMODEM: Crc<u16> = Crc::<u16>::new(&crc::CRC_16_XMODEM);
+pub static CRC_16_XMODEM: Crc<u16> = Crc::<u16>::new(&crc::CRC_16_XMODEM);
+
+/// Computes the CRC16-XMODEM checksum used by user-friendly addresses and get-method ids.
+pub fn crc16(data: &[u8]) -> u16 {
+    CRC_16_XMODEM.checksum(data)
 }
 
 #[derive(PartialEq, Eq, Clone, Hash, Default)]
@@ -85,6 +89,25 @@ impl TonAddress {
         Ok(addr)
     }
 
+    /// Returns true if the address belongs to the shard of specified workchain.
+    ///
+    /// The shard is encoded as the prefix of account ids followed by a single tag bit,
+    /// e.g. `0x8000000000000000` for the whole workchain.
+    pub fn is_in_shard(&self, workchain: i32, shard: i64) -> bool {
+        if self.workchain != workchain {
+            return false;
+        }
+        let shard = shard as u64;
+        let tag = shard & shard.wrapping_neg();
+        if tag == 0 {
+            return false;
+        }
+        let mask = !(tag | (tag - 1));
+        let mut prefix = [0u8; 8];
+        prefix.copy_from_slice(&self.hash_part[..8]);
+        (u64::from_be_bytes(prefix) ^ shard) & mask == 0
+    }
+
     pub fn from_base64_url(s: &str) -> Result<TonAddress, TonAddressParseError> {
         Ok(Self::from_base64_url_flags(s)?.0)
     }
@@ -168,6 +191,45 @@ impl TonAddress {
         Self::from_base64_src(bytes, s)
     }
 
+    /// Checks a user-friendly address in either base64 alphabet without parsing it.
+    ///
+    /// The length is checked first, then the alphabet, the tag byte and the checksum.
+    pub fn is_valid_base64(s: &str) -> Result<(), TonAddressBase64Error> {
+        if s.len() != 48 {
+            return Err(TonAddressBase64Error::InvalidLength(s.len()));
+        }
+        let url_safe = s.contains(['-', '_']);
+        let invalid_char = s.chars().enumerate().find(|(_, c)| {
+            !(c.is_ascii_alphanumeric() || if url_safe { "-_" } else { "+/" }.contains(*c))
+        });
+        if let Some((position, character)) = invalid_char {
+            return Err(TonAddressBase64Error::InvalidAlphabet {
+                character,
+                position,
+            });
+        }
+        let decoded = if url_safe {
+            URL_SAFE_NO_PAD.decode(s)
+        } else {
+            STANDARD_NO_PAD.decode(s)
+        };
+        // All 48 characters are in the alphabet, so the input decodes into 36 bytes
+        let bytes = decoded.expect("valid base64");
+        if !matches!(bytes[0], 0x11 | 0x51 | 0x91 | 0xD1) {
+            return Err(TonAddressBase64Error::InvalidTag(bytes[0]));
+        }
+        Self::verify_crc(bytes[..].try_into().expect("36 bytes"))
+    }
+
+    fn verify_crc(bytes: &[u8; 36]) -> Result<(), TonAddressBase64Error> {
+        let expected = crc16(&bytes[0..34]);
+        let actual = ((bytes[34] as u16) << 8) | bytes[35] as u16;
+        if expected != actual {
+            return Err(TonAddressBase64Error::ChecksumMismatch { expected, actual });
+        }
+        Ok(())
+    }
+
     /// Parses decoded base64 representation of an address
     ///
     /// # Returns
@@ -189,12 +251,10 @@ impl TonAddress {
             }
         };
         let workchain = bytes[1] as i8 as i32;
-        let calc_crc = CRC_16_XMODEM.checksum(&bytes[0..34]);
-        let addr_crc = ((bytes[34] as u16) << 8) | bytes[35] as u16;
-        if calc_crc != addr_crc {
+        if let Err(e) = Self::verify_crc(bytes) {
             return Err(TonAddressParseError::new(
                 src,
-                "Invalid base64src address: CRC mismatch",
+                format!("Invalid base64src address: {}", e),
             ));
         }
         let mut hash_part = [0_u8; 32];
@@ -240,24 +300,35 @@ impl TonAddress {
         bytes[0] = tag;
         bytes[1] = (self.workchain & 0xff) as u8;
         bytes[2..34].clone_from_slice(&self.hash_part);
-        let crc = CRC_16_XMODEM.checksum(&bytes[0..34]);
+        let crc = crc16(&bytes[0..34]);
         bytes[34] = ((crc >> 8) & 0xff) as u8;
         bytes[35] = (crc & 0xff) as u8;
     }
 }
 
 impl Display for TonAddress {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         f.write_str(self.to_base64_url().as_str())
     }
 }
 
 impl Debug for TonAddress {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         f.write_str(self.to_base64_url().as_str())
     }
 }
 
+/// Generates addresses whose workchain fits into `int8`, so they can be stored as `addr_std`.
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for TonAddress {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(TonAddress {
+            workchain: i8::arbitrary(u)? as i32,
+            hash_part: <[u8; 32]>::arbitrary(u)?,
+        })
+    }
+}
+
 impl FromStr for TonAddress {
     type Err = TonAddressParseError;
 
@@ -297,7 +368,7 @@ struct TonAddressVisitor;
 impl<'de> Visitor<'de> for TonAddressVisitor {
     type Value = TonAddress;
 
-    fn expecting(&self, formatter: &mut Formatter) -> std::fmt::Result {
+    fn expecting(&self, formatter: &mut Formatter) -> core::fmt::Result {
         formatter.write_str("an string representing TON address in Hex or Base64 format")
     }
 
@@ -415,6 +486,42 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn is_valid_base64_reports_failure_kind() -> anyhow::Result<()> {
+        use crate::address::{crc16, TonAddressBase64Error};
+
+        assert_eq!(crc16(b"123456789"), 0x31c3);
+        assert!(
+            TonAddress::is_valid_base64("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR").is_ok()
+        );
+        assert!(
+            TonAddress::is_valid_base64("EQDk2VTvn04SUKJrW7rXahzdF8/Qi6utb0wj43InCu9vdjrR").is_ok()
+        );
+        assert_eq!(
+            TonAddress::is_valid_base64("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjr"),
+            Err(TonAddressBase64Error::InvalidLength(47))
+        );
+        assert_eq!(
+            TonAddress::is_valid_base64("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdj!R"),
+            Err(TonAddressBase64Error::InvalidAlphabet {
+                character: '!',
+                position: 46
+            })
+        );
+        assert_eq!(
+            TonAddress::is_valid_base64("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjra"),
+            Err(TonAddressBase64Error::ChecksumMismatch {
+                expected: 0x3ad1,
+                actual: 0x3ada
+            })
+        );
+        let err = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjra"
+            .parse::<TonAddress>()
+            .unwrap_err();
+        assert!(err.to_string().contains("expected 0x3ad1, actual 0x3ada"));
+        Ok(())
+    }
+
     #[test]
     fn serialization_works() -> anyhow::Result<()> {
         let expected = "\"EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR\"";
@@ -438,6 +545,20 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn is_in_shard_works() -> anyhow::Result<()> {
+        let address: TonAddress =
+            "0:e4d954ef9f4e1250a26b5bbad76a1cdd17cfd08babad6f4c23e372270aef6f76".parse()?;
+        assert!(address.is_in_shard(0, 0x8000000000000000u64 as i64));
+        assert!(!address.is_in_shard(-1, 0x8000000000000000u64 as i64));
+        assert!(address.is_in_shard(0, 0xc000000000000000u64 as i64));
+        assert!(!address.is_in_shard(0, 0x4000000000000000));
+        assert!(address.is_in_shard(0, 0xe600000000000000u64 as i64));
+        assert!(!address.is_in_shard(0, 0xe200000000000000u64 as i64));
+        assert!(!address.is_in_shard(0, 0));
+        Ok(())
+    }
+
     #[test]
     fn deserialization_works() -> anyhow::Result<()> {
         let address = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR";
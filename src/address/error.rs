@@ -1,3 +1,5 @@
+use alloc::string::{String, ToString};
+
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -7,6 +9,23 @@ pub struct TonAddressParseError {
     message: String,
 }
 
+/// Reason of a user-friendly address being invalid, returned by
+/// [`TonAddress::is_valid_base64`](crate::address::TonAddress::is_valid_base64).
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TonAddressBase64Error {
+    #[error("Invalid length: expected 48 characters, got {0}")]
+    InvalidLength(usize),
+
+    #[error("Invalid character {character:?} at position {position}")]
+    InvalidAlphabet { character: char, position: usize },
+
+    #[error("Invalid tag byte 0x{0:02x}")]
+    InvalidTag(u8),
+
+    #[error("CRC mismatch: expected 0x{expected:04x}, actual 0x{actual:04x}")]
+    ChecksumMismatch { expected: u16, actual: u16 },
+}
+
 impl TonAddressParseError {
     pub fn new<A: ToString, M: ToString>(address: A, message: M) -> TonAddressParseError {
         TonAddressParseError {
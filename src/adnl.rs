@@ -0,0 +1,183 @@
+mod error;
+
+use alloc::string::String;
+use core::fmt::{Debug, Display, Formatter};
+use core::str::FromStr;
+
+pub use error::*;
+use sha2::{Digest, Sha256};
+
+use crate::address::crc16;
+
+/// TL id of `pub.ed25519 key:int256 = PublicKey`.
+const PUB_ED25519_TL_ID: u32 = 0x4813b4c6;
+const ADNL_ADDRESS_TAG: u8 = 0x2d;
+const BASE32_ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+/// Length of the text form, i.e. of base32 of the tag, the address and the CRC without the
+/// first character, which is always the same because of the tag.
+const ADNL_ADDRESS_TEXT_LEN: usize = 55;
+
+/// 256-bit ADNL address, e.g. `adnl_addr` of a validator or the short id of a node.
+///
+/// Formatted and parsed as the 55 characters used by the node tools, e.g. in
+/// `validator-engine-console`, or as 64 hex digits.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct AdnlAddress([u8; 32]);
+
+impl AdnlAddress {
+    pub fn new(bytes: [u8; 32]) -> AdnlAddress {
+        AdnlAddress(bytes)
+    }
+
+    /// Computes the short id of the node with the Ed25519 public key, i.e. the SHA-256 hash
+    /// of its TL serialization `pub.ed25519 key:int256 = PublicKey`.
+    pub fn from_public_key(public_key: &[u8; 32]) -> AdnlAddress {
+        let mut hasher = Sha256::new();
+        hasher.update(PUB_ED25519_TL_ID.to_le_bytes());
+        hasher.update(public_key);
+        AdnlAddress(hasher.finalize().into())
+    }
+
+    /// Parses the address from its 32 bytes, e.g. `adnl_addr` of a parsed validator.
+    pub fn from_slice(bytes: &[u8]) -> Result<AdnlAddress, AdnlAddressParseError> {
+        let bytes = bytes
+            .try_into()
+            .map_err(|_| AdnlAddressParseError::new(hex::encode(bytes), "Expected 32 bytes"))?;
+        Ok(AdnlAddress(bytes))
+    }
+
+    pub fn from_hex(hex: &str) -> Result<AdnlAddress, AdnlAddressParseError> {
+        let bytes = hex::decode(hex).map_err(|e| AdnlAddressParseError::new(hex, e))?;
+        AdnlAddress::from_slice(&bytes)
+            .map_err(|_| AdnlAddressParseError::new(hex, "Expected 64 hex digits"))
+    }
+
+    /// Parses the 55 characters form, checking its tag and CRC.
+    pub fn from_text(text: &str) -> Result<AdnlAddress, AdnlAddressParseError> {
+        if text.len() != ADNL_ADDRESS_TEXT_LEN {
+            return Err(AdnlAddressParseError::new(
+                text,
+                format!("Expected {} characters", ADNL_ADDRESS_TEXT_LEN),
+            ));
+        }
+        // The omitted first character holds the high bits of the tag
+        let mut bits = (ADNL_ADDRESS_TAG >> 3) as u32;
+        let mut bit_len = 5;
+        let mut bytes = vec![];
+        for c in text.bytes() {
+            let value = BASE32_ALPHABET
+                .iter()
+                .position(|b| *b == c.to_ascii_lowercase())
+                .ok_or_else(|| AdnlAddressParseError::new(text, "Invalid base32 character"))?;
+            bits = (bits << 5) | value as u32;
+            bit_len += 5;
+            if bit_len >= 8 {
+                bit_len -= 8;
+                bytes.push((bits >> bit_len) as u8);
+                bits &= (1 << bit_len) - 1;
+            }
+        }
+        if bytes[0] != ADNL_ADDRESS_TAG {
+            return Err(AdnlAddressParseError::new(text, "Invalid tag"));
+        }
+        let crc = u16::from_be_bytes([bytes[33], bytes[34]]);
+        if crc != crc16(&bytes[..33]) {
+            return Err(AdnlAddressParseError::new(text, "CRC mismatch"));
+        }
+        AdnlAddress::from_slice(&bytes[1..33])
+    }
+
+    pub fn to_hex(&self) -> String {
+        hex::encode(self.0)
+    }
+
+    /// Returns the 55 characters form.
+    pub fn to_text(&self) -> String {
+        let mut bytes = vec![ADNL_ADDRESS_TAG];
+        bytes.extend_from_slice(&self.0);
+        bytes.extend_from_slice(&crc16(&bytes).to_be_bytes());
+        let mut text = String::with_capacity(ADNL_ADDRESS_TEXT_LEN + 1);
+        let mut bits: u32 = 0;
+        let mut bit_len = 0;
+        for byte in bytes {
+            bits = (bits << 8) | byte as u32;
+            bit_len += 8;
+            while bit_len >= 5 {
+                bit_len -= 5;
+                text.push(BASE32_ALPHABET[((bits >> bit_len) & 0x1f) as usize] as char);
+            }
+            bits &= (1 << bit_len) - 1;
+        }
+        text.split_off(1)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl Display for AdnlAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.write_str(&self.to_text())
+    }
+}
+
+impl Debug for AdnlAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "AdnlAddress({})", self.to_hex())
+    }
+}
+
+impl FromStr for AdnlAddress {
+    type Err = AdnlAddressParseError;
+
+    /// Parses either of the 55 characters form and 64 hex digits.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() == 64 {
+            AdnlAddress::from_hex(s)
+        } else {
+            AdnlAddress::from_text(s)
+        }
+    }
+}
+
+impl From<[u8; 32]> for AdnlAddress {
+    fn from(bytes: [u8; 32]) -> Self {
+        AdnlAddress(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::adnl::AdnlAddress;
+
+    #[test]
+    fn adnl_address_works() -> anyhow::Result<()> {
+        let address = AdnlAddress::from_hex(
+            "8c4e9a8aa3e8b6d8ef3e47b2c5bae6a0b9fd7e9be0c8e0b4a9a1c61a5d6d0b1f",
+        )?;
+        let text = address.to_text();
+        assert_eq!(
+            text,
+            "wge5gukupulnwhphzd3frn242qlt7l6tpqmryfuvgq4mgs5nufr7eke"
+        );
+        assert_eq!(address.to_string(), text);
+        assert_eq!(text.parse::<AdnlAddress>()?, address);
+        assert_eq!(text.to_uppercase().parse::<AdnlAddress>()?, address);
+        assert_eq!(address.to_hex().parse::<AdnlAddress>()?, address);
+
+        let mut invalid = text.clone().into_bytes();
+        invalid[10] = if invalid[10] == b'a' { b'b' } else { b'a' };
+        assert!(AdnlAddress::from_text(&String::from_utf8(invalid)?).is_err());
+        assert!(AdnlAddress::from_text(&text[1..]).is_err());
+        assert!(AdnlAddress::from_text(&format!("1{}", &text[1..])).is_err());
+        assert!(AdnlAddress::from_slice(&[0; 31]).is_err());
+
+        let public_key: [u8; 32] = core::array::from_fn(|i| i as u8);
+        assert_eq!(
+            AdnlAddress::from_public_key(&public_key).to_hex(),
+            "dac97a9aa4db0219421e1583c57ab0ea3316892969b19f3a75e64382c3e558f7"
+        );
+        Ok(())
+    }
+}
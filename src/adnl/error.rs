@@ -0,0 +1,19 @@
+use alloc::string::{String, ToString};
+
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid ADNL address (Address: {address}, message: {message})")]
+pub struct AdnlAddressParseError {
+    address: String,
+    message: String,
+}
+
+impl AdnlAddressParseError {
+    pub fn new<A: ToString, M: ToString>(address: A, message: M) -> AdnlAddressParseError {
+        AdnlAddressParseError {
+            address: address.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
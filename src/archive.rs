@@ -0,0 +1,10 @@
+//! Readers of the files of node archives and persistent states, to index blocks and bootstrap
+//! indexers offline without a liteserver.
+
+mod error;
+mod package;
+mod persistent_state;
+
+pub use error::*;
+pub use package::*;
+pub use persistent_state::*;
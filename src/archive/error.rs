@@ -0,0 +1,24 @@
+use thiserror::Error;
+
+use crate::cell::TonCellError;
+
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Invalid package: {0}")]
+    InvalidPackage(String),
+
+    #[error("Invalid file name: {0}")]
+    InvalidFileName(String),
+
+    #[error("Invalid persistent state: {0}")]
+    InvalidState(String),
+
+    #[error("Invalid file {file_name}: {message}")]
+    InvalidFile { file_name: String, message: String },
+
+    #[error("Cell error: {0}")]
+    CellError(#[from] TonCellError),
+}
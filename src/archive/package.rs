@@ -0,0 +1,346 @@
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+use sha2::{Digest, Sha256};
+
+use crate::archive::ArchiveError;
+use crate::cell::{ArcCell, BagOfCells, ParseMode, ParseWarning, HASH_BYTES};
+use crate::responses::BlockData;
+
+/// Magic of the header of a package file.
+pub const PACKAGE_HEADER_MAGIC: u32 = 0xae8fdd01;
+/// Magic of the header of each entry of a package file.
+pub const PACKAGE_ENTRY_MAGIC: u16 = 0x1e8b;
+
+/// Id of a block as written in the file names of archive packages:
+/// `(workchain,shard,seqno):root_hash:file_hash`, with the shard and the hashes in hex.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ArchiveBlockId {
+    pub workchain: i32,
+    pub shard: u64,
+    pub seqno: u32,
+    pub root_hash: [u8; HASH_BYTES],
+    pub file_hash: [u8; HASH_BYTES],
+}
+
+impl FromStr for ArchiveBlockId {
+    type Err = ArchiveError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ArchiveError::InvalidFileName(s.to_string());
+        let (id, hashes) = s
+            .strip_prefix('(')
+            .and_then(|s| s.split_once("):"))
+            .ok_or_else(invalid)?;
+        let mut id = id.split(',');
+        let (root_hash, file_hash) = hashes.split_once(':').ok_or_else(invalid)?;
+        let hash =
+            |hash: &str| -> Option<[u8; HASH_BYTES]> { hex::decode(hash).ok()?.try_into().ok() };
+        let block_id = (|| {
+            Some(ArchiveBlockId {
+                workchain: id.next()?.parse().ok()?,
+                shard: u64::from_str_radix(id.next()?, 16).ok()?,
+                seqno: id.next()?.parse().ok()?,
+                root_hash: hash(root_hash)?,
+                file_hash: hash(file_hash)?,
+            })
+        })();
+        match block_id {
+            Some(block_id) if id.next().is_none() => Ok(block_id),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Display for ArchiveBlockId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "({},{:016X},{}):{}:{}",
+            self.workchain,
+            self.shard,
+            self.seqno,
+            hex::encode_upper(self.root_hash),
+            hex::encode_upper(self.file_hash)
+        )
+    }
+}
+
+/// Kind of a package entry, given by the prefix of its file name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PackageFile {
+    /// `block_<id>`: bag of cells of the block.
+    Block(ArchiveBlockId),
+    /// `proof_<id>`: proof of a masterchain block.
+    Proof(ArchiveBlockId),
+    /// `prooflink_<id>`: proof link of a shardchain block.
+    ProofLink(ArchiveBlockId),
+    /// Other files, e.g. states.
+    Other,
+}
+
+/// File of an archive package.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PackageEntry {
+    pub file_name: String,
+    pub data: Vec<u8>,
+}
+
+impl PackageEntry {
+    pub fn file(&self) -> Result<PackageFile, ArchiveError> {
+        let name = self.file_name.as_str();
+        let file = if let Some(id) = name.strip_prefix("block_") {
+            PackageFile::Block(id.parse()?)
+        } else if let Some(id) = name.strip_prefix("proof_") {
+            PackageFile::Proof(id.parse()?)
+        } else if let Some(id) = name.strip_prefix("prooflink_") {
+            PackageFile::ProofLink(id.parse()?)
+        } else {
+            PackageFile::Other
+        };
+        Ok(file)
+    }
+}
+
+/// Block read from an archive package.
+#[derive(Debug, Clone)]
+pub struct ArchiveBlock {
+    pub id: ArchiveBlockId,
+    pub root: ArcCell,
+    pub block: BlockData,
+    /// Structures skipped by the lenient mode, see [`ParseMode`].
+    pub warnings: Vec<ParseWarning>,
+}
+
+/// Reader of the package files (`.pack`) of node archives, which hold the blocks, proofs and
+/// proof links of a range of masterchain blocks:
+///
+/// ```raw
+/// package: magic:uint32 entries:(entry*)
+/// entry: magic:uint16 file_name_len:uint16 data_len:uint32 file_name:bytes data:bytes
+/// ```
+///
+/// Integers are little-endian. Entries are read one by one, so the whole package is not
+/// loaded into memory.
+pub struct PackageReader<R: Read> {
+    reader: R,
+}
+
+impl PackageReader<BufReader<File>> {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ArchiveError> {
+        PackageReader::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> PackageReader<R> {
+    /// Creates a reader, checking the header of the package.
+    pub fn new(mut reader: R) -> Result<Self, ArchiveError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+        let magic = u32::from_le_bytes(magic);
+        if magic != PACKAGE_HEADER_MAGIC {
+            return Err(ArchiveError::InvalidPackage(format!(
+                "Invalid header magic: {:08x}",
+                magic
+            )));
+        }
+        Ok(PackageReader { reader })
+    }
+
+    /// Returns the next entry, or `None` at the end of the package.
+    pub fn next_entry(&mut self) -> Result<Option<PackageEntry>, ArchiveError> {
+        let mut header = [0; 8];
+        if !read_exact_or_eof(&mut self.reader, &mut header)? {
+            return Ok(None);
+        }
+        let magic = u16::from_le_bytes([header[0], header[1]]);
+        if magic != PACKAGE_ENTRY_MAGIC {
+            return Err(ArchiveError::InvalidPackage(format!(
+                "Invalid entry magic: {:04x}",
+                magic
+            )));
+        }
+        let file_name_len = u16::from_le_bytes([header[2], header[3]]) as usize;
+        let data_len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let mut file_name = vec![0; file_name_len];
+        self.reader.read_exact(&mut file_name)?;
+        let file_name = String::from_utf8(file_name)
+            .map_err(|e| ArchiveError::InvalidFileName(e.to_string()))?;
+        // The length comes from the package, so the data is not allocated ahead of reading it
+        let mut data = Vec::new();
+        (&mut self.reader)
+            .take(data_len as u64)
+            .read_to_end(&mut data)?;
+        if data.len() < data_len as usize {
+            return Err(ArchiveError::InvalidPackage(format!(
+                "Truncated entry {}: {} of {} bytes",
+                file_name,
+                data.len(),
+                data_len
+            )));
+        }
+        Ok(Some(PackageEntry { file_name, data }))
+    }
+
+    /// Returns the blocks of the package, skipping proofs and other files.
+    ///
+    /// The root hash and the file hash of each block are checked against its file name.
+    pub fn blocks(
+        self,
+        mode: ParseMode,
+    ) -> impl Iterator<Item = Result<ArchiveBlock, ArchiveError>> {
+        self.filter_map(move |entry| {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => return Some(Err(e)),
+            };
+            match entry.file() {
+                Ok(PackageFile::Block(id)) => Some(load_block(id, &entry, mode)),
+                Ok(_) => None,
+                Err(e) => Some(Err(e)),
+            }
+        })
+    }
+}
+
+impl<R: Read> Iterator for PackageReader<R> {
+    type Item = Result<PackageEntry, ArchiveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry().transpose()
+    }
+}
+
+fn load_block(
+    id: ArchiveBlockId,
+    entry: &PackageEntry,
+    mode: ParseMode,
+) -> Result<ArchiveBlock, ArchiveError> {
+    let invalid = |message: &str| ArchiveError::InvalidFile {
+        file_name: entry.file_name.clone(),
+        message: message.to_string(),
+    };
+    if Sha256::digest(&entry.data).as_slice() != id.file_hash {
+        return Err(invalid("file hash mismatch"));
+    }
+    let boc = BagOfCells::parse(&entry.data)?;
+    let root = boc.single_root()?.clone();
+    if root.cell_hash_array()? != id.root_hash {
+        return Err(invalid("root hash mismatch"));
+    }
+    let parsed = root.load_block_with_mode(mode)?;
+    Ok(ArchiveBlock {
+        id,
+        root,
+        block: parsed.value,
+        warnings: parsed.warnings,
+    })
+}
+
+/// Fills the buffer, returning `false` if the reader is at its end.
+fn read_exact_or_eof<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<bool, ArchiveError> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) if read == 0 => return Ok(false),
+            Ok(0) => {
+                return Err(ArchiveError::InvalidPackage(
+                    "Truncated entry header".to_string(),
+                ))
+            }
+            Ok(n) => read += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use sha2::{Digest, Sha256};
+
+    use crate::archive::{
+        ArchiveBlockId, ArchiveError, PackageFile, PackageReader, PACKAGE_ENTRY_MAGIC,
+        PACKAGE_HEADER_MAGIC,
+    };
+    use crate::cell::{BagOfCells, ParseMode};
+
+    fn entry(package: &mut Vec<u8>, file_name: &str, data: &[u8]) {
+        package.extend_from_slice(&PACKAGE_ENTRY_MAGIC.to_le_bytes());
+        package.extend_from_slice(&(file_name.len() as u16).to_le_bytes());
+        package.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        package.extend_from_slice(file_name.as_bytes());
+        package.extend_from_slice(data);
+    }
+
+    #[test]
+    fn package_reader_works() -> anyhow::Result<()> {
+        let data = hex::decode(include_str!("../../resources/bloc/block_extra.hex").trim())?;
+        let root = BagOfCells::parse(&data)?.single_root()?.clone();
+        let info = root.load_block_header_only()?;
+        let id = ArchiveBlockId {
+            workchain: info.shard.workchain_id,
+            shard: info.shard.shard_prefix,
+            seqno: info.seq_no,
+            root_hash: root.cell_hash_array()?,
+            file_hash: Sha256::digest(&data).into(),
+        };
+        assert_eq!(id.to_string().parse::<ArchiveBlockId>()?, id);
+        assert!("(0,8000000000000000):00:00"
+            .parse::<ArchiveBlockId>()
+            .is_err());
+
+        let mut package = PACKAGE_HEADER_MAGIC.to_le_bytes().to_vec();
+        entry(&mut package, &format!("proof_{}", id), b"proof");
+        entry(&mut package, &format!("block_{}", id), &data);
+        entry(&mut package, "zerostate_0", b"state");
+
+        let entries = PackageReader::new(Cursor::new(&package))?.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].file()?, PackageFile::Proof(id.clone()));
+        assert_eq!(entries[1].file()?, PackageFile::Block(id.clone()));
+        assert_eq!(entries[2].file()?, PackageFile::Other);
+
+        let blocks = PackageReader::new(Cursor::new(&package))?
+            .blocks(ParseMode::Strict)
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].id, id);
+        assert_eq!(blocks[0].block.info.as_ref().unwrap().seq_no, id.seqno);
+
+        // Block not matching its file hash
+        let mut forged = PACKAGE_HEADER_MAGIC.to_le_bytes().to_vec();
+        let forged_id = ArchiveBlockId {
+            file_hash: [0; 32],
+            ..id
+        };
+        entry(&mut forged, &format!("block_{}", forged_id), &data);
+        let mut blocks = PackageReader::new(Cursor::new(&forged))?.blocks(ParseMode::Strict);
+        assert!(blocks.next().unwrap().is_err());
+
+        // Truncated package
+        let truncated = &package[..package.len() - 1];
+        let result = PackageReader::new(Cursor::new(truncated))?.collect::<Result<Vec<_>, _>>();
+        assert!(result.is_err());
+        assert!(PackageReader::new(Cursor::new(&[0u8; 4])).is_err());
+
+        // Entry claiming much more data than the package holds
+        let mut oversized = PACKAGE_HEADER_MAGIC.to_le_bytes().to_vec();
+        oversized.extend_from_slice(&PACKAGE_ENTRY_MAGIC.to_le_bytes());
+        oversized.extend_from_slice(&4u16.to_le_bytes());
+        oversized.extend_from_slice(&u32::MAX.to_le_bytes());
+        oversized.extend_from_slice(b"file1234");
+        let mut reader = PackageReader::new(Cursor::new(&oversized))?;
+        assert!(matches!(
+            reader.next_entry(),
+            Err(ArchiveError::InvalidPackage(_))
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,463 @@
+use std::fs;
+use std::path::Path;
+
+use crate::address::TonAddress;
+use crate::archive::ArchiveError;
+use crate::cell::{
+    ArcCell, BagOfCells, Cell, CellType, TonCellError, HASH_BYTES, SHARD_STATE_ACCOUNTS_REF,
+    SHARD_STATE_UNSPLIT_TAG,
+};
+use crate::responses::{DepthBalanceInfo, ShardAccount, ShardIdent};
+
+/// Bit length of the keys of `ShardAccounts`, i.e. of the account ids.
+const ACCOUNT_KEY_BITS: usize = 256;
+
+/// Header fields of a persistent shard state:
+///
+/// ```raw
+/// shard_state#9023afe2 global_id:int32 shard_id:ShardIdent seq_no:uint32
+///   vert_seq_no:# gen_utime:uint32 gen_lt:uint64 min_ref_mc_seqno:uint32 ... = ShardStateUnsplit;
+/// ```
+#[derive(Debug, Clone)]
+pub struct PersistentStateInfo {
+    pub global_id: i32,
+    pub shard: ShardIdent,
+    pub seq_no: u32,
+    pub vert_seq_no: u32,
+    pub gen_utime: u32,
+    pub gen_lt: u64,
+    pub min_ref_mc_seqno: u32,
+}
+
+/// Subtree of the `ShardAccounts` dictionary pruned from the header file of a split persistent
+/// state and stored in a separate part file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct StatePart {
+    /// Key prefix of the accounts of the part in the first `prefix_len` bits, the other bits
+    /// are zero.
+    pub prefix: [u8; 32],
+    pub prefix_len: usize,
+    /// Hash of the root cell of the part file.
+    pub hash: [u8; HASH_BYTES],
+}
+
+impl StatePart {
+    /// Returns the prefix as a shard id, i.e. followed by a tag bit, if it has at most 63
+    /// bits.
+    pub fn shard(&self) -> Option<u64> {
+        if self.prefix_len > 63 {
+            return None;
+        }
+        let mut prefix = [0; 8];
+        prefix.copy_from_slice(&self.prefix[..8]);
+        Some(u64::from_be_bytes(prefix) | 1 << (63 - self.prefix_len))
+    }
+}
+
+/// Account read from a persistent state.
+#[derive(Debug, Clone)]
+pub struct StateAccount {
+    pub address: TonAddress,
+    /// Augmentation of the dictionary leaf: the split depth and the balance of the account.
+    pub balance: DepthBalanceInfo,
+    pub account: ShardAccount,
+}
+
+/// Reader of the persistent shard states saved by nodes, to bootstrap an indexer with the
+/// accounts of a shard.
+///
+/// A state is stored either as a single bag of cells, or split into a header file in which
+/// subtrees of the `ShardAccounts` dictionary are replaced by pruned branches, and one part
+/// file per pruned subtree. Parts are matched to the pruned branches by the hash of their
+/// root.
+pub struct PersistentStateReader {
+    root: ArcCell,
+    info: PersistentStateInfo,
+}
+
+impl PersistentStateReader {
+    /// Reads the header file of a split state, or the file of a whole state.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ArchiveError> {
+        PersistentStateReader::parse(&fs::read(path)?)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, ArchiveError> {
+        let root = BagOfCells::parse(data)?.single_root()?.clone();
+        PersistentStateReader::new(root)
+    }
+
+    pub fn new(root: ArcCell) -> Result<Self, ArchiveError> {
+        let mut parser = root.parser();
+        if parser.load_u32(32)? != SHARD_STATE_UNSPLIT_TAG {
+            return Err(TonCellError::unexpected_structure("ShardStateUnsplit").into());
+        }
+        let info = PersistentStateInfo {
+            global_id: parser.load_i32(32)?,
+            shard: parser.load_shard_ident()?,
+            seq_no: parser.load_u32(32)?,
+            vert_seq_no: parser.load_u32(32)?,
+            gen_utime: parser.load_u32(32)?,
+            gen_lt: parser.load_u64(64)?,
+            min_ref_mc_seqno: parser.load_u32(32)?,
+        };
+        Ok(PersistentStateReader { root, info })
+    }
+
+    pub fn info(&self) -> &PersistentStateInfo {
+        &self.info
+    }
+
+    pub fn root(&self) -> &ArcCell {
+        &self.root
+    }
+
+    /// Returns the parts of a split state, in the order of their prefixes. It's empty for a
+    /// state stored in a single file.
+    pub fn parts(&self) -> Result<Vec<StatePart>, ArchiveError> {
+        let mut parts = Vec::new();
+        let mut stack: Vec<Edge> = self.root_edge()?.into_iter().collect();
+        while let Some(edge) = stack.pop() {
+            match expand(edge)? {
+                Step::Pruned(_, part) => parts.push(part),
+                Step::Fork(left, right) => {
+                    stack.push(right);
+                    stack.push(left);
+                }
+                Step::Leaf(..) => {}
+            }
+        }
+        Ok(parts)
+    }
+
+    /// Returns the accounts of the state in the order of their ids.
+    ///
+    /// `load_part` returns the bag of cells of the part file of a pruned subtree. It's called
+    /// when the iteration reaches the subtree, and the part is dropped once its accounts are
+    /// read, so a single part is kept in memory.
+    pub fn accounts<F>(&self, load_part: F) -> Result<StateAccounts<F>, ArchiveError>
+    where
+        F: FnMut(&StatePart) -> Result<Vec<u8>, ArchiveError>,
+    {
+        Ok(StateAccounts {
+            workchain: self.info.shard.workchain_id,
+            stack: self.root_edge()?.into_iter().collect(),
+            load_part,
+        })
+    }
+
+    /// Returns the root edge of `ShardAccounts`, or `None` if the dictionary is empty.
+    ///
+    /// ```raw
+    /// ahme_empty$0 = HashmapAugE n X Y;
+    /// ahme_root$1 root:^(HashmapAug n X Y) extra:Y = HashmapAugE n X Y;
+    /// ```
+    fn root_edge(&self) -> Result<Option<Edge>, ArchiveError> {
+        let accounts = self.root.reference(SHARD_STATE_ACCOUNTS_REF)?;
+        if is_pruned(accounts) {
+            return Err(ArchiveError::InvalidState(
+                "ShardAccounts is pruned".to_string(),
+            ));
+        }
+        if !accounts.parser().load_bit()? {
+            return Ok(None);
+        }
+        Ok(Some(Edge {
+            cell: accounts.reference(0)?.clone(),
+            key: [0; 32],
+            key_len: 0,
+        }))
+    }
+}
+
+/// Iterator over the accounts of a persistent state, see
+/// [`PersistentStateReader::accounts`].
+pub struct StateAccounts<F> {
+    workchain: i32,
+    stack: Vec<Edge>,
+    load_part: F,
+}
+
+impl<F> StateAccounts<F>
+where
+    F: FnMut(&StatePart) -> Result<Vec<u8>, ArchiveError>,
+{
+    fn next_account(&mut self) -> Result<Option<StateAccount>, ArchiveError> {
+        while let Some(edge) = self.stack.pop() {
+            match expand(edge)? {
+                Step::Pruned(edge, part) => {
+                    let data = (self.load_part)(&part)?;
+                    let root = BagOfCells::parse(&data)?.single_root()?.clone();
+                    if root.cell_hash_array()? != part.hash {
+                        return Err(ArchiveError::InvalidState(format!(
+                            "Part {} doesn't match its pruned subtree",
+                            hex::encode(part.hash)
+                        )));
+                    }
+                    self.stack.push(Edge { cell: root, ..edge });
+                }
+                Step::Fork(left, right) => {
+                    self.stack.push(right);
+                    self.stack.push(left);
+                }
+                Step::Leaf(edge, label_end) => {
+                    // ahmn_leaf#_ extra:Y value:X = HashmapAugNode 0 X Y;
+                    let cell = &edge.cell;
+                    let mut parser = cell.parser();
+                    parser.skip_bits(label_end)?;
+                    let mut ref_index = 0;
+                    let balance = Cell::load_depth_balance_info(cell, &mut ref_index, &mut parser)?;
+                    let account = Cell::load_shard_account(cell, &mut ref_index, &mut parser)?;
+                    return Ok(Some(StateAccount {
+                        address: TonAddress::new(self.workchain, &edge.key),
+                        balance,
+                        account,
+                    }));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<F> Iterator for StateAccounts<F>
+where
+    F: FnMut(&StatePart) -> Result<Vec<u8>, ArchiveError>,
+{
+    type Item = Result<StateAccount, ArchiveError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = self.next_account().transpose();
+        if let Some(Err(_)) = next {
+            self.stack.clear();
+        }
+        next
+    }
+}
+
+/// Edge of the `ShardAccounts` dictionary, starting after the first `key_len` bits of the
+/// keys.
+struct Edge {
+    cell: ArcCell,
+    key: [u8; 32],
+    key_len: usize,
+}
+
+enum Step {
+    Pruned(Edge, StatePart),
+    Fork(Edge, Edge),
+    /// Leaf with the bit offset of the end of its label.
+    Leaf(Edge, usize),
+}
+
+/// Reads the label of the edge:
+///
+/// ```raw
+/// ahm_edge#_ {n:#} {X:Type} {Y:Type} {l:#} {m:#} label:(HmLabel ~l n) {n = (~m) + l}
+///   node:(HashmapAugNode m X Y) = HashmapAug n X Y;
+/// ahmn_fork#_ {n:#} {X:Type} {Y:Type} left:^(HashmapAug n X Y) right:^(HashmapAug n X Y)
+///   extra:Y = HashmapAugNode (n + 1) X Y;
+/// ```
+fn expand(edge: Edge) -> Result<Step, ArchiveError> {
+    if is_pruned(&edge.cell) {
+        let part = StatePart {
+            prefix: edge.key,
+            prefix_len: edge.key_len,
+            hash: edge.cell.virtual_hash(0)?,
+        };
+        return Ok(Step::Pruned(edge, part));
+    }
+    let mut parser = edge.cell.parser();
+    let (label, label_len) = parser.load_label(ACCOUNT_KEY_BITS - edge.key_len)?;
+    let mut key = edge.key;
+    for i in 0..label_len {
+        if label.bit((label_len - 1 - i) as u64) {
+            set_bit(&mut key, edge.key_len + i);
+        }
+    }
+    let key_len = edge.key_len + label_len;
+    if key_len == ACCOUNT_KEY_BITS {
+        let label_end = parser.bit_offset();
+        return Ok(Step::Leaf(
+            Edge {
+                key,
+                key_len,
+                ..edge
+            },
+            label_end,
+        ));
+    }
+    let left = Edge {
+        cell: edge.cell.reference(0)?.clone(),
+        key,
+        key_len: key_len + 1,
+    };
+    set_bit(&mut key, key_len);
+    let right = Edge {
+        cell: edge.cell.reference(1)?.clone(),
+        key,
+        key_len: key_len + 1,
+    };
+    Ok(Step::Fork(left, right))
+}
+
+fn is_pruned(cell: &Cell) -> bool {
+    cell.cell_type == CellType::PrunnedBranchCell as u8
+}
+
+fn set_bit(key: &mut [u8; 32], bit: usize) {
+    key[bit / 8] |= 0x80 >> (bit % 8);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use num_bigint::BigUint;
+
+    use crate::address::TonAddress;
+    use crate::archive::{ArchiveError, PersistentStateReader, StatePart};
+    use crate::cell::{
+        prune_cells, ArcCell, BagOfCells, CellBuilder, StateInitBuilder, SHARD_STATE_UNSPLIT_TAG,
+    };
+    use crate::responses::AccountStorageState;
+
+    fn store_balance(builder: &mut CellBuilder, balance: u64) -> anyhow::Result<()> {
+        // split_depth, grams, no extra currencies
+        builder
+            .store_u8(5, 0)?
+            .store_var_uinteger(16, &BigUint::from(balance))?
+            .store_bit(false)?;
+        Ok(())
+    }
+
+    /// Leaf with the label `hml_same$11 v:0 n:255` after the first key bit.
+    fn leaf(address: &TonAddress, balance: u64, frozen: bool) -> anyhow::Result<ArcCell> {
+        let mut account = CellBuilder::new();
+        account
+            .store_bit(true)?
+            .store_address(address)?
+            .store_var_uinteger(7, &BigUint::from(3u8))?
+            .store_var_uinteger(7, &BigUint::from(500u16))?
+            .store_u8(3, 0)? // storage_extra_none
+            .store_u32(32, 1700000000)?
+            .store_bit(false)? // due_payment
+            .store_u64(64, 42)?
+            .store_var_uinteger(16, &BigUint::from(balance))?
+            .store_bit(false)?;
+        if frozen {
+            account.store_u8(2, 0b01)?.store_slice(&[7; 32])?;
+        } else {
+            let code = Arc::new(CellBuilder::new().store_string("code")?.build()?);
+            let data = Arc::new(CellBuilder::new().store_string("data")?.build()?);
+            account
+                .store_bit(true)?
+                .store_cell(&StateInitBuilder::new(&code, &data).build()?)?;
+        }
+        let mut leaf = CellBuilder::new();
+        leaf.store_u8(2, 0b11)?.store_bit(false)?.store_u8(8, 255)?;
+        store_balance(&mut leaf, balance)?;
+        leaf.store_reference(&Arc::new(account.build()?))?
+            .store_slice(&[1; 32])?
+            .store_u64(64, 42)?;
+        Ok(Arc::new(leaf.build()?))
+    }
+
+    fn state(accounts: ArcCell) -> anyhow::Result<ArcCell> {
+        let empty = Arc::new(CellBuilder::new().build()?);
+        let mut state = CellBuilder::new();
+        state
+            .store_u32(32, SHARD_STATE_UNSPLIT_TAG)?
+            .store_i32(32, -239)?
+            .store_u8(2, 0)? // shard_ident$00
+            .store_u8(6, 0)?
+            .store_i32(32, 0)?
+            .store_u64(64, 0x8000000000000000)?
+            .store_u32(32, 100)? // seq_no
+            .store_u32(32, 0)?
+            .store_u32(32, 1700000000)?
+            .store_u64(64, 1000)?
+            .store_u32(32, 90)?
+            .store_bit(false)? // before_split
+            .store_reference(&empty)?
+            .store_reference(&accounts)?
+            .store_reference(&empty)?
+            .store_bit(false)?; // custom
+        Ok(Arc::new(state.build()?))
+    }
+
+    #[test]
+    fn persistent_state_reader_works() -> anyhow::Result<()> {
+        let left: TonAddress = format!("0:{}", "00".repeat(32)).parse()?;
+        let right: TonAddress = format!("0:80{}", "00".repeat(31)).parse()?;
+        let right_leaf = leaf(&right, 200, true)?;
+        let mut fork = CellBuilder::new();
+        fork.store_u8(2, 0b00)?; // hml_short$0 with an empty label
+        store_balance(&mut fork, 300)?;
+        fork.store_reference(&leaf(&left, 100, false)?)?
+            .store_reference(&right_leaf)?;
+        let mut accounts = CellBuilder::new();
+        accounts
+            .store_bit(true)?
+            .store_reference(&Arc::new(fork.build()?))?;
+        store_balance(&mut accounts, 300)?;
+        let state = state(Arc::new(accounts.build()?))?;
+
+        let reader = PersistentStateReader::new(state.clone())?;
+        assert_eq!(reader.info().seq_no, 100);
+        assert_eq!(reader.info().shard.workchain_id, 0);
+        assert!(reader.parts()?.is_empty());
+        let accounts = reader
+            .accounts(|_| unreachable!())?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].address, left);
+        assert_eq!(accounts[1].address, right);
+        assert_eq!(
+            accounts[1].balance.balance.grams.value,
+            BigUint::from(200u8)
+        );
+        let account = accounts[0].account.account.as_ref().unwrap();
+        assert_eq!(account.address, left);
+        assert_eq!(account.storage_stat.used.bits.value, BigUint::from(500u16));
+        assert_eq!(account.storage_stat.last_paid, 1700000000);
+        assert_eq!(account.last_trans_lt, 42);
+        let AccountStorageState::Active { data, .. } = &account.state else {
+            panic!("Expected an active account");
+        };
+        assert_eq!(data.as_ref().unwrap().data.as_ref(), b"data");
+        let account = accounts[1].account.account.as_ref().unwrap();
+        assert!(account.state.status().is_frozen());
+        assert_eq!(accounts[1].account.last_trans_hash, [1; 32]);
+
+        // Split state with the right subtree in a part file
+        let header = prune_cells(&state, |path, _| path != [1, 0, 1])?;
+        let header = BagOfCells::new(&[header]).serialize(false)?;
+        let part_data = BagOfCells::new(&[right_leaf]).serialize(false)?;
+        let reader = PersistentStateReader::parse(&header)?;
+        let parts = reader.parts()?;
+        assert_eq!(parts.len(), 1);
+        assert_eq!(parts[0].prefix_len, 1);
+        assert_eq!(parts[0].shard(), Some(0xc000000000000000));
+        let mut loaded = Vec::new();
+        let split_accounts = reader
+            .accounts(|part: &StatePart| {
+                loaded.push(part.clone());
+                Ok(part_data.clone())
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(loaded, parts);
+        assert_eq!(split_accounts.len(), 2);
+        assert_eq!(split_accounts[1].address, right);
+        assert!(split_accounts[1].account.account.is_some());
+
+        // Part not matching the pruned subtree
+        let mut accounts = reader.accounts(|_| Ok(header.clone()))?;
+        assert!(accounts.next().unwrap().is_ok());
+        assert!(matches!(
+            accounts.next(),
+            Some(Err(ArchiveError::InvalidState(_)))
+        ));
+        assert!(accounts.next().is_none());
+        Ok(())
+    }
+}
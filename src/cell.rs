@@ -1,52 +1,85 @@
-use std::collections::HashMap;
-use std::fmt;
-use std::fmt::{Debug, Formatter};
-use std::hash::Hash;
-use std::io::Cursor;
-use std::ops::Deref;
-use std::process::exit;
-use std::sync::Arc;
-
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::{Debug, Formatter};
+use core::hash::Hash;
+use core::ops::Deref;
+
+#[cfg(feature = "arbitrary")]
+pub use self::arbitrary::*;
 pub use bag_of_cells::*;
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use base64::Engine;
 use bit_reader::BitArrayReader;
-use bit_string::*;
-use bitstream_io::{BigEndian, BitReader, BitWrite, BitWriter, ByteRead, ByteReader};
+pub use bit_string::*;
+use bitstream_io::{BigEndian, BitReader, BitWrite2 as BitWrite, BitWriter};
+pub use block_selector::*;
 pub use builder::*;
+pub use dict_builder::*;
 pub use dict_loader::*;
 pub use error::*;
-use log::debug;
 use num_bigint::BigUint;
 use num_traits::{FromPrimitive, One, ToPrimitive, Zero};
+pub use parse_mode::*;
 pub use parser::*;
+pub use path::*;
+pub use pruner::*;
 pub use raw::*;
 use sha2::{Digest, Sha256};
 pub use slice::*;
 pub use state_init::*;
+pub use tlb::*;
+pub use transaction_resolver::*;
 pub use util::*;
 
 use crate::address::TonAddress;
+use crate::compat::io::Cursor;
+use crate::compat::HashMap;
 use crate::hashmap::{Hashmap, HashmapAugEResult, HashmapAugResult};
 use crate::responses::{
-    AccountBlock, AnyCell, BinTreeFork, BinTreeLeafRes, BinTreeRes, BlkPrevRef, BlockData,
-    BlockExtra, BlockInfo, CommonTransactionMessageInfo, ConfigParam, ConfigParams,
-    ConfigParamsValidatorSet, CurrencyCollection, ExtBlkRef, MaybeRefData, McBlockExtra,
-    MessageType, ShardDescr, Transaction, TransactionBody, TransactionMessage, ValidatorDescr,
-    Validators, VarUInteger,
+    AccStatusChange, Account, AccountBlock, AccountStatus, AccountStorageState, AnyCell,
+    BinTreeAugFork, BinTreeAugLeaf, BinTreeAugRes, BinTreeFork, BinTreeLeafRes, BinTreeRes,
+    BlkPrevRef, BlockData, BlockExtra, BlockInfo, CommonTransactionMessageInfo,
+    ComputePhaseVmDetails, ComputeSkipReason, ConfigParam, ConfigParams, ConfigParamsValidatorSet,
+    CryptoSignature, CryptoSignaturePair, CurrencyCollection, DepthBalanceInfo, EnqueuedMsg,
+    ExtBlkRef, HashUpdate, ImportFees, InMsg, IntermediateAddress, KeyExtBlkRef, KeyMaxLt,
+    MaybeRefData, McBlockExtra, MerkleUpdate, MessageType, MsgEnvelope, MsgMetadata,
+    OldMcBlocksInfo, OutMsg, OutMsgQueueInfo, ShardAccount, ShardDescr, ShardFeeCreated,
+    SplitMergeInfo, StorageInfo, StorageUsedShort, TrActionPhase, TrBouncePhase, TrComputePhase,
+    TrComputePhaseVm, TrCreditPhase, TrStoragePhase, Transaction, TransactionBody,
+    TransactionDescr, TransactionDescrMergeInstall, TransactionDescrMergePrepare,
+    TransactionDescrOrdinary, TransactionDescrSplitInstall, TransactionDescrSplitPrepare,
+    TransactionDescrTickTock, TransactionMessage, ValidatorDescr, Validators, ValueFlow,
+    VarUInteger,
 };
+use crate::trace::{parse_event, parse_failed, parse_span};
 
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
 mod bag_of_cells;
 mod bit_reader;
 mod bit_string;
+mod block_selector;
 mod builder;
+mod dict_builder;
 mod dict_loader;
+mod dump;
 mod error;
+mod parse_mode;
 mod parser;
+mod path;
+mod pruner;
 mod raw;
 mod slice;
 mod state_init;
+mod tlb;
+mod transaction_rebuild;
+mod transaction_resolver;
 mod util;
+mod virtualized;
 
 pub type ArcCell = Arc<Cell>;
 
@@ -54,10 +87,19 @@ pub type SnakeFormattedDict = HashMap<[u8; 32], Vec<u8>>;
 
 pub const HASH_BYTES: usize = 32;
 pub const DEPTH_BYTES: usize = 2;
+const MAX_LEVEL: u8 = 3;
+
+/// Level masks, representation hashes and depths of the cells visited while calculating a
+/// representation, keyed by the address of the cell.
+#[derive(Default)]
+struct ReprMemo {
+    level_masks: HashMap<*const Cell, u8>,
+    hashes: HashMap<*const Cell, ([u8; HASH_BYTES], usize)>,
+}
 
 #[derive(PartialEq, Eq, Clone, Hash, Default)]
 pub struct Cell {
-    pub data: Vec<u8>,
+    pub data: Arc<[u8]>,
     pub bit_len: usize,
     pub references: Vec<ArcCell>,
     pub cell_type: u8,
@@ -65,20 +107,32 @@ pub struct Cell {
     pub is_exotic: bool,
     pub has_hashes: bool,
     pub proof: bool,
-    pub hashes: Vec<Vec<u8>>,
+    pub hashes: Vec<[u8; HASH_BYTES]>,
     pub depth: Vec<u16>,
 }
 
 impl Cell {
+    /// Returns the bits of the cell in binary and hex, 32 bits per row, see
+    /// [`CellParser::debug_dump`].
+    pub fn dump_bits(&self) -> String {
+        format!(
+            "{} bits, {} references\n{}",
+            self.bit_len,
+            self.references.len(),
+            dump::dump_bits(&self.data, 0, self.bit_len, None)
+        )
+    }
+
     pub fn parser(&self) -> CellParser {
         let bit_len = self.bit_len;
-        let cursor = Cursor::new(&self.data);
-        let bit_reader: BitReader<Cursor<&Vec<u8>>, BigEndian> =
-            BitReader::endian(cursor, BigEndian);
+        let cursor = Cursor::new(&self.data[..]);
+        let bit_reader: BitReader<Cursor<&[u8]>, BigEndian> = BitReader::endian(cursor, BigEndian);
 
         CellParser {
             bit_len,
             bit_reader,
+            references: &self.references,
+            next_ref: 0,
         }
     }
 
@@ -145,44 +199,66 @@ impl Cell {
     }
 
     fn get_level_mask(&self) -> Result<u8, TonCellError> {
-        if self.is_exotic && self.cell_type != CellType::LibraryCell as u8 {
+        self.get_level_mask_with(&mut ReprMemo::default())
+    }
+
+    fn get_level_mask_with(&self, memo: &mut ReprMemo) -> Result<u8, TonCellError> {
+        let key = self as *const Cell;
+        if let Some(level_mask) = memo.level_masks.get(&key) {
+            return Ok(*level_mask);
+        }
+        let level_mask = if self.is_exotic && self.cell_type != CellType::LibraryCell as u8 {
             // console.log(this.type);
             if self.cell_type == CellType::PrunnedBranchCell as u8 {
-                return Ok(self.level_mask);
-            }
-            if self.cell_type == CellType::MerkleProofCell as u8 {
-                return Ok(self.reference(0)?.get_level_mask()? >> 1);
-            }
-            if self.cell_type == CellType::MerkleUpdateCell as u8 {
-                return Ok(self.reference(0)?.get_level_mask()?
-                    | self.reference(1)?.get_level_mask()? >> 1);
+                self.level_mask
+            } else if self.cell_type == CellType::MerkleProofCell as u8 {
+                self.reference(0)?.get_level_mask_with(memo)? >> 1
+            } else if self.cell_type == CellType::MerkleUpdateCell as u8 {
+                self.reference(0)?.get_level_mask_with(memo)?
+                    | self.reference(1)?.get_level_mask_with(memo)? >> 1
+            } else {
+                return Err(TonCellError::cell_parser_error("Unknown special cell type"));
             }
-
-            return Err(TonCellError::cell_parser_error("Unknown special cell type"));
         } else {
             let mut level_mask = 0;
-            for i in 0..self.references.len() {
-                let reference = self.reference(i)?;
-                level_mask |= reference.get_level_mask()?;
+            for reference in &self.references {
+                level_mask |= reference.get_level_mask_with(memo)?;
             }
-            return Ok(level_mask);
-        }
+            level_mask
+        };
+        memo.level_masks.insert(key, level_mask);
+        Ok(level_mask)
     }
 
     pub fn get_hash(&self, level: u8) -> Vec<u8> {
+        self.hash_ref(level).to_vec()
+    }
+
+    /// Returns the hash of the given level without copying it.
+    ///
+    /// Hashes are calculated when the cell is deserialized from a bag of cells. Higher hashes
+    /// of pruned branches are borrowed from the cell data.
+    ///
+    /// # Panics
+    ///
+    /// If the hashes were not calculated, e.g. for cells made by [`CellBuilder`].
+    pub fn hash_ref(&self, level: u8) -> &[u8; HASH_BYTES] {
         let mut hash_i = Cell::get_hashes_count_from_mask(self.apply_level_mask(level)) - 1;
         if self.cell_type == CellType::PrunnedBranchCell as u8 {
             let this_hash_i = self.get_hashes_count() - 1;
             if hash_i != this_hash_i {
-                let bit_reader = BitArrayReader {
-                    array: self.data.clone(),
-                    cursor: 0,
-                };
-                return bit_reader.get_range(16 + (hash_i as usize) * HASH_BYTES * 8, 256);
+                let offset = 2 + hash_i as usize * HASH_BYTES;
+                // Size of the data is checked when the pruned branch is finalized
+                return self.data[offset..offset + HASH_BYTES].try_into().unwrap();
             }
             hash_i = 0;
         }
-        return self.hashes[hash_i as usize].clone();
+        &self.hashes[hash_i as usize]
+    }
+
+    /// Returns a copy of the hash of the given level, see [`Cell::hash_ref`].
+    pub fn hash_array(&self, level: u8) -> [u8; HASH_BYTES] {
+        *self.hash_ref(level)
     }
 
     fn get_depth(&self, level: Option<u8>) -> u64 {
@@ -195,7 +271,7 @@ impl Cell {
             let this_hash_i = self.get_hashes_count() - 1;
             if hash_i != this_hash_i {
                 let bit_reader = BitArrayReader {
-                    array: self.data.clone(),
+                    array: self.data.to_vec(),
                     cursor: self.bit_len,
                 };
                 return bit_reader.read_uint16(
@@ -208,27 +284,50 @@ impl Cell {
         return self.depth[hash_i as usize] as u64;
     }
 
-    fn get_max_depth(&self) -> usize {
+    /// Returns the representation hash and the depth of the cell, memoizing them by the
+    /// address of the cell so subtrees shared by several parents are visited once.
+    ///
+    /// Hashes and depths cached at deserialization are returned without visiting the
+    /// references.
+    fn repr_hash_and_depth(
+        &self,
+        memo: &mut ReprMemo,
+    ) -> Result<([u8; HASH_BYTES], usize), TonCellError> {
+        if !self.hashes.is_empty() {
+            return Ok((
+                *self.hash_ref(MAX_LEVEL),
+                self.get_depth(Some(MAX_LEVEL)) as usize,
+            ));
+        }
+        let key = self as *const Cell;
+        if let Some(res) = memo.hashes.get(&key) {
+            return Ok(*res);
+        }
         let mut max_depth = 0;
-        if !self.references.is_empty() {
-            for k in &self.references {
-                let depth = k.get_max_depth();
-                if depth > max_depth {
-                    max_depth = depth;
-                }
-            }
-            max_depth += 1;
+        for r in &self.references {
+            let (_, depth) = r.repr_hash_and_depth(memo)?;
+            max_depth = max_depth.max(depth + 1);
         }
-        max_depth
+        let hash = Sha256::digest(self.get_repr_with(memo)?).into();
+        memo.hashes.insert(key, (hash, max_depth));
+        Ok((hash, max_depth))
     }
 
     fn get_refs_descriptor(&self, _level_mask: Option<u8>) -> Result<[u8; 1], TonCellError> {
+        self.get_refs_descriptor_with(_level_mask, &mut ReprMemo::default())
+    }
+
+    fn get_refs_descriptor_with(
+        &self,
+        _level_mask: Option<u8>,
+        memo: &mut ReprMemo,
+    ) -> Result<[u8; 1], TonCellError> {
         let mut level_mask = 0u8;
         if !self.proof {
             level_mask = if let Some(level_mask) = _level_mask {
                 level_mask
             } else {
-                self.get_level_mask()?
+                self.get_level_mask_with(memo)?
             };
         }
         let mut d1: [u8; 1] = [0];
@@ -242,7 +341,7 @@ impl Cell {
     fn get_bits_descriptor(&self) -> u8 {
         let rest_bits = self.bit_len % 8;
         let full_bytes = rest_bits == 0;
-        self.data.len() as u8 * 2 - if full_bytes { 0 } else { 1 } //subtract 1 if the last byte is not full
+        (self.data.len() * 2 - if full_bytes { 0 } else { 1 }) as u8 //subtract 1 if the last byte is not full
     }
 
     fn depth_to_array(&self, depth: usize) -> [u8; 2] {
@@ -253,11 +352,15 @@ impl Cell {
     }
 
     pub fn get_repr(&self) -> Result<Vec<u8>, TonCellError> {
+        self.get_repr_with(&mut ReprMemo::default())
+    }
+
+    fn get_repr_with(&self, memo: &mut ReprMemo) -> Result<Vec<u8>, TonCellError> {
         let data_len = self.data.len();
         let rest_bits = self.bit_len % 8;
         let full_bytes = rest_bits == 0;
         let mut writer = BitWriter::endian(Vec::new(), BigEndian);
-        let val = self.get_refs_descriptor(None)?;
+        let val = self.get_refs_descriptor_with(None, memo)?;
         writer
             .write(8, val[0] as u32)
             .map_boc_serialization_error()?;
@@ -277,18 +380,19 @@ impl Cell {
                 .map_boc_serialization_error()?;
         }
 
+        let mut ref_hashes = Vec::with_capacity(self.references.len());
         for r in &self.references {
+            let (hash, depth) = r.repr_hash_and_depth(memo)?;
             writer
-                .write(8, (r.get_max_depth() / 256) as u8)
+                .write(8, (depth / 256) as u8)
                 .map_boc_serialization_error()?;
             writer
-                .write(8, (r.get_max_depth() % 256) as u8)
+                .write(8, (depth % 256) as u8)
                 .map_boc_serialization_error()?;
+            ref_hashes.push(hash);
         }
-        for r in &self.references {
-            writer
-                .write_bytes(&r.cell_hash()?)
-                .map_boc_serialization_error()?;
+        for hash in &ref_hashes {
+            writer.write_bytes(hash).map_boc_serialization_error()?;
         }
         let result = writer
             .writer()
@@ -299,7 +403,7 @@ impl Cell {
 
     pub fn finalize(&mut self) -> Result<(), TonCellError> {
         let bit_reader = BitArrayReader {
-            array: self.data.clone(),
+            array: self.data.to_vec(),
             cursor: self.bit_len,
         };
 
@@ -375,9 +479,9 @@ impl Cell {
                     ));
                 }
                 let merkle_hash = bit_reader.get_range(8, HASH_BYTES * 8);
-                let child_hash = self.references[0].get_hash(0);
+                let child_hash = self.references[0].hash_ref(0);
 
-                if !merkle_hash.eq(&child_hash) {
+                if merkle_hash.as_slice() != child_hash {
                     return Err(TonCellError::boc_deserialization_error(
                         "Hash mismatch in a MerkleProof special cell",
                     ));
@@ -403,8 +507,8 @@ impl Cell {
                     ));
                 }
                 let merkle_hash_0 = bit_reader.get_range(8, HASH_BYTES * 8);
-                let child_hash_0 = self.references[0].get_hash(0);
-                if !merkle_hash_0.eq(&child_hash_0) {
+                let child_hash_0 = self.references[0].hash_ref(0);
+                if merkle_hash_0.as_slice() != child_hash_0 {
                     return Err(TonCellError::boc_deserialization_error(
                         "First hash mismatch in a MerkleUpdate special cell",
                     ));
@@ -443,7 +547,7 @@ impl Cell {
         };
         let hash_i_offset = total_hash_count - hash_count;
 
-        self.hashes = vec![vec![]; hash_count as usize];
+        self.hashes = vec![[0; HASH_BYTES]; hash_count as usize];
         self.depth = vec![0; hash_count as usize];
 
         let mut hash_i = 0;
@@ -486,7 +590,7 @@ impl Cell {
                     ));
                 }
 
-                repr = concat_bytes(&repr, &self.hashes[(hash_i - hash_i_offset - 1) as usize]);
+                repr.extend_from_slice(&self.hashes[(hash_i - hash_i_offset - 1) as usize]);
             }
 
             let dest_i = hash_i - hash_i_offset;
@@ -502,7 +606,7 @@ impl Cell {
                     child_depth = i.get_depth(Some(level_i));
                 }
                 repr = concat_bytes(&repr, &i.depth_to_array(child_depth as usize).to_vec());
-                depth = std::cmp::max(depth, child_depth);
+                depth = core::cmp::max(depth, child_depth);
             }
 
             if self.references.len() != 0 {
@@ -519,16 +623,16 @@ impl Cell {
                 if self.cell_type == CellType::MerkleProofCell as u8
                     || self.cell_type == CellType::MerkleUpdateCell as u8
                 {
-                    repr = concat_bytes(&repr, &self.references[i].get_hash(level_i + 1));
+                    repr.extend_from_slice(self.references[i].hash_ref(level_i + 1));
                 } else {
-                    repr = concat_bytes(&repr, &self.references[i].get_hash(level_i));
+                    repr.extend_from_slice(self.references[i].hash_ref(level_i));
                 }
             }
 
             let mut hasher: Sha256 = Sha256::new();
             hasher.update(repr);
 
-            self.hashes[dest_i as usize] = hasher.finalize()[..].to_vec();
+            self.hashes[dest_i as usize] = hasher.finalize().into();
 
             hash_i += 1;
         }
@@ -538,9 +642,16 @@ impl Cell {
     }
 
     pub fn cell_hash(&self) -> Result<Vec<u8>, TonCellError> {
-        let mut hasher: Sha256 = Sha256::new();
-        hasher.update(self.get_repr()?.as_slice());
-        Ok(hasher.finalize()[..].to_vec())
+        Ok(self.cell_hash_array()?.to_vec())
+    }
+
+    /// Returns the representation hash as an array.
+    ///
+    /// The hashes calculated when the cell was deserialized are returned if present, so the
+    /// `hashes` must be cleared when the data or the references of such a cell are changed.
+    /// Otherwise the hash is calculated once for each distinct cell of the tree.
+    pub fn cell_hash_array(&self) -> Result<[u8; HASH_BYTES], TonCellError> {
+        Ok(self.repr_hash_and_depth(&mut ReprMemo::default())?.0)
     }
 
     pub fn cell_hash_base64(&self) -> Result<String, TonCellError> {
@@ -639,6 +750,42 @@ impl Cell {
         Ok(map)
     }
 
+    /// Returns the representation hash of the `Hashmap n X` rooted at the cell, checking the
+    /// labels and forks of its edges without loading the values.
+    ///
+    /// Pruned branches are not entered and stand for the subtrees they replace, so the hash of
+    /// a dictionary in a Merkle proof equals the hash of the full dictionary. Augmented
+    /// dictionaries are checked the same way, their extra data being skipped with the values.
+    pub fn dict_hash(&self, key_bit_len: usize) -> Result<[u8; HASH_BYTES], TonCellError> {
+        self.check_dict_edge(key_bit_len)?;
+        self.virtual_hash(0)
+    }
+
+    fn check_dict_edge(&self, key_bit_len: usize) -> Result<(), TonCellError> {
+        if self.cell_type == CellType::PrunnedBranchCell as u8 {
+            return Ok(());
+        }
+        let (_, label_len) = self.parser().load_label(key_bit_len)?;
+        if label_len > key_bit_len {
+            return Err(TonCellError::cell_parser_error(format!(
+                "Dictionary label of {} bits is longer than the {} remaining key bits",
+                label_len, key_bit_len
+            )));
+        }
+        if label_len == key_bit_len {
+            return Ok(());
+        }
+        if self.references.len() < 2 {
+            return Err(TonCellError::cell_parser_error(format!(
+                "Dictionary fork has {} references, expected 2",
+                self.references.len()
+            )));
+        }
+        let fork_key_bit_len = key_bit_len - label_len - 1;
+        self.references[0].check_dict_edge(fork_key_bit_len)?;
+        self.references[1].check_dict_edge(fork_key_bit_len)
+    }
+
     ///Port of https://github.com/ton-community/ton/blob/17b7e9e6154131399d57507b0c4a178752342fd8/src/boc/dict/parseDict.ts#L55
     fn dict_to_hashmap<K, V, L>(
         &self,
@@ -659,35 +806,21 @@ impl Cell {
             // Short label detected
             prefix_length = parser.load_unary_length()?;
             // Read prefix
-            if prefix_length != 0 {
-                let val = parser.load_uint(prefix_length)?;
-                pp.shl_assign_and_add(prefix_length, val);
-            }
+            pp.append(&parser.load_bitstring(prefix_length)?);
         } else {
             let lb1 = parser.load_bit()?;
             if !lb1 {
                 // Long label detected
                 prefix_length = parser
-                    .load_uint(
-                        ((dict_loader.key_bit_len() - pp.bit_len() + 1) as f32)
-                            .log2()
-                            .ceil() as usize,
-                    )?
+                    .load_uint(label_len_bits(dict_loader.key_bit_len() - pp.bit_len()))?
                     .to_usize()
                     .unwrap();
-                if prefix_length != 0 {
-                    let val = parser.load_uint(prefix_length)?;
-                    pp.shl_assign_and_add(prefix_length, val);
-                }
+                pp.append(&parser.load_bitstring(prefix_length)?);
             } else {
                 // Same label detected
                 let bit = parser.load_bit()?;
                 prefix_length = parser
-                    .load_uint(
-                        ((dict_loader.key_bit_len() - pp.bit_len() + 1) as f32)
-                            .log2()
-                            .ceil() as usize,
-                    )?
+                    .load_uint(label_len_bits(dict_loader.key_bit_len() - pp.bit_len()))?
                     .to_usize()
                     .unwrap();
                 if bit {
@@ -699,8 +832,8 @@ impl Cell {
         }
 
         if dict_loader.key_bit_len() - pp.bit_len() == 0 {
-            let bytes = pp.get_value_as_bytes();
-            let key = dict_loader.extract_key(bytes.as_slice())?;
+            // Keys are passed as in cell data, so keys of partial bytes are padded on the right
+            let key = dict_loader.extract_key(pp.data().as_slice())?;
             let offset = self.bit_len - parser.remaining_bits();
             let cell_slice = CellSlice::new_with_offset(self, offset)?;
             let value = dict_loader.extract_value(&cell_slice)?;
@@ -730,11 +863,16 @@ impl Cell {
         F: FnOnce(&Cell, &mut usize, &mut CellParser) -> Result<T, TonCellError>,
     {
         let reference = self.reference(ref_index.to_owned())?;
+        let _span = parse_span!("load_ref", ref_index = *ref_index);
         *ref_index += 1;
         let mut parser = reference.parser();
         if reference.cell_type != CellType::PrunnedBranchCell as u8 && parse_option.is_some() {
             let parse = parse_option.unwrap();
-            let res = parse(&reference, &mut 0usize, &mut parser)?;
+            let res = parse(reference, &mut 0usize, &mut parser).map_err(|e| {
+                let bit_offset = parser.bit_offset();
+                parse_failed(reference, *ref_index - 1, bit_offset, &e);
+                e.in_reference(*ref_index - 1, bit_offset)
+            })?;
             return Ok((Some(res), None));
         } else if reference.cell_type == CellType::PrunnedBranchCell as u8 {
             return Ok((None, Some(reference)));
@@ -759,32 +897,38 @@ impl Cell {
             return Ok(MaybeRefData {
                 data: None,
                 cell: None,
+                verified: false,
             });
         };
         let reference = self.reference(ref_index.to_owned())?;
+        let _span = parse_span!("load_maybe_ref", ref_index = *ref_index);
         *ref_index += 1;
         let mut new_parser = reference.parser();
-        debug!(
-            "reference cell type, ref index and ref data: {:?}, {:?}, {:?}",
-            reference.cell_type, ref_index, reference.data
-        );
+        parse_event!("loading maybe ref", cell_type = reference.cell_type);
         if reference.cell_type != CellType::PrunnedBranchCell as u8 {
             let f = parse_option.unwrap();
-            let res = f(&reference, &mut 0usize, &mut new_parser)?;
+            let res = f(reference, &mut 0usize, &mut new_parser).map_err(|e| {
+                let bit_offset = new_parser.bit_offset();
+                parse_failed(reference, *ref_index - 1, bit_offset, &e);
+                e.in_reference(*ref_index - 1, bit_offset)
+            })?;
             return Ok(MaybeRefData {
                 data: Some(res),
                 cell: None,
+                verified: false,
             });
         } else if let Some(f2) = parse_prunned_branch_cell_option {
             let res = f2(&reference, &mut 0usize, &mut new_parser)?;
             return Ok(MaybeRefData {
                 data: Some(res),
                 cell: None,
+                verified: false,
             });
         }
         Ok(MaybeRefData {
             data: None,
             cell: Some(reference.as_ref().clone()),
+            verified: false,
         })
     }
 
@@ -844,6 +988,7 @@ impl Cell {
         return Ok((None, Some(fy(cell, ref_index, parser)?)));
     }
 
+    /// Loads the rest of the cell, i.e. its remaining bits and references, as `Any`.
     pub fn load_any(
         cell: &Cell,
         ref_index: &mut usize,
@@ -853,11 +998,15 @@ impl Cell {
             .bit_reader
             .position_in_bits()
             .map_err(|err| TonCellError::cell_parser_error(err.to_string()))?;
-        Ok(AnyCell {
+        let any = AnyCell {
             cell: cell.clone(),
             ref_index: *ref_index,
             parser_positions_in_bits: position,
-        })
+        };
+        let remaining_bits = parser.remaining_bits();
+        parser.skip_bits(remaining_bits)?;
+        *ref_index = (*ref_index).max(cell.references.len());
+        Ok(any)
     }
 
     pub fn load_block_info(
@@ -867,7 +1016,7 @@ impl Cell {
     ) -> Result<BlockInfo, TonCellError> {
         let mut block_info = BlockInfo::default();
         if parser.load_u32(32)? != 0x9bc7a987 {
-            return Err(TonCellError::cell_parser_error("Not a BlockInfo"));
+            return Err(TonCellError::unexpected_structure("BlockInfo"));
         }
         let version = parser.load_u32(32)?;
         let not_master = parser.load_bit()?;
@@ -889,8 +1038,7 @@ impl Cell {
                 "data.vert_seqno_incr > data.vert_seq_no",
             ));
         }
-        let prev_seq_no = seq_no - 1;
-        parser.load_shard_ident()?;
+        block_info.shard = parser.load_shard_ident()?;
         block_info.gen_utime = parser.load_u32(32)?;
         let start_lt = parser.load_u64(64)?;
         let end_lt = parser.load_u64(64)?;
@@ -898,15 +1046,26 @@ impl Cell {
         let gen_catchain_seqno = parser.load_u32(32)?;
         let min_ref_mc_seqno = parser.load_u32(32)?;
         let prev_key_block_seqno = parser.load_u32(32)?;
-        debug!("prev key block seq no: {:?}", prev_key_block_seqno);
-        debug!("flag & 1: {:?}", flags & 1);
-        debug!("not master: {:?}", not_master);
+        parse_event!(
+            "loaded block info",
+            seq_no = seq_no,
+            prev_key_block_seqno = prev_key_block_seqno,
+            flags = flags,
+            not_master = not_master
+        );
 
+        block_info.seq_no = seq_no;
+        block_info.key_block = key_block;
+        block_info.start_lt = start_lt;
+        block_info.end_lt = end_lt;
+        block_info.prev_key_block_seqno = prev_key_block_seqno;
         if flags & 1 > 0 {
-            parser.load_global_version()?;
+            block_info.gen_software = Some(parser.load_global_version()?);
         }
         if not_master {
-            cell.load_ref_if_exist_without_self(ref_index, Some(Cell::load_blk_master_info))?;
+            block_info.master_ref = cell
+                .load_ref_if_exist_without_self(ref_index, Some(Cell::load_blk_master_info))?
+                .0;
         }
 
         let result = cell.load_ref_if_exist(
@@ -941,9 +1100,13 @@ impl Cell {
         let seqno = parser.load_u32(32)?;
         let root_hash = parser.load_bytes(32)?;
         let file_hash = parser.load_bytes(32)?;
-        debug!("end_lt and seq_no: {:?}, {:?}", end_lt, seqno);
-        debug!("root hash: {:?}", hex::encode(root_hash.clone()));
-        debug!("file hash: {:?}", file_hash);
+        parse_event!(
+            "loaded ext blk ref",
+            end_lt = end_lt,
+            seqno = seqno,
+            root_hash = hex::encode(&root_hash),
+            file_hash = hex::encode(&file_hash)
+        );
         Ok(ExtBlkRef {
             end_lt,
             seqno,
@@ -952,6 +1115,250 @@ impl Cell {
         })
     }
 
+    pub fn load_key_ext_blk_ref(parser: &mut CellParser) -> Result<KeyExtBlkRef, TonCellError> {
+        Ok(KeyExtBlkRef {
+            key: parser.load_bit()?,
+            blk_ref: Cell::load_ext_blk_ref(parser)?,
+        })
+    }
+
+    pub fn load_key_max_lt(parser: &mut CellParser) -> Result<KeyMaxLt, TonCellError> {
+        Ok(KeyMaxLt {
+            key: parser.load_bit()?,
+            max_end_lt: parser.load_u64(64)?,
+        })
+    }
+
+    /// Loads `OldMcBlocksInfo`, skipping pruned branches of the dictionary.
+    pub fn load_old_mc_blocks_info(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<OldMcBlocksInfo, TonCellError> {
+        let load_block = |_cell: &Cell,
+                          _ref_index: &mut usize,
+                          parser: &mut CellParser,
+                          _key: &BigUint|
+         -> Result<Option<KeyExtBlkRef>, TonCellError> {
+            Cell::load_key_max_lt(parser)?;
+            Ok(Some(Cell::load_key_ext_blk_ref(parser)?))
+        };
+        let mut hashmap = Hashmap::new(32, load_block);
+        // ahme_empty$0 extra:Y or ahme_root$1 root:^(HashmapAug n X Y) extra:Y
+        if parser.load_bit()? {
+            let root = cell.reference(*ref_index)?;
+            *ref_index += 1;
+            hashmap.load_hashmap(root, &mut 0, &mut root.parser(), 32, BigUint::zero(), false)?;
+        }
+        let extra = Cell::load_key_max_lt(parser)?;
+        let blocks = hashmap
+            .map
+            .into_iter()
+            .map(|(key, block)| {
+                let seqno =
+                    u32::from_str_radix(&key, 16).map_err(TonCellError::cell_parser_error)?;
+                Ok((seqno, block))
+            })
+            .collect::<Result<_, TonCellError>>()?;
+        parse_event!(
+            "loaded old mc blocks info",
+            pruned = hashmap.pruned.len(),
+            max_end_lt = extra.max_end_lt
+        );
+        Ok(OldMcBlocksInfo {
+            blocks,
+            extra,
+            pruned: !hashmap.pruned.is_empty(),
+        })
+    }
+
+    /// Loads the previous masterchain blocks of a masterchain state, e.g. of a state proof:
+    ///
+    /// ```raw
+    /// shard_state#9023afe2 global_id:int32 shard_id:ShardIdent seq_no:uint32
+    ///   vert_seq_no:# gen_utime:uint32 gen_lt:uint64 min_ref_mc_seqno:uint32
+    ///   out_msg_queue_info:^OutMsgQueueInfo before_split:(## 1) accounts:^ShardAccounts
+    ///   ^[ ... ] custom:(Maybe ^McStateExtra) = ShardStateUnsplit;
+    /// masterchain_state_extra#cc26 shard_hashes:ShardHashes config:ConfigParams
+    ///   ^[ flags:(## 16) { flags <= 1 } validator_info:ValidatorInfo
+    ///      prev_blocks:OldMcBlocksInfo ... ] global_balance:CurrencyCollection = McStateExtra;
+    /// ```
+    pub fn load_mc_state_prev_blocks(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<OldMcBlocksInfo, TonCellError> {
+        if parser.load_u32(32)? != SHARD_STATE_UNSPLIT_TAG {
+            return Err(TonCellError::unexpected_structure("ShardStateUnsplit"));
+        }
+        // global_id, shard_id, seq_no, vert_seq_no, gen_utime, gen_lt, min_ref_mc_seqno
+        parser.skip_bits(32 + 104 + 32 + 32 + 32 + 64 + 32)?;
+        parser.load_bit()?; // before_split
+                            // out_msg_queue_info, accounts, ^[ ... ]
+        *ref_index += 3;
+        if !parser.load_bit()? {
+            return Err(TonCellError::unexpected_structure("McStateExtra"));
+        }
+        let (prev_blocks, _) =
+            cell.load_ref_if_exist(ref_index, Some(Cell::load_mc_state_extra_prev_blocks))?;
+        prev_blocks.ok_or_else(|| TonCellError::unexpected_structure("McStateExtra"))
+    }
+
+    fn load_mc_state_extra_prev_blocks(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<OldMcBlocksInfo, TonCellError> {
+        if parser.load_u16(16)? != 0xcc26 {
+            return Err(TonCellError::unexpected_structure("McStateExtra"));
+        }
+        // shard_hashes
+        if parser.load_bit()? {
+            *ref_index += 1;
+        }
+        // config
+        parser.skip_bits(256)?;
+        *ref_index += 1;
+        let (prev_blocks, _) = cell.load_ref_if_exist(
+            ref_index,
+            Some(
+                |cell: &Cell, ref_index: &mut usize, parser: &mut CellParser| {
+                    // flags, validator_info
+                    parser.skip_bits(16 + 32 + 32 + 1)?;
+                    Cell::load_old_mc_blocks_info(cell, ref_index, parser)
+                },
+            ),
+        )?;
+        prev_blocks.ok_or_else(|| TonCellError::unexpected_structure("OldMcBlocksInfo"))
+    }
+
+    /// Loads the out message queue of a shard state:
+    ///
+    /// ```raw
+    /// shard_state#9023afe2 global_id:int32 shard_id:ShardIdent seq_no:uint32
+    ///   vert_seq_no:# gen_utime:uint32 gen_lt:uint64 min_ref_mc_seqno:uint32
+    ///   out_msg_queue_info:^OutMsgQueueInfo ... = ShardStateUnsplit;
+    /// ```
+    pub fn load_shard_state_out_msg_queue(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<OutMsgQueueInfo, TonCellError> {
+        if parser.load_u32(32)? != SHARD_STATE_UNSPLIT_TAG {
+            return Err(TonCellError::unexpected_structure("ShardStateUnsplit"));
+        }
+        let (queue, _) = cell.load_ref_if_exist(ref_index, Some(Cell::load_out_msg_queue_info))?;
+        queue.ok_or_else(|| TonCellError::unexpected_structure("OutMsgQueueInfo"))
+    }
+
+    pub fn load_out_msg_queue_info(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<OutMsgQueueInfo, TonCellError> {
+        let mut hashmap = Hashmap::new(
+            352,
+            |cell: &Cell, ref_index: &mut usize, parser: &mut CellParser, _key: &BigUint| {
+                parser.load_u64(64)?; // extra: uint64
+                Ok(Some(Cell::load_enqueued_msg(cell, ref_index, parser)?))
+            },
+        );
+        hashmap.deserialize_e(cell, ref_index, parser)?;
+        parse_event!(
+            "loaded out msg queue",
+            len = hashmap.map.len(),
+            pruned = hashmap.pruned.len()
+        );
+        Ok(OutMsgQueueInfo {
+            out_queue: hashmap.map,
+            pruned: !hashmap.pruned.is_empty(),
+        })
+    }
+
+    pub fn load_enqueued_msg(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<EnqueuedMsg, TonCellError> {
+        let enqueued_lt = parser.load_u64(64)?;
+        let (out_msg, _) = cell.load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?;
+        Ok(EnqueuedMsg {
+            enqueued_lt,
+            out_msg,
+        })
+    }
+
+    pub fn load_msg_envelope(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<MsgEnvelope, TonCellError> {
+        let v2 = match parser.load_u8(4)? {
+            4 => false,
+            5 => true,
+            _ => return Err(TonCellError::unexpected_structure("MsgEnvelope")),
+        };
+        let cur_addr = Cell::load_intermediate_address(parser)?;
+        let next_addr = Cell::load_intermediate_address(parser)?;
+        let fwd_fee_remaining = Cell::load_grams(parser)?;
+        let (msg, _) = cell.load_ref_if_exist(ref_index, Some(Cell::load_transaction_message))?;
+        let emitted_lt = if v2 && parser.load_bit()? {
+            Some(parser.load_u64(64)?)
+        } else {
+            None
+        };
+        let metadata = if v2 && parser.load_bit()? {
+            Some(Cell::load_msg_metadata(cell, ref_index, parser)?)
+        } else {
+            None
+        };
+        Ok(MsgEnvelope {
+            cur_addr,
+            next_addr,
+            fwd_fee_remaining,
+            msg,
+            emitted_lt,
+            metadata,
+        })
+    }
+
+    pub fn load_msg_metadata(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<MsgMetadata, TonCellError> {
+        if parser.load_u8(4)? != 0 {
+            return Err(TonCellError::unexpected_structure("MsgMetadata"));
+        }
+        let depth = parser.load_u32(32)?;
+        let initiator_addr = Cell::load_msg_address_internal(cell, ref_index, parser)?;
+        let initiator_lt = parser.load_u64(64)?;
+        Ok(MsgMetadata {
+            depth,
+            initiator_addr,
+            initiator_lt,
+        })
+    }
+
+    pub fn load_intermediate_address(
+        parser: &mut CellParser,
+    ) -> Result<IntermediateAddress, TonCellError> {
+        if !parser.load_bit()? {
+            return Ok(IntermediateAddress::Regular {
+                use_dest_bits: parser.load_u8(7)?,
+            });
+        }
+        let workchain = if parser.load_bit()? {
+            parser.load_i32(32)?
+        } else {
+            parser.load_i8(8)? as i32
+        };
+        Ok(IntermediateAddress::Simple {
+            workchain,
+            addr_pfx: parser.load_u64(64)?,
+        })
+    }
+
     pub fn load_blk_prev_info(
         cell: &Cell,
         ref_index: &mut usize,
@@ -978,130 +1385,447 @@ impl Cell {
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
-    ) -> Result<(), TonCellError> {
-        let magic = parser.load_u32(32)?;
-        if magic != 0xb8e48dfb {
-            // return Err(TonCellError::cell_parser_error("not a ValueFlow"));
-            return Ok(());
-        }
-        Ok(())
+    ) -> Result<ValueFlow, TonCellError> {
+        let v2 = match parser.load_u32(32)? {
+            0xb8e48dfb => false,
+            0x3ebf98b7 => true,
+            _ => return Err(TonCellError::unexpected_structure("ValueFlow")),
+        };
+        let [from_prev_blk, to_next_blk, imported, exported] =
+            Cell::load_value_flow_currencies(cell, ref_index)?;
+        let fees_collected = Cell::load_currency_collection(cell, ref_index, parser)?;
+        let burned = if v2 {
+            Some(Cell::load_currency_collection(cell, ref_index, parser)?)
+        } else {
+            None
+        };
+        let [fees_imported, recovered, created, minted] =
+            Cell::load_value_flow_currencies(cell, ref_index)?;
+        Ok(ValueFlow {
+            from_prev_blk,
+            to_next_blk,
+            imported,
+            exported,
+            fees_collected,
+            burned,
+            fees_imported,
+            recovered,
+            created,
+            minted,
+        })
+    }
+
+    fn load_value_flow_currencies(
+        cell: &Cell,
+        ref_index: &mut usize,
+    ) -> Result<[CurrencyCollection; 4], TonCellError> {
+        let (currencies, _) = cell.load_ref_if_exist(
+            ref_index,
+            Some(
+                |cell: &Cell, ref_index: &mut usize, parser: &mut CellParser| {
+                    Ok([
+                        Cell::load_currency_collection(cell, ref_index, parser)?,
+                        Cell::load_currency_collection(cell, ref_index, parser)?,
+                        Cell::load_currency_collection(cell, ref_index, parser)?,
+                        Cell::load_currency_collection(cell, ref_index, parser)?,
+                    ])
+                },
+            ),
+        )?;
+        currencies.ok_or_else(|| TonCellError::unexpected_structure("ValueFlow"))
     }
 
     pub fn load_merkle_update(
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
-    ) -> Result<(), TonCellError> {
+    ) -> Result<MerkleUpdate, TonCellError> {
         if parser.load_u8(8)? != 0x04 {
-            return Err(TonCellError::cell_parser_error("not a Merkle Update"));
+            return Err(TonCellError::unexpected_structure("MerkleUpdate"));
         }
-        debug!("current ref index: {:?}", ref_index);
         let old_hash = parser.load_bytes(32)?;
         let new_hash = parser.load_bytes(32)?;
-        debug!("old hash: {:?}", old_hash);
-        debug!("new hash: {:?}", new_hash);
-        let old = cell.reference(*ref_index)?;
+        let old_depth = parser.load_u16(16)?;
+        let new_depth = parser.load_u16(16)?;
+        parse_event!(
+            "loaded merkle update",
+            old_hash = hex::encode(&old_hash),
+            new_hash = hex::encode(&new_hash)
+        );
+        let old = cell.reference(*ref_index)?.clone();
         *ref_index += 1;
-        let new = cell.reference(*ref_index)?;
+        let new = cell.reference(*ref_index)?.clone();
         *ref_index += 1;
-        Ok(())
+        Ok(MerkleUpdate {
+            old_hash,
+            new_hash,
+            old_depth,
+            new_depth,
+            old,
+            new,
+        })
     }
 
     pub fn load_block_extra(
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
+    ) -> Result<BlockExtra, TonCellError> {
+        let context = &ParseContext::default();
+        Cell::load_block_extra_with(cell, ref_index, parser, &BlockSelector::all(), context)
+    }
+
+    fn load_block_extra_with(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+        selector: &BlockSelector,
+        context: &ParseContext,
     ) -> Result<BlockExtra, TonCellError> {
         if parser.load_u32(32)? != 0x4a33f6fd {
-            return Err(TonCellError::cell_parser_error("not a BlockExtra"));
+            return Err(TonCellError::unexpected_structure("BlockExtra"));
         }
 
-        // debug!("Cell hash: {:?}", cell.());
-
-        let mut block_extra = BlockExtra::default();
-
-        cell.load_ref_if_exist_without_self(ref_index, Some(Cell::load_in_msg_descr))?;
-        cell.load_ref_if_exist_without_self(ref_index, Some(Cell::load_out_msg_descr))?;
-        block_extra.account_blocks = cell
-            .load_ref_if_exist(ref_index, Some(Cell::load_shard_account_blocks))?
-            .0;
-        let rand_seed = parser.load_bytes(32)?;
-        let created_by = parser.load_bytes(32)?;
-
-        let res = cell.load_maybe_ref(
+        let in_msg_descr = cell.load_selected_ref(
             ref_index,
-            parser,
-            Some(Cell::load_mc_block_extra),
-            None::<fn(&Cell, &mut usize, &mut CellParser) -> Result<McBlockExtra, TonCellError>>,
+            selector.msg_descrs,
+            "InMsgDescr",
+            context,
+            Cell::load_in_msg_descr,
+        )?;
+        let out_msg_descr = cell.load_selected_ref(
+            ref_index,
+            selector.msg_descrs,
+            "OutMsgDescr",
+            context,
+            Cell::load_out_msg_descr,
+        )?;
+        let account_blocks = cell.load_selected_ref(
+            ref_index,
+            selector.account_blocks,
+            "ShardAccountBlocks",
+            context,
+            |cell, ref_index, parser| {
+                Cell::load_shard_account_blocks_in(cell, ref_index, parser, context)
+            },
         )?;
+        let rand_seed = parser.load_bytes(32)?;
+        let created_by = parser.load_bytes(32)?;
 
-        if let Some(custom) = res.data {
-            block_extra.custom = custom;
-        }
-        Ok(block_extra)
-    }
+        let res = if selector.mc_block_extra {
+            cell.load_maybe_ref(
+                ref_index,
+                parser,
+                Some(Cell::load_mc_block_extra),
+                None::<fn(&Cell, &mut usize, &mut CellParser) -> Result<McBlockExtra, TonCellError>>,
+            )?
+        } else if selector.config {
+            cell.load_maybe_ref(
+                ref_index,
+                parser,
+                Some(Cell::load_mc_block_config),
+                None::<fn(&Cell, &mut usize, &mut CellParser) -> Result<McBlockExtra, TonCellError>>,
+            )?
+        } else {
+            MaybeRefData::default()
+        };
 
-    pub fn load_in_msg_descr(parser: &mut CellParser) -> Result<(), TonCellError> {
-        Ok(())
+        Ok(BlockExtra {
+            in_msg_descr,
+            out_msg_descr,
+            account_blocks,
+            custom: res.data.unwrap_or_default(),
+        })
     }
 
-    pub fn load_out_msg_descr(parser: &mut CellParser) -> Result<(), TonCellError> {
-        Ok(())
+    /// Loads the messages imported by a block by the hex of their hashes:
+    ///
+    /// ```raw
+    /// _ (HashmapAugE 256 InMsg ImportFees) = InMsgDescr;
+    /// ```
+    pub fn load_in_msg_descr(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<HashMap<String, HashmapAugEResult<InMsg, ImportFees>>, TonCellError> {
+        Cell::load_hash_map_aug_e(
+            cell,
+            ref_index,
+            parser,
+            256,
+            Cell::load_in_msg,
+            Cell::load_import_fees,
+        )
     }
 
-    pub fn load_shard_account_blocks(
+    /// Loads the messages exported by a block by the hex of their hashes:
+    ///
+    /// ```raw
+    /// _ (HashmapAugE 256 OutMsg CurrencyCollection) = OutMsgDescr;
+    /// ```
+    pub fn load_out_msg_descr(
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
-    ) -> Result<HashMap<String, AccountBlock>, TonCellError> {
-        let result = Cell::load_hash_map_aug_e(
+    ) -> Result<HashMap<String, HashmapAugEResult<OutMsg, CurrencyCollection>>, TonCellError> {
+        Cell::load_hash_map_aug_e(
             cell,
             ref_index,
             parser,
             256,
-            Cell::load_account_block,
+            Cell::load_out_msg,
             Cell::load_currency_collection,
-        )?;
-        Ok(result.into_iter().map(|(k, v)| (k, v.value)).collect())
+        )
     }
 
-    pub fn load_hash_map<T>(
+    pub fn load_import_fees(
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
-        n: usize,
-        f: fn(&Cell, &mut usize, &mut CellParser, &BigUint) -> Result<Option<T>, TonCellError>,
-    ) -> Result<HashMap<String, T>, TonCellError>
-    where
-        T: Debug,
-    {
-        let mut hashmap = Hashmap::new(n, f);
-        hashmap.deserialize(cell, ref_index, parser)?;
-        Ok(hashmap.map)
+    ) -> Result<ImportFees, TonCellError> {
+        Ok(ImportFees {
+            fees_collected: Cell::load_grams(parser)?,
+            value_imported: Cell::load_currency_collection(cell, ref_index, parser)?,
+        })
     }
 
-    pub fn load_hash_map_e<T>(
+    pub fn load_in_msg(
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
-        n: usize,
-        f: fn(&Cell, &mut usize, &mut CellParser, &BigUint) -> Result<Option<T>, TonCellError>,
-    ) -> Result<HashMap<String, T>, TonCellError>
-    where
-        T: Debug,
-    {
-        let mut hashmap = Hashmap::new(n, f);
-        hashmap.deserialize_e(cell, ref_index, parser)?;
-        Ok(hashmap.map)
+    ) -> Result<InMsg, TonCellError> {
+        let in_msg = match parser.load_u8(3)? {
+            0b000 => InMsg::ImportExt {
+                msg: cell
+                    .load_ref_if_exist(ref_index, Some(Cell::load_transaction_message))?
+                    .0,
+                transaction: Cell::load_ref_hash(cell, ref_index)?,
+            },
+            0b010 => InMsg::ImportIhr {
+                msg: cell
+                    .load_ref_if_exist(ref_index, Some(Cell::load_transaction_message))?
+                    .0,
+                transaction: Cell::load_ref_hash(cell, ref_index)?,
+                ihr_fee: Cell::load_grams(parser)?,
+                proof_created: Cell::load_ref_hash(cell, ref_index)?,
+            },
+            0b011 => InMsg::ImportImm {
+                in_msg: cell
+                    .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                    .0,
+                transaction: Cell::load_ref_hash(cell, ref_index)?,
+                fwd_fee: Cell::load_grams(parser)?,
+            },
+            0b100 => InMsg::ImportFin {
+                in_msg: cell
+                    .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                    .0,
+                transaction: Cell::load_ref_hash(cell, ref_index)?,
+                fwd_fee: Cell::load_grams(parser)?,
+            },
+            0b101 => InMsg::ImportTr {
+                in_msg: cell
+                    .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                    .0,
+                out_msg: cell
+                    .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                    .0,
+                transit_fee: Cell::load_grams(parser)?,
+            },
+            0b110 => InMsg::DiscardFin {
+                in_msg: cell
+                    .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                    .0,
+                transaction_id: parser.load_u64(64)?,
+                fwd_fee: Cell::load_grams(parser)?,
+            },
+            0b111 => InMsg::DiscardTr {
+                in_msg: cell
+                    .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                    .0,
+                transaction_id: parser.load_u64(64)?,
+                fwd_fee: Cell::load_grams(parser)?,
+                proof_delivered: Cell::load_ref_hash(cell, ref_index)?,
+            },
+            0b001 => match parser.load_u8(2)? {
+                0b00 => InMsg::ImportDeferredFin {
+                    in_msg: cell
+                        .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                        .0,
+                    transaction: Cell::load_ref_hash(cell, ref_index)?,
+                    fwd_fee: Cell::load_grams(parser)?,
+                },
+                0b01 => InMsg::ImportDeferredTr {
+                    in_msg: cell
+                        .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                        .0,
+                    out_msg: cell
+                        .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                        .0,
+                },
+                _ => return Err(TonCellError::unexpected_structure("InMsg")),
+            },
+            _ => unreachable!(),
+        };
+        Ok(in_msg)
     }
 
-    pub fn load_hash_map_aug_e<F1, F2, T1, T2>(
+    pub fn load_out_msg(
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
-        n: usize,
-        f1: F1,
-        f2: F2,
+    ) -> Result<OutMsg, TonCellError> {
+        let load_in_msg = |cell: &Cell, ref_index: &mut usize| {
+            let (in_msg, _) = cell.load_ref_if_exist(ref_index, Some(Cell::load_in_msg))?;
+            Ok::<_, TonCellError>(in_msg.map(Box::new))
+        };
+        let out_msg = match parser.load_u8(3)? {
+            0b000 => OutMsg::ExportExt {
+                msg: cell
+                    .load_ref_if_exist(ref_index, Some(Cell::load_transaction_message))?
+                    .0,
+                transaction: Cell::load_ref_hash(cell, ref_index)?,
+            },
+            0b010 => OutMsg::ExportImm {
+                out_msg: cell
+                    .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                    .0,
+                transaction: Cell::load_ref_hash(cell, ref_index)?,
+                reimport: load_in_msg(cell, ref_index)?,
+            },
+            0b001 => OutMsg::ExportNew {
+                out_msg: cell
+                    .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                    .0,
+                transaction: Cell::load_ref_hash(cell, ref_index)?,
+            },
+            0b011 => OutMsg::ExportTr {
+                out_msg: cell
+                    .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                    .0,
+                imported: load_in_msg(cell, ref_index)?,
+            },
+            0b110 => {
+                if parser.load_bit()? {
+                    OutMsg::ExportDeqShort {
+                        msg_env_hash: parser.load_bytes(32)?,
+                        next_workchain: parser.load_i32(32)?,
+                        next_addr_pfx: parser.load_u64(64)?,
+                        import_block_lt: parser.load_u64(64)?,
+                    }
+                } else {
+                    OutMsg::ExportDeq {
+                        out_msg: cell
+                            .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                            .0,
+                        import_block_lt: parser.load_u64(63)?,
+                    }
+                }
+            }
+            0b111 => OutMsg::ExportTrReq {
+                out_msg: cell
+                    .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                    .0,
+                imported: load_in_msg(cell, ref_index)?,
+            },
+            0b100 => OutMsg::ExportDeqImm {
+                out_msg: cell
+                    .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                    .0,
+                reimport: load_in_msg(cell, ref_index)?,
+            },
+            0b101 => match parser.load_u8(2)? {
+                0b00 => OutMsg::ExportNewDefer {
+                    out_msg: cell
+                        .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                        .0,
+                    transaction: Cell::load_ref_hash(cell, ref_index)?,
+                },
+                0b01 => OutMsg::ExportDeferredTr {
+                    out_msg: cell
+                        .load_ref_if_exist(ref_index, Some(Cell::load_msg_envelope))?
+                        .0,
+                    imported: load_in_msg(cell, ref_index)?,
+                },
+                _ => return Err(TonCellError::unexpected_structure("OutMsg")),
+            },
+            _ => unreachable!(),
+        };
+        Ok(out_msg)
+    }
+
+    /// Skips the reference, returning the hash of the referenced cell, which is also known if
+    /// the cell is pruned.
+    fn load_ref_hash(cell: &Cell, ref_index: &mut usize) -> Result<Vec<u8>, TonCellError> {
+        let reference = cell.reference(*ref_index)?;
+        *ref_index += 1;
+        Ok(reference.get_hash(0))
+    }
+
+    pub fn load_shard_account_blocks(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<HashMap<String, AccountBlock>, TonCellError> {
+        Cell::load_shard_account_blocks_in(cell, ref_index, parser, &ParseContext::default())
+    }
+
+    fn load_shard_account_blocks_in(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+        context: &ParseContext,
+    ) -> Result<HashMap<String, AccountBlock>, TonCellError> {
+        let result = Cell::load_hash_map_aug_e(
+            cell,
+            ref_index,
+            parser,
+            256,
+            |cell: &Cell, ref_index: &mut usize, parser: &mut CellParser| {
+                Cell::load_account_block_in(cell, ref_index, parser, context)
+            },
+            Cell::load_currency_collection,
+        )?;
+        Ok(result.into_iter().map(|(k, v)| (k, v.value)).collect())
+    }
+
+    pub fn load_hash_map<T>(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+        n: usize,
+        f: fn(&Cell, &mut usize, &mut CellParser, &BigUint) -> Result<Option<T>, TonCellError>,
+    ) -> Result<HashMap<String, T>, TonCellError>
+    where
+        T: Debug,
+    {
+        let mut hashmap = Hashmap::new(n, f);
+        hashmap.deserialize(cell, ref_index, parser)?;
+        Ok(hashmap.map)
+    }
+
+    pub fn load_hash_map_e<T>(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+        n: usize,
+        f: fn(&Cell, &mut usize, &mut CellParser, &BigUint) -> Result<Option<T>, TonCellError>,
+    ) -> Result<HashMap<String, T>, TonCellError>
+    where
+        T: Debug,
+    {
+        let mut hashmap = Hashmap::new(n, f);
+        hashmap.deserialize_e(cell, ref_index, parser)?;
+        Ok(hashmap.map)
+    }
+
+    pub fn load_hash_map_aug_e<F1, F2, T1, T2>(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+        n: usize,
+        f1: F1,
+        f2: F2,
     ) -> Result<HashMap<String, HashmapAugEResult<T1, T2>>, TonCellError>
     where
         F1: FnOnce(&Cell, &mut usize, &mut CellParser) -> Result<T1, TonCellError> + Copy,
@@ -1120,7 +1844,9 @@ impl Cell {
         };
         let mut hashmap = Hashmap::new(n, hash_map_fn);
         hashmap.deserialize_e(cell, ref_index, parser)?;
-        debug!("data map: {:?}", hashmap.map);
+        // extra of the root, the aggregate of the extras of the values
+        f2(cell, ref_index, parser)?;
+        parse_event!("loaded hashmap aug e", len = hashmap.map.len());
         Ok(hashmap.map)
     }
 
@@ -1153,52 +1879,167 @@ impl Cell {
         Ok(hashmap.map)
     }
 
+    /// Loads a value of the `ShardAccounts` dictionary of a shard state:
+    ///
+    /// ```raw
+    /// account_descr$_ account:^Account last_trans_hash:bits256 last_trans_lt:uint64 = ShardAccount;
+    /// ```
+    ///
+    /// The account is `None` if it is pruned as well.
     pub fn load_shard_account(
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
-    ) -> Result<(), TonCellError> {
-        Cell::load_account(cell, ref_index, parser)?;
+    ) -> Result<ShardAccount, TonCellError> {
+        let (account, _) = cell.load_ref_if_exist(ref_index, Some(Cell::load_account))?;
         let last_trans_hash = parser.load_bytes(32)?;
         let last_trans_lt = parser.load_u64(64)?;
-        debug!("last trans hash: {:?}", last_trans_hash);
-        debug!("last trans lt: {:?}", last_trans_lt);
-        Ok(())
+        parse_event!(
+            "loaded shard account",
+            last_trans_hash = hex::encode(&last_trans_hash),
+            last_trans_lt = last_trans_lt
+        );
+        Ok(ShardAccount {
+            account: account.flatten(),
+            last_trans_hash,
+            last_trans_lt,
+        })
+    }
+
+    /// ```raw
+    /// account_none$0 = Account;
+    /// account$1 addr:MsgAddressInt storage_stat:StorageInfo storage:AccountStorage = Account;
+    /// account_storage$_ last_trans_lt:uint64 balance:CurrencyCollection state:AccountState
+    ///   = AccountStorage;
+    /// ```
+    pub fn load_account(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<Option<Account>, TonCellError> {
+        if !parser.load_bit()? {
+            return Ok(None);
+        }
+        let address = parser.load_address()?;
+        let storage_stat = Cell::load_storage_info(parser)?;
+        let last_trans_lt = parser.load_u64(64)?;
+        let balance = Cell::load_currency_collection(cell, ref_index, parser)?;
+        let state = if parser.load_bit()? {
+            let state_init = Cell::load_state_init(cell, ref_index, parser)?;
+            AccountStorageState::Active {
+                code: state_init.code.map(|code| code.as_ref().clone()),
+                data: state_init.data.map(|data| data.as_ref().clone()),
+            }
+        } else if parser.load_bit()? {
+            AccountStorageState::Frozen {
+                state_hash: parser.load_bytes(32)?,
+            }
+        } else {
+            AccountStorageState::Uninit
+        };
+        Ok(Some(Account {
+            address,
+            storage_stat,
+            last_trans_lt,
+            balance,
+            state,
+        }))
+    }
+
+    pub fn load_storage_info(parser: &mut CellParser) -> Result<StorageInfo, TonCellError> {
+        let used = Cell::load_storage_used_short(parser)?;
+        let dict_hash = match parser.load_u8(3)? {
+            0 => None,
+            1 => {
+                let mut dict_hash = [0; 32];
+                parser.load_slice(&mut dict_hash)?;
+                Some(dict_hash)
+            }
+            _ => return Err(TonCellError::unexpected_structure("StorageExtraInfo")),
+        };
+        Ok(StorageInfo {
+            used,
+            dict_hash,
+            last_paid: parser.load_u32(32)?,
+            due_payment: Cell::load_maybe_grams(parser)?,
+        })
     }
 
     pub fn load_account_block(
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
+    ) -> Result<AccountBlock, TonCellError> {
+        Cell::load_account_block_in(cell, ref_index, parser, &ParseContext::default())
+    }
+
+    /// Loads the account block. In the lenient mode, the transactions that fail to load are
+    /// left with their cells only, as pruned transactions are.
+    fn load_account_block_in(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+        context: &ParseContext,
     ) -> Result<AccountBlock, TonCellError> {
         let magic = parser.load_uint(4)?;
         if magic != BigUint::from_u8(0x5).unwrap() {
-            return Err(TonCellError::cell_parser_error("not an AccountBlock"));
+            return Err(TonCellError::unexpected_structure("AccountBlock"));
         }
         let account_addr = parser.load_bytes(32)?;
-        debug!("account addr load account block: {:?}", account_addr);
+        parse_event!(
+            "loading account block",
+            account_addr = hex::encode(&account_addr)
+        );
         let transactions = Cell::load_hash_map_aug(
             cell,
             ref_index,
             parser,
             64,
             |ref_cell: &Cell, inner_ref_index: &mut usize, _parser: &mut CellParser| {
-                let result =
-                    ref_cell.load_ref_if_exist(inner_ref_index, Some(Cell::load_transaction))?;
+                let tx_cell = ref_cell.reference(*inner_ref_index)?;
+                let result = ref_cell.load_ref_in(
+                    inner_ref_index,
+                    "Transaction",
+                    context,
+                    |cell, ref_index, parser| {
+                        Cell::load_transaction_in(cell, ref_index, parser, context)
+                    },
+                );
+                let Some((data, cell)) = context.optional("Transaction", result.map(Some))? else {
+                    return Ok(MaybeRefData {
+                        data: None,
+                        cell: Some(tx_cell.as_ref().clone()),
+                        verified: false,
+                    });
+                };
+                let verified = data
+                    .as_ref()
+                    .is_some_and(|tx| Cell::verify_transaction(tx_cell, tx));
                 Ok(MaybeRefData {
-                    data: result.0,
-                    cell: result.1.map(|v| v.clone()),
+                    data,
+                    cell: cell.cloned(),
+                    verified,
                 })
             },
             Cell::load_currency_collection,
         )?;
-        cell.load_ref_if_exist(ref_index, Some(Cell::load_hash_update))?;
+        let state_update = cell
+            .load_ref_in(ref_index, "HashUpdate", context, Cell::load_hash_update)?
+            .0;
 
         let mut account_block = AccountBlock::default();
         account_block.account_addr = account_addr;
+        account_block.state_update = state_update;
         account_block.transactions = transactions
             .into_iter()
-            .map(|(k, v)| (k, v.value))
+            .map(|(k, mut v)| {
+                // The dictionary is keyed by the lt of the transactions of the account
+                if let Some(tx) = v.value.data.as_ref() {
+                    v.value.verified &= u64::from_str_radix(&k, 16) == Ok(tx.lt)
+                        && tx.account_addr == account_block.account_addr;
+                }
+                (k, v.value)
+            })
             .collect();
         Ok(account_block)
     }
@@ -1207,25 +2048,13 @@ impl Cell {
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
-    ) -> Result<(), TonCellError> {
+    ) -> Result<DepthBalanceInfo, TonCellError> {
         let split_depth = parser.load_uint_le(30)?;
-        debug!("split depth: {:?}", split_depth);
-        Cell::load_currency_collection(cell, ref_index, parser)?;
-        Ok(())
-    }
-
-    pub fn load_account(
-        cell: &Cell,
-        ref_index: &mut usize,
-        parser: &mut CellParser,
-    ) -> Result<(), TonCellError> {
-        if parser.load_u32(32)? != 0x4a33f6fd {
-            return Err(TonCellError::cell_parser_error("not a BlockExtra"));
-        }
-        cell.load_ref_if_exist_without_self(ref_index, Some(Cell::load_in_msg_descr))?;
-        cell.load_ref_if_exist_without_self(ref_index, Some(Cell::load_out_msg_descr))?;
-        cell.load_ref_if_exist(ref_index, Some(Cell::load_shard_account_blocks))?;
-        Ok(())
+        let balance = Cell::load_currency_collection(cell, ref_index, parser)?;
+        Ok(DepthBalanceInfo {
+            split_depth: u32::try_from(split_depth).map_err(TonCellError::cell_parser_error)?,
+            balance,
+        })
     }
 
     pub fn load_currency_collection(
@@ -1247,7 +2076,7 @@ impl Cell {
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
-    ) -> Result<HashMap<String, VarUInteger>, TonCellError> {
+    ) -> Result<HashMap<u32, VarUInteger>, TonCellError> {
         let result = Cell::load_hash_map_e(
             cell,
             ref_index,
@@ -1255,20 +2084,49 @@ impl Cell {
             32,
             |cell: &Cell, ref_index: &mut usize, parser: &mut CellParser, _key: &BigUint| {
                 let result = parser.load_var_uinteger(32)?;
-                debug!("load extra currency collection: {:?}", result);
                 Ok(Some(result))
             },
         )?;
-        Ok(result)
+        result
+            .into_iter()
+            .map(|(key, value)| {
+                let id = u32::from_str_radix(&key, 16).map_err(TonCellError::cell_parser_error)?;
+                Ok((id, value))
+            })
+            .collect()
     }
 
     pub fn load_transaction(
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
+    ) -> Result<Transaction, TonCellError> {
+        Cell::load_transaction_in(cell, ref_index, parser, &ParseContext::default())
+    }
+
+    /// Loads the transaction of the root cell with the mode, checking that the whole cell is
+    /// loaded. In the lenient mode, the messages, the state update and the description are
+    /// skipped if they fail to load.
+    pub fn load_transaction_with_mode(
+        &self,
+        mode: ParseMode,
+    ) -> Result<Parsed<Transaction>, TonCellError> {
+        let context = ParseContext::new(mode);
+        let ref_index = &mut 0;
+        let parser = &mut self.parser();
+        let transaction = Cell::load_transaction_in(self, ref_index, parser, &context)?;
+        context.check_trailing_data("Transaction", self, *ref_index, parser)?;
+        Ok(context.finish(transaction))
+    }
+
+    fn load_transaction_in(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+        context: &ParseContext,
     ) -> Result<Transaction, TonCellError> {
         if parser.load_uint(4)? != BigUint::from_u8(7).unwrap() {
-            return Err(TonCellError::cell_parser_error("Not a transaction"));
+            return Err(TonCellError::unexpected_structure("Transaction"));
         }
         let mut transaction = Transaction::default();
         transaction.hash = cell.get_hash(0);
@@ -1285,61 +2143,109 @@ impl Cell {
         *ref_index += 1;
 
         if ref_cell.cell_type == CellType::OrdinaryCell as u8 {
-            let inner_ref_index = &mut 0;
-            let inner_parser = &mut ref_cell.parser();
-            transaction.in_msg = ref_cell.load_maybe_ref(
-                inner_ref_index,
-                inner_parser,
-                Some(Cell::load_transaction_message),
-                None::<
-                    fn(
-                        &Cell,
-                        &mut usize,
-                        &mut CellParser,
-                    ) -> Result<TransactionMessage, TonCellError>,
-                >,
-            )?;
-            transaction.out_msgs = Cell::load_hash_map_e(
-                &ref_cell,
-                inner_ref_index,
-                inner_parser,
-                15,
-                |inner_ref_cell: &Cell,
-                 inner_inner_ref_index: &mut usize,
-                 _inner_inner_ref_parser,
-                 _n| {
-                    inner_ref_cell
-                        .load_ref_if_exist(
-                            inner_inner_ref_index,
-                            Some(Cell::load_transaction_message),
-                        )
-                        .map(|res| {
-                            Some(MaybeRefData {
-                                data: res.0,
-                                cell: res.1.map(|cell| cell.clone()),
-                            })
-                        })
-                },
-            )?;
+            let messages = Cell::load_transaction_messages(ref_cell, context);
+            if let Some((in_msg, out_msgs)) = context.optional("Message", messages.map(Some))? {
+                transaction.in_msg = in_msg;
+                transaction.out_msgs = out_msgs;
+            }
         }
-        Cell::load_currency_collection(cell, ref_index, parser)?;
-        cell.load_ref_if_exist(ref_index, Some(Cell::load_hash_update))?;
-        cell.load_ref_if_exist(ref_index, Some(Cell::load_transaction_descr))?;
+        transaction.total_fees = Cell::load_currency_collection(cell, ref_index, parser)?;
+        let state_update =
+            cell.load_ref_in(ref_index, "HashUpdate", context, Cell::load_hash_update);
+        transaction.state_update = context.optional("HashUpdate", state_update.map(|res| res.0))?;
+        let description = cell.load_ref_in(
+            ref_index,
+            "TransactionDescr",
+            context,
+            |cell, ref_index, parser| {
+                Cell::load_transaction_descr_in(cell, ref_index, parser, context)
+            },
+        );
+        transaction.description =
+            context.optional("TransactionDescr", description.map(|res| res.0))?;
         Ok(transaction)
     }
 
+    /// Loads `in_msg` and `out_msgs` of the reference of a transaction.
+    #[allow(clippy::type_complexity)]
+    fn load_transaction_messages(
+        ref_cell: &Cell,
+        context: &ParseContext,
+    ) -> Result<
+        (
+            MaybeRefData<TransactionMessage>,
+            HashMap<String, MaybeRefData<TransactionMessage>>,
+        ),
+        TonCellError,
+    > {
+        let load_message = |cell: &Cell, ref_index: &mut usize, parser: &mut CellParser| {
+            let message = Cell::load_transaction_message_in(cell, ref_index, parser, context)?;
+            context.check_ref_trailing_data("Message", cell, *ref_index, parser)?;
+            Ok(message)
+        };
+        let inner_ref_index = &mut 0;
+        let inner_parser = &mut ref_cell.parser();
+        let in_msg = ref_cell.load_maybe_ref(
+            inner_ref_index,
+            inner_parser,
+            Some(load_message),
+            None::<
+                fn(&Cell, &mut usize, &mut CellParser) -> Result<TransactionMessage, TonCellError>,
+            >,
+        )?;
+        // Loaded by the dictionary directly, as `load_hash_map_e` takes a function pointer
+        let mut out_msgs = Hashmap::new(
+            15,
+            |inner_ref_cell: &Cell,
+             inner_inner_ref_index: &mut usize,
+             _inner_inner_ref_parser: &mut CellParser,
+             _n: &BigUint| {
+                inner_ref_cell
+                    .load_ref_if_exist(inner_inner_ref_index, Some(load_message))
+                    .map(|res| {
+                        Some(MaybeRefData {
+                            data: res.0,
+                            cell: res.1.cloned(),
+                            verified: false,
+                        })
+                    })
+            },
+        );
+        out_msgs.deserialize_e(ref_cell, inner_ref_index, inner_parser)?;
+        Ok((in_msg, out_msgs.map))
+    }
+
+    /// Returns whether the transaction re-serializes to the transaction cell, i.e. whether the
+    /// cells rebuilt from the fields of the transaction have the hash of the transaction, so
+    /// that fields not loaded by [`Cell::load_transaction`] are detected.
+    ///
+    /// Pruned branches and the messages, which are identified by their hashes, are taken from
+    /// `cell`. Returns `false` if `cell` has no calculated hashes, e.g. if it was built by
+    /// [`CellBuilder`] rather than deserialized.
+    pub fn verify_transaction(cell: &Cell, transaction: &Transaction) -> bool {
+        if cell.hashes.is_empty() {
+            return false;
+        }
+        match transaction_rebuild::rebuild_transaction(cell, transaction)
+            .and_then(transaction_rebuild::finalized)
+        {
+            Ok(rebuilt) => rebuilt.hash_ref(0)[..] == transaction.hash[..],
+            Err(_) => false,
+        }
+    }
+
     pub fn load_account_status(parser: &mut CellParser) -> Result<String, TonCellError> {
-        let status = parser.load_uint(2)?.to_u8().unwrap_or_default();
-        if status == 0 {
-            return Ok("uninit".to_string());
-        } else if status == 1 {
-            return Ok("frozen".to_string());
-        } else if status == 2 {
-            return Ok("active".to_string());
-        } else if status == 3 {
-            return Ok("nonexist".to_string());
-        } else {
-            Err(TonCellError::cell_parser_error("Wrong account status"))
+        Ok(Cell::load_account_status_type(parser)?.as_str().to_string())
+    }
+
+    pub fn load_account_status_type(
+        parser: &mut CellParser,
+    ) -> Result<AccountStatus, TonCellError> {
+        match parser.load_u8(2)? {
+            0 => Ok(AccountStatus::Uninit),
+            1 => Ok(AccountStatus::Frozen),
+            2 => Ok(AccountStatus::Active),
+            _ => Ok(AccountStatus::NonExist),
         }
     }
 
@@ -1347,10 +2253,19 @@ impl Cell {
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
+    ) -> Result<TransactionMessage, TonCellError> {
+        Cell::load_transaction_message_in(cell, ref_index, parser, &ParseContext::default())
+    }
+
+    fn load_transaction_message_in(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+        context: &ParseContext,
     ) -> Result<TransactionMessage, TonCellError> {
         let mut tx_message = TransactionMessage::default();
         tx_message.hash = cell.get_hash(0);
-        tx_message.info = Cell::load_common_msg_info(cell, ref_index, parser)?;
+        tx_message.info = Cell::load_common_msg_info_in(cell, ref_index, parser, context)?;
 
         // init
         cell.load_maybe(
@@ -1381,7 +2296,7 @@ impl Cell {
                                     )
                                 }),
                             )?;
-                            Ok((result.0, result.1.map(|cell| cell.clone())))
+                            Ok((result.0, result.1.cloned()))
                         },
                     )
                 },
@@ -1407,7 +2322,7 @@ impl Cell {
                         },
                     ),
                 )?;
-                Ok((result.0, result.1.map(|cell| cell.clone())))
+                Ok((result.0, result.1.cloned()))
             },
         )?;
         tx_message.body = TransactionBody {
@@ -1421,6 +2336,15 @@ impl Cell {
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
+    ) -> Result<CommonTransactionMessageInfo, TonCellError> {
+        Cell::load_common_msg_info_in(cell, ref_index, parser, &ParseContext::default())
+    }
+
+    fn load_common_msg_info_in(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+        context: &ParseContext,
     ) -> Result<CommonTransactionMessageInfo, TonCellError> {
         let mut data = CommonTransactionMessageInfo::default();
         let mut b = parser.load_bit()?;
@@ -1429,8 +2353,8 @@ impl Cell {
             data.ihr_disabled = parser.load_bit()?;
             data.bounce = parser.load_bit()?;
             data.bounced = parser.load_bit()?;
-            data.src = Cell::load_msg_address_internal(cell, ref_index, parser)?;
-            data.dest = Cell::load_msg_address_internal(cell, ref_index, parser)?;
+            data.src = Cell::load_msg_address_internal_in(cell, ref_index, parser, context)?;
+            data.dest = Cell::load_msg_address_internal_in(cell, ref_index, parser, context)?;
             data.value = Cell::load_currency_collection(cell, ref_index, parser)?;
             data.ihr_fee = Cell::load_grams(parser)?;
             data.fwd_fee = Cell::load_grams(parser)?;
@@ -1440,13 +2364,13 @@ impl Cell {
             b = parser.load_bit()?;
             if !b {
                 data.msg_type = MessageType::ExternalIn as u8;
-                data.src = Cell::load_msg_address_external(cell, ref_index, parser)?;
-                data.dest = Cell::load_msg_address_internal(cell, ref_index, parser)?;
+                data.src = Cell::load_msg_address_external_in(cell, ref_index, parser, context)?;
+                data.dest = Cell::load_msg_address_internal_in(cell, ref_index, parser, context)?;
                 data.import_fee = Cell::load_grams(parser)?;
             } else {
                 data.msg_type = MessageType::ExternalOut as u8;
-                data.src = Cell::load_msg_address_internal(cell, ref_index, parser)?;
-                data.dest = Cell::load_msg_address_external(cell, ref_index, parser)?;
+                data.src = Cell::load_msg_address_internal_in(cell, ref_index, parser, context)?;
+                data.dest = Cell::load_msg_address_external_in(cell, ref_index, parser, context)?;
                 data.created_lt = parser.load_u64(64)?;
                 data.created_at = parser.load_u32(32)?;
             }
@@ -1458,15 +2382,353 @@ impl Cell {
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
-    ) -> Result<(), TonCellError> {
-        // no need to impl this for now because it's in a different reference
-        Ok(())
+    ) -> Result<TransactionDescr, TonCellError> {
+        Cell::load_transaction_descr_in(cell, ref_index, parser, &ParseContext::default())
+    }
+
+    fn load_transaction_descr_in(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+        context: &ParseContext,
+    ) -> Result<TransactionDescr, TonCellError> {
+        let tag = parser.load_u8(4)?;
+        let descr = match tag {
+            0b0000 => TransactionDescr::Ordinary(Cell::load_transaction_descr_ordinary(
+                cell, ref_index, parser,
+            )?),
+            0b0001 => TransactionDescr::Storage(Cell::load_tr_storage_phase(parser)?),
+            // trans_tick_tock$001 has a 3-bit tag, the 4th bit is is_tock
+            0b0010 | 0b0011 => TransactionDescr::TickTock(TransactionDescrTickTock {
+                is_tock: tag & 1 == 1,
+                storage_ph: Cell::load_tr_storage_phase(parser)?,
+                compute_ph: Cell::load_tr_compute_phase(cell, ref_index, parser)?,
+                action: Cell::load_maybe_tr_action_phase_ref(cell, ref_index, parser)?,
+                aborted: parser.load_bit()?,
+                destroyed: parser.load_bit()?,
+            }),
+            0b0100 => TransactionDescr::SplitPrepare(TransactionDescrSplitPrepare {
+                split_info: Cell::load_split_merge_info(parser)?,
+                storage_ph: Cell::load_maybe_tr_storage_phase(cell, ref_index, parser)?,
+                compute_ph: Cell::load_tr_compute_phase(cell, ref_index, parser)?,
+                action: Cell::load_maybe_tr_action_phase_ref(cell, ref_index, parser)?,
+                aborted: parser.load_bit()?,
+                destroyed: parser.load_bit()?,
+            }),
+            0b0101 => TransactionDescr::SplitInstall(TransactionDescrSplitInstall {
+                split_info: Cell::load_split_merge_info(parser)?,
+                prepare_transaction: Box::new(Cell::load_prepare_transaction_ref(
+                    cell, ref_index, context,
+                )?),
+                installed: parser.load_bit()?,
+            }),
+            0b0110 => TransactionDescr::MergePrepare(TransactionDescrMergePrepare {
+                split_info: Cell::load_split_merge_info(parser)?,
+                storage_ph: Cell::load_tr_storage_phase(parser)?,
+                aborted: parser.load_bit()?,
+            }),
+            0b0111 => TransactionDescr::MergeInstall(TransactionDescrMergeInstall {
+                split_info: Cell::load_split_merge_info(parser)?,
+                prepare_transaction: Box::new(Cell::load_prepare_transaction_ref(
+                    cell, ref_index, context,
+                )?),
+                storage_ph: Cell::load_maybe_tr_storage_phase(cell, ref_index, parser)?,
+                credit_ph: Cell::load_maybe_tr_credit_phase(cell, ref_index, parser)?,
+                compute_ph: Cell::load_tr_compute_phase(cell, ref_index, parser)?,
+                action: Cell::load_maybe_tr_action_phase_ref(cell, ref_index, parser)?,
+                aborted: parser.load_bit()?,
+                destroyed: parser.load_bit()?,
+            }),
+            _ => {
+                return Err(TonCellError::cell_parser_error(format!(
+                    "unsupported transaction description tag: {:#06b}",
+                    tag
+                )))
+            }
+        };
+        Ok(descr)
+    }
+
+    pub fn load_transaction_descr_ordinary(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<TransactionDescrOrdinary, TonCellError> {
+        let credit_first = parser.load_bit()?;
+        let storage_ph = Cell::load_maybe_tr_storage_phase(cell, ref_index, parser)?;
+        let credit_ph = Cell::load_maybe_tr_credit_phase(cell, ref_index, parser)?;
+        let compute_ph = Cell::load_tr_compute_phase(cell, ref_index, parser)?;
+        let action = Cell::load_maybe_tr_action_phase_ref(cell, ref_index, parser)?;
+        let aborted = parser.load_bit()?;
+        let bounce = cell.load_maybe(
+            ref_index,
+            parser,
+            Some(
+                |_inner_cell: &Cell,
+                 _inner_ref_index: &mut usize,
+                 inner_parser: &mut CellParser,
+                 _params: Option<_>| { Cell::load_tr_bounce_phase(inner_parser) },
+            ),
+            None::<u64>,
+        )?;
+        let destroyed = parser.load_bit()?;
+        Ok(TransactionDescrOrdinary {
+            credit_first,
+            storage_ph,
+            credit_ph,
+            compute_ph,
+            action,
+            aborted,
+            bounce,
+            destroyed,
+        })
+    }
+
+    pub fn load_split_merge_info(parser: &mut CellParser) -> Result<SplitMergeInfo, TonCellError> {
+        Ok(SplitMergeInfo {
+            cur_shard_pfx_len: parser.load_u8(6)?,
+            acc_split_depth: parser.load_u8(6)?,
+            this_addr: parser.load_bytes(32)?,
+            sibling_addr: parser.load_bytes(32)?,
+        })
+    }
+
+    fn load_prepare_transaction_ref(
+        cell: &Cell,
+        ref_index: &mut usize,
+        context: &ParseContext,
+    ) -> Result<MaybeRefData<Transaction>, TonCellError> {
+        let result = cell.load_ref_in(
+            ref_index,
+            "Transaction",
+            context,
+            |cell, ref_index, parser| Cell::load_transaction_in(cell, ref_index, parser, context),
+        )?;
+        Ok(MaybeRefData {
+            data: result.0,
+            cell: result.1.cloned(),
+            verified: false,
+        })
+    }
+
+    fn load_maybe_tr_storage_phase(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<Option<TrStoragePhase>, TonCellError> {
+        cell.load_maybe(
+            ref_index,
+            parser,
+            Some(
+                |_inner_cell: &Cell,
+                 _inner_ref_index: &mut usize,
+                 inner_parser: &mut CellParser,
+                 _params: Option<_>| { Cell::load_tr_storage_phase(inner_parser) },
+            ),
+            None::<u64>,
+        )
+    }
+
+    fn load_maybe_tr_credit_phase(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<Option<TrCreditPhase>, TonCellError> {
+        cell.load_maybe(
+            ref_index,
+            parser,
+            Some(
+                |inner_cell: &Cell,
+                 inner_ref_index: &mut usize,
+                 inner_parser: &mut CellParser,
+                 _params: Option<_>| {
+                    Cell::load_tr_credit_phase(inner_cell, inner_ref_index, inner_parser)
+                },
+            ),
+            None::<u64>,
+        )
+    }
+
+    fn load_maybe_tr_action_phase_ref(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<MaybeRefData<TrActionPhase>, TonCellError> {
+        cell.load_maybe_ref(
+            ref_index,
+            parser,
+            Some(Cell::load_tr_action_phase),
+            None::<fn(&Cell, &mut usize, &mut CellParser) -> Result<TrActionPhase, TonCellError>>,
+        )
+    }
+
+    pub fn load_tr_storage_phase(parser: &mut CellParser) -> Result<TrStoragePhase, TonCellError> {
+        Ok(TrStoragePhase {
+            storage_fees_collected: Cell::load_grams(parser)?,
+            storage_fees_due: Cell::load_maybe_grams(parser)?,
+            status_change: Cell::load_acc_status_change(parser)?,
+        })
+    }
+
+    pub fn load_tr_credit_phase(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<TrCreditPhase, TonCellError> {
+        Ok(TrCreditPhase {
+            due_fees_collected: Cell::load_maybe_grams(parser)?,
+            credit: Cell::load_currency_collection(cell, ref_index, parser)?,
+        })
+    }
+
+    pub fn load_tr_compute_phase(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<TrComputePhase, TonCellError> {
+        if !parser.load_bit()? {
+            let reason = if !parser.load_bit()? {
+                if !parser.load_bit()? {
+                    ComputeSkipReason::NoState
+                } else {
+                    ComputeSkipReason::BadState
+                }
+            } else if !parser.load_bit()? {
+                ComputeSkipReason::NoGas
+            } else if !parser.load_bit()? {
+                ComputeSkipReason::Suspended
+            } else {
+                return Err(TonCellError::unexpected_structure("ComputeSkipReason"));
+            };
+            return Ok(TrComputePhase::Skipped(reason));
+        }
+        Ok(TrComputePhase::Vm(Box::new(TrComputePhaseVm {
+            success: parser.load_bit()?,
+            msg_state_used: parser.load_bit()?,
+            account_activated: parser.load_bit()?,
+            gas_fees: Cell::load_grams(parser)?,
+            vm_details: cell
+                .load_ref_if_exist_without_self(
+                    ref_index,
+                    Some(Cell::load_compute_phase_vm_details),
+                )?
+                .0,
+        })))
+    }
+
+    pub fn load_compute_phase_vm_details(
+        parser: &mut CellParser,
+    ) -> Result<ComputePhaseVmDetails, TonCellError> {
+        Ok(ComputePhaseVmDetails {
+            gas_used: parser.load_var_uinteger(7)?,
+            gas_limit: parser.load_var_uinteger(7)?,
+            gas_credit: if parser.load_bit()? {
+                Some(parser.load_var_uinteger(3)?)
+            } else {
+                None
+            },
+            mode: parser.load_i8(8)?,
+            exit_code: parser.load_i32(32)?,
+            exit_arg: Cell::load_maybe_i32(parser)?,
+            vm_steps: parser.load_u32(32)?,
+            vm_init_state_hash: parser.load_bytes(32)?,
+            vm_final_state_hash: parser.load_bytes(32)?,
+        })
+    }
+
+    pub fn load_tr_action_phase(
+        _cell: &Cell,
+        _ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<TrActionPhase, TonCellError> {
+        Ok(TrActionPhase {
+            success: parser.load_bit()?,
+            valid: parser.load_bit()?,
+            no_funds: parser.load_bit()?,
+            status_change: Cell::load_acc_status_change(parser)?,
+            total_fwd_fees: Cell::load_maybe_grams(parser)?,
+            total_action_fees: Cell::load_maybe_grams(parser)?,
+            result_code: parser.load_i32(32)?,
+            result_arg: Cell::load_maybe_i32(parser)?,
+            tot_actions: parser.load_u16(16)?,
+            spec_actions: parser.load_u16(16)?,
+            skipped_actions: parser.load_u16(16)?,
+            msgs_created: parser.load_u16(16)?,
+            action_list_hash: parser.load_bytes(32)?,
+            tot_msg_size: Cell::load_storage_used_short(parser)?,
+        })
+    }
+
+    pub fn load_tr_bounce_phase(parser: &mut CellParser) -> Result<TrBouncePhase, TonCellError> {
+        if parser.load_bit()? {
+            return Ok(TrBouncePhase::Ok {
+                msg_size: Cell::load_storage_used_short(parser)?,
+                msg_fees: Cell::load_grams(parser)?,
+                fwd_fees: Cell::load_grams(parser)?,
+            });
+        }
+        if !parser.load_bit()? {
+            return Ok(TrBouncePhase::NegFunds);
+        }
+        Ok(TrBouncePhase::NoFunds {
+            msg_size: Cell::load_storage_used_short(parser)?,
+            req_fwd_fees: Cell::load_grams(parser)?,
+        })
+    }
+
+    pub fn load_acc_status_change(
+        parser: &mut CellParser,
+    ) -> Result<AccStatusChange, TonCellError> {
+        if !parser.load_bit()? {
+            return Ok(AccStatusChange::Unchanged);
+        }
+        if !parser.load_bit()? {
+            Ok(AccStatusChange::Frozen)
+        } else {
+            Ok(AccStatusChange::Deleted)
+        }
+    }
+
+    pub fn load_storage_used_short(
+        parser: &mut CellParser,
+    ) -> Result<StorageUsedShort, TonCellError> {
+        Ok(StorageUsedShort {
+            cells: parser.load_var_uinteger(7)?,
+            bits: parser.load_var_uinteger(7)?,
+        })
+    }
+
+    fn load_maybe_grams(parser: &mut CellParser) -> Result<Option<VarUInteger>, TonCellError> {
+        if parser.load_bit()? {
+            Ok(Some(Cell::load_grams(parser)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn load_maybe_i32(parser: &mut CellParser) -> Result<Option<i32>, TonCellError> {
+        if parser.load_bit()? {
+            Ok(Some(parser.load_i32(32)?))
+        } else {
+            Ok(None)
+        }
     }
 
     pub fn load_msg_address_internal(
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
+    ) -> Result<TonAddress, TonCellError> {
+        Cell::load_msg_address_internal_in(cell, ref_index, parser, &ParseContext::default())
+    }
+
+    /// Loads the internal address. Addresses of other lengths than 256 bits don't fit
+    /// [`TonAddress`], so they fail to load in the strict mode and have a zero hash otherwise.
+    fn load_msg_address_internal_in(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+        context: &ParseContext,
     ) -> Result<TonAddress, TonCellError> {
         let mut ton_address = TonAddress::default();
         let addr_type = parser.load_uint(2)?;
@@ -1496,41 +2758,50 @@ impl Cell {
                 ),
                 None::<u64>,
             )?;
-            let addr_len = parser.load_uint(9)?;
+            let addr_len = parser.load_u16(9)?;
             ton_address.workchain = parser.load_i32(32)?;
-            ton_address.hash_part = parser
-                .load_bits(usize::try_from(addr_len).map_err(TonCellError::cell_parser_error)?)?
-                .try_into()
-                .ok()
-                .unwrap_or_default();
+            let hash_part = parser.load_bits(addr_len as usize)?;
+            let hash_part = match addr_len {
+                256 => Ok(hash_part.try_into().unwrap_or_default()),
+                _ => Err(format!("unsupported address length: {}", addr_len)),
+            };
+            ton_address.hash_part = context.converted("MsgAddressInt", hash_part)?;
         } else {
-            return Err(TonCellError::cell_parser_error(
-                "not a msg_address_internal",
-            ));
+            return Err(TonCellError::unexpected_structure("MsgAddressInt"));
         }
         Ok(ton_address)
     }
 
     pub fn load_msg_address_external(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<TonAddress, TonCellError> {
+        Cell::load_msg_address_external_in(cell, ref_index, parser, &ParseContext::default())
+    }
+
+    /// Loads the external address, see [`Cell::load_msg_address_internal_in`] for the
+    /// addresses of other lengths than 256 bits.
+    fn load_msg_address_external_in(
         _cell: &Cell,
         _ref_index: &mut usize,
         parser: &mut CellParser,
+        context: &ParseContext,
     ) -> Result<TonAddress, TonCellError> {
         let mut ton_address = TonAddress::default();
         let addr_type = parser.load_uint(2)?;
         if addr_type == BigUint::from_u8(0).unwrap() {
             return Ok(ton_address);
         } else if addr_type == BigUint::from_u8(1).unwrap() {
-            let addr_len = parser.load_uint(9)?;
-            ton_address.hash_part = parser
-                .load_bits(usize::try_from(addr_len).map_err(TonCellError::cell_parser_error)?)?
-                .try_into()
-                .ok()
-                .unwrap_or_default();
+            let addr_len = parser.load_u16(9)?;
+            let hash_part = parser.load_bits(addr_len as usize)?;
+            let hash_part = match addr_len {
+                256 => Ok(hash_part.try_into().unwrap_or_default()),
+                _ => Err(format!("unsupported address length: {}", addr_len)),
+            };
+            ton_address.hash_part = context.converted("MsgAddressExt", hash_part)?;
         } else {
-            return Err(TonCellError::cell_parser_error(
-                "not a msg_address_external",
-            ));
+            return Err(TonCellError::unexpected_structure("MsgAddressExt"));
         }
         Ok(ton_address)
     }
@@ -1576,19 +2847,33 @@ impl Cell {
             None,
         )?;
         // code
-        cell.load_maybe_ref(
-            ref_index,
-            parser,
-            Some(|_inner_cell: &Cell, _inner_ref: &mut usize, _parser: &mut CellParser| Ok(())),
-            None::<fn(&Cell, &mut usize, &mut CellParser) -> Result<(), TonCellError>>,
-        )?;
+        builder.code = cell
+            .load_maybe_ref(
+                ref_index,
+                parser,
+                Some(
+                    |inner_cell: &Cell, _inner_ref: &mut usize, _parser: &mut CellParser| {
+                        Ok(Some(Arc::new(inner_cell.clone())))
+                    },
+                ),
+                None::<fn(&Cell, &mut usize, &mut CellParser) -> Result<_, TonCellError>>,
+            )?
+            .data
+            .flatten();
         // data
-        cell.load_maybe_ref(
-            ref_index,
-            parser,
-            Some(|_inner_cell: &Cell, _inner_ref: &mut usize, _parser: &mut CellParser| Ok(())),
-            None::<fn(&Cell, &mut usize, &mut CellParser) -> Result<(), TonCellError>>,
-        )?;
+        builder.data = cell
+            .load_maybe_ref(
+                ref_index,
+                parser,
+                Some(
+                    |inner_cell: &Cell, _inner_ref: &mut usize, _parser: &mut CellParser| {
+                        Ok(Some(Arc::new(inner_cell.clone())))
+                    },
+                ),
+                None::<fn(&Cell, &mut usize, &mut CellParser) -> Result<_, TonCellError>>,
+            )?
+            .data
+            .flatten();
 
         // library
         cell.load_maybe(
@@ -1620,16 +2905,19 @@ impl Cell {
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
-    ) -> Result<(), TonCellError> {
+    ) -> Result<HashUpdate, TonCellError> {
         let magic = parser.load_u8(8)?;
         if magic != 0x72 {
-            return Err(TonCellError::cell_parser_error("not a hash update"));
+            return Err(TonCellError::unexpected_structure("HashUpdate"));
         }
         let old_hash = parser.load_bytes(32)?;
         let new_hash = parser.load_bytes(32)?;
-        debug!("old hash load hash update: {:?}", old_hash);
-        debug!("new hash load hash update: {:?}", new_hash);
-        Ok(())
+        parse_event!(
+            "loaded hash update",
+            old_hash = hex::encode(&old_hash),
+            new_hash = hex::encode(&new_hash)
+        );
+        Ok(HashUpdate { old_hash, new_hash })
     }
 
     pub fn load_mc_block_extra(
@@ -1641,24 +2929,25 @@ impl Cell {
 
         let magic = parser.load_u16(16)?;
         if magic != 0xcca5 {
-            return Err(TonCellError::cell_parser_error("not a McBlockExtra"));
+            return Err(TonCellError::unexpected_structure("McBlockExtra"));
         }
         let key_block = parser.load_bit()?;
         mc_block_extra.shards = Cell::load_shard_hashes(cell, ref_index, parser)?;
-        Cell::load_shard_fees(cell, ref_index, parser)?;
+        mc_block_extra.shard_fees = Cell::load_shard_fees(cell, ref_index, parser)?;
 
         let cell_r1 = cell.reference(ref_index.to_owned())?;
         *ref_index += 1;
         let new_ref_index = &mut 0usize;
         // use a new parser to reset cell cursor, since we are handling a new cell.
         let cell_r1_parser = &mut cell_r1.parser();
-        debug!("current cell data: {:?}", cell.data);
-        debug!("ref index after all: {:?}", ref_index);
-        debug!("cell r1 type: {:?}", cell_r1.cell_type);
-        debug!("cell r1: {:?}", cell_r1.data);
+        parse_event!(
+            "loading mc block extra",
+            ref_index = ref_index,
+            cell_r1_type = cell_r1.cell_type
+        );
         if cell_r1.cell_type == CellType::OrdinaryCell as u8 {
             // prev_blk_signatures
-            Cell::load_hash_map_e(
+            mc_block_extra.prev_blk_signatures = Cell::load_hash_map_e(
                 &cell_r1,
                 new_ref_index,
                 cell_r1_parser,
@@ -1671,7 +2960,7 @@ impl Cell {
                 new_ref_index,
                 cell_r1_parser,
                 Some(Cell::load_in_msg),
-                None::<fn(&Cell, &mut usize, &mut CellParser) -> Result<(), TonCellError>>,
+                None::<fn(&Cell, &mut usize, &mut CellParser) -> Result<InMsg, TonCellError>>,
             )?;
 
             // mint_msg
@@ -1680,7 +2969,7 @@ impl Cell {
                 new_ref_index,
                 cell_r1_parser,
                 Some(Cell::load_in_msg),
-                None::<fn(&Cell, &mut usize, &mut CellParser) -> Result<(), TonCellError>>,
+                None::<fn(&Cell, &mut usize, &mut CellParser) -> Result<InMsg, TonCellError>>,
             )?;
         }
         if key_block {
@@ -1689,6 +2978,36 @@ impl Cell {
         Ok(mc_block_extra)
     }
 
+    /// Loads only the config of `McBlockExtra`, skipping the shards, the shard fees and the
+    /// signatures.
+    fn load_mc_block_config(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<McBlockExtra, TonCellError> {
+        let mut mc_block_extra = McBlockExtra::default();
+
+        if parser.load_u16(16)? != 0xcca5 {
+            return Err(TonCellError::unexpected_structure("McBlockExtra"));
+        }
+        let key_block = parser.load_bit()?;
+        // shard_hashes:(HashmapE 32 ^(BinTree ShardDescr))
+        if parser.load_bit()? {
+            *ref_index += 1;
+        }
+        // shard_fees:(HashmapAugE 96 ShardFeeCreated ShardFeeCreated)
+        if parser.load_bit()? {
+            *ref_index += 1;
+        }
+        Cell::load_shard_fee_created(cell, ref_index, parser)?;
+        // ^[ prev_blk_signatures recover_create_msg mint_msg ]
+        *ref_index += 1;
+        if key_block {
+            mc_block_extra.config = Cell::load_config_params(cell, ref_index, parser)?;
+        }
+        Ok(mc_block_extra)
+    }
+
     pub fn load_shard_hashes(
         cell: &Cell,
         ref_index: &mut usize,
@@ -1725,7 +3044,9 @@ impl Cell {
         let mut result_map = HashMap::new();
         for (key, value) in hashmap {
             if let Some(tree_res) = value {
-                let shard_descrs = tree_res.get_all_shard_descrs_as_vec();
+                let workchain =
+                    u32::from_str_radix(&key, 16).map_err(TonCellError::cell_parser_error)? as i32;
+                let shard_descrs = tree_res.get_shard_descrs(workchain);
                 result_map.insert(key, shard_descrs);
             } else {
                 result_map.insert(key, Vec::new());
@@ -1810,6 +3131,39 @@ impl Cell {
         }
     }
 
+    /// Loads `BinTreeAug X Y`, parsing leaves with `parse_leaf` and extras with `parse_extra`.
+    ///
+    /// Branches stored in pruned cells are returned as `None`.
+    pub fn load_bin_tree_aug<F, FE, T, E>(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+        parse_leaf: F,
+        parse_extra: FE,
+    ) -> Result<BinTreeAugRes<T, E>, TonCellError>
+    where
+        F: FnOnce(&Cell, &mut usize, &mut CellParser) -> Result<T, TonCellError> + Copy,
+        FE: FnOnce(&Cell, &mut usize, &mut CellParser) -> Result<E, TonCellError> + Copy,
+    {
+        if !parser.load_bit()? {
+            let extra = parse_extra(cell, ref_index, parser)?;
+            let leaf = parse_leaf(cell, ref_index, parser)?;
+            return Ok(BinTreeAugRes::Leaf(BinTreeAugLeaf { leaf, extra }));
+        }
+        let load_branch =
+            |ref_cell: &Cell, inner_ref_index: &mut usize, parser: &mut CellParser| {
+                Cell::load_bin_tree_aug(ref_cell, inner_ref_index, parser, parse_leaf, parse_extra)
+            };
+        let left = cell.load_ref_if_exist(ref_index, Some(load_branch))?.0;
+        let right = cell.load_ref_if_exist(ref_index, Some(load_branch))?.0;
+        let extra = parse_extra(cell, ref_index, parser)?;
+        Ok(BinTreeAugRes::Fork(Box::new(BinTreeAugFork {
+            left,
+            right,
+            extra,
+        })))
+    }
+
     pub fn load_shard_descr(
         cell: &Cell,
         ref_index: &mut usize,
@@ -1817,7 +3171,7 @@ impl Cell {
     ) -> Result<BinTreeLeafRes, TonCellError> {
         let _type = parser.load_uint(4)?;
         if _type != BigUint::from_u8(0xa).unwrap() && _type != BigUint::from_u8(0xb).unwrap() {
-            return Err(TonCellError::cell_parser_error("not a ShardDescr"));
+            return Err(TonCellError::unexpected_structure("ShardDescr"));
         }
 
         let mut shard_descr = ShardDescr::default();
@@ -1852,26 +3206,27 @@ impl Cell {
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
-    ) -> Result<(), TonCellError> {
-        let hashmap = Cell::load_hash_map_aug_e(
+    ) -> Result<HashMap<String, HashmapAugEResult<ShardFeeCreated, ShardFeeCreated>>, TonCellError>
+    {
+        Cell::load_hash_map_aug_e(
             cell,
             ref_index,
             parser,
             96,
             Cell::load_shard_fee_created,
             Cell::load_shard_fee_created,
-        )?;
-        Ok(())
+        )
     }
 
     pub fn load_shard_fee_created(
         cell: &Cell,
         ref_index: &mut usize,
         parser: &mut CellParser,
-    ) -> Result<(), TonCellError> {
-        Cell::load_currency_collection(cell, ref_index, parser)?;
-        Cell::load_currency_collection(cell, ref_index, parser)?;
-        Ok(())
+    ) -> Result<ShardFeeCreated, TonCellError> {
+        Ok(ShardFeeCreated {
+            fees: Cell::load_currency_collection(cell, ref_index, parser)?,
+            create: Cell::load_currency_collection(cell, ref_index, parser)?,
+        })
     }
 
     pub fn load_crypto_signature_pair(
@@ -1879,36 +3234,27 @@ impl Cell {
         ref_index: &mut usize,
         parser: &mut CellParser,
         _key: &BigUint,
-    ) -> Result<Option<()>, TonCellError> {
+    ) -> Result<Option<CryptoSignaturePair>, TonCellError> {
         let node_id_short = parser.load_bytes(32)?;
-        debug!("node id short: {:?}", node_id_short);
-        Cell::load_crypto_signature(cell, ref_index, parser)?;
-        // We can safely ignore this since it is called in load_ref_if_exist
-        Ok(Some(()))
+        let sign = Cell::load_crypto_signature(cell, ref_index, parser)?;
+        Ok(Some(CryptoSignaturePair {
+            node_id_short,
+            sign,
+        }))
     }
 
     pub fn load_crypto_signature(
-        cell: &Cell,
-        ref_index: &mut usize,
+        _cell: &Cell,
+        _ref_index: &mut usize,
         parser: &mut CellParser,
-    ) -> Result<(), TonCellError> {
+    ) -> Result<CryptoSignature, TonCellError> {
         let magic = parser.load_uint(4)?;
         if magic != BigUint::from_u8(0x5).unwrap() {
-            return Err(TonCellError::cell_parser_error(
-                "not a CryptoSignatureSimple",
-            ));
+            return Err(TonCellError::unexpected_structure("CryptoSignatureSimple"));
         }
         let r = parser.load_bytes(32)?;
         let s = parser.load_bytes(32)?;
-        Ok(())
-    }
-
-    pub fn load_in_msg(
-        _cell: &Cell,
-        _ref_index: &mut usize,
-        _parser: &mut CellParser,
-    ) -> Result<(), TonCellError> {
-        Ok(())
+        Ok(CryptoSignature { r, s })
     }
 
     pub fn load_config_params(
@@ -1916,45 +3262,58 @@ impl Cell {
         ref_index: &mut usize,
         parser: &mut CellParser,
     ) -> Result<ConfigParams, TonCellError> {
-        let mut config_params = ConfigParams::default();
-
         let config_addr = parser.load_bytes(32)?;
-        debug!("config addr: {:?}", config_addr);
-        let res = cell.load_ref_if_exist(
+        parse_event!(
+            "loading config params",
+            config_addr = hex::encode(&config_addr)
+        );
+        let res = cell.load_ref_if_exist(ref_index, Some(Cell::load_config_params_dict))?;
+        res.0
+            .ok_or_else(|| TonCellError::cell_parser_error("No config params to load"))
+    }
+
+    /// Loads the `Hashmap 32 ^Cell` dictionary of config params, e.g. the config returned by
+    /// `getConfigAll`.
+    pub fn load_config_params_dict(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<ConfigParams, TonCellError> {
+        let config = Cell::load_hash_map(
+            cell,
             ref_index,
-            Some(
-                |inner_cell: &Cell, inner_ref_index: &mut usize, inner_parser: &mut CellParser| {
-                    let res = Cell::load_hash_map(
-                        inner_cell,
-                        inner_ref_index,
-                        inner_parser,
-                        32,
-                        |hashmap_cell: &Cell,
-                         hashmap_ref_index: &mut usize,
-                         _hashmap_parser: &mut CellParser,
-                         n: &BigUint| {
-                            let res = hashmap_cell.load_ref_if_exist(
-                                hashmap_ref_index,
-                                Some(|inner_inner_cell: &Cell,
-                                 inner_inner_ref_index: &mut usize,
-                                 inner_inner_parser: &mut CellParser| {
-                                    Cell::load_config_param(inner_inner_cell, inner_inner_ref_index, inner_inner_parser, n)
-                                }),
-                            )?;
-                            Ok(res.0)
+            parser,
+            32,
+            |hashmap_cell: &Cell,
+             hashmap_ref_index: &mut usize,
+             _hashmap_parser: &mut CellParser,
+             n: &BigUint| {
+                let param_cell = hashmap_cell
+                    .references
+                    .get(*hashmap_ref_index)
+                    .map(|cell| cell.as_ref().clone());
+                let res = hashmap_cell.load_ref_if_exist(
+                    hashmap_ref_index,
+                    Some(
+                        |inner_cell: &Cell,
+                         inner_ref_index: &mut usize,
+                         inner_parser: &mut CellParser| {
+                            Cell::load_config_param(inner_cell, inner_ref_index, inner_parser, n)
                         },
-                    )?;
-                    Ok(res)
-                },
-            ),
+                    ),
+                )?;
+                Ok(res.0.zip(param_cell))
+            },
         )?;
 
-        if let Some(config) = res.0 {
-            config_params.config = config;
-        } else {
-            return Err(TonCellError::cell_parser_error("No config params to load"));
+        let mut params = HashMap::new();
+        let mut cells = HashMap::new();
+        for (n, (param, cell)) in config {
+            let number = u32::from_str_radix(&n, 16).map_err(TonCellError::cell_parser_error)?;
+            cells.insert(number, cell);
+            params.insert(n, param);
         }
-        Ok(config_params)
+        Ok(ConfigParams::new(params, cells))
     }
 
     pub fn load_config_param(
@@ -1966,7 +3325,7 @@ impl Cell {
         if parser.remaining_bits() < parser.bit_len || *ref_index != 0 {
             return Err(TonCellError::cell_parser_error("Invalid config cell"));
         }
-        debug!("config param number: {:?}", n.to_string());
+        parse_event!("loading config param", n = n);
         // we dont need to implement all config params because each param is a cell ref -> they are independent.
         let n_str = n.to_string();
 
@@ -2091,15 +3450,27 @@ impl Cell {
         Ok(Some(validator))
     }
 
+    /// Loads only the `BlockInfo` of a block, leaving the value flow, the state update and the
+    /// block extra untouched, so it works with header proofs in which they are pruned.
+    pub fn load_block_header_only(&self) -> Result<BlockInfo, TonCellError> {
+        if self.parser().load_u32(32)? != 0x11ef55aa {
+            return Err(TonCellError::unexpected_structure("Block"));
+        }
+        let (block_info, _) = self.load_ref_if_exist(&mut 0, Some(Cell::load_block_info))?;
+        block_info.ok_or_else(|| TonCellError::unexpected_structure("BlockInfo"))
+    }
+
     pub fn load_block(&self) -> Result<BlockData, TonCellError> {
         let ref_index = &mut 0;
         let block_info = self
             .load_ref_if_exist(ref_index, Some(Cell::load_block_info))
             .unwrap();
-        self.load_ref_if_exist(ref_index, Some(Cell::load_value_flow))
+        let value_flow = self
+            .load_ref_if_exist(ref_index, Some(Cell::load_value_flow))
             .unwrap();
 
-        self.load_ref_if_exist(ref_index, Some(Cell::load_merkle_update))
+        let state_update = self
+            .load_ref_if_exist(ref_index, Some(Cell::load_merkle_update))
             .unwrap();
 
         let block_extra = self
@@ -2108,10 +3479,141 @@ impl Cell {
 
         Ok(BlockData {
             info: block_info.0,
+            value_flow: value_flow.0,
+            state_update: state_update.0,
             extra: block_extra.0,
         })
     }
 
+    /// Loads the block of the root cell with the mode, checking its tag and that the whole
+    /// cell is loaded. In the lenient mode, the value flow, the state update and the block
+    /// extra are skipped if they fail to load, as are the transactions of the account blocks
+    /// and their parts, see [`Cell::load_transaction_with_mode`].
+    pub fn load_block_with_mode(&self, mode: ParseMode) -> Result<Parsed<BlockData>, TonCellError> {
+        let context = ParseContext::new(mode);
+        let ref_index = &mut 0;
+        let parser = &mut self.parser();
+        if parser.load_u32(32)? != 0x11ef55aa {
+            return Err(TonCellError::unexpected_structure("Block"));
+        }
+        let _global_id = parser.load_i32(32)?;
+        let (info, _) =
+            self.load_ref_in(ref_index, "BlockInfo", &context, Cell::load_block_info)?;
+        let value_flow = self.load_ref_in(ref_index, "ValueFlow", &context, Cell::load_value_flow);
+        let value_flow = context.optional("ValueFlow", value_flow.map(|res| res.0))?;
+        let state_update = self.load_ref_in(
+            ref_index,
+            "MerkleUpdate",
+            &context,
+            Cell::load_merkle_update,
+        );
+        let state_update = context.optional("MerkleUpdate", state_update.map(|res| res.0))?;
+        let extra = self.load_ref_in(
+            ref_index,
+            "BlockExtra",
+            &context,
+            |cell, ref_index, parser| {
+                let selector = &BlockSelector::all();
+                Cell::load_block_extra_with(cell, ref_index, parser, selector, &context)
+            },
+        );
+        let extra = context.optional("BlockExtra", extra.map(|res| res.0))?;
+        context.check_trailing_data("Block", self, *ref_index, parser)?;
+        Ok(context.finish(BlockData {
+            info,
+            value_flow,
+            state_update,
+            extra,
+        }))
+    }
+
+    /// Loads the sections of the block chosen by the selector, leaving the others `None` or
+    /// empty. As with [`Cell::load_block`], pruned sections are `None`.
+    pub fn load_block_with(&self, selector: &BlockSelector) -> Result<BlockData, TonCellError> {
+        let context = &ParseContext::default();
+        let ref_index = &mut 0;
+        let info = self.load_selected_ref(
+            ref_index,
+            selector.info,
+            "BlockInfo",
+            context,
+            Cell::load_block_info,
+        )?;
+        let value_flow = self.load_selected_ref(
+            ref_index,
+            selector.value_flow,
+            "ValueFlow",
+            context,
+            Cell::load_value_flow,
+        )?;
+        let state_update = self.load_selected_ref(
+            ref_index,
+            selector.state_update,
+            "MerkleUpdate",
+            context,
+            Cell::load_merkle_update,
+        )?;
+        let extra = self.load_selected_ref(
+            ref_index,
+            selector.extra(),
+            "BlockExtra",
+            context,
+            |cell, ref_index, parser| {
+                Cell::load_block_extra_with(cell, ref_index, parser, selector, context)
+            },
+        )?;
+        Ok(BlockData {
+            info,
+            value_flow,
+            state_update,
+            extra,
+        })
+    }
+
+    /// Loads the reference if it's selected, otherwise skips it.
+    fn load_selected_ref<F, T>(
+        &self,
+        ref_index: &mut usize,
+        selected: bool,
+        structure: &'static str,
+        context: &ParseContext,
+        parse: F,
+    ) -> Result<Option<T>, TonCellError>
+    where
+        F: FnOnce(&Cell, &mut usize, &mut CellParser) -> Result<T, TonCellError>,
+    {
+        if !selected {
+            self.reference(*ref_index)?;
+            *ref_index += 1;
+            return Ok(None);
+        }
+        Ok(self.load_ref_in(ref_index, structure, context, parse)?.0)
+    }
+
+    /// Loads the reference like [`Cell::load_ref_if_exist`], checking with the mode of the
+    /// context that the whole referenced cell is loaded as the structure.
+    fn load_ref_in<F, T>(
+        &self,
+        ref_index: &mut usize,
+        structure: &'static str,
+        context: &ParseContext,
+        parse: F,
+    ) -> Result<(Option<T>, Option<&Cell>), TonCellError>
+    where
+        F: FnOnce(&Cell, &mut usize, &mut CellParser) -> Result<T, TonCellError>,
+    {
+        self.load_ref_if_exist(
+            ref_index,
+            Some(
+                |cell: &Cell, ref_index: &mut usize, parser: &mut CellParser| {
+                    let value = parse(cell, ref_index, parser)?;
+                    context.check_ref_trailing_data(structure, cell, *ref_index, parser)?;
+                    Ok(value)
+                },
+            ),
+        )
+    }
+
     pub fn load_buffer(&self, buffer: &mut Vec<u8>) -> Result<(), TonCellError> {
         let mut cell: &Cell = self;
         loop {
@@ -2183,3 +3685,255 @@ impl Debug for Cell {
         write!(f, "] }}")
     }
 }
+
+/// Bit length of the length of a long or same label of a key with `max_len` bits left, i.e.
+/// `ceil(log2(max_len + 1))`.
+fn label_len_bits(max_len: usize) -> usize {
+    (usize::BITS - max_len.leading_zeros()) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use num_bigint::BigUint;
+    use num_traits::Zero;
+
+    use crate::cell::{
+        prune_cells, BagOfCells, Cell, CellBuilder, CellParser, DictBuilder, TonCellError,
+        TonCellErrorCode, SHARD_STATE_UNSPLIT_TAG,
+    };
+    use crate::responses::{BinTreeAugLeaf, BinTreeAugRes};
+
+    fn load_u8(_: &Cell, _: &mut usize, parser: &mut CellParser) -> Result<u8, TonCellError> {
+        parser.load_u8(8)
+    }
+
+    #[test]
+    fn load_bin_tree_aug_works() -> anyhow::Result<()> {
+        let leaf = |value: u8, extra: u8| {
+            CellBuilder::new()
+                .store_bit(false)?
+                .store_u8(8, extra)?
+                .store_u8(8, value)?
+                .build()
+        };
+        let right = CellBuilder::new()
+            .store_bit(true)?
+            .store_child(leaf(2, 20)?)?
+            .store_child(leaf(3, 30)?)?
+            .store_u8(8, 50)?
+            .build()?;
+        let root = CellBuilder::new()
+            .store_bit(true)?
+            .store_child(leaf(1, 10)?)?
+            .store_child(right)?
+            .store_u8(8, 60)?
+            .build()?;
+
+        let mut parser = root.parser();
+        let tree = Cell::load_bin_tree_aug(&root, &mut 0, &mut parser, load_u8, load_u8)?;
+        assert_eq!(*tree.extra(), 60);
+        let leaves: Vec<&BinTreeAugLeaf<u8, u8>> = tree.leaves();
+        let leaves: Vec<(u8, u8)> = leaves.iter().map(|l| (l.leaf, l.extra)).collect();
+        assert_eq!(leaves, vec![(1, 10), (2, 20), (3, 30)]);
+        match tree {
+            BinTreeAugRes::Fork(fork) => assert_eq!(*fork.right.unwrap().extra(), 50),
+            BinTreeAugRes::Leaf(_) => panic!("expected a fork"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn stub_loaders_return_data() -> anyhow::Result<()> {
+        let cell = CellBuilder::new()
+            .store_u8(2, 0)?
+            .store_u8(6, 2)? // shard_pfx_bits:(#<= 60)
+            .store_i32(32, -1)?
+            .store_u64(64, 0x8000_0000_0000_0000)?
+            .store_u8(8, 0xc4)?
+            .store_u32(32, 9)?
+            .store_u64(64, 0x2e)?
+            .build()?;
+        let mut parser = cell.parser();
+        let shard = parser.load_shard_ident()?;
+        assert_eq!(shard.shard_pfx_bits, 2);
+        assert_eq!(shard.workchain_id, -1);
+        assert_eq!(shard.shard_prefix, 0x8000_0000_0000_0000);
+        let version = parser.load_global_version()?;
+        assert_eq!(version.version, 9);
+        assert_eq!(version.capabilities, 0x2e);
+
+        let cell = CellBuilder::new()
+            .store_slice(&[0xaa; 32])?
+            .store_u8(4, 5)?
+            .store_slice(&[1; 32])?
+            .store_slice(&[2; 32])?
+            .build()?;
+        let pair = Cell::load_crypto_signature_pair(
+            &cell,
+            &mut 0,
+            &mut cell.parser(),
+            &Default::default(),
+        )?
+        .unwrap();
+        assert_eq!(pair.node_id_short, vec![0xaa; 32]);
+        assert_eq!(pair.sign.r, vec![1; 32]);
+        assert_eq!(pair.sign.s, vec![2; 32]);
+        Ok(())
+    }
+
+    #[test]
+    fn load_mc_state_prev_blocks_works() -> anyhow::Result<()> {
+        let block = |seqno: u32, key: bool| {
+            CellBuilder::new()
+                .store_bit(key)? // extra: KeyMaxLt
+                .store_u64(64, seqno as u64 * 100)?
+                .store_bit(key)? // value: KeyExtBlkRef
+                .store_u64(64, seqno as u64 * 100)?
+                .store_u32(32, seqno)?
+                .store_slice(&[seqno as u8; 32])?
+                .store_slice(&[!seqno as u8; 32])?
+                .build()
+        };
+        let mut dict = DictBuilder::new(32);
+        for seqno in [1, 2, 3, 1000] {
+            dict.insert(BigUint::from(seqno), block(seqno, seqno == 1000)?)?;
+        }
+        let prev_blocks = CellBuilder::new()
+            .store_u16(16, 0)? // flags
+            .store_uint(65, &BigUint::zero())? // validator_info
+            .store_bit(true)?
+            .store_child(dict.build()?.unwrap())?
+            .store_bit(true)?
+            .store_u64(64, 100000)?
+            .build()?;
+        let empty = || CellBuilder::new().build();
+        let mc_state_extra = CellBuilder::new()
+            .store_u16(16, 0xcc26)?
+            .store_bit(true)? // shard_hashes
+            .store_child(empty()?)?
+            .store_slice(&[0x55; 32])? // config_addr
+            .store_child(empty()?)?
+            .store_child(prev_blocks)?
+            .build()?;
+        let state = Arc::new(
+            CellBuilder::new()
+                .store_u32(32, SHARD_STATE_UNSPLIT_TAG)?
+                .store_uint(329, &BigUint::zero())?
+                .store_bit(true)?
+                .store_child(empty()?)?
+                .store_child(empty()?)?
+                .store_child(empty()?)?
+                .store_child(mc_state_extra)?
+                .build()?,
+        );
+
+        let info = Cell::load_mc_state_prev_blocks(&state, &mut 0, &mut state.parser())?;
+        assert_eq!(info.blocks.len(), 4);
+        assert!(!info.pruned);
+        assert!(info.extra.key);
+        assert_eq!(info.extra.max_end_lt, 100000);
+        assert!(info.contains_block(3, &[3; 32], &[!3u8; 32]));
+        assert!(!info.contains_block(3, &[3; 32], &[3; 32]));
+        let key_blocks = info.key_blocks();
+        assert_eq!(key_blocks.len(), 1);
+        assert_eq!(key_blocks[0].blk_ref.seqno, 1000);
+        assert_eq!(info.get(2).unwrap().blk_ref.end_lt, 200);
+
+        // Keep only the path to the key block
+        let pruned = prune_cells(&state, |path, _| match path {
+            [] | [3] | [3, 2] | [3, 2, 0] => true,
+            [3, 2, 0, index, ..] => *index == 1,
+            _ => false,
+        })?;
+        let info = Cell::load_mc_state_prev_blocks(&pruned, &mut 0, &mut pruned.parser())?;
+        assert!(info.pruned);
+        assert_eq!(info.blocks.keys().copied().collect::<Vec<_>>(), vec![1000]);
+
+        let no_custom = CellBuilder::new()
+            .store_u32(32, SHARD_STATE_UNSPLIT_TAG)?
+            .store_uint(330, &BigUint::zero())?
+            .build()?;
+        let error = Cell::load_mc_state_prev_blocks(&no_custom, &mut 0, &mut no_custom.parser())
+            .unwrap_err();
+        assert_eq!(error.structure(), Some("McStateExtra"));
+        Ok(())
+    }
+
+    #[test]
+    fn parser_errors_carry_code_and_position() -> anyhow::Result<()> {
+        let hash_update = CellBuilder::new().store_u8(8, 0x73)?.build()?;
+        let cell = CellBuilder::new().store_child(hash_update)?.build()?;
+        let error = cell
+            .load_ref_if_exist(&mut 0, Some(Cell::load_hash_update))
+            .unwrap_err();
+        assert_eq!(error.code(), TonCellErrorCode::UnexpectedStructure);
+        assert_eq!(error.code().as_str(), "UNEXPECTED_STRUCTURE");
+        assert_eq!(error.structure(), Some("HashUpdate"));
+        assert_eq!(error.reference_path(), vec![0]);
+        match &error {
+            TonCellError::InReference { bit_offset, .. } => assert_eq!(*bit_offset, 8),
+            _ => panic!("expected an error in reference"),
+        }
+        let source = std::error::Error::source(&error).unwrap();
+        assert_eq!(
+            source.to_string(),
+            TonCellError::unexpected_structure("HashUpdate").to_string()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn shared_subtrees_are_hashed_once() -> anyhow::Result<()> {
+        let mut cell = Arc::new(CellBuilder::new().store_u8(8, 1)?.build()?);
+        for i in 0..64 {
+            cell = Arc::new(
+                CellBuilder::new()
+                    .store_reference(&cell)?
+                    .store_reference(&cell)?
+                    .build()?,
+            );
+            if i == 4 {
+                let boc = BagOfCells::from_root(cell.as_ref().clone()).serialize(false)?;
+                let parsed = BagOfCells::parse(&boc)?.single_root()?.clone();
+                assert_eq!(parsed.cell_hash_array()?, cell.cell_hash_array()?);
+            }
+        }
+        // Without memoization, hashing the tree would visit 2^64 paths
+        let repr = cell.get_repr()?;
+        assert_eq!(repr[2..4], [0, 63]);
+        assert_eq!(repr[6..38], cell.references[0].cell_hash_array()?);
+        Ok(())
+    }
+
+    #[test]
+    fn dict_hash_works() -> anyhow::Result<()> {
+        let mut dict = DictBuilder::new(32);
+        for key in [1u32, 2, 3, 100, 0xffff_ffff] {
+            let value = CellBuilder::new().store_u32(32, key ^ 0x5555)?.build()?;
+            dict.insert(BigUint::from(key), value)?;
+        }
+        let root = Arc::new(dict.build()?.unwrap());
+        assert_eq!(root.dict_hash(32)?, root.cell_hash_array()?);
+        assert!(root.dict_hash(16).is_err());
+
+        let pruned = prune_cells(&root, |path, _| path.len() < 2)?;
+        assert_ne!(pruned.cell_hash_array()?, root.cell_hash_array()?);
+        assert_eq!(pruned.dict_hash(32)?, root.cell_hash_array()?);
+
+        let empty = CellBuilder::new().store_bit(false)?.build()?;
+        assert_eq!(empty.parser().load_dict_hash(32)?, None);
+        let with_dict = CellBuilder::new().store_dict(&dict)?.build()?;
+        assert_eq!(
+            with_dict.parser().load_dict_hash(32)?,
+            Some(root.cell_hash_array()?)
+        );
+        let not_a_dict = CellBuilder::new()
+            .store_bit(true)?
+            .store_child(CellBuilder::new().store_u8(8, 1)?.build()?)?
+            .build()?;
+        assert!(not_a_dict.parser().load_dict_hash(32).is_err());
+        Ok(())
+    }
+}
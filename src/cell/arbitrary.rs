@@ -0,0 +1,110 @@
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+use crate::cell::{
+    BagOfCells, Cell, CellBuilder, TonCellError, MAX_CELL_BITS, MAX_CELL_REFERENCES,
+};
+
+/// Maximal depth of generated cell trees, which keeps generated structures small.
+const MAX_ARBITRARY_DEPTH: usize = 8;
+
+/// Generates ordinary cells with at most 1023 bits and 4 references.
+impl<'a> Arbitrary<'a> for Cell {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        arbitrary_cell(u, MAX_ARBITRARY_DEPTH)
+    }
+}
+
+/// Generates bags of cells with a single root, as serialization of multiple roots is not
+/// supported.
+impl<'a> Arbitrary<'a> for BagOfCells {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(BagOfCells::from_root(Cell::arbitrary(u)?))
+    }
+}
+
+fn arbitrary_cell(u: &mut Unstructured, depth: usize) -> Result<Cell> {
+    let bit_len = u.int_in_range(0..=MAX_CELL_BITS.min(u.len() * 8))?;
+    // Data is padded with zeros once the input is exhausted
+    let byte_len = bit_len.div_ceil(8);
+    let mut data = u.bytes(byte_len.min(u.len()))?.to_vec();
+    data.resize(byte_len, 0);
+    let ref_count = if depth == 0 || u.is_empty() {
+        0
+    } else {
+        u.int_in_range(0..=MAX_CELL_REFERENCES)?
+    };
+    let mut builder = CellBuilder::new();
+    builder
+        .store_bits(bit_len, &data)
+        .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+    for _ in 0..ref_count {
+        let child = arbitrary_cell(u, depth - 1)?;
+        builder
+            .store_child(child)
+            .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+    }
+    builder
+        .build()
+        .map_err(|_| arbitrary::Error::IncorrectFormat)
+}
+
+/// Serializes the bag of cells with and without CRC32, parses it back and checks that the
+/// parsed roots have the same hashes as the original ones.
+///
+/// Intended as a property for generated bags of cells:
+///
+/// ```ignore
+/// let boc = BagOfCells::arbitrary(&mut Unstructured::new(data))?;
+/// check_boc_round_trip(&boc)?;
+/// ```
+pub fn check_boc_round_trip(boc: &BagOfCells) -> core::result::Result<(), TonCellError> {
+    for has_crc32 in [false, true] {
+        let parsed = BagOfCells::parse(&boc.serialize(has_crc32)?)?;
+        if parsed.num_roots() != boc.num_roots() {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Expected {} roots after round trip, got {}",
+                boc.num_roots(),
+                parsed.num_roots()
+            )));
+        }
+        for (idx, (root, parsed_root)) in boc.roots.iter().zip(parsed.roots.iter()).enumerate() {
+            if root.cell_hash()? != parsed_root.cell_hash()? {
+                return Err(TonCellError::boc_deserialization_error(format!(
+                    "Hash of root {} changed after round trip",
+                    idx
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    use crate::address::TonAddress;
+    use crate::cell::{check_boc_round_trip, BagOfCells, CellBuilder};
+
+    #[test]
+    fn arbitrary_bag_of_cells_round_trip_works() -> anyhow::Result<()> {
+        let mut seed = 0x1234_5678_9abc_def0u64;
+        for _ in 0..64 {
+            let data: Vec<u8> = (0..4096)
+                .map(|_| {
+                    seed ^= seed << 13;
+                    seed ^= seed >> 7;
+                    seed ^= seed << 17;
+                    seed as u8
+                })
+                .collect();
+            let mut u = Unstructured::new(&data);
+            let address = TonAddress::arbitrary(&mut u)?;
+            let boc = BagOfCells::arbitrary(&mut u)?;
+            check_boc_round_trip(&boc)?;
+            let cell = CellBuilder::new().store_address(&address)?.build()?;
+            assert_eq!(cell.parser().load_address()?, address);
+        }
+        Ok(())
+    }
+}
@@ -1,15 +1,100 @@
-use std::collections::{HashMap, HashSet};
-use std::sync::Arc;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::mem::size_of;
 
-use base64::engine::general_purpose::STANDARD;
+use base64::alphabet;
+use base64::engine::general_purpose::{GeneralPurpose, GeneralPurposeConfig, STANDARD, URL_SAFE};
+use base64::engine::DecodePaddingMode;
+use base64::Engine;
 
 use crate::cell::*;
+use crate::compat::{HashMap, HashSet};
+
+/// Decoders accepting both padded and unpadded input.
+const STANDARD_TOLERANT: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::STANDARD,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+const URL_SAFE_TOLERANT: GeneralPurpose = GeneralPurpose::new(
+    &alphabet::URL_SAFE,
+    GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
 
 #[derive(PartialEq, Eq, Debug, Clone, Hash)]
 pub struct BagOfCells {
     pub roots: Vec<ArcCell>,
 }
 
+/// Cells reachable from the roots of a bag of cells by their hash, see
+/// [`BagOfCells::index`].
+///
+/// Cells are looked up by their level 0 hash, which is the hash of the original cell for
+/// cells of Merkle proofs containing pruned branches, e.g. the hash of a transaction as
+/// recorded in its block. Pruned branches themselves are not indexed.
+#[derive(Debug, Clone, Default)]
+pub struct CellIndex {
+    cells: HashMap<[u8; HASH_BYTES], ArcCell>,
+}
+
+impl CellIndex {
+    pub fn new(roots: &[ArcCell]) -> CellIndex {
+        let mut cells = HashMap::new();
+        let mut stack: Vec<&ArcCell> = roots.iter().collect();
+        while let Some(cell) = stack.pop() {
+            if cell.cell_type == CellType::PrunnedBranchCell as u8 {
+                continue;
+            }
+            // Cells with the same hash have the same references, which are already indexed
+            if cells.insert(cell.hash_array(0), cell.clone()).is_none() {
+                stack.extend(cell.references.iter());
+            }
+        }
+        CellIndex { cells }
+    }
+
+    /// Returns the cell with specified hash.
+    pub fn cell_by_hash(&self, hash: &[u8; HASH_BYTES]) -> Option<&ArcCell> {
+        self.cells.get(hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+/// Heap memory held by the cells of a bag, see [`BagOfCells::memory_usage`].
+///
+/// Cells are counted by allocation, so a cell shared by several parents is counted once, while
+/// equal cells built separately are counted each time. Allocator overhead is not included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct CellMemoryUsage {
+    /// Cells referenced by a single parent or root.
+    pub unique_cells: usize,
+    /// Cells referenced by several parents or roots.
+    pub shared_cells: usize,
+    /// Bytes of the `Cell` structures and their reference counters.
+    pub cell_bytes: usize,
+    pub data_bytes: usize,
+    pub refs_bytes: usize,
+    /// Bytes of the hashes and depths of all levels.
+    pub hashes_bytes: usize,
+}
+
+impl CellMemoryUsage {
+    pub fn cells(&self) -> usize {
+        self.unique_cells + self.shared_cells
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.cell_bytes + self.data_bytes + self.refs_bytes + self.hashes_bytes
+    }
+}
+
 impl BagOfCells {
     pub fn new(roots: &[ArcCell]) -> BagOfCells {
         BagOfCells {
@@ -54,17 +139,27 @@ impl BagOfCells {
     }
 
     pub fn parse(serial: &[u8]) -> Result<BagOfCells, TonCellError> {
-        let raw = RawBagOfCells::parse(serial)?;
+        Self::from_raw(RawBagOfCells::parse(serial)?)
+    }
+
+    /// Builds the cells of the raw bag of cells.
+    pub fn from_raw(raw: RawBagOfCells) -> Result<BagOfCells, TonCellError> {
+        let RawBagOfCells {
+            cells: mut raw_cells,
+            roots: raw_roots,
+        } = raw;
 
-        let num_cells = raw.cells.len();
+        let num_cells = raw_cells.len();
 
-        let mut cells: Vec<ArcCell> = Vec::new();
-        for i in (0..num_cells).rev() {
-            let raw_cell = &raw.cells[i];
+        // Cells are topologically sorted, so they are built starting from the last one,
+        // consuming the raw cells
+        let mut cells: Vec<ArcCell> = Vec::with_capacity(num_cells);
+        while let Some(raw_cell) = raw_cells.pop() {
+            let i = raw_cells.len();
             let mut cell = Cell {
-                data: raw_cell.data.clone(),
+                data: raw_cell.data,
                 bit_len: raw_cell.bit_len,
-                references: Vec::new(),
+                references: Vec::with_capacity(raw_cell.references.len()),
                 cell_type: raw_cell.cell_type,
                 level_mask: raw_cell.max_level,
                 is_exotic: raw_cell.is_exotic,
@@ -79,6 +174,12 @@ impl BagOfCells {
                         "References to previous cells are not supported",
                     ));
                 }
+                if *r >= num_cells {
+                    return Err(TonCellError::boc_deserialization_error(format!(
+                        "Reference {} is out of bounds",
+                        r
+                    )));
+                }
                 cell.references.push(cells[num_cells - 1 - r].clone());
             }
 
@@ -86,22 +187,64 @@ impl BagOfCells {
             cells.push(Arc::new(cell));
         }
 
-        let roots: Vec<ArcCell> = raw
-            .roots
+        let roots = raw_roots
             .iter()
-            .map(|r| cells[num_cells - 1 - r].clone())
-            .collect();
+            .map(|r| {
+                let cell = num_cells.checked_sub(r + 1).and_then(|i| cells.get(i));
+                cell.cloned().ok_or_else(|| {
+                    TonCellError::boc_deserialization_error(format!("Root {} is out of bounds", r))
+                })
+            })
+            .collect::<Result<Vec<ArcCell>, TonCellError>>()?;
         Ok(BagOfCells { roots })
     }
 
+    /// Builds the index of the cells reachable from the roots by their hash, e.g. to look up
+    /// the cells of a proof referenced by hash without searching the tree each time.
+    pub fn index(&self) -> CellIndex {
+        CellIndex::new(&self.roots)
+    }
+
+    /// Returns the heap memory held by the cells reachable from the roots.
+    pub fn memory_usage(&self) -> CellMemoryUsage {
+        // Number of references to each cell by its allocation
+        let mut ref_counts: HashMap<*const Cell, usize> = HashMap::new();
+        let mut usage = CellMemoryUsage::default();
+        let mut stack: Vec<&ArcCell> = self.roots.iter().collect();
+        while let Some(cell) = stack.pop() {
+            let count = ref_counts.entry(Arc::as_ptr(cell)).or_insert(0);
+            *count += 1;
+            if *count > 1 {
+                continue;
+            }
+            usage.cell_bytes += size_of::<Cell>() + 2 * size_of::<usize>();
+            usage.data_bytes += cell.data.len() + 2 * size_of::<usize>();
+            usage.refs_bytes += cell.references.capacity() * size_of::<ArcCell>();
+            usage.hashes_bytes +=
+                cell.hashes.capacity() * HASH_BYTES + cell.depth.capacity() * size_of::<u16>();
+            stack.extend(cell.references.iter());
+        }
+        usage.shared_cells = ref_counts.values().filter(|count| **count > 1).count();
+        usage.unique_cells = ref_counts.len() - usage.shared_cells;
+        usage
+    }
+
     pub fn parse_hex(hex: &str) -> Result<BagOfCells, TonCellError> {
         let str: String = hex.chars().filter(|c| !c.is_whitespace()).collect();
         let bin = hex::decode(str.as_str()).map_boc_deserialization_error()?;
         Self::parse(&bin)
     }
 
+    /// Parses a bag of cells encoded with the standard or the URL-safe base64 alphabet, with
+    /// or without padding. Whitespace is ignored.
     pub fn parse_base64(base64: &str) -> Result<BagOfCells, TonCellError> {
-        let bin = STANDARD.decode(base64).map_boc_deserialization_error()?;
+        let str: String = base64.chars().filter(|c| !c.is_whitespace()).collect();
+        let bin = if str.contains(['-', '_']) {
+            URL_SAFE_TOLERANT.decode(str.as_str())
+        } else {
+            STANDARD_TOLERANT.decode(str.as_str())
+        }
+        .map_boc_deserialization_error()?;
         Self::parse(&bin)
     }
 
@@ -110,6 +253,16 @@ impl BagOfCells {
         raw.serialize(has_crc32)
     }
 
+    /// Serializes the bag of cells as padded standard base64.
+    pub fn serialize_base64(&self, has_crc32: bool) -> Result<String, TonCellError> {
+        Ok(STANDARD.encode(self.serialize(has_crc32)?))
+    }
+
+    /// Serializes the bag of cells as padded URL-safe base64.
+    pub fn serialize_base64_urlsafe(&self, has_crc32: bool) -> Result<String, TonCellError> {
+        Ok(URL_SAFE.encode(self.serialize(has_crc32)?))
+    }
+
     /// Traverses all cells, fills all_cells set and inbound references map.
     fn traverse_cell_tree(
         cell: &ArcCell,
@@ -141,8 +294,9 @@ impl BagOfCells {
         Ok(())
     }
 
-    /// Constructs raw representation of BagOfCells
-    pub(crate) fn to_raw(&self) -> Result<RawBagOfCells, TonCellError> {
+    /// Constructs raw representation of BagOfCells, with the cells sorted topologically but
+    /// otherwise in unspecified order.
+    pub fn to_raw(&self) -> Result<RawBagOfCells, TonCellError> {
         let mut all_cells: HashSet<ArcCell> = HashSet::new();
         let mut in_refs: HashMap<ArcCell, HashSet<ArcCell>> = HashMap::new();
         for r in &self.roots {
@@ -206,18 +360,52 @@ impl BagOfCells {
 #[cfg(test)]
 mod tests {
     use std::any::Any;
+    use std::collections::HashSet;
     use std::hash::Hash;
+    use std::mem::size_of;
     use std::sync::Arc;
     use std::time::Instant;
 
     use log::debug;
+    use num_bigint::BigUint;
 
-    use crate::cell::{BagOfCells, Cell, CellBuilder, TonCellError};
-    use crate::message::ZERO_COINS;
-    use crate::responses::ConfigParam;
+    use crate::cell::{
+        ArcCell, BagOfCells, BlockSelector, Cell, CellBuilder, ParseMode, TonCellError, HASH_BYTES,
+    };
+    use crate::coins::Coins;
+    use crate::responses::{
+        ComputeSkipReason, ConfigParam, InMsg, OutMsg, TrComputePhase, TransactionDescr,
+    };
 
     use super::raw::CellType;
 
+    #[test]
+    fn parse_base64_accepts_all_variants() -> anyhow::Result<()> {
+        // 0xff makes the encoding differ between the alphabets
+        let cell = CellBuilder::new()
+            .store_u32(32, 0xffff_fffe)?
+            .store_u8(5, 0x1f)?
+            .build()?;
+        let boc = BagOfCells::from_root(cell);
+        let standard = boc.serialize_base64(true)?;
+        let url_safe = boc.serialize_base64_urlsafe(true)?;
+        assert_ne!(standard, url_safe);
+        assert!(url_safe.contains(['-', '_']));
+        for encoded in [
+            standard.clone(),
+            url_safe.clone(),
+            standard.trim_end_matches('=').to_string(),
+            url_safe.trim_end_matches('=').to_string(),
+            format!("{}\n{}", &standard[..8], &standard[8..]),
+        ] {
+            let parsed = BagOfCells::parse_base64(&encoded)?;
+            assert_eq!(parsed.single_root()?.data, boc.single_root()?.data);
+            assert_eq!(parsed.single_root()?.bit_len, 37);
+        }
+        assert!(BagOfCells::parse_base64("not base64!").is_err());
+        Ok(())
+    }
+
     #[test]
     fn cell_repr_works() -> anyhow::Result<()> {
         let hole_address = "EQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAM9c".parse()?;
@@ -317,11 +505,11 @@ mod tests {
         let account_lp_code = cell.reference(2)?;
 
         let protocol_fee = CellBuilder::new()
-            .store_coins(&ZERO_COINS)?
-            .store_coins(&ZERO_COINS)?
+            .store_coins(Coins::zero())?
+            .store_coins(Coins::zero())?
             .store_raw_address(&hole_address)?
-            .store_coins(&ZERO_COINS)?
-            .store_coins(&ZERO_COINS)?
+            .store_coins(Coins::zero())?
+            .store_coins(Coins::zero())?
             .build()?;
 
         let data = CellBuilder::new()
@@ -331,7 +519,7 @@ mod tests {
             .store_u8(4, 1)?
             .store_address(&token0)?
             .store_address(&token1)?
-            .store_coins(&ZERO_COINS)?
+            .store_coins(Coins::zero())?
             .store_reference(&Arc::new(protocol_fee))?
             .store_reference(jetton_wallet_code_lp)?
             .store_reference(account_lp_code)?
@@ -417,6 +605,36 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn memory_usage_counts_shared_cells_once() -> anyhow::Result<()> {
+        let leaf = Arc::new(CellBuilder::new().store_u64(64, 1)?.build()?);
+        let child = Arc::new(
+            CellBuilder::new()
+                .store_u8(8, 2)?
+                .store_reference(&leaf)?
+                .build()?,
+        );
+        let root = CellBuilder::new()
+            .store_reference(&child)?
+            .store_reference(&child)?
+            .store_reference(&leaf)?
+            .build()?;
+        let boc = BagOfCells::from_root(root);
+        let usage = boc.memory_usage();
+        assert_eq!(usage.unique_cells, 1);
+        assert_eq!(usage.shared_cells, 2);
+        assert_eq!(usage.cells(), 3);
+        assert!(usage.data_bytes >= 9);
+        assert_eq!(usage.refs_bytes % size_of::<Arc<Cell>>(), 0);
+        assert!(usage.refs_bytes >= 4 * size_of::<Arc<Cell>>());
+
+        let parsed = BagOfCells::parse(&boc.serialize(true)?)?;
+        let parsed_usage = parsed.memory_usage();
+        assert_eq!(parsed_usage.cells(), 3);
+        assert_eq!(parsed_usage.shared_cells, 2);
+        Ok(())
+    }
+
     #[test]
     fn test_load_block_header() {
         let masterchain_header_proof_boc = "b5ee9c72010209010001fa000946039ddaab41982d2e6be398d3e7158a9ee07205c7b206c0442d258c5dbb3592000c001601241011ef55aaffffff110203040501a09bc7a987000000000401024e4a500000000100ffffffff000000000000000066805df800002b20d591098000002b20d5910984bc2fe05c0008daa0024e4a4d024e4661c400000007000000000000002e0628480101ddbdb2817a252de1b6e44850b34c08e4121673dda988058e65e87a5b281436e400032a8a04b73841c55d8e0174f95752ebe40157e2dd9fc5b42e9d13576e536f5337298ca35490b4b56d1d71c8c2da191478268c41c20c8f5b2af9a4c996277acda31263ab016f016f070828480101b8b0525b528faf40f34377139c6f02e60d19f48fa4965de519ba3b23e69e82910007009800002b20d581c744024e4a4f1cce98662f39f0643cec6a83d32ecd618a49248c983db8e1edb11d70f598593d0faa26f78f8a1fced3eadbb0da1a97d305a9a874448070bf4cf2b8f98e0567cc688c0103b73841c55d8e0174f95752ebe40157e2dd9fc5b42e9d13576e536f5337298ca3a28ac6d523e24f6ac5494757f3199b30dc37b26a233e6ae63b9fc84b6333b866016f0014688c01035490b4b56d1d71c8c2da191478268c41c20c8f5b2af9a4c996277acda31263ab488f3ec970d964f5f5126831edec8def046644fc9206e544a43da4f7714e0796016f0014";
@@ -426,16 +644,159 @@ mod tests {
         assert_eq!(block.info.is_some(), true);
         assert_eq!(block.info.unwrap().gen_utime, 1719688696u32);
         assert_eq!(block.extra.is_none(), true);
+
+        let info = root.reference(0).unwrap().load_block_header_only().unwrap();
+        assert_eq!(info.gen_utime, 1719688696u32);
+        assert_eq!(info.seq_no, 0x024e4a50);
+        assert_eq!(info.shard.workchain_id, -1);
+        assert!(info.master_ref.is_none());
+        let prev = info.prev_ref.first_prev.unwrap();
+        assert_eq!(prev.seqno, info.seq_no - 1);
+        assert!(info.start_lt < info.end_lt);
+        assert!(root.load_block_header_only().is_err());
     }
 
+    const TRANSACTION_BOC: &str = "b5ee9c7201020a010002800003b5710c3760b686d87bef1f5c5a25e87201a27ef8f5f8805c62ef43700b5a7f6f89c00002aabe17f71c1261bcd503ea556b967295eeaa3d2935ddf3a8e268b87b0349f701490a360c9db00002aabe0113bc16660c34000034641b0de80102030201e004050082726303c5d7b1bc0da5acf09ab3b9cfdffb55ea0ec7f6929c09a76a49932263d1b92e977b92eb9d78b2494efa376962706b566f3b92ab7eea53e12ebdaf034cc0c3020f0c470618a1860440080901e188002186ec16d0db0f7de3eb8b44bd0e40344fdf1ebf100b8c5de86e016b4fedf138034329ed2412425c96cbcb1d44b4bfcb96b693ecf9fa4fac12b64fc913ebae528091837d8e3fd367b28676505f89fbb2bc58f8c32130d9fcba920680a7a24798514d4d18bb33061b6800000018001c060101df0700a062002d40675afa88251845b411ed5e2910e0e15892dea75b0ff286dbcba225cece54a1dcd65000000000000000000000000000000000000036363565623039393662393265643564633736303731353600e968002186ec16d0db0f7de3eb8b44bd0e40344fdf1ebf100b8c5de86e016b4fedf1390016a033ad7d44128c22da08f6af14887070ac496f53ad87f9436de5d112e7672a50ee6b28000608235a00005557c2fee384ccc18680000000001b1b1ab2b1181c9c9b311c9932b21ab2319b9b181b989a9b40009d419d8313880000000000000000110000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000020006fc9830d404c08234c0000000000020000000000028e07461aec104405e30a0eb4866ac725676188a0dfe539c310058492e5ece42040501d0c";
+
     #[test]
     fn test_load_transaction() {
-        let tx_boc = "b5ee9c7201020a010002800003b5710c3760b686d87bef1f5c5a25e87201a27ef8f5f8805c62ef43700b5a7f6f89c00002aabe17f71c1261bcd503ea556b967295eeaa3d2935ddf3a8e268b87b0349f701490a360c9db00002aabe0113bc16660c34000034641b0de80102030201e004050082726303c5d7b1bc0da5acf09ab3b9cfdffb55ea0ec7f6929c09a76a49932263d1b92e977b92eb9d78b2494efa376962706b566f3b92ab7eea53e12ebdaf034cc0c3020f0c470618a1860440080901e188002186ec16d0db0f7de3eb8b44bd0e40344fdf1ebf100b8c5de86e016b4fedf138034329ed2412425c96cbcb1d44b4bfcb96b693ecf9fa4fac12b64fc913ebae528091837d8e3fd367b28676505f89fbb2bc58f8c32130d9fcba920680a7a24798514d4d18bb33061b6800000018001c060101df0700a062002d40675afa88251845b411ed5e2910e0e15892dea75b0ff286dbcba225cece54a1dcd65000000000000000000000000000000000000036363565623039393662393265643564633736303731353600e968002186ec16d0db0f7de3eb8b44bd0e40344fdf1ebf100b8c5de86e016b4fedf1390016a033ad7d44128c22da08f6af14887070ac496f53ad87f9436de5d112e7672a50ee6b28000608235a00005557c2fee384ccc18680000000001b1b1ab2b1181c9c9b311c9932b21ab2319b9b181b989a9b40009d419d8313880000000000000000110000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000020006fc9830d404c08234c0000000000020000000000028e07461aec104405e30a0eb4866ac725676188a0dfe539c310058492e5ece42040501d0c";
+        let tx_boc = TRANSACTION_BOC;
         let cells = BagOfCells::parse_hex(tx_boc).unwrap();
         let root = cells.single_root().unwrap();
         let ref_index = &mut 0;
         let result = Cell::load_transaction(&root, ref_index, &mut root.parser()).unwrap();
         println!("result: {:?}", result);
+
+        let descr = result.description.unwrap();
+        assert!(descr.is_success());
+        assert_eq!(
+            descr.storage_phase().unwrap().storage_fees_collected.value,
+            BigUint::from(28u32)
+        );
+        let compute = descr.compute_phase().unwrap();
+        assert_eq!(compute.exit_code(), Some(0));
+        assert_eq!(compute.gas_used(), Some(&BigUint::from(3308u32)));
+        if let TrComputePhase::Vm(vm) = compute {
+            assert_eq!(vm.gas_fees.value, BigUint::from(1323200u32));
+            let details = vm.vm_details.as_ref().unwrap();
+            assert_eq!(
+                details.gas_credit.as_ref().unwrap().value,
+                BigUint::from(10000u32)
+            );
+            assert_eq!(details.vm_steps, 68);
+        } else {
+            panic!("expected a vm compute phase");
+        }
+        let action = descr.action_phase().unwrap();
+        assert_eq!(action.result_code, 0);
+        assert_eq!(action.tot_actions, 1);
+        assert_eq!(action.msgs_created, 1);
+        assert_eq!(
+            action.total_fwd_fees.as_ref().unwrap().value,
+            BigUint::from(400000u32)
+        );
+    }
+
+    #[test]
+    fn test_load_transaction_with_mode() -> anyhow::Result<()> {
+        let cells = BagOfCells::parse_hex(TRANSACTION_BOC)?;
+        let root = cells.single_root()?;
+        let parsed = root.load_transaction_with_mode(ParseMode::Strict)?;
+        assert!(parsed.is_complete());
+        assert!(parsed.value.description.is_some());
+
+        // Description with an unknown tag
+        let mut references = root.references.clone();
+        let mut descr = CellBuilder::new().store_u8(4, 0b1111)?.build()?;
+        descr.finalize()?;
+        references[2] = descr.to_arc();
+        let mut unknown_descr = CellBuilder::new()
+            .store_bits(root.bit_len, &root.data)?
+            .store_references(&references)?
+            .build()?;
+        unknown_descr.finalize()?;
+        assert!(unknown_descr
+            .load_transaction_with_mode(ParseMode::Strict)
+            .is_err());
+        let parsed = unknown_descr.load_transaction_with_mode(ParseMode::Lenient)?;
+        assert!(parsed.value.description.is_none());
+        assert!(parsed.value.state_update.is_some());
+        assert_eq!(parsed.warnings.len(), 1);
+        assert_eq!(parsed.warnings[0].structure, "TransactionDescr");
+
+        let mut trailing_data = CellBuilder::new()
+            .store_bits(root.bit_len, &root.data)?
+            .store_bit(true)?
+            .store_references(&root.references)?
+            .build()?;
+        trailing_data.finalize()?;
+        assert!(trailing_data
+            .load_transaction_with_mode(ParseMode::Strict)
+            .is_err());
+        let parsed = trailing_data.load_transaction_with_mode(ParseMode::Lenient)?;
+        assert!(parsed.value.description.is_some());
+        assert_eq!(parsed.warnings[0].structure, "Transaction");
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_transaction_descr_tick_tock() -> anyhow::Result<()> {
+        let descr_cell = CellBuilder::new()
+            .store_u8(4, 0b0011)? // trans_tick_tock$001 is_tock:true
+            .store_coins(BigUint::from(1000u32))? // storage_fees_collected
+            .store_bit(false)? // storage_fees_due
+            .store_bit(false)? // status_change: acst_unchanged
+            .store_bit(false)? // tr_phase_compute_skipped
+            .store_u8(2, 0b10)? // cskip_no_gas
+            .store_bit(false)? // action
+            .store_bit(true)? // aborted
+            .store_bit(false)? // destroyed
+            .build()?;
+        let descr = Cell::load_transaction_descr(&descr_cell, &mut 0, &mut descr_cell.parser())?;
+        match &descr {
+            TransactionDescr::TickTock(tick_tock) => {
+                assert!(tick_tock.is_tock);
+                assert_eq!(
+                    tick_tock.storage_ph.storage_fees_collected.value,
+                    BigUint::from(1000u32)
+                );
+                assert!(matches!(
+                    tick_tock.compute_ph,
+                    TrComputePhase::Skipped(ComputeSkipReason::NoGas)
+                ));
+                assert!(tick_tock.action.data.is_none());
+            }
+            _ => panic!("expected a tick-tock transaction description"),
+        }
+        assert!(descr.aborted());
+        assert!(!descr.is_success());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_transaction_descr_merge_prepare() -> anyhow::Result<()> {
+        let descr_cell = CellBuilder::new()
+            .store_u8(4, 0b0110)? // trans_merge_prepare$0110
+            .store_u8(6, 4)? // cur_shard_pfx_len
+            .store_u8(6, 0)? // acc_split_depth
+            .store_slice(&[1u8; 32])? // this_addr
+            .store_slice(&[2u8; 32])? // sibling_addr
+            .store_coins(BigUint::from(0u32))? // storage_fees_collected
+            .store_bit(false)? // storage_fees_due
+            .store_bit(false)? // status_change: acst_unchanged
+            .store_bit(false)? // aborted
+            .build()?;
+        let descr = Cell::load_transaction_descr(&descr_cell, &mut 0, &mut descr_cell.parser())?;
+        match descr {
+            TransactionDescr::MergePrepare(merge_prepare) => {
+                assert_eq!(merge_prepare.split_info.cur_shard_pfx_len, 4);
+                assert_eq!(merge_prepare.split_info.this_addr, vec![1u8; 32]);
+                assert_eq!(merge_prepare.split_info.sibling_addr, vec![2u8; 32]);
+                assert!(!merge_prepare.aborted);
+            }
+            _ => panic!("expected a merge-prepare transaction description"),
+        }
+        Ok(())
     }
 
     #[test]
@@ -467,12 +828,68 @@ mod tests {
         }
     }
 
+    #[test]
+    fn cell_by_hash_works() -> anyhow::Result<()> {
+        let cells = BagOfCells::parse_hex(SHARD_BLOCK_PROOF_BOC)?;
+        let root = cells.single_root()?;
+        let block = root.reference(0)?;
+        assert!(block.level_mask > 0);
+        let index = cells.index();
+        assert!(Arc::ptr_eq(
+            index.cell_by_hash(&block.hash_array(0)).unwrap(),
+            block
+        ));
+        assert!(index.cell_by_hash(&block.cell_hash_array()?).is_none());
+        // Pruned branches are not indexed
+        let pruned = block.reference(0)?;
+        assert_eq!(pruned.cell_type, CellType::PrunnedBranchCell as u8);
+        assert!(index.cell_by_hash(&pruned.hash_array(0)).is_none());
+
+        // Bags made from roots are indexed as well
+        let boc = BagOfCells::new(std::slice::from_ref(&root));
+        assert_eq!(boc, cells);
+        assert_eq!(boc.index().len(), index.len());
+        assert!(boc.index().cell_by_hash(&block.hash_array(0)).is_some());
+        Ok(())
+    }
+
+    // got from liteServer_getShardBlockProof, the first link.proof[0] value
+    // block proof of: https://tonviewer.com/block/(0,2000000000000000,43884169)
+    const SHARD_BLOCK_PROOF_BOC: &str = "b5ee9c720102140100027a000946039b12663e48d24513dbf303d547e6e6e10b2d637cd11600e78420bf0aed1aad2d001601241011ef55aaffffff110203040528480101a61819c2bcd15aa09958881e6cca7f84ca04aa7d118b237c96519a09446d7b8f00012848010138bf381cf9b9df9f314f1b4dc74127d398814111003bf484502ff419c414a40a0003284801015728228d3059dc5da4c5c0f6a54f8cb0d5a3d15a289d3ea9f4a8810f366be367001524894a33f6fd21a7dee602fb675d6f1144f565e364ae3db6bada5187e98275f39be72b9577bb247071c3e769233d398adc88fdedb2dba7208da38330c4cc0d0a6af3bac2a8a7c00607080928480101b43ef3d7c8e55e1f1510c1855e91323fb3521e63f851300cb3f214261be602eb0004000102284801010f8995cdfdd409bb3d03426397ec1ffc59a4931674ab7b66928dbcfe80b030b600062319cca56a03355600fa49502f90040a0b0c2103d0400d28480101f9ab2fd8292b1eda69d5a9ff670a50bc453989f2c3f746934941e7af5c26ec6a0003210150132201c00e0f2201c010112848010195f2c2c66de2c0ab069b146a5d965907a1cb41e627fb83e6517914dd3ec61e5e000301db5014ecf450123906980001555f0c75a0000001555f0c75a1faea90ae26eaf16e1f45832019c98189ae6b94b62bbf1be8f870bc0e6f37dcb14baf84c282d346327a7fc7b6a54deb707ced7b13a97c939954871045b78e7a51f0000045f8d900000000000000001239068b33061a3a1228480101f4f492b7fed135c3515e810152abc0b0473d4a98f405e79c210b2e21b54ced4100010013468c16d6020ee6b2802028480101c8e6b152e6d84bd2e285d365b1e282838d323e0d6ec730175937d6e4a5de0a2e0003";
+
+    #[test]
+    fn cached_hashes_match_computed_hashes() -> anyhow::Result<()> {
+        let cells = BagOfCells::parse_hex(SHARD_BLOCK_PROOF_BOC)?;
+        let mut stack = vec![cells.single_root()?.clone()];
+        while let Some(cell) = stack.pop() {
+            // Hashes calculated from the representation are only valid for ordinary trees
+            if !cell.is_exotic && cell.references.iter().all(|r| r.level_mask == 0) {
+                let mut uncached = cell.as_ref().clone();
+                uncached.hashes.clear();
+                assert_eq!(cell.cell_hash_array()?, uncached.cell_hash_array()?);
+                assert_eq!(cell.hash_ref(0), &uncached.cell_hash_array()?);
+            }
+            for level in 0..=3 {
+                assert_eq!(cell.hash_array(level).to_vec(), cell.get_hash(level));
+            }
+            stack.extend(cell.references.iter().cloned());
+        }
+
+        // Cells changed after deserialization are hashed from their new contents once the
+        // cached hashes are cleared
+        let cells = BagOfCells::parse(&BagOfCells::from_root(Cell::default()).serialize(false)?)?;
+        let mut changed = cells.single_root()?.as_ref().clone();
+        changed.data = Arc::from([0x80u8].as_slice());
+        changed.bit_len = 1;
+        changed.hashes.clear();
+        let expected = CellBuilder::new().store_bit(true)?.build()?;
+        assert_eq!(changed.cell_hash_array()?, expected.cell_hash_array()?);
+        Ok(())
+    }
+
     #[test]
     fn test_load_shard_block_link_master_proof() {
-        // got from liteServer_getShardBlockProof, the first link.proof[0] value
-        // block proof of: https://tonviewer.com/block/(0,2000000000000000,43884169)
-        let boc = "b5ee9c720102140100027a000946039b12663e48d24513dbf303d547e6e6e10b2d637cd11600e78420bf0aed1aad2d001601241011ef55aaffffff110203040528480101a61819c2bcd15aa09958881e6cca7f84ca04aa7d118b237c96519a09446d7b8f00012848010138bf381cf9b9df9f314f1b4dc74127d398814111003bf484502ff419c414a40a0003284801015728228d3059dc5da4c5c0f6a54f8cb0d5a3d15a289d3ea9f4a8810f366be367001524894a33f6fd21a7dee602fb675d6f1144f565e364ae3db6bada5187e98275f39be72b9577bb247071c3e769233d398adc88fdedb2dba7208da38330c4cc0d0a6af3bac2a8a7c00607080928480101b43ef3d7c8e55e1f1510c1855e91323fb3521e63f851300cb3f214261be602eb0004000102284801010f8995cdfdd409bb3d03426397ec1ffc59a4931674ab7b66928dbcfe80b030b600062319cca56a03355600fa49502f90040a0b0c2103d0400d28480101f9ab2fd8292b1eda69d5a9ff670a50bc453989f2c3f746934941e7af5c26ec6a0003210150132201c00e0f2201c010112848010195f2c2c66de2c0ab069b146a5d965907a1cb41e627fb83e6517914dd3ec61e5e000301db5014ecf450123906980001555f0c75a0000001555f0c75a1faea90ae26eaf16e1f45832019c98189ae6b94b62bbf1be8f870bc0e6f37dcb14baf84c282d346327a7fc7b6a54deb707ced7b13a97c939954871045b78e7a51f0000045f8d900000000000000001239068b33061a3a1228480101f4f492b7fed135c3515e810152abc0b0473d4a98f405e79c210b2e21b54ced4100010013468c16d6020ee6b2802028480101c8e6b152e6d84bd2e285d365b1e282838d323e0d6ec730175937d6e4a5de0a2e0003";
-        let cells = BagOfCells::parse_hex(boc).unwrap();
+        let cells = BagOfCells::parse_hex(SHARD_BLOCK_PROOF_BOC).unwrap();
         let first_root = cells.single_root().unwrap();
         // println!("cell: {:?}", cell);
         let hash = first_root.reference(0).unwrap().get_hash(0);
@@ -579,6 +996,251 @@ mod tests {
         Ok(())
     }
 
+    /// Returns the tree with the cell of the hash replaced, rebuilding the ordinary cells on
+    /// the path to it, or `None` if the tree doesn't contain the cell.
+    fn replace_cell(
+        cell: &ArcCell,
+        hash: &[u8; HASH_BYTES],
+        new: &ArcCell,
+    ) -> anyhow::Result<Option<ArcCell>> {
+        if cell.hash_ref(0) == hash {
+            return Ok(Some(new.clone()));
+        }
+        let mut references = cell.references.clone();
+        let mut replaced = false;
+        for reference in references.iter_mut() {
+            if let Some(rebuilt) = replace_cell(reference, hash, new)? {
+                *reference = rebuilt;
+                replaced = true;
+            }
+        }
+        if !replaced {
+            return Ok(None);
+        }
+        assert!(!cell.is_exotic);
+        let mut rebuilt = CellBuilder::new()
+            .store_bits(cell.bit_len, &cell.data)?
+            .store_references(&references)?
+            .build()?;
+        rebuilt.finalize()?;
+        Ok(Some(rebuilt.to_arc()))
+    }
+
+    #[test]
+    fn test_load_block_with_mode_unknown_transaction_descr() -> anyhow::Result<()> {
+        let cells = BagOfCells::parse_hex(include_str!("../../resources/bloc/block_extra.hex"))?;
+        let root = cells.single_root()?;
+        let block = root.load_block()?;
+        let account_blocks = block.extra.unwrap().account_blocks.unwrap();
+        let transactions = account_blocks
+            .values()
+            .map(|account_block| account_block.transactions.len())
+            .sum::<usize>();
+        let tx = account_blocks
+            .values()
+            .flat_map(|account_block| account_block.transactions.values())
+            .find_map(|tx| tx.data.as_ref())
+            .unwrap();
+        let index = cells.index();
+        let tx_cell = index.cell_by_hash(tx.hash.as_slice().try_into()?).unwrap();
+
+        // Description of the transaction with an unknown tag
+        let mut descr = CellBuilder::new().store_u8(4, 0b1111)?.build()?;
+        descr.finalize()?;
+        let descr_hash = tx_cell.reference(2)?.hash_array(0);
+        let unknown_descr = replace_cell(root, &descr_hash, &descr.to_arc())?.unwrap();
+
+        assert!(unknown_descr
+            .load_block_with_mode(ParseMode::Strict)
+            .is_err());
+        let parsed = unknown_descr.load_block_with_mode(ParseMode::Lenient)?;
+        assert_eq!(parsed.warnings.len(), 1);
+        assert_eq!(parsed.warnings[0].structure, "TransactionDescr");
+        // Only the description is skipped, the rest of the block extra is loaded
+        let account_blocks = parsed.value.extra.unwrap().account_blocks.unwrap();
+        let mut loaded = Vec::new();
+        for account_block in account_blocks.values() {
+            for tx in account_block.transactions.values() {
+                loaded.push(tx.data.as_ref().unwrap());
+            }
+        }
+        assert_eq!(loaded.len(), transactions);
+        let skipped = loaded
+            .iter()
+            .filter(|loaded| loaded.description.is_none())
+            .collect::<Vec<_>>();
+        assert_eq!(skipped.len(), 1);
+        assert_eq!(skipped[0].lt, tx.lt);
+        assert_eq!(skipped[0].account_addr, tx.account_addr);
+        Ok(())
+    }
+
+    #[test]
+    fn transactions_of_account_blocks_are_verified() -> anyhow::Result<()> {
+        let cells = BagOfCells::parse_hex(include_str!("../../resources/bloc/block_extra.hex"))?;
+        let block = cells.single_root()?.load_block()?;
+        let parsed = cells
+            .single_root()?
+            .load_block_with_mode(ParseMode::Strict)?;
+        assert!(parsed.is_complete());
+        assert!(parsed.value.extra.is_some());
+        let account_blocks = block.extra.unwrap().account_blocks.unwrap();
+        let index = cells.index();
+        let mut transactions = 0;
+        for account_block in account_blocks.values() {
+            for tx in account_block.transactions.values() {
+                let data = tx.data.as_ref().unwrap();
+                assert!(tx.verified);
+                let hash = data.hash.as_slice().try_into()?;
+                let cell = index.cell_by_hash(hash).unwrap();
+                assert!(Cell::verify_transaction(cell, data));
+                let mut drifted = data.clone();
+                drifted.outmsg_cnt += 1;
+                assert!(!Cell::verify_transaction(cell, &drifted));
+                // Fields of the referenced cells are verified too
+                let mut drifted = data.clone();
+                drifted.state_update.as_mut().unwrap().new_hash[0] ^= 1;
+                assert!(!Cell::verify_transaction(cell, &drifted));
+                let mut drifted = data.clone();
+                match drifted.description.as_mut().unwrap() {
+                    TransactionDescr::Ordinary(descr) => descr.aborted = !descr.aborted,
+                    TransactionDescr::TickTock(descr) => descr.aborted = !descr.aborted,
+                    _ => unreachable!(),
+                }
+                assert!(!Cell::verify_transaction(cell, &drifted));
+                // Cells without calculated hashes can't be verified
+                let built = CellBuilder::new().store_cell(cell)?.build()?;
+                assert!(!Cell::verify_transaction(&built, data));
+                transactions += 1;
+            }
+        }
+        assert!(transactions > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_with_mode_trailing_data_in_reference() -> anyhow::Result<()> {
+        let cells = BagOfCells::parse_hex(TRANSACTION_BOC)?;
+        let root = cells.single_root()?;
+
+        // Hash update followed by a bit it doesn't have
+        let state_update = root.reference(1)?;
+        let mut references = root.references.clone();
+        let mut padded = CellBuilder::new()
+            .store_cell(state_update)?
+            .store_bit(true)?
+            .build()?;
+        padded.finalize()?;
+        references[1] = padded.to_arc();
+        let mut tx = CellBuilder::new()
+            .store_bits(root.bit_len, &root.data)?
+            .store_references(&references)?
+            .build()?;
+        tx.finalize()?;
+
+        assert!(tx.load_transaction_with_mode(ParseMode::Strict).is_err());
+        let parsed = tx.load_transaction_with_mode(ParseMode::Lenient)?;
+        assert_eq!(parsed.warnings.len(), 1);
+        assert_eq!(parsed.warnings[0].structure, "HashUpdate");
+        assert!(parsed.value.state_update.is_some());
+        // The loaders without a mode ignore trailing data
+        let loaded = Cell::load_transaction(&tx, &mut 0, &mut tx.parser())?;
+        assert!(loaded.state_update.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_block_msg_descrs() -> anyhow::Result<()> {
+        let cells = BagOfCells::parse_hex(include_str!("../../resources/bloc/block_extra.hex"))?;
+        let extra = cells.single_root()?.load_block()?.extra.unwrap();
+        let loaded = extra
+            .account_blocks
+            .unwrap()
+            .values()
+            .flat_map(|account_block| account_block.transactions.values())
+            .map(|tx| tx.data.clone().unwrap())
+            .collect::<Vec<_>>();
+        let transactions = loaded
+            .iter()
+            .map(|tx| tx.hash.clone())
+            .collect::<HashSet<_>>();
+
+        let in_msg_descr = extra.in_msg_descr.unwrap();
+        assert!(!in_msg_descr.is_empty());
+        for (hash, in_msg) in &in_msg_descr {
+            match &in_msg.value {
+                InMsg::ImportExt { msg, transaction } => {
+                    assert_eq!(hex::encode(&msg.as_ref().unwrap().hash), *hash);
+                    assert!(transactions.contains(transaction));
+                }
+                InMsg::ImportFin { transaction, .. }
+                | InMsg::ImportImm { transaction, .. }
+                | InMsg::ImportDeferredFin { transaction, .. } => {
+                    assert!(transactions.contains(transaction));
+                }
+                _ => {}
+            }
+        }
+        // Messages created by the transactions are exported by the block
+        let out_msg_descr = extra.out_msg_descr.unwrap();
+        for tx in &loaded {
+            for out_msg in tx.out_msgs.values() {
+                let hash = hex::encode(&out_msg.data.as_ref().unwrap().hash);
+                assert!(out_msg_descr.contains_key(&hash));
+            }
+        }
+        for out_msg in out_msg_descr.values() {
+            match &out_msg.value {
+                OutMsg::ExportExt { transaction, .. }
+                | OutMsg::ExportNew { transaction, .. }
+                | OutMsg::ExportImm { transaction, .. }
+                | OutMsg::ExportNewDefer { transaction, .. } => {
+                    assert!(transactions.contains(transaction));
+                }
+                _ => {}
+            }
+        }
+
+        // Dequeued message with its short form, of a 4 bits tag
+        let msg_env_hash = [7u8; 32];
+        let deq_short = CellBuilder::new()
+            .store_u8(4, 0b1101)?
+            .store_slice(&msg_env_hash)?
+            .store_i32(32, -1)?
+            .store_u64(64, 1 << 63)?
+            .store_u64(64, 42)?
+            .build()?;
+        let out_msg = Cell::load_out_msg(&deq_short, &mut 0, &mut deq_short.parser())?;
+        match out_msg {
+            OutMsg::ExportDeqShort {
+                msg_env_hash: hash,
+                next_workchain,
+                next_addr_pfx,
+                import_block_lt,
+            } => {
+                assert_eq!(hash, msg_env_hash);
+                assert_eq!(next_workchain, -1);
+                assert_eq!(next_addr_pfx, 1 << 63);
+                assert_eq!(import_block_lt, 42);
+            }
+            other => panic!("unexpected out msg: {:?}", other),
+        }
+
+        let selector = BlockSelector {
+            msg_descrs: false,
+            ..BlockSelector::all()
+        };
+        let extra = cells
+            .single_root()?
+            .load_block_with(&selector)?
+            .extra
+            .unwrap();
+        assert!(extra.in_msg_descr.is_none());
+        assert!(extra.out_msg_descr.is_none());
+        assert!(extra.account_blocks.is_some());
+        Ok(())
+    }
+
     #[test]
     fn test_load_block_large() -> anyhow::Result<()> {
         // got this from: liteServer_getBlock using ton-lite-client typescript: https://github.com/ton-core/ton-lite-client
@@ -606,6 +1268,21 @@ mod tests {
         let cell_type = first_root.get_bits_descriptor();
         println!("cell type: {:?}", cell_type);
         let block_data = first_root.load_block().unwrap();
+        let value_flow = block_data.value_flow.unwrap();
+        assert_eq!(
+            value_flow.fees_collected.grams.value,
+            BigUint::from(3568385998u64)
+        );
+        assert_eq!(
+            value_flow.burned.unwrap().grams.value,
+            BigUint::from(868385997u64)
+        );
+        assert_eq!(value_flow.created.grams.value, BigUint::from(1700000000u64));
+        assert_eq!(
+            value_flow.to_next_blk.grams.value - value_flow.from_prev_blk.grams.value,
+            value_flow.fees_collected.grams.value
+        );
+        assert_eq!(value_flow.from_prev_blk.other.len(), 2);
         let block_extra = block_data.extra.unwrap();
         let param = block_extra
             .custom
@@ -645,4 +1322,56 @@ mod tests {
         // println!("{:?}", block_extra.custom.shards);
         Ok(())
     }
+
+    #[test]
+    fn test_load_block_with_selector() -> anyhow::Result<()> {
+        let cells = BagOfCells::parse_hex(include_str!("../../resources/bloc/block_extra.hex"))?;
+        let root = cells.single_root()?;
+        let block = root.load_block()?;
+        let extra = block.extra.as_ref().unwrap();
+
+        let info_only = root.load_block_with(&BlockSelector::info_only())?;
+        assert_eq!(
+            info_only.info.as_ref().unwrap().seq_no,
+            block.info.as_ref().unwrap().seq_no
+        );
+        assert!(info_only.value_flow.is_none());
+        assert!(info_only.state_update.is_none());
+        assert!(info_only.extra.is_none());
+
+        let without_accounts =
+            root.load_block_with(&BlockSelector::extra_without_account_blocks())?;
+        let custom = &without_accounts.extra.as_ref().unwrap().custom;
+        assert!(without_accounts
+            .extra
+            .as_ref()
+            .unwrap()
+            .account_blocks
+            .is_none());
+        assert_eq!(custom.shards.len(), extra.custom.shards.len());
+        assert_eq!(
+            custom.prev_blk_signatures.len(),
+            extra.custom.prev_blk_signatures.len()
+        );
+
+        let config_only = root.load_block_with(&BlockSelector::config_only())?;
+        assert!(config_only.info.is_none());
+        let custom = &config_only.extra.as_ref().unwrap().custom;
+        assert!(custom.shards.is_empty());
+        assert_eq!(
+            custom.config.cells().len(),
+            extra.custom.config.cells().len()
+        );
+        assert_eq!(
+            custom.config.to_cell()?.cell_hash(),
+            extra.custom.config.to_cell()?.cell_hash()
+        );
+
+        let all = root.load_block_with(&BlockSelector::all())?;
+        assert_eq!(
+            all.extra.unwrap().account_blocks.unwrap().len(),
+            extra.account_blocks.as_ref().unwrap().len()
+        );
+        Ok(())
+    }
 }
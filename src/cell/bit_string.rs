@@ -1,10 +1,14 @@
-use std::ops::{Add, ShlAssign};
+use alloc::vec::Vec;
+use core::ops::{Add, ShlAssign};
 
 use num_bigint::BigUint;
 use num_traits::Zero;
 
-#[derive(Clone)]
-pub(crate) struct BitString {
+use crate::cell::TonCellError;
+
+/// Bit string of exact length, e.g. a dictionary key prefix.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BitString {
     value: BigUint,
     bit_len: usize,
 }
@@ -17,6 +21,29 @@ impl BitString {
         }
     }
 
+    /// Creates a bit string from the first `bit_len` bits of `data`, most significant bit
+    /// first, as in cell data.
+    pub fn from_data(data: &[u8], bit_len: usize) -> Result<BitString, TonCellError> {
+        let len = bit_len.div_ceil(8);
+        if data.len() < len {
+            return Err(TonCellError::CellParserError(format!(
+                "Expected {} bits, got {} bytes",
+                bit_len,
+                data.len()
+            )));
+        }
+        let padding = (8 - bit_len % 8) % 8;
+        Ok(BitString {
+            value: BigUint::from_bytes_be(&data[..len]) >> padding,
+            bit_len,
+        })
+    }
+
+    /// Appends the bits of `other` after the bits of this string.
+    pub fn append(&mut self, other: &BitString) {
+        self.shl_assign_and_add(other.bit_len, other.value.clone())
+    }
+
     pub fn shl_assign_and_add(&mut self, rhs: usize, val: BigUint) {
         self.value.shl_assign(rhs);
         self.value += val;
@@ -37,8 +64,29 @@ impl BitString {
         self.bit_len
     }
 
+    /// Returns the bits, most significant bit first, with the last byte padded with zeros as
+    /// in cell data.
+    pub fn data(&self) -> Vec<u8> {
+        let padding = (8 - self.bit_len % 8) % 8;
+        let mut data = (&self.value << padding).to_bytes_be();
+        let len = self.bit_len.div_ceil(8);
+        if data.len() < len {
+            data.splice(0..0, core::iter::repeat_n(0, len - data.len()));
+        }
+        data.truncate(len);
+        data
+    }
+
+    /// Returns the value as big-endian bytes, padded with leading zeros to the bit length.
     pub fn get_value_as_bytes(&self) -> Vec<u8> {
-        self.value.to_bytes_be()
+        let bytes = self.value.to_bytes_be();
+        let len = self.bit_len.div_ceil(8);
+        if bytes.len() >= len {
+            return bytes;
+        }
+        let mut padded = vec![0; len - bytes.len()];
+        padded.extend_from_slice(&bytes);
+        padded
     }
 }
 
@@ -62,6 +110,7 @@ mod tests {
     use num_traits::ToPrimitive;
 
     use crate::cell::bit_string::create_biguint_with_ones;
+    use crate::cell::{BitString, CellBuilder};
 
     #[test]
     fn test_create_biguint_with_ones() -> anyhow::Result<()> {
@@ -97,4 +146,30 @@ mod tests {
         assert_eq!(r, BigUint::from(0x00u32));
         Ok(())
     }
+
+    #[test]
+    fn load_bitstring_keeps_exact_length() -> anyhow::Result<()> {
+        let cell = CellBuilder::new()
+            .store_u8(3, 0b101)?
+            .store_u32(12, 0x00f)?
+            .store_bit(true)?
+            .build()?;
+        let mut parser = cell.parser();
+        let mut bits = parser.load_bitstring(3)?;
+        assert_eq!(bits.bit_len(), 3);
+        assert_eq!(bits.data(), vec![0b1010_0000]);
+
+        let key = parser.load_bitstring(12)?;
+        assert_eq!(key.bit_len(), 12);
+        assert_eq!(key.data(), vec![0x00, 0xf0]);
+        assert_eq!(key, BitString::from_data(&[0x00, 0xff], 12)?);
+        bits.append(&key);
+        assert_eq!(bits.bit_len(), 15);
+        assert_eq!(bits.data(), vec![0b1010_0000, 0b0001_1110]);
+
+        assert_eq!(parser.load_bitstring(0)?, BitString::new());
+        assert!(parser.load_bitstring(2).is_err());
+        assert!(BitString::from_data(&[0xff], 9).is_err());
+        Ok(())
+    }
 }
@@ -0,0 +1,80 @@
+/// Sections of a block loaded by [`Cell::load_block_with`](crate::cell::Cell::load_block_with).
+///
+/// The references of the sections that are not selected are skipped without being parsed, so
+/// a huge key block can be loaded only for its config or its shards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockSelector {
+    pub info: bool,
+    pub value_flow: bool,
+    pub state_update: bool,
+    /// In and out message descriptors of the block extra.
+    pub msg_descrs: bool,
+    /// Account blocks of the block extra, with their transactions.
+    pub account_blocks: bool,
+    /// Whole masterchain block extra: shards, shard fees, signatures and config.
+    pub mc_block_extra: bool,
+    /// Config of key blocks, loaded even if `mc_block_extra` is not selected.
+    pub config: bool,
+}
+
+impl BlockSelector {
+    /// Selects all sections, as loaded by [`Cell::load_block`](crate::cell::Cell::load_block).
+    pub const fn all() -> BlockSelector {
+        BlockSelector {
+            info: true,
+            value_flow: true,
+            state_update: true,
+            msg_descrs: true,
+            account_blocks: true,
+            mc_block_extra: true,
+            config: true,
+        }
+    }
+
+    pub const fn none() -> BlockSelector {
+        BlockSelector {
+            info: false,
+            value_flow: false,
+            state_update: false,
+            msg_descrs: false,
+            account_blocks: false,
+            mc_block_extra: false,
+            config: false,
+        }
+    }
+
+    pub const fn info_only() -> BlockSelector {
+        BlockSelector {
+            info: true,
+            ..BlockSelector::none()
+        }
+    }
+
+    /// Selects the info and the masterchain block extra, skipping the account blocks.
+    pub const fn extra_without_account_blocks() -> BlockSelector {
+        BlockSelector {
+            info: true,
+            mc_block_extra: true,
+            config: true,
+            ..BlockSelector::none()
+        }
+    }
+
+    /// Selects only the config of key blocks.
+    pub const fn config_only() -> BlockSelector {
+        BlockSelector {
+            config: true,
+            ..BlockSelector::none()
+        }
+    }
+
+    pub(crate) fn extra(&self) -> bool {
+        self.msg_descrs || self.account_blocks || self.mc_block_extra || self.config
+    }
+}
+
+impl Default for BlockSelector {
+    fn default() -> Self {
+        BlockSelector::all()
+    }
+}
@@ -1,20 +1,24 @@
-use std::sync::Arc;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 
-use bitstream_io::{BigEndian, BitWrite, BitWriter};
+use bitstream_io::{BigEndian, BitWrite2 as BitWrite, BitWriter};
 use num_bigint::{BigInt, BigUint};
-use num_traits::Zero;
 
 use crate::address::TonAddress;
 use crate::cell::error::{MapTonCellError, TonCellError};
-use crate::cell::{ArcCell, Cell, CellParser};
+use crate::cell::{ArcCell, BitString, Cell, CellParser, DictBuilder};
+use crate::coins::Coins;
+use crate::compat::HashMap;
 
 use super::CellType;
 
-const MAX_CELL_BITS: usize = 1023;
-const MAX_CELL_REFERENCES: usize = 4;
+pub(crate) const MAX_CELL_BITS: usize = 1023;
+pub(crate) const MAX_CELL_REFERENCES: usize = 4;
 
 pub struct CellBuilder {
     bit_writer: BitWriter<Vec<u8>, BigEndian>,
+    bit_len: usize,
     references: Vec<ArcCell>,
 }
 
@@ -23,12 +27,14 @@ impl CellBuilder {
         let bit_writer = BitWriter::endian(Vec::new(), BigEndian);
         CellBuilder {
             bit_writer,
+            bit_len: 0,
             references: Vec::new(),
         }
     }
 
     pub fn store_bit(&mut self, val: bool) -> Result<&mut Self, TonCellError> {
         self.bit_writer.write_bit(val).map_cell_builder_error()?;
+        self.bit_len += 1;
         Ok(self)
     }
 
@@ -36,6 +42,7 @@ impl CellBuilder {
         self.bit_writer
             .write(bit_len as u32, val)
             .map_cell_builder_error()?;
+        self.bit_len += bit_len;
         Ok(self)
     }
 
@@ -43,6 +50,7 @@ impl CellBuilder {
         self.bit_writer
             .write(bit_len as u32, val)
             .map_cell_builder_error()?;
+        self.bit_len += bit_len;
         Ok(self)
     }
 
@@ -50,6 +58,7 @@ impl CellBuilder {
         self.bit_writer
             .write(bit_len as u32, val)
             .map_cell_builder_error()?;
+        self.bit_len += bit_len;
         Ok(self)
     }
 
@@ -57,6 +66,7 @@ impl CellBuilder {
         self.bit_writer
             .write(bit_len as u32, val)
             .map_cell_builder_error()?;
+        self.bit_len += bit_len;
         Ok(self)
     }
 
@@ -64,6 +74,7 @@ impl CellBuilder {
         self.bit_writer
             .write(bit_len as u32, val)
             .map_cell_builder_error()?;
+        self.bit_len += bit_len;
         Ok(self)
     }
 
@@ -71,6 +82,7 @@ impl CellBuilder {
         self.bit_writer
             .write(bit_len as u32, val)
             .map_cell_builder_error()?;
+        self.bit_len += bit_len;
         Ok(self)
     }
 
@@ -78,6 +90,7 @@ impl CellBuilder {
         self.bit_writer
             .write(bit_len as u32, val)
             .map_cell_builder_error()?;
+        self.bit_len += bit_len;
         Ok(self)
     }
 
@@ -160,7 +173,18 @@ impl CellBuilder {
         Ok(self)
     }
 
+    /// Stores the first `bit_len` bits of `slice`, most significant bit first.
+    ///
+    /// The bits of the last byte following them are ignored, so `slice` may be the data of
+    /// a cell or any bit string padded to full bytes.
     pub fn store_bits(&mut self, bit_len: usize, slice: &[u8]) -> Result<&mut Self, TonCellError> {
+        if slice.len() < bit_len.div_ceil(8) {
+            return Err(TonCellError::cell_builder_error(format!(
+                "Can't store {} bits from {} bytes",
+                bit_len,
+                slice.len()
+            )));
+        }
         let full_bytes = bit_len / 8;
         self.store_slice(&slice[0..full_bytes])?;
         let last_byte_len = bit_len % 8;
@@ -171,20 +195,89 @@ impl CellBuilder {
         Ok(self)
     }
 
+    /// Stores all bits of the bit string, including its leading zeros.
+    pub fn store_bitstring(&mut self, bit_string: &BitString) -> Result<&mut Self, TonCellError> {
+        self.store_bits(bit_string.bit_len(), &bit_string.data())
+    }
+
     pub fn store_string(&mut self, val: &str) -> Result<&mut Self, TonCellError> {
         self.store_slice(val.as_bytes())
     }
 
-    pub fn store_coins(&mut self, val: &BigUint) -> Result<&mut Self, TonCellError> {
+    pub fn store_coins(&mut self, val: impl Into<Coins>) -> Result<&mut Self, TonCellError> {
+        let val = val.into();
+        if !val.is_valid() {
+            return Err(TonCellError::cell_builder_error(format!(
+                "Value {} does not fit in Coins",
+                val.nanotons()
+            )));
+        }
         if val.is_zero() {
             self.store_u8(4, 0)
         } else {
-            let num_bytes = (val.bits() as usize + 7) / 8;
+            let num_bytes = (val.nanotons().bits() as usize).div_ceil(8);
             self.store_u8(4, num_bytes as u8)?;
-            self.store_uint(num_bytes * 8, val)
+            self.store_uint(num_bytes * 8, val.nanotons())
+        }
+    }
+
+    /// Stores `VarUInteger n`, where `bit_len` is `n`.
+    pub fn store_var_uinteger(
+        &mut self,
+        bit_len: usize,
+        val: &BigUint,
+    ) -> Result<&mut Self, TonCellError> {
+        let num_bytes = (val.bits() as usize).div_ceil(8);
+        if num_bytes >= bit_len {
+            return Err(TonCellError::cell_builder_error(format!(
+                "Value {} does not fit in VarUInteger {}",
+                val, bit_len
+            )));
+        }
+        let len_bits = (usize::BITS - (bit_len - 1).leading_zeros()) as usize;
+        self.store_u32(len_bits, num_bytes as u32)?;
+        if num_bytes > 0 {
+            self.store_uint(num_bytes * 8, val)?;
+        }
+        Ok(self)
+    }
+
+    /// Stores `ExtraCurrencyCollection`, a `HashmapE 32 (VarUInteger 32)` of amounts
+    /// by currency ids.
+    pub fn store_extra_currencies(
+        &mut self,
+        currencies: &HashMap<u32, BigUint>,
+    ) -> Result<&mut Self, TonCellError> {
+        let mut dict = DictBuilder::new(32);
+        for (id, amount) in currencies {
+            let value = CellBuilder::new().store_var_uinteger(32, amount)?.build()?;
+            dict.insert(BigUint::from(*id), value)?;
+        }
+        self.store_dict(&dict)
+    }
+
+    /// Stores `HashmapE n X`: a bit signaling whether the dictionary is empty,
+    /// followed by a reference to its root.
+    ///
+    /// Fields of type `Hashmap n X` embed the root instead, see [`Self::store_dict_data`].
+    pub fn store_dict(&mut self, dict: &DictBuilder) -> Result<&mut Self, TonCellError> {
+        match dict.build()? {
+            Some(root) => self.store_bit(true)?.store_child(root),
+            None => self.store_bit(false),
         }
     }
 
+    /// Stores `Hashmap n X`: the bits and the references of the root of the dictionary,
+    /// without the bit and the reference of [`Self::store_dict`].
+    ///
+    /// `Hashmap n X` can't be empty, so empty dictionaries are an error.
+    pub fn store_dict_data(&mut self, dict: &DictBuilder) -> Result<&mut Self, TonCellError> {
+        let root = dict.build()?.ok_or_else(|| {
+            TonCellError::cell_builder_error("Hashmap can't be empty, use store_dict for HashmapE")
+        })?;
+        self.store_cell(&root)
+    }
+
     /// Stores address without optimizing hole address
     pub fn store_raw_address(&mut self, val: &TonAddress) -> Result<&mut Self, TonCellError> {
         self.store_u8(2, 0b10u8)?;
@@ -247,6 +340,15 @@ impl CellBuilder {
         Ok(self)
     }
 
+    /// Stores all remaining bits of the parser and the references it has not loaded yet.
+    pub fn store_remaining(&mut self, parser: &mut CellParser) -> Result<&mut Self, TonCellError> {
+        self.store_remaining_bits(parser)?;
+        while parser.remaining_references() > 0 {
+            self.store_reference(&parser.next_reference()?)?;
+        }
+        Ok(self)
+    }
+
     pub fn store_cell_data(&mut self, cell: &Cell) -> Result<&mut Self, TonCellError> {
         let mut parser = cell.parser();
         self.store_remaining_bits(&mut parser)?;
@@ -259,6 +361,38 @@ impl CellBuilder {
         Ok(self)
     }
 
+    /// Stores `Either X ^X`.
+    ///
+    /// The cell is stored inline if it fits in the remaining capacity of the builder,
+    /// otherwise it is stored as a reference.
+    pub fn store_either_cell_or_cell_ref(
+        &mut self,
+        cell: &ArcCell,
+    ) -> Result<&mut Self, TonCellError> {
+        let fits_inline = cell.bit_len < self.remaining_bits()
+            && cell.references.len() <= self.remaining_references();
+        if fits_inline {
+            self.store_bit(false)?;
+            self.store_cell(cell)
+        } else {
+            self.store_bit(true)?;
+            self.store_reference(cell)
+        }
+    }
+
+    /// Returns the number of bits stored so far.
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    pub fn remaining_bits(&self) -> usize {
+        MAX_CELL_BITS.saturating_sub(self.bit_len)
+    }
+
+    pub fn remaining_references(&self) -> usize {
+        MAX_CELL_REFERENCES.saturating_sub(self.references.len())
+    }
+
     pub fn build(&mut self) -> Result<Cell, TonCellError> {
         let mut trailing_zeros = 0;
         while !self.bit_writer.byte_aligned() {
@@ -281,23 +415,16 @@ impl CellBuilder {
                     ref_count
                 )));
             }
-            let d1 = vec[0];
-            let level_mask = d1 >> 5;
-            let is_exotic = (d1 & 8) != 0;
-            let has_hashes = (d1 & 16) != 0;
-            let cell_type = if is_exotic {
-                vec[0]
-            } else {
-                CellType::OrdinaryCell as u8
-            };
+            // The builder only produces ordinary cells, the cell data must not be
+            // interpreted as a cell descriptor.
             Ok(Cell {
-                data: vec.to_vec(),
+                data: vec.as_slice().into(),
                 bit_len,
                 references: self.references.clone(),
-                cell_type,
-                is_exotic,
-                level_mask,
-                has_hashes,
+                cell_type: CellType::OrdinaryCell as u8,
+                is_exotic: false,
+                level_mask: 0,
+                has_hashes: false,
                 proof: false,
                 hashes: vec![],
                 depth: vec![],
@@ -318,14 +445,21 @@ impl Default for CellBuilder {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use num_bigint::BigUint;
+    use sha2::{Digest, Sha256};
+
     use crate::address::TonAddress;
-    use crate::cell::CellBuilder;
+    use crate::cell::{BitString, Cell, CellBuilder, CellType, DictBuilder};
+    use crate::coins::Coins;
 
     #[test]
     fn write_bit() -> anyhow::Result<()> {
         let mut writer = CellBuilder::new();
         let cell = writer.store_bit(true)?.build()?;
-        assert_eq!(cell.data, [0b1000_0000]);
+        assert_eq!(cell.data[..], [0b1000_0000]);
         assert_eq!(cell.bit_len, 1);
         let mut reader = cell.parser();
         let result = reader.load_bit()?;
@@ -333,12 +467,25 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn built_cells_are_ordinary() -> anyhow::Result<()> {
+        // 0xff would be an exotic cell with level mask 7 if read as a cell descriptor
+        let cell = CellBuilder::new().store_u8(8, 0xff)?.build()?;
+        assert_eq!(cell.cell_type, CellType::OrdinaryCell as u8);
+        assert!(!cell.is_exotic);
+        assert_eq!(cell.level_mask, 0);
+        // Descriptors of an ordinary cell with no references and 8 bits of data
+        let expected = Sha256::digest([0x00, 0x02, 0xff]);
+        assert_eq!(cell.cell_hash()?, expected.as_slice());
+        Ok(())
+    }
+
     #[test]
     fn write_u8() -> anyhow::Result<()> {
         let value = 234u8;
         let mut writer = CellBuilder::new();
         let cell = writer.store_u8(8, value)?.build()?;
-        assert_eq!(cell.data, [0b1110_1010]);
+        assert_eq!(cell.data[..], [0b1110_1010]);
         assert_eq!(cell.bit_len, 8);
         let mut reader = cell.parser();
         let result = reader.load_u8(8)?;
@@ -351,7 +498,7 @@ mod tests {
         let value = 0xFAD45AADu32;
         let mut writer = CellBuilder::new();
         let cell = writer.store_u32(32, value)?.build()?;
-        assert_eq!(cell.data, [0xFA, 0xD4, 0x5A, 0xAD]);
+        assert_eq!(cell.data[..], [0xFA, 0xD4, 0x5A, 0xAD]);
         assert_eq!(cell.bit_len, 32);
         let mut reader = cell.parser();
         let result = reader.load_u32(32)?;
@@ -364,7 +511,10 @@ mod tests {
         let value = 0xFAD45AADAA12FF45;
         let mut writer = CellBuilder::new();
         let cell = writer.store_u64(64, value)?.build()?;
-        assert_eq!(cell.data, [0xFA, 0xD4, 0x5A, 0xAD, 0xAA, 0x12, 0xFF, 0x45]);
+        assert_eq!(
+            cell.data[..],
+            [0xFA, 0xD4, 0x5A, 0xAD, 0xAA, 0x12, 0xFF, 0x45]
+        );
         assert_eq!(cell.bit_len, 64);
         let mut reader = cell.parser();
         let result = reader.load_u64(64)?;
@@ -377,7 +527,7 @@ mod tests {
         let value = [0xFA, 0xD4, 0x5A, 0xAD, 0xAA, 0x12, 0xFF, 0x45];
         let mut writer = CellBuilder::new();
         let cell = writer.store_slice(&value)?.build()?;
-        assert_eq!(cell.data, value);
+        assert_eq!(cell.data[..], value);
         assert_eq!(cell.bit_len, 64);
         let mut reader = cell.parser();
         let bytes = reader.load_bytes(8)?;
@@ -385,6 +535,52 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn write_bits() -> anyhow::Result<()> {
+        let mut writer = CellBuilder::new();
+        writer
+            .store_bits(0, &[])?
+            .store_bits(3, &[0b1011_1111])?
+            .store_bits(13, &[0xff, 0b0101_0111])?
+            .store_bits(8, &[0x12, 0xff])?;
+        let cell = writer.build()?;
+        assert_eq!(cell.bit_len, 24);
+        assert_eq!(cell.data[..], [0b1011_1111, 0b1110_1010, 0x12]);
+
+        assert!(CellBuilder::new().store_bits(9, &[0xff]).is_err());
+        assert!(CellBuilder::new()
+            .store_bits(1024, &[0; 128])?
+            .build()
+            .is_err());
+        let full = CellBuilder::new().store_bits(1023, &[0xff; 128])?.build()?;
+        assert_eq!(full.bit_len, 1023);
+        assert_eq!(full.data[127], 0xfe);
+        Ok(())
+    }
+
+    #[test]
+    fn write_bitstring() -> anyhow::Result<()> {
+        let mut bit_string = BitString::new();
+        bit_string.shl_assign(2);
+        bit_string.shl_assign_and_add(3, BigUint::from(0b101u32));
+        assert_eq!(bit_string.data(), [0b0010_1000]);
+
+        let cell = CellBuilder::new()
+            .store_bit(true)?
+            .store_bitstring(&bit_string)?
+            .store_bitstring(&BitString::new())?
+            .build()?;
+        assert_eq!(cell.bit_len, 6);
+        assert_eq!(cell.data[..], [0b1001_0100]);
+
+        let mut bit_string = BitString::new();
+        bit_string.shl_assign_and_fill(9);
+        let cell = CellBuilder::new().store_bitstring(&bit_string)?.build()?;
+        assert_eq!(cell.bit_len, 9);
+        assert_eq!(cell.data[..], [0xff, 0x80]);
+        Ok(())
+    }
+
     #[test]
     fn write_str() -> anyhow::Result<()> {
         let texts = ["hello", "Русский текст", "中华人民共和国", "\u{263A}😃"];
@@ -392,7 +588,7 @@ mod tests {
             let mut writer = CellBuilder::new();
             let cell = writer.store_string(text)?.build()?;
             let text_bytes = text.as_bytes();
-            assert_eq!(cell.data, text_bytes);
+            assert_eq!(&cell.data[..], text_bytes);
             assert_eq!(cell.bit_len, text_bytes.len() * 8);
             let mut reader = cell.parser();
             let remaining_bytes = reader.remaining_bytes();
@@ -409,7 +605,7 @@ mod tests {
         let mut writer = CellBuilder::new();
         let cell = writer.store_address(&addr)?.build()?;
         assert_eq!(
-            cell.data,
+            cell.data[..],
             [
                 128, 28, 155, 42, 157, 243, 233, 194, 74, 20, 77, 107, 119, 90, 237, 67, 155, 162,
                 249, 250, 17, 117, 117, 173, 233, 132, 124, 110, 68, 225, 93, 237, 238, 192
@@ -421,4 +617,120 @@ mod tests {
         assert_eq!(result, addr);
         Ok(())
     }
+
+    #[test]
+    fn write_extra_currencies() -> anyhow::Result<()> {
+        let currencies: HashMap<u32, BigUint> = [
+            (100, BigUint::from(1_000_000u32)),
+            (0xffff_ffff, BigUint::from(7u32)),
+        ]
+        .into_iter()
+        .collect();
+        let cell = CellBuilder::new()
+            .store_coins(BigUint::from(42u32))?
+            .store_extra_currencies(&currencies)?
+            .build()?;
+        let mut parser = cell.parser();
+        let collection = Cell::load_currency_collection(&cell, &mut 0, &mut parser)?;
+        assert_eq!(collection.grams.value, BigUint::from(42u32));
+        let loaded: HashMap<u32, BigUint> = collection
+            .other
+            .into_iter()
+            .map(|(id, amount)| (id, amount.value))
+            .collect();
+        assert_eq!(loaded, currencies);
+
+        let empty = CellBuilder::new()
+            .store_extra_currencies(&HashMap::new())?
+            .build()?;
+        assert_eq!(empty.bit_len, 1);
+        assert!(CellBuilder::new()
+            .store_var_uinteger(16, &(BigUint::from(1u32) << 120))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn write_dict_data() -> anyhow::Result<()> {
+        let mut dict = DictBuilder::new(8);
+        for key in [1u32, 2, 200] {
+            dict.insert(
+                BigUint::from(key),
+                CellBuilder::new().store_u32(32, key)?.build()?,
+            )?;
+        }
+        let root = dict.build()?.unwrap();
+
+        let cell = CellBuilder::new().store_dict_data(&dict)?.build()?;
+        assert_eq!(cell.cell_hash()?, root.cell_hash()?);
+        let cell = CellBuilder::new()
+            .store_u8(4, 0xa)?
+            .store_dict_data(&dict)?
+            .build()?;
+        assert_eq!(cell.bit_len, 4 + root.bit_len);
+        assert_eq!(cell.references, root.references);
+
+        let cell = CellBuilder::new().store_dict(&dict)?.build()?;
+        assert_eq!(cell.bit_len, 1);
+        assert_eq!(cell.reference(0)?.cell_hash()?, root.cell_hash()?);
+        assert!(CellBuilder::new()
+            .store_dict_data(&DictBuilder::new(8))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn write_either_cell_or_cell_ref() -> anyhow::Result<()> {
+        let small = Arc::new(CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?);
+        let cell = CellBuilder::new()
+            .store_u64(64, 0)?
+            .store_either_cell_or_cell_ref(&small)?
+            .build()?;
+        assert_eq!(cell.bit_len, 64 + 1 + 32);
+        assert!(cell.references.is_empty());
+
+        let large = Arc::new(CellBuilder::new().store_slice(&[0xff; 120])?.build()?);
+        let cell = CellBuilder::new()
+            .store_u64(64, 0)?
+            .store_either_cell_or_cell_ref(&large)?
+            .build()?;
+        assert_eq!(cell.bit_len, 64 + 1);
+        assert_eq!(cell.references, vec![large]);
+        Ok(())
+    }
+
+    #[test]
+    fn write_remaining() -> anyhow::Result<()> {
+        let child = Arc::new(CellBuilder::new().store_u8(8, 1)?.build()?);
+        let other_child = Arc::new(CellBuilder::new().store_u8(8, 2)?.build()?);
+        let cell = CellBuilder::new()
+            .store_u32(32, 0x12345678)?
+            .store_coins(BigUint::from(100u32))?
+            .store_u8(5, 0b10101)?
+            .store_reference(&child)?
+            .store_reference(&other_child)?
+            .build()?;
+
+        let mut parser = cell.parser();
+        let op = parser.load_u32(32)?;
+        parser.load_coins()?;
+        let updated = CellBuilder::new()
+            .store_u32(32, op)?
+            .store_coins(BigUint::from(200u32))?
+            .store_remaining(&mut parser)?
+            .build()?;
+        let mut parser = updated.parser();
+        assert_eq!(parser.load_u32(32)?, 0x12345678);
+        assert_eq!(parser.load_coins()?, Coins::from_nano(200));
+        assert_eq!(parser.load_u8(5)?, 0b10101);
+        assert_eq!(updated.references, vec![child.clone(), other_child.clone()]);
+
+        let mut parser = cell.parser();
+        parser.skip_bits(32)?;
+        assert_eq!(parser.next_reference()?, child);
+        let remainder = parser.remainder_to_builder()?.build()?;
+        assert_eq!(remainder.bit_len, cell.bit_len - 32);
+        assert_eq!(remainder.references, vec![other_child]);
+        Ok(())
+    }
 }
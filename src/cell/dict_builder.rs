@@ -0,0 +1,359 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::vec::Vec;
+
+use num_bigint::BigUint;
+
+use crate::cell::{Cell, CellBuilder, TonCellError};
+
+/// Builder of a `Hashmap n X` dictionary with `n`-bit unsigned keys.
+///
+/// Bits and references of the value cells are appended to the leaves of the dictionary.
+/// Labels are encoded the same way as by the node, choosing the shortest encoding of each
+/// label, so the resulting cells have the same hashes as dictionaries built on-chain and the
+/// fewest bits possible.
+///
+/// ```raw
+/// hm_edge#_ {n:#} {X:Type} {l:#} {m:#} label:(HmLabel ~l n)
+///           {n = (~m) + l} node:(HashmapNode m X) = Hashmap n X;
+/// ```
+
+#[derive(Debug, Clone)]
+pub struct DictBuilder {
+    key_bit_len: usize,
+    entries: BTreeMap<BigUint, Cell>,
+}
+
+impl DictBuilder {
+    pub fn new(key_bit_len: usize) -> DictBuilder {
+        DictBuilder {
+            key_bit_len,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Creates a builder holding the entries, with the keys and the values serialized by
+    /// the given functions, e.g. [`key_serializer_u32`] and [`value_serializer_cell_ref`].
+    ///
+    /// Later entries replace earlier entries of the same key.
+    pub fn from_entries<K, V, I, KS, VS>(
+        key_bit_len: usize,
+        entries: I,
+        key_serializer: KS,
+        mut value_serializer: VS,
+    ) -> Result<DictBuilder, TonCellError>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        KS: Fn(usize, &K) -> Result<BigUint, TonCellError>,
+        VS: FnMut(&mut CellBuilder, V) -> Result<(), TonCellError>,
+    {
+        let mut dict = DictBuilder::new(key_bit_len);
+        for (key, value) in entries {
+            let key = key_serializer(key_bit_len, &key)?;
+            let mut builder = CellBuilder::new();
+            value_serializer(&mut builder, value)?;
+            dict.insert(key, builder.build()?)?;
+        }
+        Ok(dict)
+    }
+
+    pub fn key_bit_len(&self) -> usize {
+        self.key_bit_len
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Inserts a value, replacing the previous value of the same key.
+    pub fn insert(&mut self, key: BigUint, value: Cell) -> Result<&mut Self, TonCellError> {
+        if key.bits() as usize > self.key_bit_len {
+            return Err(TonCellError::cell_builder_error(format!(
+                "Dictionary key {} does not fit in {} bits",
+                key, self.key_bit_len
+            )));
+        }
+        self.entries.insert(key, value);
+        Ok(self)
+    }
+
+    /// Builds the root cell of the dictionary or returns `None` if the dictionary is empty.
+    pub fn build(&self) -> Result<Option<Cell>, TonCellError> {
+        if self.entries.is_empty() {
+            return Ok(None);
+        }
+        let entries: Vec<(Vec<bool>, &Cell)> = self
+            .entries
+            .iter()
+            .map(|(key, value)| {
+                let bits = (0..self.key_bit_len)
+                    .rev()
+                    .map(|i| key.bit(i as u64))
+                    .collect();
+                (bits, value)
+            })
+            .collect();
+        build_edge(&entries, 0, self.key_bit_len).map(Some)
+    }
+}
+
+pub fn key_serializer_u8(bit_len: usize, key: &u8) -> Result<BigUint, TonCellError> {
+    fixed_len_key(bit_len, 8, BigUint::from(*key))
+}
+
+pub fn key_serializer_u16(bit_len: usize, key: &u16) -> Result<BigUint, TonCellError> {
+    fixed_len_key(bit_len, 16, BigUint::from(*key))
+}
+
+pub fn key_serializer_u32(bit_len: usize, key: &u32) -> Result<BigUint, TonCellError> {
+    fixed_len_key(bit_len, 32, BigUint::from(*key))
+}
+
+pub fn key_serializer_u64(bit_len: usize, key: &u64) -> Result<BigUint, TonCellError> {
+    fixed_len_key(bit_len, 64, BigUint::from(*key))
+}
+
+pub fn key_serializer_256bit(bit_len: usize, key: &[u8; 32]) -> Result<BigUint, TonCellError> {
+    fixed_len_key(bit_len, 256, BigUint::from_bytes_be(key))
+}
+
+/// Returns the key as is, the length is checked on insertion.
+pub fn key_serializer_uint(_bit_len: usize, key: &BigUint) -> Result<BigUint, TonCellError> {
+    Ok(key.clone())
+}
+
+/// Stores the bits and the references of the value in the leaf.
+pub fn value_serializer_cell(builder: &mut CellBuilder, value: Cell) -> Result<(), TonCellError> {
+    builder.store_cell(&value)?;
+    Ok(())
+}
+
+/// Stores the value as a reference of the leaf, i.e. for `HashmapE n ^X` dictionaries.
+pub fn value_serializer_cell_ref(
+    builder: &mut CellBuilder,
+    value: Cell,
+) -> Result<(), TonCellError> {
+    builder.store_child(value)?;
+    Ok(())
+}
+
+fn fixed_len_key(bit_len: usize, expected: usize, key: BigUint) -> Result<BigUint, TonCellError> {
+    if bit_len == expected {
+        Ok(key)
+    } else {
+        Err(TonCellError::cell_builder_error(format!(
+            "Invalid key len: {}, expected {} bits",
+            bit_len, expected
+        )))
+    }
+}
+
+/// Builds an edge holding the entries, whose keys are equal up to `offset` bits.
+fn build_edge(
+    entries: &[(Vec<bool>, &Cell)],
+    offset: usize,
+    remaining_bits: usize,
+) -> Result<Cell, TonCellError> {
+    // Entries are sorted, so the common prefix of the first and the last key is shared by all
+    let first = &entries[0].0[offset..];
+    let last = &entries[entries.len() - 1].0[offset..];
+    let label_len = first
+        .iter()
+        .zip(last.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut builder = CellBuilder::new();
+    store_label(&mut builder, &first[..label_len], remaining_bits)?;
+    if label_len == remaining_bits {
+        builder.store_cell(entries[0].1)?;
+    } else {
+        let fork = offset + label_len;
+        let split = entries.partition_point(|(key, _)| !key[fork]);
+        let child_bits = remaining_bits - label_len - 1;
+        builder.store_child(build_edge(&entries[..split], fork + 1, child_bits)?)?;
+        builder.store_child(build_edge(&entries[split..], fork + 1, child_bits)?)?;
+    }
+    builder.build()
+}
+
+/// Stores the shortest of the label encodings:
+///
+/// ```raw
+/// hml_short$0 {m:#} {n:#} len:(Unary ~n) {n <= m} s:(n * Bit) = HmLabel ~n m;
+/// hml_long$10 {m:#} n:(#<= m) s:(n * Bit) = HmLabel ~n m;
+/// hml_same$11 {m:#} v:Bit n:(#<= m) = HmLabel ~n m;
+/// ```
+fn store_label(
+    builder: &mut CellBuilder,
+    label: &[bool],
+    max_len: usize,
+) -> Result<(), TonCellError> {
+    let len = label.len();
+    let len_bits = (usize::BITS - max_len.leading_zeros()) as usize;
+    if len > 1 && len_bits < 2 * len - 1 && label.iter().all(|bit| *bit == label[0]) {
+        builder.store_u8(2, 0b11)?;
+        builder.store_bit(label[0])?;
+        builder.store_u32(len_bits, len as u32)?;
+        return Ok(());
+    }
+    if len_bits < len {
+        builder.store_u8(2, 0b10)?;
+        builder.store_u32(len_bits, len as u32)?;
+    } else {
+        builder.store_bit(false)?;
+        for _ in 0..len {
+            builder.store_bit(true)?;
+        }
+        builder.store_bit(false)?;
+    }
+    for bit in label {
+        builder.store_bit(*bit)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use num_bigint::BigUint;
+
+    use crate::cell::dict_builder::store_label;
+    use crate::cell::{
+        key_extractor_u16, key_extractor_uint, key_serializer_u16, key_serializer_u8,
+        value_extractor_cell, value_extractor_uint, value_serializer_cell_ref, CellBuilder,
+        DictBuilder, GenericDictLoader,
+    };
+
+    #[test]
+    fn dict_builder_round_trip_works() -> anyhow::Result<()> {
+        let values: HashMap<u32, u32> = [(0, 1), (1, 2), (7, 3), (0xffff_ffff, 4), (0x8000, 5)]
+            .into_iter()
+            .collect();
+        let mut dict = DictBuilder::new(32);
+        for (key, value) in values.iter() {
+            dict.insert(
+                BigUint::from(*key),
+                CellBuilder::new().store_u32(32, *value)?.build()?,
+            )?;
+        }
+        let root = dict.build()?.unwrap();
+        let key_extractor = |_: usize, key: &[u8]| Ok(BigUint::from_bytes_be(key));
+        let loader = GenericDictLoader::new(key_extractor, value_extractor_uint, 32);
+        let loaded = root.load_generic_dict(&loader)?;
+        let expected: HashMap<BigUint, BigUint> = values
+            .iter()
+            .map(|(k, v)| (BigUint::from(*k), BigUint::from(*v)))
+            .collect();
+        assert_eq!(loaded, expected);
+
+        assert!(DictBuilder::new(32).build()?.is_none());
+        assert!(DictBuilder::new(8)
+            .insert(BigUint::from(256u32), CellBuilder::new().build()?)
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn dict_with_unaligned_keys_round_trips() -> anyhow::Result<()> {
+        let keys = [0u32, 1, 0x7ff, 0x800, 0xabc, 0xfff];
+        let mut dict = DictBuilder::new(12);
+        for key in keys {
+            dict.insert(
+                BigUint::from(key),
+                CellBuilder::new().store_u32(32, key)?.build()?,
+            )?;
+        }
+        let root = dict.build()?.unwrap();
+        let loader = GenericDictLoader::new(key_extractor_uint, value_extractor_uint, 12);
+        let loaded = root.load_generic_dict(&loader)?;
+        let expected: HashMap<BigUint, BigUint> = keys
+            .iter()
+            .map(|key| (BigUint::from(*key), BigUint::from(*key)))
+            .collect();
+        assert_eq!(loaded, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn dict_builder_matches_node_encoding() -> anyhow::Result<()> {
+        // Single entry dict {1: 0} with 8 bit keys, long label 10 + 1000 + 00000001
+        let mut dict = DictBuilder::new(8);
+        dict.insert(BigUint::from(1u32), CellBuilder::new().build()?)?;
+        let root = dict.build()?.unwrap();
+        assert_eq!(root.bit_len, 14);
+        assert_eq!(root.data[..], vec![0b1010_0000, 0b0000_0100]);
+
+        // Single entry dict {0: 0} with 8 bit keys, same label 11 + 0 + 1000
+        let mut dict = DictBuilder::new(8);
+        dict.insert(BigUint::from(0u32), CellBuilder::new().build()?)?;
+        let root = dict.build()?.unwrap();
+        assert_eq!(root.bit_len, 7);
+        assert_eq!(root.data[..], vec![0b1101_0000]);
+        Ok(())
+    }
+
+    #[test]
+    fn dict_from_entries_works() -> anyhow::Result<()> {
+        let entries = (0..20u16).map(|key| {
+            let value = CellBuilder::new().store_u16(16, key * 3).unwrap().build();
+            (key * 7, value.unwrap())
+        });
+        let dict = DictBuilder::from_entries(
+            16,
+            entries.clone(),
+            key_serializer_u16,
+            value_serializer_cell_ref,
+        )?;
+        let mut expected = DictBuilder::new(16);
+        for (key, value) in entries.clone() {
+            expected.insert(
+                BigUint::from(key),
+                CellBuilder::new().store_child(value)?.build()?,
+            )?;
+        }
+        let root = dict.build()?.unwrap();
+        assert_eq!(root.cell_hash()?, expected.build()?.unwrap().cell_hash()?);
+
+        let loader = GenericDictLoader::new(key_extractor_u16, value_extractor_cell, 16);
+        let loaded = root.load_generic_dict(&loader)?;
+        assert_eq!(loaded.len(), 20);
+        let value = loaded[&(7 * 7)].reference(0)?.parser().load_u16(16)?;
+        assert_eq!(value, 7 * 3);
+
+        let wrong_len = DictBuilder::from_entries(
+            16,
+            [(1u8, CellBuilder::new().build()?)],
+            key_serializer_u8,
+            value_serializer_cell_ref,
+        );
+        assert!(wrong_len.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn store_label_chooses_shortest_encoding() -> anyhow::Result<()> {
+        for max_len in 0..40usize {
+            let len_bits = (usize::BITS - max_len.leading_zeros()) as usize;
+            for len in 0..=max_len {
+                for same in [true, false] {
+                    let label: Vec<bool> = (0..len).map(|i| !same && i % 2 == 1).collect();
+                    let mut builder = CellBuilder::new();
+                    store_label(&mut builder, &label, max_len)?;
+                    let short = 2 * len + 2;
+                    let long = 2 + len_bits + len;
+                    let shortest = match label.iter().all(|bit| *bit == label[0]) {
+                        true if len > 0 => short.min(long).min(3 + len_bits),
+                        _ => short.min(long),
+                    };
+                    assert_eq!(builder.bit_len(), shortest, "len {} of {}", len, max_len);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
-use std::hash::Hash;
-use std::ops::ShrAssign;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::hash::Hash;
+use core::ops::ShrAssign;
 
 use num_bigint::{BigInt, BigUint};
 
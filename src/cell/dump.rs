@@ -0,0 +1,97 @@
+use alloc::string::String;
+use core::fmt::Write;
+
+const ROW_BITS: usize = 32;
+
+/// Formats the bits `from..to` of `data` in rows of up to 32 bits aligned to multiples of 32,
+/// each row showing its bit offset, the bits grouped by bytes and their hex representation.
+///
+/// Incomplete nibbles are completed with a `1` bit followed by zeros and marked by `_`, as
+/// in the hex representation of TL-B bitstrings. The cursor is marked by `|` in the bits
+/// and by `>` before its row.
+pub(crate) fn dump_bits(data: &[u8], from: usize, to: usize, cursor: Option<usize>) -> String {
+    let bit = |i: usize| data[i / 8] & (0x80 >> (i % 8)) != 0;
+    let mut dump = String::new();
+    let mut row_start = from;
+    loop {
+        let row_end = ((row_start / ROW_BITS + 1) * ROW_BITS).min(to);
+        let cursor_in_row =
+            cursor.filter(|&c| c >= row_start && (c < row_end || c == to && row_end == to));
+        let marker = if cursor_in_row.is_some() { '>' } else { ' ' };
+        let mut bits = String::new();
+        for i in row_start..row_end {
+            if cursor_in_row == Some(i) {
+                bits.push('|');
+            } else if i > row_start && i % 8 == 0 {
+                bits.push(' ');
+            }
+            bits.push(if bit(i) { '1' } else { '0' });
+        }
+        if cursor_in_row == Some(row_end) {
+            bits.push('|');
+        }
+        let hex = hex_bits((row_start..row_end).map(bit));
+        let width = ROW_BITS + ROW_BITS / 8;
+        let _ = write!(dump, "{}{:>6}: {:<width$} {}", marker, row_start, bits, hex);
+        dump.push('\n');
+        row_start = row_end;
+        if row_start >= to {
+            break;
+        }
+    }
+    dump
+}
+
+fn hex_bits<I: Iterator<Item = bool>>(bits: I) -> String {
+    let mut hex = String::new();
+    let mut nibble = 0u8;
+    let mut len = 0;
+    for bit in bits {
+        nibble = nibble << 1 | bit as u8;
+        len += 1;
+        if len == 4 {
+            let _ = write!(hex, "{:x}", nibble);
+            nibble = 0;
+            len = 0;
+        }
+    }
+    if len > 0 {
+        nibble = (nibble << 1 | 1) << (3 - len);
+        let _ = write!(hex, "{:x}_", nibble);
+    }
+    hex
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cell::CellBuilder;
+
+    #[test]
+    fn dump_bits_works() -> anyhow::Result<()> {
+        let cell = CellBuilder::new()
+            .store_u32(32, 0x11ef55aa)?
+            .store_u8(6, 0b101101)?
+            .build()?;
+        assert_eq!(
+            cell.dump_bits(),
+            "38 bits, 0 references\n".to_owned()
+                + "      0: 00010001 11101111 01010101 10101010  11ef55aa\n"
+                + "     32: 101101                               b6_\n"
+        );
+
+        let mut parser = cell.parser();
+        parser.load_u32(32)?;
+        parser.load_u8(3)?;
+        assert_eq!(
+            parser.debug_dump(4),
+            "     31: 0                                    4_\n".to_owned()
+                + ">    32: 101|101                              b6_\n"
+        );
+        parser.load_u8(3)?;
+        assert_eq!(
+            parser.debug_dump(2),
+            ">    36: 01|                                  6_\n"
+        );
+        Ok(())
+    }
+}
@@ -1,5 +1,11 @@
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
 use thiserror::Error;
 
+use crate::cell::CellPath;
+
 #[derive(Error, Debug, PartialEq)]
 pub enum TonCellError {
     #[error("Bag of cells deserialization error ({0})")]
@@ -25,11 +31,85 @@ pub enum TonCellError {
 
     #[error("Non-empty reader (Remaining bits: {0})")]
     NonEmptyReader(usize),
+
+    #[error("Unexpected structure (Expected: {structure})")]
+    UnexpectedStructure { structure: String },
+
+    #[error("Invalid cell path (Path: {path}): {message}")]
+    InvalidPath { path: String, message: String },
+
+    #[error("Failed to parse reference (Index: {ref_index}, bit offset: {bit_offset}): {source}")]
+    InReference {
+        ref_index: usize,
+        bit_offset: usize,
+        #[source]
+        source: Box<TonCellError>,
+    },
+}
+
+/// Stable, machine-readable kind of [`TonCellError`].
+///
+/// Codes are never reused or renumbered, so they can be persisted or sent over the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TonCellErrorCode {
+    BagOfCellsDeserialization = 1,
+    BagOfCellsSerialization = 2,
+    CellBuilder = 3,
+    CellParser = 4,
+    Internal = 5,
+    InvalidIndex = 6,
+    InvalidAddressType = 7,
+    NonEmptyReader = 8,
+    UnexpectedStructure = 9,
+    InvalidPath = 10,
+}
+
+impl TonCellErrorCode {
+    /// All codes, in the order of their values.
+    pub const ALL: [TonCellErrorCode; 10] = [
+        TonCellErrorCode::BagOfCellsDeserialization,
+        TonCellErrorCode::BagOfCellsSerialization,
+        TonCellErrorCode::CellBuilder,
+        TonCellErrorCode::CellParser,
+        TonCellErrorCode::Internal,
+        TonCellErrorCode::InvalidIndex,
+        TonCellErrorCode::InvalidAddressType,
+        TonCellErrorCode::NonEmptyReader,
+        TonCellErrorCode::UnexpectedStructure,
+        TonCellErrorCode::InvalidPath,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TonCellErrorCode::BagOfCellsDeserialization => "BOC_DESERIALIZATION",
+            TonCellErrorCode::BagOfCellsSerialization => "BOC_SERIALIZATION",
+            TonCellErrorCode::CellBuilder => "CELL_BUILDER",
+            TonCellErrorCode::CellParser => "CELL_PARSER",
+            TonCellErrorCode::Internal => "INTERNAL",
+            TonCellErrorCode::InvalidIndex => "INVALID_INDEX",
+            TonCellErrorCode::InvalidAddressType => "INVALID_ADDRESS_TYPE",
+            TonCellErrorCode::NonEmptyReader => "NON_EMPTY_READER",
+            TonCellErrorCode::UnexpectedStructure => "UNEXPECTED_STRUCTURE",
+            TonCellErrorCode::InvalidPath => "INVALID_PATH",
+        }
+    }
+}
+
+impl TryFrom<i32> for TonCellErrorCode {
+    type Error = i32;
+
+    /// Returns the kind of a code, or the code itself if it's unknown.
+    fn try_from(code: i32) -> Result<Self, Self::Error> {
+        TonCellErrorCode::ALL
+            .into_iter()
+            .find(|kind| *kind as i32 == code)
+            .ok_or(code)
+    }
 }
 
 pub trait MapTonCellError<R, E>
 where
-    E: std::error::Error,
+    E: core::fmt::Display,
 {
     fn map_boc_deserialization_error(self) -> Result<R, TonCellError>;
 
@@ -42,7 +122,7 @@ where
 
 impl<R, E> MapTonCellError<R, E> for Result<R, E>
 where
-    E: std::error::Error,
+    E: core::fmt::Display,
 {
     fn map_boc_serialization_error(self) -> Result<R, TonCellError> {
         self.map_err(|e| TonCellError::boc_serialization_error(e))
@@ -62,6 +142,91 @@ where
 }
 
 impl TonCellError {
+    /// Returns the code of the root cause of the error.
+    pub fn code(&self) -> TonCellErrorCode {
+        match self.root_cause() {
+            TonCellError::BagOfCellsDeserializationError(_) => {
+                TonCellErrorCode::BagOfCellsDeserialization
+            }
+            TonCellError::BagOfCellsSerializationError(_) => {
+                TonCellErrorCode::BagOfCellsSerialization
+            }
+            TonCellError::CellBuilderError(_) => TonCellErrorCode::CellBuilder,
+            TonCellError::CellParserError(_) => TonCellErrorCode::CellParser,
+            TonCellError::InternalError(_) => TonCellErrorCode::Internal,
+            TonCellError::InvalidIndex { .. } => TonCellErrorCode::InvalidIndex,
+            TonCellError::InvalidAddressType(_) => TonCellErrorCode::InvalidAddressType,
+            TonCellError::NonEmptyReader(_) => TonCellErrorCode::NonEmptyReader,
+            TonCellError::UnexpectedStructure { .. } => TonCellErrorCode::UnexpectedStructure,
+            TonCellError::InvalidPath { .. } => TonCellErrorCode::InvalidPath,
+            TonCellError::InReference { .. } => unreachable!("root cause is never a reference"),
+        }
+    }
+
+    /// Returns the innermost error, skipping the reference positions.
+    pub fn root_cause(&self) -> &TonCellError {
+        let mut error = self;
+        while let TonCellError::InReference { source, .. } = error {
+            error = source;
+        }
+        error
+    }
+
+    /// Returns the name of the structure that failed to parse, if known.
+    pub fn structure(&self) -> Option<&str> {
+        match self.root_cause() {
+            TonCellError::UnexpectedStructure { structure } => Some(structure),
+            _ => None,
+        }
+    }
+
+    /// Returns the reference indices leading from the parsed cell to the failing one.
+    pub fn reference_path(&self) -> Vec<usize> {
+        let mut path = vec![];
+        let mut error = self;
+        while let TonCellError::InReference {
+            ref_index, source, ..
+        } = error
+        {
+            path.push(*ref_index);
+            error = source;
+        }
+        path
+    }
+
+    /// Returns [`reference_path`](Self::reference_path) as a [`CellPath`], displayed as `0.1.3`.
+    pub fn cell_path(&self) -> CellPath {
+        CellPath::from(self.reference_path())
+    }
+
+    pub fn invalid_path<P, M>(path: P, message: M) -> TonCellError
+    where
+        P: ToString,
+        M: ToString,
+    {
+        TonCellError::InvalidPath {
+            path: path.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    pub fn unexpected_structure<T>(structure: T) -> TonCellError
+    where
+        T: ToString,
+    {
+        TonCellError::UnexpectedStructure {
+            structure: structure.to_string(),
+        }
+    }
+
+    pub fn in_reference(self, ref_index: usize, bit_offset: usize) -> TonCellError {
+        TonCellError::InReference {
+            ref_index,
+            bit_offset,
+            source: Box::new(self),
+        }
+    }
+
     pub fn boc_serialization_error<T>(e: T) -> TonCellError
     where
         T: ToString,
@@ -0,0 +1,155 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt::Display;
+
+use crate::cell::{Cell, CellParser, TonCellError};
+
+/// Strictness of the block and transaction loaders, see [`Cell::load_block_with_mode`] and
+/// [`Cell::load_transaction_with_mode`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ParseMode {
+    /// Fails on unknown tags, on values that don't fit their fields, e.g. addresses of other
+    /// lengths than 256 bits, and on data left after the root structure or after the
+    /// structures of its references.
+    #[default]
+    Strict,
+    /// Skips the optional structures that fail to load, e.g. because of tags added by future
+    /// versions of the node, and records a warning for each of them. Values that don't fit
+    /// their fields are left default, also with a warning.
+    Lenient,
+}
+
+/// Structure skipped by a lenient loader.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    /// TL-B type of the skipped structure, e.g. `TransactionDescr`.
+    pub structure: &'static str,
+    pub message: String,
+}
+
+/// Value loaded with a [`ParseMode`] along with the warnings of the lenient mode.
+#[derive(Debug, Clone)]
+pub struct Parsed<T> {
+    pub value: T,
+    /// Always empty in the strict mode.
+    pub warnings: Vec<ParseWarning>,
+}
+
+impl<T> Parsed<T> {
+    pub fn is_complete(&self) -> bool {
+        self.warnings.is_empty()
+    }
+}
+
+/// Mode and warnings threaded through the loaders, shared by reference so that the loaders
+/// of dictionary values can capture it.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ParseContext {
+    /// `None` for the loaders without a mode, which fail like the strict mode but keep the
+    /// default values of lossy conversions, see [`ParseContext::converted`].
+    mode: Option<ParseMode>,
+    warnings: RefCell<Vec<ParseWarning>>,
+}
+
+impl ParseContext {
+    pub(crate) fn new(mode: ParseMode) -> ParseContext {
+        ParseContext {
+            mode: Some(mode),
+            warnings: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Returns the loaded optional structure, or in the lenient mode `None` with a warning if
+    /// it failed to load.
+    pub(crate) fn optional<T>(
+        &self,
+        structure: &'static str,
+        result: Result<Option<T>, TonCellError>,
+    ) -> Result<Option<T>, TonCellError> {
+        match (result, self.mode) {
+            (Err(e), Some(ParseMode::Lenient)) => {
+                self.warn(structure, e.to_string());
+                Ok(None)
+            }
+            (result, _) => result,
+        }
+    }
+
+    /// Returns the converted value, or if the conversion failed, e.g. because the value
+    /// doesn't fit the field, fails in the strict mode and returns the default value with a
+    /// warning in the lenient mode.
+    pub(crate) fn converted<T: Default, E: Display>(
+        &self,
+        structure: &'static str,
+        result: Result<T, E>,
+    ) -> Result<T, TonCellError> {
+        match (result, self.mode) {
+            (Ok(value), _) => Ok(value),
+            (Err(e), Some(ParseMode::Strict)) => Err(TonCellError::cell_parser_error(format!(
+                "{}: {}",
+                structure, e
+            ))),
+            (Err(e), Some(ParseMode::Lenient)) => {
+                self.warn(structure, e.to_string());
+                Ok(T::default())
+            }
+            (Err(_), None) => Ok(T::default()),
+        }
+    }
+
+    /// Checks that all bits and references of the cell are loaded.
+    pub(crate) fn check_trailing_data(
+        &self,
+        structure: &'static str,
+        cell: &Cell,
+        ref_index: usize,
+        parser: &mut CellParser,
+    ) -> Result<(), TonCellError> {
+        let remaining_bits = parser.remaining_bits();
+        let remaining_refs = cell.references.len().saturating_sub(ref_index);
+        if remaining_bits == 0 && remaining_refs == 0 {
+            return Ok(());
+        }
+        let message = format!(
+            "{} bits and {} references left after {}",
+            remaining_bits, remaining_refs, structure
+        );
+        match self.mode {
+            Some(ParseMode::Lenient) => {
+                self.warn(structure, message);
+                Ok(())
+            }
+            _ => Err(TonCellError::cell_parser_error(message)),
+        }
+    }
+
+    /// Checks the trailing data of a referenced structure like [`Self::check_trailing_data`].
+    /// The loaders without a mode don't check them, as they always ignored them.
+    pub(crate) fn check_ref_trailing_data(
+        &self,
+        structure: &'static str,
+        cell: &Cell,
+        ref_index: usize,
+        parser: &mut CellParser,
+    ) -> Result<(), TonCellError> {
+        if self.mode.is_none() {
+            return Ok(());
+        }
+        self.check_trailing_data(structure, cell, ref_index, parser)
+    }
+
+    pub(crate) fn finish<T>(self, value: T) -> Parsed<T> {
+        Parsed {
+            value,
+            warnings: self.warnings.into_inner(),
+        }
+    }
+
+    fn warn(&self, structure: &'static str, message: String) {
+        log::warn!("skipping {}: {}", structure, message);
+        self.warnings
+            .borrow_mut()
+            .push(ParseWarning { structure, message });
+    }
+}
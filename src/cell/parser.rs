@@ -1,22 +1,37 @@
-use std::io::Cursor;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 
-use bitstream_io::{BigEndian, BitRead, BitReader};
-use log::debug;
+use bitstream_io::{BigEndian, BitRead2 as BitRead, BitReader};
 use num_bigint::{BigInt, BigUint, Sign};
 use num_traits::identities::Zero;
 use num_traits::FromPrimitive;
 
 use crate::address::TonAddress;
+use crate::cell::dump::dump_bits;
 use crate::cell::util::*;
-use crate::cell::{MapTonCellError, TonCellError};
-use crate::responses::VarUInteger;
+use crate::cell::{
+    key_extractor_u32, ArcCell, BitString, CellBuilder, CellSlice, GenericDictLoader,
+    MapTonCellError, TonCellError, HASH_BYTES,
+};
+use crate::coins::Coins;
+use crate::compat::io::Cursor;
+use crate::compat::HashMap;
+use crate::responses::{Anycast, GlobalVersion, ShardIdent, VarUInteger};
+use crate::trace::parse_event;
 
 pub struct CellParser<'a> {
     pub(crate) bit_len: usize,
-    pub(crate) bit_reader: BitReader<Cursor<&'a Vec<u8>>, BigEndian>,
+    pub(crate) bit_reader: BitReader<Cursor<&'a [u8]>, BigEndian>,
+    pub(crate) references: &'a [ArcCell],
+    pub(crate) next_ref: usize,
 }
 
 impl CellParser<'_> {
+    /// Returns the number of bits loaded so far.
+    pub fn bit_offset(&mut self) -> usize {
+        self.bit_reader.position_in_bits().unwrap_or_default() as usize
+    }
+
     pub fn remaining_bits(&mut self) -> usize {
         let pos = self.bit_reader.position_in_bits().unwrap_or_default() as usize;
         if self.bit_len > pos {
@@ -31,6 +46,28 @@ impl CellParser<'_> {
         self.remaining_bits() / 8
     }
 
+    /// Returns up to `n_bits` bits before and after the cursor in binary and hex, with the
+    /// cursor marked by `|`, to inspect the data a loader failed on.
+    ///
+    /// ```
+    /// # use tonlib::cell::CellBuilder;
+    /// let cell = CellBuilder::new().store_u32(32, 0x11ef55aa)?.store_u8(4, 5)?.build()?;
+    /// let mut parser = cell.parser();
+    /// parser.load_u16(16)?;
+    /// assert_eq!(
+    ///     parser.debug_dump(8),
+    ///     ">     8: 11101111|01010101                    ef55\n",
+    /// );
+    /// # Ok::<(), tonlib::cell::TonCellError>(())
+    /// ```
+    pub fn debug_dump(&mut self, n_bits: usize) -> String {
+        let position = self.bit_offset();
+        let end = position + self.remaining_bits().min(n_bits);
+        let start = position.saturating_sub(n_bits);
+        let data = *self.bit_reader.clone().into_reader().get_ref();
+        dump_bits(data, start, end, Some(position))
+    }
+
     pub fn load_bit(&mut self) -> Result<bool, TonCellError> {
         self.bit_reader.read_bit().map_cell_parser_error()
     }
@@ -42,9 +79,8 @@ impl CellParser<'_> {
     }
 
     pub fn load_i8(&mut self, bit_len: usize) -> Result<i8, TonCellError> {
-        self.bit_reader
-            .read::<i8>(bit_len as u32)
-            .map_cell_parser_error()
+        // Reading signed types directly panics in bitstream-io if the value spans a byte boundary
+        Ok(self.load_u8(bit_len)? as i8)
     }
 
     pub fn load_u16(&mut self, bit_len: usize) -> Result<u16, TonCellError> {
@@ -54,9 +90,7 @@ impl CellParser<'_> {
     }
 
     pub fn load_i16(&mut self, bit_len: usize) -> Result<i16, TonCellError> {
-        self.bit_reader
-            .read::<i16>(bit_len as u32)
-            .map_cell_parser_error()
+        Ok(self.load_u16(bit_len)? as i16)
     }
 
     pub fn load_u32(&mut self, bit_len: usize) -> Result<u32, TonCellError> {
@@ -66,9 +100,7 @@ impl CellParser<'_> {
     }
 
     pub fn load_i32(&mut self, bit_len: usize) -> Result<i32, TonCellError> {
-        self.bit_reader
-            .read::<i32>(bit_len as u32)
-            .map_cell_parser_error()
+        Ok(self.load_u32(bit_len)? as i32)
     }
 
     pub fn load_u64(&mut self, bit_len: usize) -> Result<u64, TonCellError> {
@@ -78,9 +110,7 @@ impl CellParser<'_> {
     }
 
     pub fn load_i64(&mut self, bit_len: usize) -> Result<i64, TonCellError> {
-        self.bit_reader
-            .read::<i64>(bit_len as u32)
-            .map_cell_parser_error()
+        Ok(self.load_u64(bit_len)? as i64)
     }
 
     pub fn load_uint(&mut self, bit_len: usize) -> Result<BigUint, TonCellError> {
@@ -166,6 +196,13 @@ impl CellParser<'_> {
         Ok(res)
     }
 
+    /// Loads the bits keeping their exact length, unlike [`CellParser::load_bits`] which pads
+    /// them to whole bytes.
+    pub fn load_bitstring(&mut self, num_bits: usize) -> Result<BitString, TonCellError> {
+        let data = self.load_bits(num_bits)?;
+        BitString::from_data(&data, num_bits)
+    }
+
     pub fn load_utf8(&mut self, num_bytes: usize) -> Result<String, TonCellError> {
         let bytes = self.load_bytes(num_bytes)?;
         String::from_utf8(bytes).map_cell_parser_error()
@@ -176,12 +213,12 @@ impl CellParser<'_> {
         Ok(String::from_utf8_lossy(&bytes).to_string())
     }
 
-    pub fn load_coins(&mut self) -> Result<BigUint, TonCellError> {
+    pub fn load_coins(&mut self) -> Result<Coins, TonCellError> {
         let num_bytes = self.load_u8(4)?;
         if num_bytes == 0 {
-            Ok(BigUint::zero())
+            Ok(Coins::zero())
         } else {
-            self.load_uint((num_bytes * 8) as usize)
+            Ok(Coins::new(self.load_uint((num_bytes * 8) as usize)?))
         }
     }
 
@@ -196,7 +233,7 @@ impl CellParser<'_> {
                 self.bit_reader
                     .read_bytes(&mut hash_part)
                     .map_cell_parser_error()?;
-                let addr = TonAddress::new(wc as i32, &hash_part);
+                let addr = TonAddress::new(wc as i8 as i32, &hash_part);
                 Ok(addr)
             }
             _ => Err(TonCellError::InvalidAddressType(tp)),
@@ -226,31 +263,38 @@ impl CellParser<'_> {
             .map_cell_parser_error()
     }
 
-    pub fn load_shard_ident(&mut self) -> Result<(), TonCellError> {
+    pub fn load_shard_ident(&mut self) -> Result<ShardIdent, TonCellError> {
         let ident = self.load_uint(2)?;
         if !ident.is_zero() {
-            return Err(TonCellError::cell_parser_error("not a ShardIdent"));
+            return Err(TonCellError::unexpected_structure("ShardIdent"));
         }
         let shard_pfx_bits = self.load_uint_le(60)?;
         let workchain_id = self.load_i32(32)?;
         let shard_prefix = self.load_u64(64)?;
-
-        // FIXME: return shard ident struct
-        Ok(())
+        Ok(ShardIdent {
+            shard_pfx_bits: u32::try_from(shard_pfx_bits)
+                .map_err(TonCellError::cell_parser_error)?,
+            workchain_id,
+            shard_prefix,
+        })
     }
 
-    pub fn load_global_version(&mut self) -> Result<(), TonCellError> {
+    pub fn load_global_version(&mut self) -> Result<GlobalVersion, TonCellError> {
         let code = self.load_u8(8)?;
         if code != 0xc4 {
-            return Err(TonCellError::cell_parser_error("not a GlobalVersion"));
+            return Err(TonCellError::unexpected_structure("GlobalVersion"));
         }
         let version = self.load_u32(32)?;
         let capabilities = self.load_u64(64)?;
-        debug!(
-            "version and capabilities: {:?}, {:?}",
-            version, capabilities
+        parse_event!(
+            "loaded global version",
+            version = version,
+            capabilities = capabilities
         );
-        Ok(())
+        Ok(GlobalVersion {
+            version,
+            capabilities,
+        })
     }
 
     pub fn load_label(&mut self, m: usize) -> Result<(BigUint, usize), TonCellError> {
@@ -296,19 +340,72 @@ impl CellParser<'_> {
         Ok(data)
     }
 
+    /// Loads `ExtraCurrencyCollection`, a `HashmapE 32 (VarUInteger 32)` of amounts by
+    /// currency ids, see [`CellBuilder::store_extra_currencies`].
+    pub fn load_extra_currencies(&mut self) -> Result<HashMap<u32, BigUint>, TonCellError> {
+        if !self.load_bit()? {
+            return Ok(HashMap::new());
+        }
+        let value_extractor = |slice: &CellSlice| Ok(slice.parser()?.load_var_uinteger(32)?.value);
+        let loader = GenericDictLoader::new(key_extractor_u32, value_extractor, 32);
+        self.next_reference()?.load_generic_dict(&loader)
+    }
+
     pub fn load_sig_pub_key(&mut self) -> Result<Vec<u8>, TonCellError> {
         let magic = self.load_u32(32)?;
         if magic != 0x8e81278a {
-            return Err(TonCellError::cell_parser_error("Not a SigPubKey"));
+            return Err(TonCellError::unexpected_structure("SigPubKey"));
         }
         let pubkey = self.load_bytes(32)?;
         // println!("pub key: {:?}", hex::encode(pubkey.clone()));
         Ok(pubkey)
     }
 
-    pub fn load_anycast(&mut self) -> Result<(), TonCellError> {
+    pub fn load_anycast(&mut self) -> Result<Anycast, TonCellError> {
         let depth = self.load_uint_le(30)?;
-        self.load_bits(usize::try_from(depth).map_err(TonCellError::cell_parser_error)?)?;
-        Ok(())
+        let depth = u32::try_from(depth).map_err(TonCellError::cell_parser_error)?;
+        let rewrite_pfx = self.load_bits(depth as usize)?;
+        Ok(Anycast { depth, rewrite_pfx })
+    }
+
+    /// Returns the number of references not yet loaded with [`CellParser::next_reference`].
+    pub fn remaining_references(&self) -> usize {
+        self.references.len() - self.next_ref
+    }
+
+    pub fn next_reference(&mut self) -> Result<ArcCell, TonCellError> {
+        let reference = self
+            .references
+            .get(self.next_ref)
+            .ok_or(TonCellError::InvalidIndex {
+                idx: self.next_ref,
+                ref_count: self.references.len(),
+            })?;
+        self.next_ref += 1;
+        Ok(reference.clone())
+    }
+
+    /// Loads `HashmapE n X` and returns the hash of its root, or `None` for an empty
+    /// dictionary, see [`Cell::dict_hash`](crate::cell::Cell::dict_hash).
+    pub fn load_dict_hash(
+        &mut self,
+        key_bit_len: usize,
+    ) -> Result<Option<[u8; HASH_BYTES]>, TonCellError> {
+        if !self.load_bit()? {
+            return Ok(None);
+        }
+        self.next_reference()?.dict_hash(key_bit_len).map(Some)
+    }
+
+    /// Returns a builder containing all remaining bits and references of the parser.
+    ///
+    /// This allows to modify a field of a parsed cell and serialize it back:
+    /// the fields before it are stored to a new builder, the field is stored with
+    /// the new value and the remainder is appended with this method or
+    /// [`CellBuilder::store_remaining`].
+    pub fn remainder_to_builder(&mut self) -> Result<CellBuilder, TonCellError> {
+        let mut builder = CellBuilder::new();
+        builder.store_remaining(self)?;
+        Ok(builder)
     }
 }
@@ -0,0 +1,158 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Deref;
+use core::str::FromStr;
+
+use crate::cell::{Cell, TonCellError};
+
+/// Reference indices leading from a cell to one of its descendants.
+///
+/// Paths are written as the indices separated by dots, e.g. `0.1.3` is the fourth reference of
+/// the second reference of the first reference of the cell. The empty path is the cell itself.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct CellPath(Vec<usize>);
+
+impl CellPath {
+    pub fn new(indices: Vec<usize>) -> CellPath {
+        CellPath(indices)
+    }
+
+    pub fn into_inner(self) -> Vec<usize> {
+        self.0
+    }
+}
+
+impl Deref for CellPath {
+    type Target = [usize];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Vec<usize>> for CellPath {
+    fn from(indices: Vec<usize>) -> Self {
+        CellPath(indices)
+    }
+}
+
+impl From<&[usize]> for CellPath {
+    fn from(indices: &[usize]) -> Self {
+        CellPath(indices.to_vec())
+    }
+}
+
+impl fmt::Display for CellPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, index) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(".")?;
+            }
+            write!(f, "{}", index)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for CellPath {
+    type Err = TonCellError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Ok(CellPath::default());
+        }
+        s.split('.')
+            .map(|index| {
+                index.parse::<usize>().map_err(|_| {
+                    TonCellError::invalid_path(s, format!("invalid reference index `{}`", index))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(CellPath)
+    }
+}
+
+impl Cell {
+    /// Returns the descendant at the reference indices `path`, or the cell itself if `path` is
+    /// empty.
+    pub fn by_path(&self, path: &[usize]) -> Result<&Cell, TonCellError> {
+        let mut cell = self;
+        for (depth, &idx) in path.iter().enumerate() {
+            cell = cell.references.get(idx).ok_or_else(|| {
+                TonCellError::invalid_path(
+                    CellPath::from(path),
+                    format!(
+                        "no reference {} at `{}` (reference count: {})",
+                        idx,
+                        CellPath::from(&path[..depth]),
+                        cell.references.len()
+                    ),
+                )
+            })?;
+        }
+        Ok(cell)
+    }
+
+    /// Returns the descendant at the path written as `0.1.3`, see [`CellPath`].
+    pub fn by_path_str(&self, path: &str) -> Result<&Cell, TonCellError> {
+        self.by_path(&path.parse::<CellPath>()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cell::{Cell, CellBuilder, CellPath, TonCellError, TonCellErrorCode};
+
+    #[test]
+    fn cell_by_path_works() -> anyhow::Result<()> {
+        let leaf = CellBuilder::new().store_u8(8, 3)?.build()?;
+        let inner = CellBuilder::new()
+            .store_u8(8, 1)?
+            .store_child(CellBuilder::new().store_u8(8, 2)?.build()?)?
+            .store_child(leaf.clone())?
+            .build()?;
+        let root = CellBuilder::new().store_child(inner.clone())?.build()?;
+
+        assert_eq!(root.by_path(&[])?, &root);
+        assert_eq!(root.by_path(&[0])?, &inner);
+        assert_eq!(root.by_path(&[0, 1])?, &leaf);
+        assert_eq!(root.by_path_str("0.1")?, &leaf);
+        assert_eq!(root.by_path_str("")?, &root);
+
+        let error = root.by_path_str("0.2.0").unwrap_err();
+        assert_eq!(error.code(), TonCellErrorCode::InvalidPath);
+        assert_eq!(
+            error,
+            TonCellError::invalid_path("0.2.0", "no reference 2 at `0` (reference count: 2)")
+        );
+        let error = root.by_path_str("0.x").unwrap_err();
+        assert_eq!(
+            error,
+            TonCellError::invalid_path("0.x", "invalid reference index `x`")
+        );
+        assert!(root.by_path_str("0.").is_err());
+
+        let path: CellPath = "0.1.3".parse()?;
+        assert_eq!(&*path, &[0, 1, 3]);
+        assert_eq!(path.to_string(), "0.1.3");
+        assert_eq!(CellPath::default().to_string(), "");
+        Ok(())
+    }
+
+    #[test]
+    fn error_cell_path_locates_failing_cell() -> anyhow::Result<()> {
+        let hash_update = CellBuilder::new().store_u8(8, 0x73)?.build()?;
+        let inner = CellBuilder::new()
+            .store_child(hash_update.clone())?
+            .build()?;
+        let error = inner
+            .load_ref_if_exist(&mut 0, Some(Cell::load_hash_update))
+            .unwrap_err()
+            .in_reference(0, 0);
+        let root = CellBuilder::new().store_child(inner)?.build()?;
+
+        assert_eq!(error.cell_path().to_string(), "0.0");
+        assert_eq!(root.by_path(&error.cell_path())?, &hash_update);
+        Ok(())
+    }
+}
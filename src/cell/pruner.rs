@@ -0,0 +1,297 @@
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+use crate::address::TonAddress;
+use crate::cell::{ArcCell, BagOfCells, Cell, CellType, TonCellError, DEPTH_BYTES, HASH_BYTES};
+
+/// Tag of `ShardStateUnsplit`.
+pub const SHARD_STATE_UNSPLIT_TAG: u32 = 0x9023afe2;
+
+/// Index of the `accounts:^ShardAccounts` reference of `ShardStateUnsplit`.
+pub(crate) const SHARD_STATE_ACCOUNTS_REF: usize = 1;
+
+/// Returns a copy of the tree in which every subtree rejected by `keep` is replaced by a
+/// pruned branch carrying its hash and depth, so the copy has the same hash as the original
+/// tree.
+///
+/// `keep` is called with the path of reference indices from the root and the cell. The
+/// children of rejected cells are not visited. Trees without calculated hashes, e.g. made by
+/// [`CellBuilder`](crate::cell::CellBuilder), are hashed first.
+pub fn prune_cells<F>(root: &ArcCell, mut keep: F) -> Result<ArcCell, TonCellError>
+where
+    F: FnMut(&[usize], &Cell) -> bool,
+{
+    let root = with_hashes(root)?;
+    let mut path = Vec::new();
+    let (pruned, _) = prune_subtree(&root, &mut path, &mut keep)?;
+    Ok(pruned)
+}
+
+/// Prunes a `Hashmap n X` or `HashmapAug n X Y` dictionary, keeping only the edges leading
+/// to the given keys and the complete leaves of these keys.
+///
+/// `root` is the root edge of the dictionary, i.e. the reference of `HashmapE` or
+/// `HashmapAugE`. Keys missing in the dictionary are ignored.
+pub fn prune_dict(
+    root: &ArcCell,
+    key_bit_len: usize,
+    keys: &[BigUint],
+) -> Result<ArcCell, TonCellError> {
+    let root = with_hashes(root)?;
+    let (pruned, _) = prune_dict_edge(&root, &BigUint::zero(), 0, key_bit_len, keys)?;
+    Ok(pruned)
+}
+
+/// Prunes a `ShardStateUnsplit`, keeping only the root, the `ShardAccounts` dictionary
+/// restricted to the given accounts and the complete states of these accounts.
+///
+/// ```raw
+/// shard_state#9023afe2 ... out_msg_queue_info:^OutMsgQueueInfo before_split:(## 1)
+///   accounts:^ShardAccounts ^[ ... ] custom:(Maybe ^McStateExtra) = ShardStateUnsplit;
+/// _ (HashmapAugE 256 ShardAccount DepthBalanceInfo) = ShardAccounts;
+/// ```
+pub fn prune_shard_state(
+    state: &ArcCell,
+    accounts: &[TonAddress],
+) -> Result<ArcCell, TonCellError> {
+    let state = with_hashes(state)?;
+    if state.parser().load_u32(32)? != SHARD_STATE_UNSPLIT_TAG {
+        return Err(TonCellError::unexpected_structure("ShardStateUnsplit"));
+    }
+    let keys: Vec<BigUint> = accounts
+        .iter()
+        .map(|account| BigUint::from_bytes_be(&account.hash_part))
+        .collect();
+    let mut references = Vec::with_capacity(state.references.len());
+    for (ref_index, child) in state.references.iter().enumerate() {
+        if ref_index != SHARD_STATE_ACCOUNTS_REF {
+            references.push(Arc::new(pruned_branch(child)?));
+            continue;
+        }
+        // ahme_empty$0 or ahme_root$1 root:^(HashmapAug n X Y) extra:Y
+        let shard_accounts = match child.references.first() {
+            Some(edge) if child.parser().load_bit()? => {
+                let (edge, _) = prune_dict_edge(edge, &BigUint::zero(), 0, 256, &keys)?;
+                let mut shard_accounts_refs = child.references.clone();
+                shard_accounts_refs[0] = edge;
+                with_references(child, shard_accounts_refs)?
+            }
+            _ => child.as_ref().clone(),
+        };
+        references.push(Arc::new(shard_accounts));
+    }
+    Ok(Arc::new(with_references(&state, references)?))
+}
+
+/// Wraps a pruned tree into a Merkle proof cell:
+///
+/// ```raw
+/// !merkle_proof#03 {X:Type} virtual_hash:bits256 depth:uint16 virtual_root:^X = MERKLE_PROOF X;
+/// ```
+pub fn merkle_proof(pruned_root: &ArcCell) -> Result<Cell, TonCellError> {
+    let mut data = Vec::with_capacity(1 + HASH_BYTES + DEPTH_BYTES);
+    data.push(CellType::MerkleProofCell as u8);
+    data.extend_from_slice(pruned_root.hash_ref(0));
+    data.extend_from_slice(&(pruned_root.get_depth(Some(0)) as u16).to_be_bytes());
+    exotic_cell(data, vec![pruned_root.clone()])
+}
+
+/// Returns the pruned branch replacing the cell:
+///
+/// ```raw
+/// !pruned_branch#01 level_mask:uint8 hashes:(level * bits256) depths:(level * uint16)
+/// ```
+pub fn pruned_branch(cell: &Cell) -> Result<Cell, TonCellError> {
+    if cell.level_mask != 0 {
+        return Err(TonCellError::cell_builder_error(
+            "Only cells of level 0 can be pruned",
+        ));
+    }
+    let mut data = Vec::with_capacity(2 + HASH_BYTES + DEPTH_BYTES);
+    data.push(CellType::PrunnedBranchCell as u8);
+    data.push(1); // level_mask
+    data.extend_from_slice(cell.hash_ref(0));
+    data.extend_from_slice(&(cell.get_depth(Some(0)) as u16).to_be_bytes());
+    exotic_cell(data, vec![])
+}
+
+fn prune_subtree<F>(
+    cell: &ArcCell,
+    path: &mut Vec<usize>,
+    keep: &mut F,
+) -> Result<(ArcCell, bool), TonCellError>
+where
+    F: FnMut(&[usize], &Cell) -> bool,
+{
+    if !keep(path, cell) {
+        return Ok((Arc::new(pruned_branch(cell)?), true));
+    }
+    let mut references = Vec::with_capacity(cell.references.len());
+    let mut changed = false;
+    for (ref_index, child) in cell.references.iter().enumerate() {
+        path.push(ref_index);
+        let (child, child_changed) = prune_subtree(child, path, keep)?;
+        path.pop();
+        changed |= child_changed;
+        references.push(child);
+    }
+    if !changed {
+        return Ok((cell.clone(), false));
+    }
+    Ok((Arc::new(with_references(cell, references)?), true))
+}
+
+/// Prunes the edge whose label starts after the first `prefix_len` bits of the key.
+fn prune_dict_edge(
+    edge: &ArcCell,
+    prefix: &BigUint,
+    prefix_len: usize,
+    key_bit_len: usize,
+    keys: &[BigUint],
+) -> Result<(ArcCell, bool), TonCellError> {
+    let mut parser = edge.parser();
+    let (label, label_len) = parser.load_label(key_bit_len - prefix_len)?;
+    let prefix = (prefix << label_len) | label;
+    let prefix_len = prefix_len + label_len;
+    let shift = key_bit_len - prefix_len;
+    if !keys.iter().any(|key| key >> shift == prefix) {
+        return Ok((Arc::new(pruned_branch(edge)?), true));
+    }
+    if prefix_len == key_bit_len {
+        // Leaf of a requested key
+        return Ok((edge.clone(), false));
+    }
+    // Fork with the left and the right edges in the first two references, which are followed
+    // by references of the augmentation
+    let mut references = edge.references.clone();
+    let mut changed = false;
+    for (bit, child) in references.iter_mut().take(2).enumerate() {
+        let child_prefix = (&prefix << 1) | BigUint::from(bit as u8);
+        let (pruned, child_changed) =
+            prune_dict_edge(child, &child_prefix, prefix_len + 1, key_bit_len, keys)?;
+        *child = pruned;
+        changed |= child_changed;
+    }
+    if !changed {
+        return Ok((edge.clone(), false));
+    }
+    Ok((Arc::new(with_references(edge, references)?), true))
+}
+
+fn with_references(cell: &Cell, references: Vec<ArcCell>) -> Result<Cell, TonCellError> {
+    let mut result = Cell {
+        data: cell.data.clone(),
+        bit_len: cell.bit_len,
+        references,
+        cell_type: cell.cell_type,
+        level_mask: 0,
+        is_exotic: cell.is_exotic,
+        has_hashes: false,
+        proof: false,
+        hashes: vec![],
+        depth: vec![],
+    };
+    result.finalize()?;
+    Ok(result)
+}
+
+fn exotic_cell(data: Vec<u8>, references: Vec<ArcCell>) -> Result<Cell, TonCellError> {
+    let mut cell = Cell {
+        bit_len: data.len() * 8,
+        data: data.into(),
+        references,
+        cell_type: CellType::OrdinaryCell as u8,
+        level_mask: 0,
+        is_exotic: true,
+        has_hashes: false,
+        proof: false,
+        hashes: vec![],
+        depth: vec![],
+    };
+    cell.finalize()?;
+    Ok(cell)
+}
+
+/// Returns the tree with calculated hashes, serializing and parsing it if necessary.
+pub(super) fn with_hashes(root: &ArcCell) -> Result<ArcCell, TonCellError> {
+    if !root.hashes.is_empty() {
+        return Ok(root.clone());
+    }
+    let serial = BagOfCells::new(core::slice::from_ref(root)).serialize(false)?;
+    Ok(BagOfCells::parse(&serial)?.single_root()?.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use num_bigint::BigUint;
+
+    use crate::cell::{
+        merkle_proof, prune_cells, prune_dict, BagOfCells, Cell, CellBuilder, CellType, DictBuilder,
+    };
+
+    fn count_pruned(cell: &Cell) -> usize {
+        let own = (cell.cell_type == CellType::PrunnedBranchCell as u8) as usize;
+        own + cell
+            .references
+            .iter()
+            .map(|r| count_pruned(r))
+            .sum::<usize>()
+    }
+
+    #[test]
+    fn prune_dict_keeps_requested_keys() -> anyhow::Result<()> {
+        let mut dict = DictBuilder::new(32);
+        for key in 0..16u32 {
+            let value = CellBuilder::new()
+                .store_u32(32, key * 10)?
+                .store_child(CellBuilder::new().store_u32(32, key)?.build()?)?
+                .build()?;
+            dict.insert(BigUint::from(key * 1_000_003), value)?;
+        }
+        let root = Arc::new(dict.build()?.unwrap());
+        let key = BigUint::from(5u32 * 1_000_003);
+        let pruned = prune_dict(&root, 32, &[key])?;
+        assert_eq!(pruned.hash_ref(0).to_vec(), root.cell_hash()?);
+        assert_eq!(pruned.level_mask, 1);
+        assert!(count_pruned(&pruned) > 0);
+
+        // The proof is valid and survives a BoC round trip
+        let proof = merkle_proof(&pruned)?;
+        let serial = BagOfCells::from_root(proof).serialize(true)?;
+        let parsed = BagOfCells::parse(&serial)?;
+        let proof = parsed.single_root()?;
+        assert_eq!(proof.cell_type, CellType::MerkleProofCell as u8);
+        assert_eq!(proof.level_mask, 0);
+        assert_eq!(proof.reference(0)?.hash_ref(0).to_vec(), root.cell_hash()?);
+        Ok(())
+    }
+
+    #[test]
+    fn prune_cells_uses_predicate() -> anyhow::Result<()> {
+        let leaf = |value: u32| CellBuilder::new().store_u32(32, value)?.build();
+        let root = Arc::new(
+            CellBuilder::new()
+                .store_u8(8, 1)?
+                .store_child(leaf(1)?)?
+                .store_child(leaf(2)?)?
+                .build()?,
+        );
+        let pruned = prune_cells(&root, |path, _| path != [1])?;
+        assert_eq!(pruned.hash_ref(0).to_vec(), root.cell_hash()?);
+        assert_eq!(pruned.references[0].cell_type, CellType::OrdinaryCell as u8);
+        assert_eq!(
+            pruned.references[1].cell_type,
+            CellType::PrunnedBranchCell as u8
+        );
+
+        let unchanged = prune_cells(&root, |_, _| true)?;
+        assert_eq!(unchanged.level_mask, 0);
+        assert_eq!(unchanged.cell_hash()?, root.cell_hash()?);
+        Ok(())
+    }
+}
@@ -1,15 +1,15 @@
-use std::io::Cursor;
+use alloc::string::ToString;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
 
-use bitstream_io::{BigEndian, BitWrite, BitWriter, ByteRead, ByteReader};
+use bitstream_io::{BigEndian, BitWrite2 as BitWrite, BitWriter, ByteRead, ByteReader};
 use crc::Crc;
-use lazy_static::lazy_static;
-use log::debug;
 
-use crate::cell::{MapTonCellError, TonCellError};
+use crate::cell::{MapTonCellError, TonCellError, MAX_CELL_BITS, MAX_CELL_REFERENCES};
+use crate::compat::io::Cursor;
+use crate::trace::parse_event;
 
-lazy_static! {
-    pub static ref CRC_32_ISCSI: Crc<u32> = Crc::<u32>::new(&crc::CRC_32_ISCSI);
-}
+pub static CRC_32_ISCSI: Crc<u32> = Crc::<u32>::new(&crc::CRC_32_ISCSI);
 
 #[derive(PartialEq, Eq, Debug, Clone, Hash)]
 pub enum CellType {
@@ -38,8 +38,8 @@ impl CellType {
 ///
 /// References are stored as indices in BagOfCells.
 #[derive(PartialEq, Eq, Debug, Clone, Hash)]
-pub(crate) struct RawCell {
-    pub(crate) data: Vec<u8>,
+pub struct RawCell {
+    pub(crate) data: Arc<[u8]>,
     pub(crate) bit_len: usize,
     pub(crate) references: Vec<usize>,
     pub(crate) max_level: u8, // same as level_mask
@@ -48,31 +48,155 @@ pub(crate) struct RawCell {
     pub(crate) has_hashes: bool,
 }
 
+impl RawCell {
+    /// Creates a cell referencing the cells with specified indices in its bag of cells.
+    ///
+    /// The type of exotic cells is taken from their first byte. Fails if the length of the data
+    /// doesn't match `bit_len` or the cell has more than 1023 bits or 4 references.
+    pub fn new(
+        data: Vec<u8>,
+        bit_len: usize,
+        references: Vec<usize>,
+        level_mask: u8,
+        is_exotic: bool,
+    ) -> Result<RawCell, TonCellError> {
+        if bit_len > MAX_CELL_BITS {
+            return Err(TonCellError::boc_serialization_error(format!(
+                "Cell must contain at most {} bits, got {}",
+                MAX_CELL_BITS, bit_len
+            )));
+        }
+        if data.len() != bit_len.div_ceil(8) {
+            return Err(TonCellError::boc_serialization_error(format!(
+                "Cell of {} bits must contain {} bytes of data, got {}",
+                bit_len,
+                bit_len.div_ceil(8),
+                data.len()
+            )));
+        }
+        if references.len() > MAX_CELL_REFERENCES {
+            return Err(TonCellError::boc_serialization_error(format!(
+                "Cell must contain at most {} references, got {}",
+                MAX_CELL_REFERENCES,
+                references.len()
+            )));
+        }
+        let cell_type = match data.first() {
+            Some(cell_type) if is_exotic => *cell_type,
+            _ => CellType::OrdinaryCell as u8,
+        };
+        Ok(RawCell {
+            data: data.into(),
+            bit_len,
+            references,
+            max_level: level_mask,
+            cell_type,
+            is_exotic,
+            has_hashes: false,
+        })
+    }
+
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn bit_len(&self) -> usize {
+        self.bit_len
+    }
+
+    /// Returns the indices of the referenced cells in the bag of cells.
+    pub fn references(&self) -> &[usize] {
+        &self.references
+    }
+
+    pub fn level_mask(&self) -> u8 {
+        self.max_level
+    }
+
+    pub fn cell_type(&self) -> u8 {
+        self.cell_type
+    }
+
+    pub fn is_exotic(&self) -> bool {
+        self.is_exotic
+    }
+
+    /// Returns whether the hashes and depths of the cell were stored in the serialized bag.
+    pub fn has_hashes(&self) -> bool {
+        self.has_hashes
+    }
+}
+
 /// Raw representation of BagOfCells.
 ///
-/// `cells` must be topologically sorted.
+/// `cells` must be topologically sorted, i.e. cells only reference the cells following them.
+/// See [`BagOfCells::to_raw`](crate::cell::BagOfCells::to_raw) and
+/// [`BagOfCells::from_raw`](crate::cell::BagOfCells::from_raw) for the conversions.
 #[derive(PartialEq, Eq, Debug, Clone, Hash)]
-pub(crate) struct RawBagOfCells {
+pub struct RawBagOfCells {
     pub(crate) cells: Vec<RawCell>,
     pub(crate) roots: Vec<usize>,
 }
 
 const GENERIC_BOC_MAGIC: u32 = 0xb5ee9c72;
+/// ASCII `b5ee`: the generic magic of a bag of cells saved as hex text, as done by some older
+/// tools for proofs.
 const PROOF_BOC_MAGIC: u32 = 0x62356565;
-const _INDEXED_BOC_MAGIC: u32 = 0x68ff65f3;
-const _INDEXED_CRC32_MAGIC: u32 = 0xacc3a728;
+const INDEXED_BOC_MAGIC: u32 = 0x68ff65f3;
+const INDEXED_CRC32_MAGIC: u32 = 0xacc3a728;
 
 impl RawBagOfCells {
-    pub(crate) fn parse(serial: &[u8]) -> Result<RawBagOfCells, TonCellError> {
+    /// Creates a bag of cells in specified order, failing if the cells are not topologically
+    /// sorted or an index is out of bounds.
+    pub fn new(cells: Vec<RawCell>, roots: Vec<usize>) -> Result<RawBagOfCells, TonCellError> {
+        for (i, cell) in cells.iter().enumerate() {
+            if cell.references.iter().any(|r| *r <= i || *r >= cells.len()) {
+                return Err(TonCellError::boc_serialization_error(format!(
+                    "Cell {} must only reference the cells following it",
+                    i
+                )));
+            }
+        }
+        if let Some(root) = roots.iter().find(|r| **r >= cells.len()) {
+            return Err(TonCellError::boc_serialization_error(format!(
+                "Root {} is out of bounds",
+                root
+            )));
+        }
+        Ok(RawBagOfCells { cells, roots })
+    }
+
+    pub fn cells(&self) -> &[RawCell] {
+        &self.cells
+    }
+
+    /// Returns the indices of the root cells.
+    pub fn roots(&self) -> &[usize] {
+        &self.roots
+    }
+
+    /// Returns the offsets of the cells in the serialized cell data, as stored in the index.
+    pub fn cell_offsets(&self) -> Vec<u32> {
+        let num_ref_bytes = ref_size_bytes(self.cells.len());
+        let mut full_size = 0u32;
+        let mut index = Vec::<u32>::with_capacity(self.cells.len());
+        for cell in &self.cells {
+            index.push(full_size);
+            full_size += raw_cell_size(cell, num_ref_bytes);
+        }
+        index
+    }
+
+    pub fn parse(serial: &[u8]) -> Result<RawBagOfCells, TonCellError> {
         let cursor = Cursor::new(serial);
 
         // parse header
         let mut reader: ByteReader<Cursor<&[u8]>, BigEndian> =
             ByteReader::endian(cursor, BigEndian);
-        // serialized_boc#b5ee9c72
         let magic = reader.read::<u32>().map_boc_deserialization_error()?;
 
-        let (has_idx, has_crc32c, _has_cache_bits, size_bytes) = match magic {
+        let (has_idx, has_crc32c, _has_cache_bits, has_root_list, size_bytes) = match magic {
+            // serialized_boc#b5ee9c72
             GENERIC_BOC_MAGIC => {
                 // has_idx:(## 1) has_crc32c:(## 1) has_cache_bits:(## 1) flags:(## 2) { flags = 0 }
                 let header = reader.read::<u8>().map_boc_deserialization_error()?;
@@ -82,15 +206,29 @@ impl RawBagOfCells {
                 // size:(## 3) { size <= 4 }
                 let size = header & 0b0000_0111;
 
-                (has_idx, has_crc32c, has_cache_bits, size)
+                (has_idx, has_crc32c, has_cache_bits, true, size)
+            }
+            // serialized_boc_idx#68ff65f3 and serialized_boc_idx_crc32c#acc3a728 have a single
+            // root, the first cell, and always have an index
+            INDEXED_BOC_MAGIC | INDEXED_CRC32_MAGIC => {
+                // size:(## 8) { size <= 4 }
+                let size = reader.read::<u8>().map_boc_deserialization_error()?;
+                (true, magic == INDEXED_CRC32_MAGIC, false, false, size)
             }
+            PROOF_BOC_MAGIC => return parse_hex_text(serial),
             magic => {
                 return Err(TonCellError::boc_deserialization_error(format!(
-                    "Unsupported cell magic number: {:#}",
+                    "Unsupported cell magic number: {:#x}",
                     magic
                 )));
             }
         };
+        if size_bytes == 0 || size_bytes > 4 {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Invalid reference size: {}",
+                size_bytes
+            )));
+        }
         //   off_bytes:(## 8) { off_bytes <= 8 }
         let off_bytes = reader.read::<u8>().map_boc_deserialization_error()?;
         //cells:(##(size * 8))
@@ -103,8 +241,17 @@ impl RawBagOfCells {
         let _tot_cells_size = read_var_size(&mut reader, off_bytes)?;
         //   root_list:(roots * ##(size * 8))
         let mut root_list = vec![];
-        for _ in 0..roots {
-            root_list.push(read_var_size(&mut reader, size_bytes)?)
+        if has_root_list {
+            for _ in 0..roots {
+                root_list.push(read_var_size(&mut reader, size_bytes)?)
+            }
+        } else if roots == 1 {
+            root_list.push(0);
+        } else {
+            return Err(TonCellError::boc_deserialization_error(format!(
+                "Indexed bag of cells must have a single root, got {}",
+                roots
+            )));
         }
         //   index:has_idx?(cells * ##(off_bytes * 8))
         let mut index = vec![];
@@ -126,7 +273,7 @@ impl RawBagOfCells {
         let serial_size = serial.len();
 
         let total_bytes_unread = serial.len() - (cur_cursor / 8) as usize;
-        debug!("total bytes unread: {:?}", total_bytes_unread);
+        parse_event!("read cells header", total_bytes_unread = total_bytes_unread);
         if total_bytes_unread < _tot_cells_size {
             return Err(TonCellError::boc_deserialization_error(
                 "Not enough bytes for cells data",
@@ -157,7 +304,7 @@ impl RawBagOfCells {
         })
     }
 
-    pub(crate) fn serialize(&self, has_crc32: bool) -> Result<Vec<u8>, TonCellError> {
+    pub fn serialize(&self, has_crc32: bool) -> Result<Vec<u8>, TonCellError> {
         //Based on https://github.com/toncenter/tonweb/blob/c2d5d0fc23d2aec55a0412940ce6e580344a288c/src/boc/Cell.js#L198
 
         let root_count = self.roots.len();
@@ -168,15 +315,12 @@ impl RawBagOfCells {
             )));
         }
 
-        let num_ref_bits = 32 - (self.cells.len() as u32).leading_zeros();
-        let num_ref_bytes = (num_ref_bits + 7) / 8;
-
-        let mut full_size = 0u32;
-        let mut index = Vec::<u32>::with_capacity(self.cells.len());
-        for cell in &self.cells {
-            index.push(full_size);
-            full_size += raw_cell_size(cell, num_ref_bytes);
-        }
+        let num_ref_bytes = ref_size_bytes(self.cells.len());
+        let full_size: u32 = self
+            .cells
+            .iter()
+            .map(|cell| raw_cell_size(cell, num_ref_bytes))
+            .sum();
 
         let num_offset_bits = 32 - full_size.leading_zeros();
         let num_offset_bytes = (num_offset_bits + 7) / 8;
@@ -215,9 +359,10 @@ impl RawBagOfCells {
         writer
             .write(8 * num_offset_bytes, full_size)
             .map_boc_serialization_error()?;
+        let root = self.roots.first().copied().unwrap_or_default();
         writer
-            .write(8 * num_ref_bytes, 0)
-            .map_boc_serialization_error()?; // Root should have index 0
+            .write(8 * num_ref_bytes, root as u32)
+            .map_boc_serialization_error()?;
 
         for cell in &self.cells {
             write_raw_cell(&mut writer, cell, num_ref_bytes)?;
@@ -240,6 +385,13 @@ impl RawBagOfCells {
     }
 }
 
+/// Parses a bag of cells saved as hex text.
+fn parse_hex_text(serial: &[u8]) -> Result<RawBagOfCells, TonCellError> {
+    let text = core::str::from_utf8(serial).map_boc_deserialization_error()?;
+    let boc = hex::decode(text.trim()).map_boc_deserialization_error()?;
+    RawBagOfCells::parse(&boc)
+}
+
 fn read_cell(
     reader: &mut ByteReader<Cursor<&[u8]>, BigEndian>,
     size: u8,
@@ -300,7 +452,7 @@ fn read_cell(
         CellType::OrdinaryCell as u8
     };
     let cell = RawCell {
-        data,
+        data: data.into(),
         bit_len,
         references,
         max_level,
@@ -325,6 +477,11 @@ fn get_level_from_mask(mask: u8) -> u8 {
     return n + 1;
 }
 
+fn ref_size_bytes(cell_count: usize) -> u32 {
+    let num_ref_bits = 32 - (cell_count as u32).leading_zeros();
+    num_ref_bits.div_ceil(8)
+}
+
 fn raw_cell_size(cell: &RawCell, ref_size_bytes: u32) -> u32 {
     let data_len = (cell.bit_len + 7) / 8;
     2 + data_len as u32 + cell.references.len() as u32 * ref_size_bytes
@@ -335,14 +492,14 @@ fn write_raw_cell(
     cell: &RawCell,
     ref_size_bytes: u32,
 ) -> Result<(), TonCellError> {
-    let level = 0u32; // TODO: Support
-    let is_exotic = 0u32; // TODO: Support
+    let level_mask = (cell.max_level & 7) as u32;
+    let is_exotic = cell.is_exotic as u32;
     let num_refs = cell.references.len() as u32;
-    let d1 = num_refs + is_exotic * 8 + level * 32;
+    let d1 = num_refs + is_exotic * 8 + level_mask * 32;
 
     let padding_bits = cell.bit_len % 8;
     let full_bytes = padding_bits == 0;
-    let data = cell.data.as_slice();
+    let data = &cell.data[..];
     let data_len_bytes = (cell.bit_len + 7) / 8;
     // data_len_bytes <= 128 by spec, but d2 must be u8 by spec as well
     let d2 = (data_len_bytes * 2 - if full_bytes { 0 } else { 1 }) as u8; //subtract 1 if the last byte is not full
@@ -391,11 +548,12 @@ mod tests {
     use tokio_test::assert_ok;
 
     use super::*;
+    use crate::cell::BagOfCells;
 
     #[test]
     fn test_raw_cell_serialize() {
         let raw_cell = RawCell {
-            data: vec![1; 128],
+            data: vec![1; 128].into(),
             bit_len: 1023,
             references: vec![],
             max_level: 255,
@@ -409,4 +567,74 @@ mod tests {
         };
         let _res = assert_ok!(raw_bag.serialize(false));
     }
+
+    #[test]
+    fn test_raw_bag_custom_order() -> anyhow::Result<()> {
+        let child = RawCell::new(vec![0xab], 8, vec![], 0, false)?;
+        let root = RawCell::new(vec![0xcd, 0x80], 9, vec![0], 0, false)?;
+        assert!(RawCell::new(vec![], 9, vec![], 0, false).is_err());
+        assert!(RawCell::new(vec![0xab, 0xcd, 0xef], 9, vec![], 0, false).is_err());
+        assert!(RawCell::new(vec![0; 128], 1024, vec![], 0, false).is_err());
+        assert!(RawCell::new(vec![], 0, vec![1, 2, 3, 4, 5], 0, false).is_err());
+        assert!(RawBagOfCells::new(vec![child.clone(), root.clone()], vec![1]).is_err());
+        assert!(RawBagOfCells::new(vec![root.clone(), child.clone()], vec![2]).is_err());
+
+        let mut root = root;
+        root.references = vec![1];
+        let raw_bag = RawBagOfCells::new(vec![root, child], vec![0])?;
+        assert_eq!(raw_bag.roots(), [0]);
+        assert_eq!(raw_bag.cells()[0].references(), [1]);
+        assert_eq!(raw_bag.cells()[1].data(), [0xab]);
+        assert_eq!(raw_bag.cell_offsets(), [0, 5]);
+
+        let serial = raw_bag.serialize(true)?;
+        assert_eq!(RawBagOfCells::parse(&serial)?, raw_bag);
+        let boc = BagOfCells::from_raw(raw_bag.clone())?;
+        assert_eq!(BagOfCells::parse(&serial)?, boc);
+        let root = boc.single_root()?;
+        assert_eq!(root.bit_len, 9);
+        assert_eq!(root.reference(0)?.data[..], [0xab]);
+        assert_eq!(boc.to_raw()?, raw_bag);
+        Ok(())
+    }
+
+    #[test]
+    fn test_raw_bag_parse_magic_variants() -> anyhow::Result<()> {
+        let raw_cell = RawCell {
+            data: vec![0xab, 0xcd].into(),
+            bit_len: 16,
+            references: vec![],
+            max_level: 0,
+            cell_type: CellType::OrdinaryCell as u8,
+            is_exotic: false,
+            has_hashes: false,
+        };
+        let raw_bag = RawBagOfCells {
+            cells: vec![raw_cell],
+            roots: vec![0],
+        };
+        let generic = raw_bag.serialize(false)?;
+        // magic, flags with size 1, off_bytes 1, cells, roots, absent, tot_cells_size, root 0
+        assert_eq!(generic[4..11], [0x01, 0x01, 0x01, 0x01, 0x00, 0x04, 0x00]);
+        let cell_data = &generic[11..];
+
+        let mut indexed = INDEXED_BOC_MAGIC.to_be_bytes().to_vec();
+        // size, off_bytes, cells, roots, absent, tot_cells_size, index
+        indexed.extend([0x01, 0x01, 0x01, 0x01, 0x00, 0x04, 0x04]);
+        indexed.extend(cell_data);
+        assert_eq!(RawBagOfCells::parse(&indexed)?, raw_bag);
+
+        let mut indexed_crc32 = indexed.clone();
+        indexed_crc32[..4].copy_from_slice(&INDEXED_CRC32_MAGIC.to_be_bytes());
+        let crc32 = CRC_32_ISCSI.checksum(&indexed_crc32);
+        indexed_crc32.extend(crc32.to_le_bytes());
+        assert_eq!(RawBagOfCells::parse(&indexed_crc32)?, raw_bag);
+
+        let hex_text = format!("{}\n", hex::encode(&generic));
+        assert_eq!(RawBagOfCells::parse(hex_text.as_bytes())?, raw_bag);
+
+        let error = RawBagOfCells::parse(&[0x12, 0x34, 0x56, 0x78, 0x01]).unwrap_err();
+        assert!(error.to_string().contains("0x12345678"));
+        Ok(())
+    }
 }
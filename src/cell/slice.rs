@@ -1,10 +1,10 @@
-use std::io::Cursor;
-use std::sync::Arc;
+use alloc::sync::Arc;
 
-use bitstream_io::{BigEndian, BitRead, BitReader};
+use bitstream_io::{BigEndian, BitRead2 as BitRead, BitReader};
 
 use crate::cell::util::BitReadExt;
 use crate::cell::{ArcCell, Cell, CellBuilder, CellParser, MapTonCellError, TonCellError};
+use crate::compat::io::Cursor;
 
 use super::CellType;
 
@@ -72,8 +72,8 @@ impl CellSlice {
 
     pub fn parser(&self) -> Result<CellParser, TonCellError> {
         let bit_len = self.end_bit - self.start_bit;
-        let cursor = Cursor::new(&self.cell.data);
-        let mut bit_reader: BitReader<Cursor<&Vec<u8>>, BigEndian> =
+        let cursor = Cursor::new(&self.cell.data[..]);
+        let mut bit_reader: BitReader<Cursor<&[u8]>, BigEndian> =
             BitReader::endian(cursor, BigEndian);
         bit_reader
             .skip(self.start_bit as u32)
@@ -82,6 +82,8 @@ impl CellSlice {
         Ok(CellParser {
             bit_len,
             bit_reader,
+            references: &self.cell.references[self.start_ref..self.end_ref],
+            next_ref: 0,
         })
     }
 
@@ -137,8 +139,8 @@ impl CellSlice {
         let bit_len = self.end_bit - self.start_bit;
         let total_bytes = (bit_len + 7) / 8;
         let mut data = vec![0u8; total_bytes];
-        let cursor = Cursor::new(&self.cell.data);
-        let mut bit_reader: BitReader<Cursor<&Vec<u8>>, BigEndian> =
+        let cursor = Cursor::new(&self.cell.data[..]);
+        let mut bit_reader: BitReader<Cursor<&[u8]>, BigEndian> =
             BitReader::endian(cursor, BigEndian);
         bit_reader
             .skip(self.start_bit as u32)
@@ -154,7 +156,7 @@ impl CellSlice {
             CellType::OrdinaryCell as u8
         };
         let cell = Cell {
-            data,
+            data: data.into(),
             bit_len,
             references: self.cell.references[self.start_ref..self.end_ref].to_vec(),
             cell_type, // first byte is cell type,
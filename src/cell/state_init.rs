@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 use super::ArcCell;
 use crate::cell::{Cell, CellBuilder, TonCellError};
 
@@ -9,6 +11,8 @@ pub struct StateInitBuilder {
     tick_tock: bool,
     library: bool,
 }
+
+#[derive(Clone, Debug, Default)]
 pub struct StateInit {
     pub code: Option<ArcCell>,
     pub data: Option<ArcCell>,
@@ -62,13 +66,30 @@ impl StateInit {
     pub fn create_account_id(code: &ArcCell, data: &ArcCell) -> Result<Vec<u8>, TonCellError> {
         StateInitBuilder::new(code, data).build()?.cell_hash()
     }
+
+    /// Parses the code and the data of a state init, ignoring its other fields.
+    pub fn parse(cell: &Cell) -> Result<StateInit, TonCellError> {
+        let builder = Cell::load_state_init(cell, &mut 0, &mut cell.parser())?;
+        Ok(StateInit {
+            code: builder.code,
+            data: builder.data,
+        })
+    }
+
+    pub fn code_hash(&self) -> Result<Option<Vec<u8>>, TonCellError> {
+        self.code.as_ref().map(|code| code.cell_hash()).transpose()
+    }
+
+    pub fn data_hash(&self) -> Result<Option<Vec<u8>>, TonCellError> {
+        self.data.as_ref().map(|data| data.cell_hash()).transpose()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
 
-    use super::StateInitBuilder;
+    use super::{StateInit, StateInitBuilder};
     use crate::cell::CellBuilder;
 
     #[test]
@@ -123,4 +144,21 @@ mod tests {
         assert_eq!(state_init.data[0], 0b00111000);
         Ok(())
     }
+
+    #[test]
+    fn test_state_init_parse() -> anyhow::Result<()> {
+        let code = Arc::new(CellBuilder::new().store_string("code")?.build()?);
+        let data = Arc::new(CellBuilder::new().store_string("data")?.build()?);
+        let state_init = StateInitBuilder::new(&code, &data).build()?;
+        let parsed = StateInit::parse(&state_init)?;
+        assert_eq!(parsed.code_hash()?, Some(code.cell_hash()?));
+        assert_eq!(parsed.data_hash()?, Some(data.cell_hash()?));
+
+        let mut builder = StateInitBuilder::new(&code, &data);
+        builder.data = None;
+        let parsed = StateInit::parse(&builder.build()?)?;
+        assert_eq!(parsed.code_hash()?, Some(code.cell_hash()?));
+        assert_eq!(parsed.data_hash()?, None);
+        Ok(())
+    }
 }
@@ -0,0 +1,28 @@
+use crate::cell::{Cell, CellBuilder, CellParser, TonCellError};
+
+/// Type stored in cells according to its TL-B scheme.
+///
+/// Implementations for TL-B schemas can be generated by `tonlib::tlb_codegen`, available with
+/// the `tlb_codegen` feature.
+pub trait TlbSerialize {
+    /// Stores the bits and the references of the value.
+    fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError>;
+
+    /// Stores the value in a new cell.
+    fn to_cell(&self) -> Result<Cell, TonCellError> {
+        let mut builder = CellBuilder::new();
+        self.store(&mut builder)?;
+        builder.build()
+    }
+}
+
+/// Type loaded from cells according to its TL-B scheme.
+pub trait TlbDeserialize: Sized {
+    /// Loads the value, leaving the parser positioned after it.
+    fn load(parser: &mut CellParser) -> Result<Self, TonCellError>;
+
+    /// Loads the value from the beginning of the cell.
+    fn from_cell(cell: &Cell) -> Result<Self, TonCellError> {
+        Self::load(&mut cell.parser())
+    }
+}
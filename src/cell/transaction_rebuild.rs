@@ -0,0 +1,486 @@
+use alloc::sync::Arc;
+
+use num_bigint::BigUint;
+use num_traits::ToPrimitive;
+
+use crate::cell::{Cell, CellBuilder, CellParser, CellType, DictBuilder, TonCellError};
+use crate::hashmap::Hashmap;
+use crate::responses::{
+    AccStatusChange, ComputePhaseVmDetails, ComputeSkipReason, CurrencyCollection, HashUpdate,
+    MaybeRefData, SplitMergeInfo, StorageUsedShort, TrActionPhase, TrBouncePhase, TrComputePhase,
+    TrCreditPhase, TrStoragePhase, Transaction, TransactionDescr, VarUInteger,
+};
+
+/// Builds a cell from loaded fields, taking the references it can't rebuild from the original
+/// cell: pruned branches, whose hash is all that is known of them, and messages, which are
+/// identified by their hashes.
+struct Rebuilder<'a> {
+    original: &'a Cell,
+    builder: CellBuilder,
+    next_ref: usize,
+}
+
+impl<'a> Rebuilder<'a> {
+    fn new(original: &'a Cell) -> Self {
+        Rebuilder {
+            original,
+            builder: CellBuilder::new(),
+            next_ref: 0,
+        }
+    }
+
+    /// Stores the reference rebuilt from the matching reference of the original cell, or the
+    /// original reference if it's a pruned branch.
+    fn store_child<F>(&mut self, rebuild: F) -> Result<&mut Self, TonCellError>
+    where
+        F: FnOnce(&Cell) -> Result<Cell, TonCellError>,
+    {
+        let original = self.original.reference(self.next_ref)?;
+        self.next_ref += 1;
+        let child = if original.cell_type == CellType::PrunnedBranchCell as u8 {
+            original.clone()
+        } else {
+            Arc::new(rebuild(original)?)
+        };
+        self.builder.store_reference(&child)?;
+        Ok(self)
+    }
+
+    fn store_maybe_child<T, F>(
+        &mut self,
+        value: &MaybeRefData<T>,
+        rebuild: F,
+    ) -> Result<&mut Self, TonCellError>
+    where
+        T: Clone + core::fmt::Debug + Default,
+        F: FnOnce(&Cell, &T) -> Result<Cell, TonCellError>,
+    {
+        if value.data.is_none() && value.cell.is_none() {
+            self.builder.store_bit(false)?;
+            return Ok(self);
+        }
+        self.builder.store_bit(true)?;
+        self.store_child(|original| rebuild(original, loaded(value.data.as_ref())?))
+    }
+
+    fn build(&mut self) -> Result<Cell, TonCellError> {
+        self.builder.build()
+    }
+}
+
+fn loaded<T>(value: Option<&T>) -> Result<&T, TonCellError> {
+    value.ok_or_else(|| TonCellError::cell_builder_error("Field was not loaded"))
+}
+
+/// Calculates the hashes of the rebuilt cells, which are needed to finalize their parents.
+pub(crate) fn finalized(mut cell: Cell) -> Result<Cell, TonCellError> {
+    for reference in cell.references.iter_mut() {
+        if reference.hashes.is_empty() {
+            *reference = Arc::new(finalized(reference.as_ref().clone())?);
+        }
+    }
+    cell.finalize()?;
+    Ok(cell)
+}
+
+/// Rebuilds the transaction cell and the cells it references from the loaded transaction.
+pub(crate) fn rebuild_transaction(
+    original: &Cell,
+    transaction: &Transaction,
+) -> Result<Cell, TonCellError> {
+    let status = |status: &str| match status {
+        "uninit" => 0,
+        "frozen" => 1,
+        "active" => 2,
+        _ => 3,
+    };
+    let mut rebuilder = Rebuilder::new(original);
+    rebuilder
+        .builder
+        .store_u8(4, 7)?
+        .store_slice(&transaction.account_addr)?
+        .store_u64(64, transaction.lt)?
+        .store_slice(&transaction.prev_trans_hash)?
+        .store_u64(64, transaction.prev_trans_lt)?
+        .store_u32(32, transaction.now)?
+        .store_u32(15, transaction.outmsg_cnt as u32)?
+        .store_u8(2, status(&transaction.orig_status))?
+        .store_u8(2, status(&transaction.end_status))?;
+    rebuilder.store_child(|original| rebuild_messages(original, transaction))?;
+    store_currency_collection(&mut rebuilder, &transaction.total_fees)?;
+    rebuilder.store_child(|_| build_hash_update(loaded(transaction.state_update.as_ref())?))?;
+    rebuilder.store_child(|original| {
+        rebuild_transaction_descr(original, loaded(transaction.description.as_ref())?)
+    })?;
+    rebuilder.build()
+}
+
+/// Rebuilds `^[ in_msg:(Maybe ^(Message Any)) out_msgs:(HashmapE 15 ^(Message Any)) ]`.
+///
+/// The messages themselves are taken from the original cell after checking their hashes.
+/// The dictionary of outbound messages is rebuilt unless parts of it are pruned.
+fn rebuild_messages(original: &Cell, transaction: &Transaction) -> Result<Cell, TonCellError> {
+    let mismatch = || TonCellError::cell_builder_error("Message mismatch");
+    let mut rebuilder = Rebuilder::new(original);
+    rebuilder.store_maybe_child(&transaction.in_msg, |original, message| {
+        if original.get_hash(0) != message.hash {
+            return Err(mismatch());
+        }
+        Ok(original.clone())
+    })?;
+    rebuilder.builder.store_bit(transaction.outmsg_cnt > 0)?;
+    if transaction.outmsg_cnt == 0 {
+        return rebuilder.build();
+    }
+    rebuilder.store_child(|root| {
+        let mut out_msgs = Hashmap::new(
+            15,
+            |cell: &Cell, ref_index: &mut usize, _parser: &mut CellParser, _key: &BigUint| {
+                let message = cell.reference(*ref_index)?.clone();
+                *ref_index += 1;
+                Ok(Some(message))
+            },
+        );
+        out_msgs.deserialize(root, &mut 0, &mut root.parser())?;
+        if !out_msgs.pruned.is_empty() {
+            return Ok(root.clone());
+        }
+        if out_msgs.map.len() != transaction.out_msgs.len() {
+            return Err(mismatch());
+        }
+        let mut dict = DictBuilder::new(15);
+        for (key, message) in &out_msgs.map {
+            let out_msg = transaction.out_msgs.get(key).ok_or_else(mismatch)?;
+            if message.get_hash(0) != loaded(out_msg.data.as_ref())?.hash {
+                return Err(mismatch());
+            }
+            let key = BigUint::parse_bytes(key.as_bytes(), 16).ok_or_else(mismatch)?;
+            dict.insert(key, CellBuilder::new().store_reference(message)?.build()?)?;
+        }
+        dict.build()?.ok_or_else(mismatch)
+    })?;
+    rebuilder.build()
+}
+
+fn build_hash_update(update: &HashUpdate) -> Result<Cell, TonCellError> {
+    CellBuilder::new()
+        .store_u8(8, 0x72)?
+        .store_slice(&update.old_hash)?
+        .store_slice(&update.new_hash)?
+        .build()
+}
+
+fn rebuild_transaction_descr(
+    original: &Cell,
+    descr: &TransactionDescr,
+) -> Result<Cell, TonCellError> {
+    let mut rebuilder = Rebuilder::new(original);
+    match descr {
+        TransactionDescr::Ordinary(descr) => {
+            rebuilder
+                .builder
+                .store_u8(4, 0b0000)?
+                .store_bit(descr.credit_first)?;
+            store_maybe_storage_phase(&mut rebuilder.builder, descr.storage_ph.as_ref())?;
+            store_maybe_credit_phase(&mut rebuilder, descr.credit_ph.as_ref())?;
+            store_compute_phase(&mut rebuilder, &descr.compute_ph)?;
+            rebuilder.store_maybe_child(&descr.action, build_action_phase)?;
+            rebuilder.builder.store_bit(descr.aborted)?;
+            match descr.bounce.as_ref() {
+                Some(bounce) => {
+                    rebuilder.builder.store_bit(true)?;
+                    store_bounce_phase(&mut rebuilder.builder, bounce)?;
+                }
+                None => {
+                    rebuilder.builder.store_bit(false)?;
+                }
+            }
+            rebuilder.builder.store_bit(descr.destroyed)?;
+        }
+        TransactionDescr::Storage(storage_ph) => {
+            rebuilder.builder.store_u8(4, 0b0001)?;
+            store_storage_phase(&mut rebuilder.builder, storage_ph)?;
+        }
+        TransactionDescr::TickTock(descr) => {
+            rebuilder
+                .builder
+                .store_u8(3, 0b001)?
+                .store_bit(descr.is_tock)?;
+            store_storage_phase(&mut rebuilder.builder, &descr.storage_ph)?;
+            store_compute_phase(&mut rebuilder, &descr.compute_ph)?;
+            rebuilder.store_maybe_child(&descr.action, build_action_phase)?;
+            rebuilder
+                .builder
+                .store_bit(descr.aborted)?
+                .store_bit(descr.destroyed)?;
+        }
+        TransactionDescr::SplitPrepare(descr) => {
+            rebuilder.builder.store_u8(4, 0b0100)?;
+            store_split_merge_info(&mut rebuilder.builder, &descr.split_info)?;
+            store_maybe_storage_phase(&mut rebuilder.builder, descr.storage_ph.as_ref())?;
+            store_compute_phase(&mut rebuilder, &descr.compute_ph)?;
+            rebuilder.store_maybe_child(&descr.action, build_action_phase)?;
+            rebuilder
+                .builder
+                .store_bit(descr.aborted)?
+                .store_bit(descr.destroyed)?;
+        }
+        TransactionDescr::SplitInstall(descr) => {
+            rebuilder.builder.store_u8(4, 0b0101)?;
+            store_split_merge_info(&mut rebuilder.builder, &descr.split_info)?;
+            let prepare_transaction = descr.prepare_transaction.data.as_ref();
+            rebuilder.store_child(|original| {
+                rebuild_transaction(original, loaded(prepare_transaction)?)
+            })?;
+            rebuilder.builder.store_bit(descr.installed)?;
+        }
+        TransactionDescr::MergePrepare(descr) => {
+            rebuilder.builder.store_u8(4, 0b0110)?;
+            store_split_merge_info(&mut rebuilder.builder, &descr.split_info)?;
+            store_storage_phase(&mut rebuilder.builder, &descr.storage_ph)?;
+            rebuilder.builder.store_bit(descr.aborted)?;
+        }
+        TransactionDescr::MergeInstall(descr) => {
+            rebuilder.builder.store_u8(4, 0b0111)?;
+            store_split_merge_info(&mut rebuilder.builder, &descr.split_info)?;
+            let prepare_transaction = descr.prepare_transaction.data.as_ref();
+            rebuilder.store_child(|original| {
+                rebuild_transaction(original, loaded(prepare_transaction)?)
+            })?;
+            store_maybe_storage_phase(&mut rebuilder.builder, descr.storage_ph.as_ref())?;
+            store_maybe_credit_phase(&mut rebuilder, descr.credit_ph.as_ref())?;
+            store_compute_phase(&mut rebuilder, &descr.compute_ph)?;
+            rebuilder.store_maybe_child(&descr.action, build_action_phase)?;
+            rebuilder
+                .builder
+                .store_bit(descr.aborted)?
+                .store_bit(descr.destroyed)?;
+        }
+    }
+    rebuilder.build()
+}
+
+/// Stores `VarUInteger n` with the length it was loaded with.
+fn store_var_uinteger(
+    builder: &mut CellBuilder,
+    n: usize,
+    value: &VarUInteger,
+) -> Result<(), TonCellError> {
+    let len = value
+        .len
+        .to_usize()
+        .filter(|len| *len < n)
+        .ok_or_else(|| TonCellError::cell_builder_error("Invalid VarUInteger length"))?;
+    let len_bits = (usize::BITS - (n - 1).leading_zeros()) as usize;
+    builder.store_u32(len_bits, len as u32)?;
+    if len > 0 {
+        builder.store_uint(len * 8, &value.value)?;
+    }
+    Ok(())
+}
+
+fn store_maybe_grams(
+    builder: &mut CellBuilder,
+    value: Option<&VarUInteger>,
+) -> Result<(), TonCellError> {
+    builder.store_bit(value.is_some())?;
+    if let Some(value) = value {
+        store_var_uinteger(builder, 16, value)?;
+    }
+    Ok(())
+}
+
+fn store_maybe_i32(builder: &mut CellBuilder, value: Option<i32>) -> Result<(), TonCellError> {
+    builder.store_bit(value.is_some())?;
+    if let Some(value) = value {
+        builder.store_i32(32, value)?;
+    }
+    Ok(())
+}
+
+fn store_currency_collection(
+    rebuilder: &mut Rebuilder,
+    collection: &CurrencyCollection,
+) -> Result<(), TonCellError> {
+    store_var_uinteger(&mut rebuilder.builder, 16, &collection.grams)?;
+    if collection.other.is_empty() {
+        rebuilder.builder.store_bit(false)?;
+        return Ok(());
+    }
+    rebuilder.builder.store_bit(true)?;
+    rebuilder.store_child(|_| {
+        let mut dict = DictBuilder::new(32);
+        for (id, amount) in &collection.other {
+            let mut value = CellBuilder::new();
+            store_var_uinteger(&mut value, 32, amount)?;
+            dict.insert(BigUint::from(*id), value.build()?)?;
+        }
+        loaded(dict.build()?.as_ref()).cloned()
+    })?;
+    Ok(())
+}
+
+fn store_acc_status_change(
+    builder: &mut CellBuilder,
+    status_change: &AccStatusChange,
+) -> Result<(), TonCellError> {
+    match status_change {
+        AccStatusChange::Unchanged => builder.store_u8(1, 0b0)?,
+        AccStatusChange::Frozen => builder.store_u8(2, 0b10)?,
+        AccStatusChange::Deleted => builder.store_u8(2, 0b11)?,
+    };
+    Ok(())
+}
+
+fn store_storage_phase(
+    builder: &mut CellBuilder,
+    storage_ph: &TrStoragePhase,
+) -> Result<(), TonCellError> {
+    store_var_uinteger(builder, 16, &storage_ph.storage_fees_collected)?;
+    store_maybe_grams(builder, storage_ph.storage_fees_due.as_ref())?;
+    store_acc_status_change(builder, &storage_ph.status_change)
+}
+
+fn store_maybe_storage_phase(
+    builder: &mut CellBuilder,
+    storage_ph: Option<&TrStoragePhase>,
+) -> Result<(), TonCellError> {
+    builder.store_bit(storage_ph.is_some())?;
+    if let Some(storage_ph) = storage_ph {
+        store_storage_phase(builder, storage_ph)?;
+    }
+    Ok(())
+}
+
+fn store_maybe_credit_phase(
+    rebuilder: &mut Rebuilder,
+    credit_ph: Option<&TrCreditPhase>,
+) -> Result<(), TonCellError> {
+    rebuilder.builder.store_bit(credit_ph.is_some())?;
+    if let Some(credit_ph) = credit_ph {
+        store_maybe_grams(
+            &mut rebuilder.builder,
+            credit_ph.due_fees_collected.as_ref(),
+        )?;
+        store_currency_collection(rebuilder, &credit_ph.credit)?;
+    }
+    Ok(())
+}
+
+fn store_compute_phase(
+    rebuilder: &mut Rebuilder,
+    compute_ph: &TrComputePhase,
+) -> Result<(), TonCellError> {
+    match compute_ph {
+        TrComputePhase::Skipped(reason) => {
+            rebuilder.builder.store_bit(false)?;
+            match reason {
+                ComputeSkipReason::NoState => rebuilder.builder.store_u8(2, 0b00)?,
+                ComputeSkipReason::BadState => rebuilder.builder.store_u8(2, 0b01)?,
+                ComputeSkipReason::NoGas => rebuilder.builder.store_u8(2, 0b10)?,
+                ComputeSkipReason::Suspended => rebuilder.builder.store_u8(3, 0b110)?,
+            };
+        }
+        TrComputePhase::Vm(vm) => {
+            rebuilder
+                .builder
+                .store_bit(true)?
+                .store_bit(vm.success)?
+                .store_bit(vm.msg_state_used)?
+                .store_bit(vm.account_activated)?;
+            store_var_uinteger(&mut rebuilder.builder, 16, &vm.gas_fees)?;
+            rebuilder.store_child(|_| build_vm_details(loaded(vm.vm_details.as_ref())?))?;
+        }
+    }
+    Ok(())
+}
+
+fn build_vm_details(details: &ComputePhaseVmDetails) -> Result<Cell, TonCellError> {
+    let mut builder = CellBuilder::new();
+    store_var_uinteger(&mut builder, 7, &details.gas_used)?;
+    store_var_uinteger(&mut builder, 7, &details.gas_limit)?;
+    builder.store_bit(details.gas_credit.is_some())?;
+    if let Some(gas_credit) = details.gas_credit.as_ref() {
+        store_var_uinteger(&mut builder, 3, gas_credit)?;
+    }
+    builder
+        .store_i8(8, details.mode)?
+        .store_i32(32, details.exit_code)?;
+    store_maybe_i32(&mut builder, details.exit_arg)?;
+    builder
+        .store_u32(32, details.vm_steps)?
+        .store_slice(&details.vm_init_state_hash)?
+        .store_slice(&details.vm_final_state_hash)?;
+    builder.build()
+}
+
+fn build_action_phase(_original: &Cell, action: &TrActionPhase) -> Result<Cell, TonCellError> {
+    let mut builder = CellBuilder::new();
+    builder
+        .store_bit(action.success)?
+        .store_bit(action.valid)?
+        .store_bit(action.no_funds)?;
+    store_acc_status_change(&mut builder, &action.status_change)?;
+    store_maybe_grams(&mut builder, action.total_fwd_fees.as_ref())?;
+    store_maybe_grams(&mut builder, action.total_action_fees.as_ref())?;
+    builder.store_i32(32, action.result_code)?;
+    store_maybe_i32(&mut builder, action.result_arg)?;
+    builder
+        .store_u16(16, action.tot_actions)?
+        .store_u16(16, action.spec_actions)?
+        .store_u16(16, action.skipped_actions)?
+        .store_u16(16, action.msgs_created)?
+        .store_slice(&action.action_list_hash)?;
+    store_storage_used_short(&mut builder, &action.tot_msg_size)?;
+    builder.build()
+}
+
+fn store_storage_used_short(
+    builder: &mut CellBuilder,
+    storage_used: &StorageUsedShort,
+) -> Result<(), TonCellError> {
+    store_var_uinteger(builder, 7, &storage_used.cells)?;
+    store_var_uinteger(builder, 7, &storage_used.bits)
+}
+
+fn store_bounce_phase(
+    builder: &mut CellBuilder,
+    bounce: &TrBouncePhase,
+) -> Result<(), TonCellError> {
+    match bounce {
+        TrBouncePhase::NegFunds => {
+            builder.store_u8(2, 0b00)?;
+        }
+        TrBouncePhase::NoFunds {
+            msg_size,
+            req_fwd_fees,
+        } => {
+            builder.store_u8(2, 0b01)?;
+            store_storage_used_short(builder, msg_size)?;
+            store_var_uinteger(builder, 16, req_fwd_fees)?;
+        }
+        TrBouncePhase::Ok {
+            msg_size,
+            msg_fees,
+            fwd_fees,
+        } => {
+            builder.store_bit(true)?;
+            store_storage_used_short(builder, msg_size)?;
+            store_var_uinteger(builder, 16, msg_fees)?;
+            store_var_uinteger(builder, 16, fwd_fees)?;
+        }
+    }
+    Ok(())
+}
+
+fn store_split_merge_info(
+    builder: &mut CellBuilder,
+    info: &SplitMergeInfo,
+) -> Result<(), TonCellError> {
+    builder
+        .store_u8(6, info.cur_shard_pfx_len)?
+        .store_u8(6, info.acc_split_depth)?
+        .store_slice(&info.this_addr)?
+        .store_slice(&info.sibling_addr)?;
+    Ok(())
+}
@@ -0,0 +1,277 @@
+use alloc::vec::Vec;
+
+use crate::cell::{Cell, TonCellError, HASH_BYTES};
+use crate::compat::HashMap;
+use crate::responses::{AccountBlock, BlockData, MaybeRefData, Transaction};
+
+/// Transaction of an account block replaced by a pruned branch, e.g. in a block proof.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PrunedTransaction {
+    pub account_addr: Vec<u8>,
+    pub lt: u64,
+    pub hash: [u8; HASH_BYTES],
+}
+
+/// Transactions collected from full blocks, other proofs and transaction cells, which fill in
+/// the transactions pruned from the account blocks of a block.
+///
+/// Only transactions verified to re-serialize to their cells are collected. They're matched by
+/// the hash of their cells, and their lt and account are checked against the key of the pruned
+/// transaction and the account block.
+#[derive(Debug, Clone, Default)]
+pub struct TransactionResolver {
+    transactions: HashMap<[u8; HASH_BYTES], MaybeRefData<Transaction>>,
+}
+
+impl TransactionResolver {
+    pub fn new() -> TransactionResolver {
+        TransactionResolver::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// Adds the transaction loaded from `cell`, keyed by the hash of the cell. Fails if the
+    /// transaction doesn't re-serialize to the cell, see [`Cell::verify_transaction`].
+    pub fn add_transaction(
+        &mut self,
+        cell: &Cell,
+        mut transaction: Transaction,
+    ) -> Result<(), TonCellError> {
+        let hash = cell.virtual_hash(0)?;
+        transaction.hash = hash.to_vec();
+        if !Cell::verify_transaction(cell, &transaction) {
+            return Err(TonCellError::InternalError(format!(
+                "Transaction {} doesn't match its cell {}",
+                transaction.lt,
+                hex::encode(hash)
+            )));
+        }
+        self.insert(hash, transaction);
+        Ok(())
+    }
+
+    /// Loads and adds the transaction of the cell.
+    pub fn add_transaction_cell(&mut self, cell: &Cell) -> Result<(), TonCellError> {
+        let transaction = Cell::load_transaction(cell, &mut 0, &mut cell.parser())?;
+        self.add_transaction(cell, transaction)
+    }
+
+    /// Adds the verified transactions of the account block, returning their number. Pruned
+    /// and unverified transactions are skipped.
+    ///
+    /// The `hash` of a verified transaction is the hash of its cell, computed by
+    /// [`Cell::load_account_block`], and its fields re-serialize to the cell.
+    pub fn add_account_block(&mut self, account_block: &AccountBlock) -> usize {
+        let mut added = 0;
+        for transaction in account_block.transactions.values() {
+            let Some(data) = transaction.data.as_ref().filter(|_| transaction.verified) else {
+                continue;
+            };
+            let Ok(hash) = data.hash.as_slice().try_into() else {
+                continue;
+            };
+            self.insert(hash, data.clone());
+            added += 1;
+        }
+        added
+    }
+
+    /// Adds the verified transactions of the account blocks of the block, returning their
+    /// number.
+    pub fn add_block(&mut self, block: &BlockData) -> usize {
+        account_blocks(block)
+            .map(|account_block| self.add_account_block(account_block))
+            .sum()
+    }
+
+    fn insert(&mut self, hash: [u8; HASH_BYTES], transaction: Transaction) {
+        let transaction = MaybeRefData {
+            data: Some(transaction),
+            cell: None,
+            verified: true,
+        };
+        self.transactions.insert(hash, transaction);
+    }
+
+    /// Fills in the pruned transactions of the account block whose hash is known, returning
+    /// the number of resolved transactions.
+    pub fn resolve_account_block(
+        &self,
+        account_block: &mut AccountBlock,
+    ) -> Result<usize, TonCellError> {
+        let mut resolved = 0;
+        for (key, transaction) in account_block.transactions.iter_mut() {
+            let Some(cell) = transaction
+                .cell
+                .as_ref()
+                .filter(|_| transaction.data.is_none())
+            else {
+                continue;
+            };
+            let Some(found) = self.transactions.get(&cell.virtual_hash(0)?) else {
+                continue;
+            };
+            // Transactions are found by hash, so the data matches unless the hash is forged
+            let data = found.data.as_ref().unwrap();
+            if u64::from_str_radix(key, 16) != Ok(data.lt)
+                || data.account_addr != account_block.account_addr
+            {
+                return Err(TonCellError::InternalError(format!(
+                    "Transaction {} of account {} doesn't match the pruned transaction {}",
+                    data.lt,
+                    hex::encode(&data.account_addr),
+                    key
+                )));
+            }
+            *transaction = found.clone();
+            resolved += 1;
+        }
+        Ok(resolved)
+    }
+
+    /// Fills in the pruned transactions of the account blocks of the block whose hash is
+    /// known, returning the number of resolved transactions.
+    pub fn resolve_block(&self, block: &mut BlockData) -> Result<usize, TonCellError> {
+        let account_blocks = block
+            .extra
+            .as_mut()
+            .and_then(|extra| extra.account_blocks.as_mut());
+        let mut resolved = 0;
+        for account_block in account_blocks
+            .into_iter()
+            .flat_map(|blocks| blocks.values_mut())
+        {
+            resolved += self.resolve_account_block(account_block)?;
+        }
+        Ok(resolved)
+    }
+
+    /// Returns the pruned transactions of the account blocks of the block, i.e. the
+    /// transactions left to resolve.
+    pub fn pruned_transactions(block: &BlockData) -> Result<Vec<PrunedTransaction>, TonCellError> {
+        let mut pruned = Vec::new();
+        for account_block in account_blocks(block) {
+            for (key, transaction) in &account_block.transactions {
+                let Some(cell) = transaction
+                    .cell
+                    .as_ref()
+                    .filter(|_| transaction.data.is_none())
+                else {
+                    continue;
+                };
+                let lt = u64::from_str_radix(key, 16).map_err(|_| {
+                    TonCellError::InternalError(format!("Invalid transaction key: {}", key))
+                })?;
+                pruned.push(PrunedTransaction {
+                    account_addr: account_block.account_addr.clone(),
+                    lt,
+                    hash: cell.virtual_hash(0)?,
+                });
+            }
+        }
+        Ok(pruned)
+    }
+}
+
+fn account_blocks(block: &BlockData) -> impl Iterator<Item = &AccountBlock> {
+    block
+        .extra
+        .as_ref()
+        .and_then(|extra| extra.account_blocks.as_ref())
+        .into_iter()
+        .flat_map(|blocks| blocks.values())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::cell::{prune_cells, BagOfCells, Cell, TransactionResolver};
+
+    #[test]
+    fn pruned_transactions_are_resolved() -> anyhow::Result<()> {
+        let boc = BagOfCells::parse_hex(include_str!("../../resources/bloc/block_extra.hex"))?;
+        let root = boc.single_root()?;
+        let full = root.load_block()?;
+        let account_blocks = full
+            .extra
+            .as_ref()
+            .unwrap()
+            .account_blocks
+            .as_ref()
+            .unwrap();
+        let hashes: HashSet<Vec<u8>> = account_blocks
+            .values()
+            .flat_map(|account_block| account_block.transactions.values())
+            .map(|tx| tx.data.as_ref().unwrap().hash.clone())
+            .collect();
+        assert!(!hashes.is_empty());
+
+        let pruned_root =
+            prune_cells(root, |_, cell| !hashes.contains(&cell.cell_hash().unwrap()))?;
+        let mut pruned = pruned_root.load_block()?;
+        let missing = TransactionResolver::pruned_transactions(&pruned)?;
+        assert_eq!(missing.len(), hashes.len());
+        assert!(missing.iter().all(|tx| hashes.contains(tx.hash.as_slice())));
+
+        let mut resolver = TransactionResolver::new();
+        assert_eq!(resolver.resolve_block(&mut pruned)?, 0);
+        assert_eq!(resolver.add_block(&full), hashes.len());
+        assert_eq!(resolver.resolve_block(&mut pruned)?, hashes.len());
+        assert!(TransactionResolver::pruned_transactions(&pruned)?.is_empty());
+        let resolved = pruned.extra.unwrap().account_blocks.unwrap();
+        for (addr, account_block) in &resolved {
+            for (key, tx) in &account_block.transactions {
+                let expected = &account_blocks[addr].transactions[key];
+                assert!(tx.verified);
+                assert!(tx.cell.is_none());
+                assert_eq!(
+                    tx.data.as_ref().unwrap().hash,
+                    expected.data.as_ref().unwrap().hash
+                );
+            }
+        }
+
+        // A transaction loaded from its cell resolves it as well
+        let mut pruned = pruned_root.load_block()?;
+        let mut resolver = TransactionResolver::new();
+        let tx_cell = find_cell(root, |cell| hashes.contains(&cell.cell_hash().unwrap()));
+        resolver.add_transaction_cell(tx_cell.unwrap())?;
+        assert_eq!(resolver.resolve_block(&mut pruned)?, 1);
+
+        // Transactions that don't match their cells are rejected
+        let tx_cell = tx_cell.unwrap();
+        let mut forged = Cell::load_transaction(tx_cell, &mut 0, &mut tx_cell.parser())?;
+        forged.now += 1;
+        assert!(resolver.add_transaction(tx_cell, forged).is_err());
+        let mut unverified = full.clone();
+        for account_block in unverified
+            .extra
+            .as_mut()
+            .unwrap()
+            .account_blocks
+            .as_mut()
+            .unwrap()
+            .values_mut()
+        {
+            for tx in account_block.transactions.values_mut() {
+                tx.verified = false;
+            }
+        }
+        assert_eq!(TransactionResolver::new().add_block(&unverified), 0);
+        Ok(())
+    }
+
+    fn find_cell(cell: &Cell, f: impl Fn(&Cell) -> bool + Copy) -> Option<&Cell> {
+        if f(cell) {
+            return Some(cell);
+        }
+        cell.references.iter().find_map(|child| find_cell(child, f))
+    }
+}
@@ -1,8 +1,9 @@
-use std::io;
+use alloc::vec::Vec;
 
-use bitstream_io::{BitRead, BitReader, Endianness};
+use bitstream_io::{BitRead2 as BitRead, BitReader, Endianness};
 
 use crate::cell::{MapTonCellError, TonCellError};
+use crate::compat::io;
 
 pub trait BitReadExt {
     fn read_bits(&mut self, num_bits: usize, slice: &mut [u8]) -> Result<(), TonCellError>;
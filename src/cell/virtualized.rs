@@ -0,0 +1,88 @@
+use alloc::borrow::Cow;
+use alloc::sync::Arc;
+
+use crate::cell::pruner::with_hashes;
+use crate::cell::{Cell, CellType, TonCellError, HASH_BYTES};
+
+impl Cell {
+    /// Returns the hash of the given level, in which pruned branches of higher levels stand
+    /// for the subtrees they replace.
+    ///
+    /// The hash of level 0 of a pruned tree, e.g. the content of a Merkle proof, is the
+    /// representation hash of the original tree. Unlike [`Cell::hash_ref`], the hashes of trees
+    /// made by [`CellBuilder`](crate::cell::CellBuilder) are calculated instead of panicking.
+    pub fn virtual_hash(&self, level: u8) -> Result<[u8; HASH_BYTES], TonCellError> {
+        Ok(with_calculated_hashes(self)?.hash_array(level))
+    }
+
+    /// Returns the depth of the given level, see [`Cell::virtual_hash`].
+    pub fn virtual_depth(&self, level: u8) -> Result<u16, TonCellError> {
+        Ok(with_calculated_hashes(self)?.get_depth(Some(level)) as u16)
+    }
+
+    /// Returns the cell wrapped by Merkle proof cells, or the cell itself.
+    pub fn virtual_root(&self) -> &Cell {
+        let mut cell = self;
+        while cell.cell_type == CellType::MerkleProofCell as u8 && cell.references.len() == 1 {
+            cell = &cell.references[0];
+        }
+        cell
+    }
+
+    /// Returns whether the cells stand for the same tree, treating pruned branches as equal
+    /// to the subtrees they replace and looking through Merkle proof cells.
+    pub fn virtually_eq(&self, other: &Cell) -> Result<bool, TonCellError> {
+        let (cell, other) = (self.virtual_root(), other.virtual_root());
+        Ok(cell.virtual_hash(0)? == other.virtual_hash(0)?
+            && cell.virtual_depth(0)? == other.virtual_depth(0)?)
+    }
+}
+
+fn with_calculated_hashes(cell: &Cell) -> Result<Cow<'_, Cell>, TonCellError> {
+    if !cell.hashes.is_empty() {
+        return Ok(Cow::Borrowed(cell));
+    }
+    let hashed = with_hashes(&Arc::new(cell.clone()))?;
+    Ok(Cow::Owned(Arc::unwrap_or_clone(hashed)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::cell::{merkle_proof, prune_cells, CellBuilder};
+
+    #[test]
+    fn pruned_tree_is_virtually_equal_to_original() -> anyhow::Result<()> {
+        let leaf = CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?;
+        let inner = CellBuilder::new()
+            .store_u8(8, 1)?
+            .store_child(leaf.clone())?
+            .build()?;
+        let root = Arc::new(
+            CellBuilder::new()
+                .store_u8(8, 2)?
+                .store_child(inner)?
+                .store_child(leaf)?
+                .build()?,
+        );
+        let pruned = prune_cells(&root, |path, _| path.len() < 2)?;
+        let proof = merkle_proof(&pruned)?;
+
+        assert_ne!(pruned.cell_hash()?, root.cell_hash()?);
+        assert_eq!(pruned.virtual_hash(0)?.to_vec(), root.cell_hash()?);
+        assert_ne!(pruned.virtual_hash(1)?, pruned.virtual_hash(0)?);
+        assert_eq!(pruned.virtual_depth(0)?, root.virtual_depth(0)?);
+        assert!(pruned.virtually_eq(&root)?);
+        assert!(root.virtually_eq(&proof)?);
+        assert!(std::ptr::eq(proof.virtual_root(), pruned.as_ref()));
+
+        let other = CellBuilder::new()
+            .store_u8(8, 3)?
+            .store_reference(&root.references[0])?
+            .store_reference(&root.references[1])?
+            .build()?;
+        assert!(!pruned.virtually_eq(&other)?);
+        Ok(())
+    }
+}
@@ -1,35 +1,73 @@
 use std::fs;
 use std::ops::Deref;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock, Weak};
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
+pub use address_watcher::*;
 use async_trait::async_trait;
+pub use backfill::*;
 pub use block_functions::*;
 pub use block_stream::*;
 pub use builder::*;
 pub use callback::*;
+pub use chain_watcher::*;
 pub use connection::*;
 pub use error::*;
+pub use fee_analytics::*;
+use futures::future::join_all;
+pub use health::{ConnectionHealth, ConnectionStatus, HealthCheck};
 pub use interface::*;
+pub use message_functions::*;
+pub use out_msg_queue::*;
 use rand::Rng;
+pub use rate_limit::{QueueMetrics, RateLimit};
+pub use response_cache::*;
 use serde::{Deserialize, Serialize};
-use tokio::sync::Mutex;
+pub use shard_transitions::*;
+use tokio::sync::{Mutex, Semaphore};
+use tokio::time;
 use tokio_retry::strategy::FixedInterval;
 use tokio_retry::RetryIf;
+pub use trace_functions::*;
+pub use transaction_filter::*;
+pub use transaction_history::*;
 pub use types::*;
+pub use validator_monitor::*;
+pub use verified_shards::*;
+pub use verified_transactions::*;
 
+use crate::address::TonAddress;
+use crate::client::health::HealthRecorder;
+use crate::client::rate_limit::QueueMetricsRecorder;
 use crate::tl::*;
 
+mod address_watcher;
+mod backfill;
 mod block_functions;
 mod block_stream;
 mod builder;
 mod callback;
+mod chain_watcher;
 mod connection;
 mod error;
+mod fee_analytics;
+mod health;
 mod interface;
+mod message_functions;
+mod out_msg_queue;
+mod rate_limit;
+mod response_cache;
+mod shard_transitions;
+mod trace_functions;
+mod transaction_filter;
+mod transaction_history;
 
 mod types;
+mod validator_monitor;
+mod verified_shards;
+mod verified_transactions;
 
 /// Check on perform upon connection
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
@@ -50,6 +88,8 @@ pub struct TonClient {
 struct Inner {
     retry_strategy: RetryStrategy,
     connections: Vec<PoolConnection>,
+    semaphore: Option<Semaphore>,
+    queue_metrics: QueueMetricsRecorder,
 }
 
 impl TonClient {
@@ -60,6 +100,27 @@ impl TonClient {
         retry_strategy: &RetryStrategy,
         callback: Arc<dyn TonConnectionCallback>,
         connection_check: ConnectionCheck,
+    ) -> Result<TonClient, TonClientError> {
+        Self::with_concurrency_limit(
+            pool_size,
+            params,
+            retry_strategy,
+            callback,
+            connection_check,
+            0,
+        )
+        .await
+    }
+
+    /// Creates a new TonClient that runs at most `concurrency_limit` requests at once
+    /// across all connections of the pool. Zero means no limit.
+    pub async fn with_concurrency_limit(
+        pool_size: usize,
+        params: &TonConnectionParams,
+        retry_strategy: &RetryStrategy,
+        callback: Arc<dyn TonConnectionCallback>,
+        connection_check: ConnectionCheck,
+        concurrency_limit: usize,
     ) -> Result<TonClient, TonClientError> {
         let mut connections = Vec::with_capacity(pool_size);
         for i in 0..pool_size {
@@ -74,22 +135,53 @@ impl TonClient {
                 p.keystore_dir = Some(path_str)
             };
             let entry = PoolConnection {
-                params: p,
+                params: RwLock::new(p),
                 callback: callback.clone(),
                 conn: Mutex::new(None),
                 connection_check: connection_check.clone(),
+                health: HealthRecorder::new(i),
+                health_check: RwLock::new(None),
             };
             connections.push(entry);
         }
+        let semaphore = if concurrency_limit != 0 {
+            Some(Semaphore::new(concurrency_limit))
+        } else {
+            None
+        };
         let inner = Inner {
             retry_strategy: retry_strategy.clone(),
             connections,
+            semaphore,
+            queue_metrics: QueueMetricsRecorder::default(),
         };
         Ok(TonClient {
             inner: Arc::new(inner),
         })
     }
 
+    /// Starts probing the connections of the pool in a background task, which stops when
+    /// the last clone of the client is dropped.
+    pub(crate) fn start_health_check(&self, health_check: &HealthCheck) {
+        for connection in self.inner.connections.iter() {
+            *connection.health_check.write().unwrap() = Some(health_check.clone());
+        }
+        let inner = Arc::downgrade(&self.inner);
+        tokio::spawn(run_health_check(inner, health_check.clone()));
+    }
+
+    /// Returns the health of each connection of the pool.
+    ///
+    /// Probe results are only available if the client was built with
+    /// [`TonClientBuilder::with_health_check`].
+    pub fn connection_health(&self) -> Vec<ConnectionHealth> {
+        self.inner
+            .connections
+            .iter()
+            .map(|c| c.health.health())
+            .collect()
+    }
+
     pub fn builder() -> TonClientBuilder {
         TonClientBuilder::default()
     }
@@ -98,11 +190,31 @@ impl TonClient {
         Self::builder().build().await
     }
 
+    /// Returns the time spent by requests waiting for the global concurrency limit.
+    ///
+    /// Time spent waiting for the limits of individual connections is reported by
+    /// [`TonConnection::queue_metrics`].
+    pub fn queue_metrics(&self) -> QueueMetrics {
+        self.inner.queue_metrics.metrics()
+    }
+
     #[allow(clippy::let_and_return)]
     async fn retrying_invoke(
         &self,
         function: &TonFunction,
     ) -> Result<(TonConnection, TonResult), TonClientError> {
+        let start = Instant::now();
+        let _permit = if let Some(semaphore) = &self.inner.semaphore {
+            Some(
+                semaphore
+                    .acquire()
+                    .await
+                    .map_err(|_| TonClientError::InternalError("AcquireError".to_string()))?,
+            )
+        } else {
+            None
+        };
+        self.inner.queue_metrics.record(start.elapsed());
         let fi = FixedInterval::from_millis(self.inner.retry_strategy.interval_ms);
         let strategy = fi.take(self.inner.retry_strategy.max_retries);
         let result = RetryIf::spawn(strategy, || self.do_invoke(function), retry_condition).await;
@@ -122,14 +234,40 @@ impl TonClient {
         }
     }
 
-    #[allow(clippy::let_and_return)]
+    /// Returns a random connection, skipping connections waiting to be re-established
+    /// unless all of them are.
     fn random_item(&self) -> &PoolConnection {
-        let i = {
-            let mut rng = rand::thread_rng();
-            rng.gen_range(0..self.inner.connections.len())
-        };
-        let entry = &self.inner.connections[i];
-        entry
+        let connections = &self.inner.connections;
+        let available = connections.iter().filter(|c| c.health.is_available());
+        let count = available.clone().count();
+        let mut rng = rand::thread_rng();
+        if count == 0 {
+            return &connections[rng.gen_range(0..connections.len())];
+        }
+        available.clone().nth(rng.gen_range(0..count)).unwrap()
+    }
+
+    /// Replaces the config of all connections of the pool.
+    ///
+    /// Connections that are already established are updated in place, others will use the
+    /// new config once they are established.
+    pub async fn set_config(&self, config: &str) -> Result<(), TonClientError> {
+        for connection in self.inner.connections.iter() {
+            connection.set_config(config).await?;
+        }
+        Ok(())
+    }
+
+    /// Returns a watcher of the transactions of the account following its current last
+    /// transaction, see [`AddressWatcher`].
+    pub fn watch_address(&self, address: &TonAddress) -> AddressWatcher<TonClient> {
+        AddressWatcher::new(self, address)
+    }
+
+    /// Returns an iterator over the transactions of the account from the newest to the
+    /// oldest, see [`TransactionHistory`].
+    pub fn transaction_history(&self, address: &TonAddress) -> TransactionHistory<TonClient> {
+        TransactionHistory::new(self, address)
     }
 
     pub fn set_log_verbosity_level(verbosity_level: u32) {
@@ -161,6 +299,22 @@ impl Clone for TonClient {
     }
 }
 
+async fn run_health_check(inner: Weak<Inner>, health_check: HealthCheck) {
+    let mut interval = time::interval(Duration::from_millis(health_check.interval_ms.max(1)));
+    interval.set_missed_tick_behavior(time::MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        let Some(inner) = inner.upgrade() else {
+            break;
+        };
+        let checks = inner
+            .connections
+            .iter()
+            .map(|c| c.check_health(&health_check));
+        join_all(checks).await;
+    }
+}
+
 fn maybe_error_code(error: &TonClientError) -> Option<i32> {
     if let TonClientError::TonlibError { code, .. } = error {
         Some(*code)
@@ -178,39 +332,108 @@ fn retry_condition(error: &TonClientError) -> bool {
 }
 
 struct PoolConnection {
-    params: TonConnectionParams,
+    params: RwLock<TonConnectionParams>,
     callback: Arc<dyn TonConnectionCallback>,
     conn: Mutex<Option<(TonConnection, JoinHandle<()>)>>,
     connection_check: ConnectionCheck,
+    health: HealthRecorder,
+    health_check: RwLock<Option<HealthCheck>>,
 }
 
 impl PoolConnection {
     async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
         let mut guard = self.conn.lock().await;
-        match guard.deref() {
-            Some((conn, join_handle)) => {
-                if join_handle.is_finished() {
-                    // TODO: This is temporary implementation.
-                    // At the moment, only report dead connections, in the future need to recover
-                    log::warn!("Returning dead connection: {:?}", conn.tag());
-                }
-                Ok(conn.clone())
+        if let Some((conn, join_handle)) = guard.deref() {
+            if !join_handle.is_finished() {
+                return Ok(conn.clone());
             }
-            None => {
-                let (conn, join_handle) = match self.connection_check {
-                    ConnectionCheck::None => {
-                        TonConnection::connect_joinable(&self.params, self.callback.clone()).await?
-                    }
-                    ConnectionCheck::Health => {
-                        TonConnection::connect_healthy(&self.params, self.callback.clone()).await?
-                    }
-                    ConnectionCheck::Archive => {
-                        TonConnection::connect_archive(&self.params, self.callback.clone()).await?
-                    }
-                };
+            log::warn!("Reconnecting dead connection: {:?}", conn.tag());
+            self.health.record_dead();
+            *guard = None;
+        }
+        let params = self.params.read().unwrap().clone();
+        let result = match self.connection_check {
+            ConnectionCheck::None => {
+                TonConnection::connect_joinable(&params, self.callback.clone()).await
+            }
+            ConnectionCheck::Health => {
+                TonConnection::connect_healthy(&params, self.callback.clone()).await
+            }
+            ConnectionCheck::Archive => {
+                TonConnection::connect_archive(&params, self.callback.clone()).await
+            }
+        };
+        match result {
+            Ok((conn, join_handle)) => {
+                self.health.record_connected(conn.tag());
                 *guard = Some((conn.clone(), join_handle));
                 Ok(conn)
             }
+            Err(e) => {
+                if let Some(health_check) = self.health_check.read().unwrap().as_ref() {
+                    self.health
+                        .record_connect_failure(e.to_string(), health_check);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Probes the connection with `getMasterchainInfo`, dropping it after too many failed
+    /// probes and re-establishing dropped connections once their backoff has elapsed.
+    async fn check_health(&self, health_check: &HealthCheck) {
+        let established = match self.conn.lock().await.deref() {
+            Some((conn, join_handle)) if !join_handle.is_finished() => Some(conn.clone()),
+            Some(_) => None,
+            None if self.health.should_reconnect(Instant::now()) => None,
+            // Not used yet, or waiting for the backoff
+            None => return,
+        };
+        let conn = match established {
+            Some(conn) => conn,
+            None => match self.get_connection().await {
+                Ok(conn) => conn,
+                Err(e) => {
+                    log::warn!("Failed to re-establish connection: {}", e);
+                    return;
+                }
+            },
+        };
+        let start = Instant::now();
+        let timeout = Duration::from_millis(health_check.timeout_ms);
+        let error = match time::timeout(timeout, conn.get_masterchain_info()).await {
+            Ok(Ok((_, info))) => {
+                self.health.record_success(start.elapsed(), info.last.seqno);
+                return;
+            }
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => format!("Health check timed out after {:?}", timeout),
+        };
+        if self.health.record_failure(error, health_check) {
+            log::warn!("Dropping unhealthy connection: {:?}", conn.tag());
+            let mut guard = self.conn.lock().await;
+            if matches!(guard.deref(), Some((c, _)) if c.tag() == conn.tag()) {
+                *guard = None;
+            }
+        }
+    }
+
+    async fn set_config(&self, config: &str) -> Result<(), TonClientError> {
+        let guard = self.conn.lock().await;
+        let params = {
+            let mut params = self.params.write().unwrap();
+            params.config = config.to_string();
+            params.clone()
+        };
+        if let Some((conn, _)) = guard.deref() {
+            conn.set_config(
+                &params.config,
+                params.blockchain_name.as_deref(),
+                params.use_callbacks_for_network,
+                params.ignore_cache,
+            )
+            .await?;
         }
+        Ok(())
     }
 }
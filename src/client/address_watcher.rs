@@ -0,0 +1,181 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use futures::Stream;
+use tokio::time;
+
+use crate::address::TonAddress;
+use crate::cell::{BagOfCells, Cell, TonCellError};
+use crate::client::{TonClientError, TonClientInterface};
+use crate::events::{extract_transaction_event, TonEvent};
+use crate::responses::Transaction;
+use crate::tl::{InternalTransactionId, RawTransaction};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+const DEFAULT_MAX_POLL_INTERVAL: Duration = Duration::from_secs(10);
+const TRANSACTIONS_PAGE_SIZE: usize = 16;
+
+/// New transaction of a watched account.
+#[derive(Debug, Clone)]
+pub struct WatchedTransaction {
+    pub raw: RawTransaction,
+    /// Parsed transaction, if parsing is enabled and succeeded.
+    pub transaction: Option<Transaction>,
+    /// Event triggered by the inbound message, if parsing is enabled and it's recognized.
+    pub event: Option<TonEvent>,
+}
+
+/// Follows the transactions of an account by polling its last transaction.
+///
+/// Transactions are emitted once each in chronological order, even if the liteservers of the
+/// pool are not in sync. The poll interval doubles up to the maximum interval while the account
+/// has no new transactions, and is reset by the next transaction.
+pub struct AddressWatcher<C: TonClientInterface + Clone> {
+    client: C,
+    address: TonAddress,
+    last_transaction: Option<InternalTransactionId>,
+    poll_interval: Duration,
+    max_poll_interval: Duration,
+    parse: bool,
+    pending: VecDeque<RawTransaction>,
+}
+
+impl<C: TonClientInterface + Clone> AddressWatcher<C> {
+    /// Creates a watcher emitting the transactions following the current last transaction.
+    pub fn new(client: &C, address: &TonAddress) -> AddressWatcher<C> {
+        AddressWatcher {
+            client: client.clone(),
+            address: address.clone(),
+            last_transaction: None,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            max_poll_interval: DEFAULT_MAX_POLL_INTERVAL,
+            parse: false,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Emits the transactions following the given one instead of the current last transaction.
+    pub fn with_last_transaction(mut self, last_transaction: &InternalTransactionId) -> Self {
+        self.last_transaction = Some(last_transaction.clone());
+        self
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Sets the poll interval reached by the backoff while the account is idle.
+    pub fn with_max_poll_interval(mut self, max_poll_interval: Duration) -> Self {
+        self.max_poll_interval = max_poll_interval;
+        self
+    }
+
+    /// Parses the transactions and extracts the events of their inbound messages.
+    pub fn with_parsing(mut self, parse: bool) -> Self {
+        self.parse = parse;
+        self
+    }
+
+    /// Returns the last emitted transaction, or the transaction the watcher started from.
+    pub fn last_transaction(&self) -> Option<&InternalTransactionId> {
+        self.last_transaction.as_ref()
+    }
+
+    /// Returns the next transaction of the account.
+    ///
+    /// If there's no new transaction yet, the returned future resolves when it's found. Errors
+    /// leave the watcher unchanged, so `next` can be called again to retry.
+    pub async fn next(&mut self) -> Result<WatchedTransaction, TonClientError> {
+        let mut interval = self.poll_interval;
+        loop {
+            if let Some(raw) = self.pending.pop_front() {
+                self.last_transaction = Some(raw.transaction_id.clone());
+                return Ok(self.watched(raw));
+            }
+            let state = self.client.get_raw_account_state(&self.address).await?;
+            let last = state.last_transaction_id;
+            let Some(from) = &self.last_transaction else {
+                self.last_transaction = Some(last);
+                continue;
+            };
+            // Liteservers behind the ones already polled report older transactions
+            if last.lt <= from.lt {
+                time::sleep(interval).await;
+                interval = (interval * 2).min(self.max_poll_interval);
+                continue;
+            }
+            self.pending = self.load_transactions(&last, from.lt).await?;
+        }
+    }
+
+    /// Converts the watcher into a stream of transactions, ending after the first error.
+    pub fn into_stream(self) -> impl Stream<Item = Result<WatchedTransaction, TonClientError>> {
+        futures::stream::unfold(Some(self), |watcher| async move {
+            let mut watcher = watcher?;
+            match watcher.next().await {
+                Ok(tx) => Some((Ok(tx), Some(watcher))),
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+    }
+
+    /// Loads the transactions from `last` back to the transaction with logical time `after_lt`
+    /// exclusively, in chronological order.
+    async fn load_transactions(
+        &self,
+        last: &InternalTransactionId,
+        after_lt: i64,
+    ) -> Result<VecDeque<RawTransaction>, TonClientError> {
+        let mut transactions = VecDeque::new();
+        let mut from = last.clone();
+        'pages: loop {
+            let txs = self
+                .client
+                .get_raw_transactions_v2(&self.address, &from, TRANSACTIONS_PAGE_SIZE, false)
+                .await?;
+            for tx in txs.transactions {
+                if tx.transaction_id.lt <= after_lt {
+                    break 'pages;
+                }
+                transactions.push_front(tx);
+            }
+            if txs.previous_transaction_id.lt <= after_lt {
+                break;
+            }
+            from = txs.previous_transaction_id;
+        }
+        Ok(transactions)
+    }
+
+    fn watched(&self, raw: RawTransaction) -> WatchedTransaction {
+        let transaction = if self.parse {
+            match parse_transaction(&raw.data) {
+                Ok(transaction) => Some(transaction),
+                Err(e) => {
+                    log::warn!(
+                        "Failed to parse transaction {} of {}: {}",
+                        raw.transaction_id,
+                        self.address,
+                        e
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let event = transaction.as_ref().and_then(extract_transaction_event);
+        WatchedTransaction {
+            raw,
+            transaction,
+            event,
+        }
+    }
+}
+
+fn parse_transaction(data: &[u8]) -> Result<Transaction, TonCellError> {
+    let boc = BagOfCells::parse(data)?;
+    let root = boc.single_root()?;
+    Cell::load_transaction(root, &mut 0, &mut root.parser())
+}
@@ -0,0 +1,555 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::future::Future;
+use std::io::{ErrorKind, Write};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use futures::future::{try_join_all, BoxFuture};
+use futures::stream::{self, BoxStream};
+use futures::{FutureExt, StreamExt};
+use tokio::task::JoinHandle;
+
+use crate::client::{TonClientError, TonClientInterface};
+use crate::constants::MASTERCHAIN_ID;
+use crate::responses::SHARD_FULL;
+use crate::tl::{BlockId, BlockIdExt};
+
+pub const DEFAULT_BACKFILL_WORKERS: usize = 16;
+pub const DEFAULT_BACKFILL_CHECKPOINT_INTERVAL: u32 = 100;
+
+/// Storage of the progress of a [`Backfill`]: the seqno of the last block processed by the
+/// consumer.
+#[async_trait]
+pub trait BackfillCheckpointStore: Send + Sync {
+    async fn load(&self) -> Result<Option<i32>, TonClientError>;
+
+    async fn save(&self, seqno: i32) -> Result<(), TonClientError>;
+}
+
+/// Checkpoint store kept in memory, e.g. for backfills that don't need to survive restarts.
+#[derive(Default)]
+pub struct InMemoryCheckpointStore {
+    seqno: Mutex<Option<i32>>,
+}
+
+impl InMemoryCheckpointStore {
+    pub fn new() -> InMemoryCheckpointStore {
+        Default::default()
+    }
+}
+
+#[async_trait]
+impl BackfillCheckpointStore for InMemoryCheckpointStore {
+    async fn load(&self) -> Result<Option<i32>, TonClientError> {
+        Ok(*self.seqno.lock().unwrap())
+    }
+
+    async fn save(&self, seqno: i32) -> Result<(), TonClientError> {
+        *self.seqno.lock().unwrap() = Some(seqno);
+        Ok(())
+    }
+}
+
+/// Checkpoint store writing the seqno as text to a file.
+///
+/// The file is replaced by renaming a synced temporary file next to it, so a crash while
+/// saving leaves the previous checkpoint. The file is accessed on the blocking thread pool.
+pub struct FileCheckpointStore {
+    path: PathBuf,
+}
+
+impl FileCheckpointStore {
+    pub fn new<P: Into<PathBuf>>(path: P) -> FileCheckpointStore {
+        FileCheckpointStore { path: path.into() }
+    }
+}
+
+#[async_trait]
+impl BackfillCheckpointStore for FileCheckpointStore {
+    async fn load(&self) -> Result<Option<i32>, TonClientError> {
+        let path = self.path.clone();
+        let content = match run_blocking(move || std::fs::read_to_string(path)).await? {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let seqno = content.trim().parse().map_err(|_| {
+            TonClientError::InternalError(format!(
+                "Invalid checkpoint in {}: {}",
+                self.path.display(),
+                content
+            ))
+        })?;
+        Ok(Some(seqno))
+    }
+
+    async fn save(&self, seqno: i32) -> Result<(), TonClientError> {
+        let path = self.path.clone();
+        let mut tmp_path = path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        run_blocking(move || {
+            let mut file = File::create(&tmp_path)?;
+            file.write_all(seqno.to_string().as_bytes())?;
+            file.sync_all()?;
+            std::fs::rename(&tmp_path, &path)
+        })
+        .await??;
+        Ok(())
+    }
+}
+
+async fn run_blocking<F, R>(f: F) -> Result<R, TonClientError>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| TonClientError::InternalError(e.to_string()))
+}
+
+/// Ids of a masterchain block and of the shard blocks it commits, downloaded by
+/// [`block_ids_downloader`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BackfillBlockIds {
+    pub master: BlockIdExt,
+    /// Shard blocks created since the previous masterchain block, i.e. the shard top blocks
+    /// referenced by the masterchain block and the blocks before them that the previous
+    /// masterchain block doesn't reference, sorted by workchain, shard and seqno.
+    pub shards: Vec<BlockIdExt>,
+}
+
+/// Returns the default `download` of a [`Backfill`], which looks up the masterchain block
+/// by seqno and its shard blocks with the client.
+///
+/// The shard top blocks of `blocks.getShards` are followed back through the previous blocks
+/// of their headers up to the shard top blocks of the previous masterchain block, so that
+/// shards producing several blocks between two masterchain blocks emit all of them.
+pub fn block_ids_downloader<C>(
+    client: C,
+) -> impl Fn(i32) -> BoxFuture<'static, Result<BackfillBlockIds, TonClientError>> + Send + Sync
+where
+    C: TonClientInterface + Clone + 'static,
+{
+    move |seqno| {
+        let client = client.clone();
+        async move {
+            let (master, tops) = get_shard_tops(&client, seqno).await?;
+            let prev_tops: HashSet<BlockId> = if seqno > 0 {
+                let (_, prev_tops) = get_shard_tops(&client, seqno - 1).await?;
+                prev_tops.iter().map(BlockIdExt::to_block_id).collect()
+            } else {
+                HashSet::new()
+            };
+
+            let mut shards: HashSet<BlockIdExt> = HashSet::new();
+            let mut unprocessed = tops;
+            while !unprocessed.is_empty() {
+                let to_process: Vec<_> = unprocessed
+                    .into_iter()
+                    .filter(|id| !prev_tops.contains(&id.to_block_id()))
+                    .filter(|id| shards.insert(id.clone()))
+                    .collect();
+                let headers =
+                    try_join_all(to_process.iter().map(|id| client.get_block_header(id))).await?;
+                unprocessed = headers
+                    .into_iter()
+                    .flat_map(|header| header.prev_blocks.unwrap_or_default())
+                    .collect();
+            }
+            let mut shards: Vec<_> = shards.into_iter().collect();
+            shards.sort_by_key(|id| (id.workchain, id.shard, id.seqno));
+            Ok(BackfillBlockIds { master, shards })
+        }
+        .boxed()
+    }
+}
+
+/// Returns the masterchain block of the seqno with the shard top blocks it references.
+async fn get_shard_tops<C: TonClientInterface>(
+    client: &C,
+    seqno: i32,
+) -> Result<(BlockIdExt, Vec<BlockIdExt>), TonClientError> {
+    let block_id = BlockId {
+        workchain: MASTERCHAIN_ID,
+        shard: SHARD_FULL,
+        seqno,
+    };
+    let master = client.lookup_block(1, &block_id, 0, 0).await?;
+    let tops = client.get_block_shards(&master).await?.shards;
+    Ok((master, tops))
+}
+
+/// Block emitted by a [`Backfill`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackfillBlock<T> {
+    pub seqno: i32,
+    pub block: T,
+}
+
+/// Loads a range of masterchain blocks by seqno with a pool of workers, emitting them in
+/// order of seqno and checkpointing the progress to resume after restarts.
+///
+/// tonlib doesn't return block data, so each block is downloaded by `download`, e.g. the
+/// masterchain block and its shard blocks from `liteServer.getBlock` of a liteserver, and
+/// then parsed by `parse` on the blocking thread pool. [`block_ids_downloader`] downloads
+/// only the ids of the blocks with the client, e.g.
+/// `Backfill::new(range, block_ids_downloader(client), |_, ids| Ok(ids))`.
+///
+/// Up to `workers` blocks are loaded at once, and blocks loaded ahead of the next block to
+/// emit count against them, so at most `workers` blocks are held in memory.
+///
+/// A block is considered processed once the next one is requested, so after a crash the
+/// blocks since the last checkpoint are emitted again. The checkpoint is saved every
+/// `checkpoint_interval` processed blocks, at the end of the range and by
+/// [`Backfill::checkpoint`]. After an error, the next call retries from the failed block.
+pub struct Backfill<D, P, T> {
+    range: Range<i32>,
+    download: Arc<D>,
+    parse: Arc<P>,
+    workers: usize,
+    store: Arc<dyn BackfillCheckpointStore>,
+    checkpoint_interval: u32,
+    blocks: Option<BoxStream<'static, Result<BackfillBlock<T>, TonClientError>>>,
+    next_seqno: Option<i32>,
+    emitted: Option<i32>,
+    processed: Option<i32>,
+    unsaved: u32,
+}
+
+impl<D, Fut, R, P, T> Backfill<D, P, T>
+where
+    D: Fn(i32) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<R, TonClientError>> + Send + 'static,
+    R: Send + 'static,
+    P: Fn(i32, R) -> Result<T, TonClientError> + Send + Sync + 'static,
+    T: Send + 'static,
+{
+    /// Creates a backfill of the seqnos of `range`, checkpointing in memory by default.
+    pub fn new(range: Range<i32>, download: D, parse: P) -> Backfill<D, P, T> {
+        Backfill {
+            range,
+            download: Arc::new(download),
+            parse: Arc::new(parse),
+            workers: DEFAULT_BACKFILL_WORKERS,
+            store: Arc::new(InMemoryCheckpointStore::new()),
+            checkpoint_interval: DEFAULT_BACKFILL_CHECKPOINT_INTERVAL,
+            blocks: None,
+            next_seqno: None,
+            emitted: None,
+            processed: None,
+            unsaved: 0,
+        }
+    }
+
+    pub fn with_workers(mut self, workers: usize) -> Self {
+        self.workers = workers.max(1);
+        self
+    }
+
+    pub fn with_checkpoint_store(mut self, store: Arc<dyn BackfillCheckpointStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    pub fn with_checkpoint_interval(mut self, checkpoint_interval: u32) -> Self {
+        self.checkpoint_interval = checkpoint_interval.max(1);
+        self
+    }
+
+    /// Returns the next block, or `None` once the whole range is processed.
+    ///
+    /// The first call resumes from the block after the checkpoint, if any.
+    pub async fn next(&mut self) -> Result<Option<BackfillBlock<T>>, TonClientError> {
+        if let Some(seqno) = self.emitted.take() {
+            self.mark_processed(seqno).await?;
+        }
+        let next_seqno = match self.next_seqno {
+            Some(next_seqno) => next_seqno,
+            None => {
+                let checkpoint = self.store.load().await?;
+                let next_seqno = checkpoint.map_or(self.range.start, |seqno| {
+                    self.range.start.max(seqno.saturating_add(1))
+                });
+                self.next_seqno = Some(next_seqno);
+                next_seqno
+            }
+        };
+        let blocks = self.blocks.get_or_insert_with(|| {
+            Self::load_blocks(
+                next_seqno..self.range.end,
+                &self.download,
+                &self.parse,
+                self.workers,
+            )
+        });
+        match blocks.next().await {
+            Some(Ok(block)) => {
+                self.next_seqno = Some(block.seqno + 1);
+                self.emitted = Some(block.seqno);
+                Ok(Some(block))
+            }
+            Some(Err(e)) => {
+                // Blocks after the failed one are dropped and loaded again on the next call
+                self.blocks = None;
+                Err(e)
+            }
+            None => {
+                self.checkpoint().await?;
+                Ok(None)
+            }
+        }
+    }
+
+    /// Saves the seqno of the last processed block, e.g. before a graceful shutdown.
+    pub async fn checkpoint(&mut self) -> Result<(), TonClientError> {
+        if let (Some(seqno), true) = (self.processed, self.unsaved > 0) {
+            self.store.save(seqno).await?;
+            self.unsaved = 0;
+        }
+        Ok(())
+    }
+
+    async fn mark_processed(&mut self, seqno: i32) -> Result<(), TonClientError> {
+        self.processed = Some(seqno);
+        self.unsaved += 1;
+        if self.unsaved >= self.checkpoint_interval {
+            self.checkpoint().await?;
+        }
+        Ok(())
+    }
+
+    fn load_blocks(
+        seqnos: Range<i32>,
+        download: &Arc<D>,
+        parse: &Arc<P>,
+        workers: usize,
+    ) -> BoxStream<'static, Result<BackfillBlock<T>, TonClientError>> {
+        let download = download.clone();
+        let parse = parse.clone();
+        stream::iter(seqnos)
+            .map(move |seqno| {
+                let download = download.clone();
+                let parse = parse.clone();
+                // Blocks are loaded by spawned tasks to run in parallel on multi-threaded runtimes
+                let task = tokio::spawn(async move {
+                    let raw = download(seqno).await?;
+                    let block = tokio::task::spawn_blocking(move || parse(seqno, raw))
+                        .await
+                        .map_err(|e| TonClientError::InternalError(e.to_string()))??;
+                    Ok(BackfillBlock { seqno, block })
+                });
+                async move {
+                    // Tasks of the blocks dropped after an error are aborted
+                    let mut task = AbortOnDrop(task);
+                    (&mut task.0)
+                        .await
+                        .map_err(|e| TonClientError::InternalError(e.to_string()))?
+                }
+            })
+            .buffered(workers)
+            .boxed()
+    }
+}
+
+struct AbortOnDrop<R>(JoinHandle<R>);
+
+impl<R> Drop for AbortOnDrop<R> {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+
+    use crate::client::{
+        block_ids_downloader, Backfill, BackfillCheckpointStore, FileCheckpointStore,
+        InMemoryCheckpointStore, TonClientError, TonClientInterface, TonConnection,
+    };
+    use crate::tl::{BlockIdExt, BlocksHeader, BlocksShards, TonFunction, TonResult};
+
+    struct InFlight(Arc<AtomicUsize>);
+
+    impl Drop for InFlight {
+        fn drop(&mut self) {
+            self.0.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn backfill_works() -> anyhow::Result<()> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()?;
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let fail_once = Arc::new(AtomicBool::new(true));
+        let download = {
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            move |seqno: i32| {
+                let in_flight = in_flight.clone();
+                let max_in_flight = max_in_flight.clone();
+                let fail_once = fail_once.clone();
+                async move {
+                    let count = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(count, Ordering::SeqCst);
+                    // Decremented even if the download is aborted
+                    let _in_flight = InFlight(in_flight);
+                    // Later blocks are downloaded first
+                    tokio::time::sleep(Duration::from_millis(20 - seqno as u64 % 10)).await;
+                    if seqno == 7 && fail_once.swap(false, Ordering::SeqCst) {
+                        return Err(TonClientError::InternalError("timeout".to_string()));
+                    }
+                    Ok(seqno.to_string())
+                }
+            }
+        };
+        let parse = |_: i32, raw: String| Ok(raw.parse::<i32>().unwrap() * 2);
+        let store = Arc::new(InMemoryCheckpointStore::new());
+
+        let mut backfill = Backfill::new(1..20, download.clone(), parse)
+            .with_workers(4)
+            .with_checkpoint_store(store.clone())
+            .with_checkpoint_interval(2);
+        let blocks = runtime.block_on(async {
+            let mut blocks = vec![];
+            for _ in 0..6 {
+                blocks.push(backfill.next().await?.unwrap());
+            }
+            assert!(max_in_flight.load(Ordering::SeqCst) <= 4);
+            // The 7th block fails once and is retried by the next call
+            assert!(backfill.next().await.is_err());
+            blocks.push(backfill.next().await?.unwrap());
+            anyhow::Ok(blocks)
+        })?;
+        let seqnos: Vec<_> = blocks.iter().map(|block| block.seqno).collect();
+        assert_eq!(seqnos, [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(blocks[6].block, 14);
+        assert_eq!(runtime.block_on(store.load())?, Some(6));
+        drop(backfill);
+
+        // Resumes from the checkpoint after a crash
+        let mut backfill = Backfill::new(1..20, download, parse)
+            .with_workers(4)
+            .with_checkpoint_store(store.clone());
+        let seqnos = runtime.block_on(async {
+            let mut seqnos = vec![];
+            while let Some(block) = backfill.next().await? {
+                seqnos.push(block.seqno);
+            }
+            anyhow::Ok(seqnos)
+        })?;
+        assert_eq!(seqnos, (7..20).collect::<Vec<_>>());
+        assert_eq!(runtime.block_on(store.load())?, Some(19));
+
+        let path = std::env::temp_dir().join(format!("backfill_{}.checkpoint", std::process::id()));
+        let file_store = FileCheckpointStore::new(&path);
+        runtime.block_on(async {
+            assert_eq!(file_store.load().await?, None);
+            file_store.save(42).await?;
+            assert_eq!(file_store.load().await?, Some(42));
+            anyhow::Ok(())
+        })?;
+        // The temporary file `<path>.tmp` is renamed to the checkpoint
+        assert!(!std::path::Path::new(&format!("{}.tmp", path.display())).exists());
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    fn shard_block(seqno: i32) -> BlockIdExt {
+        BlockIdExt {
+            workchain: 0,
+            shard: i64::MIN,
+            seqno,
+            root_hash: String::new(),
+            file_hash: String::new(),
+        }
+    }
+
+    #[derive(Clone)]
+    struct MockClient;
+
+    #[async_trait]
+    impl TonClientInterface for MockClient {
+        async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+            Err(TonClientError::InternalError("No connection".to_string()))
+        }
+
+        async fn invoke_on_connection(
+            &self,
+            _function: &TonFunction,
+        ) -> Result<(TonConnection, TonResult), TonClientError> {
+            Err(TonClientError::InternalError("No connection".to_string()))
+        }
+
+        async fn invoke(&self, function: &TonFunction) -> Result<TonResult, TonClientError> {
+            let result = match function {
+                TonFunction::BlocksLookupBlock { id, .. } => TonResult::BlockIdExt(BlockIdExt {
+                    workchain: id.workchain,
+                    shard: id.shard,
+                    seqno: id.seqno,
+                    root_hash: String::new(),
+                    file_hash: String::new(),
+                }),
+                // The shard advances by 2 blocks between masterchain blocks
+                TonFunction::BlocksGetShards { id } => TonResult::BlocksShards(BlocksShards {
+                    shards: vec![shard_block(id.seqno * 2)],
+                }),
+                TonFunction::GetBlockHeader { id } => TonResult::BlocksHeader(BlocksHeader {
+                    id: id.clone(),
+                    global_id: 0,
+                    version: 0,
+                    flags: 0,
+                    after_merge: false,
+                    after_split: false,
+                    before_split: false,
+                    want_merge: false,
+                    want_split: false,
+                    validator_list_hash_short: 0,
+                    catchain_seqno: 0,
+                    min_ref_mc_seqno: 0,
+                    is_key_block: false,
+                    prev_key_block_seqno: 0,
+                    start_lt: 0,
+                    end_lt: 0,
+                    gen_utime: 0,
+                    vert_seqno: None,
+                    prev_blocks: Some(vec![shard_block(id.seqno - 1)]),
+                }),
+                _ => return Err(TonClientError::InternalError("Unexpected".to_string())),
+            };
+            Ok(result)
+        }
+    }
+
+    #[test]
+    fn block_ids_downloader_works() -> anyhow::Result<()> {
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        let mut backfill = Backfill::new(5..7, block_ids_downloader(MockClient), |_, ids| Ok(ids));
+        let blocks = runtime.block_on(async {
+            let mut blocks = vec![];
+            while let Some(block) = backfill.next().await? {
+                blocks.push(block);
+            }
+            anyhow::Ok(blocks)
+        })?;
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1].seqno, 6);
+        assert_eq!(blocks[1].block.master.workchain, -1);
+        assert_eq!(blocks[1].block.master.seqno, 6);
+        // Both shard blocks since the previous masterchain block, not only the shard top block
+        assert_eq!(blocks[1].block.shards, [shard_block(11), shard_block(12)]);
+        assert_eq!(blocks[0].block.shards, [shard_block(9), shard_block(10)]);
+        Ok(())
+    }
+}
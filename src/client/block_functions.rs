@@ -1,17 +1,206 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use futures::future::try_join_all;
+use futures::stream::{self, StreamExt, TryStreamExt};
 use futures::FutureExt;
 
 use crate::address::TonAddress;
-use crate::client::{TonClientError, TonClientInterface, TxId};
+use crate::client::{
+    BalanceSample, LookupBy, ShardTransitions, ShardsProofError, TonClientError,
+    TonClientInterface, TxId,
+};
+use crate::constants::MASTERCHAIN_ID;
 use crate::tl::{
-    BlockIdExt, BlocksAccountTransactionId, BlocksTransactions, RawTransaction,
-    NULL_BLOCKS_ACCOUNT_TRANSACTION_ID,
+    BlockId, BlockIdExt, BlocksAccountTransactionId, BlocksTransactions, RawFullAccountState,
+    RawTransaction, NULL_BLOCKS_ACCOUNT_TRANSACTION_ID,
 };
 
+/// Mode of `blocks.getTransactions` requesting account, lt and hash of each transaction
+/// along with the proof of the returned part of the `AccountBlocks` dictionary.
+pub const BLOCK_TRANSACTIONS_PROVED_MODE: u32 = 7 + 32;
+
+const BLOCK_TRANSACTIONS_AFTER_FLAG: u32 = 128;
+
+/// Maximum number of concurrent queries of [`TonBlockFunctions::get_balance_series`].
+pub const BALANCE_SAMPLING_CONCURRENCY: usize = 8;
+
+/// Returns the shard the account belongs to.
+///
+/// * `shards`: Shards of all workchains, e.g. as returned by `get_block_shards`.
+pub fn find_account_shard<'a>(
+    address: &TonAddress,
+    shards: &'a [BlockIdExt],
+) -> Option<&'a BlockIdExt> {
+    shards
+        .iter()
+        .find(|shard| address.is_in_shard(shard.workchain, shard.shard))
+}
+
 /// High-level functions for working with blocks & shards
 #[async_trait]
 pub trait TonBlockFunctions: TonClientInterface + Send + Sync {
+    /// Finds the block of specified shard by seqno, logical time or unix time.
+    async fn lookup_block_by(
+        &self,
+        workchain: i32,
+        shard: i64,
+        lookup_by: LookupBy,
+    ) -> Result<BlockIdExt, TonClientError> {
+        let (seqno, lt, utime) = match lookup_by {
+            LookupBy::Seqno(seqno) => (seqno, 0, 0),
+            LookupBy::Lt(lt) => (0, lt, 0),
+            LookupBy::Utime(utime) => (0, 0, utime),
+        };
+        let block_id = BlockId {
+            workchain,
+            shard,
+            seqno,
+        };
+        self.lookup_block(lookup_by.mode(), &block_id, lt, utime)
+            .await
+    }
+
+    /// Returns the shard blocks of the masterchain block by workchain.
+    ///
+    /// The shards are requested by `blocks.getShards`, so tonlib checks the proof of the shard
+    /// hashes against the root hash of the block. Shard hashes of a liteserver queried directly
+    /// are verified by [`AllShardsInfo::verify`].
+    ///
+    /// [`AllShardsInfo::verify`]: crate::client::AllShardsInfo::verify
+    async fn get_shards_verified(
+        &self,
+        master_block: &BlockIdExt,
+    ) -> Result<HashMap<i32, Vec<BlockIdExt>>, TonClientError> {
+        if master_block.workchain != MASTERCHAIN_ID {
+            return Err(ShardsProofError::NotMasterchainBlock(master_block.workchain).into());
+        }
+        let shards = self.get_block_shards(master_block).await?;
+        let mut result: HashMap<i32, Vec<BlockIdExt>> = HashMap::new();
+        for shard in shards.shards {
+            result.entry(shard.workchain).or_default().push(shard);
+        }
+        Ok(result)
+    }
+
+    /// Returns the block of the shard the account belongs to, as of specified masterchain block.
+    async fn get_account_shard(
+        &self,
+        address: &TonAddress,
+        mc_block_id: &BlockIdExt,
+    ) -> Result<BlockIdExt, TonClientError> {
+        if address.workchain == mc_block_id.workchain {
+            return Ok(mc_block_id.clone());
+        }
+        let shards = self.get_block_shards(mc_block_id).await?;
+        find_account_shard(address, &shards.shards)
+            .cloned()
+            .ok_or_else(|| {
+                TonClientError::InternalError(format!(
+                    "No shard of {} in masterchain block {}",
+                    address, mc_block_id.seqno
+                ))
+            })
+    }
+
+    /// Returns the state of the account, querying the block of its shard as of specified
+    /// masterchain block.
+    async fn get_raw_account_state_in_shard(
+        &self,
+        address: &TonAddress,
+        mc_block_id: &BlockIdExt,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        let shard = self.get_account_shard(address, mc_block_id).await?;
+        self.get_raw_account_state_at_block(address, &shard).await
+    }
+
+    /// Returns the balance of the account as of each masterchain block, in the order of the
+    /// blocks.
+    ///
+    /// The state is queried at the masterchain blocks, so no shard lookups are needed. Repeated
+    /// blocks are queried once and up to [`BALANCE_SAMPLING_CONCURRENCY`] queries run at once.
+    async fn get_balance_series(
+        &self,
+        address: &TonAddress,
+        mc_block_ids: &[BlockIdExt],
+    ) -> Result<Vec<BalanceSample>, TonClientError> {
+        let mut unique: HashMap<&BlockIdExt, usize> = HashMap::new();
+        let mut blocks = vec![];
+        for block_id in mc_block_ids {
+            unique.entry(block_id).or_insert_with(|| {
+                blocks.push(block_id);
+                blocks.len() - 1
+            });
+        }
+        // Futures are created upfront, as closures in the stream are not `Send` for any lifetime
+        let queries: Vec<_> = blocks
+            .into_iter()
+            .map(|block_id| self.get_raw_account_state_at_block(address, block_id))
+            .collect();
+        let states: Vec<RawFullAccountState> = stream::iter(queries)
+            .buffered(BALANCE_SAMPLING_CONCURRENCY)
+            .try_collect()
+            .await?;
+        let samples = mc_block_ids
+            .iter()
+            .map(|block_id| {
+                let state = &states[unique[block_id]];
+                BalanceSample {
+                    mc_block_id: block_id.clone(),
+                    balance: state.balance,
+                    last_transaction_id: state.last_transaction_id.clone(),
+                    sync_utime: state.sync_utime,
+                }
+            })
+            .collect();
+        Ok(samples)
+    }
+
+    /// Returns the balance of the account as of the masterchain block looked up by each unix
+    /// time, in the order of the times, see [`Self::get_balance_series`].
+    async fn get_balance_series_at(
+        &self,
+        address: &TonAddress,
+        utimes: &[i32],
+    ) -> Result<Vec<BalanceSample>, TonClientError> {
+        let lookups: Vec<_> = utimes
+            .iter()
+            .map(|utime| self.lookup_block_by(-1, i64::MIN, LookupBy::Utime(*utime)))
+            .collect();
+        let mc_block_ids: Vec<BlockIdExt> = stream::iter(lookups)
+            .buffered(BALANCE_SAMPLING_CONCURRENCY)
+            .try_collect()
+            .await?;
+        self.get_balance_series(address, &mc_block_ids).await
+    }
+
+    /// Returns the shard splits and merges between the previous masterchain block and
+    /// specified one.
+    async fn get_shard_transitions(
+        &self,
+        mc_block_id: &BlockIdExt,
+    ) -> Result<ShardTransitions, TonClientError> {
+        if mc_block_id.seqno <= 0 {
+            return Err(TonClientError::InternalError(format!(
+                "Masterchain block {} has no previous block",
+                mc_block_id.seqno
+            )));
+        }
+        let prev_block_id = self
+            .lookup_block_by(
+                mc_block_id.workchain,
+                mc_block_id.shard,
+                LookupBy::Seqno(mc_block_id.seqno - 1),
+            )
+            .await?;
+        let prev_shards = self.get_block_shards(&prev_block_id).await?;
+        let shards = self.get_block_shards(mc_block_id).await?;
+        Ok(ShardTransitions::between(
+            &prev_shards.shards,
+            &shards.shards,
+        ))
+    }
+
     /// Returns the list of all transaction IDs in specified shard.
     async fn get_shard_tx_ids(&self, shard_id: &BlockIdExt) -> Result<Vec<TxId>, TonClientError> {
         let mut after: BlocksAccountTransactionId = NULL_BLOCKS_ACCOUNT_TRANSACTION_ID.clone();
@@ -38,6 +227,67 @@ pub trait TonBlockFunctions: TonClientInterface + Send + Sync {
         Ok(transactions)
     }
 
+    /// Returns ids of all transactions in specified block, ordered by account and lt.
+    ///
+    /// Each chunk is requested with proof, so tonlib checks it against the block. Fails if
+    /// a chunk belongs to another block, is out of order, lacks any of account, lt or hash,
+    /// or is empty while the block is reported to be incomplete.
+    async fn get_block_transactions_all(
+        &self,
+        block_id: &BlockIdExt,
+    ) -> Result<Vec<TxId>, TonClientError> {
+        let mut after: BlocksAccountTransactionId = NULL_BLOCKS_ACCOUNT_TRANSACTION_ID.clone();
+        let mut transactions: Vec<TxId> = Vec::new();
+        loop {
+            let mode = if transactions.is_empty() {
+                BLOCK_TRANSACTIONS_PROVED_MODE
+            } else {
+                BLOCK_TRANSACTIONS_PROVED_MODE + BLOCK_TRANSACTIONS_AFTER_FLAG
+            };
+            let txs: BlocksTransactions = self
+                .get_block_transactions(block_id, mode, 256, &after)
+                .await?;
+            if txs.id != *block_id {
+                return Err(TonClientError::InternalError(format!(
+                    "Requested transactions of block {:?}, got {:?}",
+                    block_id, txs.id
+                )));
+            }
+            if txs.incomplete && txs.transactions.is_empty() {
+                return Err(TonClientError::InternalError(format!(
+                    "Empty incomplete list of transactions of block {:?}",
+                    block_id
+                )));
+            }
+            transactions.reserve(txs.transactions.len());
+            for tx in txs.transactions {
+                if tx.mode & 7 != 7 {
+                    return Err(TonClientError::InternalError(format!(
+                        "Incomplete transaction id in block {:?}: {:?}",
+                        block_id, tx
+                    )));
+                }
+                let is_ordered =
+                    (tx.account.as_slice(), tx.lt) > (after.account.as_slice(), after.lt);
+                if !transactions.is_empty() && !is_ordered {
+                    return Err(TonClientError::InternalError(format!(
+                        "Transaction {:?} is out of order in block {:?}",
+                        tx, block_id
+                    )));
+                }
+                transactions.push(TxId::new(block_id.workchain, &tx)?);
+                after = BlocksAccountTransactionId {
+                    account: tx.account,
+                    lt: tx.lt,
+                };
+            }
+            if !txs.incomplete {
+                break;
+            }
+        }
+        Ok(transactions)
+    }
+
     async fn get_shards_tx_ids(
         &self,
         shards: &[BlockIdExt],
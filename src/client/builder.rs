@@ -2,9 +2,11 @@ use std::sync::Arc;
 
 use super::TonConnectionCallback;
 use crate::client::{
-    error, ConnectionCheck, MultiConnectionCallback, RetryStrategy, TonClient, TonConnectionParams,
-    LOGGING_CONNECTION_CALLBACK, NOOP_CONNECTION_CALLBACK,
+    error, ConnectionCheck, HealthCheck, MultiConnectionCallback, RateLimit, RetryStrategy,
+    TonClient, TonConnectionParams, LOGGING_CONNECTION_CALLBACK, NOOP_CONNECTION_CALLBACK,
 };
+use crate::config::{embedded_config, TonConfigError};
+use crate::network::Network;
 
 pub struct TonClientBuilder {
     pool_size: usize,
@@ -12,6 +14,8 @@ pub struct TonClientBuilder {
     retry_strategy: RetryStrategy,
     callback: Arc<dyn TonConnectionCallback>,
     connection_check: ConnectionCheck,
+    concurrency_limit: usize,
+    health_check: Option<HealthCheck>,
 }
 
 impl TonClientBuilder {
@@ -22,6 +26,8 @@ impl TonClientBuilder {
             retry_strategy: RetryStrategy::default(),
             callback: LOGGING_CONNECTION_CALLBACK.clone(),
             connection_check: ConnectionCheck::None,
+            concurrency_limit: 0,
+            health_check: None,
         }
     }
 
@@ -40,6 +46,15 @@ impl TonClientBuilder {
         self
     }
 
+    /// Uses the embedded config of the network.
+    ///
+    /// Fails for custom networks, which have no embedded config and are configured by
+    /// [`with_config`](Self::with_config) instead.
+    pub fn with_network(&mut self, network: Network) -> Result<&mut Self, TonConfigError> {
+        self.connection_params.config = embedded_config(network)?.to_string();
+        Ok(self)
+    }
+
     pub fn with_retry_strategy(&mut self, retry_strategy: &RetryStrategy) -> &mut Self {
         self.retry_strategy = retry_strategy.clone();
         self
@@ -80,15 +95,46 @@ impl TonClientBuilder {
         self
     }
 
+    /// Limits the number of requests running at once on each connection of the pool.
+    pub fn with_connection_concurrency_limit(&mut self, concurrency_limit: usize) -> &mut Self {
+        self.connection_params.concurrency_limit = concurrency_limit;
+        self
+    }
+
+    /// Limits the rate of requests sent through each connection of the pool.
+    pub fn with_rate_limit(&mut self, rate_limit: &RateLimit) -> &mut Self {
+        self.connection_params.rate_limit = Some(rate_limit.clone());
+        self
+    }
+
+    /// Limits the number of requests running at once across the whole pool.
+    /// Zero means no limit.
+    pub fn with_global_concurrency_limit(&mut self, concurrency_limit: usize) -> &mut Self {
+        self.concurrency_limit = concurrency_limit;
+        self
+    }
+
+    /// Probes the connections of the pool in the background and re-establishes broken ones.
+    /// The results are reported by [`TonClient::connection_health`].
+    pub fn with_health_check(&mut self, health_check: &HealthCheck) -> &mut Self {
+        self.health_check = Some(health_check.clone());
+        self
+    }
+
     pub async fn build(&self) -> Result<TonClient, error::TonClientError> {
-        TonClient::new(
+        let client = TonClient::with_concurrency_limit(
             self.pool_size,
             &self.connection_params,
             &self.retry_strategy,
             self.callback.clone(),
             self.connection_check.clone(),
+            self.concurrency_limit,
         )
-        .await
+        .await?;
+        if let Some(health_check) = &self.health_check {
+            client.start_health_check(health_check);
+        }
+        Ok(client)
     }
 }
 
@@ -97,3 +143,22 @@ impl Default for TonClientBuilder {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::client::TonClientBuilder;
+    use crate::config::TESTNET_CONFIG;
+    use crate::network::Network;
+
+    #[test]
+    fn with_network_works() -> anyhow::Result<()> {
+        let mut builder = TonClientBuilder::new();
+        builder.with_network(Network::Testnet)?;
+        assert_eq!(builder.connection_params.config, TESTNET_CONFIG);
+
+        builder.with_config("{}");
+        assert!(builder.with_network(Network::Custom(42)).is_err());
+        assert_eq!(builder.connection_params.config, "{}");
+        Ok(())
+    }
+}
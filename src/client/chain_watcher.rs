@@ -0,0 +1,330 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use tokio::time;
+
+use crate::client::{TonClientError, TonClientInterface};
+use crate::tl::{BlockId, BlockIdExt, TonFunction, TonResult, TonResultDiscriminants};
+
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const DEFAULT_HISTORY_SIZE: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChainEvent {
+    /// Next masterchain block.
+    Block(BlockIdExt),
+    /// Previously emitted blocks are no longer part of the chain. The blocks starting
+    /// from `from_seqno` are emitted again by the following calls.
+    Reorg {
+        from_seqno: i32,
+        dropped: Vec<BlockIdExt>,
+    },
+}
+
+/// Follows the masterchain tip, verifying that each block is linked to the previous one.
+///
+/// When a block emitted before turns out to differ from the block returned by the liteserver
+/// (a reorg of not yet finalized data or an inconsistent liteserver), `ChainEvent::Reorg`
+/// is emitted and the blocks are replayed from the last consistent seqno.
+pub struct ChainWatcher<C: TonClientInterface + Clone> {
+    client: C,
+    next_seqno: i32,
+    poll_interval: Duration,
+    history_size: usize,
+    history: VecDeque<BlockIdExt>,
+}
+
+impl<C: TonClientInterface + Clone> ChainWatcher<C> {
+    pub fn new(client: &C, from_seqno: i32) -> ChainWatcher<C> {
+        ChainWatcher {
+            client: client.clone(),
+            next_seqno: from_seqno,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            history_size: DEFAULT_HISTORY_SIZE,
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Sets the number of emitted blocks that are checked for reorgs.
+    pub fn with_history_size(mut self, history_size: usize) -> Self {
+        self.history_size = history_size.max(1);
+        self
+    }
+
+    /// Returns the next chain event.
+    ///
+    /// If the next block is not yet available, the returned future resolves when it's added
+    /// to masterchain.
+    pub async fn next(&mut self) -> Result<ChainEvent, TonClientError> {
+        loop {
+            let info = match self
+                .client
+                .invoke(&TonFunction::BlocksGetMasterchainInfo {})
+                .await?
+            {
+                TonResult::BlocksMasterchainInfo(info) => info,
+                r => {
+                    return Err(TonClientError::unexpected_ton_result(
+                        TonResultDiscriminants::BlocksMasterchainInfo,
+                        r,
+                    ))
+                }
+            };
+            let tip = info.last;
+            if let Some(emitted) = self.emitted(tip.seqno) {
+                if *emitted != tip {
+                    match self.reorg().await? {
+                        Some(event) => return Ok(event),
+                        None => {
+                            time::sleep(self.poll_interval).await;
+                            continue;
+                        }
+                    }
+                }
+            }
+            if tip.seqno < self.next_seqno {
+                time::sleep(self.poll_interval).await;
+                continue;
+            }
+
+            let block = lookup_master_block(&self.client, self.next_seqno).await?;
+            if let Some(last) = self.history.back() {
+                let header = self.client.get_block_header(&block).await?;
+                let prev_blocks = header.prev_blocks.unwrap_or_default();
+                if !prev_blocks.contains(last) {
+                    match self.reorg().await? {
+                        Some(event) => return Ok(event),
+                        None => {
+                            time::sleep(self.poll_interval).await;
+                            continue;
+                        }
+                    }
+                }
+            }
+            self.history.push_back(block.clone());
+            if self.history.len() > self.history_size {
+                self.history.pop_front();
+            }
+            self.next_seqno += 1;
+            return Ok(ChainEvent::Block(block));
+        }
+    }
+
+    fn emitted(&self, seqno: i32) -> Option<&BlockIdExt> {
+        let first = self.history.front()?.seqno;
+        if seqno < first {
+            return None;
+        }
+        self.history.get((seqno - first) as usize)
+    }
+
+    /// Drops emitted blocks until the last one that is still part of the chain.
+    ///
+    /// Returns `None` if no emitted block differs from the chain, e.g. when the liteserver
+    /// answering the lookups lags behind the one that returned the new block, so the caller
+    /// retries after the poll interval.
+    async fn reorg(&mut self) -> Result<Option<ChainEvent>, TonClientError> {
+        let mut dropped = Vec::new();
+        while let Some(block) = self.history.back() {
+            let actual = lookup_master_block(&self.client, block.seqno).await?;
+            if actual == *block {
+                break;
+            }
+            dropped.push(self.history.pop_back().unwrap());
+        }
+        if dropped.is_empty() {
+            log::debug!(
+                "Masterchain block {} is not linked to the emitted blocks, retrying",
+                self.next_seqno
+            );
+            return Ok(None);
+        }
+        if self.history.is_empty() {
+            return Err(TonClientError::InternalError(format!(
+                "Reorg is deeper than the emitted blocks kept ({})",
+                dropped.len()
+            )));
+        }
+        dropped.reverse();
+        let from_seqno = self.history.back().unwrap().seqno + 1;
+        log::warn!(
+            "Masterchain reorg detected, replaying from seqno {}, dropped {} blocks",
+            from_seqno,
+            dropped.len()
+        );
+        self.next_seqno = from_seqno;
+        Ok(Some(ChainEvent::Reorg {
+            from_seqno,
+            dropped,
+        }))
+    }
+}
+
+async fn lookup_master_block<C: TonClientInterface>(
+    client: &C,
+    seqno: i32,
+) -> Result<BlockIdExt, TonClientError> {
+    let block_id = BlockId {
+        workchain: -1,
+        shard: i64::MIN,
+        seqno,
+    };
+    client.lookup_block(1, &block_id, 0, 0).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+
+    use crate::client::{
+        ChainEvent, ChainWatcher, TonClientError, TonClientInterface, TonConnection,
+    };
+    use crate::tl::{BlockIdExt, BlocksHeader, BlocksMasterchainInfo, TonFunction, TonResult};
+
+    fn block(fork: &str, seqno: i32) -> BlockIdExt {
+        BlockIdExt {
+            workchain: -1,
+            shard: i64::MIN,
+            seqno,
+            root_hash: format!("{}{}", fork, seqno),
+            file_hash: String::new(),
+        }
+    }
+
+    fn chain(forks: &[&str]) -> Vec<BlockIdExt> {
+        forks
+            .iter()
+            .enumerate()
+            .map(|(seqno, fork)| block(fork, seqno as i32))
+            .collect()
+    }
+
+    #[derive(Default)]
+    struct MockChain {
+        blocks: Vec<BlockIdExt>,
+        /// Blocks returned by the next `lagging_lookups` lookups, e.g. by a lagging liteserver.
+        lagging_blocks: Vec<BlockIdExt>,
+        lagging_lookups: usize,
+    }
+
+    #[derive(Clone, Default)]
+    struct MockClient {
+        chain: Arc<Mutex<MockChain>>,
+    }
+
+    #[async_trait]
+    impl TonClientInterface for MockClient {
+        async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+            Err(TonClientError::InternalError("No connection".to_string()))
+        }
+
+        async fn invoke_on_connection(
+            &self,
+            _function: &TonFunction,
+        ) -> Result<(TonConnection, TonResult), TonClientError> {
+            Err(TonClientError::InternalError("No connection".to_string()))
+        }
+
+        async fn invoke(&self, function: &TonFunction) -> Result<TonResult, TonClientError> {
+            let mut chain = self.chain.lock().unwrap();
+            let result = match function {
+                TonFunction::BlocksGetMasterchainInfo {} => {
+                    TonResult::BlocksMasterchainInfo(BlocksMasterchainInfo {
+                        last: chain.blocks.last().unwrap().clone(),
+                        state_root_hash: vec![],
+                        init: chain.blocks[0].clone(),
+                    })
+                }
+                TonFunction::BlocksLookupBlock { id, .. } => {
+                    let blocks = if chain.lagging_lookups > 0 {
+                        chain.lagging_lookups -= 1;
+                        &chain.lagging_blocks
+                    } else {
+                        &chain.blocks
+                    };
+                    TonResult::BlockIdExt(blocks[id.seqno as usize].clone())
+                }
+                TonFunction::GetBlockHeader { id } => TonResult::BlocksHeader(BlocksHeader {
+                    id: id.clone(),
+                    global_id: 0,
+                    version: 0,
+                    flags: 0,
+                    after_merge: false,
+                    after_split: false,
+                    before_split: false,
+                    want_merge: false,
+                    want_split: false,
+                    validator_list_hash_short: 0,
+                    catchain_seqno: 0,
+                    min_ref_mc_seqno: 0,
+                    is_key_block: false,
+                    prev_key_block_seqno: 0,
+                    start_lt: 0,
+                    end_lt: 0,
+                    gen_utime: 0,
+                    vert_seqno: None,
+                    prev_blocks: Some(vec![chain.blocks[id.seqno as usize - 1].clone()]),
+                }),
+                _ => return Err(TonClientError::InternalError("Unexpected function".into())),
+            };
+            Ok(result)
+        }
+    }
+
+    #[test]
+    fn chain_watcher_works() -> anyhow::Result<()> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()?;
+        let client = MockClient::default();
+        client.chain.lock().unwrap().blocks = chain(&["a", "a", "a", "a"]);
+        let mut watcher =
+            ChainWatcher::new(&client, 1).with_poll_interval(Duration::from_millis(1));
+        runtime.block_on(async {
+            for seqno in 1..=3 {
+                assert_eq!(watcher.next().await?, ChainEvent::Block(block("a", seqno)));
+            }
+
+            // The new block is not linked to the emitted ones, while the lookups still return
+            // them: the watcher retries instead of emitting an empty reorg
+            {
+                let mut mock = client.chain.lock().unwrap();
+                mock.blocks = chain(&["a", "a", "b", "b", "b"]);
+                mock.lagging_blocks = chain(&["a", "a", "a", "a", "b"]);
+                mock.lagging_lookups = 2;
+            }
+            assert_eq!(
+                watcher.next().await?,
+                ChainEvent::Reorg {
+                    from_seqno: 2,
+                    dropped: vec![block("a", 2), block("a", 3)],
+                }
+            );
+            for seqno in 2..=4 {
+                assert_eq!(watcher.next().await?, ChainEvent::Block(block("b", seqno)));
+            }
+            anyhow::Ok(())
+        })?;
+
+        let mut watcher =
+            ChainWatcher::new(&client, 1).with_poll_interval(Duration::from_millis(1));
+        let error = runtime.block_on(async {
+            watcher.next().await?;
+            client.chain.lock().unwrap().blocks = chain(&["c", "c", "c"]);
+            anyhow::Ok(watcher.next().await.unwrap_err())
+        })?;
+        assert_eq!(
+            error.to_string(),
+            "Internal error (Reorg is deeper than the emitted blocks kept (1))"
+        );
+        Ok(())
+    }
+}
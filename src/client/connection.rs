@@ -8,13 +8,16 @@ use async_trait::async_trait;
 use dashmap::DashMap;
 use tokio::sync::{broadcast, oneshot, Semaphore, SemaphorePermit};
 
+use crate::client::rate_limit::{QueueMetricsRecorder, TokenBucket};
 use crate::client::{
-    TonClientError, TonClientInterface, TonConnectionCallback, TonConnectionParams,
+    QueueMetrics, TonClientError, TonClientInterface, TonConnectionCallback, TonConnectionParams,
     TonNotificationReceiver,
 };
 use crate::tl::{
-    BlockId, Config, KeyStoreType, Options, OptionsInfo, SmcRunResult, TlTonClient, TonFunction,
-    TonNotification, TonResult, TonResultDiscriminants, TvmStackEntry,
+    BlockId, Config, InitRequest, KeyStoreType, Options, OptionsConfigInfo, OptionsInfo,
+    OptionsSetConfigRequest, QueryEstimateFeesRequest, QueryFees, QueryForgetRequest,
+    SmcRunGetMethodRequest, SmcRunResult, TlTonClient, TonFunction, TonNotification, TonResult,
+    TvmStackEntry,
 };
 use crate::types::TonMethodId;
 
@@ -38,6 +41,8 @@ struct Inner {
     callback: Arc<dyn TonConnectionCallback>,
     _notification_receiver: TonNotificationReceiver,
     semaphore: Option<Semaphore>,
+    rate_limiter: Option<TokenBucket>,
+    queue_metrics: QueueMetricsRecorder,
 }
 
 pub struct TonConnection {
@@ -92,6 +97,8 @@ impl TonConnection {
             callback,
             _notification_receiver: receiver,
             semaphore,
+            rate_limiter: params.rate_limit.as_ref().map(TokenBucket::new),
+            queue_metrics: QueueMetricsRecorder::default(),
         };
         let inner_arc = Arc::new(inner);
         let inner_weak: Weak<Inner> = Arc::downgrade(&inner_arc);
@@ -191,7 +198,7 @@ impl TonConnection {
         ignore_cache: bool,
         keystore_type: KeyStoreType,
     ) -> Result<OptionsInfo, TonClientError> {
-        let func = TonFunction::Init {
+        let request = InitRequest {
             options: Options {
                 config: Config {
                     config: String::from(config),
@@ -202,14 +209,27 @@ impl TonConnection {
                 keystore_type,
             },
         };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::OptionsInfo(options_info) => Ok(options_info),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::OptionsInfo,
-                r,
-            )),
-        }
+        self.invoke_request(request).await
+    }
+
+    /// Replaces the config of an initialized TonConnection, e.g. to pick up changes
+    /// of the liteserver list
+    pub async fn set_config(
+        &self,
+        config: &str,
+        blockchain_name: Option<&str>,
+        use_callbacks_for_network: bool,
+        ignore_cache: bool,
+    ) -> Result<OptionsConfigInfo, TonClientError> {
+        let request = OptionsSetConfigRequest {
+            config: Config {
+                config: String::from(config),
+                blockchain_name: blockchain_name.map(String::from),
+                use_callbacks_for_network,
+                ignore_cache,
+            },
+        };
+        self.invoke_request(request).await
     }
 
     pub fn subscribe(&self) -> TonNotificationReceiver {
@@ -222,23 +242,37 @@ impl TonConnection {
         method: &TonMethodId,
         stack: &[TvmStackEntry],
     ) -> Result<SmcRunResult, TonClientError> {
-        let func = TonFunction::SmcRunGetMethod {
+        let request = SmcRunGetMethodRequest {
             id,
             method: method.into(),
             stack: stack.to_vec(),
         };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::SmcRunResult(result) => Ok(result),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::SmcRunResult,
-                r,
-            )),
-        }
+        self.invoke_request(request).await
+    }
+
+    /// Estimates fees of the query created by `raw_create_query` on this connection.
+    pub async fn query_estimate_fees(
+        &self,
+        id: i64,
+        ignore_chksig: bool,
+    ) -> Result<QueryFees, TonClientError> {
+        let request = QueryEstimateFeesRequest { id, ignore_chksig };
+        self.invoke_request(request).await
+    }
+
+    pub async fn query_forget(&self, id: i64) -> Result<(), TonClientError> {
+        self.invoke_request(QueryForgetRequest { id }).await
+    }
+
+    /// Returns the time spent by requests of this connection waiting for the rate limit
+    /// and the concurrency limit.
+    pub fn queue_metrics(&self) -> QueueMetrics {
+        self.inner.queue_metrics.metrics()
     }
 
     async fn limit_rate(&self) -> Result<Option<SemaphorePermit>, TonClientError> {
-        Ok(if let Some(semaphore) = &self.inner.semaphore {
+        let start = Instant::now();
+        let permit = if let Some(semaphore) = &self.inner.semaphore {
             Some(
                 semaphore
                     .acquire()
@@ -247,7 +281,12 @@ impl TonConnection {
             )
         } else {
             None
-        })
+        };
+        if let Some(rate_limiter) = &self.inner.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+        self.inner.queue_metrics.record(start.elapsed());
+        Ok(permit)
     }
 }
 
@@ -3,6 +3,7 @@ use std::io;
 use thiserror::Error;
 
 use crate::address::TonAddressParseError;
+use crate::client::ShardsProofError;
 use crate::tl::{TlError, TonResult, TonResultDiscriminants};
 
 #[derive(Error, Debug)]
@@ -17,6 +18,9 @@ pub enum TonClientError {
         message: String,
     },
 
+    #[error("External message rejected (code: {code}, message: {message})")]
+    ExternalMessageRejected { code: i32, message: String },
+
     #[error("Unexpected TonResult (Actual: {actual}, expected: {expected})")]
     UnexpectedTonResult {
         actual: TonResultDiscriminants,
@@ -31,6 +35,9 @@ pub enum TonClientError {
 
     #[error("TonAddressParseError: ({0})")]
     TonAddressParseError(#[from] TonAddressParseError),
+
+    #[error("ShardsProofError: ({0})")]
+    ShardsProofError(#[from] ShardsProofError),
 }
 
 impl TonClientError {
@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+
+use num_bigint::BigUint;
+
+use crate::client::{BlockStream, TonClientError, TonClientInterface};
+use crate::responses::{BlockData, CurrencyCollection, ValueFlow};
+use crate::tl::BlockIdExt;
+
+/// Fees of a single block, in nanotons. Extra currencies are not included.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FeeSummary {
+    pub fees_collected: BigUint,
+    /// Always zero for blocks with `value_flow` (v1).
+    pub burned: BigUint,
+    pub created: BigUint,
+    pub minted: BigUint,
+}
+
+impl FeeSummary {
+    pub fn from_value_flow(value_flow: &ValueFlow) -> FeeSummary {
+        FeeSummary {
+            fees_collected: grams(&value_flow.fees_collected),
+            burned: value_flow.burned.as_ref().map(grams).unwrap_or_default(),
+            created: grams(&value_flow.created),
+            minted: grams(&value_flow.minted),
+        }
+    }
+
+    fn add(&mut self, other: &FeeSummary) {
+        self.fees_collected += &other.fees_collected;
+        self.burned += &other.burned;
+        self.created += &other.created;
+        self.minted += &other.minted;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockFees {
+    pub block_id: BlockIdExt,
+    pub fees: FeeSummary,
+}
+
+/// Fees of all analyzed blocks of a shard.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShardFees {
+    pub block_count: usize,
+    pub fees: FeeSummary,
+}
+
+/// Aggregates the value flow of blocks into per-block and per-shard fee summaries.
+///
+/// Blocks without value flow (e.g. loaded from proofs with the value flow pruned) are skipped.
+#[derive(Debug, Clone, Default)]
+pub struct BlockFeeAnalytics {
+    blocks: Vec<BlockFees>,
+    shards: BTreeMap<(i32, i64), ShardFees>,
+}
+
+impl BlockFeeAnalytics {
+    pub fn new() -> BlockFeeAnalytics {
+        Default::default()
+    }
+
+    /// Collects the fees of the next `count` masterchain blocks of `stream` and their shard
+    /// blocks.
+    ///
+    /// tonlib doesn't return block data, so it's loaded by `load_block`, e.g. from
+    /// `liteServer.getBlock` of a liteserver.
+    pub async fn collect<C, F, Fut>(
+        stream: &mut BlockStream<C>,
+        count: usize,
+        mut load_block: F,
+    ) -> Result<BlockFeeAnalytics, TonClientError>
+    where
+        C: TonClientInterface + Clone,
+        F: FnMut(BlockIdExt) -> Fut,
+        Fut: Future<Output = Result<BlockData, TonClientError>>,
+    {
+        let mut analytics = BlockFeeAnalytics::new();
+        for _ in 0..count {
+            let item = stream.next().await?;
+            for block_id in std::iter::once(item.master_shard).chain(item.shards) {
+                let block = load_block(block_id.clone()).await?;
+                analytics.add_block(&block_id, &block);
+            }
+        }
+        Ok(analytics)
+    }
+
+    /// Adds the fees of the block, returns `None` if it has no value flow.
+    pub fn add_block(&mut self, block_id: &BlockIdExt, block: &BlockData) -> Option<&BlockFees> {
+        let fees = FeeSummary::from_value_flow(block.value_flow.as_ref()?);
+        let shard = self
+            .shards
+            .entry((block_id.workchain, block_id.shard))
+            .or_default();
+        shard.block_count += 1;
+        shard.fees.add(&fees);
+        self.blocks.push(BlockFees {
+            block_id: block_id.clone(),
+            fees,
+        });
+        self.blocks.last()
+    }
+
+    /// Returns the fees of the blocks in the order they were added.
+    pub fn blocks(&self) -> &[BlockFees] {
+        &self.blocks
+    }
+
+    /// Returns the fees by `(workchain, shard)`.
+    pub fn shards(&self) -> &BTreeMap<(i32, i64), ShardFees> {
+        &self.shards
+    }
+
+    /// Returns the fees of all blocks of the workchain.
+    pub fn workchain_total(&self, workchain: i32) -> FeeSummary {
+        let mut total = FeeSummary::default();
+        self.shards
+            .range((workchain, i64::MIN)..=(workchain, i64::MAX))
+            .for_each(|(_, shard)| total.add(&shard.fees));
+        total
+    }
+
+    /// Returns the fees of all blocks.
+    pub fn total(&self) -> FeeSummary {
+        let mut total = FeeSummary::default();
+        self.shards
+            .values()
+            .for_each(|shard| total.add(&shard.fees));
+        total
+    }
+}
+
+fn grams(collection: &CurrencyCollection) -> BigUint {
+    collection.grams.value.clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use crate::client::BlockFeeAnalytics;
+    use crate::responses::{BlockData, CurrencyCollection, ValueFlow, VarUInteger};
+    use crate::tl::BlockIdExt;
+
+    fn block_id(workchain: i32, shard: i64, seqno: i32) -> BlockIdExt {
+        BlockIdExt {
+            workchain,
+            shard,
+            seqno,
+            root_hash: String::new(),
+            file_hash: String::new(),
+        }
+    }
+
+    fn block(fees_collected: u32, burned: Option<u32>, created: u32) -> BlockData {
+        let coins = |value: u32| CurrencyCollection {
+            grams: VarUInteger {
+                len: BigUint::from(4u32),
+                value: BigUint::from(value),
+            },
+            ..Default::default()
+        };
+        BlockData {
+            value_flow: Some(ValueFlow {
+                fees_collected: coins(fees_collected),
+                burned: burned.map(coins),
+                created: coins(created),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn block_fees_are_aggregated_by_shard() {
+        let mut analytics = BlockFeeAnalytics::new();
+        let master = block_id(-1, i64::MIN, 1);
+        let fees = analytics
+            .add_block(&master, &block(10, Some(5), 1700))
+            .unwrap();
+        assert_eq!(fees.fees.burned, BigUint::from(5u32));
+        analytics.add_block(&block_id(0, i64::MIN, 7), &block(3, None, 1000));
+        analytics.add_block(&block_id(0, i64::MIN, 8), &block(4, None, 1000));
+        analytics.add_block(&block_id(0, 0x4000000000000000, 9), &block(2, None, 1000));
+        assert!(analytics
+            .add_block(&block_id(0, i64::MIN, 10), &BlockData::default())
+            .is_none());
+
+        assert_eq!(analytics.blocks().len(), 4);
+        assert_eq!(analytics.shards().len(), 3);
+        let shard = &analytics.shards()[&(0, i64::MIN)];
+        assert_eq!(shard.block_count, 2);
+        assert_eq!(shard.fees.fees_collected, BigUint::from(7u32));
+        assert_eq!(
+            analytics.workchain_total(0).fees_collected,
+            BigUint::from(9u32)
+        );
+        assert_eq!(analytics.workchain_total(0).created, BigUint::from(3000u32));
+        let total = analytics.total();
+        assert_eq!(total.fees_collected, BigUint::from(19u32));
+        assert_eq!(total.burned, BigUint::from(5u32));
+        assert_eq!(total.created, BigUint::from(4700u32));
+        assert_eq!(total.minted, BigUint::from(0u32));
+    }
+}
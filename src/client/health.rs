@@ -0,0 +1,230 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Parameters of the background health checks of the connections of a pool.
+///
+/// Each established connection is probed with `getMasterchainInfo`. After `max_failures`
+/// consecutive failed probes the connection is dropped and re-established with exponential
+/// backoff between `min_backoff_ms` and `max_backoff_ms`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct HealthCheck {
+    pub interval_ms: u64,
+    /// Time after which a probe is considered failed.
+    pub timeout_ms: u64,
+    pub max_failures: u32,
+    pub min_backoff_ms: u64,
+    pub max_backoff_ms: u64,
+}
+
+impl Default for HealthCheck {
+    fn default() -> Self {
+        HealthCheck {
+            interval_ms: 10_000,
+            timeout_ms: 5_000,
+            max_failures: 3,
+            min_backoff_ms: 1_000,
+            max_backoff_ms: 60_000,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionStatus {
+    /// The connection is established lazily and has not been used yet.
+    NotConnected,
+    /// The last probe succeeded or no probe was made since connecting.
+    Healthy,
+    /// Some of the last probes failed, but fewer than `max_failures`.
+    Degraded,
+    /// The connection was dropped and is waiting to be re-established.
+    Reconnecting,
+}
+
+/// Health of a single connection of the pool, as seen by the health checks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionHealth {
+    /// Index of the connection in the pool.
+    pub index: usize,
+    /// Tag of the current connection, `None` if it's not established.
+    pub tag: Option<String>,
+    pub status: ConnectionStatus,
+    /// Latency of the last successful probe.
+    pub last_latency: Option<Duration>,
+    /// Masterchain seqno returned by the last successful probe.
+    pub last_seqno: Option<i32>,
+    pub last_error: Option<String>,
+    pub consecutive_failures: u32,
+    pub total_probes: u64,
+    pub failed_probes: u64,
+    pub reconnects: u64,
+}
+
+/// Tracks the health of a connection and schedules its reconnection.
+pub(crate) struct HealthRecorder {
+    state: Mutex<HealthState>,
+}
+
+struct HealthState {
+    health: ConnectionHealth,
+    /// Delay before the next scheduled reconnection.
+    backoff: Option<Duration>,
+    reconnect_at: Option<Instant>,
+}
+
+impl HealthRecorder {
+    pub(crate) fn new(index: usize) -> HealthRecorder {
+        let health = ConnectionHealth {
+            index,
+            tag: None,
+            status: ConnectionStatus::NotConnected,
+            last_latency: None,
+            last_seqno: None,
+            last_error: None,
+            consecutive_failures: 0,
+            total_probes: 0,
+            failed_probes: 0,
+            reconnects: 0,
+        };
+        HealthRecorder {
+            state: Mutex::new(HealthState {
+                health,
+                backoff: None,
+                reconnect_at: None,
+            }),
+        }
+    }
+
+    pub(crate) fn health(&self) -> ConnectionHealth {
+        self.state.lock().unwrap().health.clone()
+    }
+
+    /// Returns `false` if the connection is waiting to be re-established.
+    pub(crate) fn is_available(&self) -> bool {
+        self.state.lock().unwrap().health.status != ConnectionStatus::Reconnecting
+    }
+
+    /// Returns `true` if the connection is waiting to be re-established and its backoff
+    /// has elapsed.
+    pub(crate) fn should_reconnect(&self, now: Instant) -> bool {
+        let state = self.state.lock().unwrap();
+        state.health.status == ConnectionStatus::Reconnecting
+            && state.reconnect_at.is_none_or(|at| at <= now)
+    }
+
+    pub(crate) fn record_connected(&self, tag: &str) {
+        let mut state = self.state.lock().unwrap();
+        if state.health.status == ConnectionStatus::Reconnecting {
+            state.health.reconnects += 1;
+        }
+        state.health.tag = Some(tag.to_string());
+        state.health.status = ConnectionStatus::Healthy;
+        state.health.consecutive_failures = 0;
+        state.reconnect_at = None;
+    }
+
+    pub(crate) fn record_connect_failure(&self, error: String, params: &HealthCheck) {
+        let mut state = self.state.lock().unwrap();
+        state.health.last_error = Some(error);
+        state.schedule_reconnect(params);
+    }
+
+    pub(crate) fn record_success(&self, latency: Duration, seqno: i32) {
+        let mut state = self.state.lock().unwrap();
+        state.health.status = ConnectionStatus::Healthy;
+        state.health.last_latency = Some(latency);
+        state.health.last_seqno = Some(seqno);
+        state.health.consecutive_failures = 0;
+        state.health.total_probes += 1;
+        state.backoff = None;
+    }
+
+    /// Records a failed probe and returns `true` if the connection must be dropped.
+    pub(crate) fn record_failure(&self, error: String, params: &HealthCheck) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.health.last_error = Some(error);
+        state.health.consecutive_failures += 1;
+        state.health.total_probes += 1;
+        state.health.failed_probes += 1;
+        if state.health.consecutive_failures < params.max_failures.max(1) {
+            state.health.status = ConnectionStatus::Degraded;
+            return false;
+        }
+        state.schedule_reconnect(params);
+        true
+    }
+
+    /// Records that the connection loop exited, so the connection must be re-established.
+    pub(crate) fn record_dead(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.health.last_error = Some("Connection loop exited".to_string());
+        state.health.status = ConnectionStatus::Reconnecting;
+        state.health.tag = None;
+        state.reconnect_at = None;
+    }
+}
+
+impl HealthState {
+    fn schedule_reconnect(&mut self, params: &HealthCheck) {
+        let min_backoff = Duration::from_millis(params.min_backoff_ms);
+        let max_backoff = Duration::from_millis(params.max_backoff_ms).max(min_backoff);
+        let backoff = self.backoff.unwrap_or(min_backoff);
+        self.health.status = ConnectionStatus::Reconnecting;
+        self.health.tag = None;
+        self.reconnect_at = Some(Instant::now() + backoff);
+        self.backoff = Some((backoff * 2).min(max_backoff));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::client::health::HealthRecorder;
+    use crate::client::{ConnectionStatus, HealthCheck};
+
+    #[test]
+    fn health_recorder_schedules_reconnects_with_backoff() {
+        let params = HealthCheck {
+            max_failures: 2,
+            min_backoff_ms: 1_000,
+            max_backoff_ms: 3_000,
+            ..HealthCheck::default()
+        };
+        let recorder = HealthRecorder::new(3);
+        assert_eq!(recorder.health().status, ConnectionStatus::NotConnected);
+        recorder.record_connected("ton-conn-0");
+        recorder.record_success(Duration::from_millis(20), 100);
+
+        assert!(!recorder.record_failure("timed out".to_string(), &params));
+        assert_eq!(recorder.health().status, ConnectionStatus::Degraded);
+        assert!(recorder.is_available());
+        assert!(recorder.record_failure("timed out".to_string(), &params));
+        let health = recorder.health();
+        assert_eq!(health.status, ConnectionStatus::Reconnecting);
+        assert_eq!(health.tag, None);
+        assert_eq!(health.failed_probes, 2);
+        assert_eq!(health.last_seqno, Some(100));
+        assert!(!recorder.is_available());
+
+        // Backoff doubles on each failed attempt up to the maximum
+        let now = Instant::now();
+        assert!(!recorder.should_reconnect(now));
+        assert!(recorder.should_reconnect(now + Duration::from_millis(1_000)));
+        recorder.record_connect_failure("refused".to_string(), &params);
+        assert!(!recorder.should_reconnect(now + Duration::from_millis(1_500)));
+        assert!(recorder.should_reconnect(now + Duration::from_millis(2_500)));
+        recorder.record_connect_failure("refused".to_string(), &params);
+        recorder.record_connect_failure("refused".to_string(), &params);
+        assert!(recorder.should_reconnect(Instant::now() + Duration::from_millis(3_000)));
+
+        recorder.record_connected("ton-conn-1");
+        let health = recorder.health();
+        assert_eq!(health.status, ConnectionStatus::Healthy);
+        assert_eq!(health.tag.as_deref(), Some("ton-conn-1"));
+        assert_eq!(health.reconnects, 1);
+        assert_eq!(health.index, 3);
+    }
+}
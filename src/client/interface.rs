@@ -5,10 +5,18 @@ use crate::address::TonAddress;
 use crate::client::{TonClientError, TonConnection};
 use crate::contract::LoadedSmcState;
 use crate::tl::{
-    AccountAddress, BlockId, BlockIdExt, BlocksAccountTransactionId, BlocksHeader,
-    BlocksMasterchainInfo, BlocksShards, BlocksTransactions, BlocksTransactionsExt, ConfigInfo,
-    FullAccountState, InternalTransactionId, LiteServerInfo, RawFullAccountState, RawTransactions,
-    TonFunction, TonResult, TonResultDiscriminants, TvmCell,
+    AccountAddress, BlockId, BlockIdExt, BlocksAccountTransactionId,
+    BlocksGetMasterchainInfoRequest, BlocksGetShardsRequest, BlocksGetTransactionsExtRequest,
+    BlocksGetTransactionsRequest, BlocksHeader, BlocksLookupBlockRequest, BlocksMasterchainInfo,
+    BlocksShards, BlocksTransactions, BlocksTransactionsExt, ConfigInfo, FullAccountState,
+    GetAccountStateRequest, GetBlockHeaderRequest, GetConfigAllRequest, GetConfigParamRequest,
+    GetLogVerbosityLevelRequest, InternalTransactionId, LiteServerGetInfoRequest, LiteServerInfo,
+    QueryInfo, RawCreateQueryRequest, RawFullAccountState, RawGetAccountStateByTransactionRequest,
+    RawGetAccountStateRequest, RawGetTransactionsRequest, RawGetTransactionsV2Request,
+    RawSendMessageRequest, RawSendMessageReturnHashRequest, RawTransactions, SmcForgetRequest,
+    SmcGetCodeRequest, SmcGetDataRequest, SmcGetLibrariesExtRequest, SmcGetLibrariesRequest,
+    SmcGetStateRequest, SmcLoadByTransactionRequest, SmcLoadRequest, SyncRequest, TlRequest,
+    TonFunction, TonResult, TvmCell, WithBlockRequest,
 };
 
 #[async_trait]
@@ -24,23 +32,58 @@ pub trait TonClientInterface: Send + Sync {
         self.invoke_on_connection(function).await.map(|(_, r)| r)
     }
 
+    /// Invokes a typed request, checking the type of its result.
+    async fn invoke_request<R>(&self, request: R) -> Result<R::Response, TonClientError>
+    where
+        R: TlRequest + Send,
+        R::Response: Send,
+    {
+        let result = self.invoke(&request.into()).await?;
+        R::response(result).map_err(|r| TonClientError::unexpected_ton_result(R::RESULT, r))
+    }
+
+    async fn invoke_request_on_connection<R>(
+        &self,
+        request: R,
+    ) -> Result<(TonConnection, R::Response), TonClientError>
+    where
+        R: TlRequest + Send,
+        R::Response: Send,
+    {
+        let (conn, result) = self.invoke_on_connection(&request.into()).await?;
+        let response =
+            R::response(result).map_err(|r| TonClientError::unexpected_ton_result(R::RESULT, r))?;
+        Ok((conn, response))
+    }
+
     async fn get_raw_account_state(
         &self,
         account_address: &TonAddress,
     ) -> Result<RawFullAccountState, TonClientError> {
-        let func = TonFunction::RawGetAccountState {
+        let request = RawGetAccountStateRequest {
             account_address: AccountAddress {
                 account_address: account_address.to_hex(),
             },
         };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::RawFullAccountState(state) => Ok(state),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::RawFullAccountState,
-                r,
-            )),
-        }
+        self.invoke_request(request).await
+    }
+
+    /// Returns the state of the account at specified block, which must be the block of the
+    /// masterchain or of the shard the account belongs to.
+    async fn get_raw_account_state_at_block(
+        &self,
+        account_address: &TonAddress,
+        block_id: &BlockIdExt,
+    ) -> Result<RawFullAccountState, TonClientError> {
+        let request = WithBlockRequest {
+            id: block_id.clone(),
+            request: RawGetAccountStateRequest {
+                account_address: AccountAddress {
+                    account_address: account_address.to_hex(),
+                },
+            },
+        };
+        self.invoke_request(request).await
     }
 
     async fn get_raw_account_state_by_transaction(
@@ -48,20 +91,13 @@ pub trait TonClientInterface: Send + Sync {
         account_address: &TonAddress,
         transaction_id: &InternalTransactionId,
     ) -> Result<RawFullAccountState, TonClientError> {
-        let func = TonFunction::RawGetAccountStateByTransaction {
+        let request = RawGetAccountStateByTransactionRequest {
             account_address: AccountAddress {
                 account_address: account_address.to_hex(),
             },
             transaction_id: transaction_id.clone(),
         };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::RawFullAccountState(state) => Ok(state),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::RawFullAccountState,
-                r,
-            )),
-        }
+        self.invoke_request(request).await
     }
 
     async fn get_raw_transactions(
@@ -69,20 +105,13 @@ pub trait TonClientInterface: Send + Sync {
         account_address: &TonAddress,
         from_transaction_id: &InternalTransactionId,
     ) -> Result<RawTransactions, TonClientError> {
-        let func = TonFunction::RawGetTransactions {
+        let request = RawGetTransactionsRequest {
             account_address: AccountAddress {
                 account_address: account_address.to_hex(),
             },
             from_transaction_id: from_transaction_id.clone(),
         };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::RawTransactions(state) => Ok(state),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::RawTransactions,
-                r,
-            )),
-        }
+        self.invoke_request(request).await
     }
 
     async fn get_raw_transactions_v2(
@@ -92,7 +121,7 @@ pub trait TonClientInterface: Send + Sync {
         count: usize,
         try_decode_messages: bool,
     ) -> Result<RawTransactions, TonClientError> {
-        let func = TonFunction::RawGetTransactionsV2 {
+        let request = RawGetTransactionsV2Request {
             account_address: AccountAddress {
                 account_address: account_address.to_hex(),
             },
@@ -100,218 +129,171 @@ pub trait TonClientInterface: Send + Sync {
             count: count as u32,
             try_decode_messages,
         };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::RawTransactions(state) => Ok(state),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::RawTransactions,
-                r,
-            )),
-        }
+        self.invoke_request(request).await
     }
 
     async fn send_raw_message(&self, body: &[u8]) -> Result<(), TonClientError> {
-        let func = TonFunction::RawSendMessage {
+        let request = RawSendMessageRequest {
             body: body.to_vec(),
         };
-        self.invoke(&func).await?.expect_ok()
+        self.invoke_request(request).await
     }
 
     async fn send_raw_message_return_hash(&self, body: &[u8]) -> Result<Vec<u8>, TonClientError> {
-        let func = TonFunction::RawSendMessageReturnHash {
+        let request = RawSendMessageReturnHashRequest {
+            body: body.to_vec(),
+        };
+        self.invoke_request(request).await.map(|info| info.hash)
+    }
+
+    /// Creates a query sending `body` to `destination`, deploying it with `init_code`
+    /// and `init_data` if they are not empty. The query must be forgotten when it is
+    /// no longer needed.
+    async fn raw_create_query(
+        &self,
+        destination: &TonAddress,
+        init_code: &[u8],
+        init_data: &[u8],
+        body: &[u8],
+    ) -> Result<(TonConnection, QueryInfo), TonClientError> {
+        let request = RawCreateQueryRequest {
+            destination: AccountAddress {
+                account_address: destination.to_hex(),
+            },
+            init_code: init_code.to_vec(),
+            init_data: init_data.to_vec(),
             body: body.to_vec(),
         };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::RawExtMessageInfo(info) => Ok(info.hash),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::RawExtMessageInfo,
-                r,
-            )),
-        }
+        self.invoke_request_on_connection(request).await
     }
 
     async fn sync(&self) -> Result<(TonConnection, BlockIdExt), TonClientError> {
-        let func = TonFunction::Sync {};
-        let (conn, result) = self.invoke_on_connection(&func).await?;
-        match result {
-            TonResult::BlockIdExt(result) => Ok((conn, result)),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::BlockIdExt,
-                r,
-            )),
-        }
+        let request = SyncRequest {};
+        self.invoke_request_on_connection(request).await
     }
 
     async fn get_account_state(
         &self,
         account_address: &TonAddress,
     ) -> Result<FullAccountState, TonClientError> {
-        let func = TonFunction::GetAccountState {
+        let request = GetAccountStateRequest {
             account_address: AccountAddress {
                 account_address: account_address.to_hex(),
             },
         };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::FullAccountState(state) => Ok(state),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::FullAccountState,
-                r,
-            )),
-        }
+        self.invoke_request(request).await
     }
 
     async fn smc_load(
         &self,
         account_address: &TonAddress,
     ) -> Result<LoadedSmcState, TonClientError> {
-        let func = TonFunction::SmcLoad {
+        let request = SmcLoadRequest {
             account_address: AccountAddress {
                 account_address: account_address.to_hex(),
             },
         };
-        let (conn, result) = self.invoke_on_connection(&func).await?;
-        match result {
-            TonResult::SmcInfo(smc_info) => Ok(LoadedSmcState {
-                conn,
-                id: smc_info.id,
-            }),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::SmcInfo,
-                r,
-            )),
-        }
+        let (conn, smc_info) = self.invoke_request_on_connection(request).await?;
+        Ok(LoadedSmcState {
+            conn,
+            id: smc_info.id,
+        })
     }
+    /// Loads the state of the contract at specified block, which must be the block of the
+    /// masterchain or of the shard the contract belongs to.
+    async fn smc_load_at_block(
+        &self,
+        account_address: &TonAddress,
+        block_id: &BlockIdExt,
+    ) -> Result<LoadedSmcState, TonClientError> {
+        let request = WithBlockRequest {
+            id: block_id.clone(),
+            request: SmcLoadRequest {
+                account_address: AccountAddress {
+                    account_address: account_address.to_hex(),
+                },
+            },
+        };
+        let (conn, smc_info) = self.invoke_request_on_connection(request).await?;
+        Ok(LoadedSmcState {
+            conn,
+            id: smc_info.id,
+        })
+    }
+
     async fn smc_load_by_transaction(
         &self,
         address: &TonAddress,
         tx_id: &InternalTransactionId,
     ) -> Result<LoadedSmcState, TonClientError> {
-        let func = TonFunction::SmcLoadByTransaction {
+        let request = SmcLoadByTransactionRequest {
             account_address: AccountAddress {
                 account_address: address.to_hex(),
             },
             transaction_id: tx_id.clone(),
         };
-        let (conn, result) = self.invoke_on_connection(&func).await?;
-        match result {
-            TonResult::SmcInfo(smc_info) => Ok(LoadedSmcState {
-                conn,
-                id: smc_info.id,
-            }),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::SmcInfo,
-                r,
-            )),
-        }
+        let (conn, smc_info) = self.invoke_request_on_connection(request).await?;
+        Ok(LoadedSmcState {
+            conn,
+            id: smc_info.id,
+        })
     }
 
     async fn smc_forget(&self, id: i64) -> Result<TonResult, TonClientError> {
-        let func = TonFunction::SmcForget { id };
-        let result = self.invoke(&func).await?;
+        let result = self.invoke(&SmcForgetRequest { id }.into()).await?;
         Ok(result)
     }
 
     async fn smc_get_code(&self, id: i64) -> Result<TvmCell, TonClientError> {
-        let func = TonFunction::SmcGetCode { id };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::TvmCell(cell) => Ok(cell),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::TvmCell,
-                r,
-            )),
-        }
+        let request = SmcGetCodeRequest { id };
+        self.invoke_request(request).await
     }
 
     async fn smc_get_data(&self, id: i64) -> Result<TvmCell, TonClientError> {
-        let func = TonFunction::SmcGetData { id };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::TvmCell(cell) => Ok(cell),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::TvmCell,
-                r,
-            )),
-        }
+        let request = SmcGetDataRequest { id };
+        self.invoke_request(request).await
     }
 
     async fn smc_get_state(&self, id: i64) -> Result<TvmCell, TonClientError> {
-        let func = TonFunction::SmcGetState { id };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::TvmCell(cell) => Ok(cell),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::TvmCell,
-                r,
-            )),
-        }
+        let request = SmcGetStateRequest { id };
+        self.invoke_request(request).await
     }
 
     async fn smc_get_libraries(
         &self,
         library_list: &[TonLibraryId],
     ) -> Result<SmcLibraryResult, TonClientError> {
-        let func = TonFunction::SmcGetLibraries {
+        let request = SmcGetLibrariesRequest {
             library_list: library_list.to_vec(),
         };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::SmcLibraryResult(r) => Ok(r),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::SmcLibraryResult,
-                r,
-            )),
-        }
+        self.invoke_request(request).await
     }
 
     async fn smc_get_libraries_ext(
         &self,
         list: &[SmcLibraryQueryExt],
     ) -> Result<SmcLibraryResultExt, TonClientError> {
-        let func = TonFunction::SmcGetLibrariesExt {
+        let request = SmcGetLibrariesExtRequest {
             list: list.to_vec(),
         };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::SmcLibraryResultExt(r) => Ok(r),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::SmcLibraryResultExt,
-                r,
-            )),
-        }
+        self.invoke_request(request).await
     }
 
     async fn get_masterchain_info(
         &self,
     ) -> Result<(TonConnection, BlocksMasterchainInfo), TonClientError> {
-        let func = TonFunction::BlocksGetMasterchainInfo {};
-        let (conn, result) = self.invoke_on_connection(&func).await?;
-        match result {
-            TonResult::BlocksMasterchainInfo(result) => Ok((conn, result)),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::BlocksMasterchainInfo,
-                r,
-            )),
-        }
+        let request = BlocksGetMasterchainInfoRequest {};
+        self.invoke_request_on_connection(request).await
     }
 
     async fn get_block_shards(
         &self,
         block_id: &BlockIdExt,
     ) -> Result<BlocksShards, TonClientError> {
-        let func = TonFunction::BlocksGetShards {
+        let request = BlocksGetShardsRequest {
             id: block_id.clone(),
         };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::BlocksShards(result) => Ok(result),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::BlocksShards,
-                r,
-            )),
-        }
+        self.invoke_request(request).await
     }
 
     /// Attempts to find block by specified query.
@@ -324,20 +306,13 @@ pub trait TonClientInterface: Send + Sync {
         lt: i64,
         utime: i32,
     ) -> Result<BlockIdExt, TonClientError> {
-        let func = TonFunction::BlocksLookupBlock {
+        let request = BlocksLookupBlockRequest {
             mode,
             id: block_id.clone(),
             lt,
             utime,
         };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::BlockIdExt(result) => Ok(result),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::BlockIdExt,
-                r,
-            )),
-        }
+        self.invoke_request(request).await
     }
 
     /// Returns up to specified number of ids of transactions in specified block.
@@ -355,20 +330,13 @@ pub trait TonClientInterface: Send + Sync {
         count: u32,
         after: &BlocksAccountTransactionId,
     ) -> Result<BlocksTransactions, TonClientError> {
-        let func = TonFunction::BlocksGetTransactions {
+        let request = BlocksGetTransactionsRequest {
             id: block_id.clone(),
             mode,
             count,
             after: after.clone(),
         };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::BlocksTransactions(result) => Ok(result),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::BlocksTransactions,
-                r,
-            )),
-        }
+        self.invoke_request(request).await
     }
 
     async fn get_block_transactions_ext(
@@ -378,86 +346,57 @@ pub trait TonClientInterface: Send + Sync {
         count: u32,
         after: &BlocksAccountTransactionId,
     ) -> Result<BlocksTransactionsExt, TonClientError> {
-        let func = TonFunction::BlocksGetTransactionsExt {
+        let request = BlocksGetTransactionsExtRequest {
             id: block_id.clone(),
             mode,
             count,
             after: after.clone(),
         };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::BlocksTransactionsExt(result) => Ok(result),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::BlocksTransactionsExt,
-                r,
-            )),
-        }
+        self.invoke_request(request).await
     }
 
     async fn lite_server_get_info(&self) -> Result<LiteServerInfo, TonClientError> {
-        let func = TonFunction::LiteServerGetInfo {};
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::LiteServerInfo(result) => Ok(result),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::LiteServerInfo,
-                r,
-            )),
-        }
+        let request = LiteServerGetInfoRequest {};
+        self.invoke_request(request).await
     }
 
     async fn get_block_header(
         &self,
         block_id: &BlockIdExt,
     ) -> Result<BlocksHeader, TonClientError> {
-        let func = TonFunction::GetBlockHeader {
+        let request = GetBlockHeaderRequest {
             id: block_id.clone(),
         };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::BlocksHeader(header) => Ok(header),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::BlocksHeader,
-                r,
-            )),
-        }
+        self.invoke_request(request).await
     }
 
     async fn get_config_param(&self, mode: u32, param: u32) -> Result<ConfigInfo, TonClientError> {
-        let func = TonFunction::GetConfigParam { mode, param };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::ConfigInfo(result) => Ok(result),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::ConfigInfo,
-                r,
-            )),
-        }
+        let request = GetConfigParamRequest { mode, param };
+        self.invoke_request(request).await
     }
 
     async fn get_config_all(&self, mode: u32) -> Result<ConfigInfo, TonClientError> {
-        let func = TonFunction::GetConfigAll { mode };
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::ConfigInfo(result) => Ok(result),
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::ConfigInfo,
-                r,
-            )),
-        }
+        let request = GetConfigAllRequest { mode };
+        self.invoke_request(request).await
+    }
+
+    /// Returns the config as of specified masterchain block.
+    async fn get_config_all_at_block(
+        &self,
+        mode: u32,
+        mc_block_id: &BlockIdExt,
+    ) -> Result<ConfigInfo, TonClientError> {
+        let request = WithBlockRequest {
+            id: mc_block_id.clone(),
+            request: GetConfigAllRequest { mode },
+        };
+        self.invoke_request(request).await
     }
 
     async fn get_log_verbosity_level(&self) -> Result<u32, TonClientError> {
-        let func = TonFunction::GetLogVerbosityLevel {};
-        let result = self.invoke(&func).await?;
-        match result {
-            TonResult::LogVerbosityLevel(log_verbosity_level) => {
-                Ok(log_verbosity_level.verbosity_level)
-            }
-            r => Err(TonClientError::unexpected_ton_result(
-                TonResultDiscriminants::OptionsInfo,
-                r,
-            )),
-        }
+        let request = GetLogVerbosityLevelRequest {};
+        self.invoke_request(request)
+            .await
+            .map(|level| level.verbosity_level)
     }
 }
@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::time;
+
+use crate::address::TonAddress;
+use crate::cell::BagOfCells;
+use crate::client::{TonClientError, TonClientInterface};
+use crate::message::{external_message_body_hash, external_message_destination};
+use crate::tl::{InternalTransactionId, RawTransaction};
+
+const TRANSACTIONS_PAGE_SIZE: usize = 16;
+
+/// External message sent by [`TonMessageFunctions::send_message_traced`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SentExternalMessage {
+    /// Hash of the message, as returned by the liteserver.
+    pub hash: Vec<u8>,
+    pub destination: TonAddress,
+    /// Hash of the message body, used to find the transaction that accepted the message.
+    pub body_hash: Vec<u8>,
+    /// Last transaction of the destination before the message was sent.
+    pub last_transaction_id: InternalTransactionId,
+}
+
+/// High-level functions for sending external messages
+#[async_trait]
+pub trait TonMessageFunctions: TonClientInterface + Send + Sync {
+    /// Sends the serialized external message, returning its hash along with everything
+    /// needed to find the transaction that accepts it.
+    ///
+    /// Returns `TonClientError::ExternalMessageRejected` if the liteserver rejects the message,
+    /// e.g. because it was not accepted by the destination contract.
+    async fn send_message_traced(&self, boc: &[u8]) -> Result<SentExternalMessage, TonClientError> {
+        let message = BagOfCells::parse(boc)
+            .and_then(|boc| boc.single_root().cloned())
+            .map_err(|e| TonClientError::InternalError(format!("Invalid message: {}", e)))?;
+        let (destination, body_hash) = external_message_destination(&message)
+            .and_then(|dest| Ok((dest, external_message_body_hash(&message)?)))
+            .map_err(|e| TonClientError::InternalError(format!("Invalid message: {}", e)))?;
+        let state = self.get_raw_account_state(&destination).await?;
+        let hash = self
+            .send_raw_message_return_hash(boc)
+            .await
+            .map_err(|e| match e {
+                TonClientError::TonlibError { code, message, .. } => {
+                    TonClientError::ExternalMessageRejected { code, message }
+                }
+                e => e,
+            })?;
+        Ok(SentExternalMessage {
+            hash,
+            destination,
+            body_hash,
+            last_transaction_id: state.last_transaction_id,
+        })
+    }
+
+    /// Polls the destination of the message until a transaction accepting it appears.
+    ///
+    /// Returns `None` once the destination state is synced past `valid_until` without such
+    /// transaction, so the message can no longer be accepted.
+    async fn wait_message_transaction(
+        &self,
+        sent: &SentExternalMessage,
+        valid_until: u32,
+        poll_interval: Duration,
+    ) -> Result<Option<RawTransaction>, TonClientError> {
+        let mut checked_lt = sent.last_transaction_id.lt;
+        loop {
+            let state = self.get_raw_account_state(&sent.destination).await?;
+            if state.last_transaction_id.lt > checked_lt {
+                let mut from = state.last_transaction_id.clone();
+                'pages: loop {
+                    let txs = self
+                        .get_raw_transactions_v2(
+                            &sent.destination,
+                            &from,
+                            TRANSACTIONS_PAGE_SIZE,
+                            false,
+                        )
+                        .await?;
+                    for tx in &txs.transactions {
+                        if tx.transaction_id.lt <= checked_lt {
+                            break 'pages;
+                        }
+                        if is_accepting_transaction(tx, &sent.body_hash) {
+                            return Ok(Some(tx.clone()));
+                        }
+                    }
+                    if txs.transactions.is_empty() || txs.previous_transaction_id.lt == 0 {
+                        break;
+                    }
+                    from = txs.previous_transaction_id;
+                }
+                checked_lt = state.last_transaction_id.lt;
+            }
+            if state.sync_utime > valid_until as i64 {
+                return Ok(None);
+            }
+            time::sleep(poll_interval).await;
+        }
+    }
+}
+
+impl<T> TonMessageFunctions for T where T: TonClientInterface + Send + Sync {}
+
+fn is_accepting_transaction(tx: &RawTransaction, body_hash: &[u8]) -> bool {
+    match &tx.in_msg {
+        // External messages have no source
+        Some(in_msg) => {
+            in_msg.source.account_address.is_empty() && in_msg.body_hash.as_slice() == body_hash
+        }
+        None => false,
+    }
+}
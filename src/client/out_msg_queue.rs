@@ -0,0 +1,205 @@
+use std::future::Future;
+
+use crate::address::TonAddress;
+use crate::cell::{Cell, TonCellError};
+use crate::client::TonClientError;
+use crate::responses::{MsgMetadata, OutMsgQueueInfo, OutMsgQueueKey};
+use crate::tl::BlockIdExt;
+
+/// Message waiting in the out message queue of a shard to be delivered to its next hop.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueuedMessage {
+    pub hash: Vec<u8>,
+    /// Logical time at which the message was put in the queue.
+    pub enqueued_lt: u64,
+    pub src: TonAddress,
+    pub dest: TonAddress,
+    pub created_lt: u64,
+    pub created_at: u32,
+    /// Workchain and address prefix of the next hop.
+    pub next_hop: (i32, u64),
+    /// Depth and initiator of the message, set by envelopes of version 2.
+    pub metadata: Option<MsgMetadata>,
+}
+
+impl QueuedMessage {
+    /// Returns whether the message was created more than `max_age` seconds before `now`.
+    pub fn is_older_than(&self, now: u32, max_age: u32) -> bool {
+        now.saturating_sub(self.created_at) > max_age
+    }
+}
+
+/// Messages enqueued but not yet delivered by a shard as of a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardOutMsgQueue {
+    pub block_id: BlockIdExt,
+    /// Messages ordered by `enqueued_lt`. Messages with pruned envelopes are omitted.
+    pub messages: Vec<QueuedMessage>,
+    /// Whether parts of the queue are pruned, e.g. in state proofs.
+    pub pruned: bool,
+}
+
+impl ShardOutMsgQueue {
+    /// Lists the messages of the out message queue of the state of the shard block.
+    pub fn from_shard_state(
+        block_id: &BlockIdExt,
+        state: &Cell,
+    ) -> Result<ShardOutMsgQueue, TonCellError> {
+        let queue = Cell::load_shard_state_out_msg_queue(state, &mut 0, &mut state.parser())?;
+        ShardOutMsgQueue::from_queue_info(block_id, &queue)
+    }
+
+    pub fn from_queue_info(
+        block_id: &BlockIdExt,
+        queue: &OutMsgQueueInfo,
+    ) -> Result<ShardOutMsgQueue, TonCellError> {
+        let mut messages = vec![];
+        for (key, enqueued) in &queue.out_queue {
+            let key = OutMsgQueueKey::from_hex(key)?;
+            let Some((envelope, info)) = enqueued
+                .out_msg
+                .as_ref()
+                .and_then(|envelope| envelope.msg.as_ref().map(|msg| (envelope, &msg.info)))
+            else {
+                continue;
+            };
+            messages.push(QueuedMessage {
+                hash: key.msg_hash,
+                enqueued_lt: enqueued.enqueued_lt,
+                src: info.src.clone(),
+                dest: info.dest.clone(),
+                created_lt: info.created_lt,
+                created_at: info.created_at,
+                next_hop: (key.workchain, key.addr_pfx),
+                metadata: envelope.metadata.clone(),
+            });
+        }
+        messages.sort_by(|a, b| (a.enqueued_lt, &a.hash).cmp(&(b.enqueued_lt, &b.hash)));
+        Ok(ShardOutMsgQueue {
+            block_id: block_id.clone(),
+            messages,
+            pruned: queue.pruned,
+        })
+    }
+
+    /// Loads the out message queue of the shard block.
+    ///
+    /// tonlib doesn't return shard states, so the state is loaded by `load_state`, e.g. from
+    /// `liteServer.getState` of a liteserver.
+    pub async fn load<F, Fut>(
+        block_id: &BlockIdExt,
+        load_state: F,
+    ) -> Result<ShardOutMsgQueue, TonClientError>
+    where
+        F: FnOnce(BlockIdExt) -> Fut,
+        Fut: Future<Output = Result<Cell, TonClientError>>,
+    {
+        let state = load_state(block_id.clone()).await?;
+        ShardOutMsgQueue::from_shard_state(block_id, &state).map_err(|e| {
+            TonClientError::InternalError(format!(
+                "Invalid state of block {}: {}",
+                block_id.seqno, e
+            ))
+        })
+    }
+
+    /// Returns the messages created more than `max_age` seconds before `now`, e.g. the time
+    /// of the block, which are likely stuck.
+    pub fn stuck_messages(&self, now: u32, max_age: u32) -> Vec<&QueuedMessage> {
+        self.messages
+            .iter()
+            .filter(|message| message.is_older_than(now, max_age))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use crate::address::TonAddress;
+    use crate::cell::{BagOfCells, Cell, CellBuilder, DictBuilder, SHARD_STATE_UNSPLIT_TAG};
+    use crate::client::ShardOutMsgQueue;
+    use crate::coins::Coins;
+    use crate::message::{CommonMsgInfo, InternalMessageInfo};
+    use crate::tl::BlockIdExt;
+
+    #[test]
+    fn shard_out_msg_queue_works() -> anyhow::Result<()> {
+        let src = TonAddress::from_base64_url("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR")?;
+        let dest = TonAddress::from_base64_url("EQB2BtXDXaQuIcMYW7JEWhHmwHfPPwa-eoCdefiAxOhU3pQg")?;
+        let mut queue = DictBuilder::new(352);
+        for (created_at, enqueued_lt) in [(1000, 20), (1500, 10)] {
+            let mut info = InternalMessageInfo::new(&dest, &Coins::from_nano(1));
+            info.with_src(&src)
+                .with_created(enqueued_lt - 1, created_at);
+            let message = CommonMsgInfo::from(info).build_message(None, None)?;
+            let mut envelope = CellBuilder::new();
+            envelope
+                .store_u8(4, if enqueued_lt == 10 { 5 } else { 4 })?
+                .store_u8(8, 96)? // cur_addr: interm_addr_regular$0 96
+                .store_u8(8, 96)? // next_addr
+                .store_coins(BigUint::from(5u32))?
+                .store_child(message.clone())?;
+            if enqueued_lt == 10 {
+                // msg_envelope_v2 with emitted_lt and metadata
+                envelope
+                    .store_bit(true)?
+                    .store_u64(64, 9)?
+                    .store_bit(true)?
+                    .store_u8(4, 0)?
+                    .store_u32(32, 3)?
+                    .store_address(&src)?
+                    .store_u64(64, 4)?;
+            }
+            let envelope = envelope.build()?;
+            let value = CellBuilder::new()
+                .store_u64(64, enqueued_lt)? // extra
+                .store_u64(64, enqueued_lt)?
+                .store_child(envelope)?
+                .build()?;
+            let mut key = vec![0, 0, 0, 0, 0x80, 0, 0, 0, 0, 0, 0, 0];
+            key.extend(message.cell_hash()?);
+            queue.insert(BigUint::from_bytes_be(&key), value)?;
+        }
+        let out_msg_queue_info = CellBuilder::new()
+            .store_bit(true)?
+            .store_child(queue.build()?.unwrap())?
+            .store_u64(64, 10)?
+            .store_bit(false)? // proc_info
+            .store_bit(false)? // ihr_pending
+            .build()?;
+        let state = CellBuilder::new()
+            .store_u32(32, SHARD_STATE_UNSPLIT_TAG)?
+            .store_child(out_msg_queue_info)?
+            .build()?;
+        // Hashes of the messages are calculated when the state is deserialized
+        let boc = BagOfCells::parse(&BagOfCells::from_root(state).serialize(false)?)?;
+        let state = boc.single_root()?;
+
+        let block_id = BlockIdExt {
+            workchain: 0,
+            shard: i64::MIN,
+            seqno: 7,
+            root_hash: String::new(),
+            file_hash: String::new(),
+        };
+        let queue = ShardOutMsgQueue::from_shard_state(&block_id, state)?;
+        assert!(!queue.pruned);
+        assert_eq!(queue.messages.len(), 2);
+        let first = &queue.messages[0];
+        assert_eq!(first.enqueued_lt, 10);
+        assert_eq!(first.created_at, 1500);
+        assert_eq!((&first.src, &first.dest), (&src, &dest));
+        assert_eq!(first.next_hop, (0, 0x8000_0000_0000_0000));
+        let metadata = first.metadata.as_ref().unwrap();
+        assert_eq!(metadata.depth, 3);
+        assert_eq!(metadata.initiator_addr, src);
+        assert_eq!(metadata.initiator_lt, 4);
+        assert_eq!(queue.messages[1].metadata, None);
+        assert_eq!(queue.stuck_messages(2100, 1000).len(), 1);
+        assert_eq!(queue.stuck_messages(2100, 1000)[0].created_at, 1000);
+        assert!(ShardOutMsgQueue::from_shard_state(&block_id, &Cell::default()).is_err());
+        Ok(())
+    }
+}
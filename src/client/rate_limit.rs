@@ -0,0 +1,140 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::time;
+
+/// Limit of the rate of requests sent through a single connection.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RateLimit {
+    pub requests_per_second: u32,
+    /// Number of requests that can be sent at once after a period of inactivity.
+    pub burst: u32,
+}
+
+/// Token bucket limiting the rate of requests.
+///
+/// Requests reserve their tokens in advance, so they are served in order of arrival.
+pub(crate) struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    state: Mutex<TokenBucketState>,
+}
+
+struct TokenBucketState {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    pub(crate) fn new(rate_limit: &RateLimit) -> TokenBucket {
+        let burst = rate_limit.burst.max(1) as f64;
+        TokenBucket {
+            rate: rate_limit.requests_per_second.max(1) as f64,
+            burst,
+            state: Mutex::new(TokenBucketState {
+                tokens: burst,
+                updated: Instant::now(),
+            }),
+        }
+    }
+
+    pub(crate) async fn acquire(&self) {
+        let wait = {
+            let mut state = self.state.lock().unwrap();
+            let now = Instant::now();
+            let elapsed = now.duration_since(state.updated).as_secs_f64();
+            state.tokens = (state.tokens + elapsed * self.rate).min(self.burst) - 1.0;
+            state.updated = now;
+            if state.tokens >= 0.0 {
+                return;
+            }
+            Duration::from_secs_f64(-state.tokens / self.rate)
+        };
+        time::sleep(wait).await;
+    }
+}
+
+/// Time spent by requests waiting for the rate limiter and the concurrency limit.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueMetrics {
+    pub requests: u64,
+    pub total_queue_time: Duration,
+    pub max_queue_time: Duration,
+}
+
+impl QueueMetrics {
+    pub fn average_queue_time(&self) -> Duration {
+        if self.requests == 0 {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos((self.total_queue_time.as_nanos() / self.requests as u128) as u64)
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct QueueMetricsRecorder {
+    requests: AtomicU64,
+    total_queue_micros: AtomicU64,
+    max_queue_micros: AtomicU64,
+}
+
+impl QueueMetricsRecorder {
+    pub(crate) fn record(&self, queue_time: Duration) {
+        let micros = queue_time.as_micros() as u64;
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        self.total_queue_micros.fetch_add(micros, Ordering::Relaxed);
+        self.max_queue_micros.fetch_max(micros, Ordering::Relaxed);
+    }
+
+    pub(crate) fn metrics(&self) -> QueueMetrics {
+        QueueMetrics {
+            requests: self.requests.load(Ordering::Relaxed),
+            total_queue_time: Duration::from_micros(
+                self.total_queue_micros.load(Ordering::Relaxed),
+            ),
+            max_queue_time: Duration::from_micros(self.max_queue_micros.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use crate::client::rate_limit::{QueueMetricsRecorder, TokenBucket};
+    use crate::client::RateLimit;
+
+    #[test]
+    fn token_bucket_works() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()
+            .unwrap();
+        let bucket = TokenBucket::new(&RateLimit {
+            requests_per_second: 50,
+            burst: 2,
+        });
+        let start = Instant::now();
+        runtime.block_on(async {
+            for _ in 0..4 {
+                bucket.acquire().await;
+            }
+        });
+        // 2 requests are sent at once, the next 2 wait for 20ms each
+        assert!(start.elapsed() >= Duration::from_millis(39));
+    }
+
+    #[test]
+    fn queue_metrics_works() {
+        let recorder = QueueMetricsRecorder::default();
+        recorder.record(Duration::from_millis(10));
+        recorder.record(Duration::from_millis(30));
+        let metrics = recorder.metrics();
+        assert_eq!(metrics.requests, 2);
+        assert_eq!(metrics.max_queue_time, Duration::from_millis(30));
+        assert_eq!(metrics.average_queue_time(), Duration::from_millis(20));
+    }
+}
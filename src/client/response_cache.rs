@@ -0,0 +1,286 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use moka::future::Cache;
+
+use crate::client::{TonClientError, TonClientInterface, TonConnection};
+use crate::tl::{TonFunction, TonResult, NULL_TRANSACTION_ID};
+
+pub const DEFAULT_RESPONSE_CACHE_CAPACITY: u64 = 100_000;
+
+/// Storage of responses to requests of immutable chain data, see [`CachedTonClient`].
+#[async_trait]
+pub trait TonResponseCache: Send + Sync {
+    async fn get(&self, function: &TonFunction) -> Option<TonResult>;
+
+    async fn insert(&self, function: TonFunction, result: TonResult);
+}
+
+/// In-memory cache of at most `capacity` responses. Once it is full, responses are admitted
+/// and evicted by the TinyLFU policy of `moka`, i.e. by how often they are requested.
+pub struct InMemoryResponseCache {
+    cache: Cache<TonFunction, TonResult>,
+}
+
+impl InMemoryResponseCache {
+    pub fn new(capacity: u64) -> InMemoryResponseCache {
+        InMemoryResponseCache {
+            cache: Cache::builder().max_capacity(capacity).build(),
+        }
+    }
+}
+
+impl Default for InMemoryResponseCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_RESPONSE_CACHE_CAPACITY)
+    }
+}
+
+#[async_trait]
+impl TonResponseCache for InMemoryResponseCache {
+    async fn get(&self, function: &TonFunction) -> Option<TonResult> {
+        self.cache.get(function).await
+    }
+
+    async fn insert(&self, function: TonFunction, result: TonResult) {
+        self.cache.insert(function, result).await
+    }
+}
+
+/// Client caching responses that can't change once the data is finalized: blocks and their
+/// transactions addressed by `BlockIdExt`, account transactions and states addressed by
+/// transaction id and libraries addressed by hash.
+///
+/// Only successful responses are cached, so e.g. a block looked up by seqno before it was
+/// generated is requested again. All other requests are passed to the underlying client as is.
+#[derive(Clone)]
+pub struct CachedTonClient<C: TonClientInterface> {
+    client: C,
+    cache: Arc<dyn TonResponseCache>,
+}
+
+impl<C: TonClientInterface> CachedTonClient<C> {
+    /// Creates a client with the default in-memory cache.
+    pub fn new(client: C) -> CachedTonClient<C> {
+        Self::with_cache(client, Arc::new(InMemoryResponseCache::default()))
+    }
+
+    pub fn with_cache(client: C, cache: Arc<dyn TonResponseCache>) -> CachedTonClient<C> {
+        CachedTonClient { client, cache }
+    }
+
+    pub fn client(&self) -> &C {
+        &self.client
+    }
+}
+
+#[async_trait]
+impl<C: TonClientInterface> TonClientInterface for CachedTonClient<C> {
+    async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+        self.client.get_connection().await
+    }
+
+    async fn invoke_on_connection(
+        &self,
+        function: &TonFunction,
+    ) -> Result<(TonConnection, TonResult), TonClientError> {
+        if !is_immutable(function) {
+            return self.client.invoke_on_connection(function).await;
+        }
+        if let Some(result) = self.cache.get(function).await {
+            let conn = self.client.get_connection().await?;
+            return Ok((conn, result));
+        }
+        let (conn, result) = self.client.invoke_on_connection(function).await?;
+        if is_complete(function, &result) {
+            self.cache.insert(function.clone(), result.clone()).await;
+        }
+        Ok((conn, result))
+    }
+
+    async fn invoke(&self, function: &TonFunction) -> Result<TonResult, TonClientError> {
+        if !is_immutable(function) {
+            return self.client.invoke(function).await;
+        }
+        if let Some(result) = self.cache.get(function).await {
+            return Ok(result);
+        }
+        let result = self.client.invoke(function).await?;
+        if is_complete(function, &result) {
+            self.cache.insert(function.clone(), result.clone()).await;
+        }
+        Ok(result)
+    }
+}
+
+fn is_immutable(function: &TonFunction) -> bool {
+    match function {
+        // Listing from the null transaction id starts at the latest account transaction
+        TonFunction::RawGetTransactions {
+            from_transaction_id,
+            ..
+        }
+        | TonFunction::RawGetTransactionsV2 {
+            from_transaction_id,
+            ..
+        } => *from_transaction_id != *NULL_TRANSACTION_ID,
+        TonFunction::RawGetAccountStateByTransaction { .. }
+        | TonFunction::SmcGetLibraries { .. }
+        | TonFunction::BlocksGetShards { .. }
+        | TonFunction::BlocksGetTransactions { .. }
+        | TonFunction::BlocksGetTransactionsExt { .. }
+        | TonFunction::GetBlockHeader { .. } => true,
+        // Blocks looked up by lt or utime may be not yet generated
+        TonFunction::BlocksLookupBlock { mode, .. } => *mode == 1,
+        _ => false,
+    }
+}
+
+fn is_complete(function: &TonFunction, result: &TonResult) -> bool {
+    match (function, result) {
+        (_, TonResult::Error { .. }) => false,
+        (TonFunction::SmcGetLibraries { library_list }, TonResult::SmcLibraryResult(libraries)) => {
+            libraries.result.len() == library_list.len()
+        }
+        // Lookups by seqno may be answered with another block, e.g. by a lagging liteserver
+        (TonFunction::BlocksLookupBlock { id, .. }, TonResult::BlockIdExt(block_id)) => {
+            block_id.workchain == id.workchain
+                && block_id.shard == id.shard
+                && block_id.seqno == id.seqno
+        }
+        (TonFunction::BlocksLookupBlock { .. }, _) => false,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use async_trait::async_trait;
+
+    use crate::client::response_cache::is_immutable;
+    use crate::client::{CachedTonClient, TonClientError, TonClientInterface, TonConnection};
+    use crate::tl::{
+        AccountAddress, BlockId, BlockIdExt, InternalTransactionId, TonFunction, TonResult,
+        NULL_TRANSACTION_ID,
+    };
+
+    /// Answers lookups with the queued results, recording the number of lookups.
+    #[derive(Clone, Default)]
+    struct MockClient {
+        results: Arc<Mutex<Vec<Result<TonResult, TonClientError>>>>,
+        lookups: Arc<Mutex<usize>>,
+    }
+
+    #[async_trait]
+    impl TonClientInterface for MockClient {
+        async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+            Err(TonClientError::InternalError("No connection".to_string()))
+        }
+
+        async fn invoke_on_connection(
+            &self,
+            _function: &TonFunction,
+        ) -> Result<(TonConnection, TonResult), TonClientError> {
+            Err(TonClientError::InternalError("No connection".to_string()))
+        }
+
+        async fn invoke(&self, _function: &TonFunction) -> Result<TonResult, TonClientError> {
+            *self.lookups.lock().unwrap() += 1;
+            self.results.lock().unwrap().remove(0)
+        }
+    }
+
+    fn block(seqno: i32) -> BlockIdExt {
+        BlockIdExt {
+            workchain: -1,
+            shard: i64::MIN,
+            seqno,
+            root_hash: String::new(),
+            file_hash: String::new(),
+        }
+    }
+
+    #[test]
+    fn lookups_are_cached_once_found() -> anyhow::Result<()> {
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        let mock = MockClient::default();
+        *mock.results.lock().unwrap() = vec![
+            Err(TonClientError::InternalError("Block not found".to_string())),
+            Ok(TonResult::BlockIdExt(block(41))),
+            Ok(TonResult::BlockIdExt(block(42))),
+        ];
+        let client = CachedTonClient::new(mock.clone());
+        let lookup = TonFunction::BlocksLookupBlock {
+            mode: 1,
+            id: BlockId {
+                workchain: -1,
+                shard: i64::MIN,
+                seqno: 42,
+            },
+            lt: 0,
+            utime: 0,
+        };
+        runtime.block_on(async {
+            // Neither the error nor another block are served for the block not generated yet
+            assert!(client.invoke(&lookup).await.is_err());
+            assert_eq!(
+                client.invoke(&lookup).await?,
+                TonResult::BlockIdExt(block(41))
+            );
+            for _ in 0..2 {
+                assert_eq!(
+                    client.invoke(&lookup).await?,
+                    TonResult::BlockIdExt(block(42))
+                );
+            }
+            anyhow::Ok(())
+        })?;
+        assert_eq!(*mock.lookups.lock().unwrap(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn is_immutable_works() {
+        let account_address = AccountAddress {
+            account_address: "EQBiMfDMivebQb052Z6yR3jHrmwNhw1kQ5bcAUOBYsK_VPuK".to_string(),
+        };
+        assert!(!is_immutable(&TonFunction::RawGetTransactionsV2 {
+            account_address: account_address.clone(),
+            from_transaction_id: NULL_TRANSACTION_ID.clone(),
+            count: 16,
+            try_decode_messages: false,
+        }));
+        assert!(is_immutable(&TonFunction::RawGetTransactionsV2 {
+            account_address: account_address.clone(),
+            from_transaction_id: InternalTransactionId {
+                lt: 42,
+                hash: vec![1u8; 32],
+            },
+            count: 16,
+            try_decode_messages: false,
+        }));
+        assert!(!is_immutable(&TonFunction::RawGetAccountState {
+            account_address
+        }));
+        let id = BlockId {
+            workchain: -1,
+            shard: i64::MIN,
+            seqno: 1,
+        };
+        assert!(is_immutable(&TonFunction::BlocksLookupBlock {
+            mode: 1,
+            id: id.clone(),
+            lt: 0,
+            utime: 0,
+        }));
+        assert!(!is_immutable(&TonFunction::BlocksLookupBlock {
+            mode: 4,
+            id,
+            lt: 0,
+            utime: 1_700_000_000,
+        }));
+        assert!(!is_immutable(&TonFunction::BlocksGetMasterchainInfo {}));
+    }
+}
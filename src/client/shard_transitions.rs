@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+use crate::address::TonAddress;
+use crate::responses::ShardIdent;
+use crate::tl::BlockIdExt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ShardTransition {
+    /// The shard is replaced by its descendants.
+    Split {
+        parent: ShardIdent,
+        children: Vec<ShardIdent>,
+    },
+    /// The shards are replaced by their common ancestor.
+    Merge {
+        parents: Vec<ShardIdent>,
+        child: ShardIdent,
+    },
+}
+
+/// Splits and merges of shards between two masterchain blocks.
+#[derive(Debug, Clone, Default)]
+pub struct ShardTransitions {
+    transitions: Vec<ShardTransition>,
+    successors: HashMap<ShardIdent, Vec<ShardIdent>>,
+}
+
+impl ShardTransitions {
+    /// Compares the shards of consecutive masterchain blocks, e.g. as returned by
+    /// `get_block_shards`. Blocks with the invalid shard id `0` are skipped.
+    pub fn between(prev_shards: &[BlockIdExt], next_shards: &[BlockIdExt]) -> ShardTransitions {
+        let prev: Vec<_> = prev_shards.iter().filter_map(shard_of_block).collect();
+        let next: Vec<_> = next_shards.iter().filter_map(shard_of_block).collect();
+        let mut transitions = vec![];
+        let mut successors = HashMap::new();
+        for parent in &prev {
+            let children: Vec<_> = next
+                .iter()
+                .filter(|n| parent.intersects(n))
+                .copied()
+                .collect();
+            if children
+                .iter()
+                .any(|child| child != parent && parent.is_ancestor_of(child))
+            {
+                transitions.push(ShardTransition::Split {
+                    parent: *parent,
+                    children: children.clone(),
+                });
+            }
+            successors.insert(*parent, children);
+        }
+        for child in &next {
+            let parents: Vec<_> = prev
+                .iter()
+                .filter(|p| *p != child && child.is_ancestor_of(p))
+                .copied()
+                .collect();
+            if !parents.is_empty() {
+                transitions.push(ShardTransition::Merge {
+                    parents,
+                    child: *child,
+                });
+            }
+        }
+        ShardTransitions {
+            transitions,
+            successors,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transitions.is_empty()
+    }
+
+    /// Returns the splits first, then the merges.
+    pub fn transitions(&self) -> &[ShardTransition] {
+        &self.transitions
+    }
+
+    /// Returns the shards of the next block covering the accounts of `shard` of the previous
+    /// block: the shard itself if it's unchanged, its children if it's split or the merged shard.
+    ///
+    /// Returns an empty slice for shards missing in the previous block.
+    pub fn successors(&self, shard: &ShardIdent) -> &[ShardIdent] {
+        self.successors
+            .get(shard)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Returns the shard of the next block the account belongs to, given its shard in the
+    /// previous block.
+    pub fn successor_for_address(
+        &self,
+        shard: &ShardIdent,
+        address: &TonAddress,
+    ) -> Option<ShardIdent> {
+        self.successors(shard)
+            .iter()
+            .find(|successor| successor.contains_address(address))
+            .copied()
+    }
+}
+
+fn shard_of_block(block_id: &BlockIdExt) -> Option<ShardIdent> {
+    ShardIdent::from_shard_id(block_id.workchain, block_id.shard)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::address::TonAddress;
+    use crate::client::{ShardTransition, ShardTransitions};
+    use crate::responses::ShardIdent;
+    use crate::tl::BlockIdExt;
+
+    const ROOT: i64 = 0x8000000000000000u64 as i64;
+    const LEFT: i64 = 0x4000000000000000;
+    const RIGHT: i64 = 0xc000000000000000u64 as i64;
+
+    fn shards(shards: &[(i32, i64)]) -> Vec<BlockIdExt> {
+        shards
+            .iter()
+            .map(|&(workchain, shard)| BlockIdExt {
+                workchain,
+                shard,
+                seqno: 1,
+                root_hash: String::new(),
+                file_hash: String::new(),
+            })
+            .collect()
+    }
+
+    fn shard(workchain: i32, shard: i64) -> ShardIdent {
+        ShardIdent::from_shard_id(workchain, shard).unwrap()
+    }
+
+    #[test]
+    fn shard_ident_works() {
+        let root = shard(0, ROOT);
+        let left = shard(0, LEFT);
+        let left_right = shard(0, 0x6000000000000000);
+        assert_eq!((root.shard_pfx_bits, left.shard_pfx_bits), (0, 1));
+        assert_eq!(left_right.shard_prefix, 0x4000000000000000);
+        assert_eq!(left_right.shard_id(), 0x6000000000000000);
+        assert!(ShardIdent::from_shard_id(0, 0).is_none());
+        assert!(root.is_ancestor_of(&left));
+        assert!(root.is_ancestor_of(&root));
+        assert!(left.is_ancestor_of(&left_right));
+        assert!(!left.is_ancestor_of(&root));
+        assert!(!shard(0, RIGHT).intersects(&left_right));
+        assert!(!shard(-1, ROOT).is_ancestor_of(&left));
+    }
+
+    #[test]
+    fn shard_transitions_track_splits_and_merges() -> anyhow::Result<()> {
+        let address: TonAddress =
+            "0:e4d954ef9f4e1250a26b5bbad76a1cdd17cfd08babad6f4c23e372270aef6f76".parse()?;
+        let master = shard(-1, ROOT);
+        let root = shard(0, ROOT);
+        let left = shard(0, LEFT);
+        let right = shard(0, RIGHT);
+
+        let split = ShardTransitions::between(
+            &shards(&[(-1, ROOT), (0, ROOT)]),
+            &shards(&[(-1, ROOT), (0, LEFT), (0, RIGHT)]),
+        );
+        assert_eq!(
+            split.transitions(),
+            &[ShardTransition::Split {
+                parent: root,
+                children: vec![left, right],
+            }]
+        );
+        assert_eq!(split.successors(&master), &[master]);
+        assert_eq!(split.successor_for_address(&root, &address), Some(right));
+
+        let merge =
+            ShardTransitions::between(&shards(&[(0, LEFT), (0, RIGHT)]), &shards(&[(0, ROOT)]));
+        assert_eq!(
+            merge.transitions(),
+            &[ShardTransition::Merge {
+                parents: vec![left, right],
+                child: root,
+            }]
+        );
+        assert_eq!(merge.successors(&left), &[root]);
+        assert_eq!(merge.successor_for_address(&right, &address), Some(root));
+        assert!(merge.successors(&master).is_empty());
+
+        // The invalid shard id 0 is skipped
+        let unchanged =
+            ShardTransitions::between(&shards(&[(0, ROOT)]), &shards(&[(0, ROOT), (0, 0)]));
+        assert!(unchanged.is_empty());
+        Ok(())
+    }
+}
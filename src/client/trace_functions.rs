@@ -0,0 +1,278 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use futures::future::try_join_all;
+
+use crate::address::TonAddress;
+use crate::cell::{BagOfCells, Cell, TonCellError};
+use crate::client::{TonClientError, TonClientInterface};
+use crate::tl::{RawMessage, RawTransaction};
+
+const TRANSACTIONS_PAGE_SIZE: usize = 16;
+
+/// Tree of transactions caused by a transaction, following its outgoing internal messages to
+/// the transactions processing them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionTrace {
+    pub transaction: RawTransaction,
+    /// Hash of the message processed by the transaction, `None` for tick-tock transactions.
+    pub in_msg_hash: Option<Vec<u8>>,
+    /// Traces of the transactions processing the outgoing messages, in the order of their
+    /// `created_lt`.
+    pub children: Vec<TransactionTrace>,
+    /// Outgoing internal messages not yet processed by their destination.
+    pub pending_messages: Vec<RawMessage>,
+}
+
+impl TransactionTrace {
+    /// Returns `true` if all messages of the trace are processed.
+    pub fn is_complete(&self) -> bool {
+        self.pending_messages.is_empty() && self.children.iter().all(|c| c.is_complete())
+    }
+
+    /// Returns the transactions of the trace in depth-first order, starting from the root.
+    pub fn transactions(&self) -> Vec<&RawTransaction> {
+        let mut result = vec![&self.transaction];
+        for child in &self.children {
+            result.extend(child.transactions());
+        }
+        result
+    }
+
+    /// Returns the number of transactions of the trace.
+    pub fn len(&self) -> usize {
+        1 + self.children.iter().map(|c| c.len()).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+/// Hashes of the messages of a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TransactionMessageHashes {
+    pub(crate) in_msg: Option<Vec<u8>>,
+    /// Hashes of the outgoing messages by their `created_lt`.
+    pub(crate) out_msgs: HashMap<u64, Vec<u8>>,
+}
+
+impl TransactionMessageHashes {
+    pub(crate) fn parse(data: &[u8]) -> Result<TransactionMessageHashes, TonCellError> {
+        let boc = BagOfCells::parse(data)?;
+        let root = boc.single_root()?;
+        let tx = Cell::load_transaction(root, &mut 0, &mut root.parser())?;
+        let in_msg = tx.in_msg.data.map(|msg| msg.hash);
+        let out_msgs = tx
+            .out_msgs
+            .into_values()
+            .filter_map(|msg| msg.data)
+            .map(|msg| (msg.info.created_lt, msg.hash))
+            .collect();
+        Ok(TransactionMessageHashes { in_msg, out_msgs })
+    }
+}
+
+/// Node of the trace being reconstructed.
+struct TraceNode {
+    transaction: RawTransaction,
+    hashes: TransactionMessageHashes,
+    children: Vec<usize>,
+    pending_messages: Vec<RawMessage>,
+}
+
+/// Outgoing message waiting to be matched with the transaction processing it.
+struct TracedMessage {
+    parent: usize,
+    message: RawMessage,
+    hash: Vec<u8>,
+}
+
+/// High-level functions for reconstructing traces of transactions
+#[async_trait]
+pub trait TonTraceFunctions: TonClientInterface + Send + Sync {
+    /// Reconstructs the tree of transactions caused by the transaction.
+    ///
+    /// Each outgoing internal message is matched with the transaction of its destination
+    /// whose incoming message has the same `created_lt` and hash. Destinations are queried
+    /// by address, so messages crossing shards and workchains are followed as well. Messages
+    /// not yet processed are returned in [`TransactionTrace::pending_messages`].
+    ///
+    /// To get the trace of a whole operation, pass the transaction that accepted
+    /// its external message, e.g. as returned by
+    /// [`wait_message_transaction`](crate::client::TonMessageFunctions::wait_message_transaction).
+    async fn get_trace(&self, tx: &RawTransaction) -> Result<TransactionTrace, TonClientError> {
+        let mut nodes = vec![trace_node(tx.clone())?];
+        let mut frontier = vec![0];
+        while !frontier.is_empty() {
+            let mut by_destination: HashMap<String, Vec<TracedMessage>> = HashMap::new();
+            for parent in frontier.drain(..) {
+                let node = &nodes[parent];
+                for message in &node.transaction.out_msgs {
+                    // External outgoing messages have no destination
+                    if message.destination.account_address.is_empty() {
+                        continue;
+                    }
+                    let hash = node.hashes.out_msgs.get(&(message.created_lt as u64));
+                    let Some(hash) = hash else {
+                        return Err(TonClientError::InternalError(format!(
+                            "Outgoing message with created_lt {} not found in transaction {}",
+                            message.created_lt, node.transaction.transaction_id
+                        )));
+                    };
+                    by_destination
+                        .entry(message.destination.account_address.clone())
+                        .or_default()
+                        .push(TracedMessage {
+                            parent,
+                            message: message.clone(),
+                            hash: hash.clone(),
+                        });
+                }
+            }
+
+            let found = try_join_all(by_destination.iter().map(|(destination, messages)| {
+                find_processing_transactions(self, destination, messages)
+            }))
+            .await?;
+            for (messages, txs) in by_destination.into_values().zip(found) {
+                for (traced, tx) in messages.into_iter().zip(txs) {
+                    match tx {
+                        Some(tx) => {
+                            let index = nodes.len();
+                            nodes.push(trace_node(tx)?);
+                            nodes[traced.parent].children.push(index);
+                            frontier.push(index);
+                        }
+                        None => nodes[traced.parent].pending_messages.push(traced.message),
+                    }
+                }
+            }
+        }
+
+        let mut nodes: Vec<Option<TraceNode>> = nodes.into_iter().map(Some).collect();
+        Ok(build_trace(&mut nodes, 0))
+    }
+}
+
+impl<T> TonTraceFunctions for T where T: TonClientInterface + Send + Sync {}
+
+/// Walks the transactions of the destination from the latest one back to the earliest
+/// `created_lt` of the messages, returning the transaction processing each message.
+async fn find_processing_transactions<C>(
+    client: &C,
+    destination: &str,
+    messages: &[TracedMessage],
+) -> Result<Vec<Option<RawTransaction>>, TonClientError>
+where
+    C: TonClientInterface + Send + Sync + ?Sized,
+{
+    let address: TonAddress = destination.parse().map_err(|e| {
+        TonClientError::InternalError(format!("Invalid destination {}: {}", destination, e))
+    })?;
+    let mut result = vec![None; messages.len()];
+    let Some(min_created_lt) = messages.iter().map(|m| m.message.created_lt).min() else {
+        return Ok(result);
+    };
+    let state = client.get_raw_account_state(&address).await?;
+    let mut from = state.last_transaction_id;
+    let mut remaining = messages.len();
+    'pages: while from.lt > min_created_lt && remaining > 0 {
+        let txs = client
+            .get_raw_transactions_v2(&address, &from, TRANSACTIONS_PAGE_SIZE, false)
+            .await?;
+        for tx in &txs.transactions {
+            // Messages are processed by transactions with greater logical time
+            if tx.transaction_id.lt <= min_created_lt {
+                break 'pages;
+            }
+            let Some(in_msg) = &tx.in_msg else {
+                continue;
+            };
+            let candidates: Vec<usize> = (0..messages.len())
+                .filter(|&i| {
+                    result[i].is_none() && messages[i].message.created_lt == in_msg.created_lt
+                })
+                .collect();
+            if candidates.is_empty() {
+                continue;
+            }
+            let in_msg_hash = TransactionMessageHashes::parse(&tx.data)
+                .map_err(|e| invalid_transaction(tx, e))?
+                .in_msg;
+            for i in candidates {
+                if in_msg_hash.as_ref() == Some(&messages[i].hash) {
+                    result[i] = Some(tx.clone());
+                    remaining -= 1;
+                }
+            }
+        }
+        if txs.transactions.is_empty() || txs.previous_transaction_id.lt == 0 {
+            break;
+        }
+        from = txs.previous_transaction_id;
+    }
+    Ok(result)
+}
+
+fn trace_node(transaction: RawTransaction) -> Result<TraceNode, TonClientError> {
+    let hashes = TransactionMessageHashes::parse(&transaction.data)
+        .map_err(|e| invalid_transaction(&transaction, e))?;
+    Ok(TraceNode {
+        transaction,
+        hashes,
+        children: vec![],
+        pending_messages: vec![],
+    })
+}
+
+fn invalid_transaction(tx: &RawTransaction, error: TonCellError) -> TonClientError {
+    TonClientError::InternalError(format!(
+        "Invalid transaction {}: {}",
+        tx.transaction_id, error
+    ))
+}
+
+fn build_trace(nodes: &mut [Option<TraceNode>], index: usize) -> TransactionTrace {
+    // Each node is the child of exactly one parent, so it's taken once
+    let node = nodes[index].take().unwrap();
+    let mut children: Vec<TransactionTrace> = node
+        .children
+        .iter()
+        .map(|&child| build_trace(nodes, child))
+        .collect();
+    children.sort_by_key(|c| c.transaction.in_msg.as_ref().map(|m| m.created_lt));
+    let mut pending_messages = node.pending_messages;
+    pending_messages.sort_by_key(|m| m.created_lt);
+    TransactionTrace {
+        transaction: node.transaction,
+        in_msg_hash: node.hashes.in_msg,
+        children,
+        pending_messages,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cell::{BagOfCells, Cell};
+    use crate::client::trace_functions::TransactionMessageHashes;
+
+    #[test]
+    fn transaction_message_hashes_works() -> anyhow::Result<()> {
+        let tx_boc = "b5ee9c7201020a010002800003b5710c3760b686d87bef1f5c5a25e87201a27ef8f5f8805c62ef43700b5a7f6f89c00002aabe17f71c1261bcd503ea556b967295eeaa3d2935ddf3a8e268b87b0349f701490a360c9db00002aabe0113bc16660c34000034641b0de80102030201e004050082726303c5d7b1bc0da5acf09ab3b9cfdffb55ea0ec7f6929c09a76a49932263d1b92e977b92eb9d78b2494efa376962706b566f3b92ab7eea53e12ebdaf034cc0c3020f0c470618a1860440080901e188002186ec16d0db0f7de3eb8b44bd0e40344fdf1ebf100b8c5de86e016b4fedf138034329ed2412425c96cbcb1d44b4bfcb96b693ecf9fa4fac12b64fc913ebae528091837d8e3fd367b28676505f89fbb2bc58f8c32130d9fcba920680a7a24798514d4d18bb33061b6800000018001c060101df0700a062002d40675afa88251845b411ed5e2910e0e15892dea75b0ff286dbcba225cece54a1dcd65000000000000000000000000000000000000036363565623039393662393265643564633736303731353600e968002186ec16d0db0f7de3eb8b44bd0e40344fdf1ebf100b8c5de86e016b4fedf1390016a033ad7d44128c22da08f6af14887070ac496f53ad87f9436de5d112e7672a50ee6b28000608235a00005557c2fee384ccc18680000000001b1b1ab2b1181c9c9b311c9932b21ab2319b9b181b989a9b40009d419d8313880000000000000000110000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000020006fc9830d404c08234c0000000000020000000000028e07461aec104405e30a0eb4866ac725676188a0dfe539c310058492e5ece42040501d0c";
+        let boc = BagOfCells::parse_hex(tx_boc)?;
+        let hashes = TransactionMessageHashes::parse(&boc.serialize(false)?)?;
+
+        let messages = boc.single_root()?.reference(0)?;
+        let in_msg = messages.reference(0)?;
+        assert_eq!(hashes.in_msg, Some(in_msg.cell_hash()?));
+        assert_eq!(hashes.out_msgs.len(), 1);
+        // The outgoing message is created right after the transaction
+        let root = boc.single_root()?;
+        let tx = Cell::load_transaction(root, &mut 0, &mut root.parser())?;
+        let (created_lt, hash) = hashes.out_msgs.iter().next().unwrap();
+        assert_eq!(*created_lt, tx.lt + 1);
+        assert_ne!(hash, &in_msg.cell_hash()?);
+        Ok(())
+    }
+}
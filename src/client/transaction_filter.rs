@@ -0,0 +1,309 @@
+use num_bigint::BigUint;
+
+use crate::address::TonAddress;
+use crate::constants::{
+    JETTON_BURN, JETTON_BURN_NOTIFICATION, JETTON_EXCESSES, JETTON_INTERNAL_TRANSFER,
+    JETTON_TRANSFER, JETTON_TRANSFER_NOTIFICATION,
+};
+use crate::responses::{MessageType, Transaction, TransactionMessage};
+
+const JETTON_OPS: [u32; 6] = [
+    JETTON_TRANSFER,
+    JETTON_TRANSFER_NOTIFICATION,
+    JETTON_INTERNAL_TRANSFER,
+    JETTON_EXCESSES,
+    JETTON_BURN,
+    JETTON_BURN_NOTIFICATION,
+];
+
+/// Condition on a parsed transaction, see [`TransactionHistory::with_filter`].
+///
+/// Filters are combined with [`TransactionFilter::and`], [`TransactionFilter::or`] and
+/// [`TransactionFilter::not`]:
+///
+/// ```
+/// # use num_bigint::BigUint;
+/// # use tonlib::client::TransactionFilter;
+/// # use tonlib::constants::TEXT_COMMENT;
+/// let filter = TransactionFilter::success()
+///     .and(TransactionFilter::op_code(TEXT_COMMENT))
+///     .and(TransactionFilter::min_value(BigUint::from(1_000_000_000u64)));
+/// ```
+///
+/// [`TransactionHistory::with_filter`]: crate::client::TransactionHistory::with_filter
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransactionFilter {
+    /// The inbound or an outbound message has a body starting with the op code.
+    OpCode(u32),
+    /// The inbound message comes from the address or an outbound message goes to it.
+    Counterparty(TonAddress),
+    /// The inbound message or the outbound messages together carry at least this many
+    /// nanotons.
+    MinValue(BigUint),
+    /// The transaction was not aborted and its compute and action phases succeeded.
+    Success,
+    /// A jetton message is exchanged with the master or one of its jetton wallets.
+    ///
+    /// Jetton wallets don't reveal their master in transactions, so the wallets that may
+    /// appear in the history are listed, e.g. the wallet of the account as returned by
+    /// `get_wallet_address` of the master.
+    JettonMaster {
+        master: TonAddress,
+        wallets: Vec<TonAddress>,
+    },
+    All(Vec<TransactionFilter>),
+    Any(Vec<TransactionFilter>),
+    Not(Box<TransactionFilter>),
+}
+
+impl TransactionFilter {
+    pub fn op_code(op: u32) -> TransactionFilter {
+        TransactionFilter::OpCode(op)
+    }
+
+    pub fn counterparty(address: &TonAddress) -> TransactionFilter {
+        TransactionFilter::Counterparty(address.clone())
+    }
+
+    pub fn min_value(value: BigUint) -> TransactionFilter {
+        TransactionFilter::MinValue(value)
+    }
+
+    pub fn success() -> TransactionFilter {
+        TransactionFilter::Success
+    }
+
+    pub fn jetton_master(master: &TonAddress, wallets: &[TonAddress]) -> TransactionFilter {
+        TransactionFilter::JettonMaster {
+            master: master.clone(),
+            wallets: wallets.to_vec(),
+        }
+    }
+
+    pub fn and(self, other: TransactionFilter) -> TransactionFilter {
+        match self {
+            TransactionFilter::All(mut filters) => {
+                filters.push(other);
+                TransactionFilter::All(filters)
+            }
+            filter => TransactionFilter::All(vec![filter, other]),
+        }
+    }
+
+    pub fn or(self, other: TransactionFilter) -> TransactionFilter {
+        match self {
+            TransactionFilter::Any(mut filters) => {
+                filters.push(other);
+                TransactionFilter::Any(filters)
+            }
+            filter => TransactionFilter::Any(vec![filter, other]),
+        }
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> TransactionFilter {
+        TransactionFilter::Not(Box::new(self))
+    }
+
+    pub fn matches(&self, transaction: &Transaction) -> bool {
+        match self {
+            TransactionFilter::OpCode(op) => {
+                messages(transaction).any(|msg| message_op(msg) == Some(*op))
+            }
+            TransactionFilter::Counterparty(address) => {
+                counterparties(transaction).any(|(_, counterparty)| counterparty == address)
+            }
+            TransactionFilter::MinValue(min_value) => {
+                let in_value = in_message(transaction)
+                    .map(|msg| msg.info.value.grams.value.clone())
+                    .unwrap_or_default();
+                let out_value: BigUint = out_messages(transaction)
+                    .map(|msg| &msg.info.value.grams.value)
+                    .sum();
+                in_value.max(out_value) >= *min_value
+            }
+            TransactionFilter::Success => transaction
+                .description
+                .as_ref()
+                .map(|descr| descr.is_success())
+                .unwrap_or(false),
+            TransactionFilter::JettonMaster { master, wallets } => {
+                counterparties(transaction).any(|(msg, counterparty)| {
+                    (counterparty == master || wallets.contains(counterparty))
+                        && message_op(msg).is_some_and(|op| JETTON_OPS.contains(&op))
+                })
+            }
+            TransactionFilter::All(filters) => {
+                filters.iter().all(|filter| filter.matches(transaction))
+            }
+            TransactionFilter::Any(filters) => {
+                filters.iter().any(|filter| filter.matches(transaction))
+            }
+            TransactionFilter::Not(filter) => !filter.matches(transaction),
+        }
+    }
+}
+
+fn in_message(transaction: &Transaction) -> Option<&TransactionMessage> {
+    transaction.in_msg.data.as_ref()
+}
+
+fn out_messages(transaction: &Transaction) -> impl Iterator<Item = &TransactionMessage> {
+    transaction
+        .out_msgs
+        .values()
+        .filter_map(|msg| msg.data.as_ref())
+}
+
+fn messages(transaction: &Transaction) -> impl Iterator<Item = &TransactionMessage> {
+    in_message(transaction)
+        .into_iter()
+        .chain(out_messages(transaction))
+}
+
+/// Returns the messages exchanged with other accounts along with the address of the other
+/// account, i.e. the source of the inbound internal message and the destinations of the
+/// outbound internal messages.
+fn counterparties(
+    transaction: &Transaction,
+) -> impl Iterator<Item = (&TransactionMessage, &TonAddress)> {
+    let is_internal = |msg: &&TransactionMessage| msg.info.msg_type == MessageType::Internal as u8;
+    let inbound = in_message(transaction)
+        .into_iter()
+        .filter(is_internal)
+        .map(|msg| (msg, &msg.info.src));
+    let outbound = out_messages(transaction)
+        .filter(is_internal)
+        .map(|msg| (msg, &msg.info.dest));
+    inbound.chain(outbound)
+}
+
+fn message_op(msg: &TransactionMessage) -> Option<u32> {
+    let mut parser = msg.body.any_cell()?.parser().ok()?;
+    if parser.remaining_bits() < 32 {
+        return None;
+    }
+    parser.load_u32(32).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use num_bigint::BigUint;
+
+    use crate::address::TonAddress;
+    use crate::cell::CellBuilder;
+    use crate::client::TransactionFilter;
+    use crate::constants::{JETTON_TRANSFER, JETTON_TRANSFER_NOTIFICATION, TEXT_COMMENT};
+    use crate::responses::{
+        AnyCell, MaybeRefData, MessageType, Transaction, TransactionBody, TransactionMessage,
+    };
+
+    fn message(src: &TonAddress, dest: &TonAddress, value: u64, op: u32) -> TransactionMessage {
+        let mut msg = TransactionMessage::default();
+        msg.info.msg_type = MessageType::Internal as u8;
+        msg.info.src = src.clone();
+        msg.info.dest = dest.clone();
+        msg.info.value.grams.value = BigUint::from(value);
+        msg.body = TransactionBody {
+            any: Some(AnyCell {
+                cell: CellBuilder::new()
+                    .store_u32(32, op)
+                    .unwrap()
+                    .build()
+                    .unwrap(),
+                ref_index: 0,
+                parser_positions_in_bits: 0,
+            }),
+            cell_ref: None,
+        };
+        msg
+    }
+
+    fn transaction(in_msg: TransactionMessage, out_msgs: Vec<TransactionMessage>) -> Transaction {
+        let out_msgs: HashMap<_, _> = out_msgs
+            .into_iter()
+            .enumerate()
+            .map(|(i, msg)| {
+                let msg = MaybeRefData {
+                    data: Some(msg),
+                    ..Default::default()
+                };
+                (i.to_string(), msg)
+            })
+            .collect();
+        Transaction {
+            in_msg: MaybeRefData {
+                data: Some(in_msg),
+                ..Default::default()
+            },
+            out_msgs,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn transaction_filters_work() -> anyhow::Result<()> {
+        let account =
+            TonAddress::from_base64_url("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR")?;
+        let other =
+            TonAddress::from_base64_url("EQB2BtXDXaQuIcMYW7JEWhHmwHfPPwa-eoCdefiAxOhU3pQg")?;
+        let jetton_wallet =
+            TonAddress::from_base64_url("EQCxE6mUtQJKFnGfaROTKOt1lZbDiiX1kCixRv7Nw2Id_sDs")?;
+        let comment = transaction(
+            message(&other, &account, 2_000_000_000, TEXT_COMMENT),
+            vec![],
+        );
+        let notification = transaction(
+            message(&jetton_wallet, &account, 1, JETTON_TRANSFER_NOTIFICATION),
+            vec![message(&account, &other, 5, TEXT_COMMENT)],
+        );
+        let transfer = transaction(
+            message(&other, &account, 100, TEXT_COMMENT),
+            vec![message(
+                &account,
+                &jetton_wallet,
+                50_000_000,
+                JETTON_TRANSFER,
+            )],
+        );
+
+        let op = TransactionFilter::op_code(JETTON_TRANSFER);
+        assert!(!op.matches(&comment));
+        assert!(op.matches(&transfer));
+
+        let counterparty = TransactionFilter::counterparty(&other);
+        assert!(counterparty.matches(&comment));
+        assert!(counterparty.matches(&notification));
+        assert!(!TransactionFilter::counterparty(&jetton_wallet).matches(&comment));
+
+        let min_value = TransactionFilter::min_value(BigUint::from(1_000_000u32));
+        assert!(min_value.matches(&comment));
+        assert!(!min_value.matches(&notification));
+        assert!(min_value.matches(&transfer));
+
+        // Parsed transactions without description are not known to succeed
+        assert!(!TransactionFilter::success().matches(&comment));
+
+        let master = TonAddress::NULL;
+        let jetton =
+            TransactionFilter::jetton_master(&master, std::slice::from_ref(&jetton_wallet));
+        assert!(!jetton.matches(&comment));
+        assert!(jetton.matches(&notification));
+        assert!(jetton.matches(&transfer));
+        assert!(!TransactionFilter::jetton_master(&master, &[]).matches(&transfer));
+
+        let filter = jetton.clone().and(min_value.clone());
+        assert!(!filter.matches(&notification));
+        assert!(filter.matches(&transfer));
+        let filter = jetton.and(min_value.not());
+        assert!(filter.matches(&notification));
+        assert!(!filter.matches(&transfer));
+        assert!(matches!(&filter, TransactionFilter::All(filters) if filters.len() == 2));
+        let filter = op.or(TransactionFilter::op_code(TEXT_COMMENT));
+        assert!(filter.matches(&comment));
+        assert!(filter.matches(&transfer));
+        Ok(())
+    }
+}
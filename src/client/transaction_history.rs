@@ -0,0 +1,138 @@
+use std::collections::VecDeque;
+
+use futures::Stream;
+
+use crate::address::TonAddress;
+use crate::cell::{BagOfCells, Cell};
+use crate::client::{TonClientError, TonClientInterface, TransactionFilter};
+use crate::responses::Transaction;
+use crate::tl::{InternalTransactionId, RawTransaction};
+
+const TRANSACTIONS_PAGE_SIZE: usize = 16;
+
+/// Transaction of an account history along with its parsed form.
+#[derive(Debug, Clone)]
+pub struct HistoryTransaction {
+    pub raw: RawTransaction,
+    pub transaction: Transaction,
+}
+
+/// Iterates the transactions of an account from the newest to the oldest, e.g. to backfill
+/// its history.
+///
+/// Transactions are parsed before they are yielded, and those rejected by the filter are
+/// skipped without being returned.
+pub struct TransactionHistory<C: TonClientInterface + Clone> {
+    client: C,
+    address: TonAddress,
+    /// Next page to load, `None` before the last transaction is known.
+    from: Option<InternalTransactionId>,
+    until_lt: i64,
+    filter: Option<TransactionFilter>,
+    pending: VecDeque<RawTransaction>,
+    exhausted: bool,
+}
+
+impl<C: TonClientInterface + Clone> TransactionHistory<C> {
+    /// Creates an iterator starting at the current last transaction of the account.
+    pub fn new(client: &C, address: &TonAddress) -> TransactionHistory<C> {
+        TransactionHistory {
+            client: client.clone(),
+            address: address.clone(),
+            from: None,
+            until_lt: 0,
+            filter: None,
+            pending: VecDeque::new(),
+            exhausted: false,
+        }
+    }
+
+    /// Starts at the given transaction instead of the current last transaction.
+    pub fn with_start(mut self, from: &InternalTransactionId) -> Self {
+        self.from = Some(from.clone());
+        self
+    }
+
+    /// Stops before the transaction with logical time `until_lt` and older ones.
+    pub fn with_until_lt(mut self, until_lt: i64) -> Self {
+        self.until_lt = until_lt;
+        self
+    }
+
+    /// Yields only the transactions matching the filter.
+    pub fn with_filter(mut self, filter: TransactionFilter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+
+    /// Returns the next matching transaction, or `None` at the end of the history.
+    ///
+    /// Failed queries leave the iterator unchanged, so `next` can be called again to retry,
+    /// while a transaction that fails to parse is returned as an error and skipped.
+    pub async fn next(&mut self) -> Result<Option<HistoryTransaction>, TonClientError> {
+        loop {
+            if let Some(raw) = self.pending.pop_front() {
+                let transaction = parse_transaction(&raw)?;
+                let matches = self
+                    .filter
+                    .as_ref()
+                    .map(|filter| filter.matches(&transaction))
+                    .unwrap_or(true);
+                if matches {
+                    return Ok(Some(HistoryTransaction { raw, transaction }));
+                }
+                continue;
+            }
+            if self.exhausted {
+                return Ok(None);
+            }
+            let from = match &self.from {
+                Some(from) => from.clone(),
+                None => {
+                    let state = self.client.get_raw_account_state(&self.address).await?;
+                    self.from = Some(state.last_transaction_id.clone());
+                    state.last_transaction_id
+                }
+            };
+            if from.lt <= self.until_lt {
+                self.exhausted = true;
+                continue;
+            }
+            let txs = self
+                .client
+                .get_raw_transactions_v2(&self.address, &from, TRANSACTIONS_PAGE_SIZE, false)
+                .await?;
+            let until_lt = self.until_lt;
+            self.pending.extend(
+                txs.transactions
+                    .into_iter()
+                    .filter(|tx| tx.transaction_id.lt > until_lt),
+            );
+            self.exhausted = txs.previous_transaction_id.lt <= self.until_lt;
+            self.from = Some(txs.previous_transaction_id);
+        }
+    }
+
+    /// Converts the iterator into a stream of transactions, ending after the first error.
+    pub fn into_stream(self) -> impl Stream<Item = Result<HistoryTransaction, TonClientError>> {
+        futures::stream::unfold(Some(self), |history| async move {
+            let mut history = history?;
+            match history.next().await {
+                Ok(Some(tx)) => Some((Ok(tx), Some(history))),
+                Ok(None) => None,
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+    }
+}
+
+fn parse_transaction(raw: &RawTransaction) -> Result<Transaction, TonClientError> {
+    let parse = || {
+        let boc = BagOfCells::parse(&raw.data)?;
+        let root = boc.single_root()?;
+        Cell::load_transaction(root, &mut 0, &mut root.parser())
+    };
+    parse().map_err(|e| {
+        TonClientError::InternalError(format!("Invalid transaction {}: {}", raw.transaction_id, e))
+    })
+}
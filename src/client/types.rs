@@ -5,12 +5,12 @@ use serde::{Deserialize, Serialize};
 use tokio::sync::broadcast;
 
 use super::{
-    BlocksShortTxId, TonClientError, DEFAULT_CONNECTION_CONCURRENCY_LIMIT,
+    BlocksShortTxId, RateLimit, TonClientError, DEFAULT_CONNECTION_CONCURRENCY_LIMIT,
     DEFAULT_NOTIFICATION_QUEUE_LENGTH,
 };
 use crate::address::TonAddress;
 use crate::config::MAINNET_CONFIG;
-use crate::tl::{InternalTransactionId, TonNotification};
+use crate::tl::{BlockIdExt, InternalTransactionId, TonNotification};
 
 pub type TonNotificationReceiver = broadcast::Receiver<Arc<TonNotification>>;
 
@@ -39,6 +39,44 @@ impl TxId {
     }
 }
 
+/// Balance of an account as of a masterchain block, see
+/// [`TonBlockFunctions::get_balance_series`].
+///
+/// [`TonBlockFunctions::get_balance_series`]: crate::client::TonBlockFunctions::get_balance_series
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceSample {
+    pub mc_block_id: BlockIdExt,
+    /// Balance in nanotons.
+    pub balance: i64,
+    /// Last transaction of the account as of the block.
+    pub last_transaction_id: InternalTransactionId,
+    pub sync_utime: i64,
+}
+
+/// Criterion of a block lookup, see [`TonBlockFunctions::lookup_block_by`].
+///
+/// [`TonBlockFunctions::lookup_block_by`]: crate::client::TonBlockFunctions::lookup_block_by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LookupBy {
+    /// Block with specified seqno.
+    Seqno(i32),
+    /// Block containing specified logical time.
+    Lt(i64),
+    /// Block generated at specified unix time.
+    Utime(i32),
+}
+
+impl LookupBy {
+    /// Returns the mode of `blocks.lookupBlock`.
+    pub fn mode(&self) -> i32 {
+        match self {
+            LookupBy::Seqno(_) => 1,
+            LookupBy::Lt(_) => 2,
+            LookupBy::Utime(_) => 4,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct TonConnectionParams {
     pub config: String,
@@ -54,6 +92,8 @@ pub struct TonConnectionParams {
     pub notification_queue_length: usize,
     #[serde(default = "default_connection_concurrency_limit")]
     pub concurrency_limit: usize,
+    #[serde(default)]
+    pub rate_limit: Option<RateLimit>,
 }
 
 impl Default for TonConnectionParams {
@@ -66,6 +106,7 @@ impl Default for TonConnectionParams {
             keystore_dir: None,
             notification_queue_length: DEFAULT_NOTIFICATION_QUEUE_LENGTH,
             concurrency_limit: DEFAULT_CONNECTION_CONCURRENCY_LIMIT,
+            rate_limit: None,
         }
     }
 }
@@ -0,0 +1,325 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::cell::{BagOfCells, Cell, TonCellError};
+use crate::client::{ChainEvent, ChainWatcher, TonClientError, TonClientInterface};
+use crate::responses::{
+    ConfigParams, Validators, CONFIG_PARAM_CUR_VALIDATORS, CONFIG_PARAM_NEXT_VALIDATORS,
+};
+use crate::tl::BlockIdExt;
+
+#[derive(Debug, Clone)]
+pub enum ValidatorSetEvent {
+    /// Config param 36 appeared: the next validator set was elected.
+    NextSetAnnounced { block: BlockIdExt, next: Validators },
+    /// Config param 34 changed: the validator set rotated.
+    Rotated {
+        block: BlockIdExt,
+        previous: Validators,
+        current: Validators,
+    },
+}
+
+/// Share of the total weight of a validator set that belonged to a validator while the set
+/// was active.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WeightShare {
+    pub utime_since: u32,
+    pub utime_until: u32,
+    pub weight: u64,
+    pub share: f64,
+}
+
+/// Follows the key blocks of the masterchain, emitting events when the next validator set
+/// is announced and when the current set rotates.
+///
+/// The weight shares of the validators of the observed sets are kept by the
+/// [`ValidatorSetTracker`] returned by [`ValidatorSetMonitor::tracker`].
+pub struct ValidatorSetMonitor<C: TonClientInterface + Clone> {
+    client: C,
+    watcher: ChainWatcher<C>,
+    tracker: ValidatorSetTracker,
+    pending: VecDeque<ValidatorSetEvent>,
+}
+
+impl<C: TonClientInterface + Clone> ValidatorSetMonitor<C> {
+    pub fn new(client: &C, from_seqno: i32) -> ValidatorSetMonitor<C> {
+        ValidatorSetMonitor::with_watcher(client, ChainWatcher::new(client, from_seqno))
+    }
+
+    /// Creates a monitor following the blocks of `watcher`, e.g. to tweak its poll interval.
+    pub fn with_watcher(client: &C, watcher: ChainWatcher<C>) -> ValidatorSetMonitor<C> {
+        ValidatorSetMonitor {
+            client: client.clone(),
+            watcher,
+            tracker: ValidatorSetTracker::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Returns the next validator set event.
+    ///
+    /// Only key blocks can change the config, so the config of other blocks is not loaded.
+    pub async fn next(&mut self) -> Result<ValidatorSetEvent, TonClientError> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(event);
+            }
+            // Replayed blocks lead to the same sets, which are not reported twice
+            let block = match self.watcher.next().await? {
+                ChainEvent::Block(block) => block,
+                ChainEvent::Reorg { .. } => continue,
+            };
+            if self.tracker.current().is_some()
+                && !self.client.get_block_header(&block).await?.is_key_block
+            {
+                continue;
+            }
+            let info = self.client.get_config_all_at_block(0, &block).await?;
+            let params = parse_config(&info.config.bytes).map_err(|e| {
+                TonClientError::InternalError(format!("Invalid config of {:?}: {}", block, e))
+            })?;
+            self.pending.extend(self.tracker.update(&block, &params));
+        }
+    }
+
+    pub fn tracker(&self) -> &ValidatorSetTracker {
+        &self.tracker
+    }
+}
+
+/// Validator sets of the config and the weight shares of each validator in the observed sets.
+///
+/// The sets of the first update are taken as the initial state, so no events are returned
+/// for them.
+#[derive(Debug, Clone, Default)]
+pub struct ValidatorSetTracker {
+    current: Option<Validators>,
+    next: Option<Validators>,
+    shares: HashMap<Vec<u8>, Vec<WeightShare>>,
+}
+
+impl ValidatorSetTracker {
+    pub fn new() -> ValidatorSetTracker {
+        Default::default()
+    }
+
+    /// Updates the sets from the config of the block, returning the events of the changes.
+    pub fn update(&mut self, block: &BlockIdExt, params: &ConfigParams) -> Vec<ValidatorSetEvent> {
+        let mut events = vec![];
+        let initialized = self.current.is_some();
+        let next = params.validator_set(CONFIG_PARAM_NEXT_VALIDATORS);
+        let announced = match (&self.next, next) {
+            (Some(known), Some(next)) => !is_same_set(known, next),
+            (None, Some(_)) => initialized,
+            (_, None) => false,
+        };
+        if announced {
+            events.push(ValidatorSetEvent::NextSetAnnounced {
+                block: block.clone(),
+                next: next.unwrap().clone(),
+            });
+        }
+        self.next = next.cloned();
+
+        if let Some(current) = params.validator_set(CONFIG_PARAM_CUR_VALIDATORS) {
+            match self.current.replace(current.clone()) {
+                Some(previous) if !is_same_set(&previous, current) => {
+                    self.record_shares(current);
+                    events.push(ValidatorSetEvent::Rotated {
+                        block: block.clone(),
+                        previous,
+                        current: current.clone(),
+                    });
+                }
+                Some(_) => {}
+                None => self.record_shares(current),
+            }
+        }
+        events
+    }
+
+    pub fn current(&self) -> Option<&Validators> {
+        self.current.as_ref()
+    }
+
+    /// Returns the next validator set if it's already elected.
+    pub fn next_set(&self) -> Option<&Validators> {
+        self.next.as_ref()
+    }
+
+    /// Returns the weight shares of the validator in the observed sets, oldest first.
+    pub fn weight_shares(&self, public_key: &[u8]) -> &[WeightShare] {
+        self.shares
+            .get(public_key)
+            .map(|shares| shares.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Returns the public keys of all validators of the observed sets.
+    pub fn validators(&self) -> impl Iterator<Item = &[u8]> {
+        self.shares.keys().map(|key| key.as_slice())
+    }
+
+    fn record_shares(&mut self, validators: &Validators) {
+        for (public_key, share) in weight_shares(validators) {
+            let descr = validators
+                .list
+                .values()
+                .find(|descr| descr.public_key == public_key);
+            self.shares
+                .entry(public_key)
+                .or_default()
+                .push(WeightShare {
+                    utime_since: validators.utime_since,
+                    utime_until: validators.utime_until,
+                    weight: descr.map(|descr| descr.weight).unwrap_or_default(),
+                    share,
+                });
+        }
+    }
+}
+
+/// Returns the share of the total weight of each validator of the set by its public key.
+pub fn weight_shares(validators: &Validators) -> Vec<(Vec<u8>, f64)> {
+    let total_weight = match validators.total_weight {
+        0 => validators.list.values().map(|descr| descr.weight).sum(),
+        total_weight => total_weight,
+    };
+    validators
+        .list
+        .values()
+        .map(|descr| {
+            let share = match total_weight {
+                0 => 0.0,
+                total_weight => descr.weight as f64 / total_weight as f64,
+            };
+            (descr.public_key.clone(), share)
+        })
+        .collect()
+}
+
+fn is_same_set(a: &Validators, b: &Validators) -> bool {
+    a.utime_since == b.utime_since
+        && a.utime_until == b.utime_until
+        && a.total_weight == b.total_weight
+}
+
+fn parse_config(data: &[u8]) -> Result<ConfigParams, TonCellError> {
+    let boc = BagOfCells::parse(data)?;
+    let root = boc.single_root()?;
+    Cell::load_config_params_dict(root, &mut 0, &mut root.parser())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::{weight_shares, ValidatorSetEvent, ValidatorSetTracker};
+    use crate::responses::{
+        ConfigParam, ConfigParams, ConfigParamsValidatorSet, ValidatorDescr, Validators,
+    };
+    use crate::tl::BlockIdExt;
+
+    fn validators(utime_since: u32, weights: &[(u8, u64)]) -> Validators {
+        let list = weights
+            .iter()
+            .enumerate()
+            .map(|(i, (key, weight))| {
+                let descr = ValidatorDescr {
+                    public_key: vec![*key; 32],
+                    weight: *weight,
+                    ..Default::default()
+                };
+                (format!("{:x}", i), descr)
+            })
+            .collect();
+        Validators {
+            utime_since,
+            utime_until: utime_since + 100,
+            total_weight: weights.iter().map(|(_, weight)| weight).sum(),
+            list,
+            ..Default::default()
+        }
+    }
+
+    fn params(current: &Validators, next: Option<&Validators>) -> ConfigParams {
+        let mut params = ConfigParams::default();
+        let set = |number, validators: &Validators| ConfigParamsValidatorSet {
+            number,
+            validators: validators.clone(),
+        };
+        params.config.insert(
+            "22".to_string(),
+            Some(ConfigParam::ConfigParams34(set(34, current))),
+        );
+        if let Some(next) = next {
+            params.config.insert(
+                "24".to_string(),
+                Some(ConfigParam::ConfigParams36(set(36, next))),
+            );
+        }
+        params
+    }
+
+    fn block(seqno: i32) -> BlockIdExt {
+        BlockIdExt {
+            workchain: -1,
+            shard: i64::MIN,
+            seqno,
+            root_hash: String::new(),
+            file_hash: String::new(),
+        }
+    }
+
+    #[test]
+    fn validator_set_changes_are_tracked() {
+        let mut tracker = ValidatorSetTracker::new();
+        let first = validators(1000, &[(1, 30), (2, 10)]);
+        let second = validators(1100, &[(1, 20), (3, 60)]);
+
+        assert!(tracker.update(&block(1), &params(&first, None)).is_empty());
+        assert_eq!(tracker.current().unwrap().utime_since, 1000);
+        assert!(tracker.update(&block(2), &params(&first, None)).is_empty());
+
+        let events = tracker.update(&block(3), &params(&first, Some(&second)));
+        assert!(matches!(
+            &events[..],
+            [ValidatorSetEvent::NextSetAnnounced { block, next }]
+                if block.seqno == 3 && next.utime_since == 1100
+        ));
+        assert!(tracker
+            .update(&block(4), &params(&first, Some(&second)))
+            .is_empty());
+        assert_eq!(tracker.next_set().unwrap().utime_since, 1100);
+
+        let events = tracker.update(&block(5), &params(&second, None));
+        assert!(matches!(
+            &events[..],
+            [ValidatorSetEvent::Rotated { block, previous, current }]
+                if block.seqno == 5 && previous.utime_since == 1000 && current.utime_since == 1100
+        ));
+        assert!(tracker.next_set().is_none());
+
+        let shares = |key: u8| {
+            tracker
+                .weight_shares(&[key; 32])
+                .iter()
+                .map(|share| (share.utime_since, share.weight, share.share))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(shares(1), vec![(1000, 30, 0.75), (1100, 20, 0.25)]);
+        assert_eq!(shares(2), vec![(1000, 10, 0.25)]);
+        assert_eq!(shares(3), vec![(1100, 60, 0.75)]);
+        assert!(tracker.weight_shares(&[4; 32]).is_empty());
+        assert_eq!(tracker.validators().count(), 3);
+    }
+
+    #[test]
+    fn sets_of_first_update_are_initial_state() {
+        let mut tracker = ValidatorSetTracker::new();
+        let first = validators(1000, &[(1, 1)]);
+        let second = validators(1100, &[(1, 1)]);
+        assert!(tracker
+            .update(&block(1), &params(&first, Some(&second)))
+            .is_empty());
+        assert_eq!(weight_shares(&second), vec![(vec![1; 32], 1.0)]);
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use thiserror::Error;
+
+use crate::cell::{BagOfCells, Cell, CellType, TonCellError};
+use crate::constants::MASTERCHAIN_ID;
+use crate::tl::BlockIdExt;
+
+/// Error of [`AllShardsInfo::verify`].
+#[derive(Error, Debug)]
+pub enum ShardsProofError {
+    #[error("Shards are listed by masterchain blocks, got workchain {0}")]
+    NotMasterchainBlock(i32),
+
+    #[error("Invalid root hash of block {seqno} ({message})")]
+    InvalidRootHash { seqno: i32, message: String },
+
+    #[error("Proof doesn't match the root hash of block {0}")]
+    RootHashMismatch(i32),
+
+    #[error("Block {0} has no masterchain block extra")]
+    NoMcBlockExtra(i32),
+
+    #[error("Shard hashes don't match the proof of block {0}")]
+    ShardHashesMismatch(i32),
+
+    #[error("Cell error ({0})")]
+    CellError(#[from] TonCellError),
+}
+
+/// Response of `liteServer.getAllShardsInfo`:
+///
+/// ```raw
+/// liteServer.allShardsInfo id:tonNode.blockIdExt proof:bytes data:bytes = liteServer.AllShardsInfo;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AllShardsInfo {
+    /// Merkle proof of the masterchain block keeping the path to its `shard_hashes`.
+    pub proof: Vec<u8>,
+    /// Cell holding the `ShardHashes` dictionary of the block.
+    pub data: Vec<u8>,
+}
+
+impl AllShardsInfo {
+    /// Verifies the proof against the root hash of the masterchain block and returns the
+    /// shard blocks by workchain.
+    ///
+    /// ```raw
+    /// block#11ef55aa global_id:int32 info:^BlockInfo value_flow:^ValueFlow
+    ///   state_update:^(MERKLE_UPDATE ShardState) extra:^BlockExtra = Block;
+    /// block_extra#4a33f6fd in_msg_descr:^InMsgDescr out_msg_descr:^OutMsgDescr
+    ///   account_blocks:^ShardAccountBlocks rand_seed:bits256 created_by:bits256
+    ///   custom:(Maybe ^McBlockExtra) = BlockExtra;
+    /// masterchain_block_extra#cca5 key_block:(## 1) shard_hashes:ShardHashes ... = McBlockExtra;
+    /// ```
+    pub fn verify(
+        &self,
+        master_block: &BlockIdExt,
+    ) -> Result<HashMap<i32, Vec<BlockIdExt>>, ShardsProofError> {
+        let seqno = master_block.seqno;
+        if master_block.workchain != MASTERCHAIN_ID {
+            return Err(ShardsProofError::NotMasterchainBlock(
+                master_block.workchain,
+            ));
+        }
+        let root_hash = STANDARD.decode(&master_block.root_hash).map_err(|e| {
+            ShardsProofError::InvalidRootHash {
+                seqno,
+                message: e.to_string(),
+            }
+        })?;
+
+        let proof_boc = BagOfCells::parse(&self.proof)?;
+        let proof = proof_boc.single_root()?;
+        if proof.cell_type != CellType::MerkleProofCell as u8 {
+            return Err(TonCellError::unexpected_structure("MERKLE_PROOF").into());
+        }
+        let block = proof.reference(0)?;
+        if block.virtual_hash(0)? != root_hash.as_slice() {
+            return Err(ShardsProofError::RootHashMismatch(seqno));
+        }
+        if block.parser().load_u32(32)? != 0x11ef55aa {
+            return Err(TonCellError::unexpected_structure("Block").into());
+        }
+        let extra = block.reference(3)?;
+        let mut parser = extra.parser();
+        if parser.load_u32(32)? != 0x4a33f6fd {
+            return Err(TonCellError::unexpected_structure("BlockExtra").into());
+        }
+        parser.skip_bits(512)?;
+        if !parser.load_bit()? {
+            return Err(ShardsProofError::NoMcBlockExtra(seqno));
+        }
+        let mut mc_extra = extra.reference(3)?.parser();
+        mc_extra.skip_bits(16 + 1)?;
+        let proven_shard_hashes = mc_extra.load_dict_hash(32)?;
+
+        let data_boc = BagOfCells::parse(&self.data)?;
+        let data = data_boc.single_root()?;
+        if data.parser().load_dict_hash(32)? != proven_shard_hashes {
+            return Err(ShardsProofError::ShardHashesMismatch(seqno));
+        }
+
+        let shards = Cell::load_shard_hashes(data, &mut 0, &mut data.parser())?;
+        let mut result = HashMap::new();
+        for descr in shards.into_values().flatten() {
+            result
+                .entry(descr.workchain)
+                .or_insert_with(Vec::new)
+                .push(BlockIdExt {
+                    workchain: descr.workchain,
+                    shard: descr.shard,
+                    seqno: descr.seqno as i32,
+                    root_hash: STANDARD.encode(&descr.root_hash),
+                    file_hash: STANDARD.encode(&descr.file_hash),
+                });
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+
+    use crate::cell::{merkle_proof, prune_cells, BagOfCells, CellBuilder};
+    use crate::client::{
+        AllShardsInfo, ShardsProofError, TonBlockFunctions, TonClientError, TonClientInterface,
+        TonConnection,
+    };
+    use crate::tl::{BlockIdExt, BlocksShards, TonFunction, TonResult};
+
+    /// Returns the shards info of the block of the resources, with the id of the block and the
+    /// number of its shards.
+    fn shards_info() -> anyhow::Result<(AllShardsInfo, BlockIdExt, usize)> {
+        let boc = BagOfCells::parse_hex(include_str!("../../resources/bloc/block_extra.hex"))?;
+        let root = boc.single_root()?;
+        let block = root.load_block()?;
+        let mc_extra = root.reference(3)?.reference(3)?;
+        let shard_hashes = mc_extra.reference(0)?;
+
+        // Keeps the block, its extra, the masterchain extra and the whole shard hashes
+        let pruned = prune_cells(root, |path, _| {
+            path.is_empty() || path == [3] || path == [3, 3] || path.starts_with(&[3, 3, 0])
+        })?;
+        let proof = BagOfCells::from_root(merkle_proof(&pruned)?).serialize(true)?;
+        let data_cell = CellBuilder::new()
+            .store_bit(true)?
+            .store_reference(shard_hashes)?
+            .build()?;
+        let info = AllShardsInfo {
+            proof,
+            data: BagOfCells::from_root(data_cell).serialize(true)?,
+        };
+
+        let master_block = BlockIdExt {
+            workchain: -1,
+            shard: i64::MIN,
+            seqno: block.info.as_ref().unwrap().seq_no as i32,
+            root_hash: STANDARD.encode(root.virtual_hash(0)?),
+            file_hash: String::new(),
+        };
+        let expected = &block.extra.as_ref().unwrap().custom.shards;
+        Ok((
+            info,
+            master_block,
+            expected.values().map(Vec::len).sum::<usize>(),
+        ))
+    }
+
+    #[test]
+    fn all_shards_info_is_verified() -> anyhow::Result<()> {
+        let boc = BagOfCells::parse_hex(include_str!("../../resources/bloc/block_extra.hex"))?;
+        let block = boc.single_root()?.load_block()?;
+        let (info, mut master_block, shards_len) = shards_info()?;
+        let shards = info.verify(&master_block)?;
+        assert_eq!(shards.values().map(Vec::len).sum::<usize>(), shards_len);
+        let expected = &block.extra.as_ref().unwrap().custom.shards;
+        let descr = expected.values().flatten().next().unwrap();
+        let shard = shards[&descr.workchain]
+            .iter()
+            .find(|shard| shard.shard == descr.shard)
+            .unwrap();
+        assert_eq!(shard.seqno, descr.seqno as i32);
+        assert_eq!(STANDARD.decode(&shard.root_hash)?, descr.root_hash);
+
+        // Shard hashes not matching the proof
+        let other_data = CellBuilder::new().store_bit(false)?.build()?;
+        let forged = AllShardsInfo {
+            data: BagOfCells::from_root(other_data).serialize(true)?,
+            ..info.clone()
+        };
+        assert!(matches!(
+            forged.verify(&master_block),
+            Err(ShardsProofError::ShardHashesMismatch(_))
+        ));
+
+        master_block.root_hash = STANDARD.encode([0; 32]);
+        assert!(matches!(
+            info.verify(&master_block),
+            Err(ShardsProofError::RootHashMismatch(_))
+        ));
+        Ok(())
+    }
+
+    /// Answers `blocks.getShards` with the shards.
+    struct MockClient {
+        shards: Vec<BlockIdExt>,
+    }
+
+    #[async_trait]
+    impl TonClientInterface for MockClient {
+        async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+            Err(TonClientError::InternalError("No connection".to_string()))
+        }
+
+        async fn invoke_on_connection(
+            &self,
+            _function: &TonFunction,
+        ) -> Result<(TonConnection, TonResult), TonClientError> {
+            Err(TonClientError::InternalError("No connection".to_string()))
+        }
+
+        async fn invoke(&self, function: &TonFunction) -> Result<TonResult, TonClientError> {
+            match function {
+                TonFunction::BlocksGetShards { .. } => Ok(TonResult::BlocksShards(BlocksShards {
+                    shards: self.shards.clone(),
+                })),
+                _ => Err(TonClientError::InternalError("Unexpected function".into())),
+            }
+        }
+    }
+
+    #[test]
+    fn get_shards_verified_works() -> anyhow::Result<()> {
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        let (info, master_block, shards_len) = shards_info()?;
+        let expected = info.verify(&master_block)?;
+        let client = MockClient {
+            shards: expected.values().flatten().cloned().collect(),
+        };
+        let shards = runtime.block_on(client.get_shards_verified(&master_block))?;
+        assert_eq!(shards.values().map(Vec::len).sum::<usize>(), shards_len);
+        assert_eq!(shards, expected);
+
+        let shard_block = expected.values().flatten().next().unwrap().clone();
+        let result = runtime.block_on(client.get_shards_verified(&shard_block));
+        assert!(matches!(
+            result,
+            Err(TonClientError::ShardsProofError(
+                ShardsProofError::NotMasterchainBlock(_)
+            ))
+        ));
+        Ok(())
+    }
+}
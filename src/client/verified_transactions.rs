@@ -0,0 +1,308 @@
+use std::collections::HashMap;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use num_bigint::BigUint;
+use thiserror::Error;
+
+use crate::cell::{BagOfCells, Cell, CellParser, CellType, TonCellError};
+use crate::hashmap::Hashmap;
+use crate::tl::{BlockIdExt, BlocksTransactions};
+
+/// Error of [`ProvedBlockTransactions::verify`].
+#[derive(Error, Debug)]
+pub enum TransactionsProofError {
+    #[error("Invalid root hash of block {seqno} ({message})")]
+    InvalidRootHash { seqno: i32, message: String },
+
+    #[error("Proof doesn't match the root hash of block {0}")]
+    RootHashMismatch(i32),
+
+    #[error("Transaction {lt} of account {account} is not in the proof of block {seqno}")]
+    TransactionNotProven {
+        seqno: i32,
+        account: String,
+        lt: i64,
+    },
+
+    #[error("Cell error ({0})")]
+    CellError(#[from] TonCellError),
+}
+
+/// Response of `liteServer.listBlockTransactions`, e.g. of a liteserver queried directly:
+///
+/// ```raw
+/// liteServer.blockTransactions id:tonNode.blockIdExt req_count:# incomplete:Bool
+///   ids:(vector liteServer.transactionId) proof:bytes = liteServer.BlockTransactions;
+/// ```
+///
+/// tonlib checks the proof of `blocks.getTransactions` itself and doesn't return it, see
+/// [`TonBlockFunctions::get_block_transactions_all`].
+///
+/// [`TonBlockFunctions::get_block_transactions_all`]: crate::client::TonBlockFunctions::get_block_transactions_all
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvedBlockTransactions {
+    /// Ids of the transactions, as returned by `blocks.getTransactions`.
+    pub transactions: BlocksTransactions,
+    /// Merkle proof of the block keeping the paths to the transactions in its `AccountBlocks`.
+    pub proof: Vec<u8>,
+}
+
+impl ProvedBlockTransactions {
+    /// Verifies the proof against the root hash of the block and checks that each of the
+    /// transactions is in the `AccountBlocks` dictionary of the block under its account and
+    /// lt with its hash.
+    ///
+    /// ```raw
+    /// block#11ef55aa global_id:int32 info:^BlockInfo value_flow:^ValueFlow
+    ///   state_update:^(MERKLE_UPDATE ShardState) extra:^BlockExtra = Block;
+    /// block_extra#4a33f6fd in_msg_descr:^InMsgDescr out_msg_descr:^OutMsgDescr
+    ///   account_blocks:^ShardAccountBlocks rand_seed:bits256 created_by:bits256
+    ///   custom:(Maybe ^McBlockExtra) = BlockExtra;
+    /// _ (HashmapAugE 256 AccountBlock CurrencyCollection) = ShardAccountBlocks;
+    /// acc_trans#5 account_addr:bits256 transactions:(HashmapAug 64 ^Transaction CurrencyCollection)
+    ///   state_update:^(HASH_UPDATE Account) = AccountBlock;
+    /// ```
+    pub fn verify(&self, block_id: &BlockIdExt) -> Result<(), TransactionsProofError> {
+        let seqno = block_id.seqno;
+        let root_hash = STANDARD.decode(&block_id.root_hash).map_err(|e| {
+            TransactionsProofError::InvalidRootHash {
+                seqno,
+                message: e.to_string(),
+            }
+        })?;
+
+        let proof_boc = BagOfCells::parse(&self.proof)?;
+        let proof = proof_boc.single_root()?;
+        if proof.cell_type != CellType::MerkleProofCell as u8 {
+            return Err(TonCellError::unexpected_structure("MERKLE_PROOF").into());
+        }
+        let block = proof.reference(0)?;
+        if block.virtual_hash(0)? != root_hash.as_slice() {
+            return Err(TransactionsProofError::RootHashMismatch(seqno));
+        }
+        if block.parser().load_u32(32)? != 0x11ef55aa {
+            return Err(TonCellError::unexpected_structure("Block").into());
+        }
+        let extra = block.reference(3)?;
+        if extra.parser().load_u32(32)? != 0x4a33f6fd {
+            return Err(TonCellError::unexpected_structure("BlockExtra").into());
+        }
+        let account_blocks = extra.reference(2)?;
+        let proven = load_transaction_hashes(account_blocks)?;
+
+        for tx in &self.transactions.transactions {
+            let hash = u64::try_from(tx.lt)
+                .ok()
+                .and_then(|lt| proven.get(tx.account.as_slice())?.get(&lt));
+            if hash != Some(&tx.hash) {
+                return Err(TransactionsProofError::TransactionNotProven {
+                    seqno,
+                    account: hex::encode(&tx.account),
+                    lt: tx.lt,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Hashes of the transactions of an account by their lt.
+type TransactionHashes = HashMap<u64, Vec<u8>>;
+
+/// Loads the hashes of the transactions of `ShardAccountBlocks` by account and lt, skipping
+/// pruned branches. Transactions are not parsed, as their cells may be pruned as well.
+fn load_transaction_hashes(
+    account_blocks: &Cell,
+) -> Result<HashMap<Vec<u8>, TransactionHashes>, TonCellError> {
+    let load_transaction = |cell: &Cell,
+                            ref_index: &mut usize,
+                            parser: &mut CellParser,
+                            _key: &BigUint|
+     -> Result<Option<Vec<u8>>, TonCellError> {
+        Cell::load_currency_collection(cell, ref_index, parser)?;
+        let tx = cell.reference(*ref_index)?;
+        *ref_index += 1;
+        Ok(Some(tx.get_hash(0)))
+    };
+    let load_account_block = |cell: &Cell,
+                              ref_index: &mut usize,
+                              parser: &mut CellParser,
+                              _key: &BigUint|
+     -> Result<Option<(Vec<u8>, TransactionHashes)>, TonCellError> {
+        Cell::load_currency_collection(cell, ref_index, parser)?;
+        if parser.load_u8(4)? != 0x5 {
+            return Err(TonCellError::unexpected_structure("AccountBlock"));
+        }
+        let account_addr = parser.load_bytes(32)?;
+        let mut transactions = Hashmap::new(64, load_transaction);
+        transactions.deserialize(cell, ref_index, parser)?;
+        let transactions = transactions
+            .map
+            .into_iter()
+            .map(|(lt, hash)| {
+                let lt = u64::from_str_radix(&lt, 16).map_err(TonCellError::cell_parser_error)?;
+                Ok((lt, hash))
+            })
+            .collect::<Result<_, TonCellError>>()?;
+        Ok(Some((account_addr, transactions)))
+    };
+    let mut hashmap = Hashmap::new(256, load_account_block);
+    hashmap.deserialize_e(account_blocks, &mut 0, &mut account_blocks.parser())?;
+    Ok(hashmap.map.into_values().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use base64::engine::general_purpose::STANDARD;
+    use base64::Engine;
+
+    use crate::cell::{merkle_proof, prune_cells, BagOfCells};
+    use crate::client::{
+        ProvedBlockTransactions, TonBlockFunctions, TonClientError, TonClientInterface,
+        TonConnection, TransactionsProofError, TxId, BLOCK_TRANSACTIONS_PROVED_MODE,
+    };
+    use crate::tl::{BlockIdExt, BlocksShortTxId, BlocksTransactions, TonFunction, TonResult};
+
+    /// Returns the transactions of the block of the resources with their proof, with the id of
+    /// the block.
+    fn proved_transactions() -> anyhow::Result<(ProvedBlockTransactions, BlockIdExt)> {
+        let boc = BagOfCells::parse_hex(include_str!("../../resources/bloc/block_extra.hex"))?;
+        let root = boc.single_root()?;
+        let block = root.load_block()?;
+
+        // Keeps the block, its extra and the whole account blocks
+        let pruned = prune_cells(root, |path, _| {
+            path.is_empty() || path == [3] || path.starts_with(&[3, 2])
+        })?;
+        let proof = BagOfCells::from_root(merkle_proof(&pruned)?).serialize(true)?;
+
+        let block_id = BlockIdExt {
+            workchain: -1,
+            shard: i64::MIN,
+            seqno: block.info.as_ref().unwrap().seq_no as i32,
+            root_hash: STANDARD.encode(root.virtual_hash(0)?),
+            file_hash: String::new(),
+        };
+        let mut transactions: Vec<BlocksShortTxId> = block
+            .extra
+            .as_ref()
+            .unwrap()
+            .account_blocks
+            .as_ref()
+            .unwrap()
+            .values()
+            .flat_map(|account_block| {
+                account_block
+                    .transactions
+                    .iter()
+                    .map(|(lt, tx)| BlocksShortTxId {
+                        mode: 7,
+                        account: account_block.account_addr.clone(),
+                        lt: i64::from_str_radix(lt, 16).unwrap(),
+                        hash: tx.data.as_ref().unwrap().hash.clone(),
+                    })
+            })
+            .collect();
+        transactions.sort_by(|a, b| (&a.account, a.lt).cmp(&(&b.account, b.lt)));
+        let proved = ProvedBlockTransactions {
+            transactions: BlocksTransactions {
+                id: block_id.clone(),
+                req_count: transactions.len() as i32,
+                incomplete: false,
+                transactions,
+            },
+            proof,
+        };
+        Ok((proved, block_id))
+    }
+
+    #[test]
+    fn proved_block_transactions_are_verified() -> anyhow::Result<()> {
+        let (proved, mut block_id) = proved_transactions()?;
+        assert!(!proved.transactions.transactions.is_empty());
+        proved.verify(&block_id)?;
+
+        // Transaction hash not matching the proof
+        let mut forged = proved.clone();
+        forged.transactions.transactions[0].hash = vec![0; 32];
+        assert!(matches!(
+            forged.verify(&block_id),
+            Err(TransactionsProofError::TransactionNotProven { .. })
+        ));
+
+        block_id.root_hash = STANDARD.encode([0; 32]);
+        assert!(matches!(
+            proved.verify(&block_id),
+            Err(TransactionsProofError::RootHashMismatch(_))
+        ));
+        Ok(())
+    }
+
+    /// Answers `blocks.getTransactions` requested with proof with the transactions.
+    struct MockClient {
+        transactions: BlocksTransactions,
+    }
+
+    #[async_trait]
+    impl TonClientInterface for MockClient {
+        async fn get_connection(&self) -> Result<TonConnection, TonClientError> {
+            Err(TonClientError::InternalError("No connection".to_string()))
+        }
+
+        async fn invoke_on_connection(
+            &self,
+            _function: &TonFunction,
+        ) -> Result<(TonConnection, TonResult), TonClientError> {
+            Err(TonClientError::InternalError("No connection".to_string()))
+        }
+
+        async fn invoke(&self, function: &TonFunction) -> Result<TonResult, TonClientError> {
+            match function {
+                TonFunction::BlocksGetTransactions { mode, .. }
+                    if mode & BLOCK_TRANSACTIONS_PROVED_MODE == BLOCK_TRANSACTIONS_PROVED_MODE =>
+                {
+                    Ok(TonResult::BlocksTransactions(self.transactions.clone()))
+                }
+                _ => Err(TonClientError::InternalError("Unexpected function".into())),
+            }
+        }
+    }
+
+    #[test]
+    fn get_block_transactions_all_works() -> anyhow::Result<()> {
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        let (proved, block_id) = proved_transactions()?;
+        let expected = proved
+            .transactions
+            .transactions
+            .iter()
+            .map(|tx| TxId::new(block_id.workchain, tx))
+            .collect::<Result<Vec<_>, _>>()?;
+        let client = MockClient {
+            transactions: proved.transactions.clone(),
+        };
+        let ids = runtime.block_on(client.get_block_transactions_all(&block_id))?;
+        assert_eq!(ids, expected);
+
+        let mut other_block = proved.transactions.clone();
+        other_block.id.seqno += 1;
+        let client = MockClient {
+            transactions: other_block,
+        };
+        assert!(runtime
+            .block_on(client.get_block_transactions_all(&block_id))
+            .is_err());
+
+        let mut unordered = proved.transactions;
+        unordered.transactions.reverse();
+        let client = MockClient {
+            transactions: unordered,
+        };
+        assert!(runtime
+            .block_on(client.get_block_transactions_all(&block_id))
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+mod error;
+
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+
+pub use error::*;
+use num_bigint::BigUint;
+use num_traits::{ToPrimitive, Zero};
+
+/// Number of nanotons in a toncoin.
+pub const NANOTONS_PER_TON: u64 = 1_000_000_000;
+
+const DECIMALS: usize = 9;
+/// `Coins` are stored as `VarUInteger 16`, i.e. in at most 15 bytes.
+const MAX_BITS: u64 = 120;
+
+/// Amount of toncoins in nanotons, stored in cells as:
+///
+/// ```raw
+/// nanograms$_ amount:(VarUInteger 16) = Grams;
+/// ```
+///
+/// Amounts are displayed and parsed as toncoins with up to 9 decimals, e.g. `1.250000000`
+/// and `1.25`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Coins(BigUint);
+
+impl Coins {
+    pub fn new(nanotons: BigUint) -> Coins {
+        Coins(nanotons)
+    }
+
+    pub fn zero() -> Coins {
+        Coins(BigUint::zero())
+    }
+
+    pub fn from_nano(nanotons: u64) -> Coins {
+        Coins(BigUint::from(nanotons))
+    }
+
+    pub fn from_ton(tons: u64) -> Coins {
+        Coins(BigUint::from(tons) * NANOTONS_PER_TON)
+    }
+
+    pub fn nanotons(&self) -> &BigUint {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> BigUint {
+        self.0
+    }
+
+    pub fn to_u64(&self) -> Option<u64> {
+        self.0.to_u64()
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    /// Returns whether the amount can be stored in a cell, i.e. is less than 2^120 nanotons.
+    pub fn is_valid(&self) -> bool {
+        self.0.bits() <= MAX_BITS
+    }
+
+    /// Returns the sum, or `None` if it can't be stored in a cell.
+    pub fn checked_add(&self, other: &Coins) -> Option<Coins> {
+        Coins(&self.0 + &other.0).checked()
+    }
+
+    /// Returns the difference, or `None` if `other` is greater.
+    pub fn checked_sub(&self, other: &Coins) -> Option<Coins> {
+        (self.0 >= other.0).then(|| Coins(&self.0 - &other.0))
+    }
+
+    /// Returns the product, or `None` if it can't be stored in a cell.
+    pub fn checked_mul(&self, factor: u64) -> Option<Coins> {
+        Coins(&self.0 * factor).checked()
+    }
+
+    /// Returns the quotient rounded down, or `None` if `divisor` is zero.
+    pub fn checked_div(&self, divisor: u64) -> Option<Coins> {
+        (divisor != 0).then(|| Coins(&self.0 / divisor))
+    }
+
+    fn checked(self) -> Option<Coins> {
+        self.is_valid().then_some(self)
+    }
+}
+
+impl Display for Coins {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let tons = &self.0 / NANOTONS_PER_TON;
+        let nanotons = (&self.0 % NANOTONS_PER_TON).to_u64().unwrap_or_default();
+        write!(f, "{}.{:09}", tons, nanotons)
+    }
+}
+
+impl FromStr for Coins {
+    type Err = CoinsParseError;
+
+    /// Parses an amount of toncoins with up to 9 decimals, e.g. `1.25`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (tons, fraction) = match s.split_once('.') {
+            Some((tons, fraction)) if !fraction.is_empty() => (tons, fraction),
+            Some(_) => return Err(CoinsParseError::new(s, "No digits after the point")),
+            None => (s, ""),
+        };
+        let is_decimal = |digits: &str| digits.bytes().all(|b| b.is_ascii_digit());
+        if tons.is_empty() || !is_decimal(tons) || !is_decimal(fraction) {
+            return Err(CoinsParseError::new(s, "Not a decimal number"));
+        }
+        if fraction.len() > DECIMALS {
+            return Err(CoinsParseError::new(
+                s,
+                format!("More than {} decimals", DECIMALS),
+            ));
+        }
+        let digits = format!("{}{:0<width$}", tons, fraction, width = DECIMALS);
+        let nanotons = BigUint::parse_bytes(digits.as_bytes(), 10)
+            .ok_or_else(|| CoinsParseError::new(s, "Not a decimal number"))?;
+        Coins(nanotons)
+            .checked()
+            .ok_or_else(|| CoinsParseError::new(s, "Amount is 2^120 nanotons or more"))
+    }
+}
+
+impl From<BigUint> for Coins {
+    fn from(nanotons: BigUint) -> Self {
+        Coins(nanotons)
+    }
+}
+
+impl From<&BigUint> for Coins {
+    fn from(nanotons: &BigUint) -> Self {
+        Coins(nanotons.clone())
+    }
+}
+
+impl From<&Coins> for Coins {
+    fn from(coins: &Coins) -> Self {
+        coins.clone()
+    }
+}
+
+impl From<u64> for Coins {
+    fn from(nanotons: u64) -> Self {
+        Coins::from_nano(nanotons)
+    }
+}
+
+impl From<Coins> for BigUint {
+    fn from(coins: Coins) -> Self {
+        coins.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use crate::coins::Coins;
+
+    #[test]
+    fn coins_work() -> anyhow::Result<()> {
+        let coins: Coins = "1.25".parse()?;
+        assert_eq!(coins, Coins::from_nano(1_250_000_000));
+        assert_eq!(coins.to_string(), "1.250000000");
+        assert_eq!("7".parse::<Coins>()?, Coins::from_ton(7));
+        assert_eq!("0.000000001".parse::<Coins>()?, Coins::from_nano(1));
+        assert_eq!(Coins::from_nano(1).to_string(), "0.000000001");
+        assert_eq!(Coins::zero().to_string(), "0.000000000");
+        for invalid in ["", ".5", "1.", "-1", "1.2.3", "1,5", "0.0000000001", "1e9"] {
+            assert!(invalid.parse::<Coins>().is_err(), "{}", invalid);
+        }
+        let max = Coins::new((BigUint::from(1u32) << 120) - 1u32);
+        assert!(max.to_string().parse::<Coins>()? == max);
+        assert!(format!("1{}", max).parse::<Coins>().is_err());
+
+        let one = Coins::from_ton(1);
+        assert_eq!(
+            coins.checked_add(&one),
+            Some(Coins::from_nano(2_250_000_000))
+        );
+        assert_eq!(coins.checked_sub(&one), Some(Coins::from_nano(250_000_000)));
+        assert_eq!(one.checked_sub(&coins), None);
+        assert_eq!(max.checked_add(&Coins::from_nano(1)), None);
+        assert_eq!(coins.checked_mul(2), Some(Coins::from_nano(2_500_000_000)));
+        assert_eq!(max.checked_mul(2), None);
+        assert_eq!(coins.checked_div(5), Some(Coins::from_nano(250_000_000)));
+        assert_eq!(coins.checked_div(0), None);
+        Ok(())
+    }
+}
@@ -0,0 +1,19 @@
+use alloc::string::{String, ToString};
+
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid amount of coins (amount: {amount}, message: {message})")]
+pub struct CoinsParseError {
+    amount: String,
+    message: String,
+}
+
+impl CoinsParseError {
+    pub fn new<A: ToString, M: ToString>(amount: A, message: M) -> CoinsParseError {
+        CoinsParseError {
+            amount: amount.to_string(),
+            message: message.to_string(),
+        }
+    }
+}
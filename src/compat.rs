@@ -0,0 +1,10 @@
+//! Replacements of `std` items used by the encoding core, so it builds with `alloc` only.
+
+#[cfg(not(feature = "std"))]
+pub(crate) use hashbrown::{HashMap, HashSet};
+#[cfg(not(feature = "std"))]
+pub(crate) use no_std_io2::io;
+#[cfg(feature = "std")]
+pub(crate) use std::collections::{HashMap, HashSet};
+#[cfg(feature = "std")]
+pub(crate) use std::io;
@@ -1,2 +1,428 @@
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::watch;
+use tokio::time;
+
+use crate::network::Network;
+use crate::tl::BlockIdExt;
+
 pub const MAINNET_CONFIG: &str = include_str!("../resources/config/global.config.json");
 pub const TESTNET_CONFIG: &str = include_str!("../resources/config/testnet-global.config.json");
+
+pub const MAINNET_CONFIG_URL: &str = "https://ton.org/global-config.json";
+pub const TESTNET_CONFIG_URL: &str = "https://ton.org/testnet-global.config.json";
+
+const CONFIG_TYPE: &str = "config.global";
+const VALIDATOR_CONFIG_TYPE: &str = "validator.config.global";
+const PUBLIC_KEY_TYPE: &str = "pub.ed25519";
+
+#[derive(Error, Debug)]
+pub enum TonConfigError {
+    #[error("Invalid config: {0}")]
+    InvalidConfig(String),
+
+    #[error("Serde error: {0}")]
+    SerdeError(#[from] serde_json::Error),
+
+    #[error("Failed to fetch config (url: {url}, status: {status})")]
+    FetchFailed { url: String, status: StatusCode },
+
+    #[error("Transport error: {0}")]
+    TransportError(#[from] reqwest::Error),
+
+    #[error("Unexpected config update: {0}")]
+    UnexpectedUpdate(String),
+}
+
+/// Global config of the network, as accepted by tonlib.
+///
+/// Fields that are not used by the builder (e.g. `dht`) are kept as is.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct TonConfig {
+    #[serde(rename = "@type", default = "default_config_type")]
+    pub config_type: String,
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub dht: serde_json::Value,
+    pub liteservers: Vec<LiteServerConfig>,
+    pub validator: ValidatorConfig,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LiteServerConfig {
+    /// IPv4 address of the liteserver, stored as a signed big-endian integer.
+    pub ip: i32,
+    pub port: u16,
+    pub id: LiteServerId,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LiteServerId {
+    #[serde(rename = "@type", default = "default_public_key_type")]
+    pub key_type: String,
+    /// Base64-encoded ed25519 public key of the liteserver.
+    pub key: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ValidatorConfig {
+    #[serde(rename = "@type", default = "default_validator_config_type")]
+    pub validator_type: String,
+    pub zero_state: BlockIdExt,
+    pub init_block: BlockIdExt,
+    #[serde(default)]
+    pub hardforks: Vec<BlockIdExt>,
+}
+
+impl TonConfig {
+    /// Returns the embedded mainnet config.
+    pub fn mainnet() -> TonConfig {
+        // The embedded config is validated by tests
+        TonConfig::from_json(MAINNET_CONFIG).unwrap()
+    }
+
+    /// Returns the embedded testnet config.
+    pub fn testnet() -> TonConfig {
+        // The embedded config is validated by tests
+        TonConfig::from_json(TESTNET_CONFIG).unwrap()
+    }
+
+    /// Returns the embedded config of the network, failing for custom networks which have
+    /// no embedded config.
+    pub fn for_network(network: Network) -> Result<TonConfig, TonConfigError> {
+        TonConfig::from_json(embedded_config(network)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<TonConfig, TonConfigError> {
+        let config: TonConfig = serde_json::from_str(json)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Fetches the config from the url, e.g. [`MAINNET_CONFIG_URL`].
+    pub async fn from_url(url: &str) -> Result<TonConfig, TonConfigError> {
+        fetch_config(&reqwest::Client::new(), url).await
+    }
+
+    pub fn to_json(&self) -> Result<String, TonConfigError> {
+        Ok(serde_json::to_string(self)?)
+    }
+
+    pub fn builder(base: TonConfig) -> TonConfigBuilder {
+        TonConfigBuilder::new(base)
+    }
+
+    pub fn validate(&self) -> Result<(), TonConfigError> {
+        if self.liteservers.is_empty() {
+            return Err(TonConfigError::InvalidConfig(
+                "No liteservers specified".to_string(),
+            ));
+        }
+        let zero_state = &self.validator.zero_state;
+        let init_block = &self.validator.init_block;
+        if zero_state.seqno != 0 {
+            return Err(TonConfigError::InvalidConfig(format!(
+                "Zero state has non-zero seqno: {}",
+                zero_state.seqno
+            )));
+        }
+        if init_block.workchain != zero_state.workchain || init_block.shard != zero_state.shard {
+            return Err(TonConfigError::InvalidConfig(format!(
+                "Init block {:?} is not a masterchain block",
+                init_block
+            )));
+        }
+        Ok(())
+    }
+
+    /// Checks that `update` is a newer version of the config of the same network.
+    ///
+    /// The zero state must stay the same, the init block may only move forward and the
+    /// known hardforks must be kept.
+    pub fn check_update(&self, update: &TonConfig) -> Result<(), TonConfigError> {
+        let current = &self.validator;
+        let new = &update.validator;
+        if current.zero_state != new.zero_state {
+            return Err(TonConfigError::UnexpectedUpdate(format!(
+                "Zero state changed from {:?} to {:?}",
+                current.zero_state, new.zero_state
+            )));
+        }
+        let init_block_changed = if current.init_block.seqno == new.init_block.seqno {
+            current.init_block != new.init_block
+        } else {
+            current.init_block.seqno > new.init_block.seqno
+        };
+        if init_block_changed {
+            return Err(TonConfigError::UnexpectedUpdate(format!(
+                "Init block changed from {:?} to {:?}",
+                current.init_block, new.init_block
+            )));
+        }
+        if !new.hardforks.starts_with(&current.hardforks) {
+            return Err(TonConfigError::UnexpectedUpdate(format!(
+                "Hardforks changed from {:?} to {:?}",
+                current.hardforks, new.hardforks
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Keeps a config fetched from an url up to date.
+///
+/// Updates are validated with [`TonConfig::check_update`] and published to subscribers,
+/// which can apply them to a running client with [`TonClient::set_config`].
+///
+/// [`TonClient::set_config`]: crate::client::TonClient::set_config
+pub struct TonConfigRefresher {
+    url: String,
+    http_client: reqwest::Client,
+    sender: watch::Sender<TonConfig>,
+}
+
+impl TonConfigRefresher {
+    /// Fetches the initial config from the url.
+    pub async fn new(url: &str) -> Result<TonConfigRefresher, TonConfigError> {
+        let http_client = reqwest::Client::builder().build()?;
+        let config = fetch_config(&http_client, url).await?;
+        let (sender, _) = watch::channel(config);
+        Ok(TonConfigRefresher {
+            url: url.to_string(),
+            http_client,
+            sender,
+        })
+    }
+
+    pub fn config(&self) -> TonConfig {
+        self.sender.borrow().clone()
+    }
+
+    /// Returns a receiver notified about every accepted change of the config.
+    pub fn subscribe(&self) -> watch::Receiver<TonConfig> {
+        self.sender.subscribe()
+    }
+
+    /// Fetches the config once. Returns `true` if the config has changed.
+    pub async fn refresh(&self) -> Result<bool, TonConfigError> {
+        let update = fetch_config(&self.http_client, &self.url).await?;
+        self.sender.borrow().check_update(&update)?;
+        Ok(self.sender.send_if_modified(|config| {
+            if *config != update {
+                *config = update;
+                true
+            } else {
+                false
+            }
+        }))
+    }
+
+    /// Refreshes the config with the specified interval until the future is dropped.
+    ///
+    /// Failed refreshes are logged and the previous config is kept.
+    pub async fn run(&self, interval: Duration) {
+        loop {
+            time::sleep(interval).await;
+            match self.refresh().await {
+                Ok(true) => log::info!("Config updated from {}", self.url),
+                Ok(false) => {}
+                Err(e) => log::warn!("Failed to refresh config from {}: {}", self.url, e),
+            }
+        }
+    }
+}
+
+async fn fetch_config(
+    http_client: &reqwest::Client,
+    url: &str,
+) -> Result<TonConfig, TonConfigError> {
+    let response = http_client.get(url).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(TonConfigError::FetchFailed {
+            url: url.to_string(),
+            status,
+        });
+    }
+    let body = response.text().await?;
+    TonConfig::from_json(&body)
+}
+
+impl LiteServerConfig {
+    pub fn new(ip: Ipv4Addr, port: u16, key: &str) -> LiteServerConfig {
+        LiteServerConfig {
+            ip: u32::from(ip) as i32,
+            port,
+            id: LiteServerId {
+                key_type: PUBLIC_KEY_TYPE.to_string(),
+                key: key.to_string(),
+            },
+        }
+    }
+
+    pub fn ip_addr(&self) -> Ipv4Addr {
+        Ipv4Addr::from(self.ip as u32)
+    }
+}
+
+/// Returns the json of the embedded config of the network.
+pub fn embedded_config(network: Network) -> Result<&'static str, TonConfigError> {
+    match network {
+        Network::Mainnet => Ok(MAINNET_CONFIG),
+        Network::Testnet => Ok(TESTNET_CONFIG),
+        Network::Custom(global_id) => Err(TonConfigError::InvalidConfig(format!(
+            "No embedded config for network {}",
+            global_id
+        ))),
+    }
+}
+
+/// Builder of a [`TonConfig`] based on one of the embedded configs or on a custom one.
+///
+/// Client options that are not part of the global config, such as archive node checks and
+/// retry intervals, are set on [`TonClientBuilder`].
+///
+/// [`TonClientBuilder`]: crate::client::TonClientBuilder
+pub struct TonConfigBuilder {
+    config: TonConfig,
+}
+
+impl TonConfigBuilder {
+    pub fn new(base: TonConfig) -> TonConfigBuilder {
+        TonConfigBuilder { config: base }
+    }
+
+    pub fn mainnet() -> TonConfigBuilder {
+        TonConfigBuilder::new(TonConfig::mainnet())
+    }
+
+    pub fn testnet() -> TonConfigBuilder {
+        TonConfigBuilder::new(TonConfig::testnet())
+    }
+
+    pub fn for_network(network: Network) -> Result<TonConfigBuilder, TonConfigError> {
+        Ok(TonConfigBuilder::new(TonConfig::for_network(network)?))
+    }
+
+    /// Replaces the liteservers of the base config.
+    pub fn with_liteservers(&mut self, liteservers: Vec<LiteServerConfig>) -> &mut Self {
+        self.config.liteservers = liteservers;
+        self
+    }
+
+    /// Adds a liteserver to the list, skipping it if it is already present.
+    pub fn add_liteserver(&mut self, liteserver: LiteServerConfig) -> &mut Self {
+        if !self.config.liteservers.contains(&liteserver) {
+            self.config.liteservers.push(liteserver);
+        }
+        self
+    }
+
+    /// Keeps only the liteservers matching the predicate.
+    pub fn retain_liteservers<F>(&mut self, f: F) -> &mut Self
+    where
+        F: FnMut(&LiteServerConfig) -> bool,
+    {
+        self.config.liteservers.retain(f);
+        self
+    }
+
+    /// Overrides the block the client starts syncing from.
+    pub fn with_init_block(&mut self, init_block: &BlockIdExt) -> &mut Self {
+        self.config.validator.init_block = init_block.clone();
+        self
+    }
+
+    pub fn build(&self) -> Result<TonConfig, TonConfigError> {
+        self.config.validate()?;
+        Ok(self.config.clone())
+    }
+}
+
+fn default_config_type() -> String {
+    CONFIG_TYPE.to_string()
+}
+
+fn default_validator_config_type() -> String {
+    VALIDATOR_CONFIG_TYPE.to_string()
+}
+
+fn default_public_key_type() -> String {
+    PUBLIC_KEY_TYPE.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use crate::config::{LiteServerConfig, TonConfig, TonConfigBuilder, MAINNET_CONFIG};
+    use crate::network::Network;
+
+    #[test]
+    fn embedded_configs_are_valid() -> anyhow::Result<()> {
+        let mainnet = TonConfig::mainnet();
+        let testnet = TonConfig::testnet();
+        assert_ne!(mainnet.validator.zero_state, testnet.validator.zero_state);
+        let reparsed = TonConfig::from_json(&mainnet.to_json()?)?;
+        assert_eq!(reparsed, mainnet);
+        let original: serde_json::Value = serde_json::from_str(MAINNET_CONFIG)?;
+        let serialized: serde_json::Value = serde_json::from_str(&mainnet.to_json()?)?;
+        assert_eq!(original, serialized);
+        assert_eq!(TonConfig::for_network(Network::Mainnet)?, mainnet);
+        assert_eq!(TonConfig::for_network(Network::Testnet)?, testnet);
+        assert!(TonConfig::for_network(Network::Custom(42)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn config_builder_works() -> anyhow::Result<()> {
+        let liteserver = LiteServerConfig::new(
+            Ipv4Addr::new(5, 9, 10, 47),
+            19949,
+            "n4VDnSCUuSpjnCyUk9e3QOOd6o0ItSWYbTnW3Wnn8wk=",
+        );
+        assert_eq!(liteserver.ip, 84478511);
+        assert_eq!(liteserver.ip_addr(), Ipv4Addr::new(5, 9, 10, 47));
+
+        let mut init_block = TonConfig::mainnet().validator.init_block;
+        init_block.seqno += 1;
+        let config = TonConfigBuilder::mainnet()
+            .with_liteservers(vec![])
+            .add_liteserver(liteserver.clone())
+            .add_liteserver(liteserver.clone())
+            .with_init_block(&init_block)
+            .build()?;
+        assert_eq!(config.liteservers, vec![liteserver]);
+        assert_eq!(config.validator.init_block, init_block);
+
+        assert!(TonConfigBuilder::mainnet()
+            .retain_liteservers(|_| false)
+            .build()
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn check_update_works() -> anyhow::Result<()> {
+        let mainnet = TonConfig::mainnet();
+        let mut update = mainnet.clone();
+        update.liteservers.pop();
+        update.validator.init_block.seqno += 1;
+        assert!(mainnet.check_update(&update).is_ok());
+        assert!(update.check_update(&mainnet).is_err());
+
+        let mut update = mainnet.clone();
+        update.validator.init_block.root_hash = mainnet.validator.zero_state.root_hash.clone();
+        assert!(mainnet.check_update(&update).is_err());
+
+        let mut update = mainnet.clone();
+        update.validator.hardforks.clear();
+        assert!(mainnet.check_update(&update).is_err());
+
+        assert!(mainnet.check_update(&TonConfig::testnet()).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,68 @@
+//! Constants shared by message builders and decoders: op codes of the standard contracts,
+//! amounts of toncoins usually attached to standard operations and workchain ids.
+
+#[cfg(feature = "std")]
+use lazy_static::lazy_static;
+#[cfg(feature = "std")]
+use num_bigint::BigUint;
+#[cfg(feature = "std")]
+use num_traits::Zero;
+
+#[cfg(feature = "std")]
+lazy_static! {
+    pub static ref ZERO_COINS: BigUint = BigUint::zero();
+}
+
+/// Op code of a simple transfer carrying a text comment.
+pub const TEXT_COMMENT: u32 = 0;
+
+/// Prefix of the body of a bounced message, followed by the first bits of the original body.
+pub const BOUNCED_MESSAGE: u32 = 0xffffffff;
+
+// Constants from jetton standart
+// https://github.com/ton-blockchain/TEPs/blob/master/text/0074-jettons-standard.md
+
+// crc32('transfer query_id:uint64 amount:VarUInteger 16 destination:MsgAddress response_destination:MsgAddress custom_payload:Maybe ^Cell forward_ton_amount:VarUInteger 16 forward_payload:Either Cell ^Cell = InternalMsgBody') = 0x8f8a7ea5 & 0x7fffffff = 0xf8a7ea5
+// crc32('transfer_notification query_id:uint64 amount:VarUInteger 16 sender:MsgAddress forward_payload:Either Cell ^Cell = InternalMsgBody') = 0xf362d09c & 0x7fffffff = 0x7362d09c
+// crc32('excesses query_id:uint64 = InternalMsgBody') = 0x553276db | 0x80000000 = 0xd53276db
+// crc32('burn query_id:uint64 amount:VarUInteger 16 response_destination:MsgAddress custom_payload:Maybe ^Cell = InternalMsgBody') = 0x595f07bc & 0x7fffffff = 0x595f07bc
+// crc32('internal_transfer query_id:uint64 amount:VarUInteger 16 from:MsgAddress response_address:MsgAddress forward_ton_amount:VarUInteger 16 forward_payload:Either Cell ^Cell = InternalMsgBody') = 0x978d4519 & 0x7fffffff = 0x178d4519
+// crc32('burn_notification query_id:uint64 amount:VarUInteger 16 sender:MsgAddress response_destination:MsgAddress = InternalMsgBody') = 0x7bdd97de & 0x7fffffff = 0x7bdd97de
+
+pub const JETTON_TRANSFER: u32 = 0xf8a7ea5;
+pub const JETTON_TRANSFER_NOTIFICATION: u32 = 0x7362d09c;
+pub const JETTON_INTERNAL_TRANSFER: u32 = 0x178d4519;
+pub const JETTON_EXCESSES: u32 = 0xd53276db;
+pub const JETTON_BURN: u32 = 0x595f07bc;
+pub const JETTON_BURN_NOTIFICATION: u32 = 0x7bdd97de;
+
+// Constants from nft standard
+// https://github.com/ton-blockchain/TEPs/blob/master/text/0062-nft-standard.md
+
+// crc32('transfer query_id:uint64 new_owner:MsgAddress response_destination:MsgAddress custom_payload:Maybe ^Cell forward_amount:VarUInteger 16 forward_payload:Either Cell ^Cell = InternalMsgBody') = 0x5fcc3d14 & 0x7fffffff = 0x5fcc3d14
+// crc32('ownership_assigned query_id:uint64 prev_owner:MsgAddress forward_payload:Either Cell ^Cell = InternalMsgBody') = 0x85138d91 & 0x7fffffff = 0x05138d91
+// crc32('get_static_data query_id:uint64 = InternalMsgBody') = 0x2fcb26a2 & 0x7fffffff = 0x2fcb26a2
+// crc32('excesses query_id:uint64 = InternalMsgBody') = 0x553276db | 0x80000000 = 0xd53276db
+// crc32('report_static_data query_id:uint64 index:uint256 collection:MsgAddress = InternalMsgBody') = 0x0b771735 | 0x80000000 = 0x8b771735
+
+pub const NFT_TRANSFER: u32 = 0x5fcc3d14;
+pub const NFT_OWNERSHIP_ASSIGNED: u32 = 0x05138d91;
+pub const NFT_EXCESSES: u32 = 0xd53276db;
+pub const NFT_GET_STATIC_DATA: u32 = 0x2fcb26a2;
+pub const NFT_REPORT_STATIC_DATA: u32 = 0x8b771735;
+
+/// Nanotons usually attached to a jetton transfer to pay for the jetton wallets, 0.05 TON.
+pub const JETTON_TRANSFER_AMOUNT: u64 = 50_000_000;
+/// Nanotons usually attached to a jetton burn, 0.05 TON.
+pub const JETTON_BURN_AMOUNT: u64 = 50_000_000;
+/// Nanotons usually attached to an NFT transfer, 0.05 TON.
+pub const NFT_TRANSFER_AMOUNT: u64 = 50_000_000;
+/// Forward amount that is just enough for the recipient to be notified of a transfer.
+pub const NOTIFICATION_FORWARD_AMOUNT: u64 = 1;
+
+pub const MASTERCHAIN_ID: i32 = -1;
+pub const BASECHAIN_ID: i32 = 0;
+
+/// Global ids of the networks, see [`Network`](crate::network::Network).
+pub const MAINNET_GLOBAL_ID: i32 = -239;
+pub const TESTNET_GLOBAL_ID: i32 = -3;
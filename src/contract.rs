@@ -4,24 +4,30 @@ use async_trait::async_trait;
 pub use error::*;
 pub use factory::*;
 pub use interface::*;
+pub use interface_registry::*;
 pub use jetton::*;
 pub use latest_transactions_cache::*;
 pub use nft::*;
+pub use standard_getters::*;
 pub use state::*;
+pub use state_diff::*;
 pub use wallet::*;
 
 use crate::address::TonAddress;
 use crate::client::TonClientInterface;
-use crate::tl::{InternalTransactionId, RawFullAccountState};
+use crate::tl::{BlockIdExt, InternalTransactionId, RawFullAccountState};
 use crate::types::{TonMethodId, TvmStackEntry, TvmSuccess};
 
 mod error;
 mod factory;
 mod interface;
+mod interface_registry;
 mod jetton;
 mod latest_transactions_cache;
 mod nft;
+mod standard_getters;
 mod state;
+mod state_diff;
 mod wallet;
 
 pub struct TonContract {
@@ -46,6 +52,63 @@ impl TonContract {
             .await
     }
 
+    /// Returns the state of this contract at specified block, see
+    /// [`TonContractFactory::get_account_state_at_block`].
+    pub async fn get_account_state_at(
+        &self,
+        block_id: &BlockIdExt,
+    ) -> Result<RawFullAccountState, TonContractError> {
+        self.factory
+            .get_account_state_at_block(&self.address, block_id)
+            .await
+    }
+
+    pub async fn get_state_at(
+        &self,
+        block_id: &BlockIdExt,
+    ) -> Result<TonContractState, TonContractError> {
+        self.factory
+            .get_contract_state_at_block(&self.address, block_id)
+            .await
+    }
+
+    /// Runs the get method by tonlib against the state of the contract at specified block, loaded
+    /// by [`TonClientInterface::smc_load_at_block`].
+    pub async fn run_get_method_at<M, S>(
+        &self,
+        block_id: &BlockIdExt,
+        method: M,
+        stack: S,
+    ) -> Result<TvmSuccess, TonContractError>
+    where
+        M: Into<TonMethodId> + Send + Copy,
+        S: AsRef<[TvmStackEntry]> + Send,
+    {
+        let state = self
+            .factory
+            .client()
+            .smc_load_at_block(&self.address, block_id)
+            .await?;
+        run_loaded_get_method(&self.address, &state, method, stack).await
+    }
+
+    /// Emulates the get method against the state of the contract at specified masterchain
+    /// block, see [`TonContractFactory::run_get_method_historical`].
+    pub async fn run_get_method_historical<M, S>(
+        &self,
+        mc_block_id: &BlockIdExt,
+        method: M,
+        stack: S,
+    ) -> Result<TvmSuccess, TonContractError>
+    where
+        M: Into<TonMethodId> + Send + Copy,
+        S: AsRef<[TvmStackEntry]> + Send,
+    {
+        self.factory
+            .run_get_method_historical(&self.address, mc_block_id, method, stack)
+            .await
+    }
+
     pub async fn get_state(&self) -> Result<TonContractState, TonContractError> {
         let r = self
             .factory
@@ -7,6 +7,7 @@ use crate::address::TonAddress;
 use crate::cell::TonCellError;
 use crate::client::TonClientError;
 use crate::emulator::TvmEmulatorError;
+use crate::message::TonMessageError;
 use crate::tl::TvmStackError;
 use crate::types::{StackParseError, TonMethodId, TvmStackEntry};
 
@@ -34,6 +35,9 @@ pub enum TonContractError {
         error: TvmEmulatorError,
     },
 
+    #[error("TonMessageError ({0})")]
+    MessageError(#[from] TonMessageError),
+
     #[error("Illegal argument ({0})")]
     IllegalArgument(String),
 
@@ -5,18 +5,25 @@ use std::time::Duration;
 pub use builder::*;
 #[cfg(feature = "state_cache")]
 pub use cache::*;
+use futures::try_join;
+#[cfg(feature = "state_cache")]
+pub use get_method_cache::*;
 pub use library_loader::*;
 pub use library_provider::*;
 use tokio::sync::OnceCell;
 
 use crate::address::TonAddress;
-use crate::client::{TonClient, TonClientError, TonClientInterface};
+use crate::client::{TonBlockFunctions, TonClient, TonClientError, TonClientInterface};
 use crate::contract::{LoadedSmcState, TonContract, TonContractError, TonContractState};
-use crate::tl::{ConfigInfo, InternalTransactionId, RawFullAccountState};
+use crate::emulator::TvmEmulatorC7Builder;
+use crate::tl::{BlockIdExt, ConfigInfo, InternalTransactionId, RawFullAccountState};
+use crate::types::{TonMethodId, TvmStackEntry, TvmSuccess};
 
 mod builder;
 #[cfg(feature = "state_cache")]
 mod cache;
+#[cfg(feature = "state_cache")]
+mod get_method_cache;
 mod library_loader;
 mod library_provider;
 
@@ -31,6 +38,8 @@ struct Inner {
     library_provider: LibraryProvider,
     #[cfg(feature = "state_cache")]
     cache: Option<ContractFactoryCache>,
+    #[cfg(feature = "state_cache")]
+    get_method_cache: Option<GetMethodCache>,
 }
 
 impl TonContractFactory {
@@ -49,6 +58,7 @@ impl TonContractFactory {
         txid_cache_time_to_live: Duration,
         presync_blocks: i32,
         library_provider: LibraryProvider,
+        get_method_cache: Option<GetMethodCache>,
     ) -> Result<TonContractFactory, TonContractError> {
         let cache = if with_cache {
             let cache = ContractFactoryCache::new(
@@ -58,6 +68,7 @@ impl TonContractFactory {
                 txid_cache_capacity,
                 txid_cache_time_to_live,
                 presync_blocks,
+                get_method_cache.clone(),
             )
             .await?;
             Some(cache)
@@ -70,6 +81,7 @@ impl TonContractFactory {
             config_info,
             cache,
             library_provider,
+            get_method_cache,
         };
 
         Ok(TonContractFactory {
@@ -109,6 +121,13 @@ impl TonContractFactory {
         self.inner.library_provider.clone()
     }
 
+    /// Returns the cache of get method results, if enabled by
+    /// [`TonContractFactoryBuilder::with_get_method_cache`].
+    #[cfg(feature = "state_cache")]
+    pub fn get_method_cache(&self) -> Option<&GetMethodCache> {
+        self.inner.get_method_cache.as_ref()
+    }
+
     pub fn get_contract(&self, address: &TonAddress) -> TonContract {
         TonContract::new(self, address)
     }
@@ -150,6 +169,21 @@ impl TonContractFactory {
         Ok(state)
     }
 
+    /// Returns the state of the account at specified block through the client of the factory,
+    /// see [`TonClientInterface::get_raw_account_state_at_block`].
+    pub async fn get_account_state_at_block(
+        &self,
+        address: &TonAddress,
+        block_id: &BlockIdExt,
+    ) -> Result<RawFullAccountState, TonContractError> {
+        let state = self
+            .inner
+            .client
+            .get_raw_account_state_at_block(address, block_id)
+            .await?;
+        Ok(state)
+    }
+
     #[cfg(feature = "state_cache")]
     pub async fn get_smc_state_by_transaction(
         &self,
@@ -204,6 +238,49 @@ impl TonContractFactory {
         Ok(contract_state)
     }
 
+    pub async fn get_contract_state_at_block(
+        &self,
+        address: &TonAddress,
+        block_id: &BlockIdExt,
+    ) -> Result<TonContractState, TonContractError> {
+        let account_state = Arc::new(self.get_account_state_at_block(address, block_id).await?);
+        let contract_state = TonContractState::new(self, address, &account_state);
+        Ok(contract_state)
+    }
+
+    /// Emulates the get method against the state of the contract at specified masterchain
+    /// block, with the config, the time and the balance as of the block, so the result doesn't
+    /// depend on the current state of the network.
+    ///
+    /// The state and the config are proven by tonlib, so liteservers without archives can
+    /// answer as long as they keep the state of the block.
+    pub async fn run_get_method_historical<M, S>(
+        &self,
+        address: &TonAddress,
+        mc_block_id: &BlockIdExt,
+        method: M,
+        stack: S,
+    ) -> Result<TvmSuccess, TonContractError>
+    where
+        M: Into<TonMethodId> + Send + Copy,
+        S: AsRef<[TvmStackEntry]> + Send,
+    {
+        let client = self.client();
+        let (account_state, header, config) = try_join!(
+            client.get_raw_account_state_in_shard(address, mc_block_id),
+            client.get_block_header(mc_block_id),
+            client.get_config_all_at_block(0, mc_block_id),
+        )?;
+        let account_state = Arc::new(account_state);
+        let balance = account_state.balance.max(0) as u64;
+        let c7 = TvmEmulatorC7Builder::new(address, config.config.bytes.as_slice(), balance)
+            .with_unix_time(header.gen_utime as u64)
+            .build();
+        TonContractState::new(self, address, &account_state)
+            .emulate_get_method_with_c7(c7, method, stack)
+            .await
+    }
+
     #[cfg(feature = "state_cache")]
     pub fn get_factory_cache_stats(&self) -> ContractFactoryCacheStats {
         if let Some(cache) = &self.inner.cache {
@@ -2,9 +2,13 @@ use std::sync::Arc;
 #[cfg(feature = "state_cache")]
 use std::time::Duration;
 
+#[cfg(feature = "state_cache")]
+use super::GetMethodCache;
 use super::{DefaultLibraryLoader, LibraryProvider};
 use crate::client::TonClient;
 use crate::contract::{TonContractError, TonContractFactory};
+#[cfg(feature = "state_cache")]
+use crate::types::TonMethodId;
 
 #[cfg(feature = "state_cache")]
 pub struct TonContractFactoryBuilder {
@@ -16,6 +20,7 @@ pub struct TonContractFactoryBuilder {
     txid_cache_time_to_live: Duration,
     presync_blocks: i32,
     library_provider: LibraryProvider,
+    get_method_cache: Option<GetMethodCache>,
 }
 
 #[cfg(feature = "state_cache")]
@@ -40,6 +45,7 @@ impl TonContractFactoryBuilder {
             txid_cache_time_to_live: Duration::default(),
             presync_blocks: Self::DEFAULT_PRESYNC_BLOCKS,
             library_provider,
+            get_method_cache: None,
         }
     }
 
@@ -82,6 +88,27 @@ impl TonContractFactoryBuilder {
         self
     }
 
+    /// Enables caching of the results of `methods` by the state of the account, the method and
+    /// its arguments.
+    ///
+    /// Only list methods whose result depends on the account state and the arguments alone,
+    /// see [`GetMethodCache`]. The results of an account are invalidated on its new
+    /// transactions only when the account state cache is enabled too, otherwise they're
+    /// replaced once a newer state is loaded or expire after `time_to_live`.
+    pub fn with_get_method_cache<I>(
+        &mut self,
+        capacity: u64,
+        time_to_live: Duration,
+        methods: I,
+    ) -> &mut Self
+    where
+        I: IntoIterator,
+        I::Item: Into<TonMethodId>,
+    {
+        self.get_method_cache = Some(GetMethodCache::new(capacity, time_to_live, methods));
+        self
+    }
+
     pub fn presync_blocks(&mut self, presync_blocks: i32) -> &mut Self {
         self.presync_blocks = presync_blocks;
         self
@@ -97,6 +124,7 @@ impl TonContractFactoryBuilder {
             self.txid_cache_time_to_live,
             self.presync_blocks,
             self.library_provider.clone(),
+            self.get_method_cache.clone(),
         )
         .await
     }
@@ -11,7 +11,7 @@ use crate::address::TonAddress;
 use crate::client::{
     BlockStream, BlockStreamItem, TonBlockFunctions, TonClient, TonClientInterface,
 };
-use crate::contract::{LoadedSmcState, TonContractError};
+use crate::contract::{GetMethodCache, LoadedSmcState, TonContractError};
 use crate::tl::{InternalTransactionId, RawFullAccountState};
 
 type TxIdCache = Cache<TonAddress, Arc<InternalTransactionId>>;
@@ -33,6 +33,7 @@ impl ContractFactoryCache {
         txid_cache_capacity: u64,
         txid_state_cache_time_to_live: Duration,
         presync_blocks: i32,
+        get_method_cache: Option<GetMethodCache>,
     ) -> Result<ContractFactoryCache, TonContractError> {
         let inner = Inner {
             client: client.clone(),
@@ -46,6 +47,7 @@ impl ContractFactoryCache {
                 .time_to_live(txid_state_cache_time_to_live)
                 .build(),
             presync_blocks,
+            get_method_cache,
             account_state_cache_counters: ContractFactoryCacheCounters::default(),
 
             tx_id_cache_counters: ContractFactoryCacheCounters::default(),
@@ -216,6 +218,7 @@ struct Inner {
     tx_id_cache: TxIdCache,
     account_state_cache: AccountStateCache,
     presync_blocks: i32,
+    get_method_cache: Option<GetMethodCache>,
     tx_id_cache_counters: ContractFactoryCacheCounters,
     account_state_cache_counters: ContractFactoryCacheCounters,
 }
@@ -265,6 +268,9 @@ impl Inner {
             .insert(address.clone(), Arc::new(tx_id.clone()))
             .await;
         join!(self.account_state_cache.invalidate(&address),);
+        if let Some(get_method_cache) = self.get_method_cache.as_ref() {
+            get_method_cache.invalidate_address(&address);
+        }
     }
 }
 
@@ -0,0 +1,334 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use moka::future::Cache;
+use num_bigint::BigInt;
+
+use crate::address::TonAddress;
+use crate::cell::TonCellError;
+use crate::contract::TonContractError;
+use crate::tl::RawFullAccountState;
+use crate::types::{TonMethodId, TvmStackEntry, TvmSuccess};
+
+/// Key of a get method result: the account, its last transaction and balance, the hashes of
+/// its code and data, the method and its arguments.
+///
+/// The address and the balance are part of the key because they're passed to the method in
+/// `c7`. The last transaction makes the entries of an account stale on its new transactions
+/// even when they aren't invalidated explicitly.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct GetMethodCacheKey {
+    address: TonAddress,
+    last_transaction_lt: i64,
+    last_transaction_hash: Vec<u8>,
+    balance: i64,
+    code_hash: Vec<u8>,
+    data_hash: Vec<u8>,
+    method_id: i32,
+    stack: Vec<StackEntryKey>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum StackEntryKey {
+    Null,
+    Nan,
+    Int(BigInt),
+    Cell(Vec<u8>),
+    Slice {
+        cell_hash: Vec<u8>,
+        start_bit: usize,
+        end_bit: usize,
+        start_ref: usize,
+        end_ref: usize,
+    },
+}
+
+impl GetMethodCacheKey {
+    /// Returns the key of the call, or `None` if the result can't be cached, i.e. the
+    /// account isn't active or the stack has unsupported entries.
+    pub fn new(
+        address: &TonAddress,
+        account_state: &RawFullAccountState,
+        method: &TonMethodId,
+        stack: &[TvmStackEntry],
+    ) -> Result<Option<GetMethodCacheKey>, TonCellError> {
+        let Some(state_init) = account_state.state_init()? else {
+            return Ok(None);
+        };
+        let (Some(code_hash), Some(data_hash)) = (state_init.code_hash()?, state_init.data_hash()?)
+        else {
+            return Ok(None);
+        };
+        let mut stack_key = Vec::with_capacity(stack.len());
+        for entry in stack {
+            let entry_key = match entry {
+                TvmStackEntry::Null => StackEntryKey::Null,
+                TvmStackEntry::Nan => StackEntryKey::Nan,
+                TvmStackEntry::Int64(value) => StackEntryKey::Int(BigInt::from(*value)),
+                TvmStackEntry::Int257(value) => StackEntryKey::Int(value.clone()),
+                TvmStackEntry::Cell(cell) => StackEntryKey::Cell(cell.cell_hash()?),
+                TvmStackEntry::Slice(slice) => StackEntryKey::Slice {
+                    cell_hash: slice.cell.cell_hash()?,
+                    start_bit: slice.start_bit,
+                    end_bit: slice.end_bit,
+                    start_ref: slice.start_ref,
+                    end_ref: slice.end_ref,
+                },
+                TvmStackEntry::Unsupported => return Ok(None),
+            };
+            stack_key.push(entry_key);
+        }
+        Ok(Some(GetMethodCacheKey {
+            address: address.clone(),
+            last_transaction_lt: account_state.last_transaction_id.lt,
+            last_transaction_hash: account_state.last_transaction_id.hash.clone(),
+            balance: account_state.balance,
+            code_hash,
+            data_hash,
+            // Methods called by name and by id share their results
+            method_id: method.to_id(),
+            stack: stack_key,
+        }))
+    }
+
+    pub fn address(&self) -> &TonAddress {
+        &self.address
+    }
+}
+
+/// Memoization of successful get method results, so repeated calls against unchanged
+/// accounts skip the liteserver or the emulator.
+///
+/// Caching is opt-in per method: only the results of the methods passed to
+/// [`GetMethodCache::new`] are cached. Methods whose result depends on the current time or on
+/// the random seed, e.g. vesting or auction contracts reading `NOW`, must not be listed, as
+/// the key doesn't cover them.
+///
+/// It's enabled by [`TonContractFactoryBuilder::with_get_method_cache`] and kept up to date
+/// by the block loop of the state cache, which invalidates the entries of the accounts with
+/// new transactions.
+///
+/// [`TonContractFactoryBuilder::with_get_method_cache`]: crate::contract::TonContractFactoryBuilder::with_get_method_cache
+#[derive(Clone)]
+pub struct GetMethodCache {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    cache: Cache<GetMethodCacheKey, Arc<TvmSuccess>>,
+    methods: HashSet<i32>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl GetMethodCache {
+    pub fn new<I>(capacity: u64, time_to_live: Duration, methods: I) -> GetMethodCache
+    where
+        I: IntoIterator,
+        I::Item: Into<TonMethodId>,
+    {
+        let cache = Cache::builder()
+            .max_capacity(capacity)
+            .time_to_live(time_to_live)
+            .support_invalidation_closures()
+            .build();
+        GetMethodCache {
+            inner: Arc::new(Inner {
+                cache,
+                methods: methods
+                    .into_iter()
+                    .map(|method| method.into().to_id())
+                    .collect(),
+                hits: AtomicU64::new(0),
+                misses: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Returns `true` if the results of the method are cached.
+    pub fn caches(&self, method: &TonMethodId) -> bool {
+        self.inner.methods.contains(&method.to_id())
+    }
+
+    pub async fn get(&self, key: &GetMethodCacheKey) -> Option<TvmSuccess> {
+        let result = self.inner.cache.get(key).await;
+        let counter = match result {
+            Some(_) => &self.inner.hits,
+            None => &self.inner.misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        result.map(|result| result.as_ref().clone())
+    }
+
+    pub async fn insert(&self, key: GetMethodCacheKey, result: &TvmSuccess) {
+        self.inner.cache.insert(key, Arc::new(result.clone())).await;
+    }
+
+    /// Returns the cached result of the call, or runs it and caches its result if it
+    /// succeeds. Calls without a key, see [`GetMethodCacheKey::new`], and calls of methods
+    /// that aren't cached are always run.
+    pub async fn get_or_run<F>(
+        &self,
+        key: Option<GetMethodCacheKey>,
+        run: F,
+    ) -> Result<TvmSuccess, TonContractError>
+    where
+        F: std::future::Future<Output = Result<TvmSuccess, TonContractError>>,
+    {
+        let Some(key) = key.filter(|key| self.inner.methods.contains(&key.method_id)) else {
+            return run.await;
+        };
+        if let Some(result) = self.get(&key).await {
+            return Ok(result);
+        }
+        let result = run.await?;
+        self.insert(key, &result).await;
+        Ok(result)
+    }
+
+    /// Invalidates the results of the account, e.g. after a new transaction changed its
+    /// balance or state.
+    pub fn invalidate_address(&self, address: &TonAddress) {
+        let address = address.clone();
+        let result = self
+            .inner
+            .cache
+            .invalidate_entries_if(move |key, _| key.address == address);
+        if let Err(e) = result {
+            log::warn!("[GetMethodCache] Could not invalidate entries: {}", e);
+        }
+    }
+
+    pub fn invalidate_all(&self) {
+        self.inner.cache.invalidate_all();
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.inner.hits.load(Ordering::Relaxed)
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.inner.misses.load(Ordering::Relaxed)
+    }
+
+    pub fn entry_count(&self) -> u64 {
+        self.inner.cache.entry_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::executor::block_on;
+
+    use crate::address::TonAddress;
+    use crate::cell::{BagOfCells, CellBuilder};
+    use crate::contract::{GetMethodCache, GetMethodCacheKey};
+    use crate::tl::RawFullAccountState;
+    use crate::types::{TonMethodId, TvmStackEntry, TvmSuccess};
+
+    fn account_state(seqno: u32) -> anyhow::Result<RawFullAccountState> {
+        let state = serde_json::json!({
+            "balance": "1000",
+            "code": "",
+            "data": "",
+            "last_transaction_id": {"lt": "1", "hash": ""},
+            "block_id": {
+                "workchain": 0, "shard": "0", "seqno": 1, "root_hash": "", "file_hash": ""
+            },
+            "frozen_hash": "",
+            "sync_utime": "0",
+        });
+        let mut state: RawFullAccountState = serde_json::from_value(state)?;
+        let code = CellBuilder::new().store_u32(32, 1)?.build()?;
+        let data = CellBuilder::new().store_u32(32, seqno)?.build()?;
+        state.code = BagOfCells::from_root(code).serialize(false)?;
+        state.data = BagOfCells::from_root(data).serialize(false)?;
+        Ok(state)
+    }
+
+    fn success(value: i64) -> TvmSuccess {
+        TvmSuccess {
+            vm_log: None,
+            vm_exit_code: 0,
+            stack: vec![TvmStackEntry::Int64(value)],
+            missing_library: None,
+            gas_used: 100,
+        }
+    }
+
+    #[test]
+    fn get_method_cache_works() -> anyhow::Result<()> {
+        let address: TonAddress = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR".parse()?;
+        let state = account_state(1)?;
+        let method = TonMethodId::from("seqno");
+        let key = GetMethodCacheKey::new(&address, &state, &method, &[])?.unwrap();
+        let by_id = TonMethodId::Number(method.to_id());
+        assert_eq!(
+            GetMethodCacheKey::new(&address, &state, &by_id, &[])?,
+            Some(key.clone())
+        );
+        let with_args = [TvmStackEntry::Int64(5)];
+        let int257_args = [TvmStackEntry::Int257(5.into())];
+        assert_eq!(
+            GetMethodCacheKey::new(&address, &state, &method, &with_args)?,
+            GetMethodCacheKey::new(&address, &state, &method, &int257_args)?
+        );
+        assert_ne!(
+            GetMethodCacheKey::new(&address, &account_state(2)?, &method, &[])?,
+            Some(key.clone())
+        );
+        let mut next_state = state.clone();
+        next_state.last_transaction_id.lt += 1;
+        assert_ne!(
+            GetMethodCacheKey::new(&address, &next_state, &method, &[])?,
+            Some(key.clone())
+        );
+        let mut next_state = state.clone();
+        next_state.balance += 1;
+        assert_ne!(
+            GetMethodCacheKey::new(&address, &next_state, &method, &[])?,
+            Some(key.clone())
+        );
+        assert!(
+            GetMethodCacheKey::new(&address, &state, &method, &[TvmStackEntry::Unsupported])?
+                .is_none()
+        );
+
+        let cache = GetMethodCache::new(100, Duration::from_secs(60), ["seqno"]);
+        assert!(cache.caches(&by_id));
+        assert!(!cache.caches(&TonMethodId::from("get_public_key")));
+        block_on(async {
+            let result = cache
+                .get_or_run(Some(key.clone()), async { Ok(success(1)) })
+                .await?;
+            assert_eq!(result.stack, [TvmStackEntry::Int64(1)]);
+            // The cached result is returned instead of running the method again
+            let result = cache
+                .get_or_run(Some(key.clone()), async { Ok(success(2)) })
+                .await?;
+            assert_eq!(result.stack, [TvmStackEntry::Int64(1)]);
+            assert_eq!((cache.hits(), cache.misses()), (1, 1));
+
+            cache.invalidate_address(&address);
+            let result = cache
+                .get_or_run(Some(key.clone()), async { Ok(success(3)) })
+                .await?;
+            assert_eq!(result.stack, [TvmStackEntry::Int64(3)]);
+
+            // Methods that aren't opted in are always run
+            let other = TonMethodId::from("get_public_key");
+            let other_key = GetMethodCacheKey::new(&address, &state, &other, &[])?;
+            for value in [4, 5] {
+                let result = cache
+                    .get_or_run(other_key.clone(), async move { Ok(success(value)) })
+                    .await?;
+                assert_eq!(result.stack, [TvmStackEntry::Int64(value)]);
+            }
+            anyhow::Ok(())
+        })
+    }
+}
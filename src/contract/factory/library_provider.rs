@@ -1,18 +1,35 @@
 use std::sync::Arc;
 
+use moka::future::Cache;
+use sha2::{Digest, Sha256};
+
 use super::{ContractLibraryDict, LibraryLoader};
 use crate::address::TonAddress;
 use crate::contract::TonContractError;
 use crate::tl::RawFullAccountState;
 
+pub const DEFAULT_LIBRARY_CACHE_CAPACITY: u64 = 10_000;
+
+/// Provides libraries used by contract code to the emulator.
+///
+/// Libraries are loaded once per distinct code and cached by the hash of the code boc.
+/// Failed loads are not cached.
 #[derive(Clone)]
 pub struct LibraryProvider {
     loader: Arc<dyn LibraryLoader>,
+    cache: Cache<Vec<u8>, Arc<ContractLibraryDict>>,
 }
 
 impl LibraryProvider {
     pub fn new(loader: Arc<dyn LibraryLoader>) -> LibraryProvider {
-        LibraryProvider { loader }
+        Self::with_cache_capacity(loader, DEFAULT_LIBRARY_CACHE_CAPACITY)
+    }
+
+    pub fn with_cache_capacity(loader: Arc<dyn LibraryLoader>, capacity: u64) -> LibraryProvider {
+        LibraryProvider {
+            loader,
+            cache: Cache::new(capacity),
+        }
     }
 
     pub async fn get_contract_libraries(
@@ -29,7 +46,79 @@ impl LibraryProvider {
         address: &TonAddress,
         code: &[u8],
     ) -> Result<Arc<ContractLibraryDict>, TonContractError> {
-        // TODO cache
-        self.loader.load_contract_libraries(address, code).await
+        let code_hash = Sha256::digest(code).to_vec();
+        if let Some(libraries) = self.cache.get(&code_hash).await {
+            return Ok(libraries);
+        }
+        let libraries = self.loader.load_contract_libraries(address, code).await?;
+        self.cache.insert(code_hash, libraries.clone()).await;
+        Ok(libraries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+
+    use crate::address::TonAddress;
+    use crate::contract::{ContractLibraryDict, LibraryLoader, LibraryProvider, TonContractError};
+
+    #[derive(Default)]
+    struct CountingLoader {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LibraryLoader for CountingLoader {
+        async fn load_contract_libraries(
+            &self,
+            address: &TonAddress,
+            code: &[u8],
+        ) -> Result<Arc<ContractLibraryDict>, TonContractError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if code.is_empty() {
+                return Err(TonContractError::LibraryNotFound {
+                    address: address.clone(),
+                    missing_library: String::new(),
+                });
+            }
+            Ok(Arc::new(ContractLibraryDict {
+                dict_boc: code.to_vec(),
+                keys: vec![],
+            }))
+        }
+    }
+
+    #[test]
+    fn library_provider_caches_libraries() -> anyhow::Result<()> {
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        let loader = Arc::new(CountingLoader::default());
+        let provider = LibraryProvider::new(loader.clone());
+        let address = TonAddress::null();
+        runtime.block_on(async {
+            for _ in 0..3 {
+                let libraries = provider
+                    .get_libraries_by_contract_code(&address, &[1, 2, 3])
+                    .await?;
+                assert_eq!(libraries.dict_boc, vec![1, 2, 3]);
+            }
+            provider
+                .get_libraries_by_contract_code(&address, &[4])
+                .await?;
+            assert!(provider
+                .get_libraries_by_contract_code(&address, &[])
+                .await
+                .is_err());
+            assert!(provider
+                .get_libraries_by_contract_code(&address, &[])
+                .await
+                .is_err());
+            anyhow::Ok(())
+        })?;
+        assert_eq!(loader.calls.load(Ordering::SeqCst), 4);
+        Ok(())
     }
 }
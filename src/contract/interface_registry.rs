@@ -0,0 +1,227 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use lazy_static::lazy_static;
+
+use crate::cell::{BagOfCells, TonCellError};
+use crate::contract::{TonContractError, TonContractInterface};
+use crate::tl::RawFullAccountState;
+use crate::types::TvmStackEntry;
+use crate::wallet::WalletVersion;
+
+/// Interface implemented by a contract.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ContractInterface {
+    /// Wallet, with the version known if the code is one of the standard wallet codes.
+    Wallet(Option<WalletVersion>),
+    JettonMaster,
+    JettonWallet,
+    NftCollection,
+    NftItem,
+    Multisig,
+    /// Interface of a contract registered by the user.
+    Other(Cow<'static, str>),
+}
+
+/// Contract code known to the registry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterfaceDescriptor {
+    pub name: Cow<'static, str>,
+    pub interfaces: Vec<ContractInterface>,
+}
+
+impl InterfaceDescriptor {
+    pub fn new<N: Into<Cow<'static, str>>>(
+        name: N,
+        interfaces: Vec<ContractInterface>,
+    ) -> InterfaceDescriptor {
+        InterfaceDescriptor {
+            name: name.into(),
+            interfaces,
+        }
+    }
+}
+
+/// Get methods probed for contracts with unknown code, together with the number of stack
+/// entries they return, if it's fixed by the standard.
+const PROBES: [(&str, Option<usize>, ContractInterface); 5] = [
+    ("get_jetton_data", Some(5), ContractInterface::JettonMaster),
+    ("get_wallet_data", Some(4), ContractInterface::JettonWallet),
+    (
+        "get_collection_data",
+        Some(3),
+        ContractInterface::NftCollection,
+    ),
+    ("get_nft_data", Some(5), ContractInterface::NftItem),
+    ("get_multisig_data", None, ContractInterface::Multisig),
+];
+
+const WALLET_PROBES: [&str; 2] = ["seqno", "get_public_key"];
+
+lazy_static! {
+    /// Registry of the standard wallet codes.
+    pub static ref KNOWN_INTERFACES: InterfaceRegistry = InterfaceRegistry::with_known_contracts();
+}
+
+/// Registry mapping code hashes to the interfaces of the contracts.
+///
+/// Accounts whose code is not registered are classified by probing the get methods of the
+/// standard interfaces.
+#[derive(Debug, Clone, Default)]
+pub struct InterfaceRegistry {
+    by_code_hash: HashMap<[u8; 32], InterfaceDescriptor>,
+}
+
+impl InterfaceRegistry {
+    pub fn new() -> InterfaceRegistry {
+        InterfaceRegistry::default()
+    }
+
+    /// Creates a registry containing the codes of all wallet versions supported by
+    /// [`TonWallet`](crate::wallet::TonWallet).
+    pub fn with_known_contracts() -> InterfaceRegistry {
+        let wallets = [
+            ("wallet_v1r1", WalletVersion::V1R1),
+            ("wallet_v1r2", WalletVersion::V1R2),
+            ("wallet_v1r3", WalletVersion::V1R3),
+            ("wallet_v2r1", WalletVersion::V2R1),
+            ("wallet_v2r2", WalletVersion::V2R2),
+            ("wallet_v3r1", WalletVersion::V3R1),
+            ("wallet_v3r2", WalletVersion::V3R2),
+            ("wallet_v4r1", WalletVersion::V4R1),
+            ("wallet_v4r2", WalletVersion::V4R2),
+            ("highload_v1r1", WalletVersion::HighloadV1R1),
+            ("highload_v1r2", WalletVersion::HighloadV1R2),
+            ("highload_v2", WalletVersion::HighloadV2),
+            ("highload_v2r1", WalletVersion::HighloadV2R1),
+            ("highload_v2r2", WalletVersion::HighloadV2R2),
+        ];
+        let mut registry = InterfaceRegistry::new();
+        for (name, version) in wallets {
+            // Wallet codes are bundled with the crate and always parse
+            let code_hash = version.code().and_then(|c| c.cell_hash_array()).unwrap();
+            let interfaces = vec![ContractInterface::Wallet(Some(version))];
+            registry.register(code_hash, InterfaceDescriptor::new(name, interfaces));
+        }
+        registry
+    }
+
+    /// Registers the code hash, replacing the previous descriptor of the same hash.
+    pub fn register(&mut self, code_hash: [u8; 32], descriptor: InterfaceDescriptor) -> &mut Self {
+        self.by_code_hash.insert(code_hash, descriptor);
+        self
+    }
+
+    pub fn get(&self, code_hash: &[u8; 32]) -> Option<&InterfaceDescriptor> {
+        self.by_code_hash.get(code_hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_code_hash.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_code_hash.is_empty()
+    }
+
+    /// Looks up the code of the account, returning `None` for uninitialized accounts and
+    /// unknown codes.
+    pub fn lookup_account(
+        &self,
+        account_state: &RawFullAccountState,
+    ) -> Result<Option<&InterfaceDescriptor>, TonCellError> {
+        if account_state.code.is_empty() {
+            return Ok(None);
+        }
+        let code = BagOfCells::parse(&account_state.code)?;
+        let code_hash = code.single_root()?.cell_hash_array()?;
+        Ok(self.get(&code_hash))
+    }
+
+    /// Returns the interfaces of the contract, looking up its code hash and probing the get
+    /// methods of the standard interfaces if the code is unknown.
+    pub async fn detect_interfaces<C>(
+        &self,
+        contract: &C,
+    ) -> Result<Vec<ContractInterface>, TonContractError>
+    where
+        C: TonContractInterface + Sync,
+    {
+        let account_state = contract.get_account_state().await?;
+        if account_state.code.is_empty() {
+            return Ok(vec![]);
+        }
+        let descriptor =
+            self.lookup_account(&account_state)
+                .map_err(|error| TonContractError::CellError {
+                    method: "detect_interfaces".to_string(),
+                    address: contract.address().clone(),
+                    error,
+                })?;
+        if let Some(descriptor) = descriptor {
+            return Ok(descriptor.interfaces.clone());
+        }
+
+        let mut interfaces = Vec::new();
+        for (method, stack_size, interface) in PROBES {
+            if probe(contract, method, stack_size).await? {
+                interfaces.push(interface);
+            }
+        }
+        let mut is_wallet = true;
+        for method in WALLET_PROBES {
+            is_wallet &= probe(contract, method, Some(1)).await?;
+        }
+        if is_wallet {
+            interfaces.push(ContractInterface::Wallet(None));
+        }
+        Ok(interfaces)
+    }
+}
+
+/// Runs the get method without arguments, returning `false` if it fails or returns
+/// an unexpected number of stack entries.
+async fn probe<C>(
+    contract: &C,
+    method: &'static str,
+    stack_size: Option<usize>,
+) -> Result<bool, TonContractError>
+where
+    C: TonContractInterface + Sync,
+{
+    let stack: Vec<TvmStackEntry> = vec![];
+    match contract.run_get_method(method, &stack).await {
+        Ok(result) => Ok(stack_size.is_none_or(|size| result.stack.len() == size)),
+        Err(TonContractError::TvmRunError { .. })
+        | Err(TonContractError::MethodEmulationError { .. }) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contract::{ContractInterface, InterfaceDescriptor, KNOWN_INTERFACES};
+    use crate::wallet::WalletVersion;
+
+    #[test]
+    fn known_interfaces_contain_wallets() -> anyhow::Result<()> {
+        assert_eq!(KNOWN_INTERFACES.len(), 14);
+        let code_hash = WalletVersion::V4R2.code()?.cell_hash_array()?;
+        let descriptor = KNOWN_INTERFACES.get(&code_hash).unwrap();
+        assert_eq!(descriptor.name, "wallet_v4r2");
+        assert_eq!(
+            descriptor.interfaces,
+            vec![ContractInterface::Wallet(Some(WalletVersion::V4R2))]
+        );
+
+        let mut registry = KNOWN_INTERFACES.clone();
+        let custom = InterfaceDescriptor::new(
+            "my_wallet",
+            vec![ContractInterface::Other("my_interface".into())],
+        );
+        registry.register(code_hash, custom.clone());
+        assert_eq!(registry.get(&code_hash), Some(&custom));
+        assert!(registry.get(&[0; 32]).is_none());
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
+mod holders;
 mod master_contract;
 mod wallet_contract;
 
+pub use holders::*;
 pub use master_contract::*;
 pub use wallet_contract::*;
@@ -0,0 +1,383 @@
+use std::collections::HashMap;
+
+use num_bigint::{BigInt, BigUint};
+use num_traits::Zero;
+
+use crate::address::TonAddress;
+use crate::cell::TonCellError;
+use crate::constants::{
+    BOUNCED_MESSAGE, JETTON_BURN, JETTON_BURN_NOTIFICATION, JETTON_INTERNAL_TRANSFER,
+    JETTON_TRANSFER,
+};
+use crate::contract::{JettonWalletContract, TonContractError, TonContractFactory};
+use crate::responses::{MessageType, Transaction, TransactionMessage};
+
+/// Jetton wallet tracked by [`JettonHolders`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JettonHolder {
+    pub wallet: TonAddress,
+    /// Owner of the wallet, known once it sent a transfer or once it is validated.
+    pub owner: Option<TonAddress>,
+    /// Balance computed from the applied transactions, negative if the history of the
+    /// wallet is incomplete.
+    pub balance: BigInt,
+    /// Logical time of the last applied transaction of the wallet.
+    pub last_lt: u64,
+}
+
+/// Balance of a wallet that differed from `get_wallet_data` during validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JettonBalanceMismatch {
+    pub wallet: TonAddress,
+    pub tracked: BigInt,
+    pub actual: BigUint,
+}
+
+/// Balances of the holders of a jetton maintained from the transactions of its master and
+/// wallets, e.g. as a building block of token analytics.
+///
+/// Wallets are discovered from the `internal_transfer` messages sent by the master (mints) and
+/// by the wallets already known, or are added by [`JettonHolders::add_wallet`]. The
+/// transactions of each wallet are applied oldest first, and transactions of unknown accounts
+/// are ignored, so the wallets of other jettons can't credit the holders.
+#[derive(Debug, Clone)]
+pub struct JettonHolders {
+    master: TonAddress,
+    holders: HashMap<TonAddress, JettonHolder>,
+}
+
+impl JettonHolders {
+    pub fn new(master: &TonAddress) -> JettonHolders {
+        JettonHolders {
+            master: master.clone(),
+            holders: HashMap::new(),
+        }
+    }
+
+    pub fn master(&self) -> &TonAddress {
+        &self.master
+    }
+
+    /// Starts tracking the wallet with a zero balance, keeping it if it is already tracked.
+    pub fn add_wallet(&mut self, wallet: &TonAddress) -> &mut JettonHolder {
+        self.holders
+            .entry(wallet.clone())
+            .or_insert_with(|| JettonHolder {
+                wallet: wallet.clone(),
+                owner: None,
+                balance: BigInt::zero(),
+                last_lt: 0,
+            })
+    }
+
+    pub fn holder(&self, wallet: &TonAddress) -> Option<&JettonHolder> {
+        self.holders.get(wallet)
+    }
+
+    pub fn holders(&self) -> impl Iterator<Item = &JettonHolder> {
+        self.holders.values()
+    }
+
+    /// Returns the wallets with a positive balance.
+    pub fn non_zero_holders(&self) -> impl Iterator<Item = &JettonHolder> {
+        self.holders()
+            .filter(|holder| holder.balance > BigInt::zero())
+    }
+
+    /// Returns the sum of the tracked balances, which matches the total supply of the master
+    /// once the history of all wallets is applied.
+    pub fn total_supply(&self) -> BigInt {
+        self.holders().map(|holder| &holder.balance).sum()
+    }
+
+    /// Applies a transaction of the master or of a tracked wallet, returning `false` if the
+    /// transaction is ignored.
+    ///
+    /// Transactions of wallets are applied only once, transactions with a logical time not
+    /// greater than the last applied one are ignored.
+    pub fn apply_transaction(&mut self, transaction: &Transaction) -> Result<bool, TonCellError> {
+        let in_msg = match transaction.in_msg.data.as_ref() {
+            Some(in_msg) if in_msg.info.msg_type == MessageType::Internal as u8 => in_msg,
+            _ => return Ok(false),
+        };
+        let account = &in_msg.info.dest;
+        if *account != self.master
+            && self
+                .holders
+                .get(account)
+                .is_none_or(|holder| holder.last_lt >= transaction.lt)
+        {
+            return Ok(false);
+        }
+        let success = transaction
+            .description
+            .as_ref()
+            .is_some_and(|descr| descr.is_success());
+        if success {
+            for msg in transaction
+                .out_msgs
+                .values()
+                .filter_map(|msg| msg.data.as_ref())
+            {
+                if msg.info.msg_type == MessageType::Internal as u8
+                    && message_op(msg)? == Some(JETTON_INTERNAL_TRANSFER)
+                {
+                    self.add_wallet(&msg.info.dest);
+                }
+            }
+        }
+        if *account == self.master {
+            return Ok(true);
+        }
+
+        let master = self.master.clone();
+        let holder = self.add_wallet(account);
+        holder.last_lt = transaction.lt;
+        if !success {
+            return Ok(true);
+        }
+        let bounced = in_msg.info.bounced;
+        match load_amount(in_msg)? {
+            // Transfers are only accepted from the owner of the wallet
+            Some((JETTON_TRANSFER, amount)) if !bounced => {
+                holder.owner.get_or_insert_with(|| in_msg.info.src.clone());
+                holder.balance -= BigInt::from(amount);
+            }
+            Some((JETTON_BURN, amount)) if !bounced => holder.balance -= BigInt::from(amount),
+            // Bounced messages return the amount they carried
+            Some((JETTON_INTERNAL_TRANSFER, amount)) => holder.balance += BigInt::from(amount),
+            Some((JETTON_BURN_NOTIFICATION, amount)) if bounced && in_msg.info.src == master => {
+                holder.balance += BigInt::from(amount)
+            }
+            _ => {}
+        }
+        Ok(true)
+    }
+
+    /// Compares the tracked balances with `get_wallet_data` of the wallets, replacing the
+    /// tracked balances and owners by the actual ones, and returns the mismatching balances.
+    pub async fn validate(
+        &mut self,
+        factory: &TonContractFactory,
+    ) -> Result<Vec<JettonBalanceMismatch>, TonContractError> {
+        let mut mismatches = vec![];
+        for holder in self.holders.values_mut() {
+            let data = factory
+                .get_contract(&holder.wallet)
+                .get_wallet_data()
+                .await?;
+            if data.master_address != self.master {
+                return Err(TonContractError::IllegalArgument(format!(
+                    "Wallet {} belongs to jetton {}, expected {}",
+                    holder.wallet, data.master_address, self.master
+                )));
+            }
+            holder.owner = Some(data.owner_address);
+            let actual = BigInt::from(data.balance.clone());
+            if holder.balance != actual {
+                mismatches.push(JettonBalanceMismatch {
+                    wallet: holder.wallet.clone(),
+                    tracked: std::mem::replace(&mut holder.balance, actual),
+                    actual: data.balance,
+                });
+            }
+        }
+        Ok(mismatches)
+    }
+}
+
+fn message_op(msg: &TransactionMessage) -> Result<Option<u32>, TonCellError> {
+    let mut parser = match msg.body.any_cell() {
+        Some(body) => body.parser()?,
+        None => return Ok(None),
+    };
+    if parser.remaining_bits() < 32 {
+        return Ok(None);
+    }
+    parser.load_u32(32).map(Some)
+}
+
+/// Loads the op code and the amount of a jetton message, which both follow the query id, or of
+/// the original message of a bounced one.
+fn load_amount(msg: &TransactionMessage) -> Result<Option<(u32, BigUint)>, TonCellError> {
+    let mut parser = match msg.body.any_cell() {
+        Some(body) => body.parser()?,
+        None => return Ok(None),
+    };
+    if parser.remaining_bits() < 32 {
+        return Ok(None);
+    }
+    let mut op = parser.load_u32(32)?;
+    if msg.info.bounced && op == BOUNCED_MESSAGE {
+        if parser.remaining_bits() < 32 {
+            return Ok(None);
+        }
+        op = parser.load_u32(32)?;
+    }
+    match op {
+        JETTON_TRANSFER | JETTON_BURN | JETTON_INTERNAL_TRANSFER | JETTON_BURN_NOTIFICATION => {
+            parser.load_u64(64)?; // query_id
+            Ok(Some((op, parser.load_coins()?.into_inner())))
+        }
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use num_bigint::{BigInt, BigUint};
+
+    use crate::address::TonAddress;
+    use crate::cell::{Cell, CellBuilder};
+    use crate::constants::{
+        BOUNCED_MESSAGE, JETTON_BURN, JETTON_INTERNAL_TRANSFER, JETTON_TRANSFER,
+    };
+    use crate::contract::JettonHolders;
+    use crate::responses::{
+        AnyCell, MaybeRefData, MessageType, TrComputePhase, TrComputePhaseVm, Transaction,
+        TransactionBody, TransactionDescr, TransactionDescrOrdinary, TransactionMessage,
+    };
+
+    fn body(op: u32, amount: u64) -> Cell {
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u32(32, op)
+            .unwrap()
+            .store_u64(64, 0)
+            .unwrap()
+            .store_coins(BigUint::from(amount))
+            .unwrap();
+        builder.build().unwrap()
+    }
+
+    fn message(src: &TonAddress, dest: &TonAddress, body: Cell) -> TransactionMessage {
+        let mut msg = TransactionMessage::default();
+        msg.info.msg_type = MessageType::Internal as u8;
+        msg.info.src = src.clone();
+        msg.info.dest = dest.clone();
+        msg.body = TransactionBody {
+            any: Some(AnyCell {
+                cell: body,
+                ref_index: 0,
+                parser_positions_in_bits: 0,
+            }),
+            cell_ref: None,
+        };
+        msg
+    }
+
+    fn transaction(
+        lt: u64,
+        success: bool,
+        in_msg: TransactionMessage,
+        out_msgs: Vec<TransactionMessage>,
+    ) -> Transaction {
+        let out_msgs: HashMap<_, _> = out_msgs
+            .into_iter()
+            .enumerate()
+            .map(|(i, msg)| {
+                let msg = MaybeRefData {
+                    data: Some(msg),
+                    ..Default::default()
+                };
+                (i.to_string(), msg)
+            })
+            .collect();
+        let compute_ph = TrComputePhase::Vm(Box::new(TrComputePhaseVm {
+            success,
+            ..Default::default()
+        }));
+        Transaction {
+            lt,
+            in_msg: MaybeRefData {
+                data: Some(in_msg),
+                ..Default::default()
+            },
+            out_msgs,
+            description: Some(TransactionDescr::Ordinary(TransactionDescrOrdinary {
+                credit_first: false,
+                storage_ph: None,
+                credit_ph: None,
+                compute_ph,
+                action: MaybeRefData::default(),
+                aborted: false,
+                bounce: None,
+                destroyed: false,
+            })),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn jetton_holders_track_balances() -> anyhow::Result<()> {
+        let master =
+            TonAddress::from_base64_url("EQCxE6mUtQJKFnGfaROTKOt1lZbDiiX1kCixRv7Nw2Id_sDs")?;
+        let alice =
+            TonAddress::from_base64_url("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR")?;
+        let alice_wallet = TonAddress::new(0, &[1; 32]);
+        let bob_wallet = TonAddress::new(0, &[2; 32]);
+        let foreign_wallet = TonAddress::new(0, &[3; 32]);
+        let mut holders = JettonHolders::new(&master);
+
+        // Mint of 100 to alice
+        let mint = message(&master, &alice_wallet, body(JETTON_INTERNAL_TRANSFER, 100));
+        let admin = message(&alice, &master, body(21, 0));
+        assert!(holders.apply_transaction(&transaction(1, true, admin, vec![mint.clone()]))?);
+        assert!(holders.apply_transaction(&transaction(2, true, mint, vec![]))?);
+
+        // Transfer of 30 from alice to bob
+        let transfer = message(&alice, &alice_wallet, body(JETTON_TRANSFER, 30));
+        let internal = message(
+            &alice_wallet,
+            &bob_wallet,
+            body(JETTON_INTERNAL_TRANSFER, 30),
+        );
+        holders.apply_transaction(&transaction(3, true, transfer, vec![internal.clone()]))?;
+        holders.apply_transaction(&transaction(4, true, internal.clone(), vec![]))?;
+        // Already applied
+        assert!(!holders.apply_transaction(&transaction(4, true, internal, vec![]))?);
+
+        // Failed burn and burn of 10 by bob
+        let burn = message(&alice, &bob_wallet, body(JETTON_BURN, 10));
+        holders.apply_transaction(&transaction(5, false, burn.clone(), vec![]))?;
+        holders.apply_transaction(&transaction(6, true, burn, vec![]))?;
+
+        // Bounced transfer of 5 from alice to an uninitialized wallet
+        let transfer = message(&alice, &alice_wallet, body(JETTON_TRANSFER, 5));
+        holders.apply_transaction(&transaction(7, true, transfer, vec![]))?;
+        let mut bounced_body = CellBuilder::new();
+        bounced_body
+            .store_u32(32, BOUNCED_MESSAGE)?
+            .store_cell(&body(JETTON_INTERNAL_TRANSFER, 5))?;
+        let mut bounced = message(&foreign_wallet, &alice_wallet, bounced_body.build()?);
+        bounced.info.bounced = true;
+        holders.apply_transaction(&transaction(8, true, bounced, vec![]))?;
+
+        // Wallet of another jetton
+        let foreign = message(
+            &foreign_wallet,
+            &alice_wallet,
+            body(JETTON_INTERNAL_TRANSFER, 7),
+        );
+        assert!(!holders.apply_transaction(&transaction(
+            9,
+            true,
+            message(&alice, &foreign_wallet, body(JETTON_TRANSFER, 7)),
+            vec![foreign]
+        ))?);
+
+        let alice_holder = holders.holder(&alice_wallet).unwrap();
+        assert_eq!(alice_holder.balance, BigInt::from(70));
+        assert_eq!(alice_holder.owner, Some(alice));
+        assert_eq!(alice_holder.last_lt, 8);
+        let bob_holder = holders.holder(&bob_wallet).unwrap();
+        assert_eq!(bob_holder.balance, BigInt::from(20));
+        assert_eq!(bob_holder.owner, None);
+        assert!(holders.holder(&foreign_wallet).is_none());
+        assert_eq!(holders.total_supply(), BigInt::from(90));
+        assert_eq!(holders.non_zero_holders().count(), 2);
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use num_bigint::BigUint;
+
+use crate::contract::{MapStackError, TonContractError, TonContractInterface};
+use crate::types::{TvmStackEntry, TvmSuccess};
+
+/// Exit code of a get method that is not implemented by the contract.
+pub const METHOD_NOT_FOUND_EXIT_CODE: i32 = 11;
+
+/// Get methods implemented by many contracts, returning `None` if the contract does not
+/// implement the method.
+///
+/// ```rust,ignore
+/// let getters = StandardGetters::new(&contract);
+/// if let Some(public_key) = getters.get_public_key().await? {
+///     ...
+/// }
+/// ```
+pub struct StandardGetters<'a, C: TonContractInterface> {
+    contract: &'a C,
+}
+
+impl<'a, C: TonContractInterface + Sync> StandardGetters<'a, C> {
+    pub fn new(contract: &'a C) -> StandardGetters<'a, C> {
+        StandardGetters { contract }
+    }
+
+    pub async fn get_public_key(&self) -> Result<Option<[u8; 32]>, TonContractError> {
+        let method = "get_public_key";
+        let Some(value) = self.get_uint(method).await? else {
+            return Ok(None);
+        };
+        let bytes = value.to_bytes_be();
+        if bytes.len() > 32 {
+            return Err(TonContractError::InternalError(format!(
+                "{} of {} returned {} bytes",
+                method,
+                self.contract.address(),
+                bytes.len()
+            )));
+        }
+        let mut public_key = [0; 32];
+        public_key[32 - bytes.len()..].copy_from_slice(&bytes);
+        Ok(Some(public_key))
+    }
+
+    pub async fn seqno(&self) -> Result<Option<u32>, TonContractError> {
+        self.get_u32("seqno").await
+    }
+
+    pub async fn get_subwallet_id(&self) -> Result<Option<u32>, TonContractError> {
+        self.get_u32("get_subwallet_id").await
+    }
+
+    /// Returns the ids of the interfaces declared by the contract, which are returned as
+    /// integers on the stack.
+    pub async fn supported_interfaces(&self) -> Result<Option<Vec<BigUint>>, TonContractError> {
+        let method = "supported_interfaces";
+        let Some(result) = self.run(method).await? else {
+            return Ok(None);
+        };
+        let address = self.contract.address();
+        let ids = result
+            .stack
+            .iter()
+            .map(TvmStackEntry::get_biguint)
+            .collect::<Result<Vec<_>, _>>()
+            .map_stack_error(method, address)?;
+        Ok(Some(ids))
+    }
+
+    async fn get_u32(&self, method: &'static str) -> Result<Option<u32>, TonContractError> {
+        let Some(value) = self.get_uint(method).await? else {
+            return Ok(None);
+        };
+        u32::try_from(value).map(Some).map_err(|e| {
+            TonContractError::InternalError(format!(
+                "{} of {} returned {}",
+                method,
+                self.contract.address(),
+                e.into_original()
+            ))
+        })
+    }
+
+    /// Runs the get method returning a single unsigned integer.
+    async fn get_uint(&self, method: &'static str) -> Result<Option<BigUint>, TonContractError> {
+        let Some(result) = self.run(method).await? else {
+            return Ok(None);
+        };
+        let address = self.contract.address();
+        if result.stack.len() != 1 {
+            return Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address: address.clone(),
+                actual: result.stack.len(),
+                expected: 1,
+            });
+        }
+        let value = result.stack[0]
+            .get_biguint()
+            .map_stack_error(method, address)?;
+        Ok(Some(value))
+    }
+
+    async fn run(&self, method: &'static str) -> Result<Option<TvmSuccess>, TonContractError> {
+        let stack: Vec<TvmStackEntry> = vec![];
+        match self.contract.run_get_method(method, &stack).await {
+            Ok(result) => Ok(Some(result)),
+            Err(TonContractError::TvmRunError { exit_code, .. })
+                if exit_code == METHOD_NOT_FOUND_EXIT_CODE =>
+            {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
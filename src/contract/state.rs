@@ -5,8 +5,10 @@ use async_trait::async_trait;
 use crate::address::TonAddress;
 use crate::cell::Cell;
 use crate::client::{TonClientError, TonClientInterface};
-use crate::contract::{TonContractError, TonContractFactory, TonContractInterface};
-use crate::emulator::{TvmEmulator, TvmEmulatorC7Builder};
+#[cfg(feature = "state_cache")]
+use crate::contract::GetMethodCacheKey;
+use crate::contract::{LoadedSmcState, TonContractError, TonContractFactory, TonContractInterface};
+use crate::emulator::{TvmEmulator, TvmEmulatorC7, TvmEmulatorC7Builder};
 use crate::tl::RawFullAccountState;
 use crate::types::{TonMethodId, TvmMsgSuccess, TvmStackEntry, TvmSuccess};
 
@@ -80,16 +82,30 @@ impl TonContractState {
         M: Into<TonMethodId> + Send + Copy,
         S: AsRef<[TvmStackEntry]> + Send,
     {
-        let method_id = &method.into();
-        let stack_ref = stack.as_ref();
-        let state = self.account_state.clone();
         let c7 = TvmEmulatorC7Builder::new(
             &self.address,
             self.factory.get_config_cell_serial().await?,
             0,
         )
         .build();
+        self.emulate_get_method_with_c7(c7, method, stack).await
+    }
 
+    /// Emulates the get method with specified `c7`, e.g. holding the config and the time of
+    /// a past block.
+    pub async fn emulate_get_method_with_c7<M, S>(
+        &self,
+        c7: TvmEmulatorC7,
+        method: M,
+        stack: S,
+    ) -> Result<TvmSuccess, TonContractError>
+    where
+        M: Into<TonMethodId> + Send + Copy,
+        S: AsRef<[TvmStackEntry]> + Send,
+    {
+        let method_id = &method.into();
+        let stack_ref = stack.as_ref();
+        let state = self.account_state.clone();
         let libs = self
             .factory
             .library_provider()
@@ -138,11 +154,17 @@ impl TonContractState {
             0,
         )
         .build();
+        let libs = self
+            .factory
+            .library_provider()
+            .get_contract_libraries(&self.address, &self.account_state)
+            .await?;
         let run_result = tokio::task::spawn_blocking(move || {
             let code = state.code.as_slice();
             let data = state.data.as_slice();
             let mut emulator = TvmEmulator::new(code, data)?;
             emulator.set_c7(&c7)?;
+            emulator.set_libraries(libs.dict_boc.as_slice())?;
             emulator.send_internal_message(message, amount)
         })
         .await
@@ -178,42 +200,7 @@ impl TonContractState {
             }
             Err(e) => Err(e),
         }?;
-
-        let stack_tl = stack
-            .as_ref()
-            .iter()
-            .map(|e| e.try_into())
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| TonContractError::TvmStackParseError {
-                method: method.into(),
-                address: self.address().clone(),
-                error: e,
-            })?;
-
-        let run_result = state
-            .conn
-            .smc_run_get_method(state.id, &method.into(), &stack_tl)
-            .await?;
-
-        let stack = run_result
-            .stack
-            .elements
-            .iter()
-            .map(|e| e.try_into())
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| TonContractError::TvmStackParseError {
-                method: method.into(),
-                address: self.address().clone(),
-                error: e,
-            })?;
-        let result = TvmSuccess {
-            vm_log: None,
-            vm_exit_code: run_result.exit_code,
-            stack,
-            missing_library: None,
-            gas_used: run_result.gas_used as i32,
-        };
-        Self::raise_exit_error(self.address(), &method.into(), result)
+        run_loaded_get_method(address, &state, method, stack).await
     }
 
     fn raise_exit_error(
@@ -237,6 +224,54 @@ impl TonContractState {
     }
 }
 
+/// Runs the get method of the contract loaded by tonlib.
+pub(crate) async fn run_loaded_get_method<M, S>(
+    address: &TonAddress,
+    state: &LoadedSmcState,
+    method: M,
+    stack: S,
+) -> Result<TvmSuccess, TonContractError>
+where
+    M: Into<TonMethodId> + Send + Copy,
+    S: AsRef<[TvmStackEntry]> + Send,
+{
+    let stack_tl = stack
+        .as_ref()
+        .iter()
+        .map(|e| e.try_into())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| TonContractError::TvmStackParseError {
+            method: method.into(),
+            address: address.clone(),
+            error: e,
+        })?;
+
+    let run_result = state
+        .conn
+        .smc_run_get_method(state.id, &method.into(), &stack_tl)
+        .await?;
+
+    let stack = run_result
+        .stack
+        .elements
+        .iter()
+        .map(|e| e.try_into())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| TonContractError::TvmStackParseError {
+            method: method.into(),
+            address: address.clone(),
+            error: e,
+        })?;
+    let result = TvmSuccess {
+        vm_log: None,
+        vm_exit_code: run_result.exit_code,
+        stack,
+        missing_library: None,
+        gas_used: run_result.gas_used as i32,
+    };
+    TonContractState::raise_exit_error(address, &method.into(), result)
+}
+
 #[async_trait]
 impl TonContractInterface for TonContractState {
     fn factory(&self) -> &TonContractFactory {
@@ -260,6 +295,24 @@ impl TonContractInterface for TonContractState {
         M: Into<TonMethodId> + Send + Copy,
         S: AsRef<[TvmStackEntry]> + Send,
     {
+        #[cfg(feature = "state_cache")]
+        if let Some(cache) = self.factory.get_method_cache() {
+            let method_id = method.into();
+            let key = GetMethodCacheKey::new(
+                &self.address,
+                &self.account_state,
+                &method_id,
+                stack.as_ref(),
+            )
+            .map_err(|error| TonContractError::CellError {
+                method: method_id.to_string(),
+                address: self.address.clone(),
+                error,
+            })?;
+            return cache
+                .get_or_run(key, self.do_run_get_method(method, stack))
+                .await;
+        }
         self.do_run_get_method(method, stack).await
     }
 }
@@ -0,0 +1,268 @@
+use std::collections::HashSet;
+
+use crate::cell::{Cell, CellPath, TonCellError};
+use crate::responses::AccountStatus;
+use crate::tl::RawFullAccountState;
+
+/// Level of the representation hash returned by [`Cell::hash_ref`].
+const MAX_LEVEL: u8 = 3;
+
+/// Changes between two states of an account, e.g. to alert on unexpected upgrades of
+/// sensitive contracts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountStateDiff {
+    pub old_status: AccountStatus,
+    pub new_status: AccountStatus,
+    /// Balance of the new state minus balance of the old state, in nanotons.
+    pub balance_delta: i64,
+    pub code_changed: bool,
+    pub old_code_hash: Option<Vec<u8>>,
+    pub new_code_hash: Option<Vec<u8>>,
+    /// Cells of the data that were added, removed or modified, in depth-first order.
+    pub data_diffs: Vec<CellDiff>,
+    pub old_storage_stat: StorageStat,
+    pub new_storage_stat: StorageStat,
+}
+
+impl AccountStateDiff {
+    /// Returns whether the states have the same status, balance, code and data.
+    pub fn is_empty(&self) -> bool {
+        self.old_status == self.new_status
+            && self.balance_delta == 0
+            && !self.code_changed
+            && self.data_diffs.is_empty()
+    }
+
+    pub fn data_changed(&self) -> bool {
+        !self.data_diffs.is_empty()
+    }
+}
+
+/// Difference of a data cell at a path from the root of the data, see [`CellPath`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CellDiff {
+    pub path: CellPath,
+    pub kind: CellDiffKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CellDiffKind {
+    /// The cell is only present in the new state.
+    Added,
+    /// The cell is only present in the old state.
+    Removed,
+    /// The bits of the cell changed. Changes of its references are reported at their paths.
+    Modified,
+}
+
+/// Number of unique cells and bits of the code and the data of an account.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct StorageStat {
+    pub cells: u64,
+    pub bits: u64,
+}
+
+impl StorageStat {
+    /// Counts the unique cells of the trees, as the node does for the storage fees.
+    pub fn of<'a>(roots: impl IntoIterator<Item = &'a Cell>) -> Result<StorageStat, TonCellError> {
+        let mut stat = StorageStat::default();
+        let mut visited = HashSet::new();
+        let mut stack: Vec<&Cell> = roots.into_iter().collect();
+        while let Some(cell) = stack.pop() {
+            if !visited.insert(cell.cell_hash()?) {
+                continue;
+            }
+            stat.cells += 1;
+            stat.bits += cell.bit_len as u64;
+            stack.extend(cell.references.iter().map(|cell| cell.as_ref()));
+        }
+        Ok(stat)
+    }
+}
+
+/// Compares two states of an account, reporting the changes of its balance, code, data and
+/// storage stat.
+pub fn diff_account_states(
+    old: &RawFullAccountState,
+    new: &RawFullAccountState,
+) -> Result<AccountStateDiff, TonCellError> {
+    let (old_code, old_data) = (old.code_cell()?, old.data_cell()?);
+    let (new_code, new_data) = (new.code_cell()?, new.data_cell()?);
+    let old_code_hash = old_code.as_ref().map(|code| code.cell_hash()).transpose()?;
+    let new_code_hash = new_code.as_ref().map(|code| code.cell_hash()).transpose()?;
+
+    let mut data_diffs = vec![];
+    diff_cells(
+        old_data.as_deref(),
+        new_data.as_deref(),
+        &mut vec![],
+        &mut data_diffs,
+    )?;
+    let old_cells = old_code
+        .iter()
+        .chain(old_data.iter())
+        .map(|cell| cell.as_ref());
+    let new_cells = new_code
+        .iter()
+        .chain(new_data.iter())
+        .map(|cell| cell.as_ref());
+    Ok(AccountStateDiff {
+        old_status: old.status(),
+        new_status: new.status(),
+        balance_delta: new.balance.saturating_sub(old.balance),
+        code_changed: old_code_hash != new_code_hash,
+        old_code_hash,
+        new_code_hash,
+        data_diffs,
+        old_storage_stat: StorageStat::of(old_cells)?,
+        new_storage_stat: StorageStat::of(new_cells)?,
+    })
+}
+
+fn diff_cells(
+    old: Option<&Cell>,
+    new: Option<&Cell>,
+    path: &mut Vec<usize>,
+    diffs: &mut Vec<CellDiff>,
+) -> Result<(), TonCellError> {
+    let kind = match (old, new) {
+        (None, None) => return Ok(()),
+        (Some(_), None) => CellDiffKind::Removed,
+        (None, Some(_)) => CellDiffKind::Added,
+        (Some(old), Some(new)) => {
+            if same_cells(old, new)? {
+                return Ok(());
+            }
+            if old.bit_len != new.bit_len || old.data != new.data {
+                diffs.push(CellDiff {
+                    path: CellPath::from(path.as_slice()),
+                    kind: CellDiffKind::Modified,
+                });
+            }
+            let ref_count = old.references.len().max(new.references.len());
+            for i in 0..ref_count {
+                path.push(i);
+                diff_cells(
+                    old.references.get(i).map(|cell| cell.as_ref()),
+                    new.references.get(i).map(|cell| cell.as_ref()),
+                    path,
+                    diffs,
+                )?;
+                path.pop();
+            }
+            return Ok(());
+        }
+    };
+    // Descendants of added and removed cells are not reported separately
+    diffs.push(CellDiff {
+        path: CellPath::from(path.as_slice()),
+        kind,
+    });
+    Ok(())
+}
+
+/// Compares the representation hashes of the cells, using the hashes cached by deserialization
+/// if both cells have them, so that unchanged subtrees of parsed states are not hashed again.
+fn same_cells(old: &Cell, new: &Cell) -> Result<bool, TonCellError> {
+    if !old.hashes.is_empty() && !new.hashes.is_empty() {
+        return Ok(old.hash_ref(MAX_LEVEL) == new.hash_ref(MAX_LEVEL));
+    }
+    Ok(old.cell_hash_array()? == new.cell_hash_array()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cell::{BagOfCells, Cell, CellBuilder};
+    use crate::contract::{diff_account_states, CellDiffKind, StorageStat};
+    use crate::tl::RawFullAccountState;
+
+    fn account_state(
+        balance: i64,
+        code: &Cell,
+        data: &Cell,
+    ) -> anyhow::Result<RawFullAccountState> {
+        let state = serde_json::json!({
+            "balance": balance.to_string(),
+            "code": "",
+            "data": "",
+            "last_transaction_id": {"lt": "1", "hash": ""},
+            "block_id": {
+                "workchain": 0, "shard": "0", "seqno": 1, "root_hash": "", "file_hash": ""
+            },
+            "frozen_hash": "",
+            "sync_utime": "0",
+        });
+        let mut state: RawFullAccountState = serde_json::from_value(state)?;
+        state.code = BagOfCells::from_root(code.clone()).serialize(false)?;
+        state.data = BagOfCells::from_root(data.clone()).serialize(false)?;
+        Ok(state)
+    }
+
+    #[test]
+    fn account_state_diff_works() -> anyhow::Result<()> {
+        let code = CellBuilder::new().store_u32(32, 1)?.build()?;
+        let shared = CellBuilder::new().store_u8(8, 7)?.build()?;
+        let data = CellBuilder::new()
+            .store_u32(32, 100)?
+            .store_child(shared.clone())?
+            .store_child(CellBuilder::new().store_u8(8, 1)?.build()?)?
+            .build()?;
+        let old = account_state(1000, &code, &data)?;
+
+        let diff = diff_account_states(&old, &old)?;
+        assert!(diff.is_empty());
+        assert_eq!(
+            diff.old_storage_stat,
+            StorageStat {
+                cells: 4,
+                bits: 32 + 32 + 8 + 8
+            }
+        );
+
+        let new_data = CellBuilder::new()
+            .store_u32(32, 100)?
+            .store_child(shared.clone())?
+            .store_child(CellBuilder::new().store_u8(8, 2)?.build()?)?
+            .store_child(shared.clone())?
+            .build()?;
+        let new = account_state(400, &code, &new_data)?;
+        let diff = diff_account_states(&old, &new)?;
+        assert!(!diff.is_empty());
+        assert_eq!(diff.balance_delta, -600);
+        assert!(!diff.code_changed);
+        let diffs: Vec<_> = diff
+            .data_diffs
+            .iter()
+            .map(|diff| (diff.path.to_string(), diff.kind))
+            .collect();
+        assert_eq!(
+            diffs,
+            vec![
+                ("1".to_string(), CellDiffKind::Modified),
+                ("2".to_string(), CellDiffKind::Added)
+            ]
+        );
+        // The shared cell is counted once
+        assert_eq!(diff.new_storage_stat, diff.old_storage_stat);
+
+        let new_code = CellBuilder::new().store_u32(32, 2)?.build()?;
+        let new_data = CellBuilder::new().store_u32(32, 101)?.build()?;
+        let diff = diff_account_states(&old, &account_state(1000, &new_code, &new_data)?)?;
+        assert!(diff.code_changed);
+        assert_eq!(diff.new_code_hash, Some(new_code.cell_hash()?));
+        let diffs: Vec<_> = diff
+            .data_diffs
+            .iter()
+            .map(|diff| (diff.path.to_string(), diff.kind))
+            .collect();
+        assert_eq!(
+            diffs,
+            vec![
+                ("".to_string(), CellDiffKind::Modified),
+                ("0".to_string(), CellDiffKind::Removed),
+                ("1".to_string(), CellDiffKind::Removed)
+            ]
+        );
+        Ok(())
+    }
+}
@@ -1,7 +1,10 @@
 use async_trait::async_trait;
+use num_bigint::BigInt;
 use strum::IntoStaticStr;
 
 use crate::contract::{MapStackError, TonContractError, TonContractInterface};
+use crate::types::TvmStackEntry;
+use crate::wallet::HighloadQueryStatus;
 
 #[derive(IntoStaticStr)]
 #[strum(serialize_all = "snake_case")]
@@ -47,6 +50,33 @@ pub trait TonWalletContract: TonContractInterface {
             Ok(pub_key.to_bytes_be())
         }
     }
+
+    /// Checks whether a highload wallet v2 has processed the query, see
+    /// [`PendingHighloadQuery::status`](crate::wallet::PendingHighloadQuery::status).
+    async fn processed(&self, query_id: u64) -> Result<HighloadQueryStatus, TonContractError> {
+        let method = "processed?";
+        let input_stack = vec![TvmStackEntry::Int257(BigInt::from(query_id))];
+        let res = self.run_get_method(method, &input_stack).await?;
+        let stack = res.stack;
+        if stack.len() != 1 {
+            Err(TonContractError::InvalidMethodResultStackSize {
+                method: method.to_string(),
+                address: self.address().clone(),
+                actual: stack.len(),
+                expected: 1,
+            })
+        } else {
+            let result = stack[0].get_i64().map_stack_error(method, self.address())?;
+            HighloadQueryStatus::from_get_method(result).ok_or_else(|| {
+                TonContractError::InternalError(format!(
+                    "Unexpected result {} of {} of {}",
+                    result,
+                    method,
+                    self.address()
+                ))
+            })
+        }
+    }
 }
 
 impl<T> TonWalletContract for T where T: TonContractInterface {}
@@ -0,0 +1,262 @@
+mod comment;
+mod error;
+
+use std::sync::atomic::{compiler_fence, Ordering};
+
+pub use comment::*;
+pub use error::*;
+use nacl::sign::generate_keypair;
+
+use crate::adnl::AdnlAddress;
+use crate::responses::{CryptoSignaturePair, Validators};
+
+pub const PUBLIC_KEY_LEN: usize = 32;
+pub const SECRET_KEY_LEN: usize = 64;
+pub const SEED_LEN: usize = 32;
+pub const SIGNATURE_LEN: usize = 64;
+
+/// TL id of `ton.blockId root_cell_hash:int256 file_hash:int256 = ton.BlockId`, signed by
+/// validators.
+const TON_BLOCK_ID_TL_ID: u32 = 0xc50b6e70;
+
+/// Ed25519 key pair. The secret key holds the seed followed by the public key, like in NaCl.
+///
+/// The secret key is zeroed when the key pair is dropped, so the fields can't be moved out of
+/// a key pair, e.g. by destructuring it, and are cloned instead.
+#[derive(PartialEq, Eq, Clone, Hash)]
+pub struct KeyPair {
+    pub public_key: Vec<u8>,
+    pub secret_key: Vec<u8>,
+}
+
+impl KeyPair {
+    pub fn from_seed(seed: &[u8; SEED_LEN]) -> KeyPair {
+        let mut key_pair = generate_keypair(seed);
+        let result = KeyPair {
+            public_key: key_pair.pkey.to_vec(),
+            secret_key: key_pair.skey.to_vec(),
+        };
+        zeroize(&mut key_pair.skey);
+        result
+    }
+
+    pub fn from_secret_key(secret_key: &[u8]) -> Result<KeyPair, CryptoError> {
+        check_len(secret_key, SECRET_KEY_LEN)?;
+        let seed = secret_key[..SEED_LEN].try_into().unwrap();
+        let key_pair = KeyPair::from_seed(seed);
+        if key_pair.public_key != secret_key[SEED_LEN..] {
+            return Err(CryptoError::NaclError(
+                "Public key doesn't match the secret key".to_string(),
+            ));
+        }
+        Ok(key_pair)
+    }
+
+    /// Generates a key pair from a random seed of the operating system.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn generate() -> KeyPair {
+        use rand::RngCore;
+
+        let mut seed = [0; SEED_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut seed);
+        let key_pair = KeyPair::from_seed(&seed);
+        zeroize(&mut seed);
+        key_pair
+    }
+
+    pub fn sign(&self, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        sign(data, &self.secret_key)
+    }
+
+    pub fn verify(&self, data: &[u8], signature: &[u8]) -> Result<(), CryptoError> {
+        verify(data, signature, &self.public_key)
+    }
+
+    /// Returns the short id of the public key, see [`public_key_id`].
+    pub fn key_id(&self) -> Result<[u8; 32], CryptoError> {
+        public_key_id(&self.public_key)
+    }
+}
+
+impl Drop for KeyPair {
+    fn drop(&mut self) {
+        zeroize(&mut self.secret_key);
+    }
+}
+
+/// Signs `data`, usually a cell hash, with a 64-byte secret key.
+pub fn sign(data: &[u8], secret_key: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    check_len(secret_key, SECRET_KEY_LEN)?;
+    Ok(nacl::sign::signature(data, secret_key)?)
+}
+
+/// Checks the signature of `data` against a 32-byte public key.
+pub fn verify(data: &[u8], signature: &[u8], public_key: &[u8]) -> Result<(), CryptoError> {
+    check_len(public_key, PUBLIC_KEY_LEN)?;
+    if signature.len() != SIGNATURE_LEN || !nacl::sign::verify(signature, data, public_key)? {
+        return Err(CryptoError::InvalidSignature);
+    }
+    Ok(())
+}
+
+/// Returns the short id of a public key, i.e. the hash of its TL serialization
+/// `pub.ed25519 key:int256 = PublicKey`.
+///
+/// Short ids identify validators in block signatures and are the ADNL addresses of nodes,
+/// see [`AdnlAddress::from_public_key`].
+pub fn public_key_id(public_key: &[u8]) -> Result<[u8; 32], CryptoError> {
+    let key: &[u8; PUBLIC_KEY_LEN] =
+        public_key
+            .try_into()
+            .map_err(|_| CryptoError::InvalidKeyLength {
+                expected: PUBLIC_KEY_LEN,
+                actual: public_key.len(),
+            })?;
+    Ok(*AdnlAddress::from_public_key(key).as_bytes())
+}
+
+/// Checks the signatures of a block by the validators of its set, returning the total weight
+/// of the validators who signed it.
+///
+/// The block is valid if the weight is greater than 2/3 of [`Validators::total_weight`].
+pub fn verify_block_signatures<'a>(
+    root_hash: &[u8; 32],
+    file_hash: &[u8; 32],
+    signatures: impl IntoIterator<Item = &'a CryptoSignaturePair>,
+    validators: &Validators,
+) -> Result<u64, CryptoError> {
+    let mut data = Vec::with_capacity(68);
+    data.extend(TON_BLOCK_ID_TL_ID.to_le_bytes());
+    data.extend(root_hash);
+    data.extend(file_hash);
+
+    let mut signed_weight = 0u64;
+    for pair in signatures {
+        let validator = validators
+            .list
+            .values()
+            .find(|validator| {
+                public_key_id(&validator.public_key)
+                    .is_ok_and(|id| id[..] == pair.node_id_short[..])
+            })
+            .ok_or_else(|| CryptoError::UnknownValidator(hex::encode(&pair.node_id_short)))?;
+        let signature = [pair.sign.r.as_slice(), pair.sign.s.as_slice()].concat();
+        verify(&data, &signature, &validator.public_key)?;
+        signed_weight = signed_weight.saturating_add(validator.weight);
+    }
+    Ok(signed_weight)
+}
+
+/// Overwrites secret data with zeros in a way that isn't optimized out.
+pub fn zeroize(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut() {
+        // SAFETY: `byte` is a valid and aligned reference
+        unsafe { std::ptr::write_volatile(byte, 0) };
+    }
+    compiler_fence(Ordering::SeqCst);
+}
+
+fn check_len(key: &[u8], expected: usize) -> Result<(), CryptoError> {
+    if key.len() != expected {
+        return Err(CryptoError::InvalidKeyLength {
+            expected,
+            actual: key.len(),
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crypto::{
+        public_key_id, sign, verify, verify_block_signatures, zeroize, CryptoError, KeyPair,
+    };
+    use crate::responses::{CryptoSignature, CryptoSignaturePair, ValidatorDescr, Validators};
+
+    #[test]
+    fn key_pairs_work() -> anyhow::Result<()> {
+        let key_pair = KeyPair::from_seed(&[7; 32]);
+        assert_eq!(key_pair.secret_key[..32], [7; 32]);
+        assert_eq!(key_pair.secret_key[32..], key_pair.public_key[..]);
+        assert!(KeyPair::from_secret_key(&key_pair.secret_key)? == key_pair);
+        let mut tampered = key_pair.secret_key.clone();
+        tampered[40] ^= 1;
+        assert!(KeyPair::from_secret_key(&tampered).is_err());
+        assert!(KeyPair::generate() != KeyPair::generate());
+
+        let signature = key_pair.sign(b"hash")?;
+        key_pair.verify(b"hash", &signature)?;
+        assert_eq!(sign(b"hash", &key_pair.secret_key)?, signature);
+        assert!(matches!(
+            verify(b"other", &signature, &key_pair.public_key),
+            Err(CryptoError::InvalidSignature)
+        ));
+        assert!(matches!(
+            verify(b"hash", &signature[..63], &key_pair.public_key),
+            Err(CryptoError::InvalidSignature)
+        ));
+        assert!(matches!(
+            sign(b"hash", &key_pair.public_key),
+            Err(CryptoError::InvalidKeyLength {
+                expected: 64,
+                actual: 32
+            })
+        ));
+
+        let mut secret = key_pair.secret_key.clone();
+        zeroize(&mut secret);
+        assert_eq!(secret, vec![0; 64]);
+        Ok(())
+    }
+
+    #[test]
+    fn block_signatures_work() -> anyhow::Result<()> {
+        let key_pairs: Vec<KeyPair> = (1..=3).map(|i| KeyPair::from_seed(&[i; 32])).collect();
+        let validators = Validators {
+            total_weight: 60,
+            list: key_pairs
+                .iter()
+                .enumerate()
+                .map(|(i, key_pair)| {
+                    let validator = ValidatorDescr {
+                        public_key: key_pair.public_key.clone(),
+                        weight: 10 * (i as u64 + 1),
+                        ..Default::default()
+                    };
+                    (i.to_string(), validator)
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let (root_hash, file_hash) = ([1; 32], [2; 32]);
+        let mut data = vec![0x70, 0x6e, 0x0b, 0xc5];
+        data.extend(root_hash);
+        data.extend(file_hash);
+        let mut signatures = key_pairs[1..]
+            .iter()
+            .map(|key_pair| {
+                let signature = key_pair.sign(&data)?;
+                Ok(CryptoSignaturePair {
+                    node_id_short: key_pair.key_id()?.to_vec(),
+                    sign: CryptoSignature {
+                        r: signature[..32].to_vec(),
+                        s: signature[32..].to_vec(),
+                    },
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let weight = verify_block_signatures(&root_hash, &file_hash, &signatures, &validators)?;
+        assert_eq!(weight, 50);
+        assert!(matches!(
+            verify_block_signatures(&file_hash, &root_hash, &signatures, &validators),
+            Err(CryptoError::InvalidSignature)
+        ));
+
+        signatures[0].node_id_short = public_key_id(&[9; 32])?.to_vec();
+        assert!(matches!(
+            verify_block_signatures(&root_hash, &file_hash, &signatures, &validators),
+            Err(CryptoError::UnknownValidator(_))
+        ));
+        Ok(())
+    }
+}
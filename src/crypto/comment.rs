@@ -0,0 +1,229 @@
+use aes::Aes256;
+use cbc::cipher::block_padding::NoPadding;
+use cbc::cipher::{BlockDecryptMut, KeyIvInit};
+use curve25519_dalek::edwards::CompressedEdwardsY;
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha512};
+
+use crate::address::TonAddress;
+use crate::cell::Cell;
+use crate::crypto::{check_len, zeroize, CryptoError, KeyPair, PUBLIC_KEY_LEN, SEED_LEN};
+
+/// Op code of a transfer carrying a comment encrypted for the recipient:
+///
+/// ```raw
+/// encrypted_comment#2167da4b pub_xor:bits256 msg_key:bits128 data:SnakeData = Body;
+/// ```
+///
+/// `pub_xor` is the xor of the public keys of the sender and the recipient, `data` is
+/// encrypted with AES-256-CBC using their shared secret, like tonlib's `SimpleEncryptionV2`.
+pub const ENCRYPTED_COMMENT: u32 = 0x2167da4b;
+
+const MSG_KEY_LEN: usize = 16;
+const MIN_PREFIX_LEN: usize = 16;
+// Bits of the first cell of the body left after the op code
+const FIRST_CELL_BYTES: usize = (1023 - 32) / 8;
+const CELL_BYTES: usize = 1023 / 8;
+
+/// Returns the x25519 secret shared by the owner of the key pair and the owner of the
+/// ed25519 public key.
+pub fn shared_secret(key_pair: &KeyPair, public_key: &[u8]) -> Result<[u8; 32], CryptoError> {
+    check_len(public_key, PUBLIC_KEY_LEN)?;
+    let point = CompressedEdwardsY(public_key.try_into().unwrap())
+        .decompress()
+        .ok_or_else(|| CryptoError::DecryptionError("Invalid public key".to_string()))?;
+    let mut hash: [u8; 64] = Sha512::digest(&key_pair.secret_key[..SEED_LEN]).into();
+    let mut scalar = [0; 32];
+    scalar.copy_from_slice(&hash[..32]);
+    zeroize(&mut hash);
+    Ok(point.to_montgomery().mul_clamped(scalar).to_bytes())
+}
+
+/// Encrypts `data` for the owner of `public_key`, prefixing it with the xor of the public keys
+/// and with random padding. `salt` is usually the address of the sender.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn encrypt_data(
+    data: &[u8],
+    key_pair: &KeyPair,
+    public_key: &[u8],
+    salt: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    use cbc::cipher::BlockEncryptMut;
+    use rand::RngCore;
+
+    let mut secret = shared_secret(key_pair, public_key)?;
+    let prefix_len = ((MIN_PREFIX_LEN + 15 + data.len()) & !15) - data.len();
+    let mut plain = vec![0; prefix_len];
+    rand::rngs::OsRng.fill_bytes(&mut plain);
+    plain[0] = prefix_len as u8;
+    plain.extend_from_slice(data);
+
+    let data_hash = hmac_sha512(salt, &plain)?;
+    let msg_key = &data_hash[..MSG_KEY_LEN];
+    let mut encrypted: Vec<u8> = key_pair
+        .public_key
+        .iter()
+        .zip(public_key)
+        .map(|(a, b)| a ^ b)
+        .collect();
+    encrypted.extend_from_slice(msg_key);
+
+    let mut cbc_key = hmac_sha512(&secret, msg_key)?;
+    let len = plain.len();
+    cbc::Encryptor::<Aes256>::new(cbc_key[..32].into(), cbc_key[32..48].into())
+        .encrypt_padded_mut::<NoPadding>(&mut plain, len)
+        .map_err(|e| CryptoError::EncryptionError(e.to_string()))?;
+    encrypted.extend_from_slice(&plain);
+    zeroize(&mut secret);
+    zeroize(&mut cbc_key);
+    Ok(encrypted)
+}
+
+/// Decrypts data encrypted for the owner of the key pair, see [`encrypt_data`].
+pub fn decrypt_data(data: &[u8], key_pair: &KeyPair, salt: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if data.len() < PUBLIC_KEY_LEN + MSG_KEY_LEN + MIN_PREFIX_LEN
+        || !(data.len() - PUBLIC_KEY_LEN).is_multiple_of(16)
+    {
+        return Err(CryptoError::DecryptionError(format!(
+            "Invalid length of encrypted data: {}",
+            data.len()
+        )));
+    }
+    let (pub_xor, data) = data.split_at(PUBLIC_KEY_LEN);
+    let (msg_key, encrypted) = data.split_at(MSG_KEY_LEN);
+    let their_public_key: Vec<u8> = pub_xor
+        .iter()
+        .zip(&key_pair.public_key)
+        .map(|(a, b)| a ^ b)
+        .collect();
+    let mut secret = shared_secret(key_pair, &their_public_key)?;
+    let mut cbc_key = hmac_sha512(&secret, msg_key)?;
+    let mut plain = encrypted.to_vec();
+    cbc::Decryptor::<Aes256>::new(cbc_key[..32].into(), cbc_key[32..48].into())
+        .decrypt_padded_mut::<NoPadding>(&mut plain)
+        .map_err(|e| CryptoError::DecryptionError(e.to_string()))?;
+    zeroize(&mut secret);
+    zeroize(&mut cbc_key);
+
+    if hmac_sha512(salt, &plain)?[..MSG_KEY_LEN] != *msg_key {
+        return Err(CryptoError::DecryptionError("Hash mismatch".to_string()));
+    }
+    let prefix_len = plain[0] as usize;
+    if !(MIN_PREFIX_LEN..=plain.len()).contains(&prefix_len) {
+        return Err(CryptoError::DecryptionError(format!(
+            "Invalid prefix length: {}",
+            prefix_len
+        )));
+    }
+    Ok(plain.split_off(prefix_len))
+}
+
+/// Builds the body of a transfer from `sender` with a comment encrypted for the owner of
+/// `public_key`, the public key of the recipient wallet.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn encrypt_comment(
+    comment: &str,
+    key_pair: &KeyPair,
+    public_key: &[u8],
+    sender: &TonAddress,
+) -> Result<Cell, CryptoError> {
+    use crate::cell::CellBuilder;
+
+    let salt = sender.to_base64_url();
+    let encrypted = encrypt_data(comment.as_bytes(), key_pair, public_key, salt.as_bytes())?;
+    let (first, rest) = encrypted.split_at(FIRST_CELL_BYTES.min(encrypted.len()));
+    let mut next: Option<Cell> = None;
+    for chunk in rest.chunks(CELL_BYTES).rev() {
+        let mut builder = CellBuilder::new();
+        builder.store_slice(chunk)?;
+        if let Some(next) = next.take() {
+            builder.store_child(next)?;
+        }
+        next = Some(builder.build()?);
+    }
+    let mut builder = CellBuilder::new();
+    builder
+        .store_u32(32, ENCRYPTED_COMMENT)?
+        .store_slice(first)?;
+    if let Some(next) = next {
+        builder.store_child(next)?;
+    }
+    Ok(builder.build()?)
+}
+
+/// Decrypts the comment of a transfer from `sender` to the wallet of the key pair, e.g. the body
+/// of an incoming internal message with op [`ENCRYPTED_COMMENT`].
+pub fn decrypt_comment(
+    body: &Cell,
+    key_pair: &KeyPair,
+    sender: &TonAddress,
+) -> Result<String, CryptoError> {
+    let mut parser = body.parser();
+    let op = parser.load_u32(32)?;
+    if op != ENCRYPTED_COMMENT {
+        return Err(CryptoError::DecryptionError(format!(
+            "Not an encrypted comment, op: {:#x}",
+            op
+        )));
+    }
+    let remaining_bytes = parser.remaining_bytes();
+    let mut encrypted = parser.load_bytes(remaining_bytes)?;
+    let mut next = body.references.first();
+    while let Some(cell) = next {
+        encrypted.extend_from_slice(&cell.data[..cell.bit_len / 8]);
+        next = cell.references.first();
+    }
+    let salt = sender.to_base64_url();
+    let comment = decrypt_data(&encrypted, key_pair, salt.as_bytes())?;
+    String::from_utf8(comment).map_err(|e| CryptoError::DecryptionError(e.to_string()))
+}
+
+fn hmac_sha512(key: &[u8], data: &[u8]) -> Result<[u8; 64], CryptoError> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key)
+        .map_err(|e| CryptoError::EncryptionError(e.to_string()))?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::address::TonAddress;
+    use crate::crypto::{
+        decrypt_comment, decrypt_data, encrypt_comment, encrypt_data, shared_secret, CryptoError,
+        KeyPair,
+    };
+
+    #[test]
+    fn encrypted_comments_work() -> anyhow::Result<()> {
+        let sender = KeyPair::from_seed(&[1; 32]);
+        let recipient = KeyPair::from_seed(&[2; 32]);
+        let sender_address =
+            TonAddress::from_base64_url("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR")?;
+        assert_eq!(
+            shared_secret(&sender, &recipient.public_key)?,
+            shared_secret(&recipient, &sender.public_key)?
+        );
+
+        for comment in ["", "hello", &"long comment ".repeat(40)] {
+            let body = encrypt_comment(comment, &sender, &recipient.public_key, &sender_address)?;
+            assert_eq!(
+                decrypt_comment(&body, &recipient, &sender_address)?,
+                comment
+            );
+            // The sender can read its own comment too
+            assert_eq!(decrypt_comment(&body, &sender, &sender_address)?, comment);
+        }
+        let body = encrypt_comment("hello", &sender, &recipient.public_key, &sender_address)?;
+        assert!(decrypt_comment(&body, &recipient, &TonAddress::NULL).is_err());
+        assert!(decrypt_comment(&body, &KeyPair::from_seed(&[3; 32]), &sender_address).is_err());
+
+        let encrypted = encrypt_data(b"data", &sender, &recipient.public_key, b"salt")?;
+        assert_eq!(encrypted.len(), 32 + 16 + 32);
+        assert_eq!(decrypt_data(&encrypted, &recipient, b"salt")?, b"data");
+        assert!(matches!(
+            decrypt_data(&encrypted[..70], &recipient, b"salt"),
+            Err(CryptoError::DecryptionError(_))
+        ));
+        Ok(())
+    }
+}
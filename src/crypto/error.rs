@@ -0,0 +1,43 @@
+use thiserror::Error;
+
+use crate::cell::TonCellError;
+use crate::message::TonMessageError;
+
+#[derive(Debug, Error)]
+pub enum CryptoError {
+    #[error("Invalid key length (expected: {expected}, got: {actual})")]
+    InvalidKeyLength { expected: usize, actual: usize },
+
+    #[error("Invalid signature")]
+    InvalidSignature,
+
+    #[error("Unknown validator (node id: {0})")]
+    UnknownValidator(String),
+
+    #[error("Encryption error ({0})")]
+    EncryptionError(String),
+
+    #[error("Decryption error ({0})")]
+    DecryptionError(String),
+
+    #[error("NaCl cryptographic error ({0})")]
+    NaclError(String),
+
+    #[error("TonCellError ({0})")]
+    TonCellError(#[from] TonCellError),
+}
+
+impl From<nacl::Error> for CryptoError {
+    fn from(e: nacl::Error) -> Self {
+        CryptoError::NaclError(e.message)
+    }
+}
+
+impl From<CryptoError> for TonMessageError {
+    fn from(e: CryptoError) -> Self {
+        match e {
+            CryptoError::InvalidSignature => TonMessageError::InvalidSignature,
+            e => TonMessageError::NaclCryptographicError(e.to_string()),
+        }
+    }
+}
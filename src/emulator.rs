@@ -9,6 +9,7 @@ use self::types::TvmEmulatorMessageResponse;
 use crate::address::TonAddress;
 use crate::cell::{BagOfCells, Cell, CellBuilder};
 use crate::emulator::types::TvmEmulatorResponse;
+use crate::responses::ConfigParams;
 use crate::types::{TonMethodId, TvmMsgSuccess, TvmStackEntry, TvmSuccess};
 
 mod error;
@@ -76,6 +77,17 @@ impl<'a> TvmEmulatorC7Builder<'a> {
     }
 }
 
+impl TvmEmulatorC7 {
+    /// Replaces the config with the serialized params, e.g. to emulate with a tweaked gas price.
+    pub fn with_config_params(
+        &mut self,
+        config_params: &ConfigParams,
+    ) -> Result<&mut Self, TvmEmulatorError> {
+        self.config = BagOfCells::from_root(config_params.to_cell()?).serialize(false)?;
+        Ok(self)
+    }
+}
+
 impl TvmEmulator {
     pub fn new(code: &[u8], data: &[u8]) -> Result<TvmEmulator, TvmEmulatorError> {
         let emulator = TvmEmulatorUnsafe::create(code, data, DEFAULT_VM_LOG_VERBOSITY)?;
@@ -0,0 +1,314 @@
+use std::ops::Deref;
+
+use log::debug;
+use num_traits::Zero;
+
+use crate::address::TonAddress;
+use crate::cell::{Cell, CellParser, TonCellError};
+use crate::coins::Coins;
+use crate::constants::{JETTON_BURN, JETTON_TRANSFER};
+pub use crate::constants::{NFT_TRANSFER, TEXT_COMMENT};
+use crate::responses::{AnyCell, MessageType, Transaction, TransactionMessage};
+
+/// High-level event extracted from the inbound message of a transaction.
+///
+/// Every message is the inbound message of exactly one transaction, so extracting events
+/// from all transactions of a trace yields every event exactly once.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TonEvent {
+    TonTransfer(TonTransferEvent),
+    JettonTransfer(JettonTransferEvent),
+    JettonBurn(JettonBurnEvent),
+    NftTransfer(NftTransferEvent),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TonTransferEvent {
+    pub lt: u64,
+    pub sender: TonAddress,
+    pub recipient: TonAddress,
+    pub amount: Coins,
+    pub comment: Option<String>,
+    pub success: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct JettonTransferEvent {
+    pub lt: u64,
+    pub query_id: u64,
+    pub sender: TonAddress,
+    pub sender_jetton_wallet: TonAddress,
+    pub recipient: TonAddress,
+    pub response_destination: TonAddress,
+    pub amount: Coins,
+    pub forward_ton_amount: Coins,
+    pub success: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct JettonBurnEvent {
+    pub lt: u64,
+    pub query_id: u64,
+    pub owner: TonAddress,
+    pub jetton_wallet: TonAddress,
+    pub response_destination: TonAddress,
+    pub amount: Coins,
+    pub success: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct NftTransferEvent {
+    pub lt: u64,
+    pub query_id: u64,
+    pub nft_item: TonAddress,
+    pub prev_owner: TonAddress,
+    pub new_owner: TonAddress,
+    pub response_destination: TonAddress,
+    pub forward_amount: Coins,
+    pub success: bool,
+}
+
+/// Extracts events from the inbound messages of all given transactions, preserving their order.
+pub fn extract_events<'a, I>(transactions: I) -> Vec<TonEvent>
+where
+    I: IntoIterator<Item = &'a Transaction>,
+{
+    transactions
+        .into_iter()
+        .filter_map(extract_transaction_event)
+        .collect()
+}
+
+/// Extracts the event triggered by the inbound message of the transaction.
+///
+/// Returns `None` for external messages, bounced messages, messages with a pruned body and
+/// messages whose body is not recognized or can not be parsed.
+pub fn extract_transaction_event(transaction: &Transaction) -> Option<TonEvent> {
+    let in_msg = transaction.in_msg.data.as_ref()?;
+    if in_msg.info.msg_type != MessageType::Internal as u8 || in_msg.info.bounced {
+        return None;
+    }
+    let success = transaction
+        .description
+        .as_ref()
+        .map(|descr| descr.is_success())
+        .unwrap_or(false);
+    match parse_event(transaction.lt, in_msg, success) {
+        Ok(event) => event,
+        Err(e) => {
+            debug!(
+                "skipping unparseable message body of transaction {}: {:?}",
+                transaction.lt, e
+            );
+            None
+        }
+    }
+}
+
+fn parse_event(
+    lt: u64,
+    in_msg: &TransactionMessage,
+    success: bool,
+) -> Result<Option<TonEvent>, TonCellError> {
+    let info = &in_msg.info;
+    let body = match in_msg.body.any_cell() {
+        Some(body) => body,
+        None => return Ok(None),
+    };
+    let mut parser = body.parser()?;
+    if parser.remaining_bits() < 32 {
+        if parser.remaining_bits() > 0 || info.value.grams.value.is_zero() {
+            return Ok(None);
+        }
+        return Ok(Some(TonEvent::TonTransfer(TonTransferEvent {
+            lt,
+            sender: info.src.clone(),
+            recipient: info.dest.clone(),
+            amount: Coins::new(info.value.grams.value.clone()),
+            comment: None,
+            success,
+        })));
+    }
+    let event = match parser.load_u32(32)? {
+        TEXT_COMMENT => TonEvent::TonTransfer(TonTransferEvent {
+            lt,
+            sender: info.src.clone(),
+            recipient: info.dest.clone(),
+            amount: Coins::new(info.value.grams.value.clone()),
+            comment: Some(load_comment(body, &mut parser)?),
+            success,
+        }),
+        JETTON_TRANSFER => {
+            let query_id = parser.load_u64(64)?;
+            let amount = parser.load_coins()?;
+            let recipient = parser.load_address()?;
+            let response_destination = parser.load_address()?;
+            parser.load_bit()?; // custom_payload
+            let forward_ton_amount = parser.load_coins()?;
+            TonEvent::JettonTransfer(JettonTransferEvent {
+                lt,
+                query_id,
+                sender: info.src.clone(),
+                sender_jetton_wallet: info.dest.clone(),
+                recipient,
+                response_destination,
+                amount,
+                forward_ton_amount,
+                success,
+            })
+        }
+        JETTON_BURN => TonEvent::JettonBurn(JettonBurnEvent {
+            lt,
+            query_id: parser.load_u64(64)?,
+            owner: info.src.clone(),
+            jetton_wallet: info.dest.clone(),
+            amount: parser.load_coins()?,
+            response_destination: parser.load_address()?,
+            success,
+        }),
+        NFT_TRANSFER => {
+            let query_id = parser.load_u64(64)?;
+            let new_owner = parser.load_address()?;
+            let response_destination = parser.load_address()?;
+            parser.load_bit()?; // custom_payload
+            let forward_amount = parser.load_coins()?;
+            TonEvent::NftTransfer(NftTransferEvent {
+                lt,
+                query_id,
+                nft_item: info.dest.clone(),
+                prev_owner: info.src.clone(),
+                new_owner,
+                response_destination,
+                forward_amount,
+                success,
+            })
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(event))
+}
+
+/// Loads a snake-formatted comment following the op code.
+fn load_comment(body: &AnyCell, parser: &mut CellParser) -> Result<String, TonCellError> {
+    let remaining_bytes = parser.remaining_bytes();
+    let mut buffer = parser.load_bytes(remaining_bytes)?;
+    let mut next: Option<&Cell> = body
+        .cell
+        .references
+        .get(body.ref_index)
+        .map(|cell| cell.deref());
+    while let Some(cell) = next {
+        buffer.extend_from_slice(&cell.data[..cell.bit_len / 8]);
+        next = cell.references.first().map(|cell| cell.deref());
+    }
+    Ok(String::from_utf8_lossy(&buffer).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use crate::address::TonAddress;
+    use crate::cell::{Cell, CellBuilder};
+    use crate::coins::Coins;
+    use crate::events::{extract_transaction_event, TonEvent};
+    use crate::message::JETTON_TRANSFER;
+    use crate::responses::{
+        AnyCell, CommonTransactionMessageInfo, CurrencyCollection, MaybeRefData, MessageType,
+        Transaction, TransactionBody, TransactionMessage, VarUInteger,
+    };
+
+    fn transaction_with_body(src: &TonAddress, dest: &TonAddress, body: Cell) -> Transaction {
+        let info = CommonTransactionMessageInfo {
+            msg_type: MessageType::Internal as u8,
+            src: src.clone(),
+            dest: dest.clone(),
+            value: CurrencyCollection {
+                grams: VarUInteger {
+                    len: BigUint::from(4u32),
+                    value: BigUint::from(1_000_000_000u32),
+                },
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let in_msg = MaybeRefData {
+            data: Some(TransactionMessage {
+                hash: vec![],
+                info,
+                body: TransactionBody {
+                    any: None,
+                    cell_ref: Some((
+                        Some(AnyCell {
+                            cell: body,
+                            ref_index: 0,
+                            parser_positions_in_bits: 0,
+                        }),
+                        None,
+                    )),
+                },
+            }),
+            cell: None,
+            verified: false,
+        };
+        Transaction {
+            lt: 42,
+            in_msg,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn extracts_ton_transfer_with_comment() -> anyhow::Result<()> {
+        let src: TonAddress = "EQBiMfDMivebQb052Z6yR3jHrmwNhw1kQ5bcAUOBYsK_VPuK".parse()?;
+        let dest: TonAddress = "EQCDM_QGggZ3qMa_f3lRPk4_qLDnLTqdi6OkMAV2NB9r5TG3".parse()?;
+        let tail = CellBuilder::new().store_string(" world")?.build()?;
+        let body = CellBuilder::new()
+            .store_u32(32, 0)?
+            .store_string("hello")?
+            .store_child(tail)?
+            .build()?;
+        let event = extract_transaction_event(&transaction_with_body(&src, &dest, body));
+        match event {
+            Some(TonEvent::TonTransfer(transfer)) => {
+                assert_eq!(transfer.sender, src);
+                assert_eq!(transfer.recipient, dest);
+                assert_eq!(transfer.amount, Coins::from_ton(1));
+                assert_eq!(transfer.comment, Some("hello world".to_string()));
+            }
+            _ => panic!("expected a ton transfer event"),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn extracts_jetton_transfer() -> anyhow::Result<()> {
+        let owner: TonAddress = "EQBiMfDMivebQb052Z6yR3jHrmwNhw1kQ5bcAUOBYsK_VPuK".parse()?;
+        let jetton_wallet: TonAddress =
+            "EQCDM_QGggZ3qMa_f3lRPk4_qLDnLTqdi6OkMAV2NB9r5TG3".parse()?;
+        let recipient: TonAddress = "EQA-RswW9QONn88ziVm4UKnwXDEot5km7GEEXsfie_0TFOCO".parse()?;
+        let body = CellBuilder::new()
+            .store_u32(32, JETTON_TRANSFER)?
+            .store_u64(64, 7)?
+            .store_coins(BigUint::from(500u32))?
+            .store_address(&recipient)?
+            .store_address(&owner)?
+            .store_bit(false)?
+            .store_coins(BigUint::from(1u32))?
+            .store_bit(false)?
+            .build()?;
+        let event = extract_transaction_event(&transaction_with_body(&owner, &jetton_wallet, body));
+        match event {
+            Some(TonEvent::JettonTransfer(transfer)) => {
+                assert_eq!(transfer.query_id, 7);
+                assert_eq!(transfer.sender, owner);
+                assert_eq!(transfer.sender_jetton_wallet, jetton_wallet);
+                assert_eq!(transfer.recipient, recipient);
+                assert_eq!(transfer.amount, Coins::from_nano(500));
+                assert_eq!(transfer.forward_ton_amount, Coins::from_nano(1));
+            }
+            _ => panic!("expected a jetton transfer event"),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,422 @@
+//! C ABI of the core primitives.
+//!
+//! Enabled with the `ffi` feature, which, combined with `--no-default-features`, builds the
+//! library without linking `tonlibjson`. The shared library is built with
+//! `cargo rustc --lib --crate-type cdylib`. Every function returns a status code: [`TON_OK`]
+//! on success, a negative `TON_ERR_*` code for FFI-level failures or the positive
+//! [`TonCellErrorCode`] of a failed cell operation. [`ton_error_name`] returns the name of a
+//! code.
+//!
+//! Cells are passed as opaque [`TonCell`] handles. Handles, buffers and strings returned by
+//! the library are owned by the caller and must be released with [`ton_cell_free`],
+//! [`ton_buffer_free`] and [`ton_string_free`] respectively.
+
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::address::TonAddress;
+use crate::cell::{ArcCell, BagOfCells, CellBuilder, TonCellError, TonCellErrorCode};
+use crate::crypto::sign;
+use crate::message::{ExternalMessage, TonMessageError};
+
+pub const TON_OK: i32 = 0;
+pub const TON_ERR_NULL_POINTER: i32 = -1;
+pub const TON_ERR_INVALID_UTF8: i32 = -2;
+pub const TON_ERR_INVALID_ADDRESS: i32 = -3;
+pub const TON_ERR_INVALID_KEY: i32 = -4;
+pub const TON_ERR_PANIC: i32 = -5;
+
+/// Address formats accepted by [`ton_address_to_string`].
+pub const TON_ADDRESS_RAW: u32 = 0;
+pub const TON_ADDRESS_BASE64_URL: u32 = 1;
+pub const TON_ADDRESS_BASE64_STD: u32 = 2;
+
+/// Address flags accepted by [`ton_address_to_string`].
+pub const TON_ADDRESS_NON_BOUNCEABLE: u32 = 1;
+pub const TON_ADDRESS_NON_PRODUCTION: u32 = 2;
+
+/// Opaque handle of a cell.
+pub struct TonCell {
+    cell: ArcCell,
+}
+
+/// Byte buffer allocated by the library.
+#[repr(C)]
+pub struct TonBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+/// Returns the static, NUL-terminated name of a status code.
+#[no_mangle]
+pub extern "C" fn ton_error_name(code: i32) -> *const c_char {
+    let name: &'static CStr = match code {
+        TON_OK => c"OK",
+        TON_ERR_NULL_POINTER => c"NULL_POINTER",
+        TON_ERR_INVALID_UTF8 => c"INVALID_UTF8",
+        TON_ERR_INVALID_ADDRESS => c"INVALID_ADDRESS",
+        TON_ERR_INVALID_KEY => c"INVALID_KEY",
+        TON_ERR_PANIC => c"PANIC",
+        code => match TonCellErrorCode::try_from(code) {
+            Ok(kind) => cell_error_name(kind),
+            Err(_) => c"UNKNOWN",
+        },
+    };
+    name.as_ptr()
+}
+
+/// Returns the name of a cell error code, see [`TonCellErrorCode::as_str`].
+fn cell_error_name(kind: TonCellErrorCode) -> &'static CStr {
+    static NAMES: OnceLock<Vec<CString>> = OnceLock::new();
+    let names = NAMES.get_or_init(|| {
+        TonCellErrorCode::ALL
+            .iter()
+            .map(|kind| CString::new(kind.as_str()).unwrap())
+            .collect()
+    });
+    let index = TonCellErrorCode::ALL
+        .iter()
+        .position(|other| *other == kind)
+        .unwrap();
+    &names[index]
+}
+
+/// Parses a bag of cells with a single root.
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes and `out_cell` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ton_boc_parse(
+    data: *const u8,
+    len: usize,
+    out_cell: *mut *mut TonCell,
+) -> i32 {
+    guard(|| {
+        if data.is_null() || out_cell.is_null() {
+            return Err(TON_ERR_NULL_POINTER);
+        }
+        let serial = std::slice::from_raw_parts(data, len);
+        let boc = BagOfCells::parse(serial).map_err(cell_error)?;
+        let cell = boc.single_root().map_err(cell_error)?.clone();
+        *out_cell = new_handle(cell);
+        Ok(())
+    })
+}
+
+/// Serializes the cell as a bag of cells with a single root.
+///
+/// # Safety
+///
+/// `cell` must be a live handle and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ton_boc_serialize(
+    cell: *const TonCell,
+    has_crc32: bool,
+    out: *mut TonBuffer,
+) -> i32 {
+    guard(|| {
+        if cell.is_null() || out.is_null() {
+            return Err(TON_ERR_NULL_POINTER);
+        }
+        let boc = BagOfCells::new(std::slice::from_ref(&(*cell).cell));
+        let serial = boc.serialize(has_crc32).map_err(cell_error)?;
+        let mut serial = serial.into_boxed_slice();
+        *out = TonBuffer {
+            data: serial.as_mut_ptr(),
+            len: serial.len(),
+        };
+        std::mem::forget(serial);
+        Ok(())
+    })
+}
+
+/// Writes the 32-byte representation hash of the cell to `out_hash`.
+///
+/// # Safety
+///
+/// `cell` must be a live handle and `out_hash` must be valid for 32 bytes of writes.
+#[no_mangle]
+pub unsafe extern "C" fn ton_cell_hash(cell: *const TonCell, out_hash: *mut u8) -> i32 {
+    guard(|| {
+        if cell.is_null() || out_hash.is_null() {
+            return Err(TON_ERR_NULL_POINTER);
+        }
+        let hash = (*cell).cell.cell_hash_array().map_err(cell_error)?;
+        ptr::copy_nonoverlapping(hash.as_ptr(), out_hash, hash.len());
+        Ok(())
+    })
+}
+
+/// Parses an address in raw, base64 or base64url form into its workchain and 32-byte hash part.
+///
+/// # Safety
+///
+/// `address` must be a NUL-terminated string, `out_workchain` must be valid for writes and
+/// `out_hash_part` must be valid for 32 bytes of writes.
+#[no_mangle]
+pub unsafe extern "C" fn ton_address_parse(
+    address: *const c_char,
+    out_workchain: *mut i32,
+    out_hash_part: *mut u8,
+) -> i32 {
+    guard(|| {
+        if out_workchain.is_null() || out_hash_part.is_null() {
+            return Err(TON_ERR_NULL_POINTER);
+        }
+        let address = parse_address(address)?;
+        *out_workchain = address.workchain;
+        ptr::copy_nonoverlapping(address.hash_part.as_ptr(), out_hash_part, 32);
+        Ok(())
+    })
+}
+
+/// Formats an address as one of the `TON_ADDRESS_*` formats. Flags are ignored for the raw
+/// format.
+///
+/// # Safety
+///
+/// `hash_part` must point to 32 readable bytes and `out` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ton_address_to_string(
+    workchain: i32,
+    hash_part: *const u8,
+    format: u32,
+    flags: u32,
+    out: *mut *mut c_char,
+) -> i32 {
+    guard(|| {
+        if hash_part.is_null() || out.is_null() {
+            return Err(TON_ERR_NULL_POINTER);
+        }
+        let mut hash = [0u8; 32];
+        ptr::copy_nonoverlapping(hash_part, hash.as_mut_ptr(), 32);
+        let address = TonAddress::new(workchain, &hash);
+        let non_bounceable = flags & TON_ADDRESS_NON_BOUNCEABLE != 0;
+        let non_production = flags & TON_ADDRESS_NON_PRODUCTION != 0;
+        let formatted = match format {
+            TON_ADDRESS_RAW => address.to_hex(),
+            TON_ADDRESS_BASE64_URL => address.to_base64_url_flags(non_bounceable, non_production),
+            TON_ADDRESS_BASE64_STD => address.to_base64_std_flags(non_bounceable, non_production),
+            _ => return Err(TON_ERR_INVALID_ADDRESS),
+        };
+        // Formatted addresses never contain NUL bytes
+        *out = CString::new(formatted).unwrap().into_raw();
+        Ok(())
+    })
+}
+
+/// Builds an external-in message to `dest` with the body and the optional state init stored
+/// in references.
+///
+/// If `secret_key` is not null, the body is prefixed with the ed25519 signature of its hash,
+/// as expected by wallet contracts.
+///
+/// # Safety
+///
+/// `dest` must be a NUL-terminated string, `body` a live handle, `state_init` a live handle
+/// or null, `secret_key` null or pointing to 64 readable bytes and `out_cell` valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn ton_external_message_build(
+    dest: *const c_char,
+    body: *const TonCell,
+    state_init: *const TonCell,
+    secret_key: *const u8,
+    out_cell: *mut *mut TonCell,
+) -> i32 {
+    guard(|| {
+        if body.is_null() || out_cell.is_null() {
+            return Err(TON_ERR_NULL_POINTER);
+        }
+        let dest = parse_address(dest)?;
+        let mut body = (*body).cell.clone();
+        if !secret_key.is_null() {
+            let secret_key = std::slice::from_raw_parts(secret_key, 64);
+            let hash = body.cell_hash().map_err(cell_error)?;
+            let sig = sign(hash.as_slice(), secret_key).map_err(|_| TON_ERR_INVALID_KEY)?;
+            let signed = CellBuilder::new()
+                .store_slice(sig.as_slice())
+                .and_then(|builder| builder.store_cell(&body))
+                .and_then(|builder| builder.build())
+                .map_err(cell_error)?;
+            body = signed.to_arc();
+        }
+        let mut message = ExternalMessage::new(&dest);
+        message.with_body_ref(&body);
+        if !state_init.is_null() {
+            message.with_state_init_ref(&(*state_init).cell);
+        }
+        let cell = message.build().map_err(message_error)?;
+        *out_cell = new_handle(cell.to_arc());
+        Ok(())
+    })
+}
+
+/// Releases a cell handle. Null is ignored.
+///
+/// # Safety
+///
+/// `cell` must be null or a live handle, which must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn ton_cell_free(cell: *mut TonCell) {
+    if !cell.is_null() {
+        drop(Box::from_raw(cell));
+    }
+}
+
+/// Releases a buffer returned by the library.
+///
+/// # Safety
+///
+/// `buffer` must have been returned by the library and not released before.
+#[no_mangle]
+pub unsafe extern "C" fn ton_buffer_free(buffer: TonBuffer) {
+    if !buffer.data.is_null() {
+        drop(Box::from_raw(ptr::slice_from_raw_parts_mut(
+            buffer.data,
+            buffer.len,
+        )));
+    }
+}
+
+/// Releases a string returned by the library. Null is ignored.
+///
+/// # Safety
+///
+/// `string` must be null or returned by the library and not released before.
+#[no_mangle]
+pub unsafe extern "C" fn ton_string_free(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+fn guard<F: FnOnce() -> Result<(), i32>>(f: F) -> i32 {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => TON_OK,
+        Ok(Err(code)) => code,
+        Err(_) => TON_ERR_PANIC,
+    }
+}
+
+fn new_handle(cell: ArcCell) -> *mut TonCell {
+    Box::into_raw(Box::new(TonCell { cell }))
+}
+
+unsafe fn parse_address(address: *const c_char) -> Result<TonAddress, i32> {
+    if address.is_null() {
+        return Err(TON_ERR_NULL_POINTER);
+    }
+    let address = CStr::from_ptr(address)
+        .to_str()
+        .map_err(|_| TON_ERR_INVALID_UTF8)?;
+    TonAddress::from_str(address).map_err(|_| TON_ERR_INVALID_ADDRESS)
+}
+
+fn cell_error(error: TonCellError) -> i32 {
+    error.code() as i32
+}
+
+fn message_error(error: TonMessageError) -> i32 {
+    match error {
+        TonMessageError::TonCellError(e) => cell_error(e),
+        TonMessageError::NaclCryptographicError(_) | TonMessageError::InvalidSignature => {
+            TON_ERR_INVALID_KEY
+        }
+        TonMessageError::RequestRejected(_)
+        | TonMessageError::TonConnectError(_)
+        | TonMessageError::InvalidBagId(_) => TonCellErrorCode::Internal as i32,
+        TonMessageError::ForwardTonAmountIsNegative | TonMessageError::MessageLimitsExceeded(_) => {
+            TonCellErrorCode::CellBuilder as i32
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::{CStr, CString};
+    use std::ptr;
+
+    use crate::cell::{BagOfCells, CellBuilder};
+    use crate::ffi::*;
+
+    #[test]
+    fn ffi_round_trip_works() -> anyhow::Result<()> {
+        let cell = CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?;
+        let serial = BagOfCells::from_root(cell.clone()).serialize(true)?;
+        unsafe {
+            let mut handle = ptr::null_mut();
+            assert_eq!(
+                ton_boc_parse(serial.as_ptr(), serial.len(), &mut handle),
+                TON_OK
+            );
+            let mut hash = [0u8; 32];
+            assert_eq!(ton_cell_hash(handle, hash.as_mut_ptr()), TON_OK);
+            assert_eq!(hash.to_vec(), cell.cell_hash()?);
+            let mut buffer = TonBuffer {
+                data: ptr::null_mut(),
+                len: 0,
+            };
+            assert_eq!(ton_boc_serialize(handle, true, &mut buffer), TON_OK);
+            assert_eq!(std::slice::from_raw_parts(buffer.data, buffer.len), serial);
+            ton_buffer_free(buffer);
+
+            let dest = CString::new("EQBiMfDMivebQb052Z6yR3jHrmwNhw1kQ5bcAUOBYsK_VPuK")?;
+            let mut message = ptr::null_mut();
+            let status = ton_external_message_build(
+                dest.as_ptr(),
+                handle,
+                ptr::null(),
+                ptr::null(),
+                &mut message,
+            );
+            assert_eq!(status, TON_OK);
+            ton_cell_free(message);
+            ton_cell_free(handle);
+
+            let garbage = [1u8, 2, 3];
+            let status = ton_boc_parse(garbage.as_ptr(), garbage.len(), &mut handle);
+            assert_eq!(status, TonCellErrorCode::BagOfCellsDeserialization as i32);
+            assert_eq!(
+                CStr::from_ptr(ton_error_name(status)).to_str()?,
+                TonCellErrorCode::BagOfCellsDeserialization.as_str()
+            );
+            for kind in TonCellErrorCode::ALL {
+                assert_eq!(
+                    CStr::from_ptr(ton_error_name(kind as i32)).to_str()?,
+                    kind.as_str()
+                );
+            }
+            assert_eq!(CStr::from_ptr(ton_error_name(1000)).to_str()?, "UNKNOWN");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn ffi_address_conversion_works() -> anyhow::Result<()> {
+        let raw = "0:6231f0cc8af79b41bd39d99eb24778c7ae6c0d870d644396dc01438162c2bf54";
+        unsafe {
+            let mut workchain = 0;
+            let mut hash_part = [0u8; 32];
+            let address = CString::new("EQBiMfDMivebQb052Z6yR3jHrmwNhw1kQ5bcAUOBYsK_VPuK")?;
+            let status =
+                ton_address_parse(address.as_ptr(), &mut workchain, hash_part.as_mut_ptr());
+            assert_eq!(status, TON_OK);
+            let mut out = ptr::null_mut();
+            let status =
+                ton_address_to_string(workchain, hash_part.as_ptr(), TON_ADDRESS_RAW, 0, &mut out);
+            assert_eq!(status, TON_OK);
+            assert_eq!(CStr::from_ptr(out).to_str()?, raw);
+            ton_string_free(out);
+
+            let invalid = CString::new("not an address")?;
+            let status =
+                ton_address_parse(invalid.as_ptr(), &mut workchain, hash_part.as_mut_ptr());
+            assert_eq!(status, TON_ERR_INVALID_ADDRESS);
+        }
+        Ok(())
+    }
+}
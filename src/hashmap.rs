@@ -1,10 +1,16 @@
-use std::{collections::HashMap, fmt::Debug};
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Debug;
 
-use log::debug;
 use num_bigint::BigUint;
 use num_traits::FromPrimitive;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
 use crate::cell::{Cell, CellParser, CellType, TonCellError};
+use crate::compat::HashMap;
+use crate::trace::parse_event;
 
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub enum HashMapType {
@@ -108,9 +114,7 @@ where
             }
             return Ok(());
         }
-        debug!("cell type in load hashmap: {:?}", cell.cell_type);
-        debug!("cell bits: {:?}", cell.data);
-        debug!("current n & fork: {:?}, {:?}", n, fork);
+        parse_event!("loading hashmap node", key = key, n = n, fork = fork);
         if n == 0 && fork {
             let data = (self.f)(cell, ref_index, parser, &key)?;
             if let Some(data) = data {
@@ -122,7 +126,6 @@ where
         if fork {
             // left
             let left: BigUint = key << 1; // pow 2
-            debug!("left key: {:?}", left);
             let left_ref_cell = cell.reference(ref_index.to_owned())?;
             let left_parser = &mut left_ref_cell.parser();
             self.load_hashmap(
@@ -134,11 +137,9 @@ where
                 !fork,
             )?;
             *ref_index += 1;
-            debug!("left ref cell data: {:?}", left_ref_cell.data);
 
             // right
             let right = left + BigUint::from_u8(1).unwrap();
-            debug!("right key: {:?}", right);
             let right_ref_cell = cell.reference(ref_index.to_owned())?;
             let right_parser = &mut right_ref_cell.parser();
             self.load_hashmap(
@@ -150,18 +151,18 @@ where
                 !fork,
             )?;
             *ref_index += 1;
-            debug!("right ref cell data: {:?}", right_ref_cell.data);
-
-            debug!("ref index after recursion: {:?}", ref_index);
             return Ok(());
         } else {
             let label = parser.load_label(n)?;
-            debug!("label: {:?}", label);
             if label.1 > 0 {
                 let next_key = key << label.1 | label.0;
                 let m = n - usize::try_from(label.1).map_err(TonCellError::cell_parser_error)?;
-                debug!("next key: {:?}", next_key);
-                debug!("m: {:?}", m);
+                parse_event!(
+                    "loaded hashmap label",
+                    key = next_key,
+                    label_len = label.1,
+                    m = m
+                );
                 self.load_hashmap(cell, ref_index, parser, m, next_key, !fork)?;
             } else {
                 self.load_hashmap(cell, ref_index, parser, n, key, !fork)?;
@@ -172,6 +173,7 @@ where
 }
 
 #[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct HashmapAugResult<T1, T2>
 where
     T1: Clone + Debug + Default,
@@ -182,6 +184,7 @@ where
 }
 
 #[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct HashmapAugEResult<T1, T2>
 where
     T1: Clone + Debug + Default,
@@ -1,33 +1,68 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(any(feature = "std", feature = "alloc")))]
+compile_error!("either the `std` or the `alloc` feature must be enabled");
+#[cfg(all(test, not(feature = "std")))]
+compile_error!("tests need the `std` feature");
+
+#[macro_use]
+extern crate alloc;
 extern crate core;
 
 pub mod address;
+pub mod adnl;
+#[cfg(feature = "std")]
+pub mod archive;
 pub mod cell;
+pub mod coins;
+pub mod constants;
+pub mod network;
+
+#[cfg(feature = "std")]
+pub mod events;
 
+#[cfg(feature = "std")]
 pub mod message;
 
 pub mod hashmap;
 
 pub mod responses;
 
+#[cfg(all(feature = "ffi", not(target_arch = "wasm32")))]
+pub mod ffi;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+#[cfg(feature = "tlb_codegen")]
+pub mod tlb_codegen;
+
+// Key derivation and wallet messages don't need tonlibjson, so `wasm` and `ffi` enable them
+#[cfg(feature = "crypto")]
+pub mod crypto;
+#[cfg(feature = "crypto")]
+pub mod mnemonic;
+#[cfg(feature = "crypto")]
+pub mod wallet;
+
+mod compat;
+mod trace;
+
 #[doc = include_str!("../README.md")]
 #[cfg(doctest)]
 pub struct ReadmeDoctests;
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "tonlibjson", not(target_arch = "wasm32")))]
 pub mod client;
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "tonlibjson", not(target_arch = "wasm32")))]
 pub mod config;
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "tonlibjson", not(target_arch = "wasm32")))]
 pub mod contract;
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "tonlibjson", not(target_arch = "wasm32")))]
 pub mod emulator;
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "tonlibjson", not(target_arch = "wasm32")))]
 pub mod meta;
-#[cfg(not(target_arch = "wasm32"))]
-pub mod mnemonic;
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "tonlibjson", not(target_arch = "wasm32")))]
 pub mod tl;
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(all(feature = "tonlibjson", not(target_arch = "wasm32")))]
 pub mod types;
-#[cfg(not(target_arch = "wasm32"))]
-pub mod wallet;
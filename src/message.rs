@@ -1,15 +1,31 @@
+pub use airdrop::*;
+pub use common_msg_info::*;
+pub use dns::*;
 pub use error::*;
+pub use external::*;
 pub use jetton::*;
+pub use limits::*;
+pub use nft::*;
+pub use normalize::*;
+pub use out_action::*;
+pub use out_message::*;
+pub use send_mode::*;
+pub use storage::*;
+pub use ton_connect::*;
 pub use transfer::*;
 
+mod airdrop;
+mod common_msg_info;
+mod dns;
 mod error;
+mod external;
 mod jetton;
+mod limits;
+mod nft;
+mod normalize;
+mod out_action;
+mod out_message;
+mod send_mode;
+mod storage;
+mod ton_connect;
 mod transfer;
-
-use lazy_static::lazy_static;
-use num_bigint::BigUint;
-use num_traits::Zero;
-
-lazy_static! {
-    pub(crate) static ref ZERO_COINS: BigUint = BigUint::zero();
-}
@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+use crate::address::TonAddress;
+use crate::cell::{
+    merkle_proof, prune_dict, ArcCell, Cell, CellBuilder, CellParser, CellType, DictBuilder,
+    TonCellError,
+};
+use crate::coins::Coins;
+use crate::hashmap::Hashmap;
+
+// Constants from mintless jetton standard
+// https://github.com/ton-community/mintless-jetton
+
+pub const MERKLE_AIRDROP_CLAIM: u32 = 0x0df602d6;
+
+/// Bit length of the keys of the airdrop dictionary, i.e. of `addr_std` without anycast.
+pub const AIRDROP_KEY_BITS: usize = 267;
+
+/// Entry of the airdrop dictionary, whose root hash is stored by the jetton master:
+///
+/// ```raw
+/// _ amount:Coins start_from:uint48 expired_at:uint48 = AirdropItem;
+/// _ _(HashMap 267 AirdropItem) = Airdrop;
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AirdropItem {
+    pub amount: Coins,
+    /// Time the item can be claimed from.
+    pub start_from: u64,
+    /// Time the item can't be claimed anymore.
+    pub expired_at: u64,
+}
+
+impl AirdropItem {
+    pub fn new(amount: &Coins, start_from: u64, expired_at: u64) -> AirdropItem {
+        AirdropItem {
+            amount: amount.clone(),
+            start_from,
+            expired_at,
+        }
+    }
+
+    pub fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        builder
+            .store_coins(&self.amount)?
+            .store_u64(48, self.start_from)?
+            .store_u64(48, self.expired_at)?;
+        Ok(())
+    }
+
+    pub fn load(parser: &mut CellParser) -> Result<AirdropItem, TonCellError> {
+        Ok(AirdropItem {
+            amount: parser.load_coins()?,
+            start_from: parser.load_u64(48)?,
+            expired_at: parser.load_u64(48)?,
+        })
+    }
+}
+
+/// Builder of the dictionary of a mintless jetton airdrop.
+#[derive(Clone, Debug, Default)]
+pub struct AirdropBuilder {
+    items: BTreeMap<BigUint, AirdropItem>,
+}
+
+impl AirdropBuilder {
+    pub fn new() -> AirdropBuilder {
+        Default::default()
+    }
+
+    /// Adds the item of the recipient, replacing its previous item.
+    pub fn with_item(&mut self, recipient: &TonAddress, item: AirdropItem) -> &mut Self {
+        self.items.insert(airdrop_key(recipient), item);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn build(&self) -> Result<Airdrop, TonCellError> {
+        let mut dict = DictBuilder::new(AIRDROP_KEY_BITS);
+        for (key, item) in &self.items {
+            let mut builder = CellBuilder::new();
+            item.store(&mut builder)?;
+            dict.insert(key.clone(), builder.build()?)?;
+        }
+        let root = dict
+            .build()?
+            .ok_or_else(|| TonCellError::cell_builder_error("Airdrop has no items"))?;
+        Ok(Airdrop {
+            root: Arc::new(root),
+        })
+    }
+}
+
+/// Dictionary of a mintless jetton airdrop, with the proofs of its items.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Airdrop {
+    root: ArcCell,
+}
+
+impl Airdrop {
+    pub fn new(root: &ArcCell) -> Airdrop {
+        Airdrop { root: root.clone() }
+    }
+
+    /// Returns the root cell of the dictionary.
+    pub fn root(&self) -> &ArcCell {
+        &self.root
+    }
+
+    /// Returns the hash of the dictionary, stored as `merkle_hash` by the jetton master.
+    pub fn merkle_root(&self) -> Result<Vec<u8>, TonCellError> {
+        Ok(self.root.virtual_hash(0)?.to_vec())
+    }
+
+    /// Returns the Merkle proof of the item of the recipient, keeping only the path to its
+    /// leaf in the dictionary.
+    pub fn proof(&self, recipient: &TonAddress) -> Result<Cell, TonCellError> {
+        let key = airdrop_key(recipient);
+        let pruned = prune_dict(&self.root, AIRDROP_KEY_BITS, &[key])?;
+        merkle_proof(&pruned)
+    }
+
+    /// Returns the custom payload of the jetton transfer claiming the item of the recipient:
+    ///
+    /// ```raw
+    /// merkle_airdrop_claim#0df602d6 proof:^Cell = CustomPayload;
+    /// ```
+    pub fn claim_payload(&self, recipient: &TonAddress) -> Result<Cell, TonCellError> {
+        CellBuilder::new()
+            .store_u32(32, MERKLE_AIRDROP_CLAIM)?
+            .store_child(self.proof(recipient)?)?
+            .build()
+    }
+
+    /// Returns the item of the recipient proven by a Merkle proof of the airdrop with the
+    /// given root hash, or `None` if the proof shows the recipient has no item.
+    pub fn verify_proof(
+        proof: &Cell,
+        merkle_root: &[u8],
+        recipient: &TonAddress,
+    ) -> Result<Option<AirdropItem>, TonCellError> {
+        if proof.cell_type != CellType::MerkleProofCell as u8 {
+            return Err(TonCellError::unexpected_structure("MERKLE_PROOF"));
+        }
+        let root = proof.reference(0)?;
+        if root.virtual_hash(0)? != merkle_root {
+            return Err(TonCellError::InternalError(
+                "Proof doesn't match the airdrop merkle root".to_string(),
+            ));
+        }
+        let load_item = |_cell: &Cell,
+                         _ref_index: &mut usize,
+                         parser: &mut CellParser,
+                         _key: &BigUint|
+         -> Result<Option<AirdropItem>, TonCellError> {
+            Ok(Some(AirdropItem::load(parser)?))
+        };
+        let mut dict = Hashmap::new(AIRDROP_KEY_BITS, load_item);
+        dict.load_hashmap(
+            root,
+            &mut 0,
+            &mut root.parser(),
+            AIRDROP_KEY_BITS,
+            BigUint::zero(),
+            false,
+        )?;
+        let key = airdrop_key(recipient).to_str_radix(16);
+        if let Some(item) = dict.map.remove(&key) {
+            return Ok(Some(item));
+        }
+        // Absence is only proven if the path to the key isn't pruned
+        let key_bits = airdrop_key(recipient).to_str_radix(2);
+        if dict
+            .pruned
+            .iter()
+            .any(|prefix| key_bits.starts_with(prefix))
+        {
+            return Err(TonCellError::InternalError(
+                "Proof doesn't contain the recipient".to_string(),
+            ));
+        }
+        Ok(None)
+    }
+}
+
+/// Returns the key of the recipient, i.e. `addr_std$10 anycast:(Maybe Anycast)
+/// workchain_id:int8 address:bits256` without anycast.
+pub fn airdrop_key(recipient: &TonAddress) -> BigUint {
+    let tag = BigUint::from(0b100u32) << 264;
+    let workchain = BigUint::from(recipient.workchain as i8 as u8) << 256;
+    tag | workchain | BigUint::from_bytes_be(&recipient.hash_part)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::address::TonAddress;
+    use crate::cell::{BagOfCells, CellBuilder};
+    use crate::coins::Coins;
+    use crate::message::{airdrop_key, Airdrop, AirdropBuilder, AirdropItem, MERKLE_AIRDROP_CLAIM};
+
+    #[test]
+    fn airdrop_proofs_work() -> anyhow::Result<()> {
+        let recipients: Vec<TonAddress> = (0..20u8)
+            .map(|i| TonAddress::new(if i % 5 == 0 { -1 } else { 0 }, &[i * 13; 32]))
+            .collect();
+        let mut builder = AirdropBuilder::new();
+        for (i, recipient) in recipients.iter().enumerate() {
+            let amount = Coins::from_ton(i as u64 + 1);
+            builder.with_item(recipient, AirdropItem::new(&amount, 1000, 2000));
+        }
+        assert_eq!(builder.len(), 20);
+        let airdrop = builder.build()?;
+        let merkle_root = airdrop.merkle_root()?;
+
+        // The key is the address as stored by store_address
+        let stored = CellBuilder::new().store_address(&recipients[5])?.build()?;
+        assert_eq!(stored.parser().load_uint(267)?, airdrop_key(&recipients[5]));
+
+        let payload = airdrop.claim_payload(&recipients[7])?;
+        let serial = BagOfCells::from_root(payload).serialize(true)?;
+        let payload = BagOfCells::parse(&serial)?.single_root()?.clone();
+        assert_eq!(payload.parser().load_u32(32)?, MERKLE_AIRDROP_CLAIM);
+        let proof = payload.reference(0)?;
+        let item = Airdrop::verify_proof(proof, &merkle_root, &recipients[7])?;
+        assert_eq!(
+            item,
+            Some(AirdropItem::new(&Coins::from_ton(8), 1000, 2000))
+        );
+        assert!(Airdrop::verify_proof(proof, &merkle_root, &recipients[8]).is_err());
+        assert!(Airdrop::verify_proof(proof, &[0; 32], &recipients[7]).is_err());
+
+        let outsider = TonAddress::new(0, &[1; 32]);
+        let proof = airdrop.proof(&outsider)?;
+        assert!(Airdrop::verify_proof(&proof, &merkle_root, &outsider).is_err());
+        Ok(())
+    }
+}
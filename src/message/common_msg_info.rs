@@ -0,0 +1,470 @@
+use std::collections::HashMap;
+
+use num_bigint::BigUint;
+
+use crate::address::TonAddress;
+use crate::cell::{ArcCell, Cell, CellBuilder, CellParser, TonCellError};
+use crate::coins::Coins;
+use crate::message::TonMessageError;
+
+/// Header of an internal message:
+///
+/// ```raw
+/// int_msg_info$0 ihr_disabled:Bool bounce:Bool bounced:Bool
+///   src:MsgAddressInt dest:MsgAddressInt
+///   value:CurrencyCollection ihr_fee:Grams fwd_fee:Grams
+///   created_lt:uint64 created_at:uint32 = CommonMsgInfo;
+/// ```
+///
+/// `src`, fees, `created_lt` and `created_at` are zero in messages sent by wallets and filled
+/// by the validators, see [`InternalMessageInfo::with_created`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InternalMessageInfo {
+    pub ihr_disabled: bool,
+    pub bounce: bool,
+    pub bounced: bool,
+    pub src: TonAddress,
+    pub dest: TonAddress,
+    pub value: Coins,
+    pub extra_currencies: HashMap<u32, BigUint>,
+    pub ihr_fee: Coins,
+    pub fwd_fee: Coins,
+    pub created_lt: u64,
+    pub created_at: u32,
+}
+
+impl InternalMessageInfo {
+    pub fn new(dest: &TonAddress, value: &Coins) -> InternalMessageInfo {
+        InternalMessageInfo {
+            ihr_disabled: true,
+            bounce: true,
+            bounced: false,
+            src: TonAddress::NULL,
+            dest: dest.clone(),
+            value: value.clone(),
+            extra_currencies: HashMap::new(),
+            ihr_fee: Coins::zero(),
+            fwd_fee: Coins::zero(),
+            created_lt: 0,
+            created_at: 0,
+        }
+    }
+
+    pub fn with_src(&mut self, src: &TonAddress) -> &mut Self {
+        self.src = src.clone();
+        self
+    }
+
+    pub fn with_bounce(&mut self, bounce: bool) -> &mut Self {
+        self.bounce = bounce;
+        self
+    }
+
+    pub fn with_bounced(&mut self, bounced: bool) -> &mut Self {
+        self.bounced = bounced;
+        self
+    }
+
+    pub fn with_ihr_disabled(&mut self, ihr_disabled: bool) -> &mut Self {
+        self.ihr_disabled = ihr_disabled;
+        self
+    }
+
+    pub fn with_extra_currency(&mut self, id: u32, amount: &BigUint) -> &mut Self {
+        self.extra_currencies.insert(id, amount.clone());
+        self
+    }
+
+    pub fn with_fees(&mut self, ihr_fee: &Coins, fwd_fee: &Coins) -> &mut Self {
+        self.ihr_fee = ihr_fee.clone();
+        self.fwd_fee = fwd_fee.clone();
+        self
+    }
+
+    /// Sets the logical time and the unix time of the transaction which created the message.
+    pub fn with_created(&mut self, created_lt: u64, created_at: u32) -> &mut Self {
+        self.created_lt = created_lt;
+        self.created_at = created_at;
+        self
+    }
+
+    pub fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        builder.store_bit(false)?; // int_msg_info
+        builder.store_bit(self.ihr_disabled)?;
+        builder.store_bit(self.bounce)?;
+        builder.store_bit(self.bounced)?;
+        builder.store_address(&self.src)?;
+        builder.store_address(&self.dest)?;
+        builder.store_coins(&self.value)?;
+        builder.store_extra_currencies(&self.extra_currencies)?;
+        builder.store_coins(&self.ihr_fee)?;
+        builder.store_coins(&self.fwd_fee)?;
+        builder.store_u64(64, self.created_lt)?;
+        builder.store_u32(32, self.created_at)?;
+        Ok(())
+    }
+
+    pub fn load(parser: &mut CellParser) -> Result<InternalMessageInfo, TonCellError> {
+        if parser.load_bit()? {
+            return Err(TonCellError::unexpected_structure("int_msg_info"));
+        }
+        InternalMessageInfo::load_fields(parser)
+    }
+
+    fn load_fields(parser: &mut CellParser) -> Result<InternalMessageInfo, TonCellError> {
+        Ok(InternalMessageInfo {
+            ihr_disabled: parser.load_bit()?,
+            bounce: parser.load_bit()?,
+            bounced: parser.load_bit()?,
+            src: parser.load_address()?,
+            dest: parser.load_address()?,
+            value: parser.load_coins()?,
+            extra_currencies: parser.load_extra_currencies()?,
+            ihr_fee: parser.load_coins()?,
+            fwd_fee: parser.load_coins()?,
+            created_lt: parser.load_u64(64)?,
+            created_at: parser.load_u32(32)?,
+        })
+    }
+}
+
+/// Header of an inbound external message:
+///
+/// ```raw
+/// ext_in_msg_info$10 src:MsgAddressExt dest:MsgAddressInt
+///   import_fee:Grams = CommonMsgInfo;
+/// ```
+///
+/// `src` is `None`, i.e. `addr_none`, in messages sent to the network.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternalInMessageInfo {
+    pub src: Option<ExternalAddress>,
+    pub dest: TonAddress,
+    pub import_fee: Coins,
+}
+
+impl ExternalInMessageInfo {
+    pub fn new(dest: &TonAddress) -> ExternalInMessageInfo {
+        ExternalInMessageInfo {
+            src: None,
+            dest: dest.clone(),
+            import_fee: Coins::zero(),
+        }
+    }
+
+    pub fn with_src(&mut self, src: &ExternalAddress) -> &mut Self {
+        self.src = Some(src.clone());
+        self
+    }
+
+    pub fn with_import_fee(&mut self, import_fee: &Coins) -> &mut Self {
+        self.import_fee = import_fee.clone();
+        self
+    }
+
+    pub fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        builder.store_u8(2, 0b10)?; // ext_in_msg_info
+        ExternalAddress::store_opt(builder, self.src.as_ref())?;
+        builder.store_address(&self.dest)?;
+        builder.store_coins(&self.import_fee)?;
+        Ok(())
+    }
+
+    pub fn load(parser: &mut CellParser) -> Result<ExternalInMessageInfo, TonCellError> {
+        if parser.load_u8(2)? != 0b10 {
+            return Err(TonCellError::unexpected_structure("ext_in_msg_info"));
+        }
+        ExternalInMessageInfo::load_fields(parser)
+    }
+
+    fn load_fields(parser: &mut CellParser) -> Result<ExternalInMessageInfo, TonCellError> {
+        Ok(ExternalInMessageInfo {
+            src: ExternalAddress::load_opt(parser)?,
+            dest: parser.load_address()?,
+            import_fee: parser.load_coins()?,
+        })
+    }
+}
+
+/// Header of an outbound external message, e.g. an event emitted by a contract:
+///
+/// ```raw
+/// ext_out_msg_info$11 src:MsgAddressInt dest:MsgAddressExt
+///   created_lt:uint64 created_at:uint32 = CommonMsgInfo;
+/// ```
+///
+/// `src`, `created_lt` and `created_at` are zero in the out actions of contracts and filled
+/// by the validators.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternalOutMessageInfo {
+    pub src: TonAddress,
+    /// `None` for `addr_none`
+    pub dest: Option<ExternalAddress>,
+    pub created_lt: u64,
+    pub created_at: u32,
+}
+
+impl ExternalOutMessageInfo {
+    pub fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        builder.store_u8(2, 0b11)?; // ext_out_msg_info
+        builder.store_address(&self.src)?;
+        ExternalAddress::store_opt(builder, self.dest.as_ref())?;
+        builder.store_u64(64, self.created_lt)?;
+        builder.store_u32(32, self.created_at)?;
+        Ok(())
+    }
+
+    pub fn load(parser: &mut CellParser) -> Result<ExternalOutMessageInfo, TonCellError> {
+        if parser.load_u8(2)? != 0b11 {
+            return Err(TonCellError::unexpected_structure("ext_out_msg_info"));
+        }
+        ExternalOutMessageInfo::load_fields(parser)
+    }
+
+    fn load_fields(parser: &mut CellParser) -> Result<ExternalOutMessageInfo, TonCellError> {
+        Ok(ExternalOutMessageInfo {
+            src: parser.load_address()?,
+            dest: ExternalAddress::load_opt(parser)?,
+            created_lt: parser.load_u64(64)?,
+            created_at: parser.load_u32(32)?,
+        })
+    }
+}
+
+/// ```raw
+/// addr_extern$01 len:(## 9) external_address:(bits len) = MsgAddressExt;
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExternalAddress {
+    pub bit_len: usize,
+    pub address: Vec<u8>,
+}
+
+impl ExternalAddress {
+    /// Loads `MsgAddressExt`, returning `None` for `addr_none`.
+    pub fn load_opt(parser: &mut CellParser) -> Result<Option<ExternalAddress>, TonCellError> {
+        match parser.load_u8(2)? {
+            0b00 => Ok(None),
+            0b01 => {
+                let bit_len = parser.load_u16(9)? as usize;
+                Ok(Some(ExternalAddress {
+                    bit_len,
+                    address: parser.load_bits(bit_len)?,
+                }))
+            }
+            tp => Err(TonCellError::InvalidAddressType(tp)),
+        }
+    }
+
+    /// Stores `MsgAddressExt`, `addr_none` if `address` is `None`.
+    pub fn store_opt(
+        builder: &mut CellBuilder,
+        address: Option<&ExternalAddress>,
+    ) -> Result<(), TonCellError> {
+        match address {
+            None => {
+                builder.store_u8(2, 0b00)?;
+            }
+            Some(address) => {
+                builder.store_u8(2, 0b01)?;
+                builder.store_u16(9, address.bit_len as u16)?;
+                builder.store_bits(address.bit_len, &address.address)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Header of a message. The same type stands for `CommonMsgInfoRelaxed`, the header of the
+/// messages sent by contracts, whose `src` may be `addr_none`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommonMsgInfo {
+    Internal(InternalMessageInfo),
+    ExternalIn(ExternalInMessageInfo),
+    ExternalOut(ExternalOutMessageInfo),
+}
+
+impl CommonMsgInfo {
+    pub fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        match self {
+            CommonMsgInfo::Internal(info) => info.store(builder),
+            CommonMsgInfo::ExternalIn(info) => info.store(builder),
+            CommonMsgInfo::ExternalOut(info) => info.store(builder),
+        }
+    }
+
+    pub fn load(parser: &mut CellParser) -> Result<CommonMsgInfo, TonCellError> {
+        if !parser.load_bit()? {
+            return Ok(InternalMessageInfo::load_fields(parser)?.into());
+        }
+        if parser.load_bit()? {
+            Ok(ExternalOutMessageInfo::load_fields(parser)?.into())
+        } else {
+            Ok(ExternalInMessageInfo::load_fields(parser)?.into())
+        }
+    }
+
+    /// Builds the whole message with the header:
+    ///
+    /// ```raw
+    /// message$_ {X:Type} info:CommonMsgInfo
+    ///   init:(Maybe (Either StateInit ^StateInit))
+    ///   body:(Either X ^X) = Message X;
+    /// ```
+    ///
+    /// The state init and the body are stored in references, an absent body is stored as an
+    /// empty inline body.
+    pub fn build_message(
+        &self,
+        state_init: Option<&ArcCell>,
+        body: Option<&ArcCell>,
+    ) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        self.store(&mut builder)?;
+        builder.store_bit(state_init.is_some())?; // init?
+        if let Some(state_init) = state_init {
+            builder.store_bit(true)?; // ^StateInit
+            builder.store_reference(state_init)?;
+        }
+        builder.store_bit(body.is_some())?; // ^X
+        if let Some(body) = body {
+            builder.store_reference(body)?;
+        }
+        Ok(builder.build()?)
+    }
+}
+
+impl From<InternalMessageInfo> for CommonMsgInfo {
+    fn from(info: InternalMessageInfo) -> Self {
+        CommonMsgInfo::Internal(info)
+    }
+}
+
+impl From<ExternalInMessageInfo> for CommonMsgInfo {
+    fn from(info: ExternalInMessageInfo) -> Self {
+        CommonMsgInfo::ExternalIn(info)
+    }
+}
+
+impl From<ExternalOutMessageInfo> for CommonMsgInfo {
+    fn from(info: ExternalOutMessageInfo) -> Self {
+        CommonMsgInfo::ExternalOut(info)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    use num_bigint::BigUint;
+
+    use crate::address::TonAddress;
+    use crate::cell::{BagOfCells, CellBuilder};
+    use crate::coins::Coins;
+    use crate::message::{
+        CommonMsgInfo, ExternalAddress, ExternalInMessageInfo, ExternalMessage,
+        ExternalOutMessageInfo, InternalMessageInfo, TransferMessage,
+    };
+
+    #[test]
+    fn common_msg_info_matches_block_messages() -> anyhow::Result<()> {
+        let boc = include_str!("../../resources/bloc/block_extra.hex");
+        let root = BagOfCells::parse_hex(boc)?.single_root()?.clone();
+        let account_blocks = root.load_block()?.extra.unwrap().account_blocks.unwrap();
+        let mut hashes = HashSet::new();
+        for account_block in account_blocks.values() {
+            for tx in account_block
+                .transactions
+                .values()
+                .filter_map(|tx| tx.data.as_ref())
+            {
+                let messages = tx.out_msgs.values().chain(std::iter::once(&tx.in_msg));
+                hashes.extend(messages.filter_map(|msg| Some(msg.data.as_ref()?.hash.clone())));
+            }
+        }
+
+        let mut internal_messages = 0;
+        let mut stack = vec![root];
+        while let Some(cell) = stack.pop() {
+            stack.extend(cell.references.iter().cloned());
+            if !hashes.contains(&cell.cell_hash()?) || cell.data[0] & 0x80 != 0 {
+                continue;
+            }
+            // The header is rebuilt bit for bit
+            let mut parser = cell.parser();
+            let info = InternalMessageInfo::load(&mut parser)?;
+            let mut builder = CellBuilder::new();
+            info.store(&mut builder)?;
+            builder
+                .store_remaining_bits(&mut parser)?
+                .store_references(&cell.references)?;
+            assert_eq!(builder.build()?.cell_hash()?, cell.cell_hash()?);
+            assert!(info.created_lt > 0);
+            internal_messages += 1;
+        }
+        assert!(internal_messages > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn common_msg_info_works() -> anyhow::Result<()> {
+        let dest = TonAddress::from_base64_url("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR")?;
+        let body = Arc::new(CellBuilder::new().store_u32(32, 7)?.build()?);
+        let value = Coins::from_nano(1_000_000);
+        let mut transfer = TransferMessage::new(&dest, &value);
+        transfer.with_data_ref(&body);
+        let mut info = InternalMessageInfo::new(&dest, &value);
+        let message = CommonMsgInfo::from(info.clone()).build_message(None, Some(&body))?;
+        assert_eq!(message.cell_hash()?, transfer.build()?.cell_hash()?);
+
+        info.with_src(&dest)
+            .with_bounce(false)
+            .with_fees(&Coins::from_nano(1), &Coins::from_nano(2))
+            .with_extra_currency(239, &BigUint::from(5u32))
+            .with_extra_currency(240, &BigUint::from(300u32))
+            .with_created(47_000_000_000_001, 1_700_000_000);
+        let mut stored = CellBuilder::new();
+        info.store(&mut stored)?;
+        let stored = stored.build()?;
+        assert_eq!(InternalMessageInfo::load(&mut stored.parser())?, info);
+        assert_eq!(
+            CommonMsgInfo::load(&mut stored.parser())?,
+            CommonMsgInfo::from(info)
+        );
+
+        let mut external = ExternalMessage::new(&dest);
+        external.with_body_ref(&body);
+        let info = ExternalInMessageInfo::new(&dest);
+        let message = CommonMsgInfo::from(info.clone()).build_message(None, Some(&body))?;
+        assert_eq!(message.cell_hash()?, external.build()?.cell_hash()?);
+        assert_eq!(ExternalInMessageInfo::load(&mut message.parser())?, info);
+        assert!(InternalMessageInfo::load(&mut message.parser()).is_err());
+
+        let src = ExternalAddress {
+            bit_len: 12,
+            address: vec![0xab, 0xc0],
+        };
+        let mut info = ExternalInMessageInfo::new(&dest);
+        info.with_src(&src).with_import_fee(&Coins::from_nano(3));
+        let mut stored = CellBuilder::new();
+        info.store(&mut stored)?;
+        let stored = stored.build()?;
+        assert_eq!(ExternalInMessageInfo::load(&mut stored.parser())?, info);
+
+        let info = ExternalOutMessageInfo {
+            src: dest.clone(),
+            dest: Some(src),
+            created_lt: 7,
+            created_at: 8,
+        };
+        let mut stored = CellBuilder::new();
+        info.store(&mut stored)?;
+        let stored = stored.build()?;
+        assert_eq!(
+            CommonMsgInfo::load(&mut stored.parser())?,
+            CommonMsgInfo::from(info)
+        );
+        Ok(())
+    }
+}
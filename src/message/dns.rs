@@ -0,0 +1,214 @@
+use std::sync::Arc;
+
+use sha2::{Digest, Sha256};
+
+use crate::address::TonAddress;
+use crate::cell::{ArcCell, Cell, CellBuilder, TonCellError};
+use crate::message::TonMessageError;
+
+// Constants from dns standard
+// https://github.com/ton-blockchain/TEPs/blob/master/text/0081-dns-standard.md
+
+pub const DNS_CHANGE_RECORD: u32 = 0x4eb1f0f9;
+
+pub const DNS_SMC_ADDRESS: u16 = 0x9fd3;
+pub const DNS_NEXT_RESOLVER: u16 = 0xba93;
+pub const DNS_ADNL_ADDRESS: u16 = 0xad01;
+pub const DNS_STORAGE_ADDRESS: u16 = 0x7473;
+
+pub const DNS_CATEGORY_WALLET: &str = "wallet";
+pub const DNS_CATEGORY_SITE: &str = "site";
+pub const DNS_CATEGORY_STORAGE: &str = "storage";
+pub const DNS_CATEGORY_NEXT_RESOLVER: &str = "dns_next_resolver";
+
+/// Returns the key of the records of the category, i.e. the sha256 of its name.
+pub fn dns_category_key(category: &str) -> [u8; 32] {
+    Sha256::digest(category.as_bytes()).into()
+}
+
+/// Value of a DNS record, without capability or protocol lists:
+///
+/// ```raw
+/// dns_smc_address#9fd3 smc_addr:MsgAddressInt flags:(## 8) { flags <= 1 }
+///                      cap_list:flags . 0?SmcCapList = DNSRecord;
+/// dns_next_resolver#ba93 resolver:MsgAddressInt = DNSRecord;
+/// dns_adnl_address#ad01 adnl_addr:bits256 flags:(## 8) { flags <= 1 }
+///                       proto_list:flags . 0?ProtoList = DNSRecord;
+/// dns_storage_address#7473 bag_id:bits256 = DNSRecord;
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DnsRecord {
+    SmcAddress(TonAddress),
+    NextResolver(TonAddress),
+    AdnlAddress([u8; 32]),
+    StorageAddress([u8; 32]),
+}
+
+impl DnsRecord {
+    /// Returns the category the record is usually stored under.
+    pub fn category(&self) -> &'static str {
+        match self {
+            DnsRecord::SmcAddress(_) => DNS_CATEGORY_WALLET,
+            DnsRecord::NextResolver(_) => DNS_CATEGORY_NEXT_RESOLVER,
+            DnsRecord::AdnlAddress(_) => DNS_CATEGORY_SITE,
+            DnsRecord::StorageAddress(_) => DNS_CATEGORY_STORAGE,
+        }
+    }
+
+    pub fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        match self {
+            DnsRecord::SmcAddress(address) => {
+                builder.store_u32(16, DNS_SMC_ADDRESS as u32)?;
+                builder.store_address(address)?;
+                builder.store_u8(8, 0)?; // flags
+            }
+            DnsRecord::NextResolver(address) => {
+                builder.store_u32(16, DNS_NEXT_RESOLVER as u32)?;
+                builder.store_address(address)?;
+            }
+            DnsRecord::AdnlAddress(adnl) => {
+                builder.store_u32(16, DNS_ADNL_ADDRESS as u32)?;
+                builder.store_slice(adnl)?;
+                builder.store_u8(8, 0)?; // flags
+            }
+            DnsRecord::StorageAddress(bag_id) => {
+                builder.store_u32(16, DNS_STORAGE_ADDRESS as u32)?;
+                builder.store_slice(bag_id)?;
+            }
+        }
+        Ok(builder.build()?)
+    }
+
+    /// Parses a record value, ignoring capability and protocol lists.
+    pub fn parse(cell: &Cell) -> Result<DnsRecord, TonMessageError> {
+        let mut parser = cell.parser();
+        let record = match parser.load_u32(16)? as u16 {
+            DNS_SMC_ADDRESS => DnsRecord::SmcAddress(parser.load_address()?),
+            DNS_NEXT_RESOLVER => DnsRecord::NextResolver(parser.load_address()?),
+            DNS_ADNL_ADDRESS => {
+                let mut adnl = [0; 32];
+                parser.load_slice(&mut adnl)?;
+                DnsRecord::AdnlAddress(adnl)
+            }
+            DNS_STORAGE_ADDRESS => {
+                let mut bag_id = [0; 32];
+                parser.load_slice(&mut bag_id)?;
+                DnsRecord::StorageAddress(bag_id)
+            }
+            tag => {
+                return Err(TonCellError::cell_parser_error(format!(
+                    "Unknown DNS record tag {:#06x}",
+                    tag
+                ))
+                .into())
+            }
+        };
+        Ok(record)
+    }
+}
+
+/// Creates a body for changing a record of a DNS item according to TL-B schema:
+///
+/// ```raw
+/// change_dns_record#4eb1f0f9 query_id:uint64 record_key:uint256 value:(Maybe ^Cell)
+///                            = InternalMsgBody;
+/// ```
+///
+/// The record is deleted if the value is absent.
+pub struct DnsChangeRecordMessage {
+    pub query_id: Option<u64>,
+    pub key: [u8; 32],
+    pub value: Option<ArcCell>,
+}
+
+impl DnsChangeRecordMessage {
+    pub fn new(category: &str) -> DnsChangeRecordMessage {
+        DnsChangeRecordMessage {
+            query_id: None,
+            key: dns_category_key(category),
+            value: None,
+        }
+    }
+
+    /// Creates a message setting the record under its usual category.
+    pub fn set_record(record: &DnsRecord) -> Result<DnsChangeRecordMessage, TonMessageError> {
+        let mut message = DnsChangeRecordMessage::new(record.category());
+        message.with_value(record.build()?);
+        Ok(message)
+    }
+
+    pub fn with_query_id(&mut self, query_id: u64) -> &mut Self {
+        self.query_id = Some(query_id);
+        self
+    }
+
+    pub fn with_value(&mut self, value: Cell) -> &mut Self {
+        self.with_value_ref(&Arc::new(value))
+    }
+
+    pub fn with_value_ref(&mut self, value: &ArcCell) -> &mut Self {
+        self.value = Some(value.clone());
+        self
+    }
+
+    pub fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut message = CellBuilder::new();
+        message.store_u32(32, DNS_CHANGE_RECORD)?;
+        message.store_u64(64, self.query_id.unwrap_or_default())?;
+        message.store_slice(&self.key)?;
+        message.store_bit(self.value.is_some())?;
+        if let Some(value) = self.value.as_ref() {
+            message.store_reference(value)?;
+        }
+        Ok(message.build()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::address::TonAddress;
+    use crate::message::{
+        dns_category_key, DnsChangeRecordMessage, DnsRecord, DNS_CATEGORY_WALLET, DNS_CHANGE_RECORD,
+    };
+
+    #[test]
+    fn dns_records_work() -> anyhow::Result<()> {
+        // sha256("wallet")
+        assert_eq!(
+            hex::encode(dns_category_key(DNS_CATEGORY_WALLET)),
+            "e8d44050873dba865aa7c170ab4cce64d90839a34dcfd6cf71d14e0205443b1b"
+        );
+        let address =
+            TonAddress::from_base64_url("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR")?;
+        let records = [
+            DnsRecord::SmcAddress(address.clone()),
+            DnsRecord::NextResolver(address.clone()),
+            DnsRecord::AdnlAddress([0xad; 32]),
+            DnsRecord::StorageAddress([0x74; 32]),
+        ];
+        for record in &records {
+            let cell = record.build()?;
+            assert_eq!(&DnsRecord::parse(&cell)?, record);
+        }
+        assert_eq!(records[0].build()?.bit_len, 16 + 267 + 8);
+        assert_eq!(records[3].build()?.data[..2], [0x74, 0x73]);
+
+        let mut message = DnsChangeRecordMessage::set_record(&records[0])?;
+        let body = message.with_query_id(5).build()?;
+        let mut parser = body.parser();
+        assert_eq!(parser.load_u32(32)?, DNS_CHANGE_RECORD);
+        assert_eq!(parser.load_u64(64)?, 5);
+        assert_eq!(
+            parser.load_bytes(32)?,
+            dns_category_key(DNS_CATEGORY_WALLET)
+        );
+        assert!(parser.load_bit()?);
+        assert_eq!(DnsRecord::parse(body.reference(0)?)?, records[0]);
+
+        let body = DnsChangeRecordMessage::new(DNS_CATEGORY_WALLET).build()?;
+        assert_eq!(body.bit_len, 32 + 64 + 256 + 1);
+        assert!(body.references.is_empty());
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use thiserror::Error;
 
 use crate::cell::TonCellError;
+use crate::message::MessageLimitViolation;
 
 #[derive(Error, Debug)]
 pub enum TonMessageError {
@@ -10,6 +11,21 @@ pub enum TonMessageError {
     #[error("NaCl cryptographic error ({0})")]
     NaclCryptographicError(String),
 
+    #[error("Invalid signature")]
+    InvalidSignature,
+
+    #[error("Request rejected ({0})")]
+    RequestRejected(String),
+
+    #[error("Invalid bag id ({0})")]
+    InvalidBagId(String),
+
+    #[error("TON Connect error ({0})")]
+    TonConnectError(String),
+
+    #[error("Message limits exceeded ({})", .0.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "))]
+    MessageLimitsExceeded(Vec<MessageLimitViolation>),
+
     #[error("TonCellError ({0})")]
     TonCellError(#[from] TonCellError),
 }
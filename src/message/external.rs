@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use crate::address::TonAddress;
+use crate::cell::{ArcCell, Cell};
+use crate::message::{CommonMsgInfo, ExternalInMessageInfo, TonMessageError};
+
+/// Inbound external message:
+///
+/// ```raw
+/// ext_in_msg_info$10 src:MsgAddressExt dest:MsgAddressInt import_fee:Grams
+///                    init:(Maybe (Either StateInit ^StateInit)) body:(Either X ^X)
+/// ```
+///
+/// The state init and the body are always stored in references.
+pub struct ExternalMessage {
+    pub dest: TonAddress,
+    pub state_init: Option<ArcCell>,
+    pub body: Option<ArcCell>,
+}
+
+impl ExternalMessage {
+    pub fn new(dest: &TonAddress) -> Self {
+        ExternalMessage {
+            dest: dest.clone(),
+            state_init: None,
+            body: None,
+        }
+    }
+
+    pub fn with_state_init(&mut self, state_init: Cell) -> &mut Self {
+        self.with_state_init_ref(&Arc::new(state_init))
+    }
+
+    pub fn with_state_init_ref(&mut self, state_init: &ArcCell) -> &mut Self {
+        self.state_init = Some(state_init.clone());
+        self
+    }
+
+    pub fn with_body(&mut self, body: Cell) -> &mut Self {
+        self.with_body_ref(&Arc::new(body))
+    }
+
+    pub fn with_body_ref(&mut self, body: &ArcCell) -> &mut Self {
+        self.body = Some(body.clone());
+        self
+    }
+
+    pub fn build(&self) -> Result<Cell, TonMessageError> {
+        let info = ExternalInMessageInfo::new(&self.dest);
+        CommonMsgInfo::from(info).build_message(self.state_init.as_ref(), self.body.as_ref())
+    }
+}
@@ -1,28 +1,14 @@
 use std::sync::Arc;
 
-use num_bigint::BigUint;
-use num_traits::Zero;
-
 use crate::address::TonAddress;
 use crate::cell::{ArcCell, Cell, CellBuilder};
-use crate::message::{TonMessageError, ZERO_COINS};
-
-// Constants from jetton standart
-// https://github.com/ton-blockchain/TEPs/blob/master/text/0074-jettons-standard.md
-
-// crc32('transfer query_id:uint64 amount:VarUInteger 16 destination:MsgAddress response_destination:MsgAddress custom_payload:Maybe ^Cell forward_ton_amount:VarUInteger 16 forward_payload:Either Cell ^Cell = InternalMsgBody') = 0x8f8a7ea5 & 0x7fffffff = 0xf8a7ea5
-// crc32('transfer_notification query_id:uint64 amount:VarUInteger 16 sender:MsgAddress forward_payload:Either Cell ^Cell = InternalMsgBody') = 0xf362d09c & 0x7fffffff = 0x7362d09c
-// crc32('excesses query_id:uint64 = InternalMsgBody') = 0x553276db | 0x80000000 = 0xd53276db
-// crc32('burn query_id:uint64 amount:VarUInteger 16 response_destination:MsgAddress custom_payload:Maybe ^Cell = InternalMsgBody') = 0x595f07bc & 0x7fffffff = 0x595f07bc
-// crc32('internal_transfer query_id:uint64 amount:VarUInteger 16 from:MsgAddress response_address:MsgAddress forward_ton_amount:VarUInteger 16 forward_payload:Either Cell ^Cell = InternalMsgBody') = 0x978d4519 & 0x7fffffff = 0x178d4519
-// crc32('burn_notification query_id:uint64 amount:VarUInteger 16 sender:MsgAddress response_destination:MsgAddress = InternalMsgBody') = 0x7bdd97de & 0x7fffffff = 0x7bdd97de
+use crate::coins::Coins;
+use crate::message::TonMessageError;
 
-pub const JETTON_TRANSFER: u32 = 0xf8a7ea5;
-pub const JETTON_TRANSFER_NOTIFICATION: u32 = 0x7362d09c;
-pub const JETTON_INTERNAL_TRANSFER: u32 = 0x178d4519;
-pub const JETTON_EXCESSES: u32 = 0xd53276db;
-pub const JETTON_BURN: u32 = 0x595f07bc;
-pub const JETTON_BURN_NOTIFICATION: u32 = 0x7bdd97de;
+pub use crate::constants::{
+    JETTON_BURN, JETTON_BURN_NOTIFICATION, JETTON_EXCESSES, JETTON_INTERNAL_TRANSFER,
+    JETTON_TRANSFER, JETTON_TRANSFER_NOTIFICATION,
+};
 
 /// Creates a body for jetton transfer according to TL-B schema:
 ///
@@ -34,23 +20,24 @@ pub const JETTON_BURN_NOTIFICATION: u32 = 0x7bdd97de;
 /// ```
 pub struct JettonTransferMessage {
     pub query_id: Option<u64>,
-    pub amount: BigUint,
+    /// Amount in the smallest units of the jetton
+    pub amount: Coins,
     pub destination: TonAddress,
     pub response_destination: Option<TonAddress>,
     pub custom_payload: Option<ArcCell>,
-    pub forward_ton_amount: BigUint,
+    pub forward_ton_amount: Coins,
     pub forward_payload: Option<ArcCell>,
 }
 
 impl JettonTransferMessage {
-    pub fn new(destination: &TonAddress, amount: &BigUint) -> JettonTransferMessage {
+    pub fn new(destination: &TonAddress, amount: &Coins) -> JettonTransferMessage {
         JettonTransferMessage {
             query_id: None,
             amount: amount.clone(),
             destination: destination.clone(),
             response_destination: None,
             custom_payload: None,
-            forward_ton_amount: ZERO_COINS.clone(),
+            forward_ton_amount: Coins::zero(),
             forward_payload: None,
         }
     }
@@ -74,17 +61,13 @@ impl JettonTransferMessage {
         self
     }
 
-    pub fn with_forward(
-        &mut self,
-        forward_ton_amount: &BigUint,
-        forward_payload: Cell,
-    ) -> &mut Self {
+    pub fn with_forward(&mut self, forward_ton_amount: &Coins, forward_payload: Cell) -> &mut Self {
         self.with_forward_ref(forward_ton_amount, &Arc::new(forward_payload))
     }
 
     pub fn with_forward_ref(
         &mut self,
-        forward_ton_amount: &BigUint,
+        forward_ton_amount: &Coins,
         forward_payload: &ArcCell,
     ) -> &mut Self {
         self.forward_ton_amount = forward_ton_amount.clone();
@@ -115,8 +98,7 @@ impl JettonTransferMessage {
         }
         message.store_coins(&self.forward_ton_amount)?;
         if let Some(fp) = self.forward_payload.as_ref() {
-            message.store_bit(true)?;
-            message.store_reference(fp)?;
+            message.store_either_cell_or_cell_ref(fp)?;
         } else {
             message.store_bit(false)?;
         }
@@ -0,0 +1,258 @@
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+use crate::cell::{BagOfCells, Cell, CellPath, TonCellError, MAX_CELL_BITS, MAX_CELL_REFERENCES};
+use crate::message::TonMessageError;
+use crate::responses::SizeLimitsConfig;
+
+/// Protocol limit exceeded by a message, see [`check_message_limits`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MessageLimitViolation {
+    /// Cell with more than 1023 bits or 4 references.
+    CellOverflow {
+        path: CellPath,
+        bits: usize,
+        references: usize,
+    },
+    TooManyCells {
+        cells: usize,
+        max: u32,
+    },
+    TooManyBits {
+        bits: usize,
+        max: u32,
+    },
+    /// Depth of an inbound external message.
+    TooDeep {
+        depth: u16,
+        max: u16,
+    },
+    /// Size in bytes of the bag of cells of an inbound external message.
+    TooLarge {
+        size: usize,
+        max: u32,
+    },
+}
+
+impl Display for MessageLimitViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MessageLimitViolation::CellOverflow {
+                path,
+                bits,
+                references,
+            } => write!(
+                f,
+                "cell at path '{}' has {} bits and {} references",
+                path, bits, references
+            ),
+            MessageLimitViolation::TooManyCells { cells, max } => {
+                write!(f, "{} cells exceed the limit of {}", cells, max)
+            }
+            MessageLimitViolation::TooManyBits { bits, max } => {
+                write!(f, "{} bits exceed the limit of {}", bits, max)
+            }
+            MessageLimitViolation::TooDeep { depth, max } => {
+                write!(f, "depth {} exceeds the limit of {}", depth, max)
+            }
+            MessageLimitViolation::TooLarge { size, max } => {
+                write!(f, "size of {} bytes exceeds the limit of {}", size, max)
+            }
+        }
+    }
+}
+
+/// Returns the protocol limits exceeded by a message, which would make the liteserver reject
+/// an inbound external message or the action phase fail for an internal message.
+///
+/// - every cell has at most 1023 bits and 4 references,
+/// - the distinct cells below the root, i.e. of the state init and the body, and their bits
+///   are within `max_msg_cells` and `max_msg_bits`,
+/// - an inbound external message has a depth of at most `max_ext_msg_depth`, and its bag of
+///   cells without CRC has at most `max_ext_msg_size` bytes.
+///
+/// The limits are read from config param 43, see
+/// [`ConfigParams::size_limits`](crate::responses::ConfigParams::size_limits).
+pub fn check_message_limits(
+    message: &Cell,
+    limits: &SizeLimitsConfig,
+) -> Result<Vec<MessageLimitViolation>, TonCellError> {
+    let mut violations = Vec::new();
+    let mut visited = HashSet::new();
+    let mut path = Vec::new();
+    check_cells(message, &mut path, &mut visited, &mut violations);
+    if !violations.is_empty() {
+        // Cells over the limits can't be serialized
+        return Ok(violations);
+    }
+
+    let raw = BagOfCells::from_root(message.clone()).to_raw()?;
+    let root = raw.roots()[0];
+    let (cells, bits) = raw
+        .cells()
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| *index != root)
+        .fold((0, 0), |(cells, bits), (_, cell)| {
+            (cells + 1, bits + cell.bit_len())
+        });
+    if cells > limits.max_msg_cells as usize {
+        violations.push(MessageLimitViolation::TooManyCells {
+            cells,
+            max: limits.max_msg_cells,
+        });
+    }
+    if bits > limits.max_msg_bits as usize {
+        violations.push(MessageLimitViolation::TooManyBits {
+            bits,
+            max: limits.max_msg_bits,
+        });
+    }
+
+    // ext_in_msg_info$10
+    let mut parser = message.parser();
+    if parser.load_u8(2)? == 0b10 {
+        let depth = message.virtual_depth(0)?;
+        if depth > limits.max_ext_msg_depth {
+            violations.push(MessageLimitViolation::TooDeep {
+                depth,
+                max: limits.max_ext_msg_depth,
+            });
+        }
+        let size = raw.serialize(false)?.len();
+        if size > limits.max_ext_msg_size as usize {
+            violations.push(MessageLimitViolation::TooLarge {
+                size,
+                max: limits.max_ext_msg_size,
+            });
+        }
+    }
+    Ok(violations)
+}
+
+/// Checks the message against the protocol limits, failing with
+/// [`TonMessageError::MessageLimitsExceeded`] listing the violations, see
+/// [`check_message_limits`].
+pub fn validate_message(message: &Cell, limits: &SizeLimitsConfig) -> Result<(), TonMessageError> {
+    let violations = check_message_limits(message, limits)?;
+    if !violations.is_empty() {
+        return Err(TonMessageError::MessageLimitsExceeded(violations));
+    }
+    Ok(())
+}
+
+fn check_cells(
+    cell: &Cell,
+    path: &mut Vec<usize>,
+    visited: &mut HashSet<*const Cell>,
+    violations: &mut Vec<MessageLimitViolation>,
+) {
+    if !visited.insert(cell as *const Cell) {
+        return;
+    }
+    if cell.bit_len > MAX_CELL_BITS || cell.references.len() > MAX_CELL_REFERENCES {
+        violations.push(MessageLimitViolation::CellOverflow {
+            path: CellPath::new(path.clone()),
+            bits: cell.bit_len,
+            references: cell.references.len(),
+        });
+    }
+    for (index, child) in cell.references.iter().enumerate() {
+        path.push(index);
+        check_cells(child, path, visited, violations);
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::address::TonAddress;
+    use crate::cell::{Cell, CellBuilder};
+    use crate::coins::Coins;
+    use crate::message::{
+        check_message_limits, validate_message, ExternalMessage, MessageLimitViolation,
+        TransferMessage,
+    };
+    use crate::responses::SizeLimitsConfig;
+
+    /// Chain of `len` cells of 1000 bits.
+    fn chain(len: usize) -> anyhow::Result<Cell> {
+        let mut cell = CellBuilder::new().store_bits(1000, &[0xaa; 125])?.build()?;
+        for i in 1..len {
+            cell = CellBuilder::new()
+                .store_u32(32, i as u32)?
+                .store_bits(968, &[0x55; 121])?
+                .store_child(cell)?
+                .build()?;
+        }
+        Ok(cell)
+    }
+
+    #[test]
+    fn message_limits_are_checked() -> anyhow::Result<()> {
+        let dest = TonAddress::NULL;
+        let limits = SizeLimitsConfig::default();
+        let message = ExternalMessage::new(&dest).with_body(chain(10)?).build()?;
+        assert!(check_message_limits(&message, &limits)?.is_empty());
+        validate_message(&message, &limits)?;
+
+        let message = ExternalMessage::new(&dest).with_body(chain(20)?).build()?;
+        let ext_limits = SizeLimitsConfig {
+            max_ext_msg_depth: 10,
+            max_ext_msg_size: 1000,
+            ..limits.clone()
+        };
+        let violations = check_message_limits(&message, &ext_limits)?;
+        assert_eq!(violations.len(), 2);
+        assert_eq!(
+            violations[0],
+            MessageLimitViolation::TooDeep { depth: 20, max: 10 }
+        );
+        assert!(matches!(
+            violations[1],
+            MessageLimitViolation::TooLarge { max: 1000, .. }
+        ));
+        assert!(validate_message(&message, &ext_limits).is_err());
+
+        // Internal messages have no depth and size limits
+        let message = TransferMessage::new(&dest, &Coins::from_nano(1))
+            .with_data(chain(20)?)
+            .build()?;
+        assert!(check_message_limits(&message, &ext_limits)?.is_empty());
+        let msg_limits = SizeLimitsConfig {
+            max_msg_cells: 10,
+            max_msg_bits: 1000,
+            ..limits.clone()
+        };
+        let violations = check_message_limits(&message, &msg_limits)?;
+        assert_eq!(
+            violations,
+            [
+                MessageLimitViolation::TooManyCells { cells: 20, max: 10 },
+                MessageLimitViolation::TooManyBits {
+                    bits: 20 * 1000,
+                    max: 1000
+                }
+            ]
+        );
+
+        let overflow = Cell {
+            bit_len: 1024,
+            data: vec![0; 128].into(),
+            ..Default::default()
+        };
+        let message = ExternalMessage::new(&dest)
+            .with_body(CellBuilder::new().store_child(overflow)?.build()?)
+            .build()?;
+        let violations = check_message_limits(&message, &limits)?;
+        assert_eq!(
+            violations,
+            [MessageLimitViolation::CellOverflow {
+                path: "0.0".parse()?,
+                bits: 1024,
+                references: 0
+            }]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use crate::address::TonAddress;
+use crate::cell::{ArcCell, Cell, CellBuilder};
+use crate::coins::Coins;
+use crate::message::TonMessageError;
+
+pub use crate::constants::NFT_TRANSFER;
+
+/// Creates a body for nft transfer according to TL-B schema:
+///
+/// ```raw
+/// transfer#5fcc3d14 query_id:uint64 new_owner:MsgAddress response_destination:MsgAddress
+///                   custom_payload:(Maybe ^Cell) forward_amount:(VarUInteger 16)
+///                   forward_payload:(Either Cell ^Cell) = InternalMsgBody;
+/// ```
+pub struct NftTransferMessage {
+    pub query_id: Option<u64>,
+    pub new_owner: TonAddress,
+    pub response_destination: Option<TonAddress>,
+    pub custom_payload: Option<ArcCell>,
+    pub forward_amount: Coins,
+    pub forward_payload: Option<ArcCell>,
+}
+
+impl NftTransferMessage {
+    pub fn new(new_owner: &TonAddress) -> NftTransferMessage {
+        NftTransferMessage {
+            query_id: None,
+            new_owner: new_owner.clone(),
+            response_destination: None,
+            custom_payload: None,
+            forward_amount: Coins::zero(),
+            forward_payload: None,
+        }
+    }
+
+    pub fn with_query_id(&mut self, query_id: u64) -> &mut Self {
+        self.query_id = Some(query_id);
+        self
+    }
+
+    pub fn with_response_destination(&mut self, response_destination: &TonAddress) -> &mut Self {
+        self.response_destination = Some(response_destination.clone());
+        self
+    }
+
+    pub fn with_custom_payload(&mut self, custom_payload: Cell) -> &mut Self {
+        self.with_custom_payload_ref(&Arc::new(custom_payload))
+    }
+
+    pub fn with_custom_payload_ref(&mut self, custom_payload_ref: &ArcCell) -> &mut Self {
+        self.custom_payload = Some(custom_payload_ref.clone());
+        self
+    }
+
+    pub fn with_forward(&mut self, forward_amount: &Coins, forward_payload: Cell) -> &mut Self {
+        self.with_forward_ref(forward_amount, &Arc::new(forward_payload))
+    }
+
+    pub fn with_forward_ref(
+        &mut self,
+        forward_amount: &Coins,
+        forward_payload: &ArcCell,
+    ) -> &mut Self {
+        self.forward_amount = forward_amount.clone();
+        self.forward_payload = Some(forward_payload.clone());
+        self
+    }
+
+    pub fn build(&self) -> Result<Cell, TonMessageError> {
+        if self.forward_amount.is_zero() && self.forward_payload.is_some() {
+            return Err(TonMessageError::ForwardTonAmountIsNegative);
+        }
+
+        let mut message = CellBuilder::new();
+        message.store_u32(32, NFT_TRANSFER)?;
+        message.store_u64(64, self.query_id.unwrap_or_default())?;
+        message.store_address(&self.new_owner)?;
+        message.store_address(
+            self.response_destination
+                .as_ref()
+                .unwrap_or(&TonAddress::NULL),
+        )?;
+        if let Some(cp) = self.custom_payload.as_ref() {
+            message.store_bit(true)?;
+            message.store_reference(cp)?;
+        } else {
+            message.store_bit(false)?;
+        }
+        message.store_coins(&self.forward_amount)?;
+        if let Some(fp) = self.forward_payload.as_ref() {
+            message.store_either_cell_or_cell_ref(fp)?;
+        } else {
+            message.store_bit(false)?;
+        }
+        Ok(message.build()?)
+    }
+}
@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use crate::address::TonAddress;
+use crate::cell::{ArcCell, Cell, CellBuilder, TonCellError};
+use crate::message::{ExternalMessage, TonMessageError};
+
+/// Size of the ed25519 signature that prefixes the body of wallet v1-v4 and highload externals.
+pub const SIGNATURE_BITS: usize = 512;
+
+/// Builds the normalized form of an external-in message as described in TEP-467:
+///
+/// ```raw
+/// ext_in_msg_info$10 src:addr_none dest:MsgAddressInt import_fee:0
+///                    init:nothing body:(right ^Cell)
+/// ```
+///
+/// `src`, `import_fee` and `init` are set to their empty values and the body is always stored
+/// in a reference, so two externals carrying the same body to the same destination have the
+/// same normalized hash regardless of how they were serialized.
+pub fn normalize_external_message(message: &Cell) -> Result<Cell, TonMessageError> {
+    let (dest, body) = parse_external_message(message)?;
+    build_normalized_external_message(&dest, &body)
+}
+
+/// Returns the hash of the normalized form of an external-in message.
+///
+/// See [`normalize_external_message`] for details.
+pub fn normalized_external_message_hash(message: &Cell) -> Result<Vec<u8>, TonMessageError> {
+    Ok(normalize_external_message(message)?.cell_hash()?)
+}
+
+/// Returns the destination of an external-in message.
+pub fn external_message_destination(message: &Cell) -> Result<TonAddress, TonMessageError> {
+    let (dest, _) = parse_external_message(message)?;
+    Ok(dest)
+}
+
+/// Returns the body of an external-in message, regardless of whether the body
+/// is stored inline or in a reference.
+pub fn external_message_body(message: &Cell) -> Result<ArcCell, TonMessageError> {
+    let (_, body) = parse_external_message(message)?;
+    Ok(body)
+}
+
+/// Returns the hash of the body of an external-in message.
+///
+/// See [`external_message_body`] for details.
+pub fn external_message_body_hash(message: &Cell) -> Result<Vec<u8>, TonMessageError> {
+    Ok(external_message_body(message)?.cell_hash()?)
+}
+
+/// Returns the hash of a signed body with its leading signature omitted.
+///
+/// For wallets that prefix the body with the signature (v1-v4, highload) this is the hash that
+/// was actually signed, so it stays the same if the message is signed again.
+pub fn unsigned_body_hash(signed_body: &Cell) -> Result<Vec<u8>, TonMessageError> {
+    let mut parser = signed_body.parser();
+    parser.skip_bits(SIGNATURE_BITS)?;
+    let unsigned_body = CellBuilder::new()
+        .store_remaining_bits(&mut parser)?
+        .store_references(signed_body.references.as_slice())?
+        .build()?;
+    Ok(unsigned_body.cell_hash()?)
+}
+
+fn build_normalized_external_message(
+    dest: &TonAddress,
+    body: &ArcCell,
+) -> Result<Cell, TonMessageError> {
+    ExternalMessage::new(dest).with_body_ref(body).build()
+}
+
+fn parse_external_message(message: &Cell) -> Result<(TonAddress, ArcCell), TonMessageError> {
+    let mut parser = message.parser();
+    let mut ref_index = 0;
+    if parser.load_u8(2)? != 0b10 {
+        return Err(TonCellError::unexpected_structure("ext_in_msg_info").into());
+    }
+    // src: MsgAddressExt
+    match parser.load_u8(2)? {
+        0b00 => {}
+        0b01 => {
+            let len = parser.load_u16(9)?;
+            parser.skip_bits(len as usize)?;
+        }
+        _ => {
+            return Err(
+                TonCellError::cell_parser_error("Invalid source of external message").into(),
+            )
+        }
+    }
+    let dest = parser.load_address()?;
+    parser.load_coins()?; // import_fee
+    if parser.load_bit()? {
+        if parser.load_bit()? {
+            message.reference(ref_index)?;
+            ref_index += 1;
+        } else {
+            Cell::load_state_init(message, &mut ref_index, &mut parser)?;
+        }
+    }
+    let body = if parser.load_bit()? {
+        message.reference(ref_index)?.clone()
+    } else {
+        let body = CellBuilder::new()
+            .store_remaining_bits(&mut parser)?
+            .store_references(&message.references[ref_index..])?
+            .build()?;
+        Arc::new(body)
+    };
+    Ok((dest, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use num_bigint::BigUint;
+
+    use crate::address::TonAddress;
+    use crate::cell::CellBuilder;
+    use crate::message::{external_message_body_hash, normalized_external_message_hash};
+
+    // Wallets are not available with `std` alone
+    #[cfg(feature = "crypto")]
+    #[test]
+    fn normalized_hash_ignores_state_init() -> anyhow::Result<()> {
+        use std::sync::Arc;
+
+        use crate::coins::Coins;
+        use crate::message::{normalize_external_message, TransferMessage};
+        use crate::wallet::{test_wallet, Expiration, WalletVersion, DEFAULT_MESSAGE_TTL};
+
+        let wallet = test_wallet(WalletVersion::V4R2);
+        let dest: TonAddress = "EQBiMfDMivebQb052Z6yR3jHrmwNhw1kQ5bcAUOBYsK_VPuK".parse()?;
+        let transfer = Arc::new(TransferMessage::new(&dest, &Coins::from_nano(1000)).build()?);
+        let body = wallet.create_external_body(
+            Expiration::new(1_700_000_000, DEFAULT_MESSAGE_TTL),
+            1,
+            [transfer],
+        )?;
+        let signed = wallet.sign_external_body(&body)?;
+
+        let with_state_init = wallet.wrap_signed_body(signed.clone(), true)?;
+        let without_state_init = wallet.wrap_signed_body(signed.clone(), false)?;
+        assert_ne!(
+            with_state_init.cell_hash()?,
+            without_state_init.cell_hash()?
+        );
+        assert_eq!(
+            normalized_external_message_hash(&with_state_init)?,
+            normalized_external_message_hash(&without_state_init)?
+        );
+        assert_eq!(
+            external_message_body_hash(&with_state_init)?,
+            signed.cell_hash()?
+        );
+
+        let normalized = normalize_external_message(&with_state_init)?;
+        assert_eq!(normalized.references.len(), 1);
+        assert_eq!(
+            normalized_external_message_hash(&normalized)?,
+            normalized.cell_hash()?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn normalized_hash_handles_inline_body() -> anyhow::Result<()> {
+        let dest: TonAddress = "EQBiMfDMivebQb052Z6yR3jHrmwNhw1kQ5bcAUOBYsK_VPuK".parse()?;
+        let body = CellBuilder::new().store_u32(32, 0xdeadbeef)?.build()?;
+        let inline = CellBuilder::new()
+            .store_u8(2, 0b10)?
+            .store_u8(2, 0)?
+            .store_address(&dest)?
+            .store_coins(BigUint::from(5u32))?
+            .store_bit(false)?
+            .store_bit(false)?
+            .store_cell(&body)?
+            .build()?;
+        let by_ref = CellBuilder::new()
+            .store_u8(2, 0b10)?
+            .store_u8(2, 0)?
+            .store_address(&dest)?
+            .store_coins(BigUint::from(0u32))?
+            .store_bit(false)?
+            .store_bit(true)?
+            .store_child(body.clone())?
+            .build()?;
+        assert_eq!(external_message_body_hash(&inline)?, body.cell_hash()?);
+        assert_eq!(
+            normalized_external_message_hash(&inline)?,
+            normalized_external_message_hash(&by_ref)?
+        );
+        assert_eq!(
+            normalized_external_message_hash(&by_ref)?,
+            by_ref.cell_hash()?
+        );
+        Ok(())
+    }
+}
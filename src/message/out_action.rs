@@ -0,0 +1,233 @@
+use crate::cell::{ArcCell, Cell, CellBuilder, CellParser, TonCellError};
+use crate::coins::Coins;
+use crate::message::SendMode;
+
+// Constants from block.tlb
+// https://github.com/ton-blockchain/ton/blob/master/crypto/block/block.tlb
+
+pub const ACTION_SEND_MSG: u32 = 0x0ec3c86d;
+pub const ACTION_SET_CODE: u32 = 0xad4de08e;
+pub const ACTION_RESERVE_CURRENCY: u32 = 0x36e6b809;
+pub const ACTION_CHANGE_LIBRARY: u32 = 0x26fa1dd4;
+
+/// Maximum number of actions in an out action list, enforced by the action phase.
+pub const MAX_OUT_ACTIONS: usize = 255;
+
+/// Output action according to TL-B schema:
+///
+/// ```raw
+/// action_send_msg#0ec3c86d mode:(## 8) out_msg:^(MessageRelaxed Any) = OutAction;
+/// action_set_code#ad4de08e new_code:^Cell = OutAction;
+/// action_reserve_currency#36e6b809 mode:(## 8) currency:CurrencyCollection = OutAction;
+/// action_change_library#26fa1dd4 mode:(## 7) libref:LibRef = OutAction;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum OutAction {
+    SendMsg {
+        mode: SendMode,
+        out_msg: ArcCell,
+    },
+    SetCode {
+        new_code: ArcCell,
+    },
+    ReserveCurrency {
+        mode: u8,
+        grams: Coins,
+        /// Dictionary of extra currencies, `None` if empty
+        other: Option<ArcCell>,
+    },
+    ChangeLibrary {
+        mode: u8,
+        libref: LibRef,
+    },
+}
+
+/// ```raw
+/// libref_hash$0 lib_hash:bits256 = LibRef;
+/// libref_ref$1 library:^Cell = LibRef;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum LibRef {
+    Hash([u8; 32]),
+    Ref(ArcCell),
+}
+
+impl OutAction {
+    pub fn send_msg(mode: SendMode, out_msg: &ArcCell) -> OutAction {
+        OutAction::SendMsg {
+            mode,
+            out_msg: out_msg.clone(),
+        }
+    }
+
+    pub fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        match self {
+            OutAction::SendMsg { mode, out_msg } => {
+                builder
+                    .store_u32(32, ACTION_SEND_MSG)?
+                    .store_u8(8, mode.validate()?.bits())?
+                    .store_reference(out_msg)?;
+            }
+            OutAction::SetCode { new_code } => {
+                builder
+                    .store_u32(32, ACTION_SET_CODE)?
+                    .store_reference(new_code)?;
+            }
+            OutAction::ReserveCurrency { mode, grams, other } => {
+                builder
+                    .store_u32(32, ACTION_RESERVE_CURRENCY)?
+                    .store_u8(8, *mode)?
+                    .store_coins(grams)?
+                    .store_bit(other.is_some())?;
+                if let Some(other) = other {
+                    builder.store_reference(other)?;
+                }
+            }
+            OutAction::ChangeLibrary { mode, libref } => {
+                builder
+                    .store_u32(32, ACTION_CHANGE_LIBRARY)?
+                    .store_u8(7, *mode)?;
+                match libref {
+                    LibRef::Hash(hash) => {
+                        builder.store_bit(false)?.store_slice(hash)?;
+                    }
+                    LibRef::Ref(library) => {
+                        builder.store_bit(true)?.store_reference(library)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Loads an action from `parser`, taking references of `cell` starting at `ref_index`.
+    pub fn load(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<OutAction, TonCellError> {
+        let mut next_ref = || -> Result<ArcCell, TonCellError> {
+            let reference = cell.reference(*ref_index)?.clone();
+            *ref_index += 1;
+            Ok(reference)
+        };
+        let action = match parser.load_u32(32)? {
+            ACTION_SEND_MSG => OutAction::SendMsg {
+                mode: SendMode::from_bits_retain(parser.load_u8(8)?),
+                out_msg: next_ref()?,
+            },
+            ACTION_SET_CODE => OutAction::SetCode {
+                new_code: next_ref()?,
+            },
+            ACTION_RESERVE_CURRENCY => OutAction::ReserveCurrency {
+                mode: parser.load_u8(8)?,
+                grams: parser.load_coins()?,
+                other: if parser.load_bit()? {
+                    Some(next_ref()?)
+                } else {
+                    None
+                },
+            },
+            ACTION_CHANGE_LIBRARY => OutAction::ChangeLibrary {
+                mode: parser.load_u8(7)?,
+                libref: if parser.load_bit()? {
+                    LibRef::Ref(next_ref()?)
+                } else {
+                    let mut hash = [0u8; 32];
+                    parser.load_slice(&mut hash)?;
+                    LibRef::Hash(hash)
+                },
+            },
+            tag => {
+                return Err(TonCellError::cell_parser_error(format!(
+                    "Unknown out action: {:#010x}",
+                    tag
+                )))
+            }
+        };
+        Ok(action)
+    }
+}
+
+/// Builds an out action list:
+///
+/// ```raw
+/// out_list_empty$_ = OutList 0;
+/// out_list$_ {n:#} prev:^(OutList n) action:OutAction = OutList (n + 1);
+/// ```
+///
+/// Actions are performed in the order they are passed, i.e. the first action ends up
+/// in the deepest cell of the list.
+pub fn build_out_list(actions: &[OutAction]) -> Result<Cell, TonCellError> {
+    if actions.len() > MAX_OUT_ACTIONS {
+        return Err(TonCellError::cell_builder_error(format!(
+            "Too many out actions: {} (max {})",
+            actions.len(),
+            MAX_OUT_ACTIONS
+        )));
+    }
+    let mut list = CellBuilder::new().build()?;
+    for action in actions {
+        let mut builder = CellBuilder::new();
+        builder.store_child(list)?;
+        action.store(&mut builder)?;
+        list = builder.build()?;
+    }
+    Ok(list)
+}
+
+/// Parses an out action list built according to [`build_out_list`], returning actions
+/// in the order they are performed.
+pub fn parse_out_list(list: &Cell) -> Result<Vec<OutAction>, TonCellError> {
+    let mut actions = Vec::new();
+    let mut cell = list;
+    while !cell.references.is_empty() || cell.bit_len > 0 {
+        if actions.len() >= MAX_OUT_ACTIONS {
+            return Err(TonCellError::cell_parser_error(format!(
+                "Too many out actions (max {})",
+                MAX_OUT_ACTIONS
+            )));
+        }
+        let mut parser = cell.parser();
+        let mut ref_index = 1;
+        actions.push(OutAction::load(cell, &mut ref_index, &mut parser)?);
+        cell = cell.reference(0)?.as_ref();
+    }
+    actions.reverse();
+    Ok(actions)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::cell::CellBuilder;
+    use crate::coins::Coins;
+    use crate::message::{build_out_list, parse_out_list, LibRef, OutAction, SendMode};
+
+    #[test]
+    fn out_list_roundtrip() -> anyhow::Result<()> {
+        let msg = Arc::new(CellBuilder::new().store_u32(32, 1)?.build()?);
+        let code = Arc::new(CellBuilder::new().store_u32(32, 2)?.build()?);
+        let actions = vec![
+            OutAction::send_msg(SendMode::PAY_GAS_SEPARATELY | SendMode::IGNORE_ERRORS, &msg),
+            OutAction::ReserveCurrency {
+                mode: 2,
+                grams: Coins::from_nano(1_000_000),
+                other: None,
+            },
+            OutAction::ChangeLibrary {
+                mode: 1,
+                libref: LibRef::Hash([7u8; 32]),
+            },
+            OutAction::SetCode { new_code: code },
+        ];
+        let list = build_out_list(&actions)?;
+        assert_eq!(parse_out_list(&list)?, actions);
+        assert!(parse_out_list(&CellBuilder::new().build()?)?.is_empty());
+
+        let invalid = OutAction::send_msg(SendMode::from_bits_retain(64 | 128), &msg);
+        assert!(build_out_list(&[invalid]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+use std::sync::Arc;
+
+use crate::address::TonAddress;
+use crate::cell::{ArcCell, Cell, CellBuilder, CellParser, TonCellError};
+use crate::coins::Coins;
+use crate::message::{parse_out_list, CommonMsgInfo, OutAction, SendMode};
+
+/// Message sent by a contract according to TL-B schema:
+///
+/// ```raw
+/// message$_ {X:Type} info:CommonMsgInfoRelaxed
+///   init:(Maybe (Either StateInit ^StateInit))
+///   body:(Either X ^X) = MessageRelaxed X;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutMessage {
+    /// Header, never [`CommonMsgInfo::ExternalIn`].
+    pub info: CommonMsgInfo,
+    /// State init, moved to its own cell if it was stored inline.
+    pub state_init: Option<ArcCell>,
+    /// Body, moved to its own cell if it was stored inline.
+    pub body: ArcCell,
+}
+
+/// Message sent by `action_send_msg`, with its mode.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SentMessage {
+    pub mode: SendMode,
+    pub message: OutMessage,
+}
+
+impl OutMessage {
+    pub fn parse(cell: &Cell) -> Result<OutMessage, TonCellError> {
+        let mut parser = cell.parser();
+        let info = CommonMsgInfo::load(&mut parser)?;
+        if let CommonMsgInfo::ExternalIn(_) = info {
+            // ext_in_msg_info$10 is not a valid CommonMsgInfoRelaxed
+            return Err(TonCellError::unexpected_structure("CommonMsgInfoRelaxed"));
+        }
+        let state_init = if parser.load_bit()? {
+            if parser.load_bit()? {
+                Some(parser.next_reference()?)
+            } else {
+                Some(Arc::new(load_inline_state_init(&mut parser)?))
+            }
+        } else {
+            None
+        };
+        let body = if parser.load_bit()? {
+            parser.next_reference()?
+        } else {
+            Arc::new(parser.remainder_to_builder()?.build()?)
+        };
+        parser.ensure_empty()?;
+        Ok(OutMessage {
+            info,
+            state_init,
+            body,
+        })
+    }
+
+    /// Returns the destination of internal messages.
+    pub fn destination(&self) -> Option<&TonAddress> {
+        match &self.info {
+            CommonMsgInfo::Internal(info) => Some(&info.dest),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of internal messages, excluding extra currencies.
+    pub fn value(&self) -> Option<&Coins> {
+        match &self.info {
+            CommonMsgInfo::Internal(info) => Some(&info.value),
+            _ => None,
+        }
+    }
+}
+
+impl SentMessage {
+    /// Returns the messages sent by the out action list, in the order they are sent.
+    ///
+    /// Other actions are skipped, use [`parse_out_list`] to get all actions.
+    pub fn parse_out_list(list: &Cell) -> Result<Vec<SentMessage>, TonCellError> {
+        parse_out_list(list)?
+            .into_iter()
+            .filter_map(|action| match action {
+                OutAction::SendMsg { mode, out_msg } => Some((mode, out_msg)),
+                _ => None,
+            })
+            .map(|(mode, out_msg)| {
+                Ok(SentMessage {
+                    mode,
+                    message: OutMessage::parse(&out_msg)?,
+                })
+            })
+            .collect()
+    }
+
+    pub fn has_mode(&self, flag: SendMode) -> bool {
+        self.mode.contains(flag)
+    }
+}
+
+/// Copies a state init stored inline to a new cell:
+///
+/// ```raw
+/// _ split_depth:(Maybe (## 5)) special:(Maybe TickTock)
+///   code:(Maybe ^Cell) data:(Maybe ^Cell)
+///   library:(Maybe ^Cell) = StateInit;
+/// ```
+fn load_inline_state_init(parser: &mut CellParser) -> Result<Cell, TonCellError> {
+    let mut builder = CellBuilder::new();
+    for len in [5, 2] {
+        let present = parser.load_bit()?;
+        builder.store_bit(present)?;
+        if present {
+            builder.store_u8(len, parser.load_u8(len)?)?;
+        }
+    }
+    for _ in 0..3 {
+        let present = parser.load_bit()?;
+        builder.store_bit(present)?;
+        if present {
+            builder.store_reference(&parser.next_reference()?)?;
+        }
+    }
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::address::TonAddress;
+    use crate::cell::{CellBuilder, StateInitBuilder};
+    use crate::coins::Coins;
+    use crate::message::{
+        build_out_list, CommonMsgInfo, OutAction, OutMessage, SendMode, SentMessage,
+        TransferMessage,
+    };
+
+    #[test]
+    fn sent_messages_are_decoded() -> anyhow::Result<()> {
+        let dest: TonAddress = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR".parse()?;
+        let body = Arc::new(CellBuilder::new().store_u32(32, 0x12345678)?.build()?);
+        let transfer = TransferMessage::new(&dest, &Coins::from_nano(100))
+            .with_data_ref(&body)
+            .build()?;
+        let code = Arc::new(CellBuilder::new().store_u8(8, 1)?.build()?);
+        let actions = vec![
+            OutAction::send_msg(
+                SendMode::PAY_GAS_SEPARATELY | SendMode::IGNORE_ERRORS,
+                &Arc::new(transfer),
+            ),
+            OutAction::SetCode {
+                new_code: code.clone(),
+            },
+        ];
+
+        let sent = SentMessage::parse_out_list(&build_out_list(&actions)?)?;
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].mode.bits(), 3);
+        assert!(sent[0].has_mode(SendMode::PAY_GAS_SEPARATELY | SendMode::IGNORE_ERRORS));
+        let message = &sent[0].message;
+        assert_eq!(message.destination(), Some(&dest));
+        assert_eq!(message.value(), Some(&Coins::from_nano(100)));
+        assert_eq!(message.body, body);
+        assert_eq!(message.state_init, None);
+        match &message.info {
+            CommonMsgInfo::Internal(info) => {
+                assert_eq!(info.src, TonAddress::NULL);
+                assert!(info.bounce);
+            }
+            info => panic!("unexpected message info: {:?}", info),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn inline_state_init_and_body_are_decoded() -> anyhow::Result<()> {
+        let code = Arc::new(CellBuilder::new().store_u8(8, 1)?.build()?);
+        let data = Arc::new(CellBuilder::new().store_u8(8, 2)?.build()?);
+        let state_init = StateInitBuilder::new(&code, &data).build()?;
+
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u8(2, 0b11)? // ext_out_msg_info$11
+            .store_address(&TonAddress::NULL)?
+            .store_u8(2, 0b01)? // addr_extern$01
+            .store_u16(9, 12)?
+            .store_u16(12, 0xabc)?
+            .store_u64(64, 7)?
+            .store_u32(32, 8)?
+            .store_bit(true)? // init
+            .store_bit(false)? // inline state init
+            .store_cell(&state_init)?
+            .store_bit(false)? // inline body
+            .store_u8(8, 0xee)?;
+        let message = OutMessage::parse(&builder.build()?)?;
+
+        assert_eq!(message.destination(), None);
+        match &message.info {
+            CommonMsgInfo::ExternalOut(info) => {
+                let dest = info.dest.as_ref().unwrap();
+                assert_eq!((dest.bit_len, dest.address.clone()), (12, vec![0xab, 0xc0]));
+                assert_eq!(info.created_lt, 7);
+            }
+            info => panic!("unexpected message info: {:?}", info),
+        }
+        assert_eq!(message.state_init.as_deref(), Some(&state_init));
+        assert_eq!(
+            message.body.as_ref(),
+            &CellBuilder::new().store_u8(8, 0xee)?.build()?
+        );
+        Ok(())
+    }
+}
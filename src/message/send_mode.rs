@@ -0,0 +1,129 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{BitOr, BitOrAssign};
+
+use crate::cell::TonCellError;
+
+/// Mode of `action_send_msg`, a combination of flags:
+///
+/// ```raw
+/// action_send_msg#0ec3c86d mode:(## 8) out_msg:^(MessageRelaxed Any) = OutAction;
+/// ```
+///
+/// Modes with undefined flags or with both [`SendMode::CARRY_ALL_VALUE`] and
+/// [`SendMode::CARRY_ALL_BALANCE`] fail the action phase, see [`SendMode::validate`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SendMode(u8);
+
+impl SendMode {
+    /// Sends the value of the message, paying the fees from it.
+    pub const ORDINARY: SendMode = SendMode(0);
+    /// Pays the forwarding fees from the balance of the contract instead of the value.
+    pub const PAY_GAS_SEPARATELY: SendMode = SendMode(1);
+    /// Skips the message instead of failing the action phase if it can't be sent.
+    pub const IGNORE_ERRORS: SendMode = SendMode(2);
+    /// Bounces the inbound message if the action phase fails.
+    pub const BOUNCE_ON_ACTION_FAIL: SendMode = SendMode(16);
+    /// Destroys the contract if its balance becomes zero.
+    pub const DESTROY: SendMode = SendMode(32);
+    /// Carries the remaining value of the inbound message in addition to the value.
+    pub const CARRY_ALL_VALUE: SendMode = SendMode(64);
+    /// Carries the whole remaining balance of the contract.
+    pub const CARRY_ALL_BALANCE: SendMode = SendMode(128);
+
+    const DEFINED_BITS: u8 = 0b1111_0011;
+
+    /// Creates the mode from its bits, without validating them, e.g. to parse on-chain actions.
+    pub const fn from_bits_retain(bits: u8) -> SendMode {
+        SendMode(bits)
+    }
+
+    /// Creates the mode from its bits, failing if they are not a valid mode.
+    pub fn from_bits(bits: u8) -> Result<SendMode, TonCellError> {
+        SendMode(bits).validate()
+    }
+
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    pub const fn union(self, other: SendMode) -> SendMode {
+        SendMode(self.0 | other.0)
+    }
+
+    pub const fn contains(&self, other: SendMode) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub const fn intersects(&self, other: SendMode) -> bool {
+        self.0 & other.0 != 0
+    }
+
+    /// Returns the mode if it can be sent, i.e. it has no undefined flags and doesn't carry
+    /// both the inbound value and the whole balance.
+    pub fn validate(self) -> Result<SendMode, TonCellError> {
+        if self.0 & !Self::DEFINED_BITS != 0 {
+            return Err(TonCellError::cell_builder_error(format!(
+                "Send mode {} has undefined flags",
+                self
+            )));
+        }
+        if self.contains(Self::CARRY_ALL_VALUE.union(Self::CARRY_ALL_BALANCE)) {
+            return Err(TonCellError::cell_builder_error(format!(
+                "Send mode {} carries both the inbound value and the whole balance",
+                self
+            )));
+        }
+        Ok(self)
+    }
+}
+
+impl BitOr for SendMode {
+    type Output = SendMode;
+
+    fn bitor(self, rhs: SendMode) -> SendMode {
+        self.union(rhs)
+    }
+}
+
+impl BitOrAssign for SendMode {
+    fn bitor_assign(&mut self, rhs: SendMode) {
+        *self = self.union(rhs);
+    }
+}
+
+impl From<SendMode> for u8 {
+    fn from(mode: SendMode) -> u8 {
+        mode.0
+    }
+}
+
+impl Display for SendMode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::message::SendMode;
+
+    #[test]
+    fn send_mode_works() -> anyhow::Result<()> {
+        let mode = SendMode::PAY_GAS_SEPARATELY | SendMode::IGNORE_ERRORS;
+        assert_eq!(mode.bits(), 3);
+        assert!(mode.contains(SendMode::IGNORE_ERRORS));
+        assert!(!mode.intersects(SendMode::CARRY_ALL_BALANCE));
+        assert_eq!(SendMode::from_bits(3)?, mode);
+
+        let mut drain = SendMode::CARRY_ALL_BALANCE;
+        drain |= SendMode::DESTROY;
+        assert_eq!(drain.validate()?.bits(), 160);
+        assert!((SendMode::CARRY_ALL_VALUE | SendMode::CARRY_ALL_BALANCE)
+            .validate()
+            .is_err());
+        assert!(SendMode::from_bits(4).is_err());
+        assert!(SendMode::from_bits(8 | 1).is_err());
+        assert_eq!(SendMode::from_bits_retain(4).bits(), 4);
+        Ok(())
+    }
+}
@@ -0,0 +1,233 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::cell::{Cell, CellBuilder, CellParser, TonCellError};
+use crate::message::{DnsRecord, TonMessageError};
+
+// Constants from TON Storage
+// https://github.com/ton-blockchain/ton/blob/master/storage/torrent-info.tlb
+
+pub const TON_STORAGE_SCHEME: &str = "tonstorage://";
+
+// Chunks of texts in references hold their 8-bit length and up to 126 bytes
+const MAX_TEXT_CHUNK_BYTES: usize = 126;
+
+/// Id of a TON Storage bag, i.e. the hash of its [`TorrentInfo`] cell.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BagId(pub [u8; 32]);
+
+impl BagId {
+    /// Parses `tonstorage://<bag id>/<path>` uris, returning the bag id and the path in the bag.
+    pub fn from_uri(uri: &str) -> Result<(BagId, String), TonMessageError> {
+        let rest = uri
+            .strip_prefix(TON_STORAGE_SCHEME)
+            .ok_or_else(|| TonMessageError::InvalidBagId(format!("Not a storage uri: {}", uri)))?;
+        let (bag_id, path) = rest.split_once('/').unwrap_or((rest, ""));
+        Ok((BagId::from_str(bag_id)?, path.to_string()))
+    }
+
+    pub fn to_uri(&self, path: &str) -> String {
+        match path.trim_start_matches('/') {
+            "" => format!("{}{}", TON_STORAGE_SCHEME, self),
+            path => format!("{}{}/{}", TON_STORAGE_SCHEME, self, path),
+        }
+    }
+
+    /// Returns the bag id of `dns_storage_address` records.
+    pub fn from_dns_record(record: &DnsRecord) -> Option<BagId> {
+        match record {
+            DnsRecord::StorageAddress(bag_id) => Some(BagId(*bag_id)),
+            _ => None,
+        }
+    }
+
+    pub fn to_dns_record(&self) -> DnsRecord {
+        DnsRecord::StorageAddress(self.0)
+    }
+}
+
+impl FromStr for BagId {
+    type Err = TonMessageError;
+
+    /// Parses the hex representation of the bag id, in either case.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes =
+            hex::decode(s).map_err(|e| TonMessageError::InvalidBagId(format!("{}: {}", s, e)))?;
+        let bag_id = bytes
+            .try_into()
+            .map_err(|_| TonMessageError::InvalidBagId(format!("{}: Wrong length", s)))?;
+        Ok(BagId(bag_id))
+    }
+}
+
+impl Display for BagId {
+    /// Formats the bag id as upper case hex, like the storage daemon.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", hex::encode_upper(self.0))
+    }
+}
+
+/// Description of the content of a bag, whose hash is the bag id:
+///
+/// ```raw
+/// torrent_info piece_size:uint32 file_size:uint64 root_hash:(## 256) header_size:uint64
+///              header_hash:(## 256) microchunk_hash:(Maybe (## 256)) description:Text
+///              = TorrentInfo;
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TorrentInfo {
+    pub piece_size: u32,
+    pub file_size: u64,
+    pub root_hash: [u8; 32],
+    pub header_size: u64,
+    pub header_hash: [u8; 32],
+    pub microchunk_hash: Option<[u8; 32]>,
+    pub description: String,
+}
+
+impl TorrentInfo {
+    pub fn build(&self) -> Result<Cell, TonMessageError> {
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u32(32, self.piece_size)?
+            .store_u64(64, self.file_size)?
+            .store_slice(&self.root_hash)?
+            .store_u64(64, self.header_size)?
+            .store_slice(&self.header_hash)?
+            .store_bit(self.microchunk_hash.is_some())?;
+        if let Some(microchunk_hash) = self.microchunk_hash.as_ref() {
+            builder.store_slice(microchunk_hash)?;
+        }
+        store_text(&mut builder, self.description.as_bytes())?;
+        Ok(builder.build()?)
+    }
+
+    pub fn parse(cell: &Cell) -> Result<TorrentInfo, TonMessageError> {
+        let mut parser = cell.parser();
+        let mut info = TorrentInfo {
+            piece_size: parser.load_u32(32)?,
+            file_size: parser.load_u64(64)?,
+            ..Default::default()
+        };
+        parser.load_slice(&mut info.root_hash)?;
+        info.header_size = parser.load_u64(64)?;
+        parser.load_slice(&mut info.header_hash)?;
+        if parser.load_bit()? {
+            let mut microchunk_hash = [0; 32];
+            parser.load_slice(&mut microchunk_hash)?;
+            info.microchunk_hash = Some(microchunk_hash);
+        }
+        let description = load_text(cell, &mut parser)?;
+        info.description =
+            String::from_utf8(description).map_err(TonCellError::cell_parser_error)?;
+        Ok(info)
+    }
+
+    pub fn bag_id(&self) -> Result<BagId, TonMessageError> {
+        let hash = self.build()?.cell_hash()?;
+        let bag_id = hash
+            .try_into()
+            .map_err(|_| TonCellError::InternalError("Invalid cell hash".to_string()))?;
+        Ok(BagId(bag_id))
+    }
+}
+
+/// Stores `text$_ chunks:(## 8) rest:(TextChunks chunks)`, filling the current cell before
+/// moving the next chunks to references.
+fn store_text(builder: &mut CellBuilder, text: &[u8]) -> Result<(), TonCellError> {
+    let first_len = (builder.remaining_bits().saturating_sub(16) / 8).min(text.len());
+    let (first, rest) = text.split_at(first_len);
+    let rest: Vec<&[u8]> = rest.chunks(MAX_TEXT_CHUNK_BYTES).collect();
+    let chunks = if text.is_empty() { 0 } else { 1 + rest.len() };
+    let chunks = u8::try_from(chunks).map_err(TonCellError::cell_builder_error)?;
+    builder.store_u8(8, chunks)?;
+    if chunks == 0 {
+        return Ok(());
+    }
+    let mut next: Option<Cell> = None;
+    for chunk in rest.iter().rev() {
+        let mut chunk_builder = CellBuilder::new();
+        chunk_builder
+            .store_u8(8, chunk.len() as u8)?
+            .store_slice(chunk)?;
+        if let Some(next) = next.take() {
+            chunk_builder.store_child(next)?;
+        }
+        next = Some(chunk_builder.build()?);
+    }
+    builder.store_u8(8, first.len() as u8)?.store_slice(first)?;
+    if let Some(next) = next {
+        builder.store_child(next)?;
+    }
+    Ok(())
+}
+
+fn load_text(cell: &Cell, parser: &mut CellParser) -> Result<Vec<u8>, TonCellError> {
+    let chunks = parser.load_u8(8)?;
+    let mut text = vec![];
+    if chunks == 0 {
+        return Ok(text);
+    }
+    let len = parser.load_u8(8)?;
+    text.extend(parser.load_bytes(len as usize)?);
+    let mut current = cell;
+    for _ in 1..chunks {
+        current = current.reference(0)?;
+        let mut parser = current.parser();
+        let len = parser.load_u8(8)?;
+        text.extend(parser.load_bytes(len as usize)?);
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::message::{BagId, DnsRecord, TorrentInfo};
+
+    #[test]
+    fn bag_ids_work() -> anyhow::Result<()> {
+        let hex = "4C9A7C4CF1D5F3E8A1B1A0C3E4B3C62B5B5C5B0F8C7C9E6D3C2B1A0F9E8D7C6B";
+        let bag_id = BagId::from_str(&hex.to_lowercase())?;
+        assert_eq!(bag_id.to_string(), hex);
+        assert!(BagId::from_str("4C9A").is_err());
+        assert!(BagId::from_str(&hex.replace('4', "x")).is_err());
+
+        let uri = bag_id.to_uri("/images/1.png");
+        assert_eq!(uri, format!("tonstorage://{}/images/1.png", hex));
+        assert_eq!(BagId::from_uri(&uri)?, (bag_id, "images/1.png".to_string()));
+        assert_eq!(
+            BagId::from_uri(&bag_id.to_uri(""))?,
+            (bag_id, String::new())
+        );
+        assert!(BagId::from_uri("ipfs://QmHash/1.png").is_err());
+
+        let record = DnsRecord::parse(&bag_id.to_dns_record().build()?)?;
+        assert_eq!(BagId::from_dns_record(&record), Some(bag_id));
+        assert_eq!(
+            BagId::from_dns_record(&DnsRecord::AdnlAddress([0; 32])),
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn torrent_info_works() -> anyhow::Result<()> {
+        for description in ["", "short", &"long description ".repeat(40)] {
+            let info = TorrentInfo {
+                piece_size: 128 * 1024,
+                file_size: 1_000_000,
+                root_hash: [1; 32],
+                header_size: 300,
+                header_hash: [2; 32],
+                microchunk_hash: (description.len() % 2 == 0).then_some([3; 32]),
+                description: description.to_string(),
+            };
+            let cell = info.build()?;
+            assert_eq!(TorrentInfo::parse(&cell)?, info);
+            assert_eq!(info.bag_id()?.0.to_vec(), cell.cell_hash()?);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,285 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+use crate::address::TonAddress;
+use crate::cell::{ArcCell, BagOfCells};
+use crate::coins::Coins;
+use crate::message::{TonMessageError, TransferMessage};
+
+// Constants from TON Connect protocol
+// https://github.com/ton-blockchain/ton-connect/blob/main/requests-responses.md
+
+/// Global id of the mainnet, as used for the `network` of requests.
+pub const TON_CONNECT_MAINNET: &str = "-239";
+/// Global id of the testnet, as used for the `network` of requests.
+pub const TON_CONNECT_TESTNET: &str = "-3";
+
+/// Message of a `sendTransaction` request, with the state init and the payload as base64 bags
+/// of cells.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TonConnectMessage {
+    pub address: String,
+    /// Amount in nanotons, as a decimal string.
+    pub amount: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload: Option<String>,
+    #[serde(rename = "stateInit", default, skip_serializing_if = "Option::is_none")]
+    pub state_init: Option<String>,
+}
+
+impl TonConnectMessage {
+    pub fn from_transfer(transfer: &TransferMessage) -> Result<TonConnectMessage, TonMessageError> {
+        if !transfer.extra_currencies.is_empty() {
+            return Err(TonMessageError::TonConnectError(
+                "Extra currencies are not supported".to_string(),
+            ));
+        }
+        Ok(TonConnectMessage {
+            address: transfer.dest.to_base64_url(),
+            amount: transfer.value.nanotons().to_string(),
+            payload: transfer.data.as_ref().map(serialize_cell).transpose()?,
+            state_init: transfer
+                .state_init
+                .as_ref()
+                .map(serialize_cell)
+                .transpose()?,
+        })
+    }
+
+    pub fn to_transfer(&self) -> Result<TransferMessage, TonMessageError> {
+        let dest = TonAddress::from_str(&self.address).map_err(|e| {
+            TonMessageError::TonConnectError(format!("Invalid address {}: {}", self.address, e))
+        })?;
+        let value = BigUint::from_str(&self.amount).map_err(|e| {
+            TonMessageError::TonConnectError(format!("Invalid amount {}: {}", self.amount, e))
+        })?;
+        let mut transfer = TransferMessage::new(&dest, &Coins::new(value));
+        if let Some(payload) = self.payload.as_ref() {
+            transfer.with_data_ref(&parse_cell(payload)?);
+        }
+        if let Some(state_init) = self.state_init.as_ref() {
+            transfer.with_state_init_ref(&parse_cell(state_init)?);
+        }
+        Ok(transfer)
+    }
+
+    /// Returns the `ton://transfer` deep link of the message, with url-safe bags of cells.
+    pub fn to_deep_link(&self) -> Result<String, TonMessageError> {
+        let mut link = format!("ton://transfer/{}?amount={}", self.address, self.amount);
+        if let Some(payload) = self.payload.as_ref() {
+            link += &format!("&bin={}", to_url_safe(payload)?);
+        }
+        if let Some(state_init) = self.state_init.as_ref() {
+            link += &format!("&init={}", to_url_safe(state_init)?);
+        }
+        Ok(link)
+    }
+}
+
+/// Parameters of a `sendTransaction` request:
+///
+/// ```raw
+/// { "valid_until": 1658253458, "network": "-239", "from": "0:...", "messages": [...] }
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SendTransactionRequest {
+    pub valid_until: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub network: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    pub messages: Vec<TonConnectMessage>,
+}
+
+impl SendTransactionRequest {
+    pub fn new(valid_until: u64) -> SendTransactionRequest {
+        SendTransactionRequest {
+            valid_until,
+            network: None,
+            from: None,
+            messages: vec![],
+        }
+    }
+
+    pub fn with_network(&mut self, network: &str) -> &mut Self {
+        self.network = Some(network.to_string());
+        self
+    }
+
+    pub fn with_from(&mut self, from: &TonAddress) -> &mut Self {
+        self.from = Some(from.to_hex());
+        self
+    }
+
+    pub fn with_message(
+        &mut self,
+        transfer: &TransferMessage,
+    ) -> Result<&mut Self, TonMessageError> {
+        self.messages
+            .push(TonConnectMessage::from_transfer(transfer)?);
+        Ok(self)
+    }
+
+    /// Returns the transfers of the request, checking their payloads and state inits.
+    pub fn transfers(&self) -> Result<Vec<TransferMessage>, TonMessageError> {
+        self.messages.iter().map(|m| m.to_transfer()).collect()
+    }
+
+    pub fn to_json(&self) -> Result<String, TonMessageError> {
+        serde_json::to_string(self).map_err(|e| TonMessageError::TonConnectError(e.to_string()))
+    }
+
+    pub fn from_json(json: &str) -> Result<SendTransactionRequest, TonMessageError> {
+        serde_json::from_str(json).map_err(|e| TonMessageError::TonConnectError(e.to_string()))
+    }
+
+    /// Returns the JSON-RPC request sent to the wallet, whose single param is the JSON of the
+    /// request.
+    pub fn to_rpc_request(&self, id: &str) -> Result<String, TonMessageError> {
+        let rpc = RpcRequest {
+            method: "sendTransaction".to_string(),
+            params: vec![self.to_json()?],
+            id: id.to_string(),
+        };
+        serde_json::to_string(&rpc).map_err(|e| TonMessageError::TonConnectError(e.to_string()))
+    }
+}
+
+/// Response of the wallet to a `sendTransaction` request.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SendTransactionResponse {
+    pub id: String,
+    /// Signed external message sent by the wallet.
+    pub message: ArcCell,
+}
+
+impl SendTransactionResponse {
+    /// Parses the JSON-RPC response of the wallet, returning
+    /// [`TonMessageError::RequestRejected`] if the wallet replied with an error.
+    pub fn parse(json: &str) -> Result<SendTransactionResponse, TonMessageError> {
+        let rpc: RpcResponse = serde_json::from_str(json)
+            .map_err(|e| TonMessageError::TonConnectError(e.to_string()))?;
+        match (rpc.result, rpc.error) {
+            (Some(result), None) => Ok(SendTransactionResponse {
+                id: rpc.id,
+                message: parse_cell(&result)?,
+            }),
+            (None, Some(error)) => Err(TonMessageError::RequestRejected(format!(
+                "code {}: {}",
+                error.code, error.message
+            ))),
+            _ => Err(TonMessageError::TonConnectError(
+                "Response must contain either result or error".to_string(),
+            )),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RpcRequest {
+    method: String,
+    params: Vec<String>,
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct RpcResponse {
+    #[serde(default)]
+    result: Option<String>,
+    #[serde(default)]
+    error: Option<RpcError>,
+    id: String,
+}
+
+#[derive(Deserialize)]
+struct RpcError {
+    code: i32,
+    #[serde(default)]
+    message: String,
+}
+
+fn serialize_cell(cell: &ArcCell) -> Result<String, TonMessageError> {
+    Ok(BagOfCells::new(std::slice::from_ref(cell)).serialize_base64(true)?)
+}
+
+fn parse_cell(base64: &str) -> Result<ArcCell, TonMessageError> {
+    Ok(Arc::clone(BagOfCells::parse_base64(base64)?.single_root()?))
+}
+
+fn to_url_safe(base64: &str) -> Result<String, TonMessageError> {
+    let boc = BagOfCells::parse_base64(base64)?.serialize(true)?;
+    Ok(URL_SAFE_NO_PAD.encode(boc))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::address::TonAddress;
+    use crate::cell::{BagOfCells, CellBuilder};
+    use crate::coins::Coins;
+    use crate::message::{
+        SendTransactionRequest, SendTransactionResponse, TonMessageError, TransferMessage,
+        TON_CONNECT_MAINNET,
+    };
+
+    #[test]
+    fn ton_connect_requests_work() -> anyhow::Result<()> {
+        let dest = TonAddress::from_base64_url("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR")?;
+        let payload = CellBuilder::new()
+            .store_u32(32, 0)?
+            .store_string("hi")?
+            .build()?;
+        let state_init = CellBuilder::new().store_u8(5, 0b00110)?.build()?;
+        let mut transfer = TransferMessage::new(&dest, &Coins::from_nano(1_500_000_000));
+        transfer
+            .with_data(payload.clone())
+            .with_state_init(state_init);
+
+        let mut request = SendTransactionRequest::new(1658253458);
+        request
+            .with_network(TON_CONNECT_MAINNET)
+            .with_from(&dest)
+            .with_message(&transfer)?
+            .with_message(&TransferMessage::new(&dest, &Coins::from_nano(1)))?;
+        let json = request.to_json()?;
+        assert!(json.contains(r#""valid_until":1658253458,"network":"-239""#));
+        assert!(json.contains(r#""amount":"1500000000""#));
+        assert!(json.contains(r#""stateInit":"#));
+        assert!(!json.contains("null"));
+
+        let parsed = SendTransactionRequest::from_json(&json)?;
+        assert_eq!(parsed, request);
+        let transfers = parsed.transfers()?;
+        assert_eq!(
+            transfers[0].build()?.cell_hash()?,
+            transfer.build()?.cell_hash()?
+        );
+        assert_eq!(transfers[1].data, None);
+
+        let rpc = request.to_rpc_request("7")?;
+        assert!(rpc.starts_with(r#"{"method":"sendTransaction","params":["{\"valid_until\""#));
+
+        let link = request.messages[1].to_deep_link()?;
+        assert_eq!(
+            link,
+            format!("ton://transfer/{}?amount=1", dest.to_base64_url())
+        );
+        assert!(request.messages[0].to_deep_link()?.contains("&bin=te6"));
+
+        let boc = BagOfCells::from_root(payload.clone()).serialize_base64(true)?;
+        let response =
+            SendTransactionResponse::parse(&format!(r#"{{"result":"{boc}","id":"7"}}"#))?;
+        assert_eq!(response.id, "7");
+        assert_eq!(response.message.cell_hash()?, payload.cell_hash()?);
+        let rejected = SendTransactionResponse::parse(
+            r#"{"error":{"code":300,"message":"User declined the transaction"},"id":"7"}"#,
+        );
+        assert!(matches!(rejected, Err(TonMessageError::RequestRejected(_))));
+        assert!(SendTransactionResponse::parse(r#"{"id":"7"}"#).is_err());
+        Ok(())
+    }
+}
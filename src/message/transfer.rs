@@ -1,28 +1,37 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use num_bigint::BigUint;
 
 use crate::address::TonAddress;
-use crate::cell::{ArcCell, Cell, CellBuilder};
-use crate::message::{TonMessageError, ZERO_COINS};
+use crate::cell::{ArcCell, Cell};
+use crate::coins::Coins;
+use crate::message::{CommonMsgInfo, InternalMessageInfo, TonMessageError};
 
 pub struct TransferMessage {
     pub dest: TonAddress,
-    pub value: BigUint,
+    pub value: Coins,
+    pub extra_currencies: HashMap<u32, BigUint>,
     pub state_init: Option<ArcCell>,
     pub data: Option<ArcCell>,
 }
 
 impl TransferMessage {
-    pub fn new(dest: &TonAddress, value: &BigUint) -> Self {
+    pub fn new(dest: &TonAddress, value: &Coins) -> Self {
         TransferMessage {
             dest: dest.clone(),
             value: value.clone(),
+            extra_currencies: HashMap::new(),
             state_init: None,
             data: None,
         }
     }
 
+    pub fn with_extra_currency(&mut self, id: u32, amount: &BigUint) -> &mut Self {
+        self.extra_currencies.insert(id, amount.clone());
+        self
+    }
+
     pub fn with_state_init(&mut self, state_init: Cell) -> &mut Self {
         self.with_state_init_ref(&Arc::new(state_init))
     }
@@ -42,27 +51,47 @@ impl TransferMessage {
     }
 
     pub fn build(&self) -> Result<Cell, TonMessageError> {
-        let mut builder = CellBuilder::new();
-        builder.store_bit(false)?; // bit0
-        builder.store_bit(true)?; // ihr_disabled
-        builder.store_bit(true)?; // bounce
-        builder.store_bit(false)?; // bounced
-        builder.store_address(&TonAddress::NULL)?; // src_addr
-        builder.store_address(&self.dest)?; // dest_addr
-        builder.store_coins(&self.value)?; // value
-        builder.store_bit(false)?; // currency_coll
-        builder.store_coins(&ZERO_COINS)?; // ihr_fees
-        builder.store_coins(&ZERO_COINS)?; // fwd_fees
-        builder.store_u64(64, 0)?; // created_lt
-        builder.store_u32(32, 0)?; // created_at
-        builder.store_bit(self.state_init.is_some())?; // state_init?
-        if let Some(state_init) = self.state_init.as_ref() {
-            builder.store_reference(state_init)?;
-        }
-        builder.store_bit(self.data.is_some())?; // data?
-        if let Some(data) = self.data.as_ref() {
-            builder.store_reference(data)?;
-        }
-        Ok(builder.build()?)
+        let mut info = InternalMessageInfo::new(&self.dest, &self.value);
+        info.extra_currencies = self.extra_currencies.clone();
+        CommonMsgInfo::from(info).build_message(self.state_init.as_ref(), self.data.as_ref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::address::TonAddress;
+    use crate::cell::{CellBuilder, StateInitBuilder};
+    use crate::coins::Coins;
+    use crate::message::TransferMessage;
+
+    #[test]
+    fn state_init_is_stored_as_reference() -> anyhow::Result<()> {
+        let dest: TonAddress = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR".parse()?;
+        let code = Arc::new(CellBuilder::new().store_u8(8, 1)?.build()?);
+        let data = Arc::new(CellBuilder::new().store_u8(8, 2)?.build()?);
+        let state_init = Arc::new(StateInitBuilder::new(&code, &data).build()?);
+        let body = Arc::new(CellBuilder::new().store_u32(32, 0x12345678)?.build()?);
+        let message = TransferMessage::new(&dest, &Coins::from_nano(100))
+            .with_state_init_ref(&state_init)
+            .with_data_ref(&body)
+            .build()?;
+
+        let mut parser = message.parser();
+        parser.skip_bits(4)?;
+        assert_eq!(parser.load_address()?, TonAddress::NULL);
+        assert_eq!(parser.load_address()?, dest);
+        assert_eq!(parser.load_coins()?, Coins::from(100u64));
+        assert!(!parser.load_bit()?); // no extra currencies
+        parser.load_coins()?;
+        parser.load_coins()?;
+        parser.skip_bits(64 + 32)?;
+        assert!(parser.load_bit()?); // Maybe
+        assert!(parser.load_bit()?); // Either: ^StateInit
+        assert!(parser.load_bit()?); // Either: ^X
+        parser.ensure_empty()?;
+        assert_eq!(message.references, [state_init, body]);
+        Ok(())
     }
 }
@@ -15,6 +15,7 @@ use serde::de::DeserializeOwned;
 use sha2::{Digest, Sha256};
 
 use crate::cell::{ArcCell, BagOfCells, SnakeFormattedDict, TonCellError};
+use crate::message::BagId;
 
 struct MetaDataField {
     pub(crate) key: [u8; 32],
@@ -97,6 +98,14 @@ impl MetaDataContent {
             }),
         }
     }
+
+    /// Returns the TON Storage bag and the path in it of `tonstorage://` external content.
+    pub fn storage_bag_id(&self) -> Option<(BagId, String)> {
+        match self {
+            MetaDataContent::External { uri } => BagId::from_uri(uri).ok(),
+            _ => None,
+        }
+    }
 }
 
 pub struct MetaLoader<MetaData>
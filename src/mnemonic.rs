@@ -6,11 +6,13 @@ use std::collections::HashMap;
 pub use error::*;
 use hmac::{Hmac, Mac};
 use lazy_static::lazy_static;
-use nacl::sign::generate_keypair;
 use pbkdf2::password_hash::Output;
 use pbkdf2::{pbkdf2_hmac, Params};
 use sha2::Sha512;
 
+pub use crate::crypto::KeyPair;
+use crate::crypto::{zeroize, SEED_LEN};
+
 const WORDLIST_EN: &str = include_str!("mnemonic/wordlist.EN");
 const PBKDF_ITERATIONS: u32 = 100000;
 
@@ -32,12 +34,6 @@ pub struct Mnemonic {
     password: Option<String>,
 }
 
-#[derive(PartialEq, Eq, Clone, Hash)]
-pub struct KeyPair {
-    pub public_key: Vec<u8>,
-    pub secret_key: Vec<u8>,
-}
-
 impl Mnemonic {
     pub fn new(words: Vec<&str>, password: &Option<String>) -> Result<Mnemonic, MnemonicError> {
         let normalized_words: Vec<String> = words.iter().map(|w| w.trim().to_lowercase()).collect();
@@ -104,12 +100,10 @@ impl Mnemonic {
 
     pub fn to_key_pair(&self) -> Result<KeyPair, MnemonicError> {
         let entropy = to_entropy(&self.words, &self.password)?;
-        let seed = pbkdf2_sha512(entropy, "TON default seed", PBKDF_ITERATIONS, 64)?;
-        let key_pair = generate_keypair(&seed.as_slice()[0..32]);
-        Ok(KeyPair {
-            public_key: key_pair.pkey.to_vec(),
-            secret_key: key_pair.skey.to_vec(),
-        })
+        let mut seed = pbkdf2_sha512(entropy, "TON default seed", PBKDF_ITERATIONS, 64)?;
+        let key_pair = KeyPair::from_seed(seed[..SEED_LEN].try_into().unwrap());
+        zeroize(&mut seed);
+        Ok(key_pair)
     }
 }
 
@@ -124,7 +118,7 @@ fn to_entropy(words: &[String], password: &Option<String>) -> Result<Vec<u8>, Mn
 }
 
 fn pbkdf2_sha512(
-    key: Vec<u8>,
+    mut key: Vec<u8>,
     salt: &str,
     rounds: u32,
     output_length: usize,
@@ -139,6 +133,7 @@ fn pbkdf2_sha512(
         Ok(())
     })
     .map_err(MnemonicError::PasswordHashError)?;
+    zeroize(&mut key);
     Ok(output.as_bytes().to_vec())
 }
 
@@ -191,7 +186,7 @@ mod tests {
         let kp = mnemonic.to_key_pair()?;
         println!("{:?} {:?}", kp.public_key, kp.secret_key);
 
-        let res = hex::encode(kp.secret_key);
+        let res = hex::encode(&kp.secret_key);
 
         assert_eq!(res, expected);
 
@@ -0,0 +1,93 @@
+mod error;
+
+use core::fmt::{Display, Formatter};
+use core::str::FromStr;
+
+pub use error::*;
+
+use crate::constants::{MAINNET_GLOBAL_ID, TESTNET_GLOBAL_ID};
+
+/// Network identified by its global id, which selects the config of the client, the
+/// `wallet_id` of wallets v5 and the test-only flag of user-friendly addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+    /// Network with another global id, e.g. a local test network.
+    Custom(i32),
+}
+
+impl Network {
+    pub fn from_global_id(global_id: i32) -> Network {
+        match global_id {
+            MAINNET_GLOBAL_ID => Network::Mainnet,
+            TESTNET_GLOBAL_ID => Network::Testnet,
+            global_id => Network::Custom(global_id),
+        }
+    }
+
+    pub fn global_id(&self) -> i32 {
+        match self {
+            Network::Mainnet => MAINNET_GLOBAL_ID,
+            Network::Testnet => TESTNET_GLOBAL_ID,
+            Network::Custom(global_id) => *global_id,
+        }
+    }
+
+    /// Returns whether user-friendly addresses of the network carry the test-only flag, which
+    /// is the case for all networks but the mainnet.
+    pub fn is_test_only(&self) -> bool {
+        self.global_id() != MAINNET_GLOBAL_ID
+    }
+}
+
+impl Display for Network {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match Network::from_global_id(self.global_id()) {
+            Network::Mainnet => f.write_str("mainnet"),
+            Network::Testnet => f.write_str("testnet"),
+            Network::Custom(global_id) => write!(f, "{}", global_id),
+        }
+    }
+}
+
+impl FromStr for Network {
+    type Err = NetworkParseError;
+
+    /// Parses `mainnet`, `testnet` or a global id.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "mainnet" => Ok(Network::Mainnet),
+            "testnet" => Ok(Network::Testnet),
+            _ => s
+                .parse()
+                .map(Network::from_global_id)
+                .map_err(|_| NetworkParseError::new(s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::network::Network;
+
+    #[test]
+    fn network_works() -> anyhow::Result<()> {
+        assert_eq!(Network::default().global_id(), -239);
+        assert_eq!(Network::from_global_id(-3), Network::Testnet);
+        assert_eq!(Network::from_global_id(42), Network::Custom(42));
+        assert!(!Network::Mainnet.is_test_only());
+        assert!(Network::Testnet.is_test_only());
+        assert!(Network::Custom(42).is_test_only());
+
+        for network in [Network::Mainnet, Network::Testnet, Network::Custom(42)] {
+            assert_eq!(network.to_string().parse::<Network>()?, network);
+        }
+        assert_eq!("TESTNET".parse::<Network>()?, Network::Testnet);
+        assert_eq!("-239".parse::<Network>()?, Network::Mainnet);
+        assert!("devnet".parse::<Network>().is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,17 @@
+use alloc::string::{String, ToString};
+
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid network: {network}, expected mainnet, testnet or a global id")]
+pub struct NetworkParseError {
+    network: String,
+}
+
+impl NetworkParseError {
+    pub fn new<N: ToString>(network: N) -> NetworkParseError {
+        NetworkParseError {
+            network: network.to_string(),
+        }
+    }
+}
@@ -1,40 +1,356 @@
-use std::{collections::HashMap, fmt::Debug};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Debug;
 
 use num_bigint::BigUint;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-use crate::{address::TonAddress, cell::Cell};
+use crate::{
+    address::TonAddress,
+    adnl::{AdnlAddress, AdnlAddressParseError},
+    cell::{
+        key_serializer_u32, value_serializer_cell_ref, ArcCell, Cell, CellBuilder, CellParser,
+        DictBuilder, TlbDeserialize, TlbSerialize, TonCellError,
+    },
+    compat::HashMap,
+    constants::MASTERCHAIN_ID,
+    hashmap::HashmapAugEResult,
+};
+
+#[cfg(feature = "serde")]
+mod encoding;
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct VarUInteger {
+    #[cfg_attr(feature = "serde", serde(with = "encoding::biguint_decimal"))]
     pub len: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::biguint_decimal"))]
     pub value: BigUint,
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BlockData {
     pub info: Option<BlockInfo>,
+    /// `None` if the value flow is pruned.
+    pub value_flow: Option<ValueFlow>,
+    pub state_update: Option<MerkleUpdate>,
     pub extra: Option<BlockExtra>,
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct MaybeRefData<T>
 where
     T: Clone + Debug + Default,
 {
     pub data: Option<T>,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::option_cell_boc"))]
     pub cell: Option<Cell>,
+    /// Whether `data` was checked to re-serialize to the referenced cell, which is only done
+    /// for the transactions of account blocks, see [`Cell::verify_transaction`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub verified: bool,
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BlockInfo {
+    pub seq_no: u32,
+    pub key_block: bool,
+    pub shard: ShardIdent,
     pub gen_utime: u32,
+    pub start_lt: u64,
+    pub end_lt: u64,
+    pub prev_key_block_seqno: u32,
+    pub gen_software: Option<GlobalVersion>,
+    /// Last masterchain block known to shardchain blocks, `None` for masterchain blocks.
+    pub master_ref: Option<ExtBlkRef>,
     pub prev_ref: BlkPrevRef,
 }
 
+/// ```raw
+/// shard_ident$00 shard_pfx_bits:(#<= 60)
+///   workchain_id:int32 shard_prefix:uint64 = ShardIdent;
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ShardIdent {
+    pub shard_pfx_bits: u32,
+    pub workchain_id: i32,
+    pub shard_prefix: u64,
+}
+
+impl ShardIdent {
+    /// Converts the shard id followed by the tag bit, as in `BlockIdExt`. Returns `None` for
+    /// the shard id `0`, which has no tag bit.
+    pub fn from_shard_id(workchain_id: i32, shard: i64) -> Option<ShardIdent> {
+        let shard = shard as u64;
+        let tag = shard & shard.wrapping_neg();
+        if tag == 0 {
+            return None;
+        }
+        Some(ShardIdent {
+            shard_pfx_bits: 63 - tag.trailing_zeros(),
+            workchain_id,
+            shard_prefix: shard - tag,
+        })
+    }
+
+    /// Returns the shard id followed by the tag bit, as in `BlockIdExt`.
+    pub fn shard_id(&self) -> i64 {
+        let tag = 1u64 << (63 - self.shard_pfx_bits.min(63));
+        ((self.shard_prefix & !(tag | (tag - 1))) | tag) as i64
+    }
+
+    /// Returns whether `other` is this shard or one of its descendants.
+    pub fn is_ancestor_of(&self, other: &ShardIdent) -> bool {
+        self.workchain_id == other.workchain_id
+            && shard_is_ancestor(self.shard_id(), other.shard_id())
+    }
+
+    /// Returns whether the shards share any account.
+    pub fn intersects(&self, other: &ShardIdent) -> bool {
+        self.is_ancestor_of(other) || other.is_ancestor_of(self)
+    }
+
+    pub fn contains_address(&self, address: &TonAddress) -> bool {
+        address.is_in_shard(self.workchain_id, self.shard_id())
+    }
+}
+
+/// ```raw
+/// capabilities#c4 version:uint32 capabilities:uint64 = GlobalVersion;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GlobalVersion {
+    pub version: u32,
+    pub capabilities: u64,
+}
+
+/// ```raw
+/// update_hashes#72 {X:Type} old_hash:bits256 new_hash:bits256 = HASH_UPDATE X;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HashUpdate {
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+    pub old_hash: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+    pub new_hash: Vec<u8>,
+}
+
+/// ```raw
+/// !merkle_update#04 {X:Type} old_hash:bits256 new_hash:bits256 old_depth:uint16
+///   new_depth:uint16 old:^X new:^X = MERKLE_UPDATE X;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MerkleUpdate {
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+    pub old_hash: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+    pub new_hash: Vec<u8>,
+    pub old_depth: u16,
+    pub new_depth: u16,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::arc_cell_boc"))]
+    pub old: ArcCell,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::arc_cell_boc"))]
+    pub new: ArcCell,
+}
+
+/// ```raw
+/// value_flow#b8e48dfb ^[ from_prev_blk:CurrencyCollection
+///   to_next_blk:CurrencyCollection
+///   imported:CurrencyCollection
+///   exported:CurrencyCollection ]
+///   fees_collected:CurrencyCollection
+///   ^[
+///   fees_imported:CurrencyCollection
+///   recovered:CurrencyCollection
+///   created:CurrencyCollection
+///   minted:CurrencyCollection
+///   ] = ValueFlow;
+///
+/// value_flow_v2#3ebf98b7 ^[ from_prev_blk:CurrencyCollection
+///   to_next_blk:CurrencyCollection
+///   imported:CurrencyCollection
+///   exported:CurrencyCollection ]
+///   fees_collected:CurrencyCollection
+///   burned:CurrencyCollection
+///   ^[
+///   fees_imported:CurrencyCollection
+///   recovered:CurrencyCollection
+///   created:CurrencyCollection
+///   minted:CurrencyCollection
+///   ] = ValueFlow;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ValueFlow {
+    pub from_prev_blk: CurrencyCollection,
+    pub to_next_blk: CurrencyCollection,
+    pub imported: CurrencyCollection,
+    pub exported: CurrencyCollection,
+    pub fees_collected: CurrencyCollection,
+    /// Only present in `value_flow_v2`.
+    pub burned: Option<CurrencyCollection>,
+    pub fees_imported: CurrencyCollection,
+    pub recovered: CurrencyCollection,
+    pub created: CurrencyCollection,
+    pub minted: CurrencyCollection,
+}
+
+/// ```raw
+/// depth_balance$_ split_depth:(#<= 30) balance:CurrencyCollection = DepthBalanceInfo;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DepthBalanceInfo {
+    pub split_depth: u32,
+    pub balance: CurrencyCollection,
+}
+
+/// Value of the `ShardAccounts` dictionary of shard states:
+///
+/// ```raw
+/// account_descr$_ account:^Account last_trans_hash:bits256 last_trans_lt:uint64 = ShardAccount;
+/// _ (HashmapAugE 256 ShardAccount DepthBalanceInfo) = ShardAccounts;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ShardAccount {
+    /// `None` for `account_none$0`.
+    pub account: Option<Account>,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+    pub last_trans_hash: Vec<u8>,
+    pub last_trans_lt: u64,
+}
+
+/// ```raw
+/// account$1 addr:MsgAddressInt storage_stat:StorageInfo storage:AccountStorage = Account;
+/// account_storage$_ last_trans_lt:uint64 balance:CurrencyCollection state:AccountState
+///   = AccountStorage;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Account {
+    pub address: TonAddress,
+    pub storage_stat: StorageInfo,
+    pub last_trans_lt: u64,
+    pub balance: CurrencyCollection,
+    pub state: AccountStorageState,
+}
+
+/// ```raw
+/// storage_used$_ cells:(VarUInteger 7) bits:(VarUInteger 7) = StorageUsed;
+/// storage_extra_none$000 = StorageExtraInfo;
+/// storage_extra_info$001 dict_hash:uint256 = StorageExtraInfo;
+/// storage_info$_ used:StorageUsed storage_extra:StorageExtraInfo last_paid:uint32
+///   due_payment:(Maybe Grams) = StorageInfo;
+/// ```
+///
+/// `storage_extra` replaced the always zero `public_cells:(VarUInteger 7)` of older states,
+/// which is read as `storage_extra_none`.
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StorageInfo {
+    pub used: StorageUsedShort,
+    /// Hash of the extra currencies dictionary, from `storage_extra_info`.
+    pub dict_hash: Option<[u8; 32]>,
+    pub last_paid: u32,
+    pub due_payment: Option<VarUInteger>,
+}
+
+/// ```raw
+/// account_uninit$00 = AccountState;
+/// account_active$1 _:StateInit = AccountState;
+/// account_frozen$01 state_hash:bits256 = AccountState;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AccountStorageState {
+    #[default]
+    Uninit,
+    Active {
+        #[cfg_attr(feature = "serde", serde(with = "encoding::option_cell_boc"))]
+        code: Option<Cell>,
+        #[cfg_attr(feature = "serde", serde(with = "encoding::option_cell_boc"))]
+        data: Option<Cell>,
+    },
+    Frozen {
+        #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+        state_hash: Vec<u8>,
+    },
+}
+
+impl AccountStorageState {
+    pub fn status(&self) -> AccountStatus {
+        match self {
+            AccountStorageState::Uninit => AccountStatus::Uninit,
+            AccountStorageState::Active { .. } => AccountStatus::Active,
+            AccountStorageState::Frozen { .. } => AccountStatus::Frozen,
+        }
+    }
+}
+
+/// ```raw
+/// ed25519_signature#5 R:bits256 s:bits256 = CryptoSignatureSimple;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CryptoSignature {
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+    pub r: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+    pub s: Vec<u8>,
+}
+
+/// ```raw
+/// sig_pair$_ node_id_short:bits256 sign:CryptoSignature = CryptoSignaturePair;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CryptoSignaturePair {
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+    pub node_id_short: Vec<u8>,
+    pub sign: CryptoSignature,
+}
+
+/// ```raw
+/// shard_fee_created$_ fees:CurrencyCollection create:CurrencyCollection = ShardFeeCreated;
+/// ```
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ShardFeeCreated {
+    pub fees: CurrencyCollection,
+    pub create: CurrencyCollection,
+}
+
+/// ```raw
+/// anycast_info$_ depth:(#<= 30) { depth >= 1 } rewrite_pfx:(bits depth) = Anycast;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Anycast {
+    pub depth: u32,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+    pub rewrite_pfx: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BlockExtra {
-    // pub in_msg_descr: Cell,
-    // pub out_msg_descr: Cell,
+    /// Messages imported by the block by the hex of their hashes, `None` if pruned or not
+    /// selected.
+    pub in_msg_descr: Option<HashMap<String, HashmapAugEResult<InMsg, ImportFees>>>,
+    /// Messages exported by the block by the hex of their hashes, with the value they carry.
+    pub out_msg_descr: Option<HashMap<String, HashmapAugEResult<OutMsg, CurrencyCollection>>>,
     pub account_blocks: Option<HashMap<String, AccountBlock>>,
     // pub rand_seed: Vec<u8>,
     // pub created_by: Vec<u8>,
@@ -42,16 +358,23 @@ pub struct BlockExtra {
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AccountBlock {
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
     pub account_addr: Vec<u8>,
     pub transactions: HashMap<String, MaybeRefData<Transaction>>,
+    pub state_update: Option<HashUpdate>,
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Transaction {
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
     pub hash: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
     pub account_addr: Vec<u8>,
     pub lt: u64,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
     pub prev_trans_hash: Vec<u8>,
     pub prev_trans_lt: u64,
     pub now: u32,
@@ -60,22 +383,388 @@ pub struct Transaction {
     pub end_status: String,
     pub in_msg: MaybeRefData<TransactionMessage>,
     pub out_msgs: HashMap<String, MaybeRefData<TransactionMessage>>,
+    pub total_fees: CurrencyCollection,
+    pub state_update: Option<HashUpdate>,
+    pub description: Option<TransactionDescr>,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TransactionDescr {
+    Ordinary(TransactionDescrOrdinary),
+    Storage(TrStoragePhase),
+    TickTock(TransactionDescrTickTock),
+    SplitPrepare(TransactionDescrSplitPrepare),
+    SplitInstall(TransactionDescrSplitInstall),
+    MergePrepare(TransactionDescrMergePrepare),
+    MergeInstall(TransactionDescrMergeInstall),
+}
+
+impl TransactionDescr {
+    pub fn storage_phase(&self) -> Option<&TrStoragePhase> {
+        match self {
+            TransactionDescr::Ordinary(descr) => descr.storage_ph.as_ref(),
+            TransactionDescr::Storage(storage_ph) => Some(storage_ph),
+            TransactionDescr::TickTock(descr) => Some(&descr.storage_ph),
+            TransactionDescr::SplitPrepare(descr) => descr.storage_ph.as_ref(),
+            TransactionDescr::SplitInstall(_) => None,
+            TransactionDescr::MergePrepare(descr) => Some(&descr.storage_ph),
+            TransactionDescr::MergeInstall(descr) => descr.storage_ph.as_ref(),
+        }
+    }
+
+    pub fn credit_phase(&self) -> Option<&TrCreditPhase> {
+        match self {
+            TransactionDescr::Ordinary(descr) => descr.credit_ph.as_ref(),
+            TransactionDescr::MergeInstall(descr) => descr.credit_ph.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn compute_phase(&self) -> Option<&TrComputePhase> {
+        match self {
+            TransactionDescr::Ordinary(descr) => Some(&descr.compute_ph),
+            TransactionDescr::TickTock(descr) => Some(&descr.compute_ph),
+            TransactionDescr::SplitPrepare(descr) => Some(&descr.compute_ph),
+            TransactionDescr::MergeInstall(descr) => Some(&descr.compute_ph),
+            _ => None,
+        }
+    }
+
+    pub fn action_phase(&self) -> Option<&TrActionPhase> {
+        match self {
+            TransactionDescr::Ordinary(descr) => descr.action.data.as_ref(),
+            TransactionDescr::TickTock(descr) => descr.action.data.as_ref(),
+            TransactionDescr::SplitPrepare(descr) => descr.action.data.as_ref(),
+            TransactionDescr::MergeInstall(descr) => descr.action.data.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn bounce_phase(&self) -> Option<&TrBouncePhase> {
+        match self {
+            TransactionDescr::Ordinary(descr) => descr.bounce.as_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn aborted(&self) -> bool {
+        match self {
+            TransactionDescr::Ordinary(descr) => descr.aborted,
+            TransactionDescr::Storage(_) => false,
+            TransactionDescr::TickTock(descr) => descr.aborted,
+            TransactionDescr::SplitPrepare(descr) => descr.aborted,
+            TransactionDescr::SplitInstall(_) => false,
+            TransactionDescr::MergePrepare(descr) => descr.aborted,
+            TransactionDescr::MergeInstall(descr) => descr.aborted,
+        }
+    }
+
+    /// Returns `true` if the transaction was not aborted and both the compute and the action
+    /// phases (when present) have succeeded.
+    pub fn is_success(&self) -> bool {
+        let compute_success = self
+            .compute_phase()
+            .map(|compute| compute.is_success())
+            .unwrap_or(true);
+        let action_success = self
+            .action_phase()
+            .map(|action| action.success)
+            .unwrap_or(true);
+        !self.aborted() && compute_success && action_success
+    }
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TransactionDescrOrdinary {
+    pub credit_first: bool,
+    pub storage_ph: Option<TrStoragePhase>,
+    pub credit_ph: Option<TrCreditPhase>,
+    pub compute_ph: TrComputePhase,
+    pub action: MaybeRefData<TrActionPhase>,
+    pub aborted: bool,
+    pub bounce: Option<TrBouncePhase>,
+    pub destroyed: bool,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TransactionDescrTickTock {
+    pub is_tock: bool,
+    pub storage_ph: TrStoragePhase,
+    pub compute_ph: TrComputePhase,
+    pub action: MaybeRefData<TrActionPhase>,
+    pub aborted: bool,
+    pub destroyed: bool,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TransactionDescrSplitPrepare {
+    pub split_info: SplitMergeInfo,
+    pub storage_ph: Option<TrStoragePhase>,
+    pub compute_ph: TrComputePhase,
+    pub action: MaybeRefData<TrActionPhase>,
+    pub aborted: bool,
+    pub destroyed: bool,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TransactionDescrSplitInstall {
+    pub split_info: SplitMergeInfo,
+    pub prepare_transaction: Box<MaybeRefData<Transaction>>,
+    pub installed: bool,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TransactionDescrMergePrepare {
+    pub split_info: SplitMergeInfo,
+    pub storage_ph: TrStoragePhase,
+    pub aborted: bool,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TransactionDescrMergeInstall {
+    pub split_info: SplitMergeInfo,
+    pub prepare_transaction: Box<MaybeRefData<Transaction>>,
+    pub storage_ph: Option<TrStoragePhase>,
+    pub credit_ph: Option<TrCreditPhase>,
+    pub compute_ph: TrComputePhase,
+    pub action: MaybeRefData<TrActionPhase>,
+    pub aborted: bool,
+    pub destroyed: bool,
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SplitMergeInfo {
+    pub cur_shard_pfx_len: u8,
+    pub acc_split_depth: u8,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+    pub this_addr: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+    pub sibling_addr: Vec<u8>,
+}
+
+/// ```raw
+/// acc_state_uninit$00 = AccountStatus;
+/// acc_state_frozen$01 = AccountStatus;
+/// acc_state_active$10 = AccountStatus;
+/// acc_state_nonexist$11 = AccountStatus;
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AccountStatus {
+    /// The account has a balance but no code yet.
+    #[default]
+    Uninit,
+    /// The account was frozen for its storage debt, only the hash of its state is kept.
+    Frozen,
+    Active,
+    /// The account has never received funds or was deleted.
+    NonExist,
+}
+
+impl AccountStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountStatus::Uninit => "uninit",
+            AccountStatus::Frozen => "frozen",
+            AccountStatus::Active => "active",
+            AccountStatus::NonExist => "nonexist",
+        }
+    }
+
+    /// Returns whether the account has no code and is deployed by a message with a state
+    /// init.
+    pub fn needs_deploy(&self) -> bool {
+        matches!(self, AccountStatus::Uninit | AccountStatus::NonExist)
+    }
+
+    /// Returns whether the account is frozen for its storage debt. It's unfrozen by a message
+    /// paying the debt with the state init matching the frozen state hash.
+    pub fn is_frozen(&self) -> bool {
+        *self == AccountStatus::Frozen
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum AccStatusChange {
+    #[default]
+    Unchanged,
+    Frozen,
+    Deleted,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrStoragePhase {
+    pub storage_fees_collected: VarUInteger,
+    pub storage_fees_due: Option<VarUInteger>,
+    pub status_change: AccStatusChange,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrCreditPhase {
+    pub due_fees_collected: Option<VarUInteger>,
+    pub credit: CurrencyCollection,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TrComputePhase {
+    Skipped(ComputeSkipReason),
+    Vm(Box<TrComputePhaseVm>),
+}
+
+impl TrComputePhase {
+    pub fn is_success(&self) -> bool {
+        match self {
+            TrComputePhase::Skipped(_) => false,
+            TrComputePhase::Vm(vm) => vm.success,
+        }
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        match self {
+            TrComputePhase::Skipped(_) => None,
+            TrComputePhase::Vm(vm) => vm.vm_details.as_ref().map(|details| details.exit_code),
+        }
+    }
+
+    pub fn gas_used(&self) -> Option<&BigUint> {
+        match self {
+            TrComputePhase::Skipped(_) => None,
+            TrComputePhase::Vm(vm) => vm
+                .vm_details
+                .as_ref()
+                .map(|details| &details.gas_used.value),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ComputeSkipReason {
+    NoState,
+    BadState,
+    NoGas,
+    Suspended,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrComputePhaseVm {
+    pub success: bool,
+    pub msg_state_used: bool,
+    pub account_activated: bool,
+    pub gas_fees: VarUInteger,
+    /// `None` if the cell holding the vm details is pruned
+    pub vm_details: Option<ComputePhaseVmDetails>,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ComputePhaseVmDetails {
+    pub gas_used: VarUInteger,
+    pub gas_limit: VarUInteger,
+    pub gas_credit: Option<VarUInteger>,
+    pub mode: i8,
+    pub exit_code: i32,
+    pub exit_arg: Option<i32>,
+    pub vm_steps: u32,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+    pub vm_init_state_hash: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+    pub vm_final_state_hash: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrActionPhase {
+    pub success: bool,
+    pub valid: bool,
+    pub no_funds: bool,
+    pub status_change: AccStatusChange,
+    pub total_fwd_fees: Option<VarUInteger>,
+    pub total_action_fees: Option<VarUInteger>,
+    pub result_code: i32,
+    pub result_arg: Option<i32>,
+    pub tot_actions: u16,
+    pub spec_actions: u16,
+    pub skipped_actions: u16,
+    pub msgs_created: u16,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+    pub action_list_hash: Vec<u8>,
+    pub tot_msg_size: StorageUsedShort,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum TrBouncePhase {
+    NegFunds,
+    NoFunds {
+        msg_size: StorageUsedShort,
+        req_fwd_fees: VarUInteger,
+    },
+    Ok {
+        msg_size: StorageUsedShort,
+        msg_fees: VarUInteger,
+        fwd_fees: VarUInteger,
+    },
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StorageUsedShort {
+    pub cells: VarUInteger,
+    pub bits: VarUInteger,
+}
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TransactionMessage {
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
     pub hash: Vec<u8>,
     pub info: CommonTransactionMessageInfo,
     pub body: TransactionBody,
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct TransactionBody {
     pub any: Option<AnyCell>,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::body_cell_ref"))]
     pub cell_ref: Option<(Option<AnyCell>, Option<Cell>)>,
 }
 
+impl TransactionBody {
+    /// Returns the body regardless of whether it was stored inline or in a reference.
+    ///
+    /// Returns `None` if the body is stored in a pruned reference.
+    pub fn any_cell(&self) -> Option<&AnyCell> {
+        match (&self.any, &self.cell_ref) {
+            (Some(any), _) => Some(any),
+            (None, Some((any, _))) => any.as_ref(),
+            (None, None) => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CommonTransactionMessageInfo {
     pub msg_type: u8,
     pub ihr_disabled: bool,
@@ -92,6 +781,8 @@ pub struct CommonTransactionMessageInfo {
 }
 
 #[derive(PartialEq, Eq, Debug, Clone, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum MessageType {
     Internal = 0,
     ExternalIn = 1,
@@ -99,47 +790,742 @@ pub enum MessageType {
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct McBlockExtra {
     // key_block: u8,
     // shard_hashes: Hashmap,
     // shard_fees: Hashmap,
     pub shards: HashMap<String, Vec<ShardDescr>>,
+    /// Fees collected and funds created by shard, keyed by workchain and shard prefix.
+    pub shard_fees: HashMap<String, HashmapAugEResult<ShardFeeCreated, ShardFeeCreated>>,
+    /// Signatures of the previous block, keyed by validator index.
+    pub prev_blk_signatures: HashMap<String, CryptoSignaturePair>,
     pub config: ConfigParams,
 }
 
+/// Shard covering the whole workchain.
+pub const SHARD_FULL: i64 = i64::MIN;
+
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ShardDescr {
+    pub workchain: i32,
+    /// Shard id, i.e. the prefix of account ids followed by a single tag bit.
+    pub shard: i64,
     pub seqno: u32,
     pub reg_mc_seqno: u32,
     pub start_lt: u64,
     pub end_lt: u64,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
     pub root_hash: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
     pub file_hash: Vec<u8>,
     pub gen_utime: u64,
     pub next_validator_shard: u64,
 }
 
+impl ShardDescr {
+    /// Returns true if the account belongs to the shard.
+    pub fn contains_address(&self, address: &TonAddress) -> bool {
+        address.is_in_shard(self.workchain, self.shard)
+    }
+
+    /// Returns the shard this shard was split from, if any.
+    pub fn parent_shard(&self) -> Option<i64> {
+        shard_parent(self.shard)
+    }
+
+    /// Returns the shards this shard is split into.
+    pub fn child_shards(&self) -> Option<(i64, i64)> {
+        shard_children(self.shard)
+    }
+}
+
+/// Finds the shard the account belongs to.
+///
+/// * `shards`: Shards by workchain, as returned by `Cell::load_shard_hashes`.
+pub fn find_shard_descr<'a>(
+    shards: &'a HashMap<String, Vec<ShardDescr>>,
+    address: &TonAddress,
+) -> Option<&'a ShardDescr> {
+    shards
+        .values()
+        .flatten()
+        .find(|descr| descr.contains_address(address))
+}
+
+/// Returns the parent of the shard or `None` for the shard covering the whole workchain.
+pub fn shard_parent(shard: i64) -> Option<i64> {
+    let shard = shard as u64;
+    let tag = shard & shard.wrapping_neg();
+    if tag == 0 || tag == 1 << 63 {
+        return None;
+    }
+    Some(((shard - tag) | (tag << 1)) as i64)
+}
+
+/// Returns the left and right children of the shard or `None` if the shard can not be split.
+pub fn shard_children(shard: i64) -> Option<(i64, i64)> {
+    let shard = shard as u64;
+    let tag = shard & shard.wrapping_neg();
+    if tag <= 1 {
+        return None;
+    }
+    Some(((shard - tag / 2) as i64, (shard + tag / 2) as i64))
+}
+
+/// Returns true if `ancestor` is `shard` or contains it.
+pub fn shard_is_ancestor(ancestor: i64, shard: i64) -> bool {
+    let ancestor = ancestor as u64;
+    let shard = shard as u64;
+    let ancestor_tag = ancestor & ancestor.wrapping_neg();
+    let shard_tag = shard & shard.wrapping_neg();
+    if ancestor_tag == 0 || shard_tag == 0 || ancestor_tag < shard_tag {
+        return false;
+    }
+    let mask = !(ancestor_tag | (ancestor_tag - 1));
+    (ancestor ^ shard) & mask == 0
+}
+
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BlkPrevRef {
     pub first_prev: Option<ExtBlkRef>,
     pub second_prev: Option<ExtBlkRef>,
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ExtBlkRef {
     pub end_lt: u64,
     pub seqno: u32,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
     pub root_hash: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
     pub file_hash: Vec<u8>,
 }
 
+/// ```raw
+/// _ key:Bool blk_ref:ExtBlkRef = KeyExtBlkRef;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KeyExtBlkRef {
+    /// Whether the block is a key block.
+    pub key: bool,
+    pub blk_ref: ExtBlkRef,
+}
+
+/// ```raw
+/// _ key:Bool max_end_lt:uint64 = KeyMaxLt;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct KeyMaxLt {
+    /// Whether any of the blocks is a key block.
+    pub key: bool,
+    pub max_end_lt: u64,
+}
+
+/// Previous masterchain blocks of a masterchain state, i.e. `prev_blocks` of `McStateExtra`:
+///
+/// ```raw
+/// _ (HashmapAugE 32 KeyExtBlkRef KeyMaxLt) = OldMcBlocksInfo;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OldMcBlocksInfo {
+    /// Blocks by seqno.
+    pub blocks: HashMap<u32, KeyExtBlkRef>,
+    pub extra: KeyMaxLt,
+    /// Whether parts of the dictionary are pruned, e.g. in state proofs. Blocks missing in
+    /// `blocks` are then not proven to be absent.
+    pub pruned: bool,
+}
+
+impl OldMcBlocksInfo {
+    pub fn get(&self, seqno: u32) -> Option<&KeyExtBlkRef> {
+        self.blocks.get(&seqno)
+    }
+
+    /// Returns whether the state references the masterchain block with the given seqno and
+    /// hashes.
+    ///
+    /// This proves the existence of the block only if the state is trusted, e.g. it's loaded
+    /// from a state proof checked against the state hash of a trusted block.
+    pub fn contains_block(&self, seqno: u32, root_hash: &[u8], file_hash: &[u8]) -> bool {
+        self.get(seqno).is_some_and(|block| {
+            block.blk_ref.root_hash == root_hash && block.blk_ref.file_hash == file_hash
+        })
+    }
+
+    /// Returns the key blocks, ordered by seqno.
+    pub fn key_blocks(&self) -> Vec<&KeyExtBlkRef> {
+        let mut key_blocks: Vec<_> = self.blocks.values().filter(|block| block.key).collect();
+        key_blocks.sort_by_key(|block| block.blk_ref.seqno);
+        key_blocks
+    }
+}
+
+/// Address of a message routed by the hypercube routing, relative to its source and
+/// destination:
+///
+/// ```raw
+/// interm_addr_regular$0 use_dest_bits:(#<= 96) = IntermediateAddress;
+/// interm_addr_simple$10 workchain_id:int8 addr_pfx:uint64 = IntermediateAddress;
+/// interm_addr_ext$11 workchain_id:int32 addr_pfx:uint64 = IntermediateAddress;
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum IntermediateAddress {
+    /// The first `use_dest_bits` bits of the address are taken from the destination.
+    Regular { use_dest_bits: u8 },
+    /// Either `interm_addr_simple` or `interm_addr_ext`.
+    Simple { workchain: i32, addr_pfx: u64 },
+}
+
+impl Default for IntermediateAddress {
+    fn default() -> Self {
+        IntermediateAddress::Regular { use_dest_bits: 0 }
+    }
+}
+
+/// Message with its routing state:
+///
+/// ```raw
+/// msg_envelope#4 cur_addr:IntermediateAddress next_addr:IntermediateAddress
+///   fwd_fee_remaining:Grams msg:^(Message Any) = MsgEnvelope;
+/// msg_envelope_v2#5 cur_addr:IntermediateAddress next_addr:IntermediateAddress
+///   fwd_fee_remaining:Grams msg:^(Message Any) emitted_lt:(Maybe uint64)
+///   metadata:(Maybe MsgMetadata) = MsgEnvelope;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MsgEnvelope {
+    pub cur_addr: IntermediateAddress,
+    pub next_addr: IntermediateAddress,
+    pub fwd_fee_remaining: VarUInteger,
+    /// `None` if the message is pruned.
+    pub msg: Option<TransactionMessage>,
+    pub emitted_lt: Option<u64>,
+    pub metadata: Option<MsgMetadata>,
+}
+
+/// Origin of a message in the chain of messages started by an external message:
+///
+/// ```raw
+/// msg_metadata#0 depth:uint32 initiator_addr:MsgAddressInt initiator_lt:uint64 = MsgMetadata;
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MsgMetadata {
+    pub depth: u32,
+    pub initiator_addr: TonAddress,
+    pub initiator_lt: u64,
+}
+
+/// Message of an out message queue:
+///
+/// ```raw
+/// _ enqueued_lt:uint64 out_msg:^MsgEnvelope = EnqueuedMsg;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct EnqueuedMsg {
+    pub enqueued_lt: u64,
+    /// `None` if the envelope is pruned.
+    pub out_msg: Option<MsgEnvelope>,
+}
+
+/// Message imported by a block, the value of [`BlockExtra::in_msg_descr`]:
+///
+/// ```raw
+/// msg_import_ext$000 msg:^(Message Any) transaction:^Transaction = InMsg;
+/// msg_import_ihr$010 msg:^(Message Any) transaction:^Transaction ihr_fee:Grams
+///   proof_created:^Cell = InMsg;
+/// msg_import_imm$011 in_msg:^MsgEnvelope transaction:^Transaction fwd_fee:Grams = InMsg;
+/// msg_import_fin$100 in_msg:^MsgEnvelope transaction:^Transaction fwd_fee:Grams = InMsg;
+/// msg_import_tr$101 in_msg:^MsgEnvelope out_msg:^MsgEnvelope transit_fee:Grams = InMsg;
+/// msg_discard_fin$110 in_msg:^MsgEnvelope transaction_id:uint64 fwd_fee:Grams = InMsg;
+/// msg_discard_tr$111 in_msg:^MsgEnvelope transaction_id:uint64 fwd_fee:Grams
+///   proof_delivered:^Cell = InMsg;
+/// msg_import_deferred_fin$00100 in_msg:^MsgEnvelope transaction:^Transaction
+///   fwd_fee:Grams = InMsg;
+/// msg_import_deferred_tr$00101 in_msg:^MsgEnvelope out_msg:^MsgEnvelope = InMsg;
+/// ```
+///
+/// Messages and envelopes are `None` if they are pruned. Transactions and proofs are kept as
+/// the hashes of their cells, the transactions are loaded with the account blocks.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum InMsg {
+    ImportExt {
+        msg: Option<TransactionMessage>,
+        #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+        transaction: Vec<u8>,
+    },
+    ImportIhr {
+        msg: Option<TransactionMessage>,
+        #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+        transaction: Vec<u8>,
+        ihr_fee: VarUInteger,
+        #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+        proof_created: Vec<u8>,
+    },
+    ImportImm {
+        in_msg: Option<MsgEnvelope>,
+        #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+        transaction: Vec<u8>,
+        fwd_fee: VarUInteger,
+    },
+    ImportFin {
+        in_msg: Option<MsgEnvelope>,
+        #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+        transaction: Vec<u8>,
+        fwd_fee: VarUInteger,
+    },
+    ImportTr {
+        in_msg: Option<MsgEnvelope>,
+        out_msg: Option<MsgEnvelope>,
+        transit_fee: VarUInteger,
+    },
+    DiscardFin {
+        in_msg: Option<MsgEnvelope>,
+        transaction_id: u64,
+        fwd_fee: VarUInteger,
+    },
+    DiscardTr {
+        in_msg: Option<MsgEnvelope>,
+        transaction_id: u64,
+        fwd_fee: VarUInteger,
+        #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+        proof_delivered: Vec<u8>,
+    },
+    ImportDeferredFin {
+        in_msg: Option<MsgEnvelope>,
+        #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+        transaction: Vec<u8>,
+        fwd_fee: VarUInteger,
+    },
+    ImportDeferredTr {
+        in_msg: Option<MsgEnvelope>,
+        out_msg: Option<MsgEnvelope>,
+    },
+}
+
+impl Default for InMsg {
+    fn default() -> Self {
+        InMsg::ImportExt {
+            msg: None,
+            transaction: Vec::new(),
+        }
+    }
+}
+
+/// Fees of an imported message, the extra of [`BlockExtra::in_msg_descr`]:
+///
+/// ```raw
+/// import_fees$_ fees_collected:Grams value_imported:CurrencyCollection = ImportFees;
+/// ```
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ImportFees {
+    pub fees_collected: VarUInteger,
+    pub value_imported: CurrencyCollection,
+}
+
+/// Message exported by a block, the value of [`BlockExtra::out_msg_descr`]:
+///
+/// ```raw
+/// msg_export_ext$000 msg:^(Message Any) transaction:^Transaction = OutMsg;
+/// msg_export_imm$010 out_msg:^MsgEnvelope transaction:^Transaction reimport:^InMsg = OutMsg;
+/// msg_export_new$001 out_msg:^MsgEnvelope transaction:^Transaction = OutMsg;
+/// msg_export_tr$011 out_msg:^MsgEnvelope imported:^InMsg = OutMsg;
+/// msg_export_deq$1100 out_msg:^MsgEnvelope import_block_lt:uint63 = OutMsg;
+/// msg_export_deq_short$1101 msg_env_hash:bits256 next_workchain:int32 next_addr_pfx:uint64
+///   import_block_lt:uint64 = OutMsg;
+/// msg_export_tr_req$111 out_msg:^MsgEnvelope imported:^InMsg = OutMsg;
+/// msg_export_deq_imm$100 out_msg:^MsgEnvelope reimport:^InMsg = OutMsg;
+/// msg_export_new_defer$10100 out_msg:^MsgEnvelope transaction:^Transaction = OutMsg;
+/// msg_export_deferred_tr$10101 out_msg:^MsgEnvelope imported:^InMsg = OutMsg;
+/// ```
+///
+/// As in [`InMsg`], pruned messages, envelopes and in messages are `None` and transactions are
+/// kept as the hashes of their cells.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum OutMsg {
+    ExportExt {
+        msg: Option<TransactionMessage>,
+        #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+        transaction: Vec<u8>,
+    },
+    ExportImm {
+        out_msg: Option<MsgEnvelope>,
+        #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+        transaction: Vec<u8>,
+        reimport: Option<Box<InMsg>>,
+    },
+    ExportNew {
+        out_msg: Option<MsgEnvelope>,
+        #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+        transaction: Vec<u8>,
+    },
+    ExportTr {
+        out_msg: Option<MsgEnvelope>,
+        imported: Option<Box<InMsg>>,
+    },
+    ExportDeq {
+        out_msg: Option<MsgEnvelope>,
+        import_block_lt: u64,
+    },
+    ExportDeqShort {
+        #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+        msg_env_hash: Vec<u8>,
+        next_workchain: i32,
+        next_addr_pfx: u64,
+        import_block_lt: u64,
+    },
+    ExportTrReq {
+        out_msg: Option<MsgEnvelope>,
+        imported: Option<Box<InMsg>>,
+    },
+    ExportDeqImm {
+        out_msg: Option<MsgEnvelope>,
+        reimport: Option<Box<InMsg>>,
+    },
+    ExportNewDefer {
+        out_msg: Option<MsgEnvelope>,
+        #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+        transaction: Vec<u8>,
+    },
+    ExportDeferredTr {
+        out_msg: Option<MsgEnvelope>,
+        imported: Option<Box<InMsg>>,
+    },
+}
+
+impl Default for OutMsg {
+    fn default() -> Self {
+        OutMsg::ExportExt {
+            msg: None,
+            transaction: Vec::new(),
+        }
+    }
+}
+
+/// Out message queue of a shard state, without the processed and the IHR pending messages:
+///
+/// ```raw
+/// _ (HashmapAugE 352 EnqueuedMsg uint64) = OutMsgQueue;
+/// _ out_queue:OutMsgQueue proc_info:ProcessedInfo ihr_pending:IhrPendingInfo = OutMsgQueueInfo;
+/// ```
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OutMsgQueueInfo {
+    /// Messages by the hex of their key, i.e. the workchain and the address prefix of the next
+    /// hop followed by the hash of the message, see [`OutMsgQueueKey`].
+    pub out_queue: HashMap<String, EnqueuedMsg>,
+    /// Whether parts of the queue are pruned, e.g. in state proofs.
+    pub pruned: bool,
+}
+
+/// Key of a message in an out message queue:
+///
+/// ```raw
+/// workchain:int32 addr_pfx:uint64 msg_hash:bits256
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OutMsgQueueKey {
+    /// Workchain of the next hop.
+    pub workchain: i32,
+    /// Address prefix of the next hop.
+    pub addr_pfx: u64,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
+    pub msg_hash: Vec<u8>,
+}
+
+impl OutMsgQueueKey {
+    /// Parses the hex key of [`OutMsgQueueInfo::out_queue`].
+    pub fn from_hex(key: &str) -> Result<OutMsgQueueKey, TonCellError> {
+        let key = BigUint::parse_bytes(key.as_bytes(), 16)
+            .ok_or_else(|| TonCellError::cell_parser_error(format!("Invalid key {}", key)))?;
+        let bytes = key.to_bytes_be();
+        if bytes.len() > 44 {
+            return Err(TonCellError::cell_parser_error(format!(
+                "Key {} is longer than 352 bits",
+                key
+            )));
+        }
+        let mut padded = vec![0; 44 - bytes.len()];
+        padded.extend(bytes);
+        Ok(OutMsgQueueKey {
+            workchain: i32::from_be_bytes(padded[0..4].try_into().unwrap()),
+            addr_pfx: u64::from_be_bytes(padded[4..12].try_into().unwrap()),
+            msg_hash: padded[12..].to_vec(),
+        })
+    }
+}
+
+/// Number of the config param holding the gas prices of the masterchain.
+pub const CONFIG_PARAM_MC_GAS_PRICES: u32 = 20;
+/// Number of the config param holding the gas prices of the basechain.
+pub const CONFIG_PARAM_GAS_PRICES: u32 = 21;
+/// Number of the config param holding the previous validator set.
+pub const CONFIG_PARAM_PREV_VALIDATORS: u32 = 32;
+/// Number of the config param holding the current validator set.
+pub const CONFIG_PARAM_CUR_VALIDATORS: u32 = 34;
+/// Number of the config param holding the next validator set, present between the
+/// elections and the rotation.
+pub const CONFIG_PARAM_NEXT_VALIDATORS: u32 = 36;
+/// Number of the config param holding the size limits of messages and account states.
+pub const CONFIG_PARAM_SIZE_LIMITS: u32 = 43;
+
+#[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ConfigParams {
     // pub config_addr: Vec<u8>,
     pub config: HashMap<String, Option<ConfigParam>>,
+    /// Cells of all the params by their numbers, including the params not parsed into `config`.
+    /// Kept in sync with `config` by [`ConfigParams::set_param`].
+    #[cfg_attr(feature = "serde", serde(default, with = "encoding::cell_map_boc"))]
+    cells: HashMap<u32, Cell>,
+}
+
+impl ConfigParams {
+    pub(crate) fn new(
+        config: HashMap<String, Option<ConfigParam>>,
+        cells: HashMap<u32, Cell>,
+    ) -> ConfigParams {
+        ConfigParams { config, cells }
+    }
+
+    pub fn param(&self, n: u32) -> Option<&Cell> {
+        self.cells.get(&n)
+    }
+
+    /// Returns the cells of all the params by their numbers.
+    pub fn cells(&self) -> &HashMap<u32, Cell> {
+        &self.cells
+    }
+
+    /// Replaces the cell of the param and its parsed value in `config`, keyed by hex numbers.
+    pub fn set_param(&mut self, n: u32, cell: Cell) -> Result<(), TonCellError> {
+        let param = Cell::load_config_param(&cell, &mut 0, &mut cell.parser(), &BigUint::from(n))?;
+        self.config.insert(format!("{:x}", n), param);
+        self.cells.insert(n, cell);
+        Ok(())
+    }
+
+    /// Returns the parsed validator set of config param 32, 34 or 36.
+    pub fn validator_set(&self, n: u32) -> Option<&Validators> {
+        match self.config.get(&format!("{:x}", n))?.as_ref()? {
+            ConfigParam::ConfigParams32(set)
+            | ConfigParam::ConfigParams34(set)
+            | ConfigParam::ConfigParams36(set) => Some(&set.validators),
+        }
+    }
+
+    /// Returns the gas prices of the workchain, i.e. config param 20 for the masterchain and
+    /// config param 21 for the other workchains.
+    pub fn gas_prices(&self, workchain: i32) -> Result<GasLimitsPrices, TonCellError> {
+        let n = gas_prices_param(workchain);
+        let cell = self
+            .param(n)
+            .ok_or_else(|| TonCellError::cell_parser_error(format!("No config param {}", n)))?;
+        GasLimitsPrices::from_cell(cell)
+    }
+
+    pub fn set_gas_prices(
+        &mut self,
+        workchain: i32,
+        prices: &GasLimitsPrices,
+    ) -> Result<(), TonCellError> {
+        self.set_param(gas_prices_param(workchain), prices.to_cell()?)
+    }
+
+    /// Returns the size limits of config param 43.
+    pub fn size_limits(&self) -> Result<SizeLimitsConfig, TonCellError> {
+        let cell = self.param(CONFIG_PARAM_SIZE_LIMITS).ok_or_else(|| {
+            TonCellError::cell_parser_error(format!("No config param {}", CONFIG_PARAM_SIZE_LIMITS))
+        })?;
+        SizeLimitsConfig::from_cell(cell)
+    }
+
+    /// Serializes the params into the `Hashmap 32 ^Cell` dictionary, which is the config
+    /// returned by `getConfigAll` and expected by the emulators.
+    pub fn to_cell(&self) -> Result<Cell, TonCellError> {
+        let cells = self.cells.iter().map(|(n, cell)| (*n, cell.clone()));
+        DictBuilder::from_entries(32, cells, key_serializer_u32, value_serializer_cell_ref)?
+            .build()?
+            .ok_or_else(|| TonCellError::cell_builder_error("No config params"))
+    }
+}
+
+fn gas_prices_param(workchain: i32) -> u32 {
+    if workchain == MASTERCHAIN_ID {
+        CONFIG_PARAM_MC_GAS_PRICES
+    } else {
+        CONFIG_PARAM_GAS_PRICES
+    }
+}
+
+const GAS_FLAT_PFX_TAG: u8 = 0xd1;
+const GAS_PRICES_TAG: u8 = 0xdd;
+const GAS_PRICES_EXT_TAG: u8 = 0xde;
+
+/// Gas limits and prices of a workchain:
+///
+/// ```raw
+/// gas_prices#dd gas_price:uint64 gas_limit:uint64 gas_credit:uint64 block_gas_limit:uint64
+///   freeze_due_limit:uint64 delete_due_limit:uint64 = GasLimitsPrices;
+/// gas_prices_ext#de gas_price:uint64 gas_limit:uint64 special_gas_limit:uint64
+///   gas_credit:uint64 block_gas_limit:uint64 freeze_due_limit:uint64
+///   delete_due_limit:uint64 = GasLimitsPrices;
+/// gas_flat_pfx#d1 flat_gas_limit:uint64 flat_gas_price:uint64
+///   other:GasLimitsPrices = GasLimitsPrices;
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GasLimitsPrices {
+    /// Limit and price of the gas charged at a flat rate, if any.
+    pub flat_gas: Option<(u64, u64)>,
+    /// Price of the gas in 1/65536 of nanotons.
+    pub gas_price: u64,
+    pub gas_limit: u64,
+    /// Gas limit of special accounts, only present in `gas_prices_ext`.
+    pub special_gas_limit: Option<u64>,
+    pub gas_credit: u64,
+    pub block_gas_limit: u64,
+    pub freeze_due_limit: u64,
+    pub delete_due_limit: u64,
+}
+
+impl TlbSerialize for GasLimitsPrices {
+    fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        if let Some((flat_gas_limit, flat_gas_price)) = self.flat_gas {
+            builder
+                .store_u8(8, GAS_FLAT_PFX_TAG)?
+                .store_u64(64, flat_gas_limit)?
+                .store_u64(64, flat_gas_price)?;
+        }
+        match self.special_gas_limit {
+            Some(special_gas_limit) => builder
+                .store_u8(8, GAS_PRICES_EXT_TAG)?
+                .store_u64(64, self.gas_price)?
+                .store_u64(64, self.gas_limit)?
+                .store_u64(64, special_gas_limit)?,
+            None => builder
+                .store_u8(8, GAS_PRICES_TAG)?
+                .store_u64(64, self.gas_price)?
+                .store_u64(64, self.gas_limit)?,
+        };
+        builder
+            .store_u64(64, self.gas_credit)?
+            .store_u64(64, self.block_gas_limit)?
+            .store_u64(64, self.freeze_due_limit)?
+            .store_u64(64, self.delete_due_limit)?;
+        Ok(())
+    }
+}
+
+impl TlbDeserialize for GasLimitsPrices {
+    fn load(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        let mut prices = GasLimitsPrices::default();
+        let mut tag = parser.load_u8(8)?;
+        if tag == GAS_FLAT_PFX_TAG {
+            prices.flat_gas = Some((parser.load_u64(64)?, parser.load_u64(64)?));
+            tag = parser.load_u8(8)?;
+        }
+        if tag != GAS_PRICES_TAG && tag != GAS_PRICES_EXT_TAG {
+            return Err(TonCellError::unexpected_structure("GasLimitsPrices"));
+        }
+        prices.gas_price = parser.load_u64(64)?;
+        prices.gas_limit = parser.load_u64(64)?;
+        if tag == GAS_PRICES_EXT_TAG {
+            prices.special_gas_limit = Some(parser.load_u64(64)?);
+        }
+        prices.gas_credit = parser.load_u64(64)?;
+        prices.block_gas_limit = parser.load_u64(64)?;
+        prices.freeze_due_limit = parser.load_u64(64)?;
+        prices.delete_due_limit = parser.load_u64(64)?;
+        Ok(prices)
+    }
+}
+
+const SIZE_LIMITS_TAG: u8 = 0x01;
+const SIZE_LIMITS_V2_TAG: u8 = 0x02;
+
+/// Size limits of messages and account states:
+///
+/// ```raw
+/// size_limits_config#01 max_msg_bits:uint32 max_msg_cells:uint32 max_library_cells:uint32
+///   max_vm_data_depth:uint16 max_ext_msg_size:uint32 max_ext_msg_depth:uint16
+///   = SizeLimitsConfig;
+/// size_limits_config_v2#02 max_msg_bits:uint32 max_msg_cells:uint32 max_library_cells:uint32
+///   max_vm_data_depth:uint16 max_ext_msg_size:uint32 max_ext_msg_depth:uint16
+///   max_acc_state_cells:uint32 max_acc_state_bits:uint32 ... = SizeLimitsConfig;
+/// ```
+///
+/// The default values are the ones used by the nodes when the param is missing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SizeLimitsConfig {
+    pub max_msg_bits: u32,
+    pub max_msg_cells: u32,
+    pub max_library_cells: u32,
+    pub max_vm_data_depth: u16,
+    /// Maximum size in bytes of the bag of cells of an inbound external message.
+    pub max_ext_msg_size: u32,
+    pub max_ext_msg_depth: u16,
+    /// Maximum cells and bits of account states, only present in `size_limits_config_v2`.
+    pub max_acc_state: Option<(u32, u32)>,
+}
+
+impl Default for SizeLimitsConfig {
+    fn default() -> Self {
+        SizeLimitsConfig {
+            max_msg_bits: 1 << 21,
+            max_msg_cells: 1 << 13,
+            max_library_cells: 1000,
+            max_vm_data_depth: 512,
+            max_ext_msg_size: 65535,
+            max_ext_msg_depth: 512,
+            max_acc_state: None,
+        }
+    }
+}
+
+impl TlbDeserialize for SizeLimitsConfig {
+    fn load(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        let tag = parser.load_u8(8)?;
+        if tag != SIZE_LIMITS_TAG && tag != SIZE_LIMITS_V2_TAG {
+            return Err(TonCellError::unexpected_structure("SizeLimitsConfig"));
+        }
+        let mut limits = SizeLimitsConfig {
+            max_msg_bits: parser.load_u32(32)?,
+            max_msg_cells: parser.load_u32(32)?,
+            max_library_cells: parser.load_u32(32)?,
+            max_vm_data_depth: parser.load_u16(16)?,
+            max_ext_msg_size: parser.load_u32(32)?,
+            max_ext_msg_depth: parser.load_u16(16)?,
+            max_acc_state: None,
+        };
+        if tag == SIZE_LIMITS_V2_TAG {
+            // The fields following the account state limits are ignored
+            limits.max_acc_state = Some((parser.load_u32(32)?, parser.load_u32(32)?));
+        }
+        Ok(limits)
+    }
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum ConfigParam {
     ConfigParams32(ConfigParamsValidatorSet),
     ConfigParams34(ConfigParamsValidatorSet),
@@ -147,48 +1533,172 @@ pub enum ConfigParam {
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ConfigParamsValidatorSet {
     pub number: u8,
     pub validators: Validators,
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct Validators {
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
     pub _type: String,
     pub utime_since: u32,
     pub utime_until: u32,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::biguint_decimal"))]
     pub total: BigUint,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::biguint_decimal"))]
     pub main: BigUint,
     pub total_weight: u64,
     pub list: HashMap<String, ValidatorDescr>,
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct ValidatorDescr {
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
     pub _type: u8,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
     pub public_key: Vec<u8>,
     pub weight: u64,
+    #[cfg_attr(feature = "serde", serde(with = "encoding::hex_bytes"))]
     pub adnl_addr: Vec<u8>,
 }
 
+impl ValidatorDescr {
+    /// Returns the short id of the node with the public key of the validator.
+    pub fn node_id_short(&self) -> Result<AdnlAddress, AdnlAddressParseError> {
+        let public_key = self.public_key.as_slice().try_into().map_err(|_| {
+            AdnlAddressParseError::new(hex::encode(&self.public_key), "Invalid public key")
+        })?;
+        Ok(AdnlAddress::from_public_key(public_key))
+    }
+
+    /// Returns the ADNL address of the validator, which is `None` for validators described
+    /// without it.
+    pub fn adnl_address(&self) -> Result<Option<AdnlAddress>, AdnlAddressParseError> {
+        if self.adnl_addr.is_empty() {
+            return Ok(None);
+        }
+        AdnlAddress::from_slice(&self.adnl_addr).map(Some)
+    }
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum BinTreeRes {
     Fork(Box<BinTreeFork>),
     Leaf(BinTreeLeafRes),
 }
 
+/// Node of `BinTreeAug X Y`, where `T` is the type of leaves and `E` is the type of extras:
+///
+/// ```raw
+/// bta_leaf$0 {X:Type} {Y:Type} extra:Y leaf:X = BinTreeAug X Y;
+/// bta_fork$1 {X:Type} {Y:Type} left:^(BinTreeAug X Y)
+///            right:^(BinTreeAug X Y) extra:Y = BinTreeAug X Y;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum BinTreeAugRes<T, E> {
+    Fork(Box<BinTreeAugFork<T, E>>),
+    Leaf(BinTreeAugLeaf<T, E>),
+}
+
+/// Fork of `BinTreeAug`. Branches stored in pruned cells are `None`.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BinTreeAugFork<T, E> {
+    pub left: Option<BinTreeAugRes<T, E>>,
+    pub right: Option<BinTreeAugRes<T, E>>,
+    pub extra: E,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct BinTreeAugLeaf<T, E> {
+    pub leaf: T,
+    pub extra: E,
+}
+
+impl<T, E> BinTreeAugRes<T, E> {
+    /// Returns the extra of the node, which for forks aggregates the extras of both branches.
+    pub fn extra(&self) -> &E {
+        match self {
+            BinTreeAugRes::Fork(fork) => &fork.extra,
+            BinTreeAugRes::Leaf(leaf) => &leaf.extra,
+        }
+    }
+
+    /// Returns the leaves that are not pruned, from left to right.
+    pub fn leaves(&self) -> Vec<&BinTreeAugLeaf<T, E>> {
+        let mut result = Vec::new();
+        self.collect_leaves(&mut result);
+        result
+    }
+
+    fn collect_leaves<'a>(&'a self, vec: &mut Vec<&'a BinTreeAugLeaf<T, E>>) {
+        match self {
+            BinTreeAugRes::Fork(fork) => {
+                if let Some(left) = &fork.left {
+                    left.collect_leaves(vec);
+                }
+                if let Some(right) = &fork.right {
+                    right.collect_leaves(vec);
+                }
+            }
+            BinTreeAugRes::Leaf(leaf) => vec.push(leaf),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct BinTreeFork {
     pub left: Option<BinTreeRes>,
     pub right: Option<BinTreeRes>,
 }
 
 #[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
 pub enum BinTreeLeafRes {
     ShardDescr(ShardDescr),
 }
 
 impl BinTreeRes {
+    /// Returns shard descriptions of the workchain with shard ids derived from their positions
+    /// in the tree.
+    pub fn get_shard_descrs(&self, workchain: i32) -> Vec<ShardDescr> {
+        let mut result = Vec::new();
+        self.collect_shard_descrs_with_ids(workchain, SHARD_FULL, &mut result);
+        result
+    }
+
+    fn collect_shard_descrs_with_ids(&self, workchain: i32, shard: i64, vec: &mut Vec<ShardDescr>) {
+        match self {
+            BinTreeRes::Fork(fork) => {
+                // Shards of a fork are always splittable, since the tree depth is limited
+                let (left_shard, right_shard) = shard_children(shard).unwrap_or((shard, shard));
+                if let Some(left) = &fork.left {
+                    left.collect_shard_descrs_with_ids(workchain, left_shard, vec);
+                }
+                if let Some(right) = &fork.right {
+                    right.collect_shard_descrs_with_ids(workchain, right_shard, vec);
+                }
+            }
+            BinTreeRes::Leaf(BinTreeLeafRes::ShardDescr(descr)) => {
+                let mut descr = descr.clone();
+                descr.workchain = workchain;
+                descr.shard = shard;
+                vec.push(descr);
+            }
+        }
+    }
+
     pub fn get_all_shard_descrs_as_vec(&self) -> Vec<ShardDescr> {
         let mut result = Vec::new();
         self.collect_shard_descrs_into_vec(&mut result);
@@ -216,14 +1726,195 @@ impl BinTreeRes {
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct CurrencyCollection {
     pub grams: VarUInteger,
-    pub other: HashMap<String, VarUInteger>,
+    /// Extra currencies by their ids.
+    pub other: HashMap<u32, VarUInteger>,
 }
 
 #[derive(Clone, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AnyCell {
+    #[cfg_attr(feature = "serde", serde(with = "encoding::cell_boc"))]
     pub cell: Cell,
     pub ref_index: usize,
     pub parser_positions_in_bits: u64,
 }
+
+impl AnyCell {
+    /// Returns a parser positioned at the beginning of the stored data.
+    ///
+    /// References of the stored data start at `ref_index`.
+    pub fn parser(&self) -> Result<CellParser<'_>, TonCellError> {
+        let mut parser = self.cell.parser();
+        parser.skip_bits(self.parser_positions_in_bits as usize)?;
+        Ok(parser)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::address::TonAddress;
+    use crate::responses::{
+        find_shard_descr, shard_children, shard_is_ancestor, shard_parent, ShardDescr, SHARD_FULL,
+    };
+
+    #[test]
+    fn shard_assignment_works() -> anyhow::Result<()> {
+        let left = 0x4000000000000000u64 as i64;
+        let right = 0xc000000000000000u64 as i64;
+        assert_eq!(shard_children(SHARD_FULL), Some((left, right)));
+        assert_eq!(shard_parent(left), Some(SHARD_FULL));
+        assert_eq!(shard_parent(right), Some(SHARD_FULL));
+        assert_eq!(shard_parent(SHARD_FULL), None);
+        assert_eq!(shard_children(1), None);
+        assert!(shard_is_ancestor(SHARD_FULL, left));
+        assert!(shard_is_ancestor(right, right));
+        assert!(!shard_is_ancestor(left, right));
+        assert!(!shard_is_ancestor(left, SHARD_FULL));
+
+        let shards: HashMap<String, Vec<ShardDescr>> = HashMap::from([(
+            "0".to_string(),
+            [left, right]
+                .into_iter()
+                .map(|shard| ShardDescr {
+                    workchain: 0,
+                    shard,
+                    ..Default::default()
+                })
+                .collect(),
+        )]);
+        let address: TonAddress =
+            "0:e4d954ef9f4e1250a26b5bbad76a1cdd17cfd08babad6f4c23e372270aef6f76".parse()?;
+        let descr = find_shard_descr(&shards, &address).unwrap();
+        assert_eq!(descr.shard, right);
+        assert!(descr.contains_address(&address));
+        let masterchain_address: TonAddress =
+            "-1:e4d954ef9f4e1250a26b5bbad76a1cdd17cfd08babad6f4c23e372270aef6f76".parse()?;
+        assert!(find_shard_descr(&shards, &masterchain_address).is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn config_params_work() -> anyhow::Result<()> {
+        use num_bigint::BigUint;
+
+        use crate::cell::{Cell, CellBuilder, DictBuilder, TlbSerialize};
+        use crate::responses::{GasLimitsPrices, SizeLimitsConfig};
+
+        let prices = GasLimitsPrices {
+            flat_gas: Some((100, 40000)),
+            gas_price: 26214400,
+            gas_limit: 1000000,
+            special_gas_limit: Some(70000000),
+            gas_credit: 10000,
+            block_gas_limit: 10000000,
+            freeze_due_limit: 100000000,
+            delete_due_limit: 1000000000,
+        };
+        let mut dict = DictBuilder::new(32);
+        dict.insert(
+            BigUint::from(0u32),
+            CellBuilder::new()
+                .store_child(CellBuilder::new().store_slice(&[0x55; 32])?.build()?)?
+                .build()?,
+        )?;
+        dict.insert(
+            BigUint::from(21u32),
+            CellBuilder::new().store_child(prices.to_cell()?)?.build()?,
+        )?;
+        let dict = dict.build()?.unwrap();
+        let config = CellBuilder::new()
+            .store_slice(&[0x55; 32])?
+            .store_child(dict.clone())?
+            .build()?;
+        let mut params = Cell::load_config_params(&config, &mut 0, &mut config.parser())?;
+        assert_eq!(params.cells().len(), 2);
+        assert_eq!(params.to_cell()?.cell_hash(), dict.cell_hash());
+        assert_eq!(params.gas_prices(0)?, prices);
+        assert!(params.gas_prices(-1).is_err());
+
+        let mut tweaked = prices.clone();
+        tweaked.gas_price *= 2;
+        params.set_gas_prices(0, &tweaked)?;
+        assert_eq!(params.gas_prices(0)?, tweaked);
+        let config = CellBuilder::new()
+            .store_slice(&[0x55; 32])?
+            .store_child(params.to_cell()?)?
+            .build()?;
+        let reparsed = Cell::load_config_params(&config, &mut 0, &mut config.parser())?;
+        assert_eq!(reparsed.gas_prices(0)?, tweaked);
+        assert_eq!(reparsed.param(0), params.param(0));
+        let dict = params.to_cell()?;
+        let reparsed = Cell::load_config_params_dict(&dict, &mut 0, &mut dict.parser())?;
+        assert_eq!(reparsed.to_cell()?.cell_hash(), dict.cell_hash());
+
+        assert!(params.size_limits().is_err());
+        let size_limits = CellBuilder::new()
+            .store_u8(8, 0x02)?
+            .store_u32(32, 1 << 21)?
+            .store_u32(32, 1 << 13)?
+            .store_u32(32, 1000)?
+            .store_u16(16, 512)?
+            .store_u32(32, 65535)?
+            .store_u16(16, 512)?
+            .store_u32(32, 1 << 16)?
+            .store_u32(32, 1 << 25)?
+            .store_u32(32, 256)?
+            .build()?;
+        params.set_param(43, size_limits)?;
+        let limits = params.size_limits()?;
+        assert_eq!(limits.max_acc_state, Some((1 << 16, 1 << 25)));
+        assert_eq!(
+            SizeLimitsConfig {
+                max_acc_state: None,
+                ..limits
+            },
+            SizeLimitsConfig::default()
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_representation_works() -> anyhow::Result<()> {
+        use num_bigint::BigUint;
+
+        use crate::cell::CellBuilder;
+        use crate::responses::{MaybeRefData, TransactionMessage, VarUInteger};
+
+        let address: TonAddress = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR".parse()?;
+        let mut message = TransactionMessage {
+            hash: vec![0xab; 32],
+            ..Default::default()
+        };
+        message.info.src = address.clone();
+        message.info.value.grams = VarUInteger {
+            len: BigUint::from(8u32),
+            value: BigUint::from(u64::MAX) * 1000u32,
+        };
+        let cell = CellBuilder::new().store_u32(32, 7)?.build()?;
+        let data = MaybeRefData {
+            data: Some(message),
+            cell: Some(cell.clone()),
+            verified: false,
+        };
+
+        let json = serde_json::to_value(&data)?;
+        assert_eq!(json["data"]["hash"], "ab".repeat(32));
+        assert_eq!(json["data"]["info"]["src"], address.to_base64_url());
+        assert_eq!(
+            json["data"]["info"]["value"]["grams"]["value"],
+            "18446744073709551615000"
+        );
+        assert_eq!(json["cell"], "te6ccgEBAQEABgAACAAAAAc=");
+
+        let parsed: MaybeRefData<TransactionMessage> = serde_json::from_value(json.clone())?;
+        assert_eq!(parsed.cell.as_ref().unwrap().data, cell.data);
+        assert_eq!(serde_json::to_value(&parsed)?["data"], json["data"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+//! Serde representations of the fields of parsed blocks: byte strings as hex, integers of
+//! arbitrary size as decimal strings and cells as base64 bags of cells.
+
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+use num_bigint::BigUint;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::cell::{ArcCell, BagOfCells, Cell};
+use crate::compat::HashMap;
+use crate::responses::AnyCell;
+
+pub(crate) mod hex_bytes {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&hex::encode(bytes))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        hex::decode(s).map_err(D::Error::custom)
+    }
+}
+
+pub(crate) mod biguint_decimal {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        value: &BigUint,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BigUint, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        BigUint::from_str(&s).map_err(D::Error::custom)
+    }
+}
+
+pub(crate) mod cell_boc {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(cell: &Cell, serializer: S) -> Result<S::Ok, S::Error> {
+        BocRef(cell).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Cell, D::Error> {
+        Ok(Boc::deserialize(deserializer)?.0)
+    }
+}
+
+pub(crate) mod arc_cell_boc {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        cell: &ArcCell,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        BocRef(cell).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<ArcCell, D::Error> {
+        Ok(Arc::new(Boc::deserialize(deserializer)?.0))
+    }
+}
+
+pub(crate) mod option_cell_boc {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        cell: &Option<Cell>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        cell.as_ref().map(BocRef).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Cell>, D::Error> {
+        Ok(Option::<Boc>::deserialize(deserializer)?.map(|boc| boc.0))
+    }
+}
+
+/// Cells by their numbers, e.g. `ConfigParams::cells`.
+pub(crate) mod cell_map_boc {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        cells: &HashMap<u32, Cell>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(cells.iter().map(|(n, cell)| (n, BocRef(cell))))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<HashMap<u32, Cell>, D::Error> {
+        let cells = HashMap::<u32, Boc>::deserialize(deserializer)?;
+        Ok(cells.into_iter().map(|(n, boc)| (n, boc.0)).collect())
+    }
+}
+
+/// Representation of `TransactionBody::cell_ref`.
+pub(crate) mod body_cell_ref {
+    use super::*;
+
+    type CellRef = Option<(Option<AnyCell>, Option<Cell>)>;
+
+    pub(crate) fn serialize<S: Serializer>(
+        cell_ref: &CellRef,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        cell_ref
+            .as_ref()
+            .map(|(any, pruned)| (any, pruned.as_ref().map(BocRef)))
+            .serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<CellRef, D::Error> {
+        let cell_ref = Option::<(Option<AnyCell>, Option<Boc>)>::deserialize(deserializer)?;
+        Ok(cell_ref.map(|(any, pruned)| (any, pruned.map(|boc| boc.0))))
+    }
+}
+
+/// Cell serialized as a base64 bag of cells without CRC.
+struct BocRef<'a>(&'a Cell);
+
+impl Serialize for BocRef<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let boc = BagOfCells::from_root(self.0.clone())
+            .serialize_base64(false)
+            .map_err(serde::ser::Error::custom)?;
+        serializer.serialize_str(&boc)
+    }
+}
+
+struct Boc(Cell);
+
+impl<'de> Deserialize<'de> for Boc {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        let boc = BagOfCells::parse_base64(&s).map_err(D::Error::custom)?;
+        let root = boc.single_root().map_err(D::Error::custom)?;
+        Ok(Boc(root.as_ref().clone()))
+    }
+}
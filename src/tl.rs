@@ -1,6 +1,7 @@
 mod error;
 mod function;
 mod notification;
+mod request;
 mod result;
 mod serial;
 mod stack;
@@ -13,6 +14,7 @@ use base64_serde::base64_serde_type;
 pub use error::*;
 pub use function::*;
 pub use notification::*;
+pub use request::*;
 pub use result::*;
 pub use stack::*;
 use tonlib_sys::*;
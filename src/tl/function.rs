@@ -5,7 +5,7 @@ use strum::IntoStaticStr;
 
 use crate::tl::stack::TvmStackEntry;
 use crate::tl::types::{
-    AccountAddress, BlockId, BlockIdExt, BlocksAccountTransactionId, InternalTransactionId,
+    AccountAddress, BlockId, BlockIdExt, BlocksAccountTransactionId, Config, InternalTransactionId,
     Options, SmcLibraryQueryExt, SmcMethodId,
 };
 use crate::tl::Base64Standard;
@@ -57,6 +57,12 @@ pub enum TonFunction {
         options: Options,
     },
 
+    // tonlib_api.tl, line 241
+    #[serde(rename = "options.setConfig")]
+    OptionsSetConfig {
+        config: Config,
+    },
+
     // tonlib_api.tl, line 261
     #[serde(rename = "raw.sendMessageReturnHash")]
     RawSendMessageReturnHash {
@@ -104,6 +110,18 @@ pub enum TonFunction {
         body: Vec<u8>,
     },
 
+    // tonlib_api.tl, line 273
+    #[serde(rename = "raw.createQuery")]
+    RawCreateQuery {
+        destination: AccountAddress,
+        #[serde(with = "Base64Standard")]
+        init_code: Vec<u8>,
+        #[serde(with = "Base64Standard")]
+        init_data: Vec<u8>,
+        #[serde(with = "Base64Standard")]
+        body: Vec<u8>,
+    },
+
     // tonlib_api.tl, line 288
     #[serde(rename = "getAccountState")]
     GetAccountState {
@@ -123,6 +141,19 @@ pub enum TonFunction {
         mode: u32,
     },
 
+    // tonlib_api.tl, line 301
+    #[serde(rename = "query.forget")]
+    QueryForget {
+        id: i64,
+    },
+
+    // tonlib_api.tl, line 302
+    #[serde(rename = "query.estimateFees")]
+    QueryEstimateFees {
+        id: i64,
+        ignore_chksig: bool,
+    },
+
     // tonlib_api.tl, line 306
     #[serde(rename = "smc.load")]
     SmcLoad {
@@ -227,6 +258,12 @@ pub enum TonFunction {
     #[serde(rename = "liteServer.getInfo")]
     LiteServerGetInfo {},
 
+    // tonlib_api.tl, line 338
+    WithBlock {
+        id: BlockIdExt,
+        function: Box<TonFunction>,
+    },
+
     // tonlib_api.tl, line 352
     SetLogVerbosityLevel {
         new_verbosity_level: u32,
@@ -0,0 +1,321 @@
+use crate::tl::stack::{TvmCell, TvmStackEntry};
+use crate::tl::types::{
+    AccountAddress, BlockId, BlockIdExt, BlocksAccountTransactionId, BlocksHeader,
+    BlocksMasterchainInfo, BlocksShards, BlocksTransactions, BlocksTransactionsExt, Config,
+    ConfigInfo, FullAccountState, InternalTransactionId, LiteServerInfo, LogVerbosityLevel,
+    Options, OptionsConfigInfo, OptionsInfo, QueryFees, QueryInfo, RawExtMessageInfo,
+    RawFullAccountState, RawTransactions, SmcInfo, SmcLibraryQueryExt, SmcLibraryResult,
+    SmcLibraryResultExt, SmcMethodId, SmcRunResult,
+};
+use crate::tl::{TonFunction, TonLibraryId, TonResult, TonResultDiscriminants};
+
+/// Typed tonlib function, converted into [`TonFunction`] to be executed, whose result is
+/// checked to be of the type of [`TlRequest::Response`].
+pub trait TlRequest: Into<TonFunction> {
+    type Response;
+
+    /// Type of the result of the function.
+    const RESULT: TonResultDiscriminants;
+
+    /// Extracts the response from the result, returning the result back if it has another type.
+    #[allow(clippy::result_large_err)]
+    fn response(result: TonResult) -> Result<Self::Response, TonResult>;
+}
+
+/// Declares the request structs of tonlib functions, each having the fields of the variant of
+/// [`TonFunction`] after `=` and returning the variant of [`TonResult`] after `->`.
+macro_rules! tl_requests {
+    ($(
+        $(#[$attr:meta])*
+        $name:ident = $variant:ident { $($field:ident: $field_ty:ty),* $(,)? } -> $result:ident;
+    )*) => {
+        $(
+            $(#[$attr])*
+            #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+            pub struct $name {
+                $(pub $field: $field_ty,)*
+            }
+
+            impl From<$name> for TonFunction {
+                #[allow(unused_variables)]
+                fn from(request: $name) -> TonFunction {
+                    TonFunction::$variant {
+                        $($field: request.$field,)*
+                    }
+                }
+            }
+
+            impl TlRequest for $name {
+                type Response = tl_requests!(@response $result);
+
+                const RESULT: TonResultDiscriminants = TonResultDiscriminants::$result;
+
+                fn response(result: TonResult) -> Result<Self::Response, TonResult> {
+                    tl_requests!(@extract result, $result)
+                }
+            }
+        )*
+    };
+    (@response Ok) => { () };
+    (@response $result:ident) => { $result };
+    (@extract $r:ident, Ok) => {
+        match $r {
+            TonResult::Ok {} => Ok(()),
+            r => Err(r),
+        }
+    };
+    (@extract $r:ident, $result:ident) => {
+        match $r {
+            TonResult::$result(response) => Ok(response),
+            r => Err(r),
+        }
+    };
+}
+
+tl_requests! {
+    /// `init options:options = options.Info`
+    InitRequest = Init { options: Options } -> OptionsInfo;
+
+    /// `options.setConfig config:config = options.ConfigInfo`
+    OptionsSetConfigRequest = OptionsSetConfig { config: Config } -> OptionsConfigInfo;
+
+    /// `raw.sendMessageReturnHash body:bytes = raw.ExtMessageInfo`
+    RawSendMessageReturnHashRequest = RawSendMessageReturnHash {
+        body: Vec<u8>,
+    } -> RawExtMessageInfo;
+
+    /// `sync = ton.BlockIdExt`
+    SyncRequest = Sync {} -> BlockIdExt;
+
+    /// `raw.getAccountState account_address:accountAddress = raw.FullAccountState`
+    RawGetAccountStateRequest = RawGetAccountState {
+        account_address: AccountAddress,
+    } -> RawFullAccountState;
+
+    /// `raw.getAccountStateByTransaction account_address:accountAddress
+    /// transaction_id:internal.transactionId = raw.FullAccountState`
+    RawGetAccountStateByTransactionRequest = RawGetAccountStateByTransaction {
+        account_address: AccountAddress,
+        transaction_id: InternalTransactionId,
+    } -> RawFullAccountState;
+
+    /// `raw.getTransactions account_address:accountAddress
+    /// from_transaction_id:internal.transactionId = raw.Transactions`
+    RawGetTransactionsRequest = RawGetTransactions {
+        account_address: AccountAddress,
+        from_transaction_id: InternalTransactionId,
+    } -> RawTransactions;
+
+    /// `raw.getTransactionsV2 account_address:accountAddress
+    /// from_transaction_id:internal.transactionId count:# try_decode_messages:Bool
+    /// = raw.Transactions`
+    RawGetTransactionsV2Request = RawGetTransactionsV2 {
+        account_address: AccountAddress,
+        from_transaction_id: InternalTransactionId,
+        count: u32,
+        try_decode_messages: bool,
+    } -> RawTransactions;
+
+    /// `raw.sendMessage body:bytes = Ok`
+    RawSendMessageRequest = RawSendMessage { body: Vec<u8> } -> Ok;
+
+    /// `raw.createQuery destination:accountAddress init_code:bytes init_data:bytes body:bytes
+    /// = query.Info`
+    RawCreateQueryRequest = RawCreateQuery {
+        destination: AccountAddress,
+        init_code: Vec<u8>,
+        init_data: Vec<u8>,
+        body: Vec<u8>,
+    } -> QueryInfo;
+
+    /// `getAccountState account_address:accountAddress = FullAccountState`
+    GetAccountStateRequest = GetAccountState {
+        account_address: AccountAddress,
+    } -> FullAccountState;
+
+    /// `getConfigParam mode:# param:# = ConfigInfo`
+    GetConfigParamRequest = GetConfigParam { mode: u32, param: u32 } -> ConfigInfo;
+
+    /// `getConfigAll mode:# = ConfigInfo`
+    GetConfigAllRequest = GetConfigAll { mode: u32 } -> ConfigInfo;
+
+    /// `query.forget id:int53 = Ok`
+    QueryForgetRequest = QueryForget { id: i64 } -> Ok;
+
+    /// `query.estimateFees id:int53 ignore_chksig:Bool = query.Fees`
+    QueryEstimateFeesRequest = QueryEstimateFees { id: i64, ignore_chksig: bool } -> QueryFees;
+
+    /// `smc.load account_address:accountAddress = smc.Info`
+    SmcLoadRequest = SmcLoad { account_address: AccountAddress } -> SmcInfo;
+
+    /// `smc.loadByTransaction account_address:accountAddress
+    /// transaction_id:internal.transactionId = smc.Info`
+    SmcLoadByTransactionRequest = SmcLoadByTransaction {
+        account_address: AccountAddress,
+        transaction_id: InternalTransactionId,
+    } -> SmcInfo;
+
+    /// `smc.forget id:int53 = Ok`
+    SmcForgetRequest = SmcForget { id: i64 } -> Ok;
+
+    /// `smc.getCode id:int53 = tvm.Cell`
+    SmcGetCodeRequest = SmcGetCode { id: i64 } -> TvmCell;
+
+    /// `smc.getData id:int53 = tvm.Cell`
+    SmcGetDataRequest = SmcGetData { id: i64 } -> TvmCell;
+
+    /// `smc.getState id:int53 = tvm.Cell`
+    SmcGetStateRequest = SmcGetState { id: i64 } -> TvmCell;
+
+    /// `smc.runGetMethod id:int53 method:smc.MethodId stack:vector<tvm.StackEntry>
+    /// = smc.RunResult`
+    SmcRunGetMethodRequest = SmcRunGetMethod {
+        id: i64,
+        method: SmcMethodId,
+        stack: Vec<TvmStackEntry>,
+    } -> SmcRunResult;
+
+    /// `smc.getLibraries library_list:vector<int256> = smc.LibraryResult`
+    SmcGetLibrariesRequest = SmcGetLibraries {
+        library_list: Vec<TonLibraryId>,
+    } -> SmcLibraryResult;
+
+    /// `smc.getLibrariesExt list:vector<smc.LibraryQueryExt> = smc.LibraryResultExt`
+    SmcGetLibrariesExtRequest = SmcGetLibrariesExt {
+        list: Vec<SmcLibraryQueryExt>,
+    } -> SmcLibraryResultExt;
+
+    /// `blocks.getMasterchainInfo = blocks.MasterchainInfo`
+    BlocksGetMasterchainInfoRequest = BlocksGetMasterchainInfo {} -> BlocksMasterchainInfo;
+
+    /// `blocks.getShards id:ton.blockIdExt = blocks.Shards`
+    BlocksGetShardsRequest = BlocksGetShards { id: BlockIdExt } -> BlocksShards;
+
+    /// `blocks.lookupBlock mode:# id:ton.blockId lt:int64 utime:int32 = ton.BlockIdExt`
+    BlocksLookupBlockRequest = BlocksLookupBlock {
+        mode: i32,
+        id: BlockId,
+        lt: i64,
+        utime: i32,
+    } -> BlockIdExt;
+
+    /// `blocks.getTransactions id:ton.blockIdExt mode:# count:#
+    /// after:blocks.accountTransactionId = blocks.Transactions`
+    BlocksGetTransactionsRequest = BlocksGetTransactions {
+        id: BlockIdExt,
+        mode: u32,
+        count: u32,
+        after: BlocksAccountTransactionId,
+    } -> BlocksTransactions;
+
+    /// `blocks.getTransactionsExt id:ton.blockIdExt mode:# count:#
+    /// after:blocks.accountTransactionId = blocks.TransactionsExt`
+    BlocksGetTransactionsExtRequest = BlocksGetTransactionsExt {
+        id: BlockIdExt,
+        mode: u32,
+        count: u32,
+        after: BlocksAccountTransactionId,
+    } -> BlocksTransactionsExt;
+
+    /// `blocks.getBlockHeader id:ton.blockIdExt = blocks.Header`
+    GetBlockHeaderRequest = GetBlockHeader { id: BlockIdExt } -> BlocksHeader;
+
+    /// `liteServer.getInfo = liteServer.Info`
+    LiteServerGetInfoRequest = LiteServerGetInfo {} -> LiteServerInfo;
+
+    /// `setLogVerbosityLevel new_verbosity_level:int32 = Ok`
+    SetLogVerbosityLevelRequest = SetLogVerbosityLevel { new_verbosity_level: u32 } -> Ok;
+
+    /// `getLogVerbosityLevel = LogVerbosityLevel`
+    GetLogVerbosityLevelRequest = GetLogVerbosityLevel {} -> LogVerbosityLevel;
+}
+
+/// Runs the request against the state as of the block:
+/// `withBlock id:ton.blockIdExt function:Function = Object`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WithBlockRequest<R> {
+    pub id: BlockIdExt,
+    pub request: R,
+}
+
+impl<R: TlRequest> From<WithBlockRequest<R>> for TonFunction {
+    fn from(request: WithBlockRequest<R>) -> TonFunction {
+        TonFunction::WithBlock {
+            id: request.id,
+            function: Box::new(request.request.into()),
+        }
+    }
+}
+
+impl<R: TlRequest> TlRequest for WithBlockRequest<R> {
+    type Response = R::Response;
+
+    const RESULT: TonResultDiscriminants = R::RESULT;
+
+    fn response(result: TonResult) -> Result<Self::Response, TonResult> {
+        R::response(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tl::{
+        AccountAddress, BlockIdExt, GetConfigAllRequest, InternalTransactionId,
+        RawGetTransactionsRequest, RawSendMessageRequest, TlRequest, TonFunction, TonResult,
+        TonResultDiscriminants, WithBlockRequest,
+    };
+
+    #[test]
+    fn tl_requests_work() {
+        let account_address = AccountAddress {
+            account_address: "0:1234".to_string(),
+        };
+        let from_transaction_id = InternalTransactionId {
+            lt: 1,
+            hash: vec![2; 32],
+        };
+        let request = RawGetTransactionsRequest {
+            account_address: account_address.clone(),
+            from_transaction_id: from_transaction_id.clone(),
+        };
+        assert_eq!(
+            TonFunction::from(request),
+            TonFunction::RawGetTransactions {
+                account_address,
+                from_transaction_id,
+            }
+        );
+        assert_eq!(
+            RawGetTransactionsRequest::RESULT,
+            TonResultDiscriminants::RawTransactions
+        );
+        assert_eq!(RawSendMessageRequest::response(TonResult::Ok {}), Ok(()));
+
+        let id = BlockIdExt {
+            workchain: -1,
+            shard: i64::MIN,
+            seqno: 7,
+            root_hash: String::new(),
+            file_hash: String::new(),
+        };
+        let request = WithBlockRequest {
+            id: id.clone(),
+            request: GetConfigAllRequest { mode: 0 },
+        };
+        assert_eq!(
+            TonFunction::from(request),
+            TonFunction::WithBlock {
+                id,
+                function: Box::new(TonFunction::GetConfigAll { mode: 0 }),
+            }
+        );
+        assert_eq!(
+            WithBlockRequest::<GetConfigAllRequest>::RESULT,
+            TonResultDiscriminants::ConfigInfo
+        );
+        assert_eq!(
+            GetConfigAllRequest::response(TonResult::Ok {}),
+            Err(TonResult::Ok {})
+        );
+    }
+}
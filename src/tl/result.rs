@@ -8,8 +8,8 @@ use crate::tl::stack::TvmCell;
 use crate::tl::types::{
     BlockIdExt, BlocksHeader, BlocksMasterchainInfo, BlocksShards, BlocksTransactions,
     BlocksTransactionsExt, ConfigInfo, FullAccountState, LiteServerInfo, LogVerbosityLevel,
-    OptionsInfo, RawExtMessageInfo, RawFullAccountState, RawTransactions, SmcInfo,
-    SmcLibraryResult, SmcLibraryResultExt, SmcRunResult, UpdateSyncState,
+    OptionsConfigInfo, OptionsInfo, QueryFees, QueryInfo, RawExtMessageInfo, RawFullAccountState,
+    RawTransactions, SmcInfo, SmcLibraryResult, SmcLibraryResultExt, SmcRunResult, UpdateSyncState,
 };
 
 #[derive(
@@ -28,6 +28,9 @@ pub enum TonResult {
     // tonlib_api.tl, line 30
     #[serde(rename = "options.info")]
     OptionsInfo(OptionsInfo),
+    // tonlib_api.tl, line 29
+    #[serde(rename = "options.configInfo")]
+    OptionsConfigInfo(OptionsConfigInfo),
     // tonlib_api.tl, line 51
     #[serde(rename = "ton.blockIdExt")]
     BlockIdExt(BlockIdExt),
@@ -43,6 +46,12 @@ pub enum TonResult {
     // tonlib_api.tl, line 90
     #[serde(rename = "fullAccountState")]
     FullAccountState(FullAccountState),
+    // tonlib_api.tl, line 162
+    #[serde(rename = "query.fees")]
+    QueryFees(QueryFees),
+    // tonlib_api.tl, line 164
+    #[serde(rename = "query.info")]
+    QueryInfo(QueryInfo),
     // tonlib_api.tl, line 167
     #[serde(rename = "tvm.cell")]
     TvmCell(TvmCell),
@@ -114,6 +123,12 @@ impl fmt::Display for TonResult {
                 options_info.config_info.default_wallet_id
             ),
 
+            TonResult::OptionsConfigInfo(config_info) => write!(
+                f,
+                "TonResult::OptionsConfigInfo: {}",
+                config_info.default_wallet_id
+            ),
+
             TonResult::BlockIdExt(block_id_ext) => write!(
                 f,
                 "TonResult::BlockIdExt: {}:{}, seqno{}",
@@ -143,6 +158,16 @@ impl fmt::Display for TonResult {
                 full_account_state.address.account_address
             ),
 
+            TonResult::QueryFees(query_fees) => write!(
+                f,
+                "TonResult::QueryFees: source fees {}",
+                query_fees.source_fees.total()
+            ),
+
+            TonResult::QueryInfo(query_info) => {
+                write!(f, "TonResult::QueryInfo: {}", query_info.id)
+            }
+
             TonResult::SmcInfo(_) => write!(f, "TonResult::SmcInfo"),
 
             TonResult::SmcRunResult(smc_run_result) => {
@@ -11,6 +11,8 @@ use serde::{Deserialize, Serialize};
 use serde_aux::prelude::*;
 
 use super::TonLibraryId;
+use crate::cell::{ArcCell, BagOfCells, Cell, StateInit, TonCellError};
+use crate::responses::AccountStatus;
 use crate::tl::stack::{TvmCell, TvmStack};
 use crate::tl::{Base64Standard, InternalTransactionIdParseError};
 
@@ -216,6 +218,66 @@ pub struct RawFullAccountState {
     pub sync_utime: i64,
 }
 
+impl RawFullAccountState {
+    /// Returns the status of the account, telling apart accounts waiting for deployment and
+    /// frozen accounts.
+    pub fn status(&self) -> AccountStatus {
+        account_status(
+            !self.code.is_empty(),
+            &self.frozen_hash,
+            &self.last_transaction_id,
+        )
+    }
+
+    /// Returns the hash of the state of frozen accounts.
+    pub fn frozen_state_hash(&self) -> Option<&[u8]> {
+        (self.status() == AccountStatus::Frozen).then_some(self.frozen_hash.as_slice())
+    }
+
+    /// Returns the code and the data of active accounts.
+    pub fn state_init(&self) -> Result<Option<StateInit>, TonCellError> {
+        if self.status() != AccountStatus::Active {
+            return Ok(None);
+        }
+        Ok(Some(StateInit {
+            code: boc_root(&self.code)?,
+            data: boc_root(&self.data)?,
+        }))
+    }
+
+    pub fn code_cell(&self) -> Result<Option<ArcCell>, TonCellError> {
+        Ok(self.state_init()?.and_then(|state_init| state_init.code))
+    }
+
+    pub fn data_cell(&self) -> Result<Option<ArcCell>, TonCellError> {
+        Ok(self.state_init()?.and_then(|state_init| state_init.data))
+    }
+
+    /// Returns the hash of the code of active accounts, e.g. to detect the interface of the
+    /// contract.
+    pub fn code_hash(&self) -> Result<Option<Vec<u8>>, TonCellError> {
+        self.code_cell()?.map(|code| code.cell_hash()).transpose()
+    }
+
+    /// Returns whether the account is active with the code and the data of the state init,
+    /// i.e. was neither upgraded nor modified since it was deployed with it.
+    pub fn matches_state_init(&self, state_init: &Cell) -> Result<bool, TonCellError> {
+        let deployed = StateInit::parse(state_init)?;
+        match self.state_init()? {
+            Some(current) => Ok(current.code_hash()? == deployed.code_hash()?
+                && current.data_hash()? == deployed.data_hash()?),
+            None => Ok(false),
+        }
+    }
+}
+
+fn boc_root(boc: &[u8]) -> Result<Option<ArcCell>, TonCellError> {
+    if boc.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(BagOfCells::parse(boc)?.single_root()?.clone()))
+}
+
 // tonlib_api.tl, line 54
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct RawMessage {
@@ -416,6 +478,39 @@ pub struct FullAccountState {
     pub revision: i32,
 }
 
+impl FullAccountState {
+    /// Returns the status of the account, see [`RawFullAccountState::status`].
+    pub fn status(&self) -> AccountStatus {
+        match &self.account_state {
+            AccountState::Raw {
+                code, frozen_hash, ..
+            } => account_status(!code.is_empty(), frozen_hash, &self.last_transaction_id),
+            AccountState::Uninited { frozen_hash } => {
+                account_status(false, frozen_hash, &self.last_transaction_id)
+            }
+            _ => AccountStatus::Active,
+        }
+    }
+}
+
+/// tonlib reports frozen accounts as uninitialized accounts with the frozen state hash, and
+/// accounts without any transaction as uninitialized ones.
+fn account_status(
+    has_code: bool,
+    frozen_hash: &[u8],
+    last_transaction_id: &InternalTransactionId,
+) -> AccountStatus {
+    if has_code {
+        AccountStatus::Active
+    } else if !frozen_hash.is_empty() {
+        AccountStatus::Frozen
+    } else if last_transaction_id.lt == 0 {
+        AccountStatus::NonExist
+    } else {
+        AccountStatus::Uninit
+    }
+}
+
 // tonlib_api.tl, line 95-96
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 #[serde(tag = "@type")]
@@ -458,6 +553,47 @@ pub enum MsgData {
     },
 }
 
+// tonlib_api.tl, line 161
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Fees {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub in_fwd_fee: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub storage_fee: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub gas_fee: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub fwd_fee: i64,
+}
+
+impl Fees {
+    pub fn total(&self) -> i64 {
+        self.in_fwd_fee + self.storage_fee + self.gas_fee + self.fwd_fee
+    }
+}
+
+// tonlib_api.tl, line 162
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryFees {
+    pub source_fees: Fees,
+    pub destination_fees: Vec<Fees>,
+}
+
+// tonlib_api.tl, line 164
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct QueryInfo {
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub id: i64,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub valid_until: i64,
+    #[serde(with = "Base64Standard")]
+    pub body_hash: Vec<u8>,
+    #[serde(with = "Base64Standard")]
+    pub body: Vec<u8>,
+    #[serde(with = "Base64Standard")]
+    pub init_state: Vec<u8>,
+}
+
 // tonlib_api.tl, line 179
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct SmcInfo {
@@ -642,11 +778,17 @@ pub struct ConfigInfo {
 #[cfg(test)]
 mod tests {
     use std::borrow::Cow;
+    use std::sync::Arc;
 
     use tokio_test::assert_err;
 
+    use crate::cell::{BagOfCells, CellBuilder, StateInitBuilder};
+    use crate::responses::AccountStatus;
     use crate::tl::types::InternalTransactionId;
-    use crate::tl::{InternalTransactionIdParseError, SmcMethodId};
+    use crate::tl::{
+        AccountAddress, AccountState, BlockIdExt, FullAccountState,
+        InternalTransactionIdParseError, RawFullAccountState, SmcMethodId,
+    };
 
     #[test]
     fn internal_transaction_id_parse_format_works() -> anyhow::Result<()> {
@@ -663,6 +805,104 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn account_state_init_works() -> anyhow::Result<()> {
+        let code = Arc::new(CellBuilder::new().store_string("code")?.build()?);
+        let data = Arc::new(CellBuilder::new().store_string("data")?.build()?);
+        let boc = |cell: &Arc<_>| BagOfCells::new(&[Arc::clone(cell)]).serialize(true);
+        let mut account = RawFullAccountState {
+            balance: 1,
+            code: boc(&code)?,
+            data: boc(&data)?,
+            last_transaction_id: InternalTransactionId {
+                lt: 10,
+                hash: vec![0; 32],
+            },
+            block_id: BlockIdExt {
+                workchain: -1,
+                shard: i64::MIN,
+                seqno: 1,
+                root_hash: String::new(),
+                file_hash: String::new(),
+            },
+            frozen_hash: vec![],
+            sync_utime: 0,
+        };
+        assert_eq!(account.code_hash()?, Some(code.cell_hash()?));
+        assert_eq!(
+            account.data_cell()?.map(|c| c.cell_hash()).transpose()?,
+            Some(data.cell_hash()?)
+        );
+        let deployed = StateInitBuilder::new(&code, &data).build()?;
+        assert!(account.matches_state_init(&deployed)?);
+
+        let upgraded = Arc::new(CellBuilder::new().store_string("code v2")?.build()?);
+        account.code = boc(&upgraded)?;
+        assert!(!account.matches_state_init(&deployed)?);
+        assert_eq!(account.code_hash()?, Some(upgraded.cell_hash()?));
+
+        account.code = vec![];
+        assert_eq!(account.state_init()?.map(|s| s.code), None);
+        assert!(!account.matches_state_init(&deployed)?);
+        Ok(())
+    }
+
+    #[test]
+    fn account_status_works() {
+        let raw_state = |code: &[u8], frozen_hash: &[u8], lt: i64| RawFullAccountState {
+            balance: 0,
+            code: code.to_vec(),
+            data: vec![],
+            last_transaction_id: InternalTransactionId {
+                lt,
+                hash: vec![0; 32],
+            },
+            block_id: BlockIdExt {
+                workchain: -1,
+                shard: i64::MIN,
+                seqno: 1,
+                root_hash: String::new(),
+                file_hash: String::new(),
+            },
+            frozen_hash: frozen_hash.to_vec(),
+            sync_utime: 0,
+        };
+        let active = raw_state(&[1], &[], 10);
+        assert_eq!(active.status(), AccountStatus::Active);
+        assert_eq!(active.frozen_state_hash(), None);
+        let frozen = raw_state(&[], &[7; 32], 10);
+        assert_eq!(frozen.status(), AccountStatus::Frozen);
+        assert!(frozen.status().is_frozen() && !frozen.status().needs_deploy());
+        assert_eq!(frozen.frozen_state_hash(), Some(&[7; 32][..]));
+        let uninit = raw_state(&[], &[], 10);
+        assert_eq!(uninit.status(), AccountStatus::Uninit);
+        assert!(uninit.status().needs_deploy());
+        assert_eq!(raw_state(&[], &[], 0).status(), AccountStatus::NonExist);
+
+        let full_state = FullAccountState {
+            address: AccountAddress {
+                account_address: String::new(),
+            },
+            balance: 0,
+            last_transaction_id: frozen.last_transaction_id.clone(),
+            block_id: frozen.block_id.clone(),
+            sync_utime: 0,
+            account_state: AccountState::Uninited {
+                frozen_hash: vec![7; 32],
+            },
+            revision: 0,
+        };
+        assert_eq!(full_state.status(), AccountStatus::Frozen);
+        let full_state = FullAccountState {
+            account_state: AccountState::WalletV4 {
+                wallet_id: 1,
+                seqno: 2,
+            },
+            ..full_state
+        };
+        assert_eq!(full_state.status(), AccountStatus::Active);
+    }
+
     #[test]
     fn internal_transaction_id_parse_base64_works() -> anyhow::Result<()> {
         let id_str = "33256211000003:uY36AzqWPzu5mF8XPvLGyUSb54oEPsH8WWX+JKbWFaM=";
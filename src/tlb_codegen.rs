@@ -0,0 +1,643 @@
+//! Generator of Rust types with [`TlbSerialize`](crate::cell::TlbSerialize) and
+//! [`TlbDeserialize`](crate::cell::TlbDeserialize) implementations from TL-B schemas.
+//!
+//! The generator is meant to be called from a build script:
+//!
+//! ```rust,ignore
+//! let schema = std::fs::read_to_string("schema/jetton.tlb")?;
+//! let code = TlbCodegen::new().generate(&schema)?;
+//! std::fs::write(Path::new(&std::env::var("OUT_DIR")?).join("jetton.rs"), code)?;
+//! ```
+//!
+//! Supported declarations have explicit tags (`#hex`, `$bits` or `_`) and fields of the
+//! following types:
+//!
+//! * `Bool`, `Bit`, `#`, `uintN`, `intN`, `bitsN`, `(## N)`, `(uint N)`, `(int N)`,
+//!   `(bits N)` with constant `N`, at most 64 for signed integers;
+//! * `Grams`, `Coins`, `(VarUInteger 16)`, `MsgAddress`, `MsgAddressInt`;
+//! * `^Cell`, `^Any`, `^X`, `(Maybe X)`;
+//! * types declared in the schema.
+//!
+//! Types with a single constructor become structs, other types become enums with a variant
+//! per constructor. Generated code uses [`Coins`](crate::coins::Coins) for coins and `num_bigint` for
+//! unsigned integers over 64 bits.
+
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::Write;
+
+pub use error::*;
+use schema::{split_declarations, Constructor, FieldType, Tag, BUILTIN_TYPES};
+
+mod error;
+mod schema;
+
+const RUST_KEYWORDS: [&str; 34] = [
+    "as", "async", "await", "box", "break", "const", "continue", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "static", "struct", "trait", "true", "type", "unsafe", "use", "where",
+];
+
+/// Names which cannot be raw identifiers or are used by the generated code.
+const RESERVED_NAMES: [&str; 8] = [
+    "crate", "self", "Self", "super", "builder", "parser", "child", "tag",
+];
+
+/// Generator of Rust code from TL-B schemas.
+#[derive(Debug, Clone)]
+pub struct TlbCodegen {
+    crate_path: String,
+    skip_unsupported: bool,
+}
+
+impl Default for TlbCodegen {
+    fn default() -> Self {
+        TlbCodegen {
+            crate_path: "tonlib".to_string(),
+            skip_unsupported: false,
+        }
+    }
+}
+
+impl TlbCodegen {
+    pub fn new() -> TlbCodegen {
+        TlbCodegen::default()
+    }
+
+    /// Sets the path of this crate used by the generated code, `tonlib` by default.
+    pub fn with_crate_path(&mut self, crate_path: &str) -> &mut Self {
+        self.crate_path = crate_path.to_string();
+        self
+    }
+
+    /// Skips the types with unsupported declarations and the types depending on them instead
+    /// of failing, listing them in a comment of the generated code. Useful to generate the
+    /// supported part of large schemas such as `block.tlb`.
+    pub fn with_skip_unsupported(&mut self, skip_unsupported: bool) -> &mut Self {
+        self.skip_unsupported = skip_unsupported;
+        self
+    }
+
+    /// Generates the Rust code of the types declared in the schema.
+    pub fn generate(&self, schema: &str) -> Result<String, TlbCodegenError> {
+        let mut types: Vec<(String, Vec<Constructor>)> = Vec::new();
+        let mut skipped: Vec<(String, TlbCodegenError)> = Vec::new();
+        for declaration in split_declarations(schema) {
+            let constructor = match schema::parse_declaration(&declaration) {
+                Ok(constructor) => constructor,
+                Err(e @ TlbCodegenError::Unsupported { .. }) if self.skip_unsupported => {
+                    if let Some(type_name) = declared_type_name(&declaration) {
+                        skipped.push((type_name, e));
+                    }
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            if BUILTIN_TYPES.contains(&constructor.type_name.as_str()) {
+                continue;
+            }
+            match types
+                .iter_mut()
+                .find(|(name, _)| name == &constructor.type_name)
+            {
+                Some((_, constructors)) => constructors.push(constructor),
+                None => types.push((constructor.type_name.clone(), vec![constructor])),
+            }
+        }
+
+        // Remove types with skipped constructors or depending on removed types
+        let mut removed: HashSet<String> = skipped.iter().map(|(name, _)| name.clone()).collect();
+        loop {
+            let declared: HashSet<&str> = types.iter().map(|(name, _)| name.as_str()).collect();
+            let mut dependency_error = None;
+            for (type_name, constructors) in &types {
+                if removed.contains(type_name) {
+                    dependency_error = Some((type_name.clone(), None));
+                    break;
+                }
+                for constructor in constructors {
+                    if let Some(error) = check_dependencies(constructor, &declared) {
+                        dependency_error = Some((type_name.clone(), Some(error)));
+                        break;
+                    }
+                }
+                if dependency_error.is_some() {
+                    break;
+                }
+            }
+            match dependency_error {
+                None => break,
+                Some((_, Some(error))) if !self.skip_unsupported => return Err(error),
+                Some((type_name, error)) => {
+                    types.retain(|(name, _)| name != &type_name);
+                    if let Some(error) = error {
+                        skipped.push((type_name.clone(), error));
+                    }
+                    removed.insert(type_name);
+                }
+            }
+        }
+
+        for (_, constructors) in &types {
+            check_tags(constructors)?;
+        }
+
+        let mut emitter = Emitter::default();
+        for (type_name, constructors) in &types {
+            emitter.emit_type(type_name, constructors);
+        }
+        Ok(emitter.finish(&self.crate_path, &skipped))
+    }
+}
+
+/// Returns the type name of a declaration that could not be parsed.
+fn declared_type_name(declaration: &str) -> Option<String> {
+    let (_, rhs) = declaration.rsplit_once('=')?;
+    rhs.split_whitespace().next().map(str::to_string)
+}
+
+fn check_dependencies(
+    constructor: &Constructor,
+    declared: &HashSet<&str>,
+) -> Option<TlbCodegenError> {
+    let mut unknown = None;
+    for field in &constructor.fields {
+        field.ty.visit_named(&mut |name| {
+            if unknown.is_none() && !declared.contains(name) {
+                unknown = Some(name.to_string());
+            }
+        });
+    }
+    unknown.map(|type_name| TlbCodegenError::UnknownType {
+        declaration: constructor.declaration.clone(),
+        type_name,
+    })
+}
+
+/// Checks that the constructors can be told apart by their tags.
+fn check_tags(constructors: &[Constructor]) -> Result<(), TlbCodegenError> {
+    for (i, a) in constructors.iter().enumerate() {
+        for b in &constructors[i + 1..] {
+            let len = a.tag.len.min(b.tag.len);
+            if a.tag.value >> (a.tag.len - len) == b.tag.value >> (b.tag.len - len) {
+                return Err(TlbCodegenError::unsupported(
+                    &b.declaration,
+                    format!("tag is not distinguishable from the tag of {}", a.name),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Accumulates the generated code and the items it uses.
+#[derive(Default)]
+struct Emitter {
+    code: String,
+    imports: BTreeSet<&'static str>,
+}
+
+impl Emitter {
+    fn finish(self, crate_path: &str, skipped: &[(String, TlbCodegenError)]) -> String {
+        let mut out = String::new();
+        out.push_str("// @generated by tonlib::tlb_codegen, do not edit.\n");
+        if !skipped.is_empty() {
+            out.push_str("//\n// Skipped types:\n");
+            for (type_name, error) in skipped {
+                let _ = writeln!(out, "// * {}: {}", type_name, error);
+            }
+        }
+        out.push('\n');
+        if self.imports.contains("BigUint") {
+            out.push_str("use num_bigint::BigUint;\n");
+        }
+        if self.imports.contains("TonAddress") {
+            let _ = writeln!(out, "use {}::address::TonAddress;", crate_path);
+        }
+        let cell_imports: Vec<&str> = self
+            .imports
+            .iter()
+            .copied()
+            .filter(|item| !matches!(*item, "BigUint" | "Coins" | "TonAddress"))
+            .collect();
+        if !cell_imports.is_empty() {
+            let _ = writeln!(
+                out,
+                "use {}::cell::{};",
+                crate_path,
+                use_tree(&cell_imports)
+            );
+        }
+        if self.imports.contains("Coins") {
+            let _ = writeln!(out, "use {}::coins::Coins;", crate_path);
+        }
+        out.push_str(&self.code);
+        out
+    }
+
+    fn emit_type(&mut self, type_name: &str, constructors: &[Constructor]) {
+        self.imports.extend([
+            "CellBuilder",
+            "CellParser",
+            "TlbDeserialize",
+            "TlbSerialize",
+            "TonCellError",
+        ]);
+        let code = &mut String::new();
+        code.push_str("\n/// ```raw\n");
+        for constructor in constructors {
+            let _ = writeln!(code, "/// {};", constructor.declaration);
+        }
+        code.push_str("/// ```\n#[derive(Debug, Clone, PartialEq)]\n");
+        if let [constructor] = constructors {
+            self.emit_struct(code, type_name, constructor);
+        } else {
+            self.emit_enum(code, type_name, constructors);
+        }
+        self.code.push_str(code);
+    }
+
+    fn emit_struct(&mut self, code: &mut String, type_name: &str, constructor: &Constructor) {
+        let fields = field_names(constructor);
+        if fields.is_empty() {
+            let _ = writeln!(code, "pub struct {};", type_name);
+        } else {
+            let _ = writeln!(code, "pub struct {} {{", type_name);
+            self.emit_field_declarations(code, "    pub ", constructor);
+            code.push_str("}\n");
+        }
+        // Parameters of empty types are unused
+        let unused = if fields.is_empty() && constructor.tag.len == 0 {
+            "_"
+        } else {
+            ""
+        };
+
+        let _ = write!(
+            code,
+            "\nimpl TlbSerialize for {} {{\n    fn store(&self, {}builder: &mut CellBuilder) -> Result<(), TonCellError> {{\n",
+            type_name, unused
+        );
+        if !fields.is_empty() {
+            let _ = writeln!(
+                code,
+                "        let {} {{ {} }} = self;",
+                type_name,
+                fields.join(", ")
+            );
+        }
+        self.emit_stores(code, 2, constructor);
+        code.push_str("        Ok(())\n    }\n}\n");
+
+        let _ = write!(
+            code,
+            "\nimpl TlbDeserialize for {} {{\n    fn load({}parser: &mut CellParser) -> Result<Self, TonCellError> {{\n",
+            type_name, unused
+        );
+        if constructor.tag.len > 0 {
+            let _ = write!(
+                code,
+                "        if {} != {} {{\n            return Err(TonCellError::unexpected_structure(\"{}\"));\n        }}\n",
+                load_tag(constructor.tag),
+                tag_literal(constructor.tag),
+                type_name
+            );
+        }
+        self.emit_loads(code, 2, constructor);
+        let _ = write!(
+            code,
+            "        Ok({})\n    }}\n}}\n",
+            construct(type_name, &fields)
+        );
+    }
+
+    fn emit_enum(&mut self, code: &mut String, type_name: &str, constructors: &[Constructor]) {
+        let _ = writeln!(code, "pub enum {} {{", type_name);
+        for constructor in constructors {
+            let variant = variant_name(&constructor.name);
+            if constructor.fields.is_empty() {
+                let _ = writeln!(code, "    {},", variant);
+            } else {
+                let _ = writeln!(code, "    {} {{", variant);
+                self.emit_field_declarations(code, "        ", constructor);
+                code.push_str("    },\n");
+            }
+        }
+        code.push_str("}\n");
+
+        let _ = write!(
+            code,
+            "\nimpl TlbSerialize for {} {{\n    fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {{\n        match self {{\n",
+            type_name
+        );
+        for constructor in constructors {
+            let path = format!("{}::{}", type_name, variant_name(&constructor.name));
+            let _ = writeln!(
+                code,
+                "            {} => {{",
+                construct(&path, &field_names(constructor))
+            );
+            self.emit_stores(code, 4, constructor);
+            code.push_str("            }\n");
+        }
+        code.push_str("        }\n        Ok(())\n    }\n}\n");
+
+        // Tags are prefix-free, so the constructor is found by reading the tag bit by bit
+        let max_len = constructors.iter().map(|c| c.tag.len).max().unwrap_or(0);
+        let _ = write!(
+            code,
+            "\nimpl TlbDeserialize for {} {{\n    fn load(parser: &mut CellParser) -> Result<Self, TonCellError> {{\n        let mut tag = 0u64;\n        let mut tag_len = 0;\n        loop {{\n            match (tag_len, tag) {{\n",
+            type_name
+        );
+        for constructor in constructors {
+            let path = format!("{}::{}", type_name, variant_name(&constructor.name));
+            let _ = writeln!(
+                code,
+                "                ({}, {}) => {{",
+                constructor.tag.len,
+                tag_literal(constructor.tag)
+            );
+            self.emit_loads(code, 5, constructor);
+            let _ = writeln!(
+                code,
+                "                    return Ok({});\n                }}",
+                construct(&path, &field_names(constructor))
+            );
+        }
+        let _ = write!(
+            code,
+            "                _ if tag_len >= {} => {{\n                    return Err(TonCellError::unexpected_structure(\"{}\"));\n                }}\n                _ => {{}}\n            }}\n            tag = (tag << 1) | u64::from(parser.load_bit()?);\n            tag_len += 1;\n        }}\n    }}\n}}\n",
+            max_len, type_name
+        );
+    }
+
+    fn emit_field_declarations(&mut self, code: &mut String, prefix: &str, c: &Constructor) {
+        for field in &c.fields {
+            let ty = self.rust_type(&field.ty);
+            let _ = writeln!(code, "{}{}: {},", prefix, field_ident(&field.name), ty);
+        }
+    }
+
+    fn emit_stores(&mut self, code: &mut String, indent: usize, constructor: &Constructor) {
+        let pad = "    ".repeat(indent);
+        if constructor.tag.len > 0 {
+            let _ = writeln!(code, "{}{}", pad, store_tag(constructor.tag));
+        }
+        for field in &constructor.fields {
+            let var = field_ident(&field.name);
+            self.emit_store(code, indent, &field.ty, &var);
+        }
+    }
+
+    /// Emits statements storing the variable of type `&T` to `builder`.
+    fn emit_store(&mut self, code: &mut String, indent: usize, ty: &FieldType, var: &str) {
+        let pad = "    ".repeat(indent);
+        let stmt = match ty {
+            FieldType::Bool => format!("builder.store_bit(*{})?;", var),
+            FieldType::Uint(n) => match uint_type(*n) {
+                "BigUint" => format!("builder.store_uint({}, {})?;", n, var),
+                ty => format!("builder.store_{}({}, *{})?;", ty, n, var),
+            },
+            FieldType::Int(n) => format!("builder.store_{}({}, *{})?;", int_type(*n), n, var),
+            FieldType::Bits(n) => format!("builder.store_bits({}, {})?;", n, var),
+            FieldType::Coins => format!("builder.store_coins({})?;", var),
+            FieldType::Address => format!("builder.store_address({})?;", var),
+            FieldType::Cell => unreachable!("cells outside of references are rejected"),
+            FieldType::Ref(inner) => match inner.as_ref() {
+                FieldType::Cell => format!("builder.store_reference({})?;", var),
+                FieldType::Named(_) => format!("builder.store_child({}.to_cell()?)?;", var),
+                inner => {
+                    let _ = writeln!(
+                        code,
+                        "{}let mut child = CellBuilder::new();\n{}{{\n{}    let builder = &mut child;",
+                        pad, pad, pad
+                    );
+                    self.emit_store(code, indent + 1, inner, var);
+                    let _ = writeln!(code, "{}}}", pad);
+                    "builder.store_child(child.build()?)?;".to_string()
+                }
+            },
+            FieldType::Maybe(inner) => {
+                let _ = writeln!(
+                    code,
+                    "{}match {} {{\n{}    Some({}) => {{\n{}        builder.store_bit(true)?;",
+                    pad, var, pad, var, pad
+                );
+                self.emit_store(code, indent + 2, inner, var);
+                let _ = writeln!(
+                    code,
+                    "{}    }}\n{}    None => {{\n{}        builder.store_bit(false)?;\n{}    }}\n{}}}",
+                    pad, pad, pad, pad, pad
+                );
+                return;
+            }
+            FieldType::Named(_) => format!("{}.store(builder)?;", var),
+        };
+        let _ = writeln!(code, "{}{}", pad, stmt);
+    }
+
+    fn emit_loads(&mut self, code: &mut String, indent: usize, constructor: &Constructor) {
+        let pad = "    ".repeat(indent);
+        for field in &constructor.fields {
+            let expr = self.load_expr(&field.ty, indent);
+            let _ = writeln!(code, "{}let {} = {};", pad, field_ident(&field.name), expr);
+        }
+    }
+
+    /// Returns the expression loading the value from `parser`.
+    fn load_expr(&mut self, ty: &FieldType, indent: usize) -> String {
+        let pad = "    ".repeat(indent);
+        match ty {
+            FieldType::Bool => "parser.load_bit()?".to_string(),
+            FieldType::Uint(n) => match uint_type(*n) {
+                "BigUint" => format!("parser.load_uint({})?", n),
+                ty => format!("parser.load_{}({})?", ty, n),
+            },
+            FieldType::Int(n) => {
+                let ty = int_type(*n);
+                // Loaded values are not sign-extended
+                let shift = ty[1..].parse::<usize>().unwrap() - n;
+                match shift {
+                    0 => format!("parser.load_{}({})?", ty, n),
+                    _ => format!("(parser.load_{}({})? << {}) >> {}", ty, n, shift, shift),
+                }
+            }
+            FieldType::Bits(n) => format!("parser.load_bits({})?", n),
+            FieldType::Coins => "parser.load_coins()?".to_string(),
+            FieldType::Address => "parser.load_address()?".to_string(),
+            FieldType::Cell => unreachable!("cells outside of references are rejected"),
+            FieldType::Ref(inner) => match inner.as_ref() {
+                FieldType::Cell => "parser.next_reference()?".to_string(),
+                FieldType::Named(name) => {
+                    format!("Box::new({}::from_cell(parser.next_reference()?.as_ref())?)", name)
+                }
+                inner => format!(
+                    "{{\n{}    let child = parser.next_reference()?;\n{}    let parser = &mut child.parser();\n{}    {}\n{}}}",
+                    pad,
+                    pad,
+                    pad,
+                    self.load_expr(inner, indent + 1),
+                    pad
+                ),
+            },
+            FieldType::Maybe(inner) => format!(
+                "if parser.load_bit()? {{\n{}    Some({})\n{}}} else {{\n{}    None\n{}}}",
+                pad,
+                self.load_expr(inner, indent + 1),
+                pad,
+                pad,
+                pad
+            ),
+            FieldType::Named(name) => format!("{}::load(parser)?", name),
+        }
+    }
+
+    fn rust_type(&mut self, ty: &FieldType) -> String {
+        match ty {
+            FieldType::Bool => "bool".to_string(),
+            FieldType::Uint(n) => self.use_type(uint_type(*n)),
+            FieldType::Int(n) => self.use_type(int_type(*n)),
+            FieldType::Bits(_) => "Vec<u8>".to_string(),
+            FieldType::Coins => self.use_type("Coins"),
+            FieldType::Address => self.use_type("TonAddress"),
+            FieldType::Cell => unreachable!("cells outside of references are rejected"),
+            FieldType::Ref(inner) => match inner.as_ref() {
+                FieldType::Cell => self.use_type("ArcCell"),
+                FieldType::Named(name) => format!("Box<{}>", name),
+                inner => self.rust_type(inner),
+            },
+            FieldType::Maybe(inner) => format!("Option<{}>", self.rust_type(inner)),
+            FieldType::Named(name) => name.clone(),
+        }
+    }
+
+    fn use_type(&mut self, ty: &'static str) -> String {
+        if matches!(ty, "BigUint" | "Coins" | "TonAddress" | "ArcCell") {
+            self.imports.insert(ty);
+        }
+        ty.to_string()
+    }
+}
+
+fn use_tree(items: &[&str]) -> String {
+    match items {
+        [item] => item.to_string(),
+        _ => format!("{{{}}}", items.join(", ")),
+    }
+}
+
+fn uint_type(n: usize) -> &'static str {
+    match n {
+        0..=8 => "u8",
+        9..=16 => "u16",
+        17..=32 => "u32",
+        33..=64 => "u64",
+        _ => "BigUint",
+    }
+}
+
+/// Returns the type of signed integers of at most 64 bits, with the sizes supported by both
+/// `CellBuilder` and `CellParser`.
+fn int_type(n: usize) -> &'static str {
+    match n {
+        0..=8 => "i8",
+        9..=32 => "i32",
+        _ => "i64",
+    }
+}
+
+fn store_tag(tag: Tag) -> String {
+    format!(
+        "builder.store_{}({}, {})?;",
+        uint_type(tag.len.max(1)),
+        tag.len,
+        tag_literal(tag)
+    )
+}
+
+fn load_tag(tag: Tag) -> String {
+    format!("parser.load_{}({})?", uint_type(tag.len.max(1)), tag.len)
+}
+
+fn tag_literal(tag: Tag) -> String {
+    if tag.len > 0 && tag.len.is_multiple_of(4) {
+        format!("0x{:0width$x}", tag.value, width = tag.len / 4)
+    } else {
+        format!("0b{:0width$b}", tag.value, width = tag.len.max(1))
+    }
+}
+
+fn field_names(constructor: &Constructor) -> Vec<String> {
+    constructor
+        .fields
+        .iter()
+        .map(|field| field_ident(&field.name))
+        .collect()
+}
+
+fn field_ident(name: &str) -> String {
+    if RUST_KEYWORDS.contains(&name) {
+        format!("r#{}", name)
+    } else if RESERVED_NAMES.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Converts a constructor name such as `addr_std` to `AddrStd`.
+fn variant_name(constructor: &str) -> String {
+    constructor
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            core::iter::once(first).chain(chars).collect::<String>()
+        })
+        .collect()
+}
+
+fn construct(path: &str, fields: &[String]) -> String {
+    if fields.is_empty() {
+        path.to_string()
+    } else {
+        format!("{} {{ {} }}", path, fields.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tlb_codegen::{TlbCodegen, TlbCodegenError};
+
+    #[test]
+    fn generate_reports_unsupported_declarations() -> anyhow::Result<()> {
+        let schema = "
+            // Types with implicit parameters are not supported
+            hm_edge#_ {n:#} {X:Type} {l:#} {m:#} label:(HmLabel ~l n) = Hashmap n X;
+            pair$_ a:uint8 b:(Maybe ^Cell) = Pair;
+            holder$_ pair:Pair dict:(Hashmap 8 Cell) = Holder;
+        ";
+        let error = TlbCodegen::new().generate(schema).unwrap_err();
+        assert!(matches!(error, TlbCodegenError::Unsupported { .. }));
+
+        let code = TlbCodegen::new()
+            .with_skip_unsupported(true)
+            .generate(schema)?;
+        assert!(code.contains("// * Hashmap: Unsupported declaration"));
+        assert!(code.contains("// * Holder: Unsupported declaration"));
+        assert!(code.contains("pub struct Pair {"));
+        assert!(!code.contains("pub struct Holder"));
+
+        let error = TlbCodegen::new()
+            .generate("a$0 = A; b$01 x:Unknown = A;")
+            .unwrap_err();
+        assert!(matches!(error, TlbCodegenError::UnknownType { .. }));
+        let error = TlbCodegen::new()
+            .generate("a$0 = A; b$01 = A;")
+            .unwrap_err();
+        assert!(matches!(error, TlbCodegenError::Unsupported { .. }));
+        let error = TlbCodegen::new().generate("a = A;").unwrap_err();
+        assert!(matches!(error, TlbCodegenError::Unsupported { .. }));
+        Ok(())
+    }
+}
@@ -0,0 +1,35 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum TlbCodegenError {
+    #[error("Invalid declaration `{declaration}`: {message}")]
+    Syntax {
+        declaration: String,
+        message: String,
+    },
+
+    #[error("Unsupported declaration `{declaration}`: {reason}")]
+    Unsupported { declaration: String, reason: String },
+
+    #[error("Unknown type {type_name} in declaration `{declaration}`")]
+    UnknownType {
+        declaration: String,
+        type_name: String,
+    },
+}
+
+impl TlbCodegenError {
+    pub(crate) fn syntax<M: ToString>(declaration: &str, message: M) -> TlbCodegenError {
+        TlbCodegenError::Syntax {
+            declaration: declaration.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    pub(crate) fn unsupported<R: ToString>(declaration: &str, reason: R) -> TlbCodegenError {
+        TlbCodegenError::Unsupported {
+            declaration: declaration.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
@@ -0,0 +1,375 @@
+use crate::tlb_codegen::TlbCodegenError;
+
+/// Types handled natively, whose declarations in the schema are ignored.
+pub(crate) const BUILTIN_TYPES: [&str; 11] = [
+    "Bool",
+    "Bit",
+    "Maybe",
+    "Grams",
+    "Coins",
+    "MsgAddress",
+    "MsgAddressInt",
+    "Cell",
+    "Any",
+    "VarUInteger",
+    "Unit",
+];
+
+/// Constructor of a TL-B type:
+///
+/// ```raw
+/// name#tag field:Type ... = TypeName;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Constructor {
+    pub(crate) name: String,
+    pub(crate) tag: Tag,
+    pub(crate) fields: Vec<Field>,
+    pub(crate) type_name: String,
+    /// Declaration with normalized whitespace.
+    pub(crate) declaration: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Tag {
+    pub(crate) value: u64,
+    pub(crate) len: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Field {
+    pub(crate) name: String,
+    pub(crate) ty: FieldType,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FieldType {
+    Bool,
+    Uint(usize),
+    Int(usize),
+    Bits(usize),
+    Coins,
+    Address,
+    /// `Cell` or `Any`, only supported in references.
+    Cell,
+    Ref(Box<FieldType>),
+    Maybe(Box<FieldType>),
+    Named(String),
+}
+
+impl FieldType {
+    /// Calls `f` with the names of the types of the schema used by the field.
+    pub(crate) fn visit_named<F: FnMut(&str)>(&self, f: &mut F) {
+        match self {
+            FieldType::Ref(inner) | FieldType::Maybe(inner) => inner.visit_named(f),
+            FieldType::Named(name) => f(name),
+            _ => {}
+        }
+    }
+
+    /// Checks that `Cell` is only used as `^Cell`.
+    fn cells_in_refs(&self) -> bool {
+        match self {
+            FieldType::Cell => false,
+            FieldType::Ref(inner) => **inner == FieldType::Cell || inner.cells_in_refs(),
+            FieldType::Maybe(inner) => inner.cells_in_refs(),
+            _ => true,
+        }
+    }
+}
+
+/// Removes the comments and splits the schema into declarations.
+pub(crate) fn split_declarations(schema: &str) -> Vec<String> {
+    let mut text = String::with_capacity(schema.len());
+    let mut rest = schema;
+    while !rest.is_empty() {
+        if let Some(comment) = rest.strip_prefix("//") {
+            rest = comment.find('\n').map_or("", |end| &comment[end..]);
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            rest = comment.find("*/").map_or("", |end| &comment[end + 2..]);
+            text.push(' ');
+        } else {
+            let c = rest.chars().next().unwrap();
+            text.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    text.split(';')
+        .map(|declaration| declaration.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|declaration| !declaration.is_empty())
+        .collect()
+}
+
+/// Parses a declaration returned by [`split_declarations`].
+pub(crate) fn parse_declaration(declaration: &str) -> Result<Constructor, TlbCodegenError> {
+    let tokens = tokenize(declaration);
+    let Some(eq) = tokens.iter().position(|t| t == "=") else {
+        return Err(TlbCodegenError::syntax(declaration, "missing `=`"));
+    };
+    let (lhs, rhs) = (&tokens[..eq], &tokens[eq + 1..]);
+    let type_name = match rhs {
+        [name] if is_identifier(name) => name.clone(),
+        [] => return Err(TlbCodegenError::syntax(declaration, "missing type name")),
+        _ => {
+            return Err(TlbCodegenError::unsupported(
+                declaration,
+                "parameterized types",
+            ))
+        }
+    };
+    let Some((spec, mut lhs)) = lhs.split_first() else {
+        return Err(TlbCodegenError::syntax(declaration, "missing constructor"));
+    };
+    let (name, tag) = parse_constructor_spec(declaration, spec)?;
+
+    let mut fields = Vec::new();
+    while let Some(token) = lhs.first() {
+        if token == "{" {
+            return Err(TlbCodegenError::unsupported(
+                declaration,
+                "implicit parameters and constraints",
+            ));
+        }
+        let name = match lhs {
+            [name, colon, ..] if colon == ":" => {
+                lhs = &lhs[2..];
+                name.clone()
+            }
+            _ => "_".to_string(),
+        };
+        let name = match name.as_str() {
+            "_" => format!("field_{}", fields.len()),
+            _ if is_identifier(&name) => name,
+            _ => {
+                return Err(TlbCodegenError::syntax(
+                    declaration,
+                    format!("invalid field name {}", name),
+                ))
+            }
+        };
+        let (ty, rest) = parse_term(declaration, lhs)?;
+        if !ty.cells_in_refs() {
+            return Err(TlbCodegenError::unsupported(
+                declaration,
+                "cells outside of references",
+            ));
+        }
+        lhs = rest;
+        fields.push(Field { name, ty });
+    }
+    Ok(Constructor {
+        name,
+        tag,
+        fields,
+        type_name,
+        declaration: declaration.to_string(),
+    })
+}
+
+fn tokenize(declaration: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    for c in declaration.chars() {
+        if c.is_whitespace() || "()^=:{}[]".contains(c) {
+            if !word.is_empty() {
+                tokens.push(std::mem::take(&mut word));
+            }
+            if !c.is_whitespace() {
+                tokens.push(c.to_string());
+            }
+        } else {
+            word.push(c);
+        }
+    }
+    if !word.is_empty() {
+        tokens.push(word);
+    }
+    tokens
+}
+
+/// Parses `name#hex`, `name$bits` or `_`.
+fn parse_constructor_spec(declaration: &str, spec: &str) -> Result<(String, Tag), TlbCodegenError> {
+    let empty = Tag { value: 0, len: 0 };
+    if spec == "_" {
+        return Ok(("_".to_string(), empty));
+    }
+    let (name, tag) = if let Some((name, hex)) = spec.split_once('#') {
+        let tag = match hex {
+            "_" => empty,
+            _ if hex.len() <= 16 => Tag {
+                value: u64::from_str_radix(hex, 16)
+                    .map_err(|_| TlbCodegenError::syntax(declaration, "invalid hex tag"))?,
+                len: hex.len() * 4,
+            },
+            _ => {
+                return Err(TlbCodegenError::unsupported(
+                    declaration,
+                    "tags over 64 bits",
+                ))
+            }
+        };
+        (name, tag)
+    } else if let Some((name, bits)) = spec.split_once('$') {
+        let tag = match bits {
+            "_" => empty,
+            _ if bits.len() <= 64 => Tag {
+                value: u64::from_str_radix(bits, 2)
+                    .map_err(|_| TlbCodegenError::syntax(declaration, "invalid binary tag"))?,
+                len: bits.len(),
+            },
+            _ => {
+                return Err(TlbCodegenError::unsupported(
+                    declaration,
+                    "tags over 64 bits",
+                ))
+            }
+        };
+        (name, tag)
+    } else {
+        return Err(TlbCodegenError::unsupported(
+            declaration,
+            "implicit CRC32 tags, the tag must be given with `#` or `$`",
+        ));
+    };
+    if !is_identifier(name) {
+        return Err(TlbCodegenError::syntax(
+            declaration,
+            format!("invalid constructor name {}", name),
+        ));
+    }
+    Ok((name.to_string(), tag))
+}
+
+/// Parses a type term, returning the remaining tokens.
+fn parse_term<'a>(
+    declaration: &str,
+    tokens: &'a [String],
+) -> Result<(FieldType, &'a [String]), TlbCodegenError> {
+    let Some((token, rest)) = tokens.split_first() else {
+        return Err(TlbCodegenError::syntax(declaration, "missing field type"));
+    };
+    match token.as_str() {
+        "^" => {
+            let (inner, rest) = parse_term(declaration, rest)?;
+            Ok((FieldType::Ref(Box::new(inner)), rest))
+        }
+        "(" => {
+            let Some(close) = matching_paren(rest) else {
+                return Err(TlbCodegenError::syntax(
+                    declaration,
+                    "unbalanced parentheses",
+                ));
+            };
+            let ty = parse_expression(declaration, &rest[..close])?;
+            Ok((ty, &rest[close + 1..]))
+        }
+        "[" => Err(TlbCodegenError::unsupported(declaration, "anonymous cells")),
+        "{" => Err(TlbCodegenError::unsupported(
+            declaration,
+            "implicit parameters and constraints",
+        )),
+        word => Ok((parse_type_name(declaration, word)?, rest)),
+    }
+}
+
+/// Parses the contents of parentheses.
+fn parse_expression(declaration: &str, tokens: &[String]) -> Result<FieldType, TlbCodegenError> {
+    let words: Vec<&str> = tokens.iter().map(String::as_str).collect();
+    let ty = match words.as_slice() {
+        ["##", n] | ["uint", n] => FieldType::Uint(parse_len(declaration, n)?),
+        ["int", n] => parse_int(declaration, n)?,
+        ["bits", n] => FieldType::Bits(parse_len(declaration, n)?),
+        ["VarUInteger", "16"] => FieldType::Coins,
+        ["Maybe", ..] => {
+            let (inner, rest) = parse_term(declaration, &tokens[1..])?;
+            if !rest.is_empty() {
+                return Err(TlbCodegenError::syntax(
+                    declaration,
+                    "unexpected tokens after Maybe",
+                ));
+            }
+            FieldType::Maybe(Box::new(inner))
+        }
+        _ => {
+            let (ty, rest) = parse_term(declaration, tokens)?;
+            if !rest.is_empty() {
+                return Err(TlbCodegenError::unsupported(
+                    declaration,
+                    format!("type expression ({})", words.join(" ")),
+                ));
+            }
+            ty
+        }
+    };
+    Ok(ty)
+}
+
+fn parse_type_name(declaration: &str, word: &str) -> Result<FieldType, TlbCodegenError> {
+    let sized = |prefix: &str| {
+        word.strip_prefix(prefix)
+            .filter(|n| !n.is_empty() && n.chars().all(|c| c.is_ascii_digit()))
+    };
+    let ty = match word {
+        "Bool" | "Bit" => FieldType::Bool,
+        "#" => FieldType::Uint(32),
+        "Grams" | "Coins" => FieldType::Coins,
+        "MsgAddress" | "MsgAddressInt" => FieldType::Address,
+        "Cell" | "Any" => FieldType::Cell,
+        _ => {
+            if let Some(n) = sized("uint") {
+                FieldType::Uint(parse_len(declaration, n)?)
+            } else if let Some(n) = sized("int") {
+                parse_int(declaration, n)?
+            } else if let Some(n) = sized("bits") {
+                FieldType::Bits(parse_len(declaration, n)?)
+            } else if is_identifier(word) && word.starts_with(|c: char| c.is_ascii_uppercase()) {
+                FieldType::Named(word.to_string())
+            } else {
+                return Err(TlbCodegenError::unsupported(
+                    declaration,
+                    format!("type {}", word),
+                ));
+            }
+        }
+    };
+    Ok(ty)
+}
+
+fn parse_len(declaration: &str, n: &str) -> Result<usize, TlbCodegenError> {
+    match n.parse::<usize>() {
+        Ok(n) if n <= 1023 => Ok(n),
+        _ => Err(TlbCodegenError::unsupported(
+            declaration,
+            format!("length {}, only constant lengths are supported", n),
+        )),
+    }
+}
+
+/// Parses the length of signed integers, which are stored as `i8`, `i32` or `i64`.
+fn parse_int(declaration: &str, n: &str) -> Result<FieldType, TlbCodegenError> {
+    match parse_len(declaration, n)? {
+        n @ 1..=64 => Ok(FieldType::Int(n)),
+        n => Err(TlbCodegenError::unsupported(
+            declaration,
+            format!("signed integers of {} bits", n),
+        )),
+    }
+}
+
+fn matching_paren(tokens: &[String]) -> Option<usize> {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate() {
+        match token.as_str() {
+            "(" => depth += 1,
+            ")" if depth == 0 => return Some(i),
+            ")" => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn is_identifier(s: &str) -> bool {
+    s.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
@@ -0,0 +1,59 @@
+//! Diagnostics of cell parsing.
+//!
+//! With the `tracing` feature enabled, parser events are emitted as `tracing` events with
+//! structured fields, and loads of referenced cells are wrapped in spans carrying the
+//! reference index. Without it, events are written with `log` at trace level, with the fields
+//! appended to the message.
+//!
+//! Data dumps are emitted at trace level only. Parsing failures are reported at debug level
+//! together with the hash of the failing cell and the bit offset of the parser.
+
+use crate::cell::{Cell, TonCellError};
+
+/// Emits a parser event with the given fields.
+macro_rules! parse_event {
+    ($msg:literal $(, $key:ident = $value:expr)* $(,)?) => {{
+        #[cfg(feature = "tracing")]
+        tracing::trace!($($key = ?$value,)* $msg);
+        #[cfg(not(feature = "tracing"))]
+        log::trace!(concat!($msg $(, " ", stringify!($key), "={:?}")*) $(, $value)*);
+    }};
+}
+
+/// Enters a span with the given fields, which is exited when the returned guard is dropped.
+macro_rules! parse_span {
+    ($name:literal $(, $key:ident = $value:expr)* $(,)?) => {{
+        #[cfg(feature = "tracing")]
+        let span = tracing::trace_span!($name $(, $key = ?$value)*).entered();
+        #[cfg(not(feature = "tracing"))]
+        let span = $crate::trace::ParseSpan;
+        span
+    }};
+}
+
+pub(crate) use {parse_event, parse_span};
+
+/// Guard returned by [`parse_span`] when the `tracing` feature is disabled.
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct ParseSpan;
+
+/// Reports a failure to parse the cell at the given reference index.
+pub(crate) fn parse_failed(cell: &Cell, ref_index: usize, bit_offset: usize, error: &TonCellError) {
+    // Hashing the cell is skipped unless the event is emitted
+    #[cfg(feature = "tracing")]
+    if tracing::enabled!(tracing::Level::DEBUG) {
+        let cell_hash = cell.cell_hash().map(hex::encode).unwrap_or_default();
+        tracing::debug!(cell_hash, ref_index, bit_offset, %error, "failed to parse cell");
+    }
+    #[cfg(not(feature = "tracing"))]
+    if log::log_enabled!(log::Level::Debug) {
+        let cell_hash = cell.cell_hash().map(hex::encode).unwrap_or_default();
+        log::debug!(
+            "failed to parse cell: {} cell_hash={} ref_index={} bit_offset={}",
+            error,
+            cell_hash,
+            ref_index,
+            bit_offset
+        );
+    }
+}
@@ -112,7 +112,7 @@ impl TvmStackEntry {
         match self {
             TvmStackEntry::Slice(slice) => {
                 let data = &slice.cell.data;
-                let value = String::from_utf8(data.clone())?;
+                let value = String::from_utf8(data.to_vec())?;
                 Ok(value)
             }
 
@@ -197,7 +197,7 @@ impl TryFrom<&String> for TvmStackEntry {
         };
         // todo: support reference and snake format
         let cell = Cell {
-            data: bytes.clone(),
+            data: bytes.as_slice().into(),
             bit_len,
             references: vec![],
             cell_type: bytes[0], // first byte is cell type
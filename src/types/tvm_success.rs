@@ -1,7 +1,8 @@
-use crate::cell::ArcCell;
+use crate::cell::{ArcCell, TonCellError};
+use crate::message::{parse_out_list, OutAction, SentMessage};
 use crate::types::TvmStackEntry;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TvmSuccess {
     pub vm_log: Option<String>,
     pub vm_exit_code: i32,
@@ -33,3 +34,21 @@ pub struct TvmMsgSuccess {
     pub gas_used: i32,
     pub actions: Option<ArcCell>,
 }
+
+impl TvmMsgSuccess {
+    /// Returns the actions emitted by the contract, in the order they are performed.
+    pub fn out_actions(&self) -> Result<Vec<OutAction>, TonCellError> {
+        match &self.actions {
+            Some(actions) => parse_out_list(actions),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Returns the messages sent by the contract with their modes, in the order they are sent.
+    pub fn sent_messages(&self) -> Result<Vec<SentMessage>, TonCellError> {
+        match &self.actions {
+            Some(actions) => SentMessage::parse_out_list(actions),
+            None => Ok(vec![]),
+        }
+    }
+}
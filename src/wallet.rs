@@ -1,20 +1,39 @@
+#[cfg(all(feature = "tonlibjson", not(target_arch = "wasm32")))]
+mod batch;
+#[cfg(all(feature = "tonlibjson", not(target_arch = "wasm32")))]
+mod estimate;
+mod expiration;
+mod highload;
 mod types;
+mod w5;
 
+use std::collections::HashMap;
 use std::sync::Arc;
 
+#[cfg(all(feature = "tonlibjson", not(target_arch = "wasm32")))]
+pub use batch::*;
+#[cfg(all(feature = "tonlibjson", not(target_arch = "wasm32")))]
+pub use estimate::*;
+pub use expiration::*;
+pub use highload::*;
 use lazy_static::lazy_static;
-use nacl::sign::signature;
 pub use types::*;
+pub use w5::*;
 
 use crate::address::TonAddress;
 use crate::cell::{
     ArcCell, BagOfCells, Cell, CellBuilder, StateInit, StateInitBuilder, TonCellError,
 };
-use crate::message::{TonMessageError, ZERO_COINS};
-use crate::mnemonic::KeyPair;
+use crate::coins::Coins;
+use crate::crypto::KeyPair;
+use crate::message::{SendMode, TonMessageError};
+use crate::network::Network;
 
 pub const DEFAULT_WALLET_ID: i32 = 0x29a9a317;
 
+/// Send mode of the internal messages of wallets: pay fees separately, ignore errors.
+pub const DEFAULT_SEND_MODE: SendMode = SendMode::PAY_GAS_SEPARATELY.union(SendMode::IGNORE_ERRORS);
+
 lazy_static! {
     pub static ref WALLET_V1R1_CODE: BagOfCells = {
         let code = include_str!("../resources/wallet/wallet_v1r1.code");
@@ -74,7 +93,31 @@ lazy_static! {
     };
 }
 
-#[derive(PartialEq, Eq, Clone, Hash)]
+const WALLET_VERSIONS: [WalletVersion; 14] = [
+    WalletVersion::V1R1,
+    WalletVersion::V1R2,
+    WalletVersion::V1R3,
+    WalletVersion::V2R1,
+    WalletVersion::V2R2,
+    WalletVersion::V3R1,
+    WalletVersion::V3R2,
+    WalletVersion::V4R1,
+    WalletVersion::V4R2,
+    WalletVersion::HighloadV1R1,
+    WalletVersion::HighloadV1R2,
+    WalletVersion::HighloadV2,
+    WalletVersion::HighloadV2R1,
+    WalletVersion::HighloadV2R2,
+];
+
+lazy_static! {
+    static ref WALLET_VERSIONS_BY_CODE_HASH: HashMap<Vec<u8>, WalletVersion> = WALLET_VERSIONS
+        .into_iter()
+        .map(|version| (version.code().unwrap().cell_hash().unwrap(), version))
+        .collect();
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub enum WalletVersion {
     V1R1,
     V1R2,
@@ -113,6 +156,12 @@ impl WalletVersion {
         code.single_root()
     }
 
+    /// Returns the version whose code has the same hash as `code`.
+    pub fn from_code(code: &Cell) -> Result<Option<WalletVersion>, TonCellError> {
+        let code_hash = code.cell_hash()?;
+        Ok(WALLET_VERSIONS_BY_CODE_HASH.get(&code_hash).cloned())
+    }
+
     pub fn initial_data(
         &self,
         key_pair: &KeyPair,
@@ -165,6 +214,18 @@ impl WalletVersion {
         Ok(Arc::new(data_cell))
     }
 
+    /// Returns true if the wallet data of this version contains `wallet_id` (subwallet id).
+    pub fn has_wallet_id(&self) -> bool {
+        !matches!(
+            self,
+            WalletVersion::V1R1
+                | WalletVersion::V1R2
+                | WalletVersion::V1R3
+                | WalletVersion::V2R1
+                | WalletVersion::V2R2
+        )
+    }
+
     pub fn has_op(&self) -> bool {
         matches!(self, WalletVersion::V4R2)
     }
@@ -176,15 +237,26 @@ pub struct TonWallet {
     pub version: WalletVersion,
     pub address: TonAddress,
     pub wallet_id: i32,
+    pub network: Network,
 }
 
 impl TonWallet {
+    /// Derives the wallet of the key pair with the given wallet id.
+    ///
+    /// The versions without a wallet id, i.e. v1 and v2, only accept [`DEFAULT_WALLET_ID`].
     pub fn derive(
+        network: Network,
         workchain: i32,
         version: WalletVersion,
         key_pair: &KeyPair,
         wallet_id: i32,
     ) -> Result<TonWallet, TonCellError> {
+        if !version.has_wallet_id() && wallet_id != DEFAULT_WALLET_ID {
+            return Err(TonCellError::InternalError(format!(
+                "Wallet id {} is not supported by wallet version {:?}",
+                wallet_id, version
+            )));
+        }
         let data = version.initial_data(key_pair, wallet_id)?;
         let code = version.code()?;
         let state_init_hash = StateInit::create_account_id(code, &data)?;
@@ -202,63 +274,91 @@ impl TonWallet {
             version,
             address: addr,
             wallet_id,
+            network,
         })
     }
 
+    /// Derives the wallet with the default wallet id of the given workchain
+    /// increased by `subwallet`.
+    ///
+    /// The versions without a wallet id only have the subwallet 0, i.e. the wallet with
+    /// [`DEFAULT_WALLET_ID`] in any workchain.
+    pub fn derive_subwallet(
+        network: Network,
+        workchain: i32,
+        version: WalletVersion,
+        key_pair: &KeyPair,
+        subwallet: u32,
+    ) -> Result<TonWallet, TonCellError> {
+        if !version.has_wallet_id() && subwallet == 0 {
+            return Self::derive(network, workchain, version, key_pair, DEFAULT_WALLET_ID);
+        }
+        let wallet_id = DEFAULT_WALLET_ID
+            .checked_add(workchain)
+            .and_then(|id| id.checked_add_unsigned(subwallet))
+            .ok_or_else(|| {
+                TonCellError::InternalError(format!("Invalid subwallet: {}", subwallet))
+            })?;
+        Self::derive(network, workchain, version, key_pair, wallet_id)
+    }
+
     pub fn derive_default(
+        network: Network,
         version: WalletVersion,
         key_pair: &KeyPair,
     ) -> Result<TonWallet, TonCellError> {
-        let wallet_id = DEFAULT_WALLET_ID;
-        let data = version.initial_data(key_pair, wallet_id)?;
-        let code = version.code()?;
-        let state_init_hash = StateInit::create_account_id(code, &data)?;
-        let hash_part = match state_init_hash.as_slice().try_into() {
-            Ok(hash_part) => hash_part,
-            Err(_) => {
-                return Err(TonCellError::InternalError(
-                    "StateInit returned hash pof wrong size".to_string(),
-                ))
-            }
-        };
-        let addr = TonAddress::new(0, &hash_part);
-        Ok(TonWallet {
-            key_pair: key_pair.clone(),
-            version,
-            address: addr,
-            wallet_id,
-        })
+        Self::derive(network, 0, version, key_pair, DEFAULT_WALLET_ID)
+    }
+
+    /// Formats the address of the wallet for its network, setting the test-only flag on
+    /// other networks than the mainnet.
+    pub fn to_base64_url(&self, non_bounceable: bool) -> String {
+        self.address
+            .to_base64_url_flags(non_bounceable, self.network.is_test_only())
     }
 
     pub fn create_external_message<T: AsRef<[ArcCell]>>(
         &self,
-        expire_at: u32,
+        expiration: Expiration,
         seqno: u32,
         internal_messages: T,
         state_init: bool,
     ) -> Result<Cell, TonMessageError> {
-        let body = self.create_external_body(expire_at, seqno, internal_messages)?;
+        let body = self.create_external_body(expiration, seqno, internal_messages)?;
         let signed = self.sign_external_body(&body)?;
         let wrapped = self.wrap_signed_body(signed, state_init)?;
         Ok(wrapped)
     }
 
+    /// Builds the unsigned body of the external message, with `valid_until` of the expiration
+    /// for the versions supporting it, i.e. all but v1.
     pub fn create_external_body<T: AsRef<[ArcCell]>>(
         &self,
-        expire_at: u32,
+        expiration: Expiration,
         seqno: u32,
         internal_messages: T,
     ) -> Result<Cell, TonCellError> {
+        let expire_at = expiration.valid_until;
         let mut builder = CellBuilder::new();
-        builder
-            .store_i32(32, self.wallet_id)?
-            .store_u32(32, expire_at)?
-            .store_u32(32, seqno)?;
+        match self.version {
+            WalletVersion::V1R1 | WalletVersion::V1R2 | WalletVersion::V1R3 => {
+                builder.store_u32(32, seqno)?;
+            }
+            WalletVersion::V2R1 | WalletVersion::V2R2 => {
+                builder.store_u32(32, seqno)?.store_u32(32, expire_at)?;
+            }
+            _ => {
+                builder
+                    .store_i32(32, self.wallet_id)?
+                    .store_u32(32, expire_at)?
+                    .store_u32(32, seqno)?;
+            }
+        }
         if self.version.has_op() {
             builder.store_u8(8, 0)?;
         }
         for internal_message in internal_messages.as_ref() {
-            builder.store_u8(8, 3)?; // send_mode
+            builder.store_u8(8, DEFAULT_SEND_MODE.bits())?;
             builder.store_reference(internal_message)?;
         }
         builder.build()
@@ -266,8 +366,7 @@ impl TonWallet {
 
     pub fn sign_external_body(&self, external_body: &Cell) -> Result<Cell, TonMessageError> {
         let message_hash = external_body.cell_hash()?;
-        let sig = signature(message_hash.as_slice(), self.key_pair.secret_key.as_slice())
-            .map_err(|e| TonMessageError::NaclCryptographicError(e.message))?;
+        let sig = self.key_pair.sign(message_hash.as_slice())?;
         let mut body_builder = CellBuilder::new();
         body_builder.store_slice(sig.as_slice())?;
         body_builder.store_cell(external_body)?;
@@ -284,12 +383,12 @@ impl TonWallet {
             .store_u8(2, 2)? // No idea
             .store_address(&TonAddress::NULL)? // src
             .store_address(&self.address)? // dest
-            .store_coins(&ZERO_COINS)?; // import fee
+            .store_coins(Coins::zero())?; // import fee
         if state_init {
             wrap_builder.store_bit(true)?; // state init present
             wrap_builder.store_bit(true)?; // state init in ref
             let initial_data = self.version.initial_data(&self.key_pair, self.wallet_id)?;
-            let code = WALLET_V4R2_CODE.single_root()?.clone();
+            let code = self.version.code()?.clone();
             let state_init = StateInitBuilder::new(&code, &initial_data).build()?;
             wrap_builder.store_child(state_init)?;
         } else {
@@ -301,30 +400,139 @@ impl TonWallet {
     }
 }
 
+/// Key pair of the mnemonic shared by the tests of wallets and messages.
+#[cfg(test)]
+pub(crate) fn test_key_pair() -> KeyPair {
+    let mnemonic_str = "fancy carpet hello mandate penalty trial consider \
+    property top vicious exit rebuild tragic profit urban major total month holiday \
+    sudden rib gather media vicious";
+    let mnemonic = crate::mnemonic::Mnemonic::from_str(mnemonic_str, &None).unwrap();
+    mnemonic.to_key_pair().unwrap()
+}
+
+/// Mainnet wallet of [`test_key_pair`] with the default wallet id.
+#[cfg(test)]
+pub(crate) fn test_wallet(version: WalletVersion) -> TonWallet {
+    TonWallet::derive_default(Network::Mainnet, version, &test_key_pair()).unwrap()
+}
+
 #[cfg(test)]
 mod tests {
+    use std::sync::Arc;
+
     use crate::address::TonAddress;
-    use crate::mnemonic::Mnemonic;
-    use crate::wallet::{TonWallet, WalletVersion};
+    use crate::cell::CellBuilder;
+    use crate::network::Network;
+    use crate::wallet::{
+        test_key_pair, test_wallet, Expiration, TonWallet, WalletVersion, DEFAULT_MESSAGE_TTL,
+        DEFAULT_WALLET_ID,
+    };
 
     #[test]
     fn derive_wallet_works() -> anyhow::Result<()> {
-        let mnemonic_str = "fancy carpet hello mandate penalty trial consider \
-        property top vicious exit rebuild tragic profit urban major total month holiday \
-        sudden rib gather media vicious";
-        let mnemonic = Mnemonic::from_str(mnemonic_str, &None)?;
-        let key_pair = mnemonic.to_key_pair()?;
-        let wallet_v3 = TonWallet::derive_default(WalletVersion::V3R1, &key_pair)?;
+        let key_pair = test_key_pair();
+        let wallet_v3 =
+            TonWallet::derive_default(Network::Mainnet, WalletVersion::V3R1, &key_pair)?;
         let expected_v3: TonAddress = "EQBiMfDMivebQb052Z6yR3jHrmwNhw1kQ5bcAUOBYsK_VPuK".parse()?;
         assert_eq!(wallet_v3.address, expected_v3);
-        let wallet_v3r2 = TonWallet::derive_default(WalletVersion::V3R2, &key_pair)?;
+        let wallet_v3r2 =
+            TonWallet::derive_default(Network::Mainnet, WalletVersion::V3R2, &key_pair)?;
         let expected_v3r2: TonAddress =
             "EQA-RswW9QONn88ziVm4UKnwXDEot5km7GEEXsfie_0TFOCO".parse()?;
         assert_eq!(wallet_v3r2.address, expected_v3r2);
-        let wallet_v4r2 = TonWallet::derive_default(WalletVersion::V4R2, &key_pair)?;
+        let wallet_v4r2 =
+            TonWallet::derive_default(Network::Mainnet, WalletVersion::V4R2, &key_pair)?;
         let expected_v4r2: TonAddress =
             "EQCDM_QGggZ3qMa_f3lRPk4_qLDnLTqdi6OkMAV2NB9r5TG3".parse()?;
         assert_eq!(wallet_v4r2.address, expected_v4r2);
+        assert_eq!(
+            wallet_v4r2.to_base64_url(false),
+            expected_v4r2.to_base64_url()
+        );
+
+        let testnet_v4r2 =
+            TonWallet::derive_default(Network::Testnet, WalletVersion::V4R2, &key_pair)?;
+        assert_eq!(testnet_v4r2.address, expected_v4r2);
+        assert_eq!(
+            testnet_v4r2.to_base64_url(true),
+            expected_v4r2.to_base64_url_flags(true, true)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn derive_subwallet_works() -> anyhow::Result<()> {
+        let key_pair = test_key_pair();
+        let default = test_wallet(WalletVersion::V4R2);
+        let subwallet =
+            TonWallet::derive_subwallet(Network::Mainnet, 0, WalletVersion::V4R2, &key_pair, 0)?;
+        assert_eq!(subwallet.address, default.address);
+        let subwallet =
+            TonWallet::derive_subwallet(Network::Mainnet, 0, WalletVersion::V4R2, &key_pair, 1)?;
+        assert_eq!(subwallet.wallet_id, DEFAULT_WALLET_ID + 1);
+        assert_ne!(subwallet.address, default.address);
+        let masterchain =
+            TonWallet::derive_subwallet(Network::Mainnet, -1, WalletVersion::V3R2, &key_pair, 0)?;
+        assert_eq!(masterchain.wallet_id, DEFAULT_WALLET_ID - 1);
+        assert_eq!(masterchain.address.workchain, -1);
+        let v2 = TonWallet::derive(
+            Network::Mainnet,
+            0,
+            WalletVersion::V2R2,
+            &key_pair,
+            DEFAULT_WALLET_ID,
+        )?;
+        let default_v2 = test_wallet(WalletVersion::V2R2);
+        assert_eq!(v2.wallet_id, DEFAULT_WALLET_ID);
+        assert_eq!(v2.address, default_v2.address);
+        assert!(TonWallet::derive(Network::Mainnet, 0, WalletVersion::V2R2, &key_pair, 1).is_err());
+        assert!(TonWallet::derive(Network::Mainnet, 0, WalletVersion::V1R3, &key_pair, 1).is_err());
+        let v1_masterchain =
+            TonWallet::derive_subwallet(Network::Mainnet, -1, WalletVersion::V1R3, &key_pair, 0)?;
+        assert_eq!(v1_masterchain.wallet_id, DEFAULT_WALLET_ID);
+        assert!(TonWallet::derive_subwallet(
+            Network::Mainnet,
+            0,
+            WalletVersion::V1R3,
+            &key_pair,
+            1
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn legacy_wallet_external_body_works() -> anyhow::Result<()> {
+        let msg = Arc::new(CellBuilder::new().store_u32(32, 1)?.build()?);
+        let expiration = Expiration::new(1_700_000_000, DEFAULT_MESSAGE_TTL);
+
+        let wallet_v1r3 = test_wallet(WalletVersion::V1R3);
+        let body = wallet_v1r3.create_external_body(expiration, 7, [msg.clone()])?;
+        let mut parser = body.parser();
+        assert_eq!(parser.load_u32(32)?, 7);
+        assert_eq!(parser.load_u8(8)?, 3);
+        assert_eq!(parser.remaining_bits(), 0);
+        assert_eq!(body.references.len(), 1);
+
+        let wallet_v2r2 = test_wallet(WalletVersion::V2R2);
+        let body = wallet_v2r2.create_external_body(expiration, 7, [msg])?;
+        let mut parser = body.parser();
+        assert_eq!(parser.load_u32(32)?, 7);
+        assert_eq!(parser.load_u32(32)?, expiration.valid_until);
+        assert_eq!(parser.load_u8(8)?, 3);
+        assert_eq!(parser.remaining_bits(), 0);
+
+        let signed = wallet_v2r2.sign_external_body(&body)?;
+        let external = wallet_v2r2.wrap_signed_body(signed, true)?;
+        let state_init = external.reference(0)?;
+        assert_eq!(
+            state_init.reference(0)?.cell_hash()?,
+            WalletVersion::V2R2.code()?.cell_hash()?
+        );
+        assert_eq!(
+            state_init.cell_hash()?,
+            wallet_v2r2.address.hash_part.to_vec()
+        );
         Ok(())
     }
 }
@@ -0,0 +1,423 @@
+use std::ops::Range;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::stream::{self, StreamExt};
+use num_bigint::BigUint;
+use tokio::time;
+
+use crate::cell::{ArcCell, BagOfCells, Cell, CellBuilder, DictBuilder, TonCellError};
+use crate::client::TonClientInterface;
+use crate::contract::{MapCellError, TonContractError};
+use crate::message::TonMessageError;
+use crate::wallet::{
+    highload_query_id, Expiration, ExternalMessageStatus, PendingExternal, TonWallet,
+    WalletVersion, DEFAULT_CLOCK_SKEW, DEFAULT_MESSAGE_TTL, DEFAULT_SEND_MODE,
+};
+
+/// Maximum number of internal messages in an external message of a highload wallet.
+pub const HIGHLOAD_MAX_MESSAGES: usize = 254;
+
+/// Maximum number of internal messages in an external message of a regular wallet.
+pub const WALLET_MAX_MESSAGES: usize = 4;
+
+const DEFAULT_BATCH_CONCURRENCY: usize = 8;
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+impl WalletVersion {
+    /// Returns true for highload wallets v2, which use query ids instead of seqno.
+    pub fn has_query_id(&self) -> bool {
+        matches!(
+            self,
+            WalletVersion::HighloadV2 | WalletVersion::HighloadV2R1 | WalletVersion::HighloadV2R2
+        )
+    }
+
+    /// Returns the maximum number of internal messages in a single external message.
+    pub fn max_messages(&self) -> usize {
+        if self.has_query_id() {
+            HIGHLOAD_MAX_MESSAGES
+        } else {
+            WALLET_MAX_MESSAGES
+        }
+    }
+}
+
+impl TonWallet {
+    /// Creates the unsigned body of a highload wallet v2 external message:
+    ///
+    /// ```raw
+    /// subwallet_id:uint32 query_id:uint64 msgs:(HashmapE 16 (uint8, ^Cell))
+    /// ```
+    pub fn create_highload_body<T: AsRef<[ArcCell]>>(
+        &self,
+        query_id: u64,
+        internal_messages: T,
+    ) -> Result<Cell, TonCellError> {
+        if !self.version.has_query_id() {
+            return Err(TonCellError::InternalError(
+                "Query ids are only supported by highload wallets v2".to_string(),
+            ));
+        }
+        let internal_messages = internal_messages.as_ref();
+        if internal_messages.len() > HIGHLOAD_MAX_MESSAGES {
+            return Err(TonCellError::InternalError(format!(
+                "Highload wallet can send at most {} messages at once, got {}",
+                HIGHLOAD_MAX_MESSAGES,
+                internal_messages.len()
+            )));
+        }
+        let mut dict = DictBuilder::new(16);
+        for (index, internal_message) in internal_messages.iter().enumerate() {
+            let value = CellBuilder::new()
+                .store_u8(8, DEFAULT_SEND_MODE.bits())?
+                .store_reference(internal_message)?
+                .build()?;
+            dict.insert(BigUint::from(index), value)?;
+        }
+        let mut builder = CellBuilder::new();
+        builder
+            .store_i32(32, self.wallet_id)?
+            .store_u64(64, query_id)?;
+        match dict.build()? {
+            Some(root) => builder.store_bit(true)?.store_child(root)?,
+            None => builder.store_bit(false)?,
+        };
+        builder.build()
+    }
+
+    /// Creates a signed highload wallet v2 external message.
+    pub fn create_highload_message<T: AsRef<[ArcCell]>>(
+        &self,
+        query_id: u64,
+        internal_messages: T,
+        state_init: bool,
+    ) -> Result<Cell, TonMessageError> {
+        let body = self.create_highload_body(query_id, internal_messages)?;
+        let signed = self.sign_external_body(&body)?;
+        self.wrap_signed_body(signed, state_init)
+    }
+}
+
+/// Splits `count` requests into consecutive batches of at most `max_messages` requests.
+pub fn plan_batches(count: usize, max_messages: usize) -> Vec<Range<usize>> {
+    let max_messages = max_messages.max(1);
+    (0..count)
+        .step_by(max_messages)
+        .map(|start| start..(start + max_messages).min(count))
+        .collect()
+}
+
+/// External message carrying a batch of requests.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SentBatch {
+    /// Indices of the requests in the batch.
+    pub requests: Range<usize>,
+    pub expiration: Expiration,
+    /// Query id of highload wallets, `None` for wallets using seqno.
+    pub query_id: Option<u64>,
+    /// Seqno of regular wallets, `None` for highload wallets.
+    pub seqno: Option<u32>,
+}
+
+#[derive(Clone, Debug)]
+pub enum BatchRequestOutcome {
+    /// The batch of the request was sent. Batches of highload wallets are reported once
+    /// they are accepted by the liteserver, batches of regular wallets once the seqno
+    /// of the wallet has moved past the seqno of the batch.
+    Sent { batch: usize, message_hash: Vec<u8> },
+    Failed {
+        batch: usize,
+        error: Arc<TonContractError>,
+    },
+    /// The request was not sent, because an earlier batch of the regular wallet failed.
+    Skipped,
+}
+
+impl BatchRequestOutcome {
+    pub fn is_sent(&self) -> bool {
+        matches!(self, BatchRequestOutcome::Sent { .. })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BatchReport {
+    pub batches: Vec<SentBatch>,
+    /// Outcomes in the order of the requests.
+    pub outcomes: Vec<BatchRequestOutcome>,
+}
+
+/// Sends large numbers of internal messages, e.g. withdrawals of an exchange, from a
+/// deployed wallet.
+///
+/// Highload wallets v2 send up to [`HIGHLOAD_MAX_MESSAGES`] messages per external message
+/// with unique query ids, submitting up to `concurrency` external messages at once.
+/// Other wallets send up to [`WALLET_MAX_MESSAGES`] messages per external message, one
+/// external message at a time, waiting for each seqno to be applied before sending the next.
+pub struct BatchSender<C: TonClientInterface + Clone> {
+    client: C,
+    wallet: TonWallet,
+    ttl: u32,
+    clock_skew: u32,
+    concurrency: usize,
+    poll_interval: Duration,
+    query_sequence: AtomicU32,
+}
+
+impl<C: TonClientInterface + Clone> BatchSender<C> {
+    pub fn new(client: &C, wallet: &TonWallet) -> BatchSender<C> {
+        BatchSender {
+            client: client.clone(),
+            wallet: wallet.clone(),
+            ttl: DEFAULT_MESSAGE_TTL,
+            clock_skew: DEFAULT_CLOCK_SKEW,
+            concurrency: DEFAULT_BATCH_CONCURRENCY,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            query_sequence: AtomicU32::new(0),
+        }
+    }
+
+    /// Sets the lifetime of the external messages, in seconds.
+    pub fn with_ttl(mut self, ttl: u32) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Sets the tolerance of the clock used to tell that the external messages of regular
+    /// wallets expired, see [`Expiration::clock_skew`].
+    pub fn with_clock_skew(mut self, clock_skew: u32) -> Self {
+        self.clock_skew = clock_skew;
+        self
+    }
+
+    /// Sets the number of external messages of highload wallets submitted at once.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets the interval of polling the seqno of regular wallets.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Sends the internal messages and reports the outcome of each of them.
+    pub async fn send(&self, internal_messages: &[ArcCell]) -> BatchReport {
+        let ranges = plan_batches(internal_messages.len(), self.wallet.version.max_messages());
+        if self.wallet.version.has_query_id() {
+            self.send_highload(internal_messages, ranges).await
+        } else {
+            self.send_sequential(internal_messages, ranges).await
+        }
+    }
+
+    async fn send_highload(
+        &self,
+        internal_messages: &[ArcCell],
+        ranges: Vec<Range<usize>>,
+    ) -> BatchReport {
+        let expiration = self.expiration();
+        let batches: Vec<SentBatch> = ranges
+            .into_iter()
+            .map(|requests| {
+                let sequence = self.query_sequence.fetch_add(1, Ordering::Relaxed);
+                SentBatch {
+                    requests,
+                    expiration,
+                    query_id: Some(highload_query_id(expiration.valid_until, sequence)),
+                    seqno: None,
+                }
+            })
+            .collect();
+        let results: Vec<_> = stream::iter(batches.iter().enumerate())
+            .map(|(index, batch)| async move {
+                let messages = &internal_messages[batch.requests.clone()];
+                let query_id = batch.query_id.unwrap_or_default();
+                let result = match self
+                    .wallet
+                    .create_highload_message(query_id, messages, false)
+                {
+                    Ok(message) => self.send_message(&message).await,
+                    Err(e) => Err(e.into()),
+                };
+                (index, result)
+            })
+            .buffer_unordered(self.concurrency)
+            .collect()
+            .await;
+
+        let mut outcomes = vec![BatchRequestOutcome::Skipped; internal_messages.len()];
+        for (index, result) in results {
+            let outcome = batch_outcome(index, result);
+            outcomes[batches[index].requests.clone()].fill(outcome);
+        }
+        BatchReport { batches, outcomes }
+    }
+
+    async fn send_sequential(
+        &self,
+        internal_messages: &[ArcCell],
+        ranges: Vec<Range<usize>>,
+    ) -> BatchReport {
+        let mut outcomes = vec![BatchRequestOutcome::Skipped; internal_messages.len()];
+        let mut batches = Vec::with_capacity(ranges.len());
+        let mut seqno = match self.wallet_seqno().await {
+            Ok(seqno) => seqno,
+            Err(e) => {
+                let outcome = batch_outcome(0, Err(e));
+                if let Some(first) = ranges.first() {
+                    outcomes[first.clone()].fill(outcome);
+                }
+                return BatchReport { batches, outcomes };
+            }
+        };
+        for (index, requests) in ranges.into_iter().enumerate() {
+            let expiration = self.expiration();
+            let batch = SentBatch {
+                requests: requests.clone(),
+                expiration,
+                query_id: None,
+                seqno: Some(seqno),
+            };
+            batches.push(batch);
+            let messages = &internal_messages[requests.clone()];
+            let result = match self
+                .wallet
+                .create_external_message(expiration, seqno, messages, false)
+            {
+                Ok(message) => self.send_and_confirm(&message, seqno, expiration).await,
+                Err(e) => Err(e.into()),
+            };
+            let failed = result.is_err();
+            outcomes[requests].fill(batch_outcome(index, result));
+            if failed {
+                break;
+            }
+            seqno += 1;
+        }
+        BatchReport { batches, outcomes }
+    }
+
+    /// Returns the expiration of an external message created now.
+    fn expiration(&self) -> Expiration {
+        Expiration::new(unix_time(), self.ttl).with_clock_skew(self.clock_skew)
+    }
+
+    async fn send_message(&self, message: &Cell) -> Result<Vec<u8>, TonContractError> {
+        let boc = BagOfCells::from_root(message.clone())
+            .serialize(true)
+            .map_cell_error("send_batch", &self.wallet.address)?;
+        Ok(self.client.send_raw_message_return_hash(&boc).await?)
+    }
+
+    /// Sends the message and waits until the wallet seqno moves past `seqno`.
+    async fn send_and_confirm(
+        &self,
+        message: &Cell,
+        seqno: u32,
+        expiration: Expiration,
+    ) -> Result<Vec<u8>, TonContractError> {
+        let message_hash = self.send_message(message).await?;
+        let pending = PendingExternal::new(seqno, Some(expiration));
+        loop {
+            time::sleep(self.poll_interval).await;
+            let account_seqno = self.wallet_seqno().await?;
+            match pending.status(unix_time(), account_seqno) {
+                ExternalMessageStatus::Applied => return Ok(message_hash),
+                ExternalMessageStatus::Pending => continue,
+                ExternalMessageStatus::Expired => {
+                    return Err(TonContractError::InternalError(format!(
+                        "External message with seqno {} expired at {}",
+                        seqno, expiration.valid_until
+                    )))
+                }
+            }
+        }
+    }
+
+    /// Reads the seqno of the wallet from its data, where it's stored in the first 32 bits.
+    async fn wallet_seqno(&self) -> Result<u32, TonContractError> {
+        let address = &self.wallet.address;
+        let state = self.client.get_raw_account_state(address).await?;
+        if state.data.is_empty() {
+            return Err(TonContractError::IllegalArgument(format!(
+                "Wallet {} is not deployed",
+                address
+            )));
+        }
+        let method = "seqno";
+        let data = BagOfCells::parse(&state.data).map_cell_error(method, address)?;
+        let root = data.single_root().map_cell_error(method, address)?;
+        root.parser().load_u32(32).map_cell_error(method, address)
+    }
+}
+
+fn batch_outcome(batch: usize, result: Result<Vec<u8>, TonContractError>) -> BatchRequestOutcome {
+    match result {
+        Ok(message_hash) => BatchRequestOutcome::Sent {
+            batch,
+            message_hash,
+        },
+        Err(error) => BatchRequestOutcome::Failed {
+            batch,
+            error: Arc::new(error),
+        },
+    }
+}
+
+fn unix_time() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as u32)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::cell::{key_extractor_u16, value_extractor_cell, CellBuilder, GenericDictLoader};
+    use crate::wallet::{
+        highload_query_id, plan_batches, test_wallet, WalletVersion, HIGHLOAD_MAX_MESSAGES,
+    };
+
+    #[test]
+    fn plan_batches_works() {
+        assert_eq!(plan_batches(0, 4), vec![]);
+        assert_eq!(plan_batches(9, 4), vec![0..4, 4..8, 8..9]);
+        assert_eq!(plan_batches(254, HIGHLOAD_MAX_MESSAGES), vec![0..254]);
+        assert_eq!(highload_query_id(1_700_000_000, 5) >> 32, 1_700_000_000);
+    }
+
+    #[test]
+    fn highload_body_works() -> anyhow::Result<()> {
+        let wallet = test_wallet(WalletVersion::HighloadV2R2);
+        let messages: Vec<_> = (0..10u32)
+            .map(|i| Ok(Arc::new(CellBuilder::new().store_u32(32, i)?.build()?)))
+            .collect::<anyhow::Result<_>>()?;
+        let query_id = highload_query_id(1_700_000_000, 1);
+        let body = wallet.create_highload_body(query_id, &messages)?;
+        let mut parser = body.parser();
+        assert_eq!(parser.load_i32(32)?, wallet.wallet_id);
+        assert_eq!(parser.load_u64(64)?, query_id);
+        assert!(parser.load_bit()?);
+
+        let loader = GenericDictLoader::new(key_extractor_u16, value_extractor_cell, 16);
+        let dict = body.reference(0)?.load_generic_dict(&loader)?;
+        assert_eq!(dict.len(), messages.len());
+        for (index, message) in messages.iter().enumerate() {
+            let value = &dict[&(index as u16)];
+            assert_eq!(value.parser().load_u8(8)?, 3);
+            assert_eq!(value.reference(0)?, message);
+        }
+
+        let v4 = test_wallet(WalletVersion::V4R2);
+        assert!(v4.create_highload_body(query_id, &messages).is_err());
+        let too_many = vec![messages[0].clone(); HIGHLOAD_MAX_MESSAGES + 1];
+        assert!(wallet.create_highload_body(query_id, too_many).is_err());
+        assert_eq!(wallet.version.max_messages(), HIGHLOAD_MAX_MESSAGES);
+        Ok(())
+    }
+}
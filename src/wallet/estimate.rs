@@ -0,0 +1,160 @@
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+use crate::cell::{ArcCell, BagOfCells, Cell, TonCellError};
+use crate::client::TonClientInterface;
+use crate::coins::Coins;
+use crate::contract::{MapCellError, TonContractError};
+use crate::emulator::{TvmEmulator, TvmEmulatorC7Builder};
+use crate::responses::AccountStatus;
+use crate::tl::Fees;
+use crate::wallet::{Expiration, TonWallet};
+
+/// Fees, value and exit code of a transfer, estimated before it is sent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransferEstimate {
+    /// Fees paid by the wallet for processing the external message.
+    pub source_fees: Fees,
+    /// Fees paid by each destination for processing its internal message.
+    pub destination_fees: Vec<Fees>,
+    /// Total value attached to the internal messages.
+    pub forwarded_value: Coins,
+    /// Exit code of the compute phase of the wallet, emulated against its current state, or
+    /// its initial state if it's deployed by the transfer. `None` if the wallet has neither.
+    ///
+    /// The exit code of the action phase is not available: the emulator only runs the
+    /// compute phase, so failures of sending the messages, e.g. for lack of funds, are not
+    /// detected.
+    pub compute_exit_code: Option<i32>,
+}
+
+impl TransferEstimate {
+    pub fn total_fees(&self) -> i64 {
+        self.source_fees.total() + self.destination_fees.iter().map(Fees::total).sum::<i64>()
+    }
+}
+
+impl TonWallet {
+    /// Estimates fees of the transfer against the current state of the wallet and the
+    /// destinations, without sending it.
+    pub async fn estimate_transfer<C, T>(
+        &self,
+        client: &C,
+        expiration: Expiration,
+        seqno: u32,
+        internal_messages: T,
+        state_init: bool,
+    ) -> Result<TransferEstimate, TonContractError>
+    where
+        C: TonClientInterface,
+        T: AsRef<[ArcCell]>,
+    {
+        let method = "estimate_transfer";
+        let mut forwarded_value = BigUint::zero();
+        for internal_message in internal_messages.as_ref() {
+            forwarded_value += internal_message_value(internal_message)
+                .map_cell_error(method, &self.address)?
+                .into_inner();
+        }
+        let body = self
+            .create_external_body(expiration, seqno, internal_messages)
+            .map_cell_error(method, &self.address)?;
+        let signed = self.sign_external_body(&body)?;
+        let (init_code, init_data) = if state_init {
+            let code = self.version.code().map_cell_error(method, &self.address)?;
+            let data = self
+                .version
+                .initial_data(&self.key_pair, self.wallet_id)
+                .map_cell_error(method, &self.address)?;
+            (
+                serialize(code.as_ref()).map_cell_error(method, &self.address)?,
+                serialize(data.as_ref()).map_cell_error(method, &self.address)?,
+            )
+        } else {
+            (vec![], vec![])
+        };
+        let body = serialize(&signed).map_cell_error(method, &self.address)?;
+
+        let (conn, query) = client
+            .raw_create_query(&self.address, &init_code, &init_data, &body)
+            .await?;
+        let fees = conn.query_estimate_fees(query.id, false).await;
+        // The query is forgotten to free it in tonlib, which must not hide the estimate
+        if let Err(e) = conn.query_forget(query.id).await {
+            log::warn!("Could not forget query {}: {}", query.id, e);
+        }
+        let fees = fees?;
+
+        let account_state = client.get_raw_account_state(&self.address).await?;
+        let (code, data) = if account_state.status() == AccountStatus::Active {
+            (account_state.code, account_state.data)
+        } else {
+            (init_code, init_data)
+        };
+        let compute_exit_code = if code.is_empty() {
+            None
+        } else {
+            let config = client.get_config_all(0).await?;
+            let balance = account_state.balance.max(0) as u64;
+            let c7 =
+                TvmEmulatorC7Builder::new(&self.address, &config.config.bytes, balance).build();
+            let result = tokio::task::spawn_blocking(move || {
+                let mut emulator = TvmEmulator::new(&code, &data)?;
+                emulator.set_c7(&c7)?;
+                emulator.send_external_message(signed)
+            })
+            .await
+            .map_err(|e| TonContractError::InternalError(e.to_string()))?
+            .map_err(|e| TonContractError::MessageEmulationError {
+                address: self.address.clone(),
+                error: e,
+            })?;
+            Some(result.vm_exit_code)
+        };
+        Ok(TransferEstimate {
+            source_fees: fees.source_fees,
+            destination_fees: fees.destination_fees,
+            forwarded_value: Coins::new(forwarded_value),
+            compute_exit_code,
+        })
+    }
+}
+
+fn serialize(cell: &Cell) -> Result<Vec<u8>, TonCellError> {
+    BagOfCells::from_root(cell.clone()).serialize(false)
+}
+
+/// Returns the value attached to an internal message:
+///
+/// ```raw
+/// int_msg_info$0 ihr_disabled:Bool bounce:Bool bounced:Bool
+///   src:MsgAddressInt dest:MsgAddressInt
+///   value:CurrencyCollection ...
+/// ```
+fn internal_message_value(message: &Cell) -> Result<Coins, TonCellError> {
+    let mut parser = message.parser();
+    if parser.load_bit()? {
+        return Err(TonCellError::unexpected_structure("int_msg_info"));
+    }
+    parser.skip_bits(3)?;
+    parser.load_address()?;
+    parser.load_address()?;
+    parser.load_coins()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::address::TonAddress;
+    use crate::coins::Coins;
+    use crate::message::TransferMessage;
+    use crate::wallet::estimate::internal_message_value;
+
+    #[test]
+    fn internal_message_value_works() -> anyhow::Result<()> {
+        let dest: TonAddress = "EQBiMfDMivebQb052Z6yR3jHrmwNhw1kQ5bcAUOBYsK_VPuK".parse()?;
+        let value = Coins::from_nano(1_500_000_000);
+        let message = TransferMessage::new(&dest, &value).build()?;
+        assert_eq!(internal_message_value(&message)?, value);
+        Ok(())
+    }
+}
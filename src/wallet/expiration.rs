@@ -0,0 +1,192 @@
+use crate::cell::{Cell, TonCellError};
+use crate::message::{external_message_body, TonMessageError, SIGNATURE_BITS};
+use crate::wallet::WalletVersion;
+
+/// Default lifetime of an external message, in seconds.
+pub const DEFAULT_MESSAGE_TTL: u32 = 60;
+
+/// Default tolerance between the local clock and the time of the last known block, in seconds.
+pub const DEFAULT_CLOCK_SKEW: u32 = 30;
+
+/// Returns `valid_until` of an external message created at `now` that must be accepted
+/// within `ttl` seconds.
+pub fn valid_until(now: u32, ttl: u32) -> u32 {
+    now.saturating_add(ttl)
+}
+
+/// Expiration of an external message built by [`TonWallet::create_external_message`] and
+/// other wallet builders.
+///
+/// [`TonWallet::create_external_message`]: crate::wallet::TonWallet::create_external_message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Expiration {
+    /// Time until which the wallet accepts the message.
+    pub valid_until: u32,
+    /// Seconds past `valid_until` during which the message is still considered pending, as
+    /// the time of the last known block may lag behind the time of the validators.
+    pub clock_skew: u32,
+}
+
+impl Expiration {
+    /// Returns the expiration of a message created at `now` that must be accepted within
+    /// `ttl` seconds, tolerating [`DEFAULT_CLOCK_SKEW`].
+    pub fn new(now: u32, ttl: u32) -> Expiration {
+        Expiration {
+            valid_until: valid_until(now, ttl),
+            clock_skew: DEFAULT_CLOCK_SKEW,
+        }
+    }
+
+    pub fn with_clock_skew(mut self, clock_skew: u32) -> Self {
+        self.clock_skew = clock_skew;
+        self
+    }
+
+    /// Returns true if the message can never be accepted once the last known block is
+    /// generated at `now`.
+    pub fn is_expired(&self, now: u32) -> bool {
+        now > self.valid_until.saturating_add(self.clock_skew)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExternalMessageStatus {
+    /// The message may still be accepted by the wallet.
+    Pending,
+    /// The wallet seqno has moved past the seqno of the message.
+    Applied,
+    /// The message can never be accepted by the wallet.
+    Expired,
+}
+
+/// Replay protection fields of an external message sent to a wallet.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PendingExternal {
+    pub seqno: u32,
+    /// `None` for wallet versions without expiration (v1).
+    pub expiration: Option<Expiration>,
+}
+
+impl PendingExternal {
+    pub fn new(seqno: u32, expiration: Option<Expiration>) -> PendingExternal {
+        PendingExternal { seqno, expiration }
+    }
+
+    /// Reads seqno and `valid_until` of an external message sent to the wallet of given
+    /// version, which is considered pending for `clock_skew` seconds past `valid_until`.
+    pub fn from_external_message(
+        version: &WalletVersion,
+        message: &Cell,
+        clock_skew: u32,
+    ) -> Result<PendingExternal, TonMessageError> {
+        let body = external_message_body(message)?;
+        Ok(Self::from_signed_body(version, &body, clock_skew)?)
+    }
+
+    /// Reads seqno and `valid_until` of a signed external body of the wallet of given version,
+    /// see [`Self::from_external_message`].
+    pub fn from_signed_body(
+        version: &WalletVersion,
+        signed_body: &Cell,
+        clock_skew: u32,
+    ) -> Result<PendingExternal, TonCellError> {
+        let mut parser = signed_body.parser();
+        parser.skip_bits(SIGNATURE_BITS)?;
+        let expiration = |valid_until| Expiration {
+            valid_until,
+            clock_skew,
+        };
+        let pending = match version {
+            WalletVersion::V1R1 | WalletVersion::V1R2 | WalletVersion::V1R3 => {
+                PendingExternal::new(parser.load_u32(32)?, None)
+            }
+            WalletVersion::V2R1 | WalletVersion::V2R2 => {
+                let seqno = parser.load_u32(32)?;
+                let valid_until = parser.load_u32(32)?;
+                PendingExternal::new(seqno, Some(expiration(valid_until)))
+            }
+            WalletVersion::V3R1
+            | WalletVersion::V3R2
+            | WalletVersion::V4R1
+            | WalletVersion::V4R2 => {
+                parser.skip_bits(32)?; // wallet_id
+                let valid_until = parser.load_u32(32)?;
+                let seqno = parser.load_u32(32)?;
+                PendingExternal::new(seqno, Some(expiration(valid_until)))
+            }
+            WalletVersion::HighloadV1R1
+            | WalletVersion::HighloadV1R2
+            | WalletVersion::HighloadV2
+            | WalletVersion::HighloadV2R1
+            | WalletVersion::HighloadV2R2 => {
+                return Err(TonCellError::InternalError(
+                    "Highload wallets do not use seqno".to_string(),
+                ))
+            }
+        };
+        Ok(pending)
+    }
+
+    /// Classifies the message using the current seqno of the wallet and `now`, the time of the
+    /// last known block. The message is considered expired only after the clock skew of its
+    /// expiration, so it can't be resent while it still may be accepted.
+    ///
+    /// `Applied` means that a message with the same seqno was accepted, which is not
+    /// necessarily this message.
+    pub fn status(&self, now: u32, account_seqno: u32) -> ExternalMessageStatus {
+        if account_seqno > self.seqno {
+            return ExternalMessageStatus::Applied;
+        }
+        match self.expiration {
+            Some(expiration) if expiration.is_expired(now) => ExternalMessageStatus::Expired,
+            _ => ExternalMessageStatus::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::cell::CellBuilder;
+    use crate::wallet::{
+        test_wallet, Expiration, ExternalMessageStatus, PendingExternal, WalletVersion,
+        DEFAULT_CLOCK_SKEW, DEFAULT_MESSAGE_TTL,
+    };
+
+    #[test]
+    fn pending_external_status_works() -> anyhow::Result<()> {
+        let wallet = test_wallet(WalletVersion::V4R2);
+        let msg = Arc::new(CellBuilder::new().store_u32(32, 1)?.build()?);
+        let now = 1_700_000_000;
+        let expiration = Expiration::new(now, DEFAULT_MESSAGE_TTL);
+        assert_eq!(expiration.valid_until, now + DEFAULT_MESSAGE_TTL);
+        let message = wallet.create_external_message(expiration, 5, [msg], false)?;
+
+        let pending =
+            PendingExternal::from_external_message(&wallet.version, &message, DEFAULT_CLOCK_SKEW)?;
+        assert_eq!(pending, PendingExternal::new(5, Some(expiration)));
+        let expire_at = expiration.valid_until;
+        assert_eq!(pending.status(now, 5), ExternalMessageStatus::Pending);
+        assert_eq!(pending.status(now, 6), ExternalMessageStatus::Applied);
+        assert_eq!(
+            pending.status(expire_at + DEFAULT_CLOCK_SKEW, 5),
+            ExternalMessageStatus::Pending
+        );
+        assert_eq!(
+            pending.status(expire_at + DEFAULT_CLOCK_SKEW + 1, 5),
+            ExternalMessageStatus::Expired
+        );
+        assert_eq!(
+            pending.status(expire_at + DEFAULT_CLOCK_SKEW + 1, 6),
+            ExternalMessageStatus::Applied
+        );
+
+        let strict = PendingExternal::new(5, Some(expiration.with_clock_skew(0)));
+        assert_eq!(
+            strict.status(expire_at + 1, 5),
+            ExternalMessageStatus::Expired
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+use crate::wallet::{valid_until, ExternalMessageStatus};
+
+/// Returns the query id of a highload wallet v2 external message, which is valid until
+/// `valid_until` and unique among the queries of the wallet with the same expiration.
+pub fn highload_query_id(valid_until: u32, sequence: u32) -> u64 {
+    ((valid_until as u64) << 32) | sequence as u64
+}
+
+/// Returns the time until which a highload wallet v2 accepts the query id.
+pub fn highload_query_valid_until(query_id: u64) -> u32 {
+    (query_id >> 32) as u32
+}
+
+/// Generates query ids of a highload wallet v2 which are never reused, even if the clock goes
+/// backwards. Processors sharing a wallet must use distinct first sequences, e.g. random ones,
+/// as the wallet rejects a query id it has already processed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HighloadQueryIdGenerator {
+    last_query_id: Option<u64>,
+    first_sequence: u32,
+}
+
+impl HighloadQueryIdGenerator {
+    pub fn new(first_sequence: u32) -> HighloadQueryIdGenerator {
+        HighloadQueryIdGenerator {
+            last_query_id: None,
+            first_sequence,
+        }
+    }
+
+    /// Returns a new query id of a message created at `now`, expiring after `ttl` seconds.
+    pub fn next(&mut self, now: u32, ttl: u32) -> u64 {
+        let fresh = highload_query_id(valid_until(now, ttl), self.first_sequence);
+        let query_id = match self.last_query_id {
+            Some(last) if last >= fresh => last.saturating_add(1),
+            _ => fresh,
+        };
+        self.last_query_id = Some(query_id);
+        query_id
+    }
+}
+
+/// Result of the `processed?` get-method of a highload wallet v2.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HighloadQueryStatus {
+    /// The query id is not stored by the wallet and wasn't cleaned up yet.
+    NotProcessed,
+    Processed,
+    /// The query id is older than the queries cleaned up by the wallet, so whether it was
+    /// processed is unknown.
+    Forgotten,
+}
+
+impl HighloadQueryStatus {
+    /// Converts the result of the get-method, i.e. `-1` for processed queries, `1` for
+    /// forgotten ones and `0` otherwise.
+    pub fn from_get_method(result: i64) -> Option<HighloadQueryStatus> {
+        match result {
+            -1 => Some(HighloadQueryStatus::Processed),
+            0 => Some(HighloadQueryStatus::NotProcessed),
+            1 => Some(HighloadQueryStatus::Forgotten),
+            _ => None,
+        }
+    }
+}
+
+/// Query of a highload wallet v2 sent to the network.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PendingHighloadQuery {
+    pub query_id: u64,
+}
+
+impl PendingHighloadQuery {
+    pub fn new(query_id: u64) -> PendingHighloadQuery {
+        PendingHighloadQuery { query_id }
+    }
+
+    pub fn valid_until(&self) -> u32 {
+        highload_query_valid_until(self.query_id)
+    }
+
+    /// Classifies the query using the result of the `processed?` get-method and `now`, the time
+    /// of the last known block, like [`PendingExternal::status`](crate::wallet::PendingExternal::status).
+    ///
+    /// Returns `None` for forgotten queries, whose messages must not be sent again until their
+    /// transactions are checked.
+    pub fn status(
+        &self,
+        processed: HighloadQueryStatus,
+        now: u32,
+        clock_skew: u32,
+    ) -> Option<ExternalMessageStatus> {
+        match processed {
+            HighloadQueryStatus::Processed => Some(ExternalMessageStatus::Applied),
+            HighloadQueryStatus::Forgotten => None,
+            HighloadQueryStatus::NotProcessed
+                if now > self.valid_until().saturating_add(clock_skew) =>
+            {
+                Some(ExternalMessageStatus::Expired)
+            }
+            HighloadQueryStatus::NotProcessed => Some(ExternalMessageStatus::Pending),
+        }
+    }
+
+    /// Returns whether the messages of the query can be sent again with a new query id, i.e.
+    /// the query can never be processed.
+    pub fn can_regenerate(
+        &self,
+        processed: HighloadQueryStatus,
+        now: u32,
+        clock_skew: u32,
+    ) -> bool {
+        self.status(processed, now, clock_skew) == Some(ExternalMessageStatus::Expired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::wallet::{
+        highload_query_valid_until, ExternalMessageStatus, HighloadQueryIdGenerator,
+        HighloadQueryStatus, PendingHighloadQuery, DEFAULT_CLOCK_SKEW, DEFAULT_MESSAGE_TTL,
+    };
+
+    #[test]
+    fn highload_query_ids_work() {
+        let now = 1_700_000_000;
+        let mut generator = HighloadQueryIdGenerator::new(7);
+        let first = generator.next(now, DEFAULT_MESSAGE_TTL);
+        assert_eq!(highload_query_valid_until(first), now + DEFAULT_MESSAGE_TTL);
+        assert_eq!(first as u32, 7);
+        let second = generator.next(now, DEFAULT_MESSAGE_TTL);
+        assert_eq!(second, first + 1);
+        // The clock went backwards
+        assert_eq!(generator.next(now - 10, DEFAULT_MESSAGE_TTL), first + 2);
+        let later = generator.next(now + 1, DEFAULT_MESSAGE_TTL);
+        assert_eq!(
+            highload_query_valid_until(later),
+            now + 1 + DEFAULT_MESSAGE_TTL
+        );
+        assert_eq!(later as u32, 7);
+
+        assert_eq!(
+            HighloadQueryStatus::from_get_method(-1),
+            Some(HighloadQueryStatus::Processed)
+        );
+        assert_eq!(HighloadQueryStatus::from_get_method(2), None);
+
+        let pending = PendingHighloadQuery::new(first);
+        let expire_at = pending.valid_until();
+        let status = |processed, now| pending.status(processed, now, DEFAULT_CLOCK_SKEW);
+        let not_processed = HighloadQueryStatus::NotProcessed;
+        assert_eq!(
+            status(not_processed, now),
+            Some(ExternalMessageStatus::Pending)
+        );
+        assert_eq!(
+            status(not_processed, expire_at + DEFAULT_CLOCK_SKEW),
+            Some(ExternalMessageStatus::Pending)
+        );
+        assert_eq!(
+            status(not_processed, expire_at + DEFAULT_CLOCK_SKEW + 1),
+            Some(ExternalMessageStatus::Expired)
+        );
+        assert_eq!(
+            status(HighloadQueryStatus::Processed, now),
+            Some(ExternalMessageStatus::Applied)
+        );
+        assert_eq!(
+            status(HighloadQueryStatus::Forgotten, expire_at + 1000),
+            None
+        );
+        assert!(pending.can_regenerate(not_processed, expire_at + 1000, DEFAULT_CLOCK_SKEW));
+        assert!(!pending.can_regenerate(
+            HighloadQueryStatus::Forgotten,
+            expire_at + 1000,
+            DEFAULT_CLOCK_SKEW
+        ));
+    }
+}
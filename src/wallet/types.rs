@@ -1,6 +1,11 @@
-use crate::cell::{Cell, CellBuilder, TonCellError};
+use crate::cell::{
+    key_extractor_256bit, key_serializer_256bit, Cell, CellBuilder, DictBuilder, GenericDictLoader,
+    TonCellError,
+};
+use crate::wallet::WalletVersion;
 
 /// WalletVersion::V1R1 | WalletVersion::V1R2 | WalletVersion::V1R3 | WalletVersion::V2R1 | WalletVersion::V2R2
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WalletDataV1V2 {
     pub seqno: u32,
     pub public_key: [u8; 32],
@@ -30,6 +35,7 @@ impl TryFrom<WalletDataV1V2> for Cell {
 }
 
 /// WalletVersion::V3R1 | WalletVersion::V3R2
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WalletDataV3 {
     pub seqno: u32,
     pub wallet_id: i32,
@@ -66,6 +72,7 @@ impl TryFrom<WalletDataV3> for Cell {
 }
 
 /// WalletVersion::V4R1 | WalletVersion::V4R2
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WalletDataV4 {
     pub seqno: u32,
     pub wallet_id: i32,
@@ -105,6 +112,7 @@ impl TryFrom<WalletDataV4> for Cell {
 }
 
 /// WalletVersion::HighloadV2R2
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct WalletDataHighloadV2R2 {
     pub wallet_id: i32,
     pub last_cleaned_time: u64,
@@ -143,3 +151,213 @@ impl TryFrom<WalletDataHighloadV2R2> for Cell {
             .build()
     }
 }
+
+/// Wallet v5 according to TL-B schema:
+///
+/// ```raw
+/// contract_state$_ is_signature_allowed:(## 1) seqno:# wallet_id:(## 32)
+///   public_key:(## 256) extensions_dict:(HashmapE 256 int1) = ContractState;
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalletDataV5 {
+    pub is_signature_allowed: bool,
+    pub seqno: u32,
+    /// Encoded [`W5WalletId`](crate::wallet::W5WalletId).
+    pub wallet_id: i32,
+    pub public_key: [u8; 32],
+    /// Address hashes of the extensions, which are in the workchain of the wallet.
+    pub extensions: Vec<[u8; 32]>,
+}
+
+impl TryFrom<Cell> for WalletDataV5 {
+    type Error = TonCellError;
+
+    fn try_from(value: Cell) -> Result<Self, Self::Error> {
+        let mut parser = value.parser();
+        let is_signature_allowed = parser.load_bit()?;
+        let seqno = parser.load_u32(32)?;
+        let wallet_id = parser.load_i32(32)?;
+        let mut public_key = [0u8; 32];
+        parser.load_slice(&mut public_key)?;
+        let mut extensions = if parser.load_bit()? {
+            let loader = GenericDictLoader::new(key_extractor_256bit, |_| Ok(()), 256);
+            let dict = value.reference(0)?.load_generic_dict(&loader)?;
+            dict.into_keys().collect()
+        } else {
+            vec![]
+        };
+        extensions.sort();
+        Ok(Self {
+            is_signature_allowed,
+            seqno,
+            wallet_id,
+            public_key,
+            extensions,
+        })
+    }
+}
+
+impl TryFrom<WalletDataV5> for Cell {
+    type Error = TonCellError;
+
+    fn try_from(value: WalletDataV5) -> Result<Self, Self::Error> {
+        let extensions = DictBuilder::from_entries(
+            256,
+            value.extensions.iter().map(|hash| (*hash, ())),
+            key_serializer_256bit,
+            |builder, _| builder.store_bit(true).map(|_| ()),
+        )?;
+        CellBuilder::new()
+            .store_bit(value.is_signature_allowed)?
+            .store_u32(32, value.seqno)?
+            .store_i32(32, value.wallet_id)?
+            .store_slice(&value.public_key)?
+            .store_dict(&extensions)?
+            .build()
+    }
+}
+
+/// Parameters of a wallet recovered from the data cell of its account.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VersionedWalletData {
+    V1V2(WalletDataV1V2),
+    V3(WalletDataV3),
+    V4(WalletDataV4),
+    V5(WalletDataV5),
+    /// Highload wallets v1 share the layout of wallets v3.
+    HighloadV1(WalletDataV3),
+    HighloadV2(WalletDataHighloadV2R2),
+}
+
+impl VersionedWalletData {
+    /// Parses the data cell of a wallet of the given version.
+    pub fn parse(
+        version: &WalletVersion,
+        data: &Cell,
+    ) -> Result<VersionedWalletData, TonCellError> {
+        let data = data.clone();
+        let wallet_data = match version {
+            WalletVersion::V1R1
+            | WalletVersion::V1R2
+            | WalletVersion::V1R3
+            | WalletVersion::V2R1
+            | WalletVersion::V2R2 => VersionedWalletData::V1V2(data.try_into()?),
+            WalletVersion::V3R1 | WalletVersion::V3R2 => VersionedWalletData::V3(data.try_into()?),
+            WalletVersion::V4R1 | WalletVersion::V4R2 => VersionedWalletData::V4(data.try_into()?),
+            WalletVersion::HighloadV1R1 | WalletVersion::HighloadV1R2 => {
+                VersionedWalletData::HighloadV1(data.try_into()?)
+            }
+            WalletVersion::HighloadV2
+            | WalletVersion::HighloadV2R1
+            | WalletVersion::HighloadV2R2 => VersionedWalletData::HighloadV2(data.try_into()?),
+        };
+        Ok(wallet_data)
+    }
+
+    /// Detects the wallet version by the code of the account and parses its data, returning
+    /// `None` for codes of other contracts.
+    ///
+    /// Wallets v5 are not detected by code, their data is parsed with
+    /// [`WalletDataV5::try_from`].
+    pub fn from_state(
+        code: &Cell,
+        data: &Cell,
+    ) -> Result<Option<(WalletVersion, VersionedWalletData)>, TonCellError> {
+        match WalletVersion::from_code(code)? {
+            Some(version) => {
+                let wallet_data = VersionedWalletData::parse(&version, data)?;
+                Ok(Some((version, wallet_data)))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the public key controlling the wallet.
+    pub fn public_key(&self) -> &[u8; 32] {
+        match self {
+            VersionedWalletData::V1V2(data) => &data.public_key,
+            VersionedWalletData::V3(data) | VersionedWalletData::HighloadV1(data) => {
+                &data.public_key
+            }
+            VersionedWalletData::V4(data) => &data.public_key,
+            VersionedWalletData::V5(data) => &data.public_key,
+            VersionedWalletData::HighloadV2(data) => &data.public_key,
+        }
+    }
+
+    /// Returns the seqno, or `None` for highload wallets v2 which replace it with query ids.
+    pub fn seqno(&self) -> Option<u32> {
+        match self {
+            VersionedWalletData::V1V2(data) => Some(data.seqno),
+            VersionedWalletData::V3(data) | VersionedWalletData::HighloadV1(data) => {
+                Some(data.seqno)
+            }
+            VersionedWalletData::V4(data) => Some(data.seqno),
+            VersionedWalletData::V5(data) => Some(data.seqno),
+            VersionedWalletData::HighloadV2(_) => None,
+        }
+    }
+
+    /// Returns the subwallet id, or `None` for wallets v1 and v2 which have none.
+    pub fn wallet_id(&self) -> Option<i32> {
+        match self {
+            VersionedWalletData::V1V2(_) => None,
+            VersionedWalletData::V3(data) | VersionedWalletData::HighloadV1(data) => {
+                Some(data.wallet_id)
+            }
+            VersionedWalletData::V4(data) => Some(data.wallet_id),
+            VersionedWalletData::V5(data) => Some(data.wallet_id),
+            VersionedWalletData::HighloadV2(data) => Some(data.wallet_id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cell::{Cell, CellBuilder};
+    use crate::wallet::{
+        test_key_pair, VersionedWalletData, W5WalletId, WalletDataV5, WalletVersion,
+    };
+
+    #[test]
+    fn wallet_data_is_recovered() -> anyhow::Result<()> {
+        let key_pair = test_key_pair();
+
+        for (version, wallet_id) in [
+            (WalletVersion::V2R2, None),
+            (WalletVersion::V3R2, Some(42)),
+            (WalletVersion::V4R2, Some(42)),
+            (WalletVersion::HighloadV2R2, Some(42)),
+        ] {
+            let data = version.initial_data(&key_pair, wallet_id.unwrap_or(0))?;
+            let (detected, wallet_data) =
+                VersionedWalletData::from_state(version.code()?, &data)?.unwrap();
+            assert_eq!(detected, version);
+            assert_eq!(wallet_data.public_key().as_slice(), key_pair.public_key);
+            assert_eq!(wallet_data.wallet_id(), wallet_id);
+        }
+        let other_code = CellBuilder::new().store_u8(8, 1)?.build()?;
+        let data = WalletVersion::V4R2.initial_data(&key_pair, 0)?;
+        assert!(VersionedWalletData::from_state(&other_code, &data)?.is_none());
+
+        let v5 = WalletDataV5 {
+            is_signature_allowed: true,
+            seqno: 7,
+            wallet_id: W5WalletId::mainnet(0, 0)?.wallet_id(),
+            public_key: key_pair.public_key.as_slice().try_into()?,
+            extensions: vec![[1; 32], [2; 32]],
+        };
+        let cell: Cell = v5.clone().try_into()?;
+        assert_eq!(WalletDataV5::try_from(cell)?, v5);
+        let empty = WalletDataV5 {
+            extensions: vec![],
+            ..v5
+        };
+        let cell: Cell = empty.clone().try_into()?;
+        assert_eq!(cell.references.len(), 0);
+        let wallet_data = VersionedWalletData::V5(WalletDataV5::try_from(cell)?);
+        assert_eq!(wallet_data.seqno(), Some(7));
+        assert_eq!(wallet_data.public_key(), &empty.public_key);
+        Ok(())
+    }
+}
@@ -0,0 +1,635 @@
+use std::sync::Arc;
+
+use num_bigint::BigUint;
+
+use crate::address::TonAddress;
+use crate::cell::{ArcCell, Cell, CellBuilder, CellParser, TonCellError};
+use crate::coins::Coins;
+use crate::crypto::{verify, KeyPair};
+use crate::message::{
+    build_out_list, parse_out_list, OutAction, OutMessage, SendMode, TonMessageError,
+    TransferMessage,
+};
+use crate::network::Network;
+
+// Constants from wallet v5 contract
+// https://github.com/ton-blockchain/wallet-contract-v5/blob/main/types.tlb
+
+pub const W5_EXTERNAL_SIGNED: u32 = 0x7369676e;
+pub const W5_INTERNAL_SIGNED: u32 = 0x73696e74;
+pub const W5_INTERNAL_EXTENSION: u32 = 0x6578746e;
+
+pub const W5_ACTION_ADD_EXTENSION: u8 = 0x02;
+pub const W5_ACTION_DELETE_EXTENSION: u8 = 0x03;
+pub const W5_ACTION_SET_SIGNATURE_AUTH_ALLOWED: u8 = 0x04;
+
+pub use crate::constants::{MAINNET_GLOBAL_ID, TESTNET_GLOBAL_ID};
+
+const SIGNATURE_BITS: usize = 512;
+const MAX_SUBWALLET_NUMBER: u32 = (1 << 15) - 1;
+
+/// Components of the wallet v5 `wallet_id`, which is the network global id xored with
+/// the client context:
+///
+/// ```raw
+/// wallet_id_client$1 workchain:int8 wallet_version:uint8 subwallet_number:uint15 = Context;
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct W5WalletId {
+    pub network_global_id: i32,
+    pub workchain: i8,
+    pub wallet_version: u8,
+    pub subwallet_number: u16,
+}
+
+impl W5WalletId {
+    pub fn new(
+        network: Network,
+        workchain: i32,
+        subwallet_number: u32,
+    ) -> Result<W5WalletId, TonCellError> {
+        let workchain = i8::try_from(workchain).map_err(|_| {
+            TonCellError::InternalError(format!("Invalid wallet v5 workchain: {}", workchain))
+        })?;
+        if subwallet_number > MAX_SUBWALLET_NUMBER {
+            return Err(TonCellError::InternalError(format!(
+                "Wallet v5 subwallet number must be at most {}, got {}",
+                MAX_SUBWALLET_NUMBER, subwallet_number
+            )));
+        }
+        Ok(W5WalletId {
+            network_global_id: network.global_id(),
+            workchain,
+            wallet_version: 0,
+            subwallet_number: subwallet_number as u16,
+        })
+    }
+
+    pub fn mainnet(workchain: i32, subwallet_number: u32) -> Result<W5WalletId, TonCellError> {
+        Self::new(Network::Mainnet, workchain, subwallet_number)
+    }
+
+    pub fn testnet(workchain: i32, subwallet_number: u32) -> Result<W5WalletId, TonCellError> {
+        Self::new(Network::Testnet, workchain, subwallet_number)
+    }
+
+    pub fn network(&self) -> Network {
+        Network::from_global_id(self.network_global_id)
+    }
+
+    /// Decodes the components of `wallet_id` stored in the data of a wallet deployed
+    /// on the network.
+    pub fn from_wallet_id(wallet_id: i32, network: Network) -> Result<W5WalletId, TonCellError> {
+        let network_global_id = network.global_id();
+        let context = (wallet_id ^ network_global_id) as u32;
+        if context >> 31 == 0 {
+            return Err(TonCellError::InternalError(format!(
+                "Wallet id {} does not contain a client context",
+                wallet_id
+            )));
+        }
+        Ok(W5WalletId {
+            network_global_id,
+            workchain: (context >> 23) as u8 as i8,
+            wallet_version: (context >> 15) as u8,
+            subwallet_number: (context & MAX_SUBWALLET_NUMBER) as u16,
+        })
+    }
+
+    pub fn wallet_id(&self) -> i32 {
+        let context = (1u32 << 31)
+            | ((self.workchain as u8 as u32) << 23)
+            | ((self.wallet_version as u32) << 15)
+            | self.subwallet_number as u32;
+        context as i32 ^ self.network_global_id
+    }
+}
+
+/// Extended action of wallet v5 according to TL-B schema:
+///
+/// ```raw
+/// action_add_ext#02 addr:MsgAddressInt = ExtendedAction;
+/// action_delete_ext#03 addr:MsgAddressInt = ExtendedAction;
+/// action_set_signature_auth_allowed#04 allowed:(## 1) = ExtendedAction;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub enum W5ExtendedAction {
+    AddExtension(TonAddress),
+    DeleteExtension(TonAddress),
+    SetSignatureAuthAllowed(bool),
+}
+
+impl W5ExtendedAction {
+    pub fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        match self {
+            W5ExtendedAction::AddExtension(addr) => {
+                builder
+                    .store_u8(8, W5_ACTION_ADD_EXTENSION)?
+                    .store_address(addr)?;
+            }
+            W5ExtendedAction::DeleteExtension(addr) => {
+                builder
+                    .store_u8(8, W5_ACTION_DELETE_EXTENSION)?
+                    .store_address(addr)?;
+            }
+            W5ExtendedAction::SetSignatureAuthAllowed(allowed) => {
+                builder
+                    .store_u8(8, W5_ACTION_SET_SIGNATURE_AUTH_ALLOWED)?
+                    .store_bit(*allowed)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load(parser: &mut CellParser) -> Result<W5ExtendedAction, TonCellError> {
+        let action = match parser.load_u8(8)? {
+            W5_ACTION_ADD_EXTENSION => W5ExtendedAction::AddExtension(parser.load_address()?),
+            W5_ACTION_DELETE_EXTENSION => W5ExtendedAction::DeleteExtension(parser.load_address()?),
+            W5_ACTION_SET_SIGNATURE_AUTH_ALLOWED => {
+                W5ExtendedAction::SetSignatureAuthAllowed(parser.load_bit()?)
+            }
+            op => {
+                return Err(TonCellError::cell_parser_error(format!(
+                    "Unknown wallet v5 extended action: {:#04x}",
+                    op
+                )))
+            }
+        };
+        Ok(action)
+    }
+}
+
+/// Actions requested from wallet v5 according to TL-B schema:
+///
+/// ```raw
+/// actions$_ out_actions:(Maybe ^OutList) has_other_actions:(## 1) {m:#} {n:#}
+///           other_actions:(ActionList n m) = InnerRequest;
+/// ```
+///
+/// The first extended action is stored inline, each following one is stored in a reference
+/// of the previous one.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct W5InnerRequest {
+    pub out_actions: Vec<OutAction>,
+    pub extended_actions: Vec<W5ExtendedAction>,
+}
+
+impl W5InnerRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_out_action(&mut self, action: OutAction) -> &mut Self {
+        self.out_actions.push(action);
+        self
+    }
+
+    pub fn with_extended_action(&mut self, action: W5ExtendedAction) -> &mut Self {
+        self.extended_actions.push(action);
+        self
+    }
+
+    pub fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        if self.out_actions.is_empty() {
+            builder.store_bit(false)?;
+        } else {
+            builder
+                .store_bit(true)?
+                .store_child(build_out_list(&self.out_actions)?)?;
+        }
+        match self.extended_actions.split_first() {
+            None => {
+                builder.store_bit(false)?;
+            }
+            Some((first, rest)) => {
+                builder.store_bit(true)?;
+                first.store(builder)?;
+                if let Some(rest) = Self::build_extended_actions(rest)? {
+                    builder.store_child(rest)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load(
+        cell: &Cell,
+        ref_index: &mut usize,
+        parser: &mut CellParser,
+    ) -> Result<W5InnerRequest, TonCellError> {
+        let mut request = W5InnerRequest::default();
+        if parser.load_bit()? {
+            request.out_actions = parse_out_list(cell.reference(*ref_index)?)?;
+            *ref_index += 1;
+        }
+        if parser.load_bit()? {
+            request
+                .extended_actions
+                .push(W5ExtendedAction::load(parser)?);
+            let mut next = cell.references.get(*ref_index);
+            if next.is_some() {
+                *ref_index += 1;
+            }
+            while let Some(next_cell) = next {
+                let mut next_parser = next_cell.parser();
+                request
+                    .extended_actions
+                    .push(W5ExtendedAction::load(&mut next_parser)?);
+                next = next_cell.references.first();
+            }
+        }
+        Ok(request)
+    }
+
+    fn build_extended_actions(actions: &[W5ExtendedAction]) -> Result<Option<Cell>, TonCellError> {
+        let mut next: Option<Cell> = None;
+        for action in actions.iter().rev() {
+            let mut builder = CellBuilder::new();
+            action.store(&mut builder)?;
+            if let Some(next) = next {
+                builder.store_child(next)?;
+            }
+            next = Some(builder.build()?);
+        }
+        Ok(next)
+    }
+}
+
+/// Signed request of wallet v5 according to TL-B schema:
+///
+/// ```raw
+/// signed_request$_ wallet_id:(## 32) valid_until:(## 32) msg_seqno:(## 32)
+///                  inner:InnerRequest signature:bits512 = SignedRequest;
+/// internal_signed#73696e74 signed:SignedRequest = InternalMsgBody;
+/// external_signed#7369676e signed:SignedRequest = ExternalMsgBody;
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct W5SignedRequest {
+    pub wallet_id: i32,
+    pub valid_until: u32,
+    pub msg_seqno: u32,
+    pub inner: W5InnerRequest,
+}
+
+impl W5SignedRequest {
+    pub fn new(wallet_id: i32, valid_until: u32, msg_seqno: u32, inner: W5InnerRequest) -> Self {
+        W5SignedRequest {
+            wallet_id,
+            valid_until,
+            msg_seqno,
+            inner,
+        }
+    }
+
+    /// Builds the body without signature, `op` is either [`W5_EXTERNAL_SIGNED`]
+    /// or [`W5_INTERNAL_SIGNED`].
+    pub fn build_unsigned(&self, op: u32) -> Result<Cell, TonCellError> {
+        let mut builder = CellBuilder::new();
+        builder
+            .store_u32(32, op)?
+            .store_i32(32, self.wallet_id)?
+            .store_u32(32, self.valid_until)?
+            .store_u32(32, self.msg_seqno)?;
+        self.inner.store(&mut builder)?;
+        builder.build()
+    }
+
+    /// Builds the body and appends the signature of its hash.
+    pub fn sign(&self, op: u32, key_pair: &KeyPair) -> Result<Cell, TonMessageError> {
+        let unsigned = self.build_unsigned(op)?;
+        let sig = key_pair.sign(unsigned.cell_hash()?.as_slice())?;
+        let mut builder = CellBuilder::new();
+        builder.store_cell(&unsigned)?.store_slice(sig.as_slice())?;
+        Ok(builder.build()?)
+    }
+
+    /// Parses a signed body, returning op, request and signature.
+    pub fn parse(body: &Cell) -> Result<(u32, W5SignedRequest, Vec<u8>), TonCellError> {
+        let mut parser = body.parser();
+        let mut ref_index = 0;
+        let op = parser.load_u32(32)?;
+        if op != W5_EXTERNAL_SIGNED && op != W5_INTERNAL_SIGNED {
+            return Err(TonCellError::cell_parser_error(format!(
+                "Not a wallet v5 signed request: {:#010x}",
+                op
+            )));
+        }
+        let wallet_id = parser.load_i32(32)?;
+        let valid_until = parser.load_u32(32)?;
+        let msg_seqno = parser.load_u32(32)?;
+        let inner = W5InnerRequest::load(body, &mut ref_index, &mut parser)?;
+        if parser.remaining_bits() != SIGNATURE_BITS {
+            return Err(TonCellError::cell_parser_error(format!(
+                "Expected {} bits of signature, got {}",
+                SIGNATURE_BITS,
+                parser.remaining_bits()
+            )));
+        }
+        let signature = parser.load_bits(SIGNATURE_BITS)?;
+        let request = W5SignedRequest::new(wallet_id, valid_until, msg_seqno, inner);
+        Ok((op, request, signature))
+    }
+}
+
+/// Request signed by the owner of wallet v5 and sent to the wallet by a relayer in an internal
+/// message paying the fees, i.e. the gasless flow.
+///
+/// The owner signs an `internal_signed` request, typically including a transfer paying the
+/// relayer for its service, and hands the body to the relayer, which checks the request with
+/// [`W5RelayedRequest::verify`] and [`W5RelayedRequest::validate`] before sending the message
+/// built by [`W5RelayedRequest::build_relay_message`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct W5RelayedRequest {
+    pub wallet: TonAddress,
+    pub request: W5SignedRequest,
+    /// Signed `internal_signed` body.
+    pub body: ArcCell,
+    /// State init deploying the wallet, for the first request of the wallet.
+    pub state_init: Option<ArcCell>,
+}
+
+/// Limits of a relayed request, checked by the relayer before paying for it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct W5RelayLimits {
+    pub wallet_id: i32,
+    /// Current seqno of the wallet.
+    pub seqno: u32,
+    pub now: u32,
+    /// Maximal time between `now` and `valid_until` of the request, in seconds.
+    pub max_ttl: u32,
+    /// Maximal total value of the messages sent by the wallet.
+    pub max_value: Coins,
+}
+
+impl W5RelayedRequest {
+    pub fn sign(
+        wallet: &TonAddress,
+        request: W5SignedRequest,
+        key_pair: &KeyPair,
+    ) -> Result<W5RelayedRequest, TonMessageError> {
+        let body = Arc::new(request.sign(W5_INTERNAL_SIGNED, key_pair)?);
+        Ok(W5RelayedRequest {
+            wallet: wallet.clone(),
+            request,
+            body,
+            state_init: None,
+        })
+    }
+
+    /// Parses a signed body received from the owner of the wallet.
+    pub fn parse(wallet: &TonAddress, body: &ArcCell) -> Result<W5RelayedRequest, TonMessageError> {
+        let (op, request, _) = W5SignedRequest::parse(body)?;
+        if op != W5_INTERNAL_SIGNED {
+            return Err(TonMessageError::RequestRejected(format!(
+                "Expected an internal signed request, got {:#010x}",
+                op
+            )));
+        }
+        Ok(W5RelayedRequest {
+            wallet: wallet.clone(),
+            request,
+            body: body.clone(),
+            state_init: None,
+        })
+    }
+
+    pub fn with_state_init(mut self, state_init: &ArcCell) -> Self {
+        self.state_init = Some(state_init.clone());
+        self
+    }
+
+    /// Checks the signature of the body against the public key of the wallet.
+    pub fn verify(&self, public_key: &[u8]) -> Result<(), TonMessageError> {
+        let mut parser = self.body.parser();
+        let unsigned_len = parser
+            .remaining_bits()
+            .checked_sub(SIGNATURE_BITS)
+            .ok_or(TonMessageError::InvalidSignature)?;
+        let unsigned_data = parser.load_bits(unsigned_len)?;
+        let signature = parser.load_bits(SIGNATURE_BITS)?;
+        let unsigned = CellBuilder::new()
+            .store_bits(unsigned_len, &unsigned_data)?
+            .store_references(&self.body.references)?
+            .build()?;
+        verify(unsigned.cell_hash()?.as_slice(), &signature, public_key)?;
+        Ok(())
+    }
+
+    /// Checks the replay protection fields of the request and that it only sends messages of
+    /// bounded value, so it can't spend more than the relayer expects.
+    ///
+    /// Extended actions and out actions other than `action_send_msg` are rejected, as well as
+    /// messages carrying the remaining value of the relayer message or the whole balance.
+    pub fn validate(&self, limits: &W5RelayLimits) -> Result<(), TonMessageError> {
+        let reject = |reason: String| Err(TonMessageError::RequestRejected(reason));
+        let request = &self.request;
+        if request.wallet_id != limits.wallet_id {
+            return reject(format!("Unexpected wallet id {}", request.wallet_id));
+        }
+        if request.msg_seqno != limits.seqno {
+            return reject(format!(
+                "Seqno {} doesn't match the wallet seqno {}",
+                request.msg_seqno, limits.seqno
+            ));
+        }
+        if request.valid_until <= limits.now || request.valid_until - limits.now > limits.max_ttl {
+            return reject(format!("Invalid valid_until {}", request.valid_until));
+        }
+        if !request.inner.extended_actions.is_empty() {
+            return reject("Extended actions are not allowed".to_string());
+        }
+        let mut value = BigUint::default();
+        for action in &request.inner.out_actions {
+            let OutAction::SendMsg { mode, out_msg } = action else {
+                return reject("Only messages can be sent".to_string());
+            };
+            if mode.intersects(SendMode::CARRY_ALL_BALANCE | SendMode::CARRY_ALL_VALUE) {
+                return reject(format!("Send mode {} is not allowed", mode));
+            }
+            if let Some(message_value) = OutMessage::parse(out_msg)?.value() {
+                value += message_value.nanotons();
+            }
+        }
+        if &value > limits.max_value.nanotons() {
+            return reject(format!("Messages send {} nanotons", value));
+        }
+        Ok(())
+    }
+
+    /// Builds the internal message sent by the relayer to the wallet, carrying `value` to pay
+    /// for the messages and the fees.
+    pub fn build_relay_message(&self, value: &Coins) -> Result<Cell, TonMessageError> {
+        let mut message = TransferMessage::new(&self.wallet, value);
+        message.with_data_ref(&self.body);
+        if let Some(state_init) = &self.state_init {
+            message.with_state_init_ref(state_init);
+        }
+        message.build()
+    }
+}
+
+/// Creates a body of a request sent to wallet v5 by one of its extensions:
+///
+/// ```raw
+/// internal_extension#6578746e query_id:(## 64) inner:InnerRequest = InternalMsgBody;
+/// ```
+pub fn build_w5_extension_body(
+    query_id: u64,
+    inner: &W5InnerRequest,
+) -> Result<Cell, TonCellError> {
+    let mut builder = CellBuilder::new();
+    builder
+        .store_u32(32, W5_INTERNAL_EXTENSION)?
+        .store_u64(64, query_id)?;
+    inner.store(&mut builder)?;
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::address::TonAddress;
+    use crate::cell::CellBuilder;
+    use crate::coins::Coins;
+    use crate::crypto::{verify, KeyPair};
+    use crate::message::{OutAction, OutMessage, SendMode, TonMessageError, TransferMessage};
+    use crate::network::Network;
+    use crate::wallet::{
+        test_key_pair, W5ExtendedAction, W5InnerRequest, W5RelayLimits, W5RelayedRequest,
+        W5SignedRequest, W5WalletId, DEFAULT_SEND_MODE, W5_EXTERNAL_SIGNED,
+    };
+
+    #[test]
+    fn w5_signed_request_roundtrip() -> anyhow::Result<()> {
+        let key_pair = test_key_pair();
+        let extension: TonAddress = "EQBiMfDMivebQb052Z6yR3jHrmwNhw1kQ5bcAUOBYsK_VPuK".parse()?;
+        let msg = Arc::new(CellBuilder::new().store_u32(32, 1)?.build()?);
+
+        let mut inner = W5InnerRequest::new();
+        inner
+            .with_out_action(OutAction::send_msg(DEFAULT_SEND_MODE, &msg))
+            .with_out_action(OutAction::send_msg(SendMode::PAY_GAS_SEPARATELY, &msg))
+            .with_extended_action(W5ExtendedAction::AddExtension(extension.clone()))
+            .with_extended_action(W5ExtendedAction::DeleteExtension(extension))
+            .with_extended_action(W5ExtendedAction::SetSignatureAuthAllowed(false));
+        let request = W5SignedRequest::new(0x7fffff11, 1_700_000_000, 5, inner);
+
+        let body = request.sign(W5_EXTERNAL_SIGNED, &key_pair)?;
+        let (op, parsed, signature) = W5SignedRequest::parse(&body)?;
+        assert_eq!(op, W5_EXTERNAL_SIGNED);
+        assert_eq!(parsed, request);
+
+        let unsigned_hash = request.build_unsigned(W5_EXTERNAL_SIGNED)?.cell_hash()?;
+        verify(
+            unsigned_hash.as_slice(),
+            signature.as_slice(),
+            key_pair.public_key.as_slice(),
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn w5_wallet_id_works() -> anyhow::Result<()> {
+        // Default wallet ids used by wallet v5r1 on mainnet and testnet
+        assert_eq!(W5WalletId::mainnet(0, 0)?.wallet_id(), 2147483409);
+        assert_eq!(W5WalletId::testnet(0, 0)?.wallet_id(), 2147483645);
+
+        let wallet_id = W5WalletId::testnet(-1, 17)?;
+        assert_eq!(
+            W5WalletId::from_wallet_id(wallet_id.wallet_id(), Network::Testnet)?,
+            wallet_id
+        );
+        assert!(W5WalletId::mainnet(0, 1 << 15).is_err());
+        assert!(W5WalletId::mainnet(1000, 0).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn w5_relayed_request_works() -> anyhow::Result<()> {
+        let key_pair = test_key_pair();
+        let wallet: TonAddress = "EQBiMfDMivebQb052Z6yR3jHrmwNhw1kQ5bcAUOBYsK_VPuK".parse()?;
+        let relayer: TonAddress = "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR".parse()?;
+        let transfer = |value: u64| -> anyhow::Result<_> {
+            Ok(Arc::new(
+                TransferMessage::new(&relayer, &Coins::from_nano(value)).build()?,
+            ))
+        };
+        let wallet_id = W5WalletId::mainnet(0, 0)?.wallet_id();
+        let mut inner = W5InnerRequest::new();
+        inner
+            .with_out_action(OutAction::send_msg(
+                DEFAULT_SEND_MODE,
+                &transfer(50_000_000)?,
+            ))
+            .with_out_action(OutAction::send_msg(
+                DEFAULT_SEND_MODE,
+                &transfer(100_000_000)?,
+            ));
+        let request = W5SignedRequest::new(wallet_id, 1_700_000_060, 4, inner.clone());
+
+        let signed = W5RelayedRequest::sign(&wallet, request, &key_pair)?;
+        let relayed = W5RelayedRequest::parse(&wallet, &signed.body)?;
+        assert_eq!(relayed, signed);
+        relayed.verify(&key_pair.public_key)?;
+        assert!(matches!(
+            relayed.verify(&KeyPair::from_seed(&[1; 32]).public_key),
+            Err(TonMessageError::InvalidSignature)
+        ));
+
+        let limits = W5RelayLimits {
+            wallet_id,
+            seqno: 4,
+            now: 1_700_000_000,
+            max_ttl: 300,
+            max_value: Coins::from_nano(150_000_000),
+        };
+        relayed.validate(&limits)?;
+        for limits in [
+            W5RelayLimits {
+                seqno: 5,
+                ..limits.clone()
+            },
+            W5RelayLimits {
+                now: 1_700_000_060,
+                ..limits.clone()
+            },
+            W5RelayLimits {
+                max_ttl: 30,
+                ..limits.clone()
+            },
+            W5RelayLimits {
+                max_value: Coins::from_nano(100_000_000),
+                ..limits.clone()
+            },
+        ] {
+            assert!(relayed.validate(&limits).is_err());
+        }
+        let mut drain = inner.clone();
+        drain.with_out_action(OutAction::send_msg(
+            SendMode::CARRY_ALL_BALANCE,
+            &transfer(0)?,
+        ));
+        let mut extension = inner;
+        extension.with_extended_action(W5ExtendedAction::AddExtension(relayer.clone()));
+        for inner in [drain, extension] {
+            let request = W5SignedRequest::new(wallet_id, 1_700_000_060, 4, inner);
+            let relayed = W5RelayedRequest::sign(&wallet, request, &key_pair)?;
+            assert!(matches!(
+                relayed.validate(&limits),
+                Err(TonMessageError::RequestRejected(_))
+            ));
+        }
+
+        let state_init = Arc::new(CellBuilder::new().store_u8(8, 1)?.build()?);
+        let message = relayed
+            .with_state_init(&state_init)
+            .build_relay_message(&Coins::from_nano(200_000_000))?;
+        let message = OutMessage::parse(&message)?;
+        assert_eq!(message.destination(), Some(&wallet));
+        assert_eq!(message.value(), Some(&Coins::from_nano(200_000_000)));
+        assert_eq!(message.body, signed.body);
+        assert_eq!(message.state_init, Some(state_init));
+        Ok(())
+    }
+}
@@ -0,0 +1,382 @@
+//! JavaScript bindings built with `wasm-bindgen`.
+//!
+//! Enabled with the `wasm` feature. The bindings cover parsing and serialization of bags of
+//! cells, address conversion, jetton and NFT transfer bodies, internal transfer messages,
+//! signing of external message bodies, mnemonic key derivation and wallet external messages,
+//! so messages can be signed offline. Coin amounts are passed as decimal strings, as they
+//! do not fit into JavaScript numbers.
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use num_bigint::BigUint;
+use wasm_bindgen::prelude::*;
+
+use crate::address::TonAddress;
+use crate::cell::{ArcCell, BagOfCells, CellBuilder};
+use crate::coins::Coins;
+use crate::crypto::{sign, KeyPair};
+use crate::message::{JettonTransferMessage, NftTransferMessage, TransferMessage};
+use crate::mnemonic::Mnemonic;
+use crate::network::Network;
+use crate::wallet::{Expiration, TonWallet, WalletVersion, DEFAULT_WALLET_ID};
+
+/// Cell exported to JavaScript as `Cell`.
+#[wasm_bindgen(js_name = Cell)]
+#[derive(Clone)]
+pub struct WasmCell {
+    cell: ArcCell,
+}
+
+#[wasm_bindgen(js_class = Cell)]
+impl WasmCell {
+    /// Parses a bag of cells with a single root.
+    #[wasm_bindgen(js_name = fromBoc)]
+    pub fn from_boc(boc: &[u8]) -> Result<WasmCell, JsError> {
+        Self::from_bag(BagOfCells::parse(boc)?)
+    }
+
+    #[wasm_bindgen(js_name = fromBocBase64)]
+    pub fn from_boc_base64(boc: &str) -> Result<WasmCell, JsError> {
+        Self::from_bag(BagOfCells::parse_base64(boc)?)
+    }
+
+    #[wasm_bindgen(js_name = fromBocHex)]
+    pub fn from_boc_hex(boc: &str) -> Result<WasmCell, JsError> {
+        Self::from_bag(BagOfCells::parse_hex(boc)?)
+    }
+
+    /// Serializes the cell as a bag of cells with a single root.
+    #[wasm_bindgen(js_name = toBoc)]
+    pub fn to_boc(&self, has_crc32: bool) -> Result<Vec<u8>, JsError> {
+        Ok(BagOfCells::new(std::slice::from_ref(&self.cell)).serialize(has_crc32)?)
+    }
+
+    #[wasm_bindgen(js_name = toBocBase64)]
+    pub fn to_boc_base64(&self, has_crc32: bool) -> Result<String, JsError> {
+        Ok(BagOfCells::new(std::slice::from_ref(&self.cell)).serialize_base64(has_crc32)?)
+    }
+
+    /// Representation hash of the cell.
+    pub fn hash(&self) -> Result<Vec<u8>, JsError> {
+        Ok(self.cell.cell_hash()?)
+    }
+
+    #[wasm_bindgen(js_name = hashHex)]
+    pub fn hash_hex(&self) -> Result<String, JsError> {
+        Ok(hex::encode(self.hash()?))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> Vec<u8> {
+        self.cell.data.to_vec()
+    }
+
+    #[wasm_bindgen(getter, js_name = bitLength)]
+    pub fn bit_len(&self) -> usize {
+        self.cell.bit_len
+    }
+
+    #[wasm_bindgen(getter, js_name = refCount)]
+    pub fn ref_count(&self) -> usize {
+        self.cell.references.len()
+    }
+
+    /// Returns the referenced cell at the given index.
+    pub fn reference(&self, index: usize) -> Result<WasmCell, JsError> {
+        let cell = self.cell.reference(index)?.clone();
+        Ok(WasmCell { cell })
+    }
+
+    fn from_bag(boc: BagOfCells) -> Result<WasmCell, JsError> {
+        let cell = boc.single_root()?.clone();
+        Ok(WasmCell { cell })
+    }
+}
+
+impl From<ArcCell> for WasmCell {
+    fn from(cell: ArcCell) -> Self {
+        WasmCell { cell }
+    }
+}
+
+impl WasmCell {
+    pub fn cell(&self) -> &ArcCell {
+        &self.cell
+    }
+}
+
+/// Address exported to JavaScript as `Address`.
+#[wasm_bindgen(js_name = Address)]
+#[derive(Clone)]
+pub struct WasmAddress {
+    address: TonAddress,
+}
+
+#[wasm_bindgen(js_class = Address)]
+impl WasmAddress {
+    /// Parses an address in raw, base64 or base64url form.
+    #[wasm_bindgen(constructor)]
+    pub fn new(address: &str) -> Result<WasmAddress, JsError> {
+        let address = TonAddress::from_str(address)?;
+        Ok(WasmAddress { address })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn workchain(&self) -> i32 {
+        self.address.workchain
+    }
+
+    #[wasm_bindgen(getter, js_name = hashPart)]
+    pub fn hash_part(&self) -> Vec<u8> {
+        self.address.hash_part.to_vec()
+    }
+
+    /// Returns the address in `workchain:hex` form.
+    #[wasm_bindgen(js_name = toRaw)]
+    pub fn to_raw(&self) -> String {
+        self.address.to_hex()
+    }
+
+    /// Returns the address in user-friendly base64url form.
+    #[wasm_bindgen(js_name = toBase64Url)]
+    pub fn to_base64_url(&self, non_bounceable: bool, non_production: bool) -> String {
+        self.address
+            .to_base64_url_flags(non_bounceable, non_production)
+    }
+
+    #[wasm_bindgen(js_name = toBase64Std)]
+    pub fn to_base64_std(&self, non_bounceable: bool, non_production: bool) -> String {
+        self.address
+            .to_base64_std_flags(non_bounceable, non_production)
+    }
+
+    pub fn equals(&self, other: &WasmAddress) -> bool {
+        self.address == other.address
+    }
+}
+
+impl From<TonAddress> for WasmAddress {
+    fn from(address: TonAddress) -> Self {
+        WasmAddress { address }
+    }
+}
+
+impl WasmAddress {
+    pub fn address(&self) -> &TonAddress {
+        &self.address
+    }
+}
+
+/// Builds the body of a jetton transfer.
+#[wasm_bindgen(js_name = jettonTransferBody)]
+pub fn jetton_transfer_body(
+    query_id: u64,
+    destination: &WasmAddress,
+    amount: &str,
+    response_destination: &WasmAddress,
+    forward_ton_amount: &str,
+    forward_payload: Option<WasmCell>,
+) -> Result<WasmCell, JsError> {
+    let mut message = JettonTransferMessage::new(&destination.address, &parse_amount(amount)?);
+    message
+        .with_query_id(query_id)
+        .with_response_destination(&response_destination.address);
+    if let Some(forward_payload) = forward_payload {
+        message.with_forward_ref(&parse_amount(forward_ton_amount)?, &forward_payload.cell);
+    }
+    Ok(Arc::new(message.build()?).into())
+}
+
+/// Builds the body of an NFT transfer.
+#[wasm_bindgen(js_name = nftTransferBody)]
+pub fn nft_transfer_body(
+    query_id: u64,
+    new_owner: &WasmAddress,
+    response_destination: &WasmAddress,
+    forward_amount: &str,
+    forward_payload: Option<WasmCell>,
+) -> Result<WasmCell, JsError> {
+    let mut message = NftTransferMessage::new(&new_owner.address);
+    message
+        .with_query_id(query_id)
+        .with_response_destination(&response_destination.address);
+    if let Some(forward_payload) = forward_payload {
+        message.with_forward_ref(&parse_amount(forward_amount)?, &forward_payload.cell);
+    }
+    Ok(Arc::new(message.build()?).into())
+}
+
+/// Builds an internal message transferring `amount` nanotons with an optional body.
+#[wasm_bindgen(js_name = transferMessage)]
+pub fn transfer_message(
+    destination: &WasmAddress,
+    amount: &str,
+    body: Option<WasmCell>,
+) -> Result<WasmCell, JsError> {
+    let mut message = TransferMessage::new(&destination.address, &parse_amount(amount)?);
+    if let Some(body) = body {
+        message.with_data_ref(&body.cell);
+    }
+    Ok(Arc::new(message.build()?).into())
+}
+
+/// Signs the hash of an external message body with an ed25519 secret key and prepends the
+/// signature to the body, as done by the wallet contracts.
+#[wasm_bindgen(js_name = signBody)]
+pub fn sign_body(body: &WasmCell, secret_key: &[u8]) -> Result<WasmCell, JsError> {
+    let hash = body.cell.cell_hash()?;
+    let sig = sign(hash.as_slice(), secret_key)?;
+    let mut builder = CellBuilder::new();
+    builder.store_slice(sig.as_slice())?;
+    builder.store_cell(&body.cell)?;
+    Ok(Arc::new(builder.build()?).into())
+}
+
+/// Ed25519 key pair exported to JavaScript as `KeyPair`.
+#[wasm_bindgen(js_name = KeyPair)]
+#[derive(Clone)]
+pub struct WasmKeyPair {
+    key_pair: KeyPair,
+}
+
+#[wasm_bindgen(js_class = KeyPair)]
+impl WasmKeyPair {
+    /// Derives the key pair of a 24-word TON mnemonic, words separated by spaces.
+    #[wasm_bindgen(js_name = fromMnemonic)]
+    pub fn from_mnemonic(mnemonic: &str, password: Option<String>) -> Result<WasmKeyPair, JsError> {
+        let key_pair = Mnemonic::from_str(mnemonic, &password)?.to_key_pair()?;
+        Ok(WasmKeyPair { key_pair })
+    }
+
+    #[wasm_bindgen(getter, js_name = publicKey)]
+    pub fn public_key(&self) -> Vec<u8> {
+        self.key_pair.public_key.clone()
+    }
+
+    #[wasm_bindgen(getter, js_name = secretKey)]
+    pub fn secret_key(&self) -> Vec<u8> {
+        self.key_pair.secret_key.clone()
+    }
+}
+
+/// Wallet contract version exported to JavaScript as `WalletVersion`.
+#[wasm_bindgen(js_name = WalletVersion)]
+#[derive(Clone, Copy)]
+pub enum WasmWalletVersion {
+    V1R1,
+    V1R2,
+    V1R3,
+    V2R1,
+    V2R2,
+    V3R1,
+    V3R2,
+    V4R1,
+    V4R2,
+    HighloadV1R1,
+    HighloadV1R2,
+    HighloadV2,
+    HighloadV2R1,
+    HighloadV2R2,
+}
+
+impl From<WasmWalletVersion> for WalletVersion {
+    fn from(version: WasmWalletVersion) -> Self {
+        match version {
+            WasmWalletVersion::V1R1 => WalletVersion::V1R1,
+            WasmWalletVersion::V1R2 => WalletVersion::V1R2,
+            WasmWalletVersion::V1R3 => WalletVersion::V1R3,
+            WasmWalletVersion::V2R1 => WalletVersion::V2R1,
+            WasmWalletVersion::V2R2 => WalletVersion::V2R2,
+            WasmWalletVersion::V3R1 => WalletVersion::V3R1,
+            WasmWalletVersion::V3R2 => WalletVersion::V3R2,
+            WasmWalletVersion::V4R1 => WalletVersion::V4R1,
+            WasmWalletVersion::V4R2 => WalletVersion::V4R2,
+            WasmWalletVersion::HighloadV1R1 => WalletVersion::HighloadV1R1,
+            WasmWalletVersion::HighloadV1R2 => WalletVersion::HighloadV1R2,
+            WasmWalletVersion::HighloadV2 => WalletVersion::HighloadV2,
+            WasmWalletVersion::HighloadV2R1 => WalletVersion::HighloadV2R1,
+            WasmWalletVersion::HighloadV2R2 => WalletVersion::HighloadV2R2,
+        }
+    }
+}
+
+/// Wallet exported to JavaScript as `Wallet`.
+#[wasm_bindgen(js_name = Wallet)]
+pub struct WasmWallet {
+    wallet: TonWallet,
+}
+
+#[wasm_bindgen(js_class = Wallet)]
+impl WasmWallet {
+    /// Derives the wallet of the key pair, with the default wallet id if `wallet_id` is not set.
+    /// `network` is `mainnet` (default), `testnet` or a global id.
+    #[wasm_bindgen(constructor)]
+    pub fn new(
+        version: WasmWalletVersion,
+        key_pair: &WasmKeyPair,
+        workchain: i32,
+        wallet_id: Option<i32>,
+        network: Option<String>,
+    ) -> Result<WasmWallet, JsError> {
+        let network = match network {
+            Some(network) => network.parse::<Network>()?,
+            None => Network::Mainnet,
+        };
+        let wallet = TonWallet::derive(
+            network,
+            workchain,
+            version.into(),
+            &key_pair.key_pair,
+            wallet_id.unwrap_or(DEFAULT_WALLET_ID),
+        )?;
+        Ok(WasmWallet { wallet })
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn address(&self) -> WasmAddress {
+        self.wallet.address.clone().into()
+    }
+
+    /// Returns the user-friendly address with the test-only flag set on other networks than
+    /// the mainnet.
+    #[wasm_bindgen(js_name = toBase64Url)]
+    pub fn to_base64_url(&self, non_bounceable: bool) -> String {
+        self.wallet.to_base64_url(non_bounceable)
+    }
+
+    #[wasm_bindgen(getter, js_name = walletId)]
+    pub fn wallet_id(&self) -> i32 {
+        self.wallet.wallet_id
+    }
+
+    /// Builds the signed external message sending the internal messages with mode 3, created
+    /// at `now` and accepted by the wallet within `ttl` seconds. Set `state_init` to deploy the
+    /// wallet with the first message.
+    #[wasm_bindgen(js_name = createExternalMessage)]
+    pub fn create_external_message(
+        &self,
+        now: u32,
+        ttl: u32,
+        seqno: u32,
+        internal_messages: Vec<WasmCell>,
+        state_init: bool,
+    ) -> Result<WasmCell, JsError> {
+        let internal_messages: Vec<ArcCell> =
+            internal_messages.into_iter().map(|m| m.cell).collect();
+        let expiration = Expiration::new(now, ttl);
+        let message = self.wallet.create_external_message(
+            expiration,
+            seqno,
+            internal_messages,
+            state_init,
+        )?;
+        Ok(Arc::new(message).into())
+    }
+}
+
+fn parse_amount(amount: &str) -> Result<Coins, JsError> {
+    BigUint::from_str(amount)
+        .map(Coins::new)
+        .map_err(|e| JsError::new(&format!("invalid amount {amount}: {e}")))
+}
@@ -1,7 +1,9 @@
+#![cfg(feature = "tonlibjson")]
+
 use tokio_test::assert_ok;
 use tonlib::client::{
-    BlockStream, TonBlockFunctions, TonClientInterface, TonConnection, TonConnectionParams,
-    LOGGING_CONNECTION_CALLBACK,
+    BlockStream, ChainEvent, ChainWatcher, TonBlockFunctions, TonClientInterface, TonConnection,
+    TonConnectionParams, LOGGING_CONNECTION_CALLBACK,
 };
 use tonlib::tl::InternalTransactionId;
 
@@ -30,6 +32,28 @@ pub async fn block_listener_works() {
     }
 }
 
+#[tokio::test]
+pub async fn chain_watcher_works() {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let (_, mc_info) = assert_ok!(client.get_masterchain_info().await);
+    let seqno = mc_info.last.seqno - 5;
+    let mut watcher = ChainWatcher::new(&client, seqno);
+    let mut expected_seqno = seqno;
+    for _ in 0..8 {
+        match assert_ok!(watcher.next().await) {
+            ChainEvent::Block(block) => {
+                assert_eq!(block.seqno, expected_seqno);
+                expected_seqno += 1;
+            }
+            ChainEvent::Reorg { from_seqno, .. } => {
+                log::warn!("Reorg from {}", from_seqno);
+                expected_seqno = from_seqno;
+            }
+        }
+    }
+}
+
 #[tokio::test]
 pub async fn block_listener_get_block_header() {
     common::init_logging();
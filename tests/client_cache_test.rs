@@ -1,3 +1,5 @@
+#![cfg(feature = "tonlibjson")]
+
 #[cfg(feature = "state_cache")]
 use std::time::Duration;
 
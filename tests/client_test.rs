@@ -1,3 +1,5 @@
+#![cfg(feature = "tonlibjson")]
+
 use std::fs::create_dir_all;
 use std::path::Path;
 use std::str::FromStr;
@@ -12,8 +14,10 @@ use tokio::{self};
 use tokio_test::assert_ok;
 use tonlib::address::TonAddress;
 use tonlib::cell::{key_extractor_256bit, value_extractor_cell, BagOfCells, GenericDictLoader};
-use tonlib::client::{TonBlockFunctions, TonClient, TonClientBuilder, TonClientInterface, TxId};
-use tonlib::config::{MAINNET_CONFIG, TESTNET_CONFIG};
+use tonlib::client::{
+    LookupBy, TonBlockFunctions, TonClient, TonClientBuilder, TonClientInterface, TxId,
+};
+use tonlib::config::{TonConfig, MAINNET_CONFIG, MAINNET_CONFIG_URL, TESTNET_CONFIG};
 use tonlib::contract::{TonContractFactory, TonContractInterface};
 use tonlib::tl::{
     BlockId, BlockIdExt, BlocksShards, BlocksTransactions, BlocksTransactionsExt,
@@ -203,6 +207,68 @@ async fn client_get_block_header_works() {
     log::info!("{:?}", r);
 }
 
+#[tokio::test]
+async fn test_client_lookup_block_by() {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let (_, info) = assert_ok!(client.get_masterchain_info().await);
+    let last = &info.last;
+    let by_seqno = assert_ok!(
+        client
+            .lookup_block_by(last.workchain, last.shard, LookupBy::Seqno(last.seqno))
+            .await
+    );
+    assert_eq!(&by_seqno, last);
+    let header = assert_ok!(client.get_block_header(last).await);
+    let by_lt = assert_ok!(
+        client
+            .lookup_block_by(last.workchain, last.shard, LookupBy::Lt(header.start_lt))
+            .await
+    );
+    assert_eq!(&by_lt, last);
+    let by_utime = assert_ok!(
+        client
+            .lookup_block_by(
+                last.workchain,
+                last.shard,
+                LookupBy::Utime(header.gen_utime as i32)
+            )
+            .await
+    );
+    log::info!("{:?}", by_utime);
+}
+
+#[tokio::test]
+async fn test_client_get_balance_series() {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let address = &assert_ok!(TonAddress::from_base64_url(
+        "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR"
+    ));
+    let (_, info) = assert_ok!(client.get_masterchain_info().await);
+    let prev = assert_ok!(
+        client
+            .lookup_block_by(-1, i64::MIN, LookupBy::Seqno(info.last.seqno - 1000))
+            .await
+    );
+    let blocks = vec![prev.clone(), info.last.clone(), prev.clone()];
+    let samples = assert_ok!(client.get_balance_series(address, &blocks).await);
+    assert_eq!(samples.len(), 3);
+    assert_eq!(samples[0], samples[2]);
+    assert_eq!(samples[1].mc_block_id, info.last);
+    log::info!("{:?}", samples);
+
+    let header = assert_ok!(client.get_block_header(&prev).await);
+    let utime = header.gen_utime as i32;
+    let samples = assert_ok!(
+        client
+            .get_balance_series_at(address, &[utime, utime + 3600])
+            .await
+    );
+    assert_eq!(samples.len(), 2);
+    log::info!("{:?}", samples);
+}
+
 #[tokio::test]
 async fn test_client_blocks_get_transactions() {
     common::init_logging();
@@ -319,6 +385,24 @@ pub async fn test_get_block_header() {
     log::info!("{:?}", headers);
 }
 
+#[tokio::test]
+async fn test_get_shards_verified() {
+    common::init_logging();
+    let client = &common::new_mainnet_client().await;
+    let (_, info) = assert_ok!(client.get_masterchain_info().await);
+    let shards = assert_ok!(client.get_shards_verified(&info.last).await);
+    let block_shards = assert_ok!(client.get_block_shards(&info.last).await);
+    assert_eq!(
+        shards.values().map(Vec::len).sum::<usize>(),
+        block_shards.shards.len()
+    );
+    for shard in &block_shards.shards {
+        assert!(shards[&shard.workchain].contains(shard));
+    }
+    assert!(client.get_shards_verified(&shards[&0][0]).await.is_err());
+    log::info!("{:?}", shards);
+}
+
 #[tokio::test]
 async fn test_get_shard_tx_ids() {
     common::init_logging();
@@ -330,6 +414,19 @@ async fn test_get_shard_tx_ids() {
     log::info!("{:?}", ids);
 }
 
+#[tokio::test]
+async fn test_get_block_transactions_all() {
+    common::init_logging();
+    let client = &common::new_testnet_client().await;
+    let (_, info) = assert_ok!(client.get_masterchain_info().await);
+    let shards = assert_ok!(client.get_block_shards(&info.last).await);
+    assert!(!shards.shards.is_empty());
+    let ids = assert_ok!(client.get_block_transactions_all(&shards.shards[0]).await);
+    let shard_ids = assert_ok!(client.get_shard_tx_ids(&shards.shards[0]).await);
+    assert_eq!(ids, shard_ids);
+    log::info!("{:?}", ids);
+}
+
 #[tokio::test]
 async fn test_get_shard_transactions_works() {
     common::init_logging();
@@ -613,3 +710,16 @@ async fn archive_node_client_test() {
     let (_, master_info) = client.get_masterchain_info().await.unwrap();
     log::info!("master_info: {:?}", master_info);
 }
+
+#[tokio::test]
+async fn client_set_config_works() {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let embedded = TonConfig::mainnet();
+    let fetched = assert_ok!(TonConfig::from_url(MAINNET_CONFIG_URL).await);
+    assert_ok!(embedded.check_update(&fetched));
+    let config = assert_ok!(fetched.to_json());
+    assert_ok!(client.set_config(&config).await);
+    let (_, info) = assert_ok!(client.get_masterchain_info().await);
+    log::info!("{:?}", info);
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "tonlibjson")]
+
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
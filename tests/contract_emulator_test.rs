@@ -1,3 +1,5 @@
+#![cfg(feature = "tonlibjson")]
+
 mod common;
 mod contract_emulator_tests {
     use tokio_test::assert_ok;
@@ -1,3 +1,5 @@
+#![cfg(feature = "tonlibjson")]
+
 use std::thread;
 use std::time::Duration;
 
@@ -7,10 +9,12 @@ use common::new_mainnet_client;
 use num_bigint::BigUint;
 use tokio_test::assert_ok;
 use tonlib::address::TonAddress;
+use tonlib::client::TonClientInterface;
 use tonlib::contract::{
-    TonContractError, TonContractFactory, TonContractInterface, TonContractState,
+    StandardGetters, TonContractError, TonContractFactory, TonContractInterface, TonContractState,
 };
 use tonlib::mnemonic::Mnemonic;
+use tonlib::network::Network;
 use tonlib::types::TvmSuccess;
 use tonlib::wallet::{TonWallet, WalletVersion};
 
@@ -176,6 +180,61 @@ async fn test_contract_state_by_transaction() {
     assert_eq!(result1.stack, result2.stack);
 }
 
+#[tokio::test]
+async fn test_contract_state_at_block() {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let factory = assert_ok!(TonContractFactory::builder(&client).build().await);
+    let contract = factory.get_contract(&assert_ok!(
+        "EQB3ncyBUTjZUA5EnFKR5_EnOMI9V1tTEAAPaiU71gc4TiUt".parse()
+    ));
+    let (_, info) = assert_ok!(client.get_masterchain_info().await);
+    let block_id = info.last;
+    let account_state = assert_ok!(contract.get_account_state_at(&block_id).await);
+    log::info!(
+        "Using state {} at block {}",
+        account_state.last_transaction_id,
+        block_id.seqno
+    );
+    let contract_state = assert_ok!(
+        contract
+            .get_state_by_transaction(&account_state.last_transaction_id)
+            .await
+    );
+    let method_name = "get_router_data";
+    let result1 = assert_ok!(contract_state.run_get_method(method_name, vec![]).await);
+    let result2 = assert_ok!(
+        contract
+            .run_get_method_at(&block_id, method_name, vec![])
+            .await
+    );
+    assert_eq!(result1.stack, result2.stack);
+}
+
+#[tokio::test]
+async fn test_run_get_method_historical() {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let factory = assert_ok!(TonContractFactory::builder(&client).build().await);
+    let contract = factory.get_contract(&assert_ok!(
+        "EQB3ncyBUTjZUA5EnFKR5_EnOMI9V1tTEAAPaiU71gc4TiUt".parse()
+    ));
+    let (_, info) = assert_ok!(client.get_masterchain_info().await);
+    let block_id = info.last;
+    let method_name = "get_router_data";
+    let result1 = assert_ok!(
+        contract
+            .run_get_method_historical(&block_id, method_name, vec![])
+            .await
+    );
+    let result2 = assert_ok!(
+        contract
+            .run_get_method_at(&block_id, method_name, vec![])
+            .await
+    );
+    assert_eq!(result1.stack, result2.stack);
+}
+
 #[tokio::test]
 async fn test_state_dropping() {
     common::init_logging();
@@ -209,7 +268,11 @@ async fn test_derive_undeployed() {
     let mnemonic_str = "mechanic sudden cannon bind monkey brown moment able street pride struggle team outdoor canyon coin tourist service second crazy tank sell regret sample attitude";
     let mnemonic = assert_ok!(Mnemonic::from_str(mnemonic_str, &None));
     let key_pair = assert_ok!(mnemonic.to_key_pair());
-    let wallet_v4r2 = assert_ok!(TonWallet::derive_default(WalletVersion::V4R2, &key_pair));
+    let wallet_v4r2 = assert_ok!(TonWallet::derive_default(
+        Network::Mainnet,
+        WalletVersion::V4R2,
+        &key_pair
+    ));
 
     let address = wallet_v4r2.address;
     log::info!("addr: {}", address);
@@ -232,3 +295,18 @@ async fn test_string_in_tvm_success() -> anyhow::Result<()> {
     assert_eq!("constant_product", pool_type);
     Ok(())
 }
+
+#[tokio::test]
+async fn test_standard_getters_handle_missing_methods() -> anyhow::Result<()> {
+    common::init_logging();
+    let client = new_mainnet_client().await;
+    let factory = TonContractFactory::builder(&client).build().await?;
+    // Moon jetton master
+    let contract =
+        factory.get_contract(&"EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR".parse()?);
+    let getters = StandardGetters::new(&contract);
+    assert_eq!(assert_ok!(getters.get_public_key().await), None);
+    assert_eq!(assert_ok!(getters.seqno().await), None);
+    assert_eq!(assert_ok!(getters.get_subwallet_id().await), None);
+    Ok(())
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "tonlibjson")]
+
 use std::ffi::CString;
 use std::io;
 use std::sync::Arc;
@@ -1,3 +1,5 @@
+#![cfg(feature = "tonlibjson")]
+
 use num_bigint::BigUint;
 use tokio_test::assert_ok;
 use tonlib::address::TonAddress;
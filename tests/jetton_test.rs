@@ -1,7 +1,11 @@
+#![cfg(feature = "tonlibjson")]
+
 use sha2::{Digest, Sha256};
 use tokio_test::assert_ok;
 use tonlib::address::TonAddress;
-use tonlib::contract::{JettonMasterContract, TonContractFactory};
+use tonlib::contract::{
+    ContractInterface, JettonMasterContract, TonContractFactory, KNOWN_INTERFACES,
+};
 use tonlib::meta::*;
 
 mod common;
@@ -27,6 +31,18 @@ async fn test_get_jetton_content_uri() {
     assert_eq!(content_res.decimals.unwrap(), 0x9);
 }
 
+#[tokio::test]
+async fn test_detect_jetton_master_interface() {
+    common::init_logging();
+    let client = common::new_mainnet_client().await;
+    let factory = assert_ok!(TonContractFactory::builder(&client).build().await);
+    let contract = factory.get_contract(&assert_ok!(
+        "EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR".parse()
+    )); // Moon jetton
+    let interfaces = assert_ok!(KNOWN_INTERFACES.detect_interfaces(&contract).await);
+    assert_eq!(interfaces, vec![ContractInterface::JettonMaster]);
+}
+
 #[tokio::test]
 async fn test_get_jetton_content_internal_uri() {
     common::init_logging();
@@ -1,3 +1,5 @@
+#![cfg(feature = "tonlibjson")]
+
 use futures::future::try_join_all;
 use rand::Rng;
 use tokio_test::assert_ok;
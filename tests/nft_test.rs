@@ -1,3 +1,5 @@
+#![cfg(feature = "tonlibjson")]
+
 use tokio_test::assert_ok;
 use tonlib::contract::{NftCollectionContract, NftItemContract, TonContractFactory};
 use tonlib::meta::*;
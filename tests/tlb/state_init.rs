@@ -0,0 +1,244 @@
+// @generated by tonlib::tlb_codegen, do not edit.
+
+use tonlib::address::TonAddress;
+use tonlib::cell::{ArcCell, CellBuilder, CellParser, TlbDeserialize, TlbSerialize, TonCellError};
+use tonlib::coins::Coins;
+
+/// ```raw
+/// tick_tock$_ tick:Bool tock:Bool = TickTock;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TickTock {
+    pub tick: bool,
+    pub tock: bool,
+}
+
+impl TlbSerialize for TickTock {
+    fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        let TickTock { tick, tock } = self;
+        builder.store_bit(*tick)?;
+        builder.store_bit(*tock)?;
+        Ok(())
+    }
+}
+
+impl TlbDeserialize for TickTock {
+    fn load(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        let tick = parser.load_bit()?;
+        let tock = parser.load_bit()?;
+        Ok(TickTock { tick, tock })
+    }
+}
+
+/// ```raw
+/// _ split_depth:(Maybe (## 5)) special:(Maybe TickTock) code:(Maybe ^Cell) data:(Maybe ^Cell) library:(Maybe ^Cell) = StateInit;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateInit {
+    pub split_depth: Option<u8>,
+    pub special: Option<TickTock>,
+    pub code: Option<ArcCell>,
+    pub data: Option<ArcCell>,
+    pub library: Option<ArcCell>,
+}
+
+impl TlbSerialize for StateInit {
+    fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        let StateInit { split_depth, special, code, data, library } = self;
+        match split_depth {
+            Some(split_depth) => {
+                builder.store_bit(true)?;
+                builder.store_u8(5, *split_depth)?;
+            }
+            None => {
+                builder.store_bit(false)?;
+            }
+        }
+        match special {
+            Some(special) => {
+                builder.store_bit(true)?;
+                special.store(builder)?;
+            }
+            None => {
+                builder.store_bit(false)?;
+            }
+        }
+        match code {
+            Some(code) => {
+                builder.store_bit(true)?;
+                builder.store_reference(code)?;
+            }
+            None => {
+                builder.store_bit(false)?;
+            }
+        }
+        match data {
+            Some(data) => {
+                builder.store_bit(true)?;
+                builder.store_reference(data)?;
+            }
+            None => {
+                builder.store_bit(false)?;
+            }
+        }
+        match library {
+            Some(library) => {
+                builder.store_bit(true)?;
+                builder.store_reference(library)?;
+            }
+            None => {
+                builder.store_bit(false)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TlbDeserialize for StateInit {
+    fn load(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        let split_depth = if parser.load_bit()? {
+            Some(parser.load_u8(5)?)
+        } else {
+            None
+        };
+        let special = if parser.load_bit()? {
+            Some(TickTock::load(parser)?)
+        } else {
+            None
+        };
+        let code = if parser.load_bit()? {
+            Some(parser.next_reference()?)
+        } else {
+            None
+        };
+        let data = if parser.load_bit()? {
+            Some(parser.next_reference()?)
+        } else {
+            None
+        };
+        let library = if parser.load_bit()? {
+            Some(parser.next_reference()?)
+        } else {
+            None
+        };
+        Ok(StateInit { split_depth, special, code, data, library })
+    }
+}
+
+/// ```raw
+/// transfer#0f8a7ea5 query_id:uint64 amount:(VarUInteger 16) destination:MsgAddress response_destination:MsgAddress custom_payload:(Maybe ^Cell) forward_ton_amount:(VarUInteger 16) forward_payload:(Maybe ^Cell) = JettonMsg;
+/// burn#595f07bc query_id:uint64 amount:(VarUInteger 16) response_destination:MsgAddress custom_payload:(Maybe ^Cell) = JettonMsg;
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum JettonMsg {
+    Transfer {
+        query_id: u64,
+        amount: Coins,
+        destination: TonAddress,
+        response_destination: TonAddress,
+        custom_payload: Option<ArcCell>,
+        forward_ton_amount: Coins,
+        forward_payload: Option<ArcCell>,
+    },
+    Burn {
+        query_id: u64,
+        amount: Coins,
+        response_destination: TonAddress,
+        custom_payload: Option<ArcCell>,
+    },
+}
+
+impl TlbSerialize for JettonMsg {
+    fn store(&self, builder: &mut CellBuilder) -> Result<(), TonCellError> {
+        match self {
+            JettonMsg::Transfer { query_id, amount, destination, response_destination, custom_payload, forward_ton_amount, forward_payload } => {
+                builder.store_u32(32, 0x0f8a7ea5)?;
+                builder.store_u64(64, *query_id)?;
+                builder.store_coins(amount)?;
+                builder.store_address(destination)?;
+                builder.store_address(response_destination)?;
+                match custom_payload {
+                    Some(custom_payload) => {
+                        builder.store_bit(true)?;
+                        builder.store_reference(custom_payload)?;
+                    }
+                    None => {
+                        builder.store_bit(false)?;
+                    }
+                }
+                builder.store_coins(forward_ton_amount)?;
+                match forward_payload {
+                    Some(forward_payload) => {
+                        builder.store_bit(true)?;
+                        builder.store_reference(forward_payload)?;
+                    }
+                    None => {
+                        builder.store_bit(false)?;
+                    }
+                }
+            }
+            JettonMsg::Burn { query_id, amount, response_destination, custom_payload } => {
+                builder.store_u32(32, 0x595f07bc)?;
+                builder.store_u64(64, *query_id)?;
+                builder.store_coins(amount)?;
+                builder.store_address(response_destination)?;
+                match custom_payload {
+                    Some(custom_payload) => {
+                        builder.store_bit(true)?;
+                        builder.store_reference(custom_payload)?;
+                    }
+                    None => {
+                        builder.store_bit(false)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl TlbDeserialize for JettonMsg {
+    fn load(parser: &mut CellParser) -> Result<Self, TonCellError> {
+        let mut tag = 0u64;
+        let mut tag_len = 0;
+        loop {
+            match (tag_len, tag) {
+                (32, 0x0f8a7ea5) => {
+                    let query_id = parser.load_u64(64)?;
+                    let amount = parser.load_coins()?;
+                    let destination = parser.load_address()?;
+                    let response_destination = parser.load_address()?;
+                    let custom_payload = if parser.load_bit()? {
+                        Some(parser.next_reference()?)
+                    } else {
+                        None
+                    };
+                    let forward_ton_amount = parser.load_coins()?;
+                    let forward_payload = if parser.load_bit()? {
+                        Some(parser.next_reference()?)
+                    } else {
+                        None
+                    };
+                    return Ok(JettonMsg::Transfer { query_id, amount, destination, response_destination, custom_payload, forward_ton_amount, forward_payload });
+                }
+                (32, 0x595f07bc) => {
+                    let query_id = parser.load_u64(64)?;
+                    let amount = parser.load_coins()?;
+                    let response_destination = parser.load_address()?;
+                    let custom_payload = if parser.load_bit()? {
+                        Some(parser.next_reference()?)
+                    } else {
+                        None
+                    };
+                    return Ok(JettonMsg::Burn { query_id, amount, response_destination, custom_payload });
+                }
+                _ if tag_len >= 32 => {
+                    return Err(TonCellError::unexpected_structure("JettonMsg"));
+                }
+                _ => {}
+            }
+            tag = (tag << 1) | u64::from(parser.load_bit()?);
+            tag_len += 1;
+        }
+    }
+}
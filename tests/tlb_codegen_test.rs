@@ -0,0 +1,88 @@
+use std::sync::Arc;
+
+use tonlib::address::TonAddress;
+use tonlib::cell::{CellBuilder, StateInitBuilder, TlbDeserialize, TlbSerialize};
+use tonlib::coins::Coins;
+use tonlib::message::JettonTransferMessage;
+
+#[rustfmt::skip]
+#[path = "tlb/state_init.rs"]
+mod state_init;
+
+use state_init::{JettonMsg, StateInit, TickTock};
+
+#[test]
+fn generated_state_init_works() -> anyhow::Result<()> {
+    let code = Arc::new(CellBuilder::new().store_string("code")?.build()?);
+    let data = Arc::new(CellBuilder::new().store_string("data")?.build()?);
+    let state_init = StateInit {
+        split_depth: None,
+        special: None,
+        code: Some(code.clone()),
+        data: Some(data.clone()),
+        library: None,
+    };
+    let cell = state_init.to_cell()?;
+    assert_eq!(cell, StateInitBuilder::new(&code, &data).build()?);
+    assert_eq!(StateInit::from_cell(&cell)?, state_init);
+
+    let state_init = StateInit {
+        split_depth: Some(17),
+        special: Some(TickTock {
+            tick: true,
+            tock: false,
+        }),
+        code: None,
+        data: Some(data),
+        library: Some(code),
+    };
+    assert_eq!(StateInit::from_cell(&state_init.to_cell()?)?, state_init);
+    Ok(())
+}
+
+#[test]
+fn generated_jetton_msg_works() -> anyhow::Result<()> {
+    let destination =
+        TonAddress::from_base64_url("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR")?;
+    let amount = Coins::from_ton(1);
+    let expected = JettonTransferMessage::new(&destination, &amount)
+        .with_query_id(7)
+        .build()?;
+    let msg = JettonMsg::from_cell(&expected)?;
+    assert_eq!(
+        msg,
+        JettonMsg::Transfer {
+            query_id: 7,
+            amount: amount.clone(),
+            destination,
+            response_destination: TonAddress::NULL,
+            custom_payload: None,
+            forward_ton_amount: Coins::zero(),
+            forward_payload: None,
+        }
+    );
+    assert_eq!(msg.to_cell()?, expected);
+
+    let burn = JettonMsg::Burn {
+        query_id: 1,
+        amount,
+        response_destination: TonAddress::NULL,
+        custom_payload: Some(Arc::new(CellBuilder::new().store_u8(8, 1)?.build()?)),
+    };
+    assert_eq!(JettonMsg::from_cell(&burn.to_cell()?)?, burn);
+
+    let unknown = CellBuilder::new().store_u32(32, 0x0f8a7ea6)?.build()?;
+    assert!(JettonMsg::from_cell(&unknown).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "tlb_codegen")]
+#[test]
+fn generated_code_is_up_to_date() -> anyhow::Result<()> {
+    use tonlib::tlb_codegen::TlbCodegen;
+
+    let schema = include_str!("tlb/state_init.tlb");
+    let code = TlbCodegen::new().generate(schema)?;
+    assert_eq!(code, include_str!("tlb/state_init.rs"));
+    Ok(())
+}
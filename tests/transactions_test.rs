@@ -1,3 +1,5 @@
+#![cfg(feature = "tonlibjson")]
+
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Instant;
@@ -5,8 +7,10 @@ use std::{thread, time};
 
 use anyhow::anyhow;
 use futures::future::join_all;
+use futures::StreamExt;
 use tokio_test::assert_ok;
 use tonlib::address::TonAddress;
+use tonlib::client::{TonClientInterface, TonTraceFunctions, TransactionFilter, TransactionTrace};
 use tonlib::contract::{LatestContractTransactionsCache, TonContractFactory};
 use tonlib::tl::RawTransaction;
 
@@ -127,6 +131,113 @@ async fn get_txs_for_empty_works() {
     );
 }
 
+#[tokio::test]
+async fn get_trace_works() -> anyhow::Result<()> {
+    common::init_logging();
+    let addr: TonAddress = "EQB3ncyBUTjZUA5EnFKR5_EnOMI9V1tTEAAPaiU71gc4TiUt".parse()?;
+
+    let client = common::new_mainnet_client().await;
+    let state = client.get_raw_account_state(&addr).await?;
+    let txs = client
+        .get_raw_transactions_v2(&addr, &state.last_transaction_id, 8, false)
+        .await?;
+    // Skip the latest transactions, whose messages may still be in flight
+    let tx = txs.transactions.last().unwrap();
+    let trace = client.get_trace(tx).await?;
+    log::info!(
+        "Trace of {} has {} transactions, complete: {}",
+        tx.transaction_id,
+        trace.len(),
+        trace.is_complete()
+    );
+    assert_eq!(&trace.transaction, tx);
+    assert_eq!(trace.transactions().len(), trace.len());
+    check_trace(&trace)?;
+    Ok(())
+}
+
+fn check_trace(trace: &TransactionTrace) -> anyhow::Result<()> {
+    for child in &trace.children {
+        let in_msg = child.transaction.in_msg.as_ref().unwrap();
+        let out_msg = trace
+            .transaction
+            .out_msgs
+            .iter()
+            .find(|m| m.created_lt == in_msg.created_lt)
+            .ok_or_else(|| anyhow!("Unexpected child {}", child.transaction.transaction_id))?;
+        let destination: TonAddress = out_msg.destination.account_address.parse()?;
+        let address: TonAddress = child.transaction.address.account_address.parse()?;
+        assert_eq!(destination, address);
+        assert!(child.transaction.transaction_id.lt > trace.transaction.transaction_id.lt);
+        check_trace(child)?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn watch_address_works() -> anyhow::Result<()> {
+    common::init_logging();
+    let address: TonAddress = "Ef8zMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzM0vF".parse()?;
+    let client = common::new_mainnet_client().await;
+    let state = client.get_raw_account_state(&address).await?;
+    let txs = client
+        .get_raw_transactions_v2(&address, &state.last_transaction_id, 5, false)
+        .await?;
+    assert_eq!(txs.transactions.len(), 5);
+
+    // Replays the 4 transactions following the oldest one
+    let watcher = client
+        .watch_address(&address)
+        .with_last_transaction(&txs.transactions[4].transaction_id)
+        .with_parsing(true);
+    let watched: Vec<_> = watcher.into_stream().take(4).collect().await;
+    for (watched, expected) in watched.into_iter().zip(txs.transactions[..4].iter().rev()) {
+        let watched = watched?;
+        assert_eq!(watched.raw.transaction_id, expected.transaction_id);
+        let transaction = watched.transaction.unwrap();
+        assert_eq!(transaction.lt as i64, expected.transaction_id.lt);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn transaction_history_works() -> anyhow::Result<()> {
+    common::init_logging();
+    let address: TonAddress = "Ef8zMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzMzM0vF".parse()?;
+    let client = common::new_mainnet_client().await;
+    let state = client.get_raw_account_state(&address).await?;
+    let txs = client
+        .get_raw_transactions_v2(&address, &state.last_transaction_id, 20, false)
+        .await?;
+    assert_eq!(txs.transactions.len(), 20);
+
+    // Spans two pages of the history
+    let history = client
+        .transaction_history(&address)
+        .with_start(&state.last_transaction_id)
+        .with_until_lt(txs.transactions[19].transaction_id.lt);
+    let all: Vec<_> = history.into_stream().collect().await;
+    assert_eq!(all.len(), 19);
+    for (tx, expected) in all.into_iter().zip(&txs.transactions) {
+        assert_eq!(tx?.raw.transaction_id, expected.transaction_id);
+    }
+
+    let filtered: Vec<_> = client
+        .transaction_history(&address)
+        .with_start(&state.last_transaction_id)
+        .with_until_lt(txs.transactions[19].transaction_id.lt)
+        .with_filter(TransactionFilter::success().not())
+        .into_stream()
+        .collect()
+        .await;
+    for tx in filtered {
+        let tx = tx?;
+        let description = tx.transaction.description;
+        assert!(description.is_none_or(|descr| !descr.is_success()));
+    }
+    Ok(())
+}
+
 fn check_order(trs: Vec<Arc<RawTransaction>>) -> anyhow::Result<()> {
     let mut lt = 0;
     for t in trs.iter() {
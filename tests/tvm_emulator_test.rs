@@ -1,3 +1,5 @@
+#![cfg(feature = "tonlibjson")]
+
 mod common;
 
 mod contract_emulator_tests {
@@ -10,8 +12,9 @@ mod contract_emulator_tests {
     use tokio::{self};
     use tokio_test::assert_ok;
     use tonlib::address::TonAddress;
-    use tonlib::cell::{BagOfCells, CellBuilder, CellSlice};
+    use tonlib::cell::{BagOfCells, Cell, CellBuilder, CellSlice};
     use tonlib::client::TonClientInterface;
+    use tonlib::coins::Coins;
     use tonlib::contract::{
         JettonData, JettonMasterContract, TonContractFactory, TonContractInterface,
     };
@@ -309,6 +312,46 @@ mod contract_emulator_tests {
         assert_eq!(blockchain_result, expected);
     }
 
+    #[tokio::test]
+    async fn test_emulator_with_config_params() {
+        common::init_logging();
+        let client = common::new_mainnet_client().await;
+
+        let address = assert_ok!("EQDk2VTvn04SUKJrW7rXahzdF8_Qi6utb0wj43InCu9vdjrR".parse());
+        let info = assert_ok!(client.get_config_all(0).await);
+        let boc = assert_ok!(BagOfCells::parse(&info.config.bytes));
+        let dict = assert_ok!(boc.single_root());
+        let mut config_params = assert_ok!(Cell::load_config_params_dict(
+            dict,
+            &mut 0,
+            &mut dict.parser()
+        ));
+        let mut gas_prices = assert_ok!(config_params.gas_prices(0));
+        gas_prices.gas_price *= 2;
+        assert_ok!(config_params.set_gas_prices(0, &gas_prices));
+
+        // DROP, PUSHINT 21, CONFIGOPTPARAM: drops the method id and returns config param 21,
+        // i.e. the gas prices of the basechain, as seen by the contract
+        let code = assert_ok!(CellBuilder::new()
+            .store_slice(&[0x30, 0x80, 0x15, 0xf8, 0x33])
+            .and_then(|builder| builder.build()));
+        let code = assert_ok!(BagOfCells::from_root(code).serialize(false));
+        let data = assert_ok!(CellBuilder::new().build());
+        let data = assert_ok!(BagOfCells::from_root(data).serialize(false));
+        let mut emulator = assert_ok!(TvmEmulator::new(&code, &data));
+        let mut c7 = TvmEmulatorC7Builder::new(&address, &[], 0).build();
+        assert_ok!(c7.with_config_params(&config_params));
+        assert_ok!(emulator.set_c7(&c7));
+        let result = assert_ok!(emulator.run_get_method(&"get_gas_prices".into(), &[]));
+        assert!(result.exit_success());
+        let param = assert_ok!(result.stack[0].get_cell());
+        let expected = config_params.param(21).unwrap();
+        assert_eq!(
+            assert_ok!(param.cell_hash()),
+            assert_ok!(expected.cell_hash())
+        );
+    }
+
     fn emulate_get_wallet_address(
         code: &[u8],
         data: &[u8],
@@ -414,7 +457,7 @@ mod contract_emulator_tests {
         let address = assert_ok!(TonAddress::from_base64_url(
             "Ef8CmPZLxWB-9ypeGdGhEqA6ZNLBFUwnqXPH2eUQd_MzbGh_"
         ));
-        let msg = assert_ok!(JettonTransferMessage::new(&address, &BigUint::from(1u32)).build());
+        let msg = assert_ok!(JettonTransferMessage::new(&address, &Coins::from_nano(1)).build());
 
         let mut emulator = assert_ok!(TvmEmulator::new(code, data));
         let r = assert_ok!(emulator.send_external_message(msg));
@@ -443,7 +486,7 @@ mod contract_emulator_tests {
         let address = assert_ok!(TonAddress::from_base64_url(
             "Ef8CmPZLxWB-9ypeGdGhEqA6ZNLBFUwnqXPH2eUQd_MzbGh_"
         ));
-        let msg = assert_ok!(JettonTransferMessage::new(&address, &BigUint::from(1u32)).build());
+        let msg = assert_ok!(JettonTransferMessage::new(&address, &Coins::from_nano(1)).build());
 
         let mut emulator = assert_ok!(TvmEmulator::new(code, data));
         let r = assert_ok!(emulator.send_internal_message(msg, 300));